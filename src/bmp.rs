@@ -0,0 +1,428 @@
+//! Windows bitmap (BMP) images.
+//!
+//! Firmware logos and boot splash screens are normally stored as
+//! uncompressed BMP files. This module decodes them into `BltPixel` buffers
+//! which can be blitted with the graphics output protocol, optionally
+//! centered on the screen and scaled.
+//!
+//! Uncompressed images with 1, 4, 8, 16, 24 and 32 bits per pixel are
+//! supported, as well as 16 and 32-bit images with custom bit fields.
+//! Run-length encoded images are not.
+//!
+//! Images can also be captured from the screen and encoded as 24-bit BMP
+//! files, to save screenshots.
+
+use crate::proto::console::gop::{BltOp, BltPixel, BltRegion, GraphicsOutput};
+use crate::{Result, ResultExt, Status};
+use alloc_api::vec;
+use alloc_api::vec::Vec;
+
+/// Size of the file header preceding the bitmap header.
+const FILE_HEADER_SIZE: usize = 14;
+
+/// Size of the original `BITMAPINFOHEADER`, which later headers extend.
+const INFO_HEADER_SIZE: usize = 40;
+
+/// No compression.
+const BI_RGB: u32 = 0;
+
+/// No compression, with masks for the color channels.
+const BI_BITFIELDS: u32 = 3;
+
+/// Decoded bitmap image.
+#[derive(Debug, Clone)]
+pub struct BmpImage {
+    width: usize,
+    height: usize,
+    pixels: Vec<BltPixel>,
+}
+
+/// Where to draw an image on the screen.
+#[derive(Debug, Clone, Copy)]
+pub enum Position {
+    /// Put the top-left corner of the image at these coordinates.
+    At(usize, usize),
+    /// Center the image on the screen.
+    Center,
+}
+
+/// How to scale an image before drawing it.
+#[derive(Debug, Clone, Copy)]
+pub enum Scaling {
+    /// Draw the image at its original size.
+    None,
+    /// Multiply both dimensions of the image by an integer factor.
+    Factor(usize),
+    /// Make the image as large as possible while fitting on the screen and
+    /// keeping its aspect ratio.
+    Fit,
+}
+
+impl BmpImage {
+    /// Decode a BMP file.
+    ///
+    /// # Errors
+    /// * `uefi::Status::INVALID_PARAMETER`  The data is not a valid BMP file.
+    /// * `uefi::Status::UNSUPPORTED`        The bitmap is compressed or uses an unsupported format.
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        Self::decode_impl(data).map(Into::into).map_err(Into::into)
+    }
+
+    fn decode_impl(data: &[u8]) -> core::result::Result<Self, Status> {
+        if data.get(..2) != Some(b"BM") {
+            return Err(Status::INVALID_PARAMETER);
+        }
+        let pixels_offset = read_u32(data, 10)? as usize;
+        let header_size = read_u32(data, FILE_HEADER_SIZE)? as usize;
+        if header_size < INFO_HEADER_SIZE {
+            // The OS/2 `BITMAPCOREHEADER` is not used by firmware logos
+            return Err(Status::UNSUPPORTED);
+        }
+        let header = data
+            .get(FILE_HEADER_SIZE..FILE_HEADER_SIZE + header_size)
+            .ok_or(Status::INVALID_PARAMETER)?;
+
+        let width = read_u32(header, 4)? as i32;
+        let height = read_u32(header, 8)? as i32;
+        let bits_per_pixel = read_u16(header, 14)?;
+        let compression = read_u32(header, 16)?;
+        let colors_used = read_u32(header, 32)? as usize;
+        if width <= 0 || height == 0 {
+            return Err(Status::INVALID_PARAMETER);
+        }
+        // A negative height indicates that rows are stored top-down
+        let top_down = height < 0;
+        let width = width as usize;
+        let height = if top_down {
+            -i64::from(height)
+        } else {
+            i64::from(height)
+        } as usize;
+
+        let format = match (compression, bits_per_pixel) {
+            (BI_RGB, 1) | (BI_RGB, 4) | (BI_RGB, 8) => {
+                let max_colors = 1 << bits_per_pixel;
+                let colors = if colors_used == 0 {
+                    max_colors
+                } else {
+                    colors_used.min(max_colors)
+                };
+                let palette_offset = FILE_HEADER_SIZE + header_size;
+                let palette = data
+                    .get(palette_offset..palette_offset + 4 * colors)
+                    .ok_or(Status::INVALID_PARAMETER)?;
+                PixelFormat::Indexed(palette)
+            }
+            (BI_RGB, 16) => PixelFormat::Masks(ChannelMasks::new(0x7c00, 0x03e0, 0x001f)),
+            (BI_RGB, 24) | (BI_RGB, 32) => PixelFormat::Bgr,
+            (BI_BITFIELDS, 16) | (BI_BITFIELDS, 32) => {
+                // The masks follow the 40-byte header, or are part of the
+                // larger headers.
+                let masks = data
+                    .get(FILE_HEADER_SIZE + INFO_HEADER_SIZE..)
+                    .ok_or(Status::INVALID_PARAMETER)?;
+                PixelFormat::Masks(ChannelMasks::new(
+                    read_u32(masks, 0)?,
+                    read_u32(masks, 4)?,
+                    read_u32(masks, 8)?,
+                ))
+            }
+            (BI_RGB, _) | (BI_BITFIELDS, _) => return Err(Status::INVALID_PARAMETER),
+            _ => return Err(Status::UNSUPPORTED),
+        };
+
+        // Rows are padded to a multiple of 4 bytes
+        let row_bits = width
+            .checked_mul(usize::from(bits_per_pixel))
+            .ok_or(Status::INVALID_PARAMETER)?;
+        let row_size = ((row_bits + 31) >> 5) * 4;
+        let pixels_end = row_size
+            .checked_mul(height)
+            .and_then(|size| size.checked_add(pixels_offset))
+            .ok_or(Status::INVALID_PARAMETER)?;
+        let rows = data
+            .get(pixels_offset..pixels_end)
+            .ok_or(Status::INVALID_PARAMETER)?;
+
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let row = if top_down { y } else { height - 1 - y };
+            let row = &rows[row * row_size..(row + 1) * row_size];
+            for x in 0..width {
+                pixels.push(format.decode(row, x, bits_per_pixel)?);
+            }
+        }
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Capture the contents of the screen.
+    ///
+    /// The pixels are read with a blit operation, which converts them from
+    /// the RGB, BGR or bitmask format of the frame buffer, and also works in
+    /// Blt-only modes.
+    pub fn capture(gop: &mut GraphicsOutput) -> Result<Self> {
+        let (width, height) = gop.current_mode_info().resolution();
+        let mut pixels = vec![BltPixel::new(0, 0, 0); width * height];
+        if width == 0 || height == 0 {
+            return Ok(Self::from_pixels(width, height, pixels).into());
+        }
+        gop.blt(BltOp::VideoToBltBuffer {
+            buffer: &mut pixels,
+            src: (0, 0),
+            dest: BltRegion::Full,
+            dims: (width, height),
+        })
+        .map_inner(|()| Self::from_pixels(width, height, pixels))
+    }
+
+    /// Encode the image as an uncompressed 24-bit BMP file.
+    pub fn encode(&self) -> Vec<u8> {
+        let row_size = (self.width * 3 + 3) & !3;
+        let pixels_offset = FILE_HEADER_SIZE + INFO_HEADER_SIZE;
+        let file_size = pixels_offset + row_size * self.height;
+
+        let mut data = Vec::with_capacity(file_size);
+        // File header
+        data.extend_from_slice(b"BM");
+        data.extend_from_slice(&(file_size as u32).to_le_bytes());
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&(pixels_offset as u32).to_le_bytes());
+        // Info header
+        data.extend_from_slice(&(INFO_HEADER_SIZE as u32).to_le_bytes());
+        data.extend_from_slice(&(self.width as u32).to_le_bytes());
+        data.extend_from_slice(&(self.height as u32).to_le_bytes());
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&24u16.to_le_bytes());
+        data.extend_from_slice(&BI_RGB.to_le_bytes());
+        data.extend_from_slice(&((row_size * self.height) as u32).to_le_bytes());
+        // Resolution (about 96 DPI), palette size and important colors
+        data.extend_from_slice(&3780u32.to_le_bytes());
+        data.extend_from_slice(&3780u32.to_le_bytes());
+        data.extend_from_slice(&[0; 8]);
+
+        // Rows are stored bottom-up, and padded to a multiple of 4 bytes
+        let padding = row_size - self.width * 3;
+        for row in self.pixels.chunks_exact(self.width.max(1)).rev() {
+            for pixel in row {
+                data.extend_from_slice(&[pixel.blue, pixel.green, pixel.red]);
+            }
+            data.extend_from_slice(&[0; 3][..padding]);
+        }
+        data
+    }
+
+    /// Create an image from pixels stored row after row, top row first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of pixels does not match the dimensions.
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<BltPixel>) -> Self {
+        assert_eq!(
+            width.checked_mul(height),
+            Some(pixels.len()),
+            "Pixel count does not match image dimensions"
+        );
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Returns the (width, height) of the image, in pixels.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Pixels of the image, row after row, top row first.
+    pub fn pixels(&self) -> &[BltPixel] {
+        &self.pixels
+    }
+
+    /// Returns the color of a pixel, or `None` if it is outside of the image.
+    pub fn pixel(&self, x: usize, y: usize) -> Option<BltPixel> {
+        if x < self.width && y < self.height {
+            Some(self.pixels[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    /// Returns a copy of the image resized to (width, height) by picking the
+    /// nearest pixel.
+    ///
+    /// An empty image has no pixels to pick from, so it is returned unchanged.
+    pub fn resize(&self, width: usize, height: usize) -> Self {
+        if self.pixels.is_empty() {
+            return self.clone();
+        }
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let src_row = y * self.height / height * self.width;
+            for x in 0..width {
+                pixels.push(self.pixels[src_row + x * self.width / width]);
+            }
+        }
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Draw the image on the screen.
+    ///
+    /// The image is scaled first and then positioned. The parts of the image
+    /// which do not fit on the screen are not drawn.
+    pub fn draw(&self, gop: &mut GraphicsOutput, position: Position, scaling: Scaling) -> Result {
+        if self.pixels.is_empty() {
+            return Ok(().into());
+        }
+        let screen = gop.current_mode_info().resolution();
+        let size = match scaling {
+            Scaling::None => self.size(),
+            Scaling::Factor(factor) => (self.width * factor, self.height * factor),
+            Scaling::Fit => {
+                // Compare the aspect ratios of the image and of the screen
+                if self.width * screen.1 > screen.0 * self.height {
+                    (screen.0, self.height * screen.0 / self.width)
+                } else {
+                    (self.width * screen.1 / self.height, screen.1)
+                }
+            }
+        };
+        let resized;
+        let image = if size == self.size() {
+            self
+        } else {
+            resized = self.resize(size.0, size.1);
+            &resized
+        };
+
+        // Coordinates of the top-left corner, which may be off-screen
+        let (x, y) = match position {
+            Position::At(x, y) => (x as isize, y as isize),
+            Position::Center => (
+                (screen.0 as isize - size.0 as isize) / 2,
+                (screen.1 as isize - size.1 as isize) / 2,
+            ),
+        };
+        let clip = |start: isize, len: usize, max: usize| {
+            let src = (-start).max(0) as usize;
+            let dest = start.max(0) as usize;
+            let len = len.saturating_sub(src).min(max.saturating_sub(dest));
+            (src, dest, len)
+        };
+        let (src_x, dest_x, width) = clip(x, size.0, screen.0);
+        let (src_y, dest_y, height) = clip(y, size.1, screen.1);
+        if width == 0 || height == 0 {
+            return Ok(().into());
+        }
+
+        gop.blt(BltOp::BufferToVideo {
+            buffer: &image.pixels,
+            src: BltRegion::SubRectangle {
+                coords: (src_x, src_y),
+                px_stride: image.width,
+            },
+            dest: (dest_x, dest_y),
+            dims: (width, height),
+        })
+    }
+}
+
+/// Encoding of the pixels of a bitmap.
+enum PixelFormat<'a> {
+    /// Indexes into a palette of BGRx colors
+    Indexed(&'a [u8]),
+    /// 24 or 32-bit BGR(x) pixels
+    Bgr,
+    /// 16 or 32-bit pixels with custom channel masks
+    Masks(ChannelMasks),
+}
+
+impl PixelFormat<'_> {
+    fn decode(
+        &self,
+        row: &[u8],
+        x: usize,
+        bits_per_pixel: u16,
+    ) -> core::result::Result<BltPixel, Status> {
+        match self {
+            PixelFormat::Indexed(palette) => {
+                let bits = usize::from(bits_per_pixel);
+                let bit = x * bits;
+                let shift = 8 - bits - bit % 8;
+                let index = usize::from((row[bit / 8] >> shift) & ((1 << bits) - 1) as u8);
+                let entry = palette
+                    .get(index * 4..index * 4 + 3)
+                    .ok_or(Status::INVALID_PARAMETER)?;
+                Ok(BltPixel::new(entry[2], entry[1], entry[0]))
+            }
+            PixelFormat::Bgr => {
+                let bytes = usize::from(bits_per_pixel / 8);
+                let pixel = &row[x * bytes..];
+                Ok(BltPixel::new(pixel[2], pixel[1], pixel[0]))
+            }
+            PixelFormat::Masks(masks) => {
+                let value = if bits_per_pixel == 16 {
+                    u32::from(read_u16(row, x * 2)?)
+                } else {
+                    read_u32(row, x * 4)?
+                };
+                Ok(masks.decode(value))
+            }
+        }
+    }
+}
+
+/// Masks selecting the bits of each color channel in a pixel.
+struct ChannelMasks {
+    red: u32,
+    green: u32,
+    blue: u32,
+}
+
+impl ChannelMasks {
+    fn new(red: u32, green: u32, blue: u32) -> Self {
+        Self { red, green, blue }
+    }
+
+    fn decode(&self, value: u32) -> BltPixel {
+        BltPixel::new(
+            decode_channel(value, self.red),
+            decode_channel(value, self.green),
+            decode_channel(value, self.blue),
+        )
+    }
+}
+
+/// Scales the bits of `value` selected by `mask` to an 8-bit color channel
+fn decode_channel(value: u32, mask: u32) -> u8 {
+    if mask == 0 {
+        return 0;
+    }
+    let shift = mask.trailing_zeros();
+    let max = u64::from(mask >> shift);
+    let channel = u64::from((value & mask) >> shift);
+    (channel * 255 / max) as u8
+}
+
+fn read_u16(data: &[u8], offset: usize) -> core::result::Result<u16, Status> {
+    let bytes = data
+        .get(offset..offset + 2)
+        .ok_or(Status::INVALID_PARAMETER)?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> core::result::Result<u32, Status> {
+    let bytes = data
+        .get(offset..offset + 4)
+        .ok_or(Status::INVALID_PARAMETER)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
@@ -5,7 +5,7 @@
 use core::{ffi::c_void, mem::MaybeUninit};
 
 /// Opaque handle to an UEFI entity (protocol, image...)
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 #[repr(transparent)]
 pub struct Handle(*mut c_void);
 
@@ -13,6 +13,16 @@ impl Handle {
     pub(crate) unsafe fn uninitialized() -> Self {
         MaybeUninit::zeroed().assume_init()
     }
+
+    /// Null handle, used by the interfaces which take an optional handle
+    pub(crate) fn null() -> Self {
+        Handle(core::ptr::null_mut())
+    }
+
+    /// Whether this is the null handle
+    pub(crate) fn is_null(&self) -> bool {
+        self.0.is_null()
+    }
 }
 
 /// Handle to an event structure
@@ -20,6 +30,13 @@ impl Handle {
 #[repr(transparent)]
 pub struct Event(*mut c_void);
 
+impl Event {
+    /// Null event, used by the interfaces which take an optional event
+    pub(crate) fn null() -> Self {
+        Event(core::ptr::null_mut())
+    }
+}
+
 /// Trait for querying the alignment of a struct
 ///
 /// Needed for dynamic-sized types because `mem::align_of` has a `Sized` bound (due to `dyn Trait`)
@@ -49,5 +66,13 @@ pub use self::chars::{Char16, Char8};
 #[macro_use]
 mod enums;
 
+mod net;
+pub use self::net::{IpAddress, Ipv4Address, Ipv6Address, MacAddress};
+
 mod strs;
 pub use self::strs::{CStr16, CStr8};
+
+mod path;
+#[cfg(feature = "exts")]
+pub use self::path::PathBuf;
+pub use self::path::{Components, Path};
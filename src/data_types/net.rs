@@ -0,0 +1,281 @@
+//! Network addresses, as stored by the network protocols.
+//!
+//! The IP addresses convert to and from those of `core::net`, which are used
+//! to format and parse them.
+
+use crate::Status;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::net::{AddrParseError, IpAddr, Ipv4Addr, Ipv6Addr};
+use core::str::FromStr;
+
+/// Hardware address of a network interface.
+///
+/// Only the first `hw_address_size` bytes of the mode of the interface are
+/// meaningful, the rest is zero padding. For Ethernet interfaces, these are
+/// the first 6 bytes.
+///
+/// The `Display` formatter prints the address as colon-separated bytes, such
+/// as `52:54:00:12:34:56`, stopping after the first 6 bytes unless the
+/// padding is not zero.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Hash)]
+#[repr(transparent)]
+pub struct MacAddress(pub [u8; 32]);
+
+impl MacAddress {
+    /// Returns the address of an Ethernet interface, made of the first 6
+    /// bytes.
+    pub fn ethernet(&self) -> [u8; 6] {
+        let mut address = [0; 6];
+        address.copy_from_slice(&self.0[..6]);
+        address
+    }
+
+    /// Bytes printed by the formatters
+    fn significant_bytes(&self) -> &[u8] {
+        let len = self
+            .0
+            .iter()
+            .rposition(|&byte| byte != 0)
+            .map_or(0, |i| i + 1);
+        &self.0[..len.max(6)]
+    }
+}
+
+impl From<[u8; 6]> for MacAddress {
+    /// Creates the address of an Ethernet interface.
+    fn from(address: [u8; 6]) -> Self {
+        let mut padded = [0; 32];
+        padded[..6].copy_from_slice(&address);
+        Self(padded)
+    }
+}
+
+impl fmt::Debug for MacAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MacAddress({})", self)
+    }
+}
+
+impl fmt::Display for MacAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, byte) in self.significant_bytes().iter().enumerate() {
+            if i != 0 {
+                f.write_str(":")?;
+            }
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for MacAddress {
+    type Err = Status;
+
+    /// Parses 6 to 32 bytes of two hexadecimal digits separated by colons or
+    /// hyphens, failing with `InvalidParameter` otherwise
+    fn from_str(string: &str) -> Result<Self, Status> {
+        let mut address = [0; 32];
+        let mut len = 0;
+        for part in string.split([':', '-']) {
+            let byte = address.get_mut(len).ok_or(Status::INVALID_PARAMETER)?;
+            if part.len() != 2 {
+                return Err(Status::INVALID_PARAMETER);
+            }
+            *byte = u8::from_str_radix(part, 16).map_err(|_| Status::INVALID_PARAMETER)?;
+            len += 1;
+        }
+        if len < 6 {
+            return Err(Status::INVALID_PARAMETER);
+        }
+        Ok(Self(address))
+    }
+}
+
+/// IPv4 address, in network byte order.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+#[repr(transparent)]
+pub struct Ipv4Address(pub [u8; 4]);
+
+impl Ipv4Address {
+    /// The unspecified address, `0.0.0.0`.
+    pub const UNSPECIFIED: Self = Self([0; 4]);
+
+    /// Creates the address `a.b.c.d`.
+    pub const fn new(a: u8, b: u8, c: u8, d: u8) -> Self {
+        Self([a, b, c, d])
+    }
+
+    /// Returns the bytes of the address.
+    pub const fn octets(&self) -> [u8; 4] {
+        self.0
+    }
+}
+
+impl From<[u8; 4]> for Ipv4Address {
+    fn from(octets: [u8; 4]) -> Self {
+        Self(octets)
+    }
+}
+
+impl From<Ipv4Addr> for Ipv4Address {
+    fn from(address: Ipv4Addr) -> Self {
+        Self(address.octets())
+    }
+}
+
+impl From<Ipv4Address> for Ipv4Addr {
+    fn from(address: Ipv4Address) -> Self {
+        Self::from(address.0)
+    }
+}
+
+impl fmt::Display for Ipv4Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&Ipv4Addr::from(*self), f)
+    }
+}
+
+impl FromStr for Ipv4Address {
+    type Err = AddrParseError;
+
+    /// Parses an address in dotted decimal notation, such as `10.0.2.15`
+    fn from_str(string: &str) -> Result<Self, AddrParseError> {
+        string.parse::<Ipv4Addr>().map(Self::from)
+    }
+}
+
+/// IPv6 address, in network byte order.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+#[repr(transparent)]
+pub struct Ipv6Address(pub [u8; 16]);
+
+impl Ipv6Address {
+    /// The unspecified address, `::`.
+    pub const UNSPECIFIED: Self = Self([0; 16]);
+
+    /// Returns the bytes of the address.
+    pub const fn octets(&self) -> [u8; 16] {
+        self.0
+    }
+}
+
+impl From<[u8; 16]> for Ipv6Address {
+    fn from(octets: [u8; 16]) -> Self {
+        Self(octets)
+    }
+}
+
+impl From<Ipv6Addr> for Ipv6Address {
+    fn from(address: Ipv6Addr) -> Self {
+        Self(address.octets())
+    }
+}
+
+impl From<Ipv6Address> for Ipv6Addr {
+    fn from(address: Ipv6Address) -> Self {
+        Self::from(address.0)
+    }
+}
+
+impl fmt::Display for Ipv6Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&Ipv6Addr::from(*self), f)
+    }
+}
+
+impl FromStr for Ipv6Address {
+    type Err = AddrParseError;
+
+    /// Parses an address in the notation of RFC 5952, such as `fe80::1`
+    fn from_str(string: &str) -> Result<Self, AddrParseError> {
+        string.parse::<Ipv6Addr>().map(Self::from)
+    }
+}
+
+/// IPv4 or IPv6 address, as stored by the interfaces which take either.
+///
+/// Like `EFI_IP_ADDRESS`, this is a 4-byte aligned union of both, where an
+/// IPv4 address only uses the first 4 bytes. Which one is stored depends on
+/// the context, e.g. on a separate flag. The unused bytes are always zero.
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub union IpAddress {
+    addr: [u32; 4],
+    v4: Ipv4Address,
+    v6: Ipv6Address,
+}
+
+impl IpAddress {
+    /// Stores an IPv4 address.
+    pub fn new_v4(address: Ipv4Address) -> Self {
+        let mut octets = [0; 16];
+        octets[..4].copy_from_slice(&address.0);
+        Self {
+            v6: Ipv6Address(octets),
+        }
+    }
+
+    /// Stores an IPv6 address.
+    pub const fn new_v6(address: Ipv6Address) -> Self {
+        Self { v6: address }
+    }
+
+    /// Returns the address as an IPv4 address.
+    pub fn as_ipv4(&self) -> Ipv4Address {
+        // All the bytes are initialized by the constructors
+        unsafe { self.v4 }
+    }
+
+    /// Returns the address as an IPv6 address.
+    pub fn as_ipv6(&self) -> Ipv6Address {
+        unsafe { self.v6 }
+    }
+}
+
+impl Default for IpAddress {
+    fn default() -> Self {
+        Self { addr: [0; 4] }
+    }
+}
+
+impl PartialEq for IpAddress {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_ipv6() == other.as_ipv6()
+    }
+}
+
+impl Eq for IpAddress {}
+
+impl Hash for IpAddress {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_ipv6().hash(state)
+    }
+}
+
+impl fmt::Debug for IpAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("IpAddress").field(&self.as_ipv6().0).finish()
+    }
+}
+
+impl From<Ipv4Address> for IpAddress {
+    fn from(address: Ipv4Address) -> Self {
+        Self::new_v4(address)
+    }
+}
+
+impl From<Ipv6Address> for IpAddress {
+    fn from(address: Ipv6Address) -> Self {
+        Self::new_v6(address)
+    }
+}
+
+impl From<IpAddr> for IpAddress {
+    fn from(address: IpAddr) -> Self {
+        match address {
+            IpAddr::V4(address) => Self::new_v4(address.into()),
+            IpAddr::V6(address) => Self::new_v6(address.into()),
+        }
+    }
+}
@@ -0,0 +1,316 @@
+use super::chars::Char16;
+use super::strs::CStr16;
+use core::fmt;
+#[cfg(feature = "exts")]
+use {
+    super::chars::NUL_16,
+    crate::Status,
+    alloc_api::vec::Vec,
+    core::{convert::TryFrom, ops::Deref, str::FromStr},
+};
+
+/// Separator between the components of a UEFI path
+const SEPARATOR: char = '\\';
+
+fn is_separator(c: &Char16) -> bool {
+    u16::from(*c) == SEPARATOR as u16
+}
+
+/// A borrowed UEFI file path
+///
+/// UEFI paths are UCS-2 strings whose components are separated by backslashes
+/// (`\`). A path starting with a backslash is relative to the root of the
+/// volume, other paths are relative to the directory they are opened from.
+///
+/// This type is largely inspired by `std::path::Path`. Unlike `CStr16`, it is
+/// not null-terminated, which allows taking sub-paths without copying.
+#[repr(transparent)]
+pub struct Path([Char16]);
+
+impl Path {
+    /// Wraps a slice of UCS-2 characters as a path
+    pub fn from_chars(chars: &[Char16]) -> &Self {
+        unsafe { &*(chars as *const [Char16] as *const Self) }
+    }
+
+    /// Returns the characters of this path
+    pub fn as_chars(&self) -> &[Char16] {
+        &self.0
+    }
+
+    /// Returns true if the path is empty
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns true if the path is relative to the root of the volume
+    pub fn is_absolute(&self) -> bool {
+        matches!(self.0.first(), Some(c) if is_separator(c))
+    }
+
+    /// Iterate over the components of this path
+    ///
+    /// Empty components, such as those produced by a leading or repeated
+    /// separator, are skipped.
+    pub fn components(&self) -> Components<'_> {
+        Components { rest: &self.0 }
+    }
+
+    /// Returns the last component of this path, if any
+    pub fn file_name(&self) -> Option<&Path> {
+        self.components().next_back()
+    }
+
+    /// Returns this path without its last component
+    ///
+    /// Returns `None` if the path has no components.
+    pub fn parent(&self) -> Option<&Path> {
+        let chars = self.trim_trailing_separators();
+        let name_start = chars
+            .iter()
+            .rposition(is_separator)
+            .map_or(0, |pos| pos + 1);
+        if name_start == chars.len() {
+            return None;
+        }
+
+        // Keep the leading separator of an absolute path
+        let mut parent = &chars[..name_start];
+        while parent.len() > 1 && matches!(parent.last(), Some(c) if is_separator(c)) {
+            parent = &parent[..parent.len() - 1];
+        }
+        Some(Path::from_chars(parent))
+    }
+
+    /// Creates an owned path with `path` appended to this path
+    ///
+    /// See `PathBuf::push` for details.
+    #[cfg(feature = "exts")]
+    pub fn join(&self, path: &Path) -> PathBuf {
+        let mut buf = PathBuf::from(self);
+        buf.push(path);
+        buf
+    }
+
+    fn trim_trailing_separators(&self) -> &[Char16] {
+        let end = self
+            .0
+            .iter()
+            .rposition(|c| !is_separator(c))
+            .map_or(0, |pos| pos + 1);
+        &self.0[..end]
+    }
+}
+
+impl<'a> From<&'a CStr16> for &'a Path {
+    fn from(string: &'a CStr16) -> Self {
+        let chars = string.to_u16_slice();
+        Path::from_chars(unsafe { &*(chars as *const [u16] as *const [Char16]) })
+    }
+}
+
+impl PartialEq for Path {
+    fn eq(&self, other: &Path) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for Path {}
+
+impl fmt::Debug for Path {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Path(\"{}\")", self)
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for c in self.0.iter() {
+            <Char16 as fmt::Display>::fmt(c, f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Iterator over the components of a `Path`
+#[derive(Debug)]
+pub struct Components<'a> {
+    rest: &'a [Char16],
+}
+
+impl<'a> Iterator for Components<'a> {
+    type Item = &'a Path;
+
+    fn next(&mut self) -> Option<&'a Path> {
+        while let Some((first, rest)) = self.rest.split_first() {
+            if !is_separator(first) {
+                break;
+            }
+            self.rest = rest;
+        }
+        if self.rest.is_empty() {
+            return None;
+        }
+
+        let end = self
+            .rest
+            .iter()
+            .position(is_separator)
+            .unwrap_or(self.rest.len());
+        let (component, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        Some(Path::from_chars(component))
+    }
+}
+
+impl<'a> DoubleEndedIterator for Components<'a> {
+    fn next_back(&mut self) -> Option<&'a Path> {
+        while let Some((last, rest)) = self.rest.split_last() {
+            if !is_separator(last) {
+                break;
+            }
+            self.rest = rest;
+        }
+        if self.rest.is_empty() {
+            return None;
+        }
+
+        let start = self
+            .rest
+            .iter()
+            .rposition(is_separator)
+            .map_or(0, |pos| pos + 1);
+        let (rest, component) = self.rest.split_at(start);
+        self.rest = rest;
+        Some(Path::from_chars(component))
+    }
+}
+
+/// An owned, mutable UEFI file path
+///
+/// This is the owned counterpart of `Path`. It is always null-terminated, so
+/// that it can be passed to UEFI functions as a `CStr16`.
+#[cfg(feature = "exts")]
+#[derive(Clone, PartialEq, Eq)]
+pub struct PathBuf {
+    // Always ends with NUL_16, which is not part of the path
+    chars: Vec<Char16>,
+}
+
+#[cfg(feature = "exts")]
+impl PathBuf {
+    /// Creates an empty path
+    pub fn new() -> Self {
+        Self {
+            chars: alloc_api::vec![NUL_16],
+        }
+    }
+
+    /// Borrows this path
+    pub fn as_path(&self) -> &Path {
+        Path::from_chars(&self.chars[..self.chars.len() - 1])
+    }
+
+    /// Borrows this path as a null-terminated string
+    pub fn as_cstr16(&self) -> &CStr16 {
+        let codes = unsafe { &*(&self.chars[..] as *const [Char16] as *const [u16]) };
+        unsafe { CStr16::from_u16_with_nul_unchecked(codes) }
+    }
+
+    /// Appends `path` to this path
+    ///
+    /// If `path` is absolute, it replaces this path. Otherwise, it is appended
+    /// after a separator, unless this path is empty or already ends with one.
+    pub fn push(&mut self, path: &Path) {
+        if path.is_absolute() {
+            self.chars.clear();
+        } else {
+            self.chars.pop();
+            if matches!(self.chars.last(), Some(c) if !is_separator(c)) {
+                self.chars.push(Char16::try_from(SEPARATOR).unwrap());
+            }
+        }
+        self.chars.extend_from_slice(path.as_chars());
+        self.chars.push(NUL_16);
+    }
+
+    /// Removes the last component of this path
+    ///
+    /// Returns false, leaving the path untouched, if it has no components.
+    pub fn pop(&mut self) -> bool {
+        match self.parent().map(|parent| parent.0.len()) {
+            Some(len) => {
+                self.chars.truncate(len);
+                self.chars.push(NUL_16);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(feature = "exts")]
+impl Default for PathBuf {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "exts")]
+impl Deref for PathBuf {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        self.as_path()
+    }
+}
+
+#[cfg(feature = "exts")]
+impl From<&Path> for PathBuf {
+    fn from(path: &Path) -> Self {
+        let mut chars = Vec::with_capacity(path.0.len() + 1);
+        chars.extend_from_slice(path.as_chars());
+        chars.push(NUL_16);
+        Self { chars }
+    }
+}
+
+#[cfg(feature = "exts")]
+impl From<&CStr16> for PathBuf {
+    fn from(string: &CStr16) -> Self {
+        <&Path>::from(string).into()
+    }
+}
+
+#[cfg(feature = "exts")]
+impl FromStr for PathBuf {
+    type Err = Status;
+
+    /// Encodes a path to UCS-2, failing if it contains characters which can
+    /// not be represented or a NUL character
+    fn from_str(string: &str) -> Result<Self, Status> {
+        let mut chars = Vec::with_capacity(string.len() + 1);
+        for c in string.chars() {
+            match Char16::try_from(c) {
+                Ok(NUL_16) | Err(_) => return Err(Status::INVALID_PARAMETER),
+                Ok(c) => chars.push(c),
+            }
+        }
+        chars.push(NUL_16);
+        Ok(Self { chars })
+    }
+}
+
+#[cfg(feature = "exts")]
+impl fmt::Debug for PathBuf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PathBuf(\"{}\")", self.as_path())
+    }
+}
+
+#[cfg(feature = "exts")]
+impl fmt::Display for PathBuf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.as_path().fmt(f)
+    }
+}
@@ -0,0 +1,648 @@
+//! Read-only FAT file system driver.
+//!
+//! Firmware normally exposes FAT volumes through the `SimpleFileSystem`
+//! protocol, but some refuse to bind it to certain devices, such as disk
+//! images attached as raw RAM disks. This module implements read-only access
+//! to FAT12, FAT16 and FAT32 volumes on top of any `Volume`, which can be
+//! backed by a `DiskIo` or a `BlockIO` protocol.
+//!
+//! Paths are UEFI paths, with components separated by backslashes (`\`), and
+//! are always interpreted relative to the root of the volume. File names are
+//! compared case-insensitively, like FAT drivers do.
+
+use crate::io::{self, Read, Seek, SeekFrom};
+use crate::proto::media::block::{BlockBuffer, BlockIO, Lba};
+use crate::proto::media::disk::DiskIo;
+use crate::proto::media::file::FileAttribute;
+use crate::{Result, ResultExt, Status};
+use alloc_api::{string::String, vec::Vec};
+use core::cmp;
+use core::convert::TryFrom;
+
+/// Random-access storage containing a FAT volume
+pub trait Volume {
+    /// Fill `buffer` with the bytes found at `offset` from the start of the
+    /// volume
+    fn read_at(&mut self, offset: u64, buffer: &mut [u8]) -> io::Result<()>;
+}
+
+/// Volume backed by a `DiskIo` protocol
+pub struct DiskVolume<'a> {
+    disk_io: &'a DiskIo,
+    media_id: u32,
+    start: u64,
+}
+
+impl<'a> DiskVolume<'a> {
+    /// Access the volume starting `start` bytes into the medium `media_id`
+    pub fn new(disk_io: &'a DiskIo, media_id: u32, start: u64) -> Self {
+        Self {
+            disk_io,
+            media_id,
+            start,
+        }
+    }
+}
+
+impl Volume for DiskVolume<'_> {
+    fn read_at(&mut self, offset: u64, buffer: &mut [u8]) -> io::Result<()> {
+        let offset = self
+            .start
+            .checked_add(offset)
+            .ok_or(Status::INVALID_PARAMETER)?;
+        self.disk_io
+            .read_disk(self.media_id, offset, buffer)
+            .log_warning()
+            .map_err(|error| error.status())
+    }
+}
+
+/// Volume backed by a `BlockIO` protocol
+///
+/// Reads are performed one block at a time, and the last block read is
+/// cached, so this is slower than a `DiskVolume`. It is useful for devices
+/// which firmware did not layer the `DiskIo` protocol on.
+pub struct BlockVolume<'a> {
+    block_io: &'a BlockIO,
+    start_lba: Lba,
+    block: BlockBuffer,
+    cached_lba: Option<Lba>,
+}
+
+impl<'a> BlockVolume<'a> {
+    /// Access the volume starting at block `start_lba` of the device
+    pub fn new(block_io: &'a BlockIO, start_lba: Lba) -> Self {
+        let block = BlockBuffer::new(block_io, block_io.media().block_size() as usize);
+        Self {
+            block_io,
+            start_lba,
+            block,
+            cached_lba: None,
+        }
+    }
+}
+
+impl Volume for BlockVolume<'_> {
+    fn read_at(&mut self, offset: u64, mut buffer: &mut [u8]) -> io::Result<()> {
+        let block_size = u64::from(self.block_io.media().block_size());
+        let mut offset = offset;
+        while !buffer.is_empty() {
+            let lba = self
+                .start_lba
+                .checked_add(offset / block_size)
+                .ok_or(Status::INVALID_PARAMETER)?;
+            if self.cached_lba != Some(lba) {
+                self.cached_lba = None;
+                self.block.read(self.block_io, lba)?;
+                self.cached_lba = Some(lba);
+            }
+
+            let start = (offset % block_size) as usize;
+            let block = &self.block.as_slice()[start..];
+            let len = cmp::min(block.len(), buffer.len());
+            buffer[..len].copy_from_slice(&block[..len]);
+            buffer = &mut buffer[len..];
+            offset += len as u64;
+        }
+        Ok(())
+    }
+}
+
+/// Variant of the FAT format, determined by the number of clusters
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FatType {
+    /// 12-bit cluster numbers
+    Fat12,
+    /// 16-bit cluster numbers
+    Fat16,
+    /// 28-bit cluster numbers
+    Fat32,
+}
+
+/// Size of a directory entry
+const DIR_ENTRY_SIZE: usize = 32;
+
+/// Attribute combination marking a long file name entry
+const ATTR_LONG_NAME: u8 = 0x0f;
+
+/// Attribute marking the volume label entry
+const ATTR_VOLUME_ID: u8 = 0x08;
+
+/// First byte of the name of a deleted directory entry
+const DELETED_ENTRY: u8 = 0xe5;
+
+/// Read-only FAT file system
+pub struct FatFileSystem<V: Volume> {
+    volume: V,
+    fat_type: FatType,
+    /// Byte offset of the first FAT
+    fat_offset: u64,
+    /// Byte offset and size of the fixed root directory of FAT12/16 volumes
+    root_dir_offset: u64,
+    root_dir_size: usize,
+    /// First cluster of the root directory of FAT32 volumes
+    root_cluster: u32,
+    /// Byte offset of cluster 2
+    data_offset: u64,
+    cluster_size: u32,
+    cluster_count: u32,
+}
+
+impl<V: Volume> FatFileSystem<V> {
+    /// Mount the FAT volume stored in `volume`
+    ///
+    /// Fails with `Status::UNSUPPORTED` if the volume does not start with a
+    /// valid FAT boot sector.
+    pub fn mount(mut volume: V) -> Result<Self> {
+        let mut boot_sector = [0; 512];
+        volume.read_at(0, &mut boot_sector)?;
+        let u16_at =
+            |offset: usize| u16::from_le_bytes([boot_sector[offset], boot_sector[offset + 1]]);
+        let u32_at = |offset: usize| {
+            u32::from_le_bytes([
+                boot_sector[offset],
+                boot_sector[offset + 1],
+                boot_sector[offset + 2],
+                boot_sector[offset + 3],
+            ])
+        };
+
+        let bytes_per_sector = u32::from(u16_at(11));
+        let sectors_per_cluster = u32::from(boot_sector[13]);
+        let reserved_sectors = u32::from(u16_at(14));
+        let num_fats = u32::from(boot_sector[16]);
+        let root_entry_count = u32::from(u16_at(17));
+        let total_sectors = match u16_at(19) {
+            0 => u32_at(32),
+            sectors => u32::from(sectors),
+        };
+        let fat_size = match u16_at(22) {
+            0 => u32_at(36),
+            sectors => u32::from(sectors),
+        };
+
+        if boot_sector[510..] != [0x55, 0xaa]
+            || !(512..=4096).contains(&bytes_per_sector)
+            || !bytes_per_sector.is_power_of_two()
+            || !sectors_per_cluster.is_power_of_two()
+            || reserved_sectors == 0
+            || num_fats == 0
+            || fat_size == 0
+        {
+            return Err(Status::UNSUPPORTED.into());
+        }
+
+        let root_dir_size = root_entry_count * DIR_ENTRY_SIZE as u32;
+        let root_dir_sectors = match root_dir_size % bytes_per_sector {
+            0 => root_dir_size / bytes_per_sector,
+            _ => root_dir_size / bytes_per_sector + 1,
+        };
+        let data_start = u64::from(reserved_sectors)
+            + u64::from(num_fats) * u64::from(fat_size)
+            + u64::from(root_dir_sectors);
+        let data_sectors = u64::from(total_sectors)
+            .checked_sub(data_start)
+            .ok_or(Status::UNSUPPORTED)?;
+        let cluster_count = (data_sectors / u64::from(sectors_per_cluster)) as u32;
+
+        // The FAT type is determined by the cluster count alone
+        let fat_type = if cluster_count < 4085 {
+            FatType::Fat12
+        } else if cluster_count < 65525 {
+            FatType::Fat16
+        } else {
+            FatType::Fat32
+        };
+
+        let sector = |sectors: u64| sectors * u64::from(bytes_per_sector);
+        Ok(Self {
+            volume,
+            fat_type,
+            fat_offset: sector(reserved_sectors.into()),
+            root_dir_offset: sector(data_start - u64::from(root_dir_sectors)),
+            root_dir_size: root_dir_size as usize,
+            root_cluster: if fat_type == FatType::Fat32 {
+                u32_at(44)
+            } else {
+                0
+            },
+            data_offset: sector(data_start),
+            cluster_size: bytes_per_sector * sectors_per_cluster,
+            cluster_count,
+        }
+        .into())
+    }
+
+    /// Variant of the FAT format used by the volume
+    pub fn fat_type(&self) -> FatType {
+        self.fat_type
+    }
+
+    /// Size of the allocation units of the volume, in bytes
+    pub fn cluster_size(&self) -> u32 {
+        self.cluster_size
+    }
+
+    /// List the entries of a directory
+    ///
+    /// The `.` and `..` entries of subdirectories are included.
+    pub fn read_dir(&mut self, path: &str) -> Result<Vec<DirEntry>> {
+        let dir = self.lookup(path)?;
+        if !dir.is_directory() {
+            return Err(Status::INVALID_PARAMETER.into());
+        }
+        Ok(self.dir_entries(dir.first_cluster)?.into())
+    }
+
+    /// Query information about a file or directory
+    pub fn metadata(&mut self, path: &str) -> Result<DirEntry> {
+        Ok(self.lookup(path)?.into())
+    }
+
+    /// Open a file for reading
+    ///
+    /// Fails with `Status::INVALID_PARAMETER` if `path` is a directory.
+    pub fn open(&mut self, path: &str) -> Result<FatFile<'_, V>> {
+        let entry = self.lookup(path)?;
+        if entry.is_directory() {
+            return Err(Status::INVALID_PARAMETER.into());
+        }
+        Ok(FatFile {
+            cursor: (0, entry.first_cluster),
+            fs: self,
+            first_cluster: entry.first_cluster,
+            size: entry.size,
+            position: 0,
+        }
+        .into())
+    }
+
+    /// Read the entire contents of a file
+    ///
+    /// Fails with `Status::INVALID_PARAMETER` if `path` is a directory.
+    pub fn read(&mut self, path: &str) -> Result<Vec<u8>> {
+        let mut file = self.open(path)?.log();
+        let mut buffer = alloc_api::vec![0; file.size() as usize];
+        file.read_exact(&mut buffer)?;
+        Ok(buffer.into())
+    }
+
+    /// Find the entry designated by `path`
+    fn lookup(&mut self, path: &str) -> io::Result<DirEntry> {
+        let mut entry = DirEntry::root();
+        for component in path.split('\\').filter(|c| !c.is_empty()) {
+            if !entry.is_directory() {
+                return Err(Status::NOT_FOUND);
+            }
+            entry = self
+                .dir_entries(entry.first_cluster)?
+                .into_iter()
+                .find(|child| names_match(&child.name, component))
+                .ok_or(Status::NOT_FOUND)?;
+        }
+        Ok(entry)
+    }
+
+    /// Parse the entries of the directory starting at `first_cluster`
+    ///
+    /// A first cluster of 0 designates the root directory.
+    fn dir_entries(&mut self, first_cluster: u32) -> io::Result<Vec<DirEntry>> {
+        let data = if first_cluster == 0 && self.fat_type != FatType::Fat32 {
+            let mut data = alloc_api::vec![0; self.root_dir_size];
+            self.volume.read_at(self.root_dir_offset, &mut data)?;
+            data
+        } else {
+            let first_cluster = match first_cluster {
+                0 => self.root_cluster,
+                cluster => cluster,
+            };
+            self.read_chain(first_cluster)?
+        };
+
+        let mut entries = Vec::new();
+        let mut long_name = LongName::new();
+        for raw in data.chunks_exact(DIR_ENTRY_SIZE) {
+            match raw[0] {
+                0 => break,
+                DELETED_ENTRY => {
+                    long_name.reset();
+                    continue;
+                }
+                _ => {}
+            }
+
+            let attributes = raw[11];
+            if attributes & 0x3f == ATTR_LONG_NAME {
+                long_name.push(raw);
+                continue;
+            }
+            if attributes & ATTR_VOLUME_ID != 0 {
+                long_name.reset();
+                continue;
+            }
+
+            let short_name = <&[u8; 11]>::try_from(&raw[..11]).unwrap();
+            let name = long_name
+                .take(short_name)
+                .unwrap_or_else(|| format_short_name(short_name, raw[12]));
+            let cluster_high = u32::from(u16::from_le_bytes([raw[20], raw[21]]));
+            let cluster_low = u32::from(u16::from_le_bytes([raw[26], raw[27]]));
+            entries.push(DirEntry {
+                name,
+                attributes: FileAttribute::from_bits_truncate(attributes.into()),
+                size: u32::from_le_bytes([raw[28], raw[29], raw[30], raw[31]]).into(),
+                first_cluster: (cluster_high << 16) | cluster_low,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Read all the clusters of a cluster chain
+    fn read_chain(&mut self, first_cluster: u32) -> io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        let mut cluster = Some(first_cluster);
+        while let Some(current) = cluster {
+            // A chain can't be longer than the number of clusters
+            if data.len() / self.cluster_size as usize > self.cluster_count as usize {
+                return Err(Status::VOLUME_CORRUPTED);
+            }
+            let start = data.len();
+            data.resize(start + self.cluster_size as usize, 0);
+            let offset = self.cluster_offset(current)?;
+            self.volume.read_at(offset, &mut data[start..])?;
+            cluster = self.next_cluster(current)?;
+        }
+        Ok(data)
+    }
+
+    /// Byte offset of a data cluster
+    fn cluster_offset(&self, cluster: u32) -> io::Result<u64> {
+        if cluster < 2 || cluster - 2 >= self.cluster_count {
+            return Err(Status::VOLUME_CORRUPTED);
+        }
+        Ok(self.data_offset + u64::from(cluster - 2) * u64::from(self.cluster_size))
+    }
+
+    /// Look up the cluster following `cluster` in the FAT
+    ///
+    /// Returns `None` at the end of the chain.
+    fn next_cluster(&mut self, cluster: u32) -> io::Result<Option<u32>> {
+        let mut bytes = [0; 4];
+        let (next, end_of_chain) = match self.fat_type {
+            FatType::Fat12 => {
+                let offset = u64::from(cluster) + u64::from(cluster / 2);
+                self.volume
+                    .read_at(self.fat_offset + offset, &mut bytes[..2])?;
+                let pair = u32::from(u16::from_le_bytes([bytes[0], bytes[1]]));
+                let next = if cluster & 1 == 0 {
+                    pair & 0xfff
+                } else {
+                    pair >> 4
+                };
+                (next, 0xff7)
+            }
+            FatType::Fat16 => {
+                let offset = u64::from(cluster) * 2;
+                self.volume
+                    .read_at(self.fat_offset + offset, &mut bytes[..2])?;
+                (u32::from(u16::from_le_bytes([bytes[0], bytes[1]])), 0xfff7)
+            }
+            FatType::Fat32 => {
+                let offset = u64::from(cluster) * 4;
+                self.volume.read_at(self.fat_offset + offset, &mut bytes)?;
+                (u32::from_le_bytes(bytes) & 0x0fff_ffff, 0x0fff_fff7)
+            }
+        };
+
+        // The value just below the end-of-chain markers flags bad clusters
+        match next {
+            next if next > end_of_chain => Ok(None),
+            next if next < 2 || next == end_of_chain => Err(Status::VOLUME_CORRUPTED),
+            next => Ok(Some(next)),
+        }
+    }
+}
+
+/// Entry of a FAT directory
+#[derive(Debug, Clone)]
+pub struct DirEntry {
+    name: String,
+    attributes: FileAttribute,
+    size: u64,
+    first_cluster: u32,
+}
+
+impl DirEntry {
+    /// Entry describing the root directory, which has no directory entry
+    fn root() -> Self {
+        Self {
+            name: String::new(),
+            attributes: FileAttribute::DIRECTORY,
+            size: 0,
+            first_cluster: 0,
+        }
+    }
+
+    /// Name of the file, which is its long name if it has one
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Attributes of the file
+    pub fn attributes(&self) -> FileAttribute {
+        self.attributes
+    }
+
+    /// True if this entry is a directory
+    pub fn is_directory(&self) -> bool {
+        self.attributes.contains(FileAttribute::DIRECTORY)
+    }
+
+    /// Size of the file in bytes, 0 for directories
+    pub fn file_size(&self) -> u64 {
+        self.size
+    }
+}
+
+/// File opened for reading on a `FatFileSystem`
+pub struct FatFile<'fs, V: Volume> {
+    fs: &'fs mut FatFileSystem<V>,
+    first_cluster: u32,
+    size: u64,
+    position: u64,
+    /// Index in the chain and number of the last cluster that was accessed
+    cursor: (u64, u32),
+}
+
+impl<V: Volume> FatFile<'_, V> {
+    /// Size of the file in bytes
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Find the cluster containing the current position
+    fn current_cluster(&mut self) -> io::Result<u32> {
+        let index = self.position / u64::from(self.fs.cluster_size);
+        let (mut current_index, mut cluster) = self.cursor;
+        if index < current_index {
+            current_index = 0;
+            cluster = self.first_cluster;
+        }
+        while current_index < index {
+            cluster = self
+                .fs
+                .next_cluster(cluster)?
+                .ok_or(Status::VOLUME_CORRUPTED)?;
+            current_index += 1;
+        }
+        self.cursor = (current_index, cluster);
+        Ok(cluster)
+    }
+}
+
+impl<V: Volume> Read for FatFile<'_, V> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if self.position >= self.size || buffer.is_empty() {
+            return Ok(0);
+        }
+
+        let cluster_size = u64::from(self.fs.cluster_size);
+        let in_cluster = self.position % cluster_size;
+        let len = cmp::min(cluster_size - in_cluster, self.size - self.position);
+        let len = cmp::min(len, buffer.len() as u64) as usize;
+
+        let cluster = self.current_cluster()?;
+        let offset = self.fs.cluster_offset(cluster)? + in_cluster;
+        self.fs.volume.read_at(offset, &mut buffer[..len])?;
+        self.position += len as u64;
+        Ok(len)
+    }
+}
+
+impl<V: Volume> Seek for FatFile<'_, V> {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match position {
+            SeekFrom::Start(offset) => {
+                self.position = offset;
+                return Ok(offset);
+            }
+            SeekFrom::End(offset) => (self.size, offset),
+            SeekFrom::Current(offset) => (self.position, offset),
+        };
+        let position = if offset < 0 {
+            base.checked_sub(offset.unsigned_abs())
+        } else {
+            base.checked_add(offset as u64)
+        };
+        self.position = position.ok_or(Status::INVALID_PARAMETER)?;
+        Ok(self.position)
+    }
+}
+
+/// Accumulator for the long file name entries preceding a short entry
+struct LongName {
+    chars: Vec<u16>,
+    /// Sequence number of the last entry, which must decrease down to 1
+    sequence: u8,
+    checksum: u8,
+}
+
+impl LongName {
+    fn new() -> Self {
+        Self {
+            chars: Vec::new(),
+            sequence: 0,
+            checksum: 0,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.chars.clear();
+        self.sequence = 0;
+    }
+
+    fn push(&mut self, raw: &[u8]) {
+        let order = raw[0];
+        let sequence = order & 0x1f;
+        let checksum = raw[13];
+        if order & 0x40 != 0 {
+            self.reset();
+            self.checksum = checksum;
+        } else if sequence + 1 != self.sequence || checksum != self.checksum {
+            self.reset();
+            return;
+        }
+        self.sequence = sequence;
+
+        // Entries are stored last part first
+        let part = raw[1..11]
+            .chunks_exact(2)
+            .chain(raw[14..26].chunks_exact(2))
+            .chain(raw[28..32].chunks_exact(2))
+            .map(|c| u16::from_le_bytes([c[0], c[1]]));
+        let mut chars: Vec<u16> = part.collect();
+        chars.append(&mut self.chars);
+        self.chars = chars;
+    }
+
+    /// Retrieve the long name belonging to the entry with `short_name`
+    fn take(&mut self, short_name: &[u8; 11]) -> Option<String> {
+        let complete = self.sequence == 1 && self.checksum == short_name_checksum(short_name);
+        let chars = core::mem::take(&mut self.chars);
+        self.sequence = 0;
+        if !complete {
+            return None;
+        }
+
+        let len = chars.iter().position(|&c| c == 0).unwrap_or(chars.len());
+        Some(
+            core::char::decode_utf16(chars[..len].iter().copied())
+                .map(|c| c.unwrap_or(core::char::REPLACEMENT_CHARACTER))
+                .collect(),
+        )
+    }
+}
+
+/// Checksum of a short name, stored in its long name entries
+fn short_name_checksum(short_name: &[u8; 11]) -> u8 {
+    short_name
+        .iter()
+        .fold(0u8, |sum, &c| sum.rotate_right(1).wrapping_add(c))
+}
+
+/// Format an 8.3 name with the lowercase flags found in byte 12 of its entry
+fn format_short_name(short_name: &[u8; 11], flags: u8) -> String {
+    let decode = |bytes: &[u8], lowercase: bool| {
+        let mut bytes = bytes.to_vec();
+        while bytes.last() == Some(&b' ') {
+            bytes.pop();
+        }
+        // A leading 0x05 stands for a 0xe5 byte
+        if bytes.first() == Some(&0x05) {
+            bytes[0] = DELETED_ENTRY;
+        }
+        bytes
+            .into_iter()
+            .map(|b| match b {
+                b if b.is_ascii() && lowercase => char::from(b.to_ascii_lowercase()),
+                b if b.is_ascii() => char::from(b),
+                _ => core::char::REPLACEMENT_CHARACTER,
+            })
+            .collect::<String>()
+    };
+
+    let mut name = decode(&short_name[..8], flags & 0x08 != 0);
+    let extension = decode(&short_name[8..], flags & 0x10 != 0);
+    if !extension.is_empty() {
+        name.push('.');
+        name.push_str(&extension);
+    }
+    name
+}
+
+/// Compare file names case-insensitively
+fn names_match(a: &str, b: &str) -> bool {
+    a.chars()
+        .flat_map(char::to_lowercase)
+        .eq(b.chars().flat_map(char::to_lowercase))
+}
@@ -0,0 +1,187 @@
+//! High-level file system access.
+//!
+//! This module provides a `FileSystem` type which wraps a `SimpleFileSystem`
+//! protocol and offers operations on whole files and directories, in the
+//! spirit of `std::fs`. Paths are UCS-2 `Path`s, with components separated
+//! by backslashes (`\`), and are always interpreted relative to the root of
+//! the volume.
+//!
+//! Warnings emitted by the intermediate operations are logged.
+
+use crate::data_types::{Path, PathBuf};
+use crate::proto::media::file::{
+    Directory, File, FileAttribute, FileHandle, FileInfo, FileMode, FileType, RegularFile,
+};
+use crate::proto::media::fs::SimpleFileSystem;
+use crate::{Result, ResultExt, Status};
+use alloc_api::{boxed::Box, string::ToString, vec::Vec};
+
+/// High-level wrapper around a `SimpleFileSystem` protocol
+pub struct FileSystem<'a> {
+    proto: &'a mut SimpleFileSystem,
+}
+
+impl<'a> FileSystem<'a> {
+    /// Wrap a `SimpleFileSystem` protocol
+    pub fn new(proto: &'a mut SimpleFileSystem) -> Self {
+        Self { proto }
+    }
+
+    /// Read the entire contents of a file
+    ///
+    /// Fails with `Status::INVALID_PARAMETER` if `path` is a directory.
+    pub fn read(&mut self, path: &Path) -> Result<Vec<u8>> {
+        let mut file = self.open_regular(path, FileMode::Read)?.log();
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?.log();
+        Ok(buffer.into())
+    }
+
+    /// Write a slice as the entire contents of a file
+    ///
+    /// The file is created if it does not exist. Otherwise, it is truncated
+    /// before being written to, which keeps its attributes and creation time.
+    pub fn write(&mut self, path: &Path, content: &[u8]) -> Result {
+        let mut file = self.open_regular(path, FileMode::CreateReadWrite)?.log();
+        file.modify_info(|info| info.set_file_size(0))?.log();
+        file.write(content).discard_errdata()?.log();
+        file.flush()
+    }
+
+    /// Copy the contents of a file to another file
+    ///
+    /// The destination file is created or replaced as in `write()`.
+    pub fn copy(&mut self, from: &Path, to: &Path) -> Result {
+        let content = self.read(from)?.log();
+        self.write(to, &content)
+    }
+
+    /// Query information about a file or directory
+    pub fn metadata(&mut self, path: &Path) -> Result<Box<FileInfo>> {
+        self.open(path, FileMode::Read, FileAttribute::empty())?
+            .log()
+            .get_boxed_info::<FileInfo>()
+    }
+
+    /// Create a new, empty directory
+    ///
+    /// The parent directory must already exist. Succeeds if the directory
+    /// already exists.
+    pub fn create_dir(&mut self, path: &Path) -> Result {
+        let handle = self
+            .open(path, FileMode::CreateReadWrite, FileAttribute::DIRECTORY)?
+            .log();
+        into_directory(handle).map_inner(|_| ())
+    }
+
+    /// Create a directory and all of its missing parents
+    pub fn create_dir_all(&mut self, path: &Path) -> Result {
+        let mut dir = self.proto.open_volume()?.log();
+        for component in path.components() {
+            let handle = dir
+                .open_cstr16(
+                    PathBuf::from(component).as_cstr16(),
+                    FileMode::CreateReadWrite,
+                    FileAttribute::DIRECTORY,
+                )?
+                .log();
+            dir = into_directory(handle)?.log();
+        }
+        Ok(().into())
+    }
+
+    /// Remove a file
+    ///
+    /// Fails with `Status::INVALID_PARAMETER` if `path` is a directory.
+    pub fn remove_file(&mut self, path: &Path) -> Result {
+        let file = self.open_regular(path, FileMode::ReadWrite)?.log();
+        delete(file)
+    }
+
+    /// Remove an empty directory
+    pub fn remove_dir(&mut self, path: &Path) -> Result {
+        let dir = self.open_dir(path)?.log();
+        delete(dir)
+    }
+
+    /// Remove a directory after removing all of its contents
+    pub fn remove_dir_all(&mut self, path: &Path) -> Result {
+        let mut dir = self.open_dir(path)?.log();
+        remove_dir_contents(&mut dir)?.log();
+        delete(dir)
+    }
+
+    /// Open a file or directory relative to the root of the volume
+    fn open(
+        &mut self,
+        path: &Path,
+        open_mode: FileMode,
+        attributes: FileAttribute,
+    ) -> Result<FileHandle> {
+        let mut root = self.proto.open_volume()?.log();
+        root.open_cstr16(PathBuf::from(path).as_cstr16(), open_mode, attributes)
+    }
+
+    /// Open a regular file, failing if `path` is a directory
+    fn open_regular(&mut self, path: &Path, open_mode: FileMode) -> Result<RegularFile> {
+        let handle = self.open(path, open_mode, FileAttribute::empty())?.log();
+        match handle.into_regular_file()?.log() {
+            Some(file) => Ok(file.into()),
+            None => Err(Status::INVALID_PARAMETER.into()),
+        }
+    }
+
+    /// Open a directory, failing if `path` is a regular file
+    fn open_dir(&mut self, path: &Path) -> Result<Directory> {
+        let handle = self
+            .open(path, FileMode::ReadWrite, FileAttribute::empty())?
+            .log();
+        into_directory(handle)
+    }
+}
+
+/// Convert a file handle into a directory, failing if it is a regular file
+fn into_directory(handle: FileHandle) -> Result<Directory> {
+    match handle.into_directory()?.log() {
+        Some(dir) => Ok(dir.into()),
+        None => Err(Status::INVALID_PARAMETER.into()),
+    }
+}
+
+/// Delete a file, treating a failure to delete it as an error
+fn delete(file: impl File) -> Result {
+    file.delete().warning_as_error().map(|()| ().into())
+}
+
+/// Recursively remove the contents of a directory
+fn remove_dir_contents(dir: &mut Directory) -> Result {
+    // Collect the names first, as deleting entries while enumerating them
+    // may confuse the file system driver.
+    dir.reset_entry_readout()?.log();
+    let mut names = Vec::new();
+    for entry in dir.entries() {
+        let entry = entry?.log();
+        let name = entry.file_name();
+        if !matches!(name.to_string().as_str(), "." | "..") {
+            names.push(PathBuf::from(name));
+        }
+    }
+
+    for name in &names {
+        let handle = dir
+            .open_cstr16(
+                name.as_cstr16(),
+                FileMode::ReadWrite,
+                FileAttribute::empty(),
+            )?
+            .log();
+        match handle.into_type()?.log() {
+            FileType::Regular(file) => delete(file)?.log(),
+            FileType::Dir(mut subdir) => {
+                remove_dir_contents(&mut subdir)?.log();
+                delete(subdir)?.log();
+            }
+        }
+    }
+    Ok(().into())
+}
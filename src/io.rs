@@ -0,0 +1,95 @@
+//! Byte stream I/O traits.
+//!
+//! These traits are a `no_std` counterpart to the `Read`, `Write` and `Seek`
+//! traits of Rust's standard library. They allow generic code, such as file
+//! format parsers, to operate on any UEFI byte stream without knowing about the
+//! underlying protocol.
+//!
+//! Unlike UEFI functions, these traits report errors as a bare `Status` and do
+//! not propagate warnings, which are logged instead.
+
+use crate::Status;
+
+/// Result type of the I/O traits
+pub type Result<T> = core::result::Result<T, Status>;
+
+/// Source of bytes
+pub trait Read {
+    /// Pull some bytes from this source into `buffer`
+    ///
+    /// Returns the number of bytes that were read, which may be smaller than
+    /// the size of the buffer. A return value of 0 indicates that the end of
+    /// the stream was reached.
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize>;
+
+    /// Read the exact number of bytes required to fill `buffer`
+    ///
+    /// Fails with `Status::END_OF_FILE` if the end of the stream is reached
+    /// before the buffer could be filled. The contents of the buffer are
+    /// unspecified in that case.
+    fn read_exact(&mut self, mut buffer: &mut [u8]) -> Result<()> {
+        while !buffer.is_empty() {
+            match self.read(buffer)? {
+                0 => return Err(Status::END_OF_FILE),
+                n => buffer = &mut buffer[n..],
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sink of bytes
+pub trait Write {
+    /// Write some bytes from `buffer` into this sink
+    ///
+    /// Returns the number of bytes that were written, which may be smaller
+    /// than the size of the buffer.
+    fn write(&mut self, buffer: &[u8]) -> Result<usize>;
+
+    /// Make sure that all buffered data reaches its destination
+    fn flush(&mut self) -> Result<()>;
+
+    /// Write the whole contents of `buffer` into this sink
+    ///
+    /// Fails with `Status::END_OF_MEDIA` if the sink stops accepting data
+    /// before the buffer could be written.
+    fn write_all(&mut self, mut buffer: &[u8]) -> Result<()> {
+        while !buffer.is_empty() {
+            match self.write(buffer)? {
+                0 => return Err(Status::END_OF_MEDIA),
+                n => buffer = &buffer[n..],
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Position that a `Seek` operation is relative to
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SeekFrom {
+    /// Offset from the start of the stream
+    Start(u64),
+    /// Offset from the end of the stream
+    End(i64),
+    /// Offset from the current position
+    Current(i64),
+}
+
+/// Byte stream with a movable cursor
+pub trait Seek {
+    /// Move the cursor to a new position, returned as an offset from the
+    /// start of the stream
+    ///
+    /// Seeking to a negative offset fails with `Status::INVALID_PARAMETER`.
+    fn seek(&mut self, position: SeekFrom) -> Result<u64>;
+
+    /// Move the cursor back to the start of the stream
+    fn rewind(&mut self) -> Result<()> {
+        self.seek(SeekFrom::Start(0)).map(|_| ())
+    }
+
+    /// Query the current position of the cursor
+    fn stream_position(&mut self) -> Result<u64> {
+        self.seek(SeekFrom::Current(0))
+    }
+}
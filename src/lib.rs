@@ -51,8 +51,21 @@ pub mod table;
 
 pub mod proto;
 
+pub mod io;
+
+#[cfg(feature = "exts")]
+pub mod bmp;
+
+#[cfg(feature = "exts")]
+pub mod fat;
+
+#[cfg(feature = "exts")]
+pub mod fs;
+
 pub mod prelude;
 
+pub mod print;
+
 #[cfg(feature = "alloc")]
 pub mod alloc;
 
@@ -61,3 +74,6 @@ pub mod exts;
 
 #[cfg(feature = "logger")]
 pub mod logger;
+
+#[cfg(feature = "tui")]
+pub mod tui;
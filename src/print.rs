@@ -0,0 +1,133 @@
+//! Support for the `print!`, `println!`, `eprint!` and `eprintln!` macros.
+//!
+//! The macros write to the standard output and standard error of the system
+//! table, like their counterparts of the standard library, so that
+//! applications do not have to pass an `Output` to every function which
+//! prints some text. Keeping diagnostics on the standard error lets them be
+//! told apart from regular output when the firmware redirects one of them,
+//! to a serial port for instance.
+//!
+//! # Usage
+//!
+//! Call the `init` function with a reference to the system table. Until it is
+//! called, text printed by the macros is discarded.
+//!
+//! Call the `exit_boot_services` function before exiting UEFI boot services.
+//! Failure to do so will turn subsequent printing into undefined behaviour.
+
+use crate::proto::console::text::Output;
+use crate::table::{Boot, SystemTable};
+use core::fmt::{self, Write};
+use core::ptr::NonNull;
+
+/// Standard output of the system table, used by the macros.
+///
+/// The inner pointer is only safe to dereference if UEFI boot services have not been
+/// exited by the host application yet.
+static mut STDOUT: Option<NonNull<Output<'static>>> = None;
+
+/// Standard error of the system table, used by the `eprint` macros.
+///
+/// The same safety requirements as for `STDOUT` apply.
+static mut STDERR: Option<NonNull<Output<'static>>> = None;
+
+/// Makes the printing macros write to the standard output and standard error
+/// of `system_table`.
+///
+/// # Safety
+///
+/// This function is unsafe because you _must_ make sure that exit_boot_services
+/// will be called when UEFI boot services will be exited.
+pub unsafe fn init(system_table: &SystemTable<Boot>) {
+    STDOUT = NonNull::new(system_table.stdout() as *mut _ as *mut _);
+    STDERR = NonNull::new(system_table.stderr() as *mut _ as *mut _);
+}
+
+/// Notify the printing macros that boot services are not safe to call anymore
+///
+/// You must arrange for this function to be called on exit from UEFI boot services
+pub fn exit_boot_services() {
+    unsafe {
+        STDOUT = None;
+        STDERR = None;
+    }
+}
+
+/// Implementation of the `print!` macro, which is not part of the public API.
+#[doc(hidden)]
+pub fn _print(args: fmt::Arguments) {
+    if let Some(mut stdout) = unsafe { STDOUT } {
+        let stdout = unsafe { stdout.as_mut() };
+        stdout
+            .write_fmt(args)
+            .expect("Failed to write to the standard output");
+    }
+}
+
+/// Implementation of the `eprint!` macro, which is not part of the public API.
+#[doc(hidden)]
+pub fn _eprint(args: fmt::Arguments) {
+    if let Some(mut stderr) = unsafe { STDERR } {
+        let stderr = unsafe { stderr.as_mut() };
+        stderr
+            .write_fmt(args)
+            .expect("Failed to write to the standard error");
+    }
+}
+
+/// Prints to the standard output of the system table.
+///
+/// Equivalent to the `println!` macro, except that a newline is not printed
+/// at the end of the message.
+///
+/// Nothing is printed until `uefi::print::init()` has been called.
+///
+/// # Panics
+///
+/// Panics if writing to the standard output fails.
+#[macro_export]
+macro_rules! print {
+    ($($arg:tt)*) => ($crate::print::_print(format_args!($($arg)*)));
+}
+
+/// Prints to the standard output of the system table, with a newline.
+///
+/// The arguments are formatted as with the `format!` macro.
+///
+/// Nothing is printed until `uefi::print::init()` has been called.
+///
+/// # Panics
+///
+/// Panics if writing to the standard output fails.
+#[macro_export]
+macro_rules! println {
+    () => ($crate::print!("\n"));
+    ($($arg:tt)*) => ($crate::print::_print(format_args!("{}\n", format_args!($($arg)*))));
+}
+
+/// Prints to the standard error of the system table.
+///
+/// Equivalent to the `print!` macro, except that the text goes to the
+/// standard error.
+///
+/// # Panics
+///
+/// Panics if writing to the standard error fails.
+#[macro_export]
+macro_rules! eprint {
+    ($($arg:tt)*) => ($crate::print::_eprint(format_args!($($arg)*)));
+}
+
+/// Prints to the standard error of the system table, with a newline.
+///
+/// Equivalent to the `println!` macro, except that the text goes to the
+/// standard error.
+///
+/// # Panics
+///
+/// Panics if writing to the standard error fails.
+#[macro_export]
+macro_rules! eprintln {
+    () => ($crate::eprint!("\n"));
+    ($($arg:tt)*) => ($crate::print::_eprint(format_args!("{}\n", format_args!($($arg)*))));
+}
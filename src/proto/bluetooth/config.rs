@@ -0,0 +1,350 @@
+//! Bluetooth configuration protocol.
+
+use super::BluetoothAddress;
+use crate::proto::Protocol;
+use crate::{unsafe_guid, Result, Status};
+use bitflags::bitflags;
+use core::ffi::c_void;
+use core::{mem, ptr, str};
+
+/// Bluetooth host controller configuration.
+///
+/// It is installed on the handles of the Bluetooth controllers, and is used
+/// to discover the remote devices in range, and connect to them.
+#[repr(C)]
+#[unsafe_guid("62960cf3-40ff-4263-a77c-dfdebd191b4b")]
+#[derive(Protocol)]
+pub struct BluetoothConfig {
+    init: extern "efiapi" fn(this: &BluetoothConfig) -> Status,
+    scan: unsafe extern "efiapi" fn(
+        this: &BluetoothConfig,
+        rescan: bool,
+        scan_type: u8,
+        callback: Option<BluetoothScanCallback>,
+        context: *mut c_void,
+    ) -> Status,
+    connect: extern "efiapi" fn(this: &BluetoothConfig, bd_addr: &BluetoothAddress) -> Status,
+    disconnect: extern "efiapi" fn(
+        this: &BluetoothConfig,
+        bd_addr: &BluetoothAddress,
+        reason: u8,
+    ) -> Status,
+    get_data: extern "efiapi" fn(
+        this: &BluetoothConfig,
+        data_type: BluetoothConfigDataType,
+        data_size: &mut usize,
+        data: *mut c_void,
+    ) -> Status,
+    set_data: extern "efiapi" fn(
+        this: &BluetoothConfig,
+        data_type: BluetoothConfigDataType,
+        data_size: usize,
+        data: *const c_void,
+    ) -> Status,
+    get_remote_data: extern "efiapi" fn(
+        this: &BluetoothConfig,
+        data_type: BluetoothConfigDataType,
+        bd_addr: &BluetoothAddress,
+        data_size: &mut usize,
+        data: *mut c_void,
+    ) -> Status,
+    register_pin_callback: usize,
+    register_get_link_key_callback: usize,
+    register_set_link_key_callback: usize,
+    register_link_connect_complete_callback: usize,
+}
+
+impl BluetoothConfig {
+    /// Initializes the controller.
+    ///
+    /// This must be called before any other method.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if the controller could not be initialized
+    pub fn init(&self) -> Result {
+        (self.init)(self).into()
+    }
+
+    /// Starts scanning for the remote devices in range, and returns without
+    /// waiting for the scan to complete.
+    ///
+    /// The devices found are then listed by the `AVAILABLE_DEVICE_LIST` data
+    /// of the controller. A scan is only started if `rescan` is set, or if no
+    /// scan was done yet. `scan_type` selects the type of devices to scan
+    /// for, 0 scanning for all of them.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if the scan could not be started
+    pub fn scan(&self, rescan: bool, scan_type: u8) -> Result {
+        unsafe { (self.scan)(self, rescan, scan_type, None, ptr::null_mut()) }.into()
+    }
+
+    /// Starts scanning for the remote devices in range, like `scan()`, and
+    /// calls `callback` with `context` for each device found.
+    ///
+    /// The callback may be called before this returns, and after it
+    /// returns, until the scan completes.
+    ///
+    /// # Safety
+    ///
+    /// `context` must remain valid for as long as the callback may be called,
+    /// i.e. until the scan completes.
+    pub unsafe fn scan_with_callback(
+        &self,
+        rescan: bool,
+        scan_type: u8,
+        callback: BluetoothScanCallback,
+        context: *mut c_void,
+    ) -> Result {
+        (self.scan)(self, rescan, scan_type, Some(callback), context).into()
+    }
+
+    /// Connects to a remote device, pairing with it if needed.
+    ///
+    /// # Errors
+    ///
+    /// - `NotFound` if the device was not found by a scan
+    /// - `DeviceError` if the connection failed
+    pub fn connect(&self, address: &BluetoothAddress) -> Result {
+        (self.connect)(self, address).into()
+    }
+
+    /// Disconnects from a remote device, for the given HCI reason code.
+    ///
+    /// # Errors
+    ///
+    /// - `NotFound` if the device is not connected
+    /// - `DeviceError` if the disconnection failed
+    pub fn disconnect(&self, address: &BluetoothAddress, reason: u8) -> Result {
+        (self.disconnect)(self, address, reason).into()
+    }
+
+    /// Reads data of the controller of the given type into `buffer`, and
+    /// returns its size.
+    ///
+    /// If the buffer is too small, the required buffer size will be returned
+    /// as part of the error.
+    ///
+    /// # Errors
+    ///
+    /// - `BufferTooSmall` if the buffer cannot hold the data
+    /// - `Unsupported` if the data type cannot be read
+    pub fn get_data(
+        &self,
+        data_type: BluetoothConfigDataType,
+        buffer: &mut [u8],
+    ) -> Result<usize, Option<usize>> {
+        let mut size = buffer.len();
+        (self.get_data)(
+            self,
+            data_type,
+            &mut size,
+            buffer.as_mut_ptr() as *mut c_void,
+        )
+        .into_with(
+            || size,
+            |s| {
+                if s == Status::BUFFER_TOO_SMALL {
+                    Some(size)
+                } else {
+                    None
+                }
+            },
+        )
+    }
+
+    /// Sets data of the controller of the given type.
+    ///
+    /// # Errors
+    ///
+    /// - `Unsupported` if the data type cannot be set
+    /// - `InvalidParameter` if the data is not valid
+    pub fn set_data(&self, data_type: BluetoothConfigDataType, data: &[u8]) -> Result {
+        (self.set_data)(self, data_type, data.len(), data.as_ptr() as *const c_void).into()
+    }
+
+    /// Reads data of a remote device of the given type into `buffer`, and
+    /// returns its size.
+    ///
+    /// If the buffer is too small, the required buffer size will be returned
+    /// as part of the error.
+    ///
+    /// # Errors
+    ///
+    /// - `BufferTooSmall` if the buffer cannot hold the data
+    /// - `NotFound` if the device was not found by a scan
+    /// - `Unsupported` if the data type cannot be read
+    pub fn get_remote_data(
+        &self,
+        data_type: BluetoothConfigDataType,
+        address: &BluetoothAddress,
+        buffer: &mut [u8],
+    ) -> Result<usize, Option<usize>> {
+        let mut size = buffer.len();
+        let data = buffer.as_mut_ptr() as *mut c_void;
+        (self.get_remote_data)(self, data_type, address, &mut size, data).into_with(
+            || size,
+            |s| {
+                if s == Status::BUFFER_TOO_SMALL {
+                    Some(size)
+                } else {
+                    None
+                }
+            },
+        )
+    }
+
+    /// Returns the address of the controller.
+    pub fn address(&self) -> Result<BluetoothAddress> {
+        let mut address = BluetoothAddress::default();
+        let mut size = mem::size_of_val(&address);
+        let data = &mut address as *mut BluetoothAddress as *mut c_void;
+        (self.get_data)(self, BluetoothConfigDataType::BD_ADDR, &mut size, data)
+            .into_with_val(|| address)
+    }
+
+    /// Returns the class of a remote device.
+    pub fn remote_class_of_device(
+        &self,
+        address: &BluetoothAddress,
+    ) -> Result<BluetoothClassOfDevice> {
+        let mut class = BluetoothClassOfDevice::default();
+        let mut size = mem::size_of_val(&class);
+        let data = &mut class as *mut BluetoothClassOfDevice as *mut c_void;
+        let data_type = BluetoothConfigDataType::CLASS_OF_DEVICE;
+        (self.get_remote_data)(self, data_type, address, &mut size, data).into_with_val(|| class)
+    }
+
+    /// Returns whether a remote device is connected and paired.
+    pub fn remote_device_state(
+        &self,
+        address: &BluetoothAddress,
+    ) -> Result<BluetoothRemoteDeviceState> {
+        let mut state = 0u8;
+        let mut size = mem::size_of_val(&state);
+        let data = &mut state as *mut u8 as *mut c_void;
+        let data_type = BluetoothConfigDataType::REMOTE_DEVICE_STATE;
+        (self.get_remote_data)(self, data_type, address, &mut size, data)
+            .into_with_val(|| BluetoothRemoteDeviceState::from_bits_truncate(state))
+    }
+}
+
+/// Function called by `BluetoothConfig::scan_with_callback()` for each
+/// device found, with the context given to it.
+pub type BluetoothScanCallback = extern "efiapi" fn(
+    this: &BluetoothConfig,
+    context: *mut c_void,
+    info: &BluetoothScanCallbackInformation,
+) -> Status;
+
+/// Remote device found by a scan.
+#[repr(C)]
+pub struct BluetoothScanCallbackInformation {
+    bd_addr: BluetoothAddress,
+    remote_device_state: u8,
+    class_of_device: BluetoothClassOfDevice,
+    remote_device_name: [u8; 248],
+}
+
+impl BluetoothScanCallbackInformation {
+    /// Address of the device.
+    pub fn address(&self) -> &BluetoothAddress {
+        &self.bd_addr
+    }
+
+    /// Whether the device is connected and paired.
+    pub fn remote_device_state(&self) -> BluetoothRemoteDeviceState {
+        BluetoothRemoteDeviceState::from_bits_truncate(self.remote_device_state)
+    }
+
+    /// Class of the device.
+    pub fn class_of_device(&self) -> BluetoothClassOfDevice {
+        self.class_of_device
+    }
+
+    /// Name of the device, or `None` if it is not valid UTF-8.
+    ///
+    /// The name is nul-terminated, unless it fills the whole 248 bytes.
+    pub fn name(&self) -> Option<&str> {
+        let name = &self.remote_device_name;
+        let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+        str::from_utf8(&self.remote_device_name[..len]).ok()
+    }
+}
+
+/// Class of a Bluetooth device, as assigned by the Bluetooth SIG.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+#[repr(C)]
+pub struct BluetoothClassOfDevice(pub [u8; 3]);
+
+impl BluetoothClassOfDevice {
+    /// Major class of the device, such as 5 for peripherals.
+    pub fn major_device_class(&self) -> u8 {
+        ((self.value() >> 8) & 0x1f) as u8
+    }
+
+    /// Minor class of the device, within its major class.
+    pub fn minor_device_class(&self) -> u8 {
+        ((self.value() >> 2) & 0x3f) as u8
+    }
+
+    /// Services provided by the device, as a bit mask.
+    pub fn major_service_class(&self) -> u16 {
+        ((self.value() >> 13) & 0x7ff) as u16
+    }
+
+    fn value(&self) -> u32 {
+        let [low, middle, high] = self.0;
+        u32::from_le_bytes([low, middle, high, 0])
+    }
+}
+
+bitflags! {
+    /// State of a remote Bluetooth device.
+    pub struct BluetoothRemoteDeviceState: u8 {
+        /// The device is connected.
+        const CONNECTED = 0x1;
+        /// The device is paired.
+        const PAIRED = 0x2;
+    }
+}
+
+newtype_enum! {
+    /// Type of the data of a `BluetoothConfig` instance, or of a remote
+    /// device.
+    pub enum BluetoothConfigDataType: u32 => {
+        /// Name of the device, as a nul-terminated UTF-8 string.
+        DEVICE_NAME                          = 0,
+        /// Class of the device, as a `BluetoothClassOfDevice`.
+        CLASS_OF_DEVICE                      = 1,
+        /// State of a remote device, as a `u8` made of
+        /// `BluetoothRemoteDeviceState` flags.
+        REMOTE_DEVICE_STATE                  = 2,
+        /// Service discovery information of a remote device.
+        SDP_INFO                             = 3,
+        /// Address of the controller, as a `BluetoothAddress`.
+        BD_ADDR                              = 4,
+        /// Whether the controller is discoverable, as a `bool`.
+        DISCOVERABLE                         = 5,
+        /// Devices paired with the controller.
+        CONTROLLER_STORED_PAIRED_DEVICE_LIST = 6,
+        /// Devices found by the last scan.
+        AVAILABLE_DEVICE_LIST                = 7,
+        /// Random address of the controller.
+        RANDOM_ADDRESS                       = 8,
+        /// Signal strength of a remote device.
+        RSSI                                 = 9,
+        /// Advertisement data of a remote device.
+        ADVERTISEMENT_DATA                   = 10,
+        /// Input and output capabilities used for pairing.
+        IO_CAPABILITY                        = 11,
+        /// Whether out-of-band pairing data is present.
+        OOB_DATA_FLAG                        = 12,
+        /// Type of the link key.
+        KEY_TYPE                             = 13,
+        /// Size of the encryption key.
+        ENC_KEY_SIZE                         = 14,
+    }
+}
@@ -0,0 +1,11 @@
+//! Bluetooth protocols.
+//!
+//! These protocols give access to the Bluetooth controllers of the machine,
+//! so that devices such as keyboards can be paired before an OS is running.
+
+pub mod config;
+
+/// Address of a Bluetooth device, least significant byte first.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+#[repr(C)]
+pub struct BluetoothAddress(pub [u8; 6]);
@@ -0,0 +1,157 @@
+//! Batched blit operations.
+//!
+//! On some firmware, each call to the blit function of the graphics output
+//! protocol has a large fixed cost. UI code which redraws a screen with many
+//! small rectangles can record them in a `BltBatch`, which merges adjacent
+//! fills of the same color, and then issues all remaining operations at once.
+
+use super::gop::{BltOp, BltPixel, BltRegion, GraphicsOutput};
+use crate::Result;
+use alloc_api::vec::Vec;
+
+/// List of blit operations to be performed on a `GraphicsOutput`.
+///
+/// The operations are performed in the order in which they were recorded.
+pub struct BltBatch<'buf> {
+    ops: Vec<BltOp<'buf>>,
+    coalesce: bool,
+}
+
+impl Default for BltBatch<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'buf> BltBatch<'buf> {
+    /// Create an empty batch, which coalesces fills.
+    pub fn new() -> Self {
+        Self {
+            ops: Vec::new(),
+            coalesce: true,
+        }
+    }
+
+    /// Enables or disables the merging of a fill with the previous operation,
+    /// when it is a fill of the same color sharing a whole edge with it.
+    pub fn set_coalescing(&mut self, coalesce: bool) {
+        self.coalesce = coalesce;
+    }
+
+    /// Returns the number of recorded operations.
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// True if no operation was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Discards the recorded operations.
+    pub fn clear(&mut self) {
+        self.ops.clear();
+    }
+
+    /// Records the filling of a rectangle with a color.
+    pub fn fill(&mut self, color: BltPixel, dest: (usize, usize), dims: (usize, usize)) {
+        self.push(BltOp::VideoFill { color, dest, dims });
+    }
+
+    /// Records the copy of a rectangle of the screen to another position.
+    pub fn copy(&mut self, src: (usize, usize), dest: (usize, usize), dims: (usize, usize)) {
+        self.push(BltOp::VideoToVideo { src, dest, dims });
+    }
+
+    /// Records the drawing of a rectangle of `buffer` on the screen.
+    pub fn draw(
+        &mut self,
+        buffer: &'buf [BltPixel],
+        src: BltRegion,
+        dest: (usize, usize),
+        dims: (usize, usize),
+    ) {
+        self.push(BltOp::BufferToVideo {
+            buffer,
+            src,
+            dest,
+            dims,
+        });
+    }
+
+    /// Records an operation.
+    ///
+    /// Operations on empty rectangles are discarded.
+    pub fn push(&mut self, op: BltOp<'buf>) {
+        let dims = match &op {
+            BltOp::VideoFill { dims, .. }
+            | BltOp::VideoToBltBuffer { dims, .. }
+            | BltOp::BufferToVideo { dims, .. }
+            | BltOp::VideoToVideo { dims, .. } => *dims,
+        };
+        if dims.0 == 0 || dims.1 == 0 {
+            return;
+        }
+
+        if self.coalesce {
+            if let (
+                Some(BltOp::VideoFill {
+                    color: last_color,
+                    dest: last_dest,
+                    dims: last_dims,
+                }),
+                BltOp::VideoFill { color, dest, dims },
+            ) = (self.ops.last_mut(), &op)
+            {
+                if same_color(last_color, color) {
+                    if let Some((merged_dest, merged_dims)) =
+                        merge(*last_dest, *last_dims, *dest, *dims)
+                    {
+                        *last_dest = merged_dest;
+                        *last_dims = merged_dims;
+                        return;
+                    }
+                }
+            }
+        }
+        self.ops.push(op);
+    }
+
+    /// Performs the recorded operations, and clears the batch.
+    ///
+    /// # Errors
+    ///
+    /// Stops at the first operation which fails, returning its error. The
+    /// batch is cleared in any case.
+    pub fn submit(&mut self, gop: &mut GraphicsOutput) -> Result {
+        for op in self.ops.drain(..) {
+            gop.blt(op)?.log();
+        }
+        Ok(().into())
+    }
+}
+
+fn same_color(a: &BltPixel, b: &BltPixel) -> bool {
+    (a.red, a.green, a.blue) == (b.red, b.green, b.blue)
+}
+
+/// Returns the rectangle covered by two rectangles sharing a whole edge, if
+/// they do
+fn merge(
+    a: (usize, usize),
+    a_dims: (usize, usize),
+    b: (usize, usize),
+    b_dims: (usize, usize),
+) -> Option<((usize, usize), (usize, usize))> {
+    let (a_right, a_bottom) = (a.0 + a_dims.0, a.1 + a_dims.1);
+    let (b_right, b_bottom) = (b.0 + b_dims.0, b.1 + b_dims.1);
+    if a.1 == b.1 && a_dims.1 == b_dims.1 && (a_right == b.0 || b_right == a.0) {
+        // Side by side
+        Some(((a.0.min(b.0), a.1), (a_dims.0 + b_dims.0, a_dims.1)))
+    } else if a.0 == b.0 && a_dims.0 == b_dims.0 && (a_bottom == b.1 || b_bottom == a.1) {
+        // On top of each other
+        Some(((a.0, a.1.min(b.1)), (a_dims.0, a_dims.1 + b_dims.1)))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,91 @@
+//! Console control protocol.
+//!
+//! This protocol comes from the Intel Framework specification, which predates
+//! UEFI. Some older firmware, notably on Macs, keeps showing the text console
+//! after a graphics mode has been set, until the screen is switched to
+//! graphics with this protocol: drawing with the GOP is otherwise invisible.
+
+use crate::proto::Protocol;
+use crate::{unsafe_guid, CStr16, Char16, Result, ResultExt, Status};
+
+/// Switches the screen between the text console and graphics.
+#[repr(C)]
+#[unsafe_guid("f42f7782-012e-4c12-9956-49f94304f721")]
+#[derive(Protocol)]
+pub struct ConsoleControl {
+    get_mode: extern "efiapi" fn(
+        this: &ConsoleControl,
+        mode: &mut ScreenMode,
+        gop_uga_exists: &mut bool,
+        std_in_locked: &mut bool,
+    ) -> Status,
+    set_mode: extern "efiapi" fn(this: &mut ConsoleControl, mode: ScreenMode) -> Status,
+    lock_std_in: extern "efiapi" fn(this: &mut ConsoleControl, password: *const Char16) -> Status,
+}
+
+impl ConsoleControl {
+    /// Returns the current state of the screen.
+    ///
+    /// # Errors
+    ///
+    /// - `Unsupported` if the state could not be retrieved
+    pub fn state(&self) -> Result<ConsoleState> {
+        let mut mode = ScreenMode::TEXT;
+        let mut graphics_available = false;
+        let mut std_in_locked = false;
+        (self.get_mode)(self, &mut mode, &mut graphics_available, &mut std_in_locked).into_with_val(
+            || ConsoleState {
+                mode,
+                graphics_available,
+                std_in_locked,
+            },
+        )
+    }
+
+    /// Returns what the screen currently shows.
+    pub fn mode(&self) -> Result<ScreenMode> {
+        self.state().map_inner(|state| state.mode)
+    }
+
+    /// Switches the screen to the text console or to graphics.
+    ///
+    /// On the firmware which provides this protocol, the output of the graphics
+    /// output protocol is only shown once the screen is switched to graphics.
+    ///
+    /// # Errors
+    ///
+    /// - `Unsupported` if the mode is not supported by the firmware
+    pub fn set_mode(&mut self, mode: ScreenMode) -> Result {
+        (self.set_mode)(self, mode).into()
+    }
+
+    /// Locks the standard input until `password` is typed on the console.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if the console input could not be locked
+    pub fn lock_std_in(&mut self, password: &CStr16) -> Result {
+        (self.lock_std_in)(self, password.as_ptr()).into()
+    }
+}
+
+/// State of the screen, as returned by `ConsoleControl::state()`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ConsoleState {
+    /// What the screen currently shows.
+    pub mode: ScreenMode,
+    /// True if a graphics output or UGA draw device is available.
+    pub graphics_available: bool,
+    /// True if the standard input is locked by `lock_std_in()`.
+    pub std_in_locked: bool,
+}
+
+newtype_enum! {
+    /// What a screen controlled by `ConsoleControl` shows.
+    pub enum ScreenMode: u32 => {
+        /// The text console.
+        TEXT = 0,
+        /// The output of graphics devices.
+        GRAPHICS = 1,
+    }
+}
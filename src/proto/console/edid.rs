@@ -0,0 +1,221 @@
+//! Extended Display Identification Data protocols.
+//!
+//! The EDID of a display describes its manufacturer, its name and its
+//! supported timings, and in particular its native resolution. UEFI exposes
+//! the EDID read from the display attached to a video output with
+//! `EdidDiscovered`, and the EDID actually used by the firmware, which may
+//! have been overridden by a platform driver, with `EdidActive`.
+
+use crate::proto::Protocol;
+use crate::unsafe_guid;
+use core::{slice, str};
+
+/// Header found at the start of every EDID base block.
+pub const EDID_HEADER: [u8; 8] = [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00];
+
+/// Size of the EDID base block.
+const EDID_BLOCK_SIZE: usize = 128;
+
+/// Offset of the four 18-byte descriptors of the base block.
+const DESCRIPTORS_OFFSET: usize = 54;
+
+/// Tag of the display descriptor containing the display name.
+const DISPLAY_NAME_TAG: u8 = 0xfc;
+
+/// EDID read from the display attached to a video output device.
+#[repr(C)]
+#[unsafe_guid("1c0c34f6-d380-41fa-a049-8ad06c1a66aa")]
+#[derive(Protocol)]
+pub struct EdidDiscovered {
+    size_of_edid: u32,
+    edid: *const u8,
+}
+
+impl EdidDiscovered {
+    /// Returns the raw EDID, or `None` if the display did not provide one.
+    pub fn edid(&self) -> Option<&[u8]> {
+        unsafe { edid_slice(self.size_of_edid, self.edid) }
+    }
+
+    /// Parses the base block of the EDID.
+    pub fn parse(&self) -> Option<Edid> {
+        self.edid().and_then(Edid::parse)
+    }
+}
+
+/// EDID used by the firmware for a video output device.
+///
+/// This is either the discovered EDID, or one provided by a platform
+/// override driver.
+#[repr(C)]
+#[unsafe_guid("bd8c1056-9f36-44ec-92a8-a6337f817986")]
+#[derive(Protocol)]
+pub struct EdidActive {
+    size_of_edid: u32,
+    edid: *const u8,
+}
+
+impl EdidActive {
+    /// Returns the raw EDID, or `None` if there is no active EDID.
+    pub fn edid(&self) -> Option<&[u8]> {
+        unsafe { edid_slice(self.size_of_edid, self.edid) }
+    }
+
+    /// Parses the base block of the EDID.
+    pub fn parse(&self) -> Option<Edid> {
+        self.edid().and_then(Edid::parse)
+    }
+}
+
+unsafe fn edid_slice<'a>(size: u32, edid: *const u8) -> Option<&'a [u8]> {
+    if size == 0 || edid.is_null() {
+        None
+    } else {
+        Some(slice::from_raw_parts(edid, size as usize))
+    }
+}
+
+/// Base block of an EDID structure.
+///
+/// Only the most useful information is decoded, extension blocks are ignored.
+#[derive(Clone, Copy)]
+pub struct Edid {
+    block: [u8; EDID_BLOCK_SIZE],
+}
+
+impl Edid {
+    /// Parses the base block of an EDID, returning `None` if its header or
+    /// checksum is invalid.
+    pub fn parse(edid: &[u8]) -> Option<Self> {
+        let data = edid.get(..EDID_BLOCK_SIZE)?;
+        if data[..8] != EDID_HEADER {
+            return None;
+        }
+        let checksum = data.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte));
+        if checksum != 0 {
+            return None;
+        }
+
+        let mut block = [0; EDID_BLOCK_SIZE];
+        block.copy_from_slice(data);
+        Some(Self { block })
+    }
+
+    /// Raw contents of the base block.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.block
+    }
+
+    /// Three-letter manufacturer ID, assigned by Microsoft (formerly PNP ID).
+    pub fn manufacturer_id(&self) -> [u8; 3] {
+        let id = u16::from_be_bytes([self.block[8], self.block[9]]);
+        let letter = |shift: u16| b'@' + ((id >> shift) & 0x1f) as u8;
+        [letter(10), letter(5), letter(0)]
+    }
+
+    /// Product code assigned by the manufacturer.
+    pub fn product_code(&self) -> u16 {
+        u16::from_le_bytes([self.block[10], self.block[11]])
+    }
+
+    /// Version and revision of the EDID structure, for example (1, 4).
+    pub fn version(&self) -> (u8, u8) {
+        (self.block[18], self.block[19])
+    }
+
+    /// Maximum (width, height) of the image in centimeters, or `None` if it is
+    /// undefined, as for projectors.
+    pub fn screen_size_cm(&self) -> Option<(u8, u8)> {
+        match (self.block[21], self.block[22]) {
+            (0, _) | (_, 0) => None,
+            size => Some(size),
+        }
+    }
+
+    /// Iterate over the detailed timings of the base block.
+    ///
+    /// The first one is the preferred timing of the display.
+    pub fn detailed_timings(&self) -> impl Iterator<Item = DetailedTiming> + '_ {
+        self.descriptors().filter_map(DetailedTiming::parse)
+    }
+
+    /// The preferred timing of the display, normally its native resolution.
+    pub fn preferred_timing(&self) -> Option<DetailedTiming> {
+        self.detailed_timings().next()
+    }
+
+    /// Name of the display, if provided.
+    pub fn display_name(&self) -> Option<&str> {
+        let descriptor = self
+            .descriptors()
+            .find(|d| d[..3] == [0, 0, 0] && d[3] == DISPLAY_NAME_TAG)?;
+        let text = &descriptor[5..];
+        let len = text.iter().position(|&c| c == b'\n').unwrap_or(text.len());
+        let name = str::from_utf8(&text[..len]).ok()?.trim_end();
+        Some(name)
+    }
+
+    fn descriptors(&self) -> impl Iterator<Item = &[u8]> {
+        self.block[DESCRIPTORS_OFFSET..DESCRIPTORS_OFFSET + 4 * 18].chunks_exact(18)
+    }
+}
+
+impl core::fmt::Debug for Edid {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let id = self.manufacturer_id();
+        f.debug_struct("Edid")
+            .field("manufacturer_id", &str::from_utf8(&id).unwrap_or("???"))
+            .field("product_code", &self.product_code())
+            .field("version", &self.version())
+            .field("display_name", &self.display_name())
+            .field("preferred_timing", &self.preferred_timing())
+            .finish()
+    }
+}
+
+/// Detailed timing descriptor of an EDID.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct DetailedTiming {
+    /// Pixel clock, in kHz.
+    pub pixel_clock_khz: u32,
+    /// Number of visible pixels in a line.
+    pub horizontal_active: u16,
+    /// Number of blanking pixels in a line.
+    pub horizontal_blanking: u16,
+    /// Number of visible lines.
+    pub vertical_active: u16,
+    /// Number of blanking lines.
+    pub vertical_blanking: u16,
+    /// Width of the image in millimeters.
+    pub horizontal_size_mm: u16,
+    /// Height of the image in millimeters.
+    pub vertical_size_mm: u16,
+}
+
+impl DetailedTiming {
+    /// Parse an 18-byte descriptor, returning `None` if it is not a timing.
+    fn parse(d: &[u8]) -> Option<Self> {
+        let pixel_clock = u16::from_le_bytes([d[0], d[1]]);
+        if pixel_clock == 0 {
+            return None;
+        }
+        let combine = |low: u8, high_nibble: u8| u16::from(low) | (u16::from(high_nibble) << 8);
+        Some(Self {
+            pixel_clock_khz: u32::from(pixel_clock) * 10,
+            horizontal_active: combine(d[2], d[4] >> 4),
+            horizontal_blanking: combine(d[3], d[4] & 0xf),
+            vertical_active: combine(d[5], d[7] >> 4),
+            vertical_blanking: combine(d[6], d[7] & 0xf),
+            horizontal_size_mm: combine(d[12], d[14] >> 4),
+            vertical_size_mm: combine(d[13], d[14] & 0xf),
+        })
+    }
+
+    /// Returns the (width, height) of the visible image, in pixels.
+    pub fn resolution(&self) -> (usize, usize) {
+        (
+            usize::from(self.horizontal_active),
+            usize::from(self.vertical_active),
+        )
+    }
+}
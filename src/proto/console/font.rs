@@ -0,0 +1,236 @@
+//! Bitmap fonts for drawing text on graphics devices.
+//!
+//! A small 8x8 font covering printable ASCII is built in. Larger fonts, and
+//! fonts covering more characters, can be loaded from PC Screen Font (PSF)
+//! files, the format of the Linux console fonts.
+
+use core::convert::TryInto;
+
+/// Magic number of PSF version 1 files.
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+
+/// Magic number of PSF version 2 files.
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xb5, 0x4a, 0x86];
+
+/// Flag of PSF1 files containing 512 glyphs instead of 256.
+const PSF1_MODE512: u8 = 0x01;
+
+/// Largest number of pixels in a glyph supported by `Font::from_psf()`.
+pub const MAX_GLYPH_PIXELS: usize = 32 * 32;
+
+/// Monochrome bitmap font with fixed-size glyphs.
+#[derive(Debug, Clone, Copy)]
+pub struct Font<'a> {
+    width: usize,
+    height: usize,
+    first_char: u32,
+    glyphs: &'a [u8],
+}
+
+impl Font<'static> {
+    /// Built-in 8x8 font, covering printable ASCII characters.
+    pub fn builtin() -> Self {
+        Self {
+            width: 8,
+            height: 8,
+            first_char: 0x20,
+            glyphs: &BUILTIN_GLYPHS,
+        }
+    }
+}
+
+impl<'a> Font<'a> {
+    /// Load a PSF version 1 or 2 font.
+    ///
+    /// Glyphs are looked up by their index, so the font must be laid out in
+    /// Unicode order, as most fonts are for ASCII and Latin-1 characters.
+    /// Returns `None` if the data is not a valid font, or if its glyphs have
+    /// more than `MAX_GLYPH_PIXELS` pixels.
+    pub fn from_psf(data: &'a [u8]) -> Option<Self> {
+        let (width, height, count, glyphs_offset) = if data.get(..2)? == PSF1_MAGIC {
+            let count = if *data.get(2)? & PSF1_MODE512 != 0 {
+                512
+            } else {
+                256
+            };
+            (8, usize::from(*data.get(3)?), count, 4)
+        } else if data.get(..4)? == PSF2_MAGIC {
+            let field = |index: usize| -> Option<usize> {
+                let bytes = data.get(index * 4..index * 4 + 4)?;
+                Some(u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+            };
+            let (header_size, count, glyph_size) = (field(2)?, field(4)?, field(5)?);
+            let (height, width) = (field(6)?, field(7)?);
+            if glyph_size != ((width + 7) >> 3) * height {
+                return None;
+            }
+            (width, height, count, header_size)
+        } else {
+            return None;
+        };
+
+        if width == 0 || height == 0 || width * height > MAX_GLYPH_PIXELS {
+            return None;
+        }
+        let glyphs_size = ((width + 7) >> 3) * height * count;
+        let glyphs = data.get(glyphs_offset..glyphs_offset.checked_add(glyphs_size)?)?;
+        Some(Self {
+            width,
+            height,
+            first_char: 0,
+            glyphs,
+        })
+    }
+
+    /// Returns the (width, height) of the glyphs, in pixels.
+    pub fn glyph_size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Returns the glyph of a character, or `None` if the font does not
+    /// contain it.
+    pub fn glyph(&self, c: char) -> Option<Glyph<'a>> {
+        let index = (c as u32).checked_sub(self.first_char)? as usize;
+        let size = self.bytes_per_row() * self.height;
+        let bytes = self.glyphs.get(index * size..(index + 1) * size)?;
+        Some(Glyph {
+            width: self.width,
+            height: self.height,
+            bytes,
+        })
+    }
+
+    fn bytes_per_row(&self) -> usize {
+        (self.width + 7) >> 3
+    }
+}
+
+/// Bitmap of a character in a `Font`.
+#[derive(Debug, Clone, Copy)]
+pub struct Glyph<'a> {
+    width: usize,
+    height: usize,
+    bytes: &'a [u8],
+}
+
+impl Glyph<'_> {
+    /// Returns the (width, height) of the glyph, in pixels.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// True if the pixel at (x, y) is part of the character.
+    ///
+    /// Pixels outside of the glyph are never set.
+    pub fn is_set(&self, x: usize, y: usize) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        let byte = self.bytes[y * ((self.width + 7) >> 3) + (x >> 3)];
+        byte & (0x80 >> (x & 7)) != 0
+    }
+}
+
+/// Glyphs of the built-in font, from U+0020 to U+007E.
+///
+/// Each byte is a row of the glyph, with the leftmost pixel in the highest
+/// bit.
+#[rustfmt::skip]
+const BUILTIN_GLYPHS: [u8; 95 * 8] = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // ' '
+    0x18, 0x18, 0x18, 0x18, 0x18, 0x00, 0x18, 0x00, // '!'
+    0x36, 0x36, 0x24, 0x00, 0x00, 0x00, 0x00, 0x00, // '"'
+    0x36, 0x36, 0x7f, 0x36, 0x7f, 0x36, 0x36, 0x00, // '#'
+    0x18, 0x3e, 0x60, 0x3c, 0x06, 0x7c, 0x18, 0x00, // '$'
+    0x63, 0x66, 0x0c, 0x18, 0x30, 0x66, 0xc6, 0x00, // '%'
+    0x38, 0x6c, 0x38, 0x76, 0xdc, 0xcc, 0x76, 0x00, // '&'
+    0x18, 0x18, 0x30, 0x00, 0x00, 0x00, 0x00, 0x00, // "'"
+    0x0c, 0x18, 0x30, 0x30, 0x30, 0x18, 0x0c, 0x00, // '('
+    0x30, 0x18, 0x0c, 0x0c, 0x0c, 0x18, 0x30, 0x00, // ')'
+    0x00, 0x66, 0x3c, 0xff, 0x3c, 0x66, 0x00, 0x00, // '*'
+    0x00, 0x18, 0x18, 0x7e, 0x18, 0x18, 0x00, 0x00, // '+'
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x18, 0x30, // ','
+    0x00, 0x00, 0x00, 0x7e, 0x00, 0x00, 0x00, 0x00, // '-'
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x18, 0x00, // '.'
+    0x03, 0x06, 0x0c, 0x18, 0x30, 0x60, 0xc0, 0x00, // '/'
+    0x3c, 0x66, 0x6e, 0x7e, 0x76, 0x66, 0x3c, 0x00, // '0'
+    0x18, 0x38, 0x18, 0x18, 0x18, 0x18, 0x7e, 0x00, // '1'
+    0x3c, 0x66, 0x06, 0x0c, 0x18, 0x30, 0x7e, 0x00, // '2'
+    0x3c, 0x66, 0x06, 0x1c, 0x06, 0x66, 0x3c, 0x00, // '3'
+    0x0e, 0x1e, 0x36, 0x66, 0x7f, 0x06, 0x06, 0x00, // '4'
+    0x7e, 0x60, 0x7c, 0x06, 0x06, 0x66, 0x3c, 0x00, // '5'
+    0x1c, 0x30, 0x60, 0x7c, 0x66, 0x66, 0x3c, 0x00, // '6'
+    0x7e, 0x06, 0x0c, 0x18, 0x30, 0x30, 0x30, 0x00, // '7'
+    0x3c, 0x66, 0x66, 0x3c, 0x66, 0x66, 0x3c, 0x00, // '8'
+    0x3c, 0x66, 0x66, 0x3e, 0x06, 0x0c, 0x38, 0x00, // '9'
+    0x00, 0x18, 0x18, 0x00, 0x00, 0x18, 0x18, 0x00, // ':'
+    0x00, 0x18, 0x18, 0x00, 0x00, 0x18, 0x18, 0x30, // ';'
+    0x06, 0x0c, 0x18, 0x30, 0x18, 0x0c, 0x06, 0x00, // '<'
+    0x00, 0x00, 0x7e, 0x00, 0x7e, 0x00, 0x00, 0x00, // '='
+    0x60, 0x30, 0x18, 0x0c, 0x18, 0x30, 0x60, 0x00, // '>'
+    0x3c, 0x66, 0x06, 0x0c, 0x18, 0x00, 0x18, 0x00, // '?'
+    0x3c, 0x66, 0x6e, 0x6e, 0x60, 0x62, 0x3c, 0x00, // '@'
+    0x18, 0x3c, 0x66, 0x66, 0x7e, 0x66, 0x66, 0x00, // 'A'
+    0x7c, 0x66, 0x66, 0x7c, 0x66, 0x66, 0x7c, 0x00, // 'B'
+    0x3c, 0x66, 0x60, 0x60, 0x60, 0x66, 0x3c, 0x00, // 'C'
+    0x78, 0x6c, 0x66, 0x66, 0x66, 0x6c, 0x78, 0x00, // 'D'
+    0x7e, 0x60, 0x60, 0x7c, 0x60, 0x60, 0x7e, 0x00, // 'E'
+    0x7e, 0x60, 0x60, 0x7c, 0x60, 0x60, 0x60, 0x00, // 'F'
+    0x3c, 0x66, 0x60, 0x6e, 0x66, 0x66, 0x3e, 0x00, // 'G'
+    0x66, 0x66, 0x66, 0x7e, 0x66, 0x66, 0x66, 0x00, // 'H'
+    0x3c, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3c, 0x00, // 'I'
+    0x1e, 0x0c, 0x0c, 0x0c, 0x0c, 0x6c, 0x38, 0x00, // 'J'
+    0x66, 0x6c, 0x78, 0x70, 0x78, 0x6c, 0x66, 0x00, // 'K'
+    0x60, 0x60, 0x60, 0x60, 0x60, 0x60, 0x7e, 0x00, // 'L'
+    0xc6, 0xee, 0xfe, 0xd6, 0xc6, 0xc6, 0xc6, 0x00, // 'M'
+    0x66, 0x76, 0x7e, 0x7e, 0x6e, 0x66, 0x66, 0x00, // 'N'
+    0x3c, 0x66, 0x66, 0x66, 0x66, 0x66, 0x3c, 0x00, // 'O'
+    0x7c, 0x66, 0x66, 0x7c, 0x60, 0x60, 0x60, 0x00, // 'P'
+    0x3c, 0x66, 0x66, 0x66, 0x6e, 0x3c, 0x0e, 0x00, // 'Q'
+    0x7c, 0x66, 0x66, 0x7c, 0x78, 0x6c, 0x66, 0x00, // 'R'
+    0x3c, 0x66, 0x60, 0x3c, 0x06, 0x66, 0x3c, 0x00, // 'S'
+    0x7e, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x00, // 'T'
+    0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x3c, 0x00, // 'U'
+    0x66, 0x66, 0x66, 0x66, 0x66, 0x3c, 0x18, 0x00, // 'V'
+    0xc6, 0xc6, 0xc6, 0xd6, 0xfe, 0xee, 0xc6, 0x00, // 'W'
+    0x66, 0x66, 0x3c, 0x18, 0x3c, 0x66, 0x66, 0x00, // 'X'
+    0x66, 0x66, 0x66, 0x3c, 0x18, 0x18, 0x18, 0x00, // 'Y'
+    0x7e, 0x06, 0x0c, 0x18, 0x30, 0x60, 0x7e, 0x00, // 'Z'
+    0x3c, 0x30, 0x30, 0x30, 0x30, 0x30, 0x3c, 0x00, // '['
+    0xc0, 0x60, 0x30, 0x18, 0x0c, 0x06, 0x03, 0x00, // '\\'
+    0x3c, 0x0c, 0x0c, 0x0c, 0x0c, 0x0c, 0x3c, 0x00, // ']'
+    0x18, 0x3c, 0x66, 0x00, 0x00, 0x00, 0x00, 0x00, // '^'
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, // '_'
+    0x30, 0x18, 0x0c, 0x00, 0x00, 0x00, 0x00, 0x00, // '`'
+    0x00, 0x00, 0x3c, 0x06, 0x3e, 0x66, 0x3e, 0x00, // 'a'
+    0x60, 0x60, 0x7c, 0x66, 0x66, 0x66, 0x7c, 0x00, // 'b'
+    0x00, 0x00, 0x3c, 0x60, 0x60, 0x60, 0x3c, 0x00, // 'c'
+    0x06, 0x06, 0x3e, 0x66, 0x66, 0x66, 0x3e, 0x00, // 'd'
+    0x00, 0x00, 0x3c, 0x66, 0x7e, 0x60, 0x3c, 0x00, // 'e'
+    0x1c, 0x30, 0x7c, 0x30, 0x30, 0x30, 0x30, 0x00, // 'f'
+    0x00, 0x00, 0x3e, 0x66, 0x66, 0x3e, 0x06, 0x3c, // 'g'
+    0x60, 0x60, 0x7c, 0x66, 0x66, 0x66, 0x66, 0x00, // 'h'
+    0x18, 0x00, 0x38, 0x18, 0x18, 0x18, 0x3c, 0x00, // 'i'
+    0x0c, 0x00, 0x1c, 0x0c, 0x0c, 0x0c, 0x6c, 0x38, // 'j'
+    0x60, 0x60, 0x66, 0x6c, 0x78, 0x6c, 0x66, 0x00, // 'k'
+    0x38, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3c, 0x00, // 'l'
+    0x00, 0x00, 0xcc, 0xfe, 0xd6, 0xd6, 0xc6, 0x00, // 'm'
+    0x00, 0x00, 0x7c, 0x66, 0x66, 0x66, 0x66, 0x00, // 'n'
+    0x00, 0x00, 0x3c, 0x66, 0x66, 0x66, 0x3c, 0x00, // 'o'
+    0x00, 0x00, 0x7c, 0x66, 0x66, 0x7c, 0x60, 0x60, // 'p'
+    0x00, 0x00, 0x3e, 0x66, 0x66, 0x3e, 0x06, 0x06, // 'q'
+    0x00, 0x00, 0x6c, 0x76, 0x60, 0x60, 0x60, 0x00, // 'r'
+    0x00, 0x00, 0x3e, 0x60, 0x3c, 0x06, 0x7c, 0x00, // 's'
+    0x30, 0x30, 0x7c, 0x30, 0x30, 0x30, 0x1c, 0x00, // 't'
+    0x00, 0x00, 0x66, 0x66, 0x66, 0x66, 0x3e, 0x00, // 'u'
+    0x00, 0x00, 0x66, 0x66, 0x66, 0x3c, 0x18, 0x00, // 'v'
+    0x00, 0x00, 0xc6, 0xd6, 0xd6, 0xfe, 0x6c, 0x00, // 'w'
+    0x00, 0x00, 0x66, 0x3c, 0x18, 0x3c, 0x66, 0x00, // 'x'
+    0x00, 0x00, 0x66, 0x66, 0x66, 0x3e, 0x06, 0x3c, // 'y'
+    0x00, 0x00, 0x7e, 0x0c, 0x18, 0x30, 0x7e, 0x00, // 'z'
+    0x0e, 0x18, 0x18, 0x70, 0x18, 0x18, 0x0e, 0x00, // '{'
+    0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x00, // '|'
+    0x70, 0x18, 0x18, 0x0e, 0x18, 0x18, 0x70, 0x00, // '}'
+    0x00, 0x00, 0x73, 0xdc, 0x00, 0x00, 0x00, 0x00, // '~'
+
+];
@@ -0,0 +1,229 @@
+//! Double-buffered drawing on top of the graphics output protocol.
+//!
+//! Drawing directly into the frame buffer of a `GraphicsOutput` is unsafe,
+//! requires knowledge of its pixel format, and shows every intermediate state
+//! of the screen. The `Framebuffer` type of this module instead draws into a
+//! back buffer in system memory, and copies the regions which changed to the
+//! screen when `flush()` is called.
+
+use super::gop::{BltOp, BltPixel, BltRegion, GraphicsOutput, ModeInfo};
+use crate::Result;
+use alloc_api::vec;
+use alloc_api::vec::Vec;
+
+/// Back buffer covering the screen of a `GraphicsOutput`
+///
+/// Drawing operations only modify the back buffer and are clipped to its
+/// bounds. The smallest rectangle containing all modified pixels is tracked,
+/// and written to the screen by `flush()`.
+pub struct Framebuffer<'gop, 'boot> {
+    gop: &'gop mut GraphicsOutput<'boot>,
+    info: ModeInfo,
+    pixels: Vec<BltPixel>,
+    dirty: Option<DirtyRect>,
+}
+
+/// Region of the back buffer which differs from the screen, as a range of
+/// columns and a range of rows
+#[derive(Clone, Copy, Debug)]
+struct DirtyRect {
+    x: (usize, usize),
+    y: (usize, usize),
+}
+
+impl DirtyRect {
+    fn union(self, other: DirtyRect) -> DirtyRect {
+        DirtyRect {
+            x: (self.x.0.min(other.x.0), self.x.1.max(other.x.1)),
+            y: (self.y.0.min(other.y.0), self.y.1.max(other.y.1)),
+        }
+    }
+}
+
+impl<'gop, 'boot> Framebuffer<'gop, 'boot> {
+    /// Create a back buffer for the screen of `gop` in its current mode,
+    /// initialized with the current contents of the screen.
+    ///
+    /// The framebuffer must not be used after the mode of `gop` was changed.
+    pub fn new(gop: &'gop mut GraphicsOutput<'boot>) -> Result<Self> {
+        let info = gop.current_mode_info();
+        let (width, height) = info.resolution();
+        let mut pixels = vec![BltPixel::new(0, 0, 0); width * height];
+        if width != 0 && height != 0 {
+            gop.blt(BltOp::VideoToBltBuffer {
+                buffer: &mut pixels,
+                src: (0, 0),
+                dest: BltRegion::Full,
+                dims: (width, height),
+            })?
+            .log();
+        }
+
+        Ok(Self {
+            gop,
+            info,
+            pixels,
+            dirty: None,
+        }
+        .into())
+    }
+
+    /// Returns the (width, height) of the framebuffer, in pixels
+    pub fn size(&self) -> (usize, usize) {
+        self.info.resolution()
+    }
+
+    /// Contents of the back buffer, one row after the other
+    pub fn pixels(&self) -> &[BltPixel] {
+        &self.pixels
+    }
+
+    /// Returns the color of a pixel of the back buffer, or `None` if it is
+    /// outside of the framebuffer
+    pub fn pixel(&self, x: usize, y: usize) -> Option<BltPixel> {
+        let (width, height) = self.size();
+        if x < width && y < height {
+            Some(self.pixels[y * width + x])
+        } else {
+            None
+        }
+    }
+
+    /// Sets the color of a pixel, ignoring pixels outside of the framebuffer
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: BltPixel) {
+        self.fill_rect((x, y), (1, 1), color);
+    }
+
+    /// Fills a rectangle with a solid color, clipping it to the framebuffer
+    ///
+    /// The rectangle is given by the coordinates of its top-left corner and
+    /// its (width, height).
+    pub fn fill_rect(&mut self, coords: (usize, usize), dims: (usize, usize), color: BltPixel) {
+        let rect = match self.clip(coords, dims) {
+            Some(rect) => rect,
+            None => return,
+        };
+        let width = self.size().0;
+        for row in self.pixels[rect.y.0 * width..rect.y.1 * width].chunks_exact_mut(width) {
+            for pixel in &mut row[rect.x.0..rect.x.1] {
+                *pixel = color;
+            }
+        }
+        self.mark_dirty(rect);
+    }
+
+    /// Fills the whole framebuffer with a solid color
+    pub fn clear(&mut self, color: BltPixel) {
+        let size = self.size();
+        self.fill_rect((0, 0), size, color);
+    }
+
+    /// Copies an image to the framebuffer, clipping it to the framebuffer
+    ///
+    /// The image is stored row after row in `image`, and has the given
+    /// (width, height). Its top-left corner is drawn at `coords`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `image` is smaller than its dimensions.
+    pub fn draw_image(&mut self, coords: (usize, usize), dims: (usize, usize), image: &[BltPixel]) {
+        assert!(
+            dims.0.saturating_mul(dims.1) <= image.len(),
+            "Image is smaller than its dimensions"
+        );
+        let rect = match self.clip(coords, dims) {
+            Some(rect) => rect,
+            None => return,
+        };
+        let width = self.size().0;
+        let copy_width = rect.x.1 - rect.x.0;
+        for y in rect.y.0..rect.y.1 {
+            let src = (y - coords.1) * dims.0;
+            let dest = y * width + rect.x.0;
+            self.pixels[dest..dest + copy_width].copy_from_slice(&image[src..src + copy_width]);
+        }
+        self.mark_dirty(rect);
+    }
+
+    /// Writes the regions of the back buffer which were modified since the
+    /// last flush to the screen.
+    ///
+    /// In modes where the frame buffer can be accessed directly, the pixels
+    /// are converted to its pixel format and written to it. Otherwise, they
+    /// are transferred with a blit operation.
+    pub fn flush(&mut self) -> Result {
+        let rect = match self.dirty.take() {
+            Some(rect) => rect,
+            None => return Ok(().into()),
+        };
+        let width = self.size().0;
+        let dims = (rect.x.1 - rect.x.0, rect.y.1 - rect.y.0);
+
+        let bytes_per_pixel = match self.info.bytes_per_pixel() {
+            Some(bytes_per_pixel) => bytes_per_pixel,
+            None => {
+                return self.gop.blt(BltOp::BufferToVideo {
+                    buffer: &self.pixels,
+                    src: BltRegion::SubRectangle {
+                        coords: (rect.x.0, rect.y.0),
+                        px_stride: width,
+                    },
+                    dest: (rect.x.0, rect.y.0),
+                    dims,
+                });
+            }
+        };
+
+        let info = self.info;
+        let stride = info.stride();
+        let mut fb = self.gop.frame_buffer();
+        for y in rect.y.0..rect.y.1 {
+            let row = &self.pixels[y * width + rect.x.0..y * width + rect.x.1];
+            let mut index = (y * stride + rect.x.0) * bytes_per_pixel;
+            for &pixel in row {
+                let value = info.encode_pixel(pixel).unwrap().to_le_bytes();
+                unsafe {
+                    if bytes_per_pixel == 4 {
+                        fb.write_value(index, value);
+                    } else {
+                        for (offset, &byte) in value[..bytes_per_pixel].iter().enumerate() {
+                            fb.write_byte(index + offset, byte);
+                        }
+                    }
+                }
+                index += bytes_per_pixel;
+            }
+        }
+        Ok(().into())
+    }
+
+    /// Writes the entire back buffer to the screen
+    pub fn flush_all(&mut self) -> Result {
+        let size = self.size();
+        if let Some(rect) = self.clip((0, 0), size) {
+            self.mark_dirty(rect);
+        }
+        self.flush()
+    }
+
+    /// Clip a rectangle to the framebuffer, returning `None` if it is empty
+    fn clip(&self, (x, y): (usize, usize), (width, height): (usize, usize)) -> Option<DirtyRect> {
+        let (max_x, max_y) = self.size();
+        let rect = DirtyRect {
+            x: (x.min(max_x), x.saturating_add(width).min(max_x)),
+            y: (y.min(max_y), y.saturating_add(height).min(max_y)),
+        };
+        if rect.x.0 < rect.x.1 && rect.y.0 < rect.y.1 {
+            Some(rect)
+        } else {
+            None
+        }
+    }
+
+    fn mark_dirty(&mut self, rect: DirtyRect) {
+        self.dirty = Some(match self.dirty {
+            Some(dirty) => dirty.union(rect),
+            None => rect,
+        });
+    }
+}
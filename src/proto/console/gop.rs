@@ -59,6 +59,18 @@ use crate::{unsafe_guid, Completion, Result, Status};
 use core::marker::PhantomData;
 use core::mem;
 use core::ptr;
+#[cfg(feature = "embedded-graphics")]
+use {
+    crate::result::Error,
+    crate::ResultExt,
+    embedded_graphics_core::{
+        draw_target::DrawTarget,
+        geometry::{Dimensions, OriginDimensions, Size},
+        pixelcolor::{Rgb888, RgbColor},
+        primitives::Rectangle,
+        Pixel,
+    },
+};
 
 /// Provides access to the video hardware's frame buffer.
 ///
@@ -118,6 +130,67 @@ impl<'boot> GraphicsOutput<'boot> {
         }
     }
 
+    /// Returns the mode the video device is currently in.
+    pub fn current_mode(&self) -> Result<Mode> {
+        self.query_mode(self.mode.mode)
+    }
+
+    /// Returns the first mode with the given resolution.
+    ///
+    /// Modes whose frame buffer can be accessed directly are preferred over
+    /// Blt-only modes of the same resolution.
+    pub fn mode_matching(&self, width: usize, height: usize) -> Option<Mode> {
+        let mut matching = self
+            .modes()
+            .map(Completion::log)
+            .filter(|mode| mode.info().resolution() == (width, height));
+        let first = matching.next()?;
+        if first.info().pixel_format() != PixelFormat::BltOnly {
+            return Some(first);
+        }
+        Some(
+            matching
+                .find(|mode| mode.info().pixel_format() != PixelFormat::BltOnly)
+                .unwrap_or(first),
+        )
+    }
+
+    /// Returns the mode with the highest resolution.
+    ///
+    /// Modes whose frame buffer can be accessed directly are preferred over
+    /// Blt-only modes of the same resolution, and earlier modes are preferred
+    /// over later ones.
+    pub fn best_mode(&self) -> Option<Mode> {
+        self.max_mode_by(|info| {
+            let (width, height) = info.resolution();
+            (width * height, info.pixel_format() != PixelFormat::BltOnly)
+        })
+    }
+
+    /// Returns the mode with the highest resolution among those using the
+    /// given pixel format, or the mode with the highest resolution if none
+    /// uses it.
+    pub fn best_mode_with_format(&self, format: PixelFormat) -> Option<Mode> {
+        self.max_mode_by(|info| {
+            let (width, height) = info.resolution();
+            (info.pixel_format() == format, width * height)
+        })
+    }
+
+    /// Find the first mode with the highest `key`
+    fn max_mode_by<K: Ord>(&self, key: impl Fn(&ModeInfo) -> K) -> Option<Mode> {
+        self.modes()
+            .map(Completion::log)
+            .fold(None, |best: Option<(K, Mode)>, mode| {
+                let mode_key = key(mode.info());
+                match best {
+                    Some((best_key, best)) if best_key >= mode_key => Some((best_key, best)),
+                    _ => Some((mode_key, mode)),
+                }
+            })
+            .map(|(_, mode)| mode)
+    }
+
     /// Sets the video device into the specified mode, clearing visible portions
     /// of the output display to black.
     ///
@@ -429,6 +502,57 @@ impl ModeInfo {
     pub fn stride(&self) -> usize {
         self.stride as usize
     }
+
+    /// Returns the size of a pixel in the frame buffer, in bytes, or `None`
+    /// in a Blt-only mode.
+    ///
+    /// Pixels are 4 bytes long in RGB and BGR modes. In bitmask modes, their
+    /// size is determined by the highest bit set in the masks.
+    pub fn bytes_per_pixel(&self) -> Option<usize> {
+        match self.format {
+            PixelFormat::Rgb | PixelFormat::Bgr => Some(4),
+            PixelFormat::Bitmask => {
+                let mask = self.mask;
+                let bits = 32 - (mask.red | mask.green | mask.blue | mask.reserved).leading_zeros();
+                Some(((bits as usize + 7) >> 3).max(1))
+            }
+            PixelFormat::BltOnly => None,
+        }
+    }
+
+    /// Converts a pixel to its representation in the frame buffer, or returns
+    /// `None` in a Blt-only mode.
+    ///
+    /// The representation is stored in the low `bytes_per_pixel()` bytes of
+    /// the result, in little-endian order.
+    pub fn encode_pixel(&self, pixel: BltPixel) -> Option<u32> {
+        let BltPixel {
+            red, green, blue, ..
+        } = pixel;
+        match self.format {
+            PixelFormat::Rgb => Some(u32::from_le_bytes([red, green, blue, 0])),
+            PixelFormat::Bgr => Some(u32::from_le_bytes([blue, green, red, 0])),
+            PixelFormat::Bitmask => {
+                let mask = self.mask;
+                Some(
+                    encode_channel(red, mask.red)
+                        | encode_channel(green, mask.green)
+                        | encode_channel(blue, mask.blue),
+                )
+            }
+            PixelFormat::BltOnly => None,
+        }
+    }
+}
+
+/// Scales an 8-bit color channel to the bits selected by `mask`
+fn encode_channel(value: u8, mask: u32) -> u32 {
+    if mask == 0 {
+        return 0;
+    }
+    let shift = mask.trailing_zeros();
+    let max = u64::from(mask >> shift);
+    ((u64::from(value) * max / 255) as u32) << shift
 }
 
 /// Iterator for graphics modes.
@@ -563,6 +687,109 @@ pub enum BltOp<'buf> {
     },
 }
 
+/// Drawing surface covering the screen of a `GraphicsOutput`
+///
+/// This implements the `DrawTarget` trait of the `embedded-graphics` crate.
+/// Drawing operations are clipped to the screen, and pixels are converted to
+/// the pixel format of the current mode. Pixels are written directly to the
+/// frame buffer when possible, and blitted one by one in Blt-only modes.
+#[cfg(feature = "embedded-graphics")]
+pub struct GopDisplay<'gop, 'boot> {
+    gop: &'gop mut GraphicsOutput<'boot>,
+    info: ModeInfo,
+}
+
+#[cfg(feature = "embedded-graphics")]
+impl<'gop, 'boot> GopDisplay<'gop, 'boot> {
+    /// Draw on the screen of `gop`, in its current mode
+    ///
+    /// The display must not be used after the mode of `gop` was changed.
+    pub fn new(gop: &'gop mut GraphicsOutput<'boot>) -> Self {
+        let info = gop.current_mode_info();
+        Self { gop, info }
+    }
+}
+
+#[cfg(feature = "embedded-graphics")]
+impl From<Rgb888> for BltPixel {
+    fn from(color: Rgb888) -> Self {
+        BltPixel::new(color.r(), color.g(), color.b())
+    }
+}
+
+#[cfg(feature = "embedded-graphics")]
+impl OriginDimensions for GopDisplay<'_, '_> {
+    fn size(&self) -> Size {
+        let (width, height) = self.info.resolution();
+        Size::new(width as u32, height as u32)
+    }
+}
+
+#[cfg(feature = "embedded-graphics")]
+impl DrawTarget for GopDisplay<'_, '_> {
+    type Color = Rgb888;
+    type Error = Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> core::result::Result<(), Error>
+    where
+        I: IntoIterator<Item = Pixel<Rgb888>>,
+    {
+        let bounds = self.bounding_box();
+        let pixels = pixels
+            .into_iter()
+            .filter(move |Pixel(point, _)| bounds.contains(*point))
+            .map(|Pixel(point, color)| ((point.x as usize, point.y as usize), color.into()));
+
+        let bytes_per_pixel = match self.info.bytes_per_pixel() {
+            Some(bytes_per_pixel) => bytes_per_pixel,
+            None => {
+                for (dest, color) in pixels {
+                    self.gop
+                        .blt(BltOp::VideoFill {
+                            color,
+                            dest,
+                            dims: (1, 1),
+                        })
+                        .log_warning()?;
+                }
+                return Ok(());
+            }
+        };
+
+        let stride = self.info.stride();
+        let info = self.info;
+        let mut fb = self.gop.frame_buffer();
+        for ((x, y), color) in pixels {
+            let value = info.encode_pixel(color).unwrap().to_le_bytes();
+            let base = (y * stride + x) * bytes_per_pixel;
+            for (offset, byte) in value.iter().take(bytes_per_pixel).enumerate() {
+                unsafe { fb.write_byte(base + offset, *byte) };
+            }
+        }
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Rgb888) -> core::result::Result<(), Error> {
+        let area = area.intersection(&self.bounding_box());
+        if area.is_zero_sized() {
+            return Ok(());
+        }
+
+        self.gop
+            .blt(BltOp::VideoFill {
+                color: color.into(),
+                dest: (area.top_left.x as usize, area.top_left.y as usize),
+                dims: (area.size.width as usize, area.size.height as usize),
+            })
+            .log_warning()
+    }
+
+    fn clear(&mut self, color: Rgb888) -> core::result::Result<(), Error> {
+        let area = self.bounding_box();
+        self.fill_solid(&area, color)
+    }
+}
+
 /// Direct access to a memory-mapped frame buffer
 pub struct FrameBuffer<'gop> {
     base: *mut u8,
@@ -0,0 +1,231 @@
+//! Text console drawn on a graphics output device.
+//!
+//! Once a graphics mode has been set, the `SimpleTextOutput` protocol is
+//! often unavailable, or draws text which does not match the rest of the
+//! screen. `GraphicsConsole` instead renders text with a bitmap `Font`
+//! through the `GraphicsOutput` protocol, wrapping long lines and scrolling
+//! the screen when its last line is full.
+
+use super::font::{Font, MAX_GLYPH_PIXELS};
+use super::gop::{BltOp, BltPixel, BltRegion, GraphicsOutput};
+use crate::{Result, ResultExt, Status};
+use core::fmt;
+
+/// Number of columns between tab stops.
+const TAB_WIDTH: usize = 8;
+
+/// Text console covering the screen of a `GraphicsOutput`.
+pub struct GraphicsConsole<'gop, 'boot, 'font> {
+    gop: &'gop mut GraphicsOutput<'boot>,
+    font: Font<'font>,
+    columns: usize,
+    rows: usize,
+    cursor: (usize, usize),
+    foreground: BltPixel,
+    background: BltPixel,
+    cursor_visible: bool,
+}
+
+impl<'gop, 'boot> GraphicsConsole<'gop, 'boot, 'static> {
+    /// Create a console using the built-in font, on the screen of `gop` in
+    /// its current mode.
+    ///
+    /// The screen is not cleared. The console must not be used after the
+    /// mode of `gop` was changed.
+    pub fn new(gop: &'gop mut GraphicsOutput<'boot>) -> Self {
+        Self::with_font(gop, Font::builtin())
+    }
+}
+
+impl<'gop, 'boot, 'font> GraphicsConsole<'gop, 'boot, 'font> {
+    /// Create a console using the given font.
+    pub fn with_font(gop: &'gop mut GraphicsOutput<'boot>, font: Font<'font>) -> Self {
+        let (width, height) = gop.current_mode_info().resolution();
+        let (glyph_width, glyph_height) = font.glyph_size();
+        Self {
+            gop,
+            font,
+            columns: width / glyph_width,
+            rows: height / glyph_height,
+            cursor: (0, 0),
+            foreground: BltPixel::new(0xaa, 0xaa, 0xaa),
+            background: BltPixel::new(0, 0, 0),
+            cursor_visible: false,
+        }
+    }
+
+    /// Returns the number of (columns, rows) of the console.
+    pub fn size(&self) -> (usize, usize) {
+        (self.columns, self.rows)
+    }
+
+    /// Returns the (column, row) of the cursor.
+    pub fn cursor_position(&self) -> (usize, usize) {
+        self.cursor
+    }
+
+    /// Moves the cursor to the given (column, row).
+    ///
+    /// # Errors
+    ///
+    /// - `Unsupported` if the position is outside of the console
+    pub fn set_cursor_position(&mut self, column: usize, row: usize) -> Result {
+        if column >= self.columns || row >= self.rows {
+            return Err(Status::UNSUPPORTED.into());
+        }
+        self.draw_cursor(false)?.log();
+        self.cursor = (column, row);
+        self.draw_cursor(true)
+    }
+
+    /// Sets the colors of the text printed from now on.
+    pub fn set_colors(&mut self, foreground: BltPixel, background: BltPixel) {
+        self.foreground = foreground;
+        self.background = background;
+    }
+
+    /// Shows or hides the cursor, drawn as an underline.
+    pub fn enable_cursor(&mut self, visible: bool) -> Result {
+        self.draw_cursor(false)?.log();
+        self.cursor_visible = visible;
+        self.draw_cursor(true)
+    }
+
+    /// Fills the console with the background color and moves the cursor to
+    /// the top-left corner.
+    pub fn clear(&mut self) -> Result {
+        let (width, height) = self.gop.current_mode_info().resolution();
+        self.gop
+            .blt(BltOp::VideoFill {
+                color: self.background,
+                dest: (0, 0),
+                dims: (width, height),
+            })?
+            .log();
+        self.cursor = (0, 0);
+        self.draw_cursor(true)
+    }
+
+    /// Prints a string, interpreting line feeds, carriage returns, tabs and
+    /// backspaces.
+    ///
+    /// Characters which are missing from the font are printed as `?`.
+    pub fn output_string(&mut self, s: &str) -> Result {
+        if self.columns == 0 || self.rows == 0 {
+            return Ok(().into());
+        }
+        self.draw_cursor(false)?.log();
+        for c in s.chars() {
+            self.put_char(c)?.log();
+        }
+        self.draw_cursor(true)
+    }
+
+    fn put_char(&mut self, c: char) -> Result {
+        match c {
+            '\n' => self.new_line(),
+            '\r' => {
+                self.cursor.0 = 0;
+                Ok(().into())
+            }
+            '\t' => {
+                let spaces = TAB_WIDTH - self.cursor.0 % TAB_WIDTH;
+                for _ in 0..spaces {
+                    self.put_char(' ')?.log();
+                }
+                Ok(().into())
+            }
+            '\u{8}' => {
+                self.cursor.0 = self.cursor.0.saturating_sub(1);
+                Ok(().into())
+            }
+            c => {
+                if self.cursor.0 == self.columns {
+                    self.new_line()?.log();
+                }
+                self.draw_glyph(c)?.log();
+                self.cursor.0 += 1;
+                Ok(().into())
+            }
+        }
+    }
+
+    /// Moves the cursor to the start of the next line, scrolling if needed
+    fn new_line(&mut self) -> Result {
+        self.cursor.0 = 0;
+        if self.cursor.1 + 1 < self.rows {
+            self.cursor.1 += 1;
+            return Ok(().into());
+        }
+
+        let (glyph_width, glyph_height) = self.font.glyph_size();
+        let width = self.columns * glyph_width;
+        if self.rows > 1 {
+            self.gop
+                .blt(BltOp::VideoToVideo {
+                    src: (0, glyph_height),
+                    dest: (0, 0),
+                    dims: (width, (self.rows - 1) * glyph_height),
+                })?
+                .log();
+        }
+        self.gop.blt(BltOp::VideoFill {
+            color: self.background,
+            dest: (0, (self.rows - 1) * glyph_height),
+            dims: (width, glyph_height),
+        })
+    }
+
+    /// Draws a character in the cell of the cursor
+    fn draw_glyph(&mut self, c: char) -> Result {
+        let glyph = match self.font.glyph(c).or_else(|| self.font.glyph('?')) {
+            Some(glyph) => glyph,
+            None => return Ok(().into()),
+        };
+        let (width, height) = glyph.size();
+        let mut pixels = [self.background; MAX_GLYPH_PIXELS];
+        for y in 0..height {
+            for x in 0..width {
+                if glyph.is_set(x, y) {
+                    pixels[y * width + x] = self.foreground;
+                }
+            }
+        }
+        self.gop.blt(BltOp::BufferToVideo {
+            buffer: &pixels,
+            src: BltRegion::SubRectangle {
+                coords: (0, 0),
+                px_stride: width,
+            },
+            dest: (self.cursor.0 * width, self.cursor.1 * height),
+            dims: (width, height),
+        })
+    }
+
+    /// Draws or erases the cursor, if it is enabled and inside the console
+    fn draw_cursor(&mut self, visible: bool) -> Result {
+        let (column, row) = self.cursor;
+        if !self.cursor_visible || column >= self.columns || row >= self.rows {
+            return Ok(().into());
+        }
+        let (width, height) = self.font.glyph_size();
+        let color = if visible {
+            self.foreground
+        } else {
+            self.background
+        };
+        self.gop.blt(BltOp::VideoFill {
+            color,
+            dest: (column * width, (row + 1) * height - 1),
+            dims: (width, 1),
+        })
+    }
+}
+
+impl fmt::Write for GraphicsConsole<'_, '_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.output_string(s)
+            .warning_as_error()
+            .map_err(|_| fmt::Error)
+    }
+}
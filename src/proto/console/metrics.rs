@@ -0,0 +1,102 @@
+//! Character cell geometry of the text console.
+//!
+//! When the text console is drawn on a graphics device, each character is
+//! drawn in a cell of a fixed size. The UEFI specification defines the size of
+//! the standard (narrow) glyphs as 8×19 pixels, and firmware based on EDK2
+//! centers the text on the screen when the resolution is not a multiple of
+//! it. `ConsoleMetrics` correlates the current graphics and text modes to
+//! find where each cell is, so that graphics can be aligned with the text.
+
+use super::gop::GraphicsOutput;
+use super::text::Output;
+
+/// Size of the standard (narrow) glyphs of the UEFI console, in pixels.
+pub const STANDARD_GLYPH_SIZE: (usize, usize) = (8, 19);
+
+/// Position and size of the character cells of the text console on the
+/// screen.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ConsoleMetrics {
+    /// (width, height) of a character cell, in pixels.
+    pub cell_size: (usize, usize),
+    /// (x, y) of the top-left corner of the first cell, in pixels.
+    pub origin: (usize, usize),
+    /// Number of (columns, rows) of the text mode.
+    pub text_size: (usize, usize),
+}
+
+impl ConsoleMetrics {
+    /// Computes the geometry of a text mode of `text_size` (columns, rows)
+    /// drawn on a screen of `resolution` (width, height).
+    ///
+    /// The standard glyph size is assumed if the text fits on the screen with
+    /// it, and the text is centered. Otherwise, the cells are assumed to cover
+    /// the whole screen.
+    ///
+    /// Returns `None` if the text mode has no columns or rows, or if it has
+    /// more of them than the screen has pixels.
+    pub fn from_modes(resolution: (usize, usize), text_size: (usize, usize)) -> Option<Self> {
+        let (width, height) = resolution;
+        let (columns, rows) = text_size;
+        if columns == 0 || rows == 0 {
+            return None;
+        }
+        let (glyph_width, glyph_height) = STANDARD_GLYPH_SIZE;
+        let fits = |count: usize, glyph: usize, pixels: usize| match count.checked_mul(glyph) {
+            Some(size) => size <= pixels,
+            None => false,
+        };
+        let cell_size = if fits(columns, glyph_width, width) && fits(rows, glyph_height, height) {
+            STANDARD_GLYPH_SIZE
+        } else {
+            (width / columns, height / rows)
+        };
+        if cell_size.0 == 0 || cell_size.1 == 0 {
+            return None;
+        }
+        let origin = (
+            (width - columns * cell_size.0) / 2,
+            (height - rows * cell_size.1) / 2,
+        );
+        Some(Self {
+            cell_size,
+            origin,
+            text_size,
+        })
+    }
+
+    /// Computes the geometry of the current text mode of `output`, drawn on
+    /// the screen of `gop` in its current mode.
+    ///
+    /// Returns `None` if the current text mode is unknown.
+    pub fn query(gop: &GraphicsOutput, output: &Output) -> Option<Self> {
+        let mode = output.current_mode().ok()?.log()?;
+        let resolution = gop.current_mode_info().resolution();
+        Self::from_modes(resolution, (mode.columns(), mode.rows()))
+    }
+
+    /// Returns the (x, y) of the top-left corner of the cell at the given
+    /// (column, row), in pixels.
+    pub fn cell_position(&self, column: usize, row: usize) -> (usize, usize) {
+        (
+            self.origin.0 + column * self.cell_size.0,
+            self.origin.1 + row * self.cell_size.1,
+        )
+    }
+
+    /// Returns the (column, row) of the cell containing the pixel at (x, y),
+    /// or `None` if it is outside of the text or the cells are empty.
+    pub fn cell_at(&self, x: usize, y: usize) -> Option<(usize, usize)> {
+        let column = x
+            .checked_sub(self.origin.0)?
+            .checked_div(self.cell_size.0)?;
+        let row = y
+            .checked_sub(self.origin.1)?
+            .checked_div(self.cell_size.1)?;
+        if column < self.text_size.0 && row < self.text_size.1 {
+            Some((column, row))
+        } else {
+            None
+        }
+    }
+}
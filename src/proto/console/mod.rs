@@ -3,7 +3,17 @@
 //! The console represents the various input and output methods
 //! used by the user to interact with the early boot platform.
 
+#[cfg(feature = "exts")]
+pub mod blt_batch;
+pub mod control;
+pub mod edid;
+pub mod font;
+#[cfg(feature = "exts")]
+pub mod framebuffer;
 pub mod gop;
+pub mod graphics_console;
+pub mod metrics;
 pub mod pointer;
 pub mod serial;
 pub mod text;
+pub mod uga;
@@ -0,0 +1,139 @@
+use crate::proto::Protocol;
+use crate::{unsafe_guid, Event, Result, Status};
+use bitflags::bitflags;
+use core::mem::MaybeUninit;
+
+/// Provides information about an absolute pointer device, such as a
+/// touchscreen or a graphics tablet.
+#[repr(C)]
+#[unsafe_guid("8d59d32b-c655-4ae9-9b15-f25904992a43")]
+#[derive(Protocol)]
+pub struct AbsolutePointer<'boot> {
+    reset: extern "efiapi" fn(this: &mut AbsolutePointer, ext_verif: bool) -> Status,
+    get_state:
+        extern "efiapi" fn(this: &AbsolutePointer, state: *mut AbsolutePointerState) -> Status,
+    wait_for_input: Event,
+    mode: &'boot AbsolutePointerMode,
+}
+
+impl<'boot> AbsolutePointer<'boot> {
+    /// Resets the pointer device hardware.
+    ///
+    /// The `extended_verification` parameter is used to request that UEFI
+    /// performs an extended check and reset of the input device.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if the device is malfunctioning and cannot be reset.
+    pub fn reset(&mut self, extended_verification: bool) -> Result {
+        (self.reset)(self, extended_verification).into()
+    }
+
+    /// Retrieves the pointer device's current state, if a state change occured
+    /// since the last time this function was called.
+    ///
+    /// Use `wait_for_input_event()` with the `BootServices::wait_for_event()`
+    /// interface in order to wait for input from the pointer device.
+    ///
+    /// # Errors
+    /// - `DeviceError` if there was an issue with the pointer device.
+    pub fn read_state(&mut self) -> Result<Option<AbsolutePointerState>> {
+        let mut pointer_state = MaybeUninit::<AbsolutePointerState>::uninit();
+
+        match (self.get_state)(self, pointer_state.as_mut_ptr()) {
+            Status::NOT_READY => Ok(None.into()),
+            other => other.into_with_val(|| unsafe { Some(pointer_state.assume_init()) }),
+        }
+    }
+
+    /// Event to be used with `BootServices::wait_for_event()` in order to wait
+    /// for input from the pointer device
+    pub fn wait_for_input_event(&self) -> Event {
+        self.wait_for_input
+    }
+
+    /// Returns a reference to the pointer device information.
+    pub fn mode(&self) -> &AbsolutePointerMode {
+        self.mode
+    }
+}
+
+/// Information about this absolute pointer device.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct AbsolutePointerMode {
+    /// The minimum value reported on the X/Y/Z axis.
+    pub absolute_min: (u64, u64, u64),
+    /// The maximum value reported on the X/Y/Z axis.
+    ///
+    /// If the minimum and maximum of an axis are both 0, then the device does
+    /// _not_ support that axis.
+    pub absolute_max: (u64, u64, u64),
+    /// Capabilities of the device.
+    pub attributes: AbsolutePointerAttributes,
+}
+
+impl AbsolutePointerMode {
+    /// Maps a position reported by the device to a `width` × `height` area,
+    /// such as the screen, clamping it to the calibrated range of the device.
+    ///
+    /// Returns `None` if the device does not support the X and Y axes.
+    pub fn scale_position(
+        &self,
+        state: &AbsolutePointerState,
+        width: usize,
+        height: usize,
+    ) -> Option<(usize, usize)> {
+        let scale = |current: u64, min: u64, max: u64, size: usize| {
+            if max <= min {
+                return None;
+            }
+            let offset = u128::from(current.max(min).min(max) - min);
+            let range = u128::from(max - min);
+            let size = size.saturating_sub(1) as u128;
+            Some((offset * size / range) as usize)
+        };
+        let (min_x, min_y, _) = self.absolute_min;
+        let (max_x, max_y, _) = self.absolute_max;
+        let (x, y, _) = state.current;
+        Some((
+            scale(x, min_x, max_x, width)?,
+            scale(y, min_y, max_y, height)?,
+        ))
+    }
+}
+
+bitflags! {
+    /// Capabilities of an absolute pointer device.
+    pub struct AbsolutePointerAttributes: u32 {
+        /// The device has an alternate button, such as the side button of a pen.
+        const SUPPORTS_ALT_ACTIVE = 0x1;
+        /// The Z axis reports the pressure applied to the device rather than
+        /// the distance to it.
+        const SUPPORTS_PRESSURE_AS_Z = 0x2;
+    }
+}
+
+/// The absolute position of the pointer and the state of its buttons.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct AbsolutePointerState {
+    /// The position on the X/Y/Z axis, within the range given by
+    /// `AbsolutePointerMode`.
+    ///
+    /// If `AbsolutePointerMode` indicates an axis is not supported, it must
+    /// be ignored.
+    pub current: (u64, u64, u64),
+    /// The buttons which are currently active.
+    pub active_buttons: AbsolutePointerButtons,
+}
+
+bitflags! {
+    /// Buttons of an absolute pointer device.
+    pub struct AbsolutePointerButtons: u32 {
+        /// The device is being touched.
+        const TOUCH_ACTIVE = 0x1;
+        /// The alternate button is pressed.
+        const ALT_ACTIVE = 0x2;
+    }
+}
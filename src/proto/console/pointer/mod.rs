@@ -4,6 +4,15 @@ use crate::proto::Protocol;
 use crate::{unsafe_guid, Event, Result, Status};
 use core::mem::MaybeUninit;
 
+mod absolute;
+pub use self::absolute::{
+    AbsolutePointer, AbsolutePointerAttributes, AbsolutePointerButtons, AbsolutePointerMode,
+    AbsolutePointerState,
+};
+
+mod tracker;
+pub use self::tracker::{PointerButtons, PointerChange, PointerTracker};
+
 /// Provides information about a pointer device.
 #[repr(C)]
 #[unsafe_guid("31878c87-0b75-11d5-9a4f-0090273fc14d")]
@@ -61,11 +70,12 @@ impl<'boot> Pointer<'boot> {
 #[derive(Debug, Copy, Clone)]
 #[repr(C)]
 pub struct PointerMode {
-    // The pointer device's resolution on the X/Y/Z axis in counts/mm.
-    // If a value is 0, then the device does _not_ support that axis.
-    resolution: (u64, u64, u64),
+    /// The pointer device's resolution on the X/Y/Z axis in counts/mm.
+    ///
+    /// If a value is 0, then the device does _not_ support that axis.
+    pub resolution: (u64, u64, u64),
     /// Whether the devices has a left button / right button.
-    has_button: (bool, bool),
+    pub has_button: (bool, bool),
 }
 
 /// The relative change in the pointer's state.
@@ -0,0 +1,232 @@
+use super::{
+    AbsolutePointer, AbsolutePointerButtons, AbsolutePointerMode, AbsolutePointerState, Pointer,
+    PointerMode, PointerState,
+};
+use crate::proto::console::gop::GraphicsOutput;
+use crate::Result;
+use bitflags::bitflags;
+
+/// Default number of pixels the cursor moves for each millimeter of movement
+/// of a relative pointer device.
+const DEFAULT_PIXELS_PER_MM: i64 = 4;
+
+bitflags! {
+    /// Buttons tracked by a `PointerTracker`.
+    ///
+    /// For absolute pointer devices, touching the device presses the primary
+    /// button, and the alternate button is the secondary one.
+    pub struct PointerButtons: u8 {
+        /// Left mouse button, or touch.
+        const PRIMARY = 0x1;
+        /// Right mouse button, or alternate button.
+        const SECONDARY = 0x2;
+    }
+}
+
+/// Changes caused by a state update of a pointer device.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct PointerChange {
+    /// True if the cursor position changed.
+    pub moved: bool,
+    /// Buttons which were pressed since the previous update.
+    pub pressed: PointerButtons,
+    /// Buttons which were released since the previous update.
+    pub released: PointerButtons,
+}
+
+/// Cursor position and button state built from the state updates of a
+/// pointer device.
+///
+/// The relative movements reported by a `Pointer` are integrated into a
+/// position on the screen, and the positions reported by an
+/// `AbsolutePointer` are scaled to it. In both cases, the position stays
+/// within the bounds of the screen, and changes of the buttons are reported
+/// as presses and releases.
+#[derive(Debug, Clone)]
+pub struct PointerTracker {
+    position: (usize, usize),
+    bounds: (usize, usize),
+    buttons: PointerButtons,
+    pixels_per_mm: i64,
+    /// Movement of each axis which did not add up to a full pixel yet, in
+    /// device counts
+    remainder: (i64, i64),
+}
+
+impl PointerTracker {
+    /// Track a cursor on a `width` × `height` screen, starting at its center.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            position: (width / 2, height / 2),
+            bounds: (width, height),
+            buttons: PointerButtons::empty(),
+            pixels_per_mm: DEFAULT_PIXELS_PER_MM,
+            remainder: (0, 0),
+        }
+    }
+
+    /// Track a cursor on the screen of `gop`, in its current mode.
+    pub fn for_gop(gop: &GraphicsOutput) -> Self {
+        let (width, height) = gop.current_mode_info().resolution();
+        Self::new(width, height)
+    }
+
+    /// Returns the (x, y) position of the cursor, in pixels.
+    pub fn position(&self) -> (usize, usize) {
+        self.position
+    }
+
+    /// Moves the cursor, clamping the position to the screen.
+    pub fn set_position(&mut self, x: usize, y: usize) {
+        self.position = self.clamp(x, y);
+        self.remainder = (0, 0);
+    }
+
+    /// Changes the size of the screen, for instance after a mode change,
+    /// clamping the cursor position to it.
+    pub fn set_bounds(&mut self, width: usize, height: usize) {
+        self.bounds = (width, height);
+        self.position = self.clamp(self.position.0, self.position.1);
+    }
+
+    /// Sets the number of pixels the cursor moves for each millimeter of
+    /// movement of a relative pointer device.
+    ///
+    /// For devices which do not report their resolution, this is the number
+    /// of pixels moved per count instead.
+    pub fn set_speed(&mut self, pixels_per_mm: u32) {
+        self.pixels_per_mm = i64::from(pixels_per_mm);
+    }
+
+    /// Returns the buttons which are currently pressed.
+    pub fn buttons(&self) -> PointerButtons {
+        self.buttons
+    }
+
+    /// Applies a state update of a relative pointer device.
+    pub fn update(&mut self, mode: &PointerMode, state: &PointerState) -> PointerChange {
+        let (resolution_x, resolution_y, _) = mode.resolution;
+        let (dx, dy, _) = state.relative_movement;
+        let (dx, remainder_x) = self.scale(dx, resolution_x, self.remainder.0);
+        let (dy, remainder_y) = self.scale(dy, resolution_y, self.remainder.1);
+        self.remainder = (remainder_x, remainder_y);
+
+        let x = offset(self.position.0, dx);
+        let y = offset(self.position.1, dy);
+        let moved = self.move_to(x, y);
+
+        let mut buttons = PointerButtons::empty();
+        buttons.set(PointerButtons::PRIMARY, state.button.0 && mode.has_button.0);
+        buttons.set(
+            PointerButtons::SECONDARY,
+            state.button.1 && mode.has_button.1,
+        );
+        self.change(moved, buttons)
+    }
+
+    /// Applies a state update of an absolute pointer device.
+    ///
+    /// The cursor does not move if the device does not support the X and Y
+    /// axes.
+    pub fn update_absolute(
+        &mut self,
+        mode: &AbsolutePointerMode,
+        state: &AbsolutePointerState,
+    ) -> PointerChange {
+        let moved = match mode.scale_position(state, self.bounds.0, self.bounds.1) {
+            Some((x, y)) => self.move_to(x, y),
+            None => false,
+        };
+
+        let mut buttons = PointerButtons::empty();
+        buttons.set(
+            PointerButtons::PRIMARY,
+            state
+                .active_buttons
+                .contains(AbsolutePointerButtons::TOUCH_ACTIVE),
+        );
+        buttons.set(
+            PointerButtons::SECONDARY,
+            state
+                .active_buttons
+                .contains(AbsolutePointerButtons::ALT_ACTIVE),
+        );
+        self.change(moved, buttons)
+    }
+
+    /// Reads the state of a relative pointer device and applies it, returning
+    /// `None` if it did not change.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if there was an issue with the pointer device.
+    pub fn poll(&mut self, pointer: &mut Pointer) -> Result<Option<PointerChange>> {
+        let state = pointer.read_state()?.log();
+        Ok(state
+            .map(|state| self.update(pointer.mode(), &state))
+            .into())
+    }
+
+    /// Reads the state of an absolute pointer device and applies it,
+    /// returning `None` if it did not change.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if there was an issue with the pointer device.
+    pub fn poll_absolute(
+        &mut self,
+        pointer: &mut AbsolutePointer,
+    ) -> Result<Option<PointerChange>> {
+        let state = pointer.read_state()?.log();
+        Ok(state
+            .map(|state| self.update_absolute(pointer.mode(), &state))
+            .into())
+    }
+
+    /// Converts a movement in device counts to pixels, returning the pixels
+    /// and the new remainder
+    fn scale(&self, counts: i32, resolution: u64, remainder: i64) -> (i64, i64) {
+        if resolution == 0 {
+            return (i64::from(counts) * self.pixels_per_mm, 0);
+        }
+        // Resolutions larger than this are not realistic, and would overflow
+        let resolution = resolution.min(1 << 31) as i64;
+        let total = i64::from(counts) * self.pixels_per_mm + remainder;
+        (total / resolution, total % resolution)
+    }
+
+    /// Moves the cursor, returning whether its position changed
+    fn move_to(&mut self, x: usize, y: usize) -> bool {
+        let position = self.clamp(x, y);
+        let moved = position != self.position;
+        self.position = position;
+        moved
+    }
+
+    fn clamp(&self, x: usize, y: usize) -> (usize, usize) {
+        (
+            x.min(self.bounds.0.saturating_sub(1)),
+            y.min(self.bounds.1.saturating_sub(1)),
+        )
+    }
+
+    /// Records the new state of the buttons
+    fn change(&mut self, moved: bool, buttons: PointerButtons) -> PointerChange {
+        let change = PointerChange {
+            moved,
+            pressed: buttons - self.buttons,
+            released: self.buttons - buttons,
+        };
+        self.buttons = buttons;
+        change
+    }
+}
+
+/// Adds a signed offset to a coordinate, saturating at 0
+fn offset(coordinate: usize, delta: i64) -> usize {
+    if delta < 0 {
+        coordinate.saturating_sub((-delta) as usize)
+    } else {
+        coordinate.saturating_add(delta as usize)
+    }
+}
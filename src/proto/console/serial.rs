@@ -1,6 +1,8 @@
 //! Abstraction over byte stream devices, also known as serial I/O devices.
 
+use crate::io;
 use crate::proto::Protocol;
+use crate::result::Error;
 use crate::{unsafe_guid, Result, Status};
 use bitflags::bitflags;
 
@@ -35,7 +37,19 @@ pub struct Serial<'boot> {
     io_mode: &'boot IoMode,
 }
 
+/// Revision 1.0 of the Serial I/O protocol.
+pub const SERIAL_IO_PROTOCOL_REVISION: u32 = 0x0001_0000;
+
+/// Revision 1.1 of the Serial I/O protocol, which adds the `device_type_guid`
+/// field and the Serial Terminal device type.
+pub const SERIAL_IO_PROTOCOL_REVISION1P1: u32 = 0x0001_0001;
+
 impl<'boot> Serial<'boot> {
+    /// Revision of the protocol implemented by the device.
+    pub fn revision(&self) -> u32 {
+        self.revision
+    }
+
     /// Reset the device.
     pub fn reset(&mut self) -> Result {
         (self.reset)(self).into()
@@ -72,6 +86,18 @@ impl<'boot> Serial<'boot> {
         .into()
     }
 
+    /// Sets the number of microseconds to wait before a read or write
+    /// operation times out, keeping the other attributes of the device.
+    ///
+    /// A timeout of 0 selects the device's default timeout.
+    pub fn set_timeout(&mut self, timeout: u32) -> Result {
+        let mode = IoMode {
+            timeout,
+            ..*self.io_mode()
+        };
+        self.set_attributes(&mode)
+    }
+
     /// Retrieve the device's current control bits.
     pub fn get_control_bits(&self) -> Result<ControlBits> {
         let mut bits = ControlBits::empty();
@@ -99,6 +125,30 @@ impl<'boot> Serial<'boot> {
         )
     }
 
+    /// Reads data from this device, waiting at most `timeout` microseconds for
+    /// each character.
+    ///
+    /// The timeout of the device is restored afterwards. As with `read()`, a
+    /// `Status::TIMEOUT` error indicates how many bytes were read before the
+    /// device stopped sending data. If the read succeeded but the timeout
+    /// could not be restored, the error indicates that `data` was filled.
+    pub fn read_with_timeout(&mut self, data: &mut [u8], timeout: u32) -> Result<(), usize> {
+        // Nothing has been read yet when changing the attributes fails
+        let nothing_read = |error: Error| Error::new(error.status(), 0);
+        let old_timeout = self.io_mode().timeout;
+        let status = self.set_timeout(timeout).map_err(nothing_read)?.status();
+        let result = self.read(data);
+        let restored = self.set_timeout(old_timeout);
+        match (result, restored) {
+            (Ok(completion), Ok(restored)) => Ok(completion
+                .with_status(status)
+                .with_status(restored.status())),
+            (Ok(_), Err(error)) => Err(Error::new(error.status(), data.len())),
+            // The read error is more relevant to the caller than the restore one
+            (Err(error), _) => Err(error),
+        }
+    }
+
     /// Writes data to this device.
     ///
     /// This operation will block until the data has been fully written or an
@@ -113,6 +163,42 @@ impl<'boot> Serial<'boot> {
     }
 }
 
+/// Reads return the data received before a timeout, and only fail with
+/// `Status::TIMEOUT` if no data at all was received.
+impl io::Read for Serial<'_> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        match Serial::read(self, buffer) {
+            Ok(completion) => {
+                completion.log();
+                Ok(buffer.len())
+            }
+            Err(error) => match error.split() {
+                (Status::TIMEOUT, read) if read > 0 => Ok(read),
+                (status, _) => Err(status),
+            },
+        }
+    }
+}
+
+impl io::Write for Serial<'_> {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        match Serial::write(self, buffer) {
+            Ok(completion) => {
+                completion.log();
+                Ok(buffer.len())
+            }
+            Err(error) => match error.split() {
+                (Status::TIMEOUT, written) if written > 0 => Ok(written),
+                (status, _) => Err(status),
+            },
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 /// Structure representing the device's current parameters.
 ///
 /// The default values for all UART-like devices is:
@@ -0,0 +1,288 @@
+use super::output::{Color, Output};
+use crate::ResultExt;
+use core::fmt;
+
+/// Maximal number of parameters of an escape sequence, extra ones are ignored.
+const MAX_PARAMS: usize = 8;
+
+/// UEFI colors matching the ANSI colors 0-15: black, red, green, yellow,
+/// blue, magenta, cyan and white, followed by their bright variants.
+const ANSI_COLORS: [Color; 16] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Brown,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::LightGray,
+    Color::DarkGray,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::Yellow,
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::White,
+];
+
+/// Default ANSI foreground color, white (displayed as light gray).
+const DEFAULT_FOREGROUND: u8 = 7;
+
+/// Default ANSI background color, black.
+const DEFAULT_BACKGROUND: u8 = 0;
+
+/// Text output which interprets ANSI (VT100) escape sequences.
+///
+/// Text written with the `fmt::Write` trait is forwarded to the underlying
+/// `Output`, except for escape sequences, which are translated to the
+/// equivalent calls to `set_color()`, `set_cursor_position()`, `clear()` and
+/// `enable_cursor()`. This lets code written for terminals, such as colored
+/// loggers, work on the UEFI console.
+///
+/// The following control sequences are supported, all others are discarded:
+///
+/// - `ESC [ n m`: colors, including bold (bright) and bright colors
+/// - `ESC [ n A/B/C/D`: cursor up/down/forward/back
+/// - `ESC [ row ; column H` or `f`: cursor position
+/// - `ESC [ n G`: cursor column
+/// - `ESC [ 2 J`: clear screen (without moving the cursor)
+/// - `ESC [ n K`: erase in line
+/// - `ESC [ s` and `ESC [ u`: save and restore cursor position
+/// - `ESC [ ? 25 h/l`: show/hide cursor
+pub struct AnsiOutput<'out, 'boot> {
+    output: &'out mut Output<'boot>,
+    state: State,
+    params: [usize; MAX_PARAMS],
+    param_count: usize,
+    private: bool,
+    foreground: u8,
+    background: u8,
+    bold: bool,
+    saved_cursor: (usize, usize),
+}
+
+/// State of the escape sequence parser
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum State {
+    /// Printing text
+    Text,
+    /// After an ESC character
+    Escape,
+    /// Inside a control sequence, after `ESC [`
+    Csi,
+}
+
+impl<'out, 'boot> AnsiOutput<'out, 'boot> {
+    /// Interpret escape sequences in the text written to `output`.
+    ///
+    /// Colors are assumed to be the defaults (light gray on black) until
+    /// they are changed by an escape sequence.
+    pub fn new(output: &'out mut Output<'boot>) -> Self {
+        Self {
+            output,
+            state: State::Text,
+            params: [0; MAX_PARAMS],
+            param_count: 0,
+            private: false,
+            foreground: DEFAULT_FOREGROUND,
+            background: DEFAULT_BACKGROUND,
+            bold: false,
+            saved_cursor: (0, 0),
+        }
+    }
+
+    /// Access the underlying output device.
+    pub fn output(&mut self) -> &mut Output<'boot> {
+        self.output
+    }
+
+    /// Process one character of an escape sequence
+    fn escape_char(&mut self, c: char) -> fmt::Result {
+        match (self.state, c) {
+            (State::Escape, '[') => {
+                self.state = State::Csi;
+                self.params = [0; MAX_PARAMS];
+                self.param_count = 0;
+                self.private = false;
+            }
+            (State::Escape, _) => self.state = State::Text,
+            (State::Csi, '0'..='9') => {
+                if self.param_count == 0 {
+                    self.param_count = 1;
+                }
+                if let Some(param) = self.params.get_mut(self.param_count - 1) {
+                    let digit = c as usize - '0' as usize;
+                    *param = param.saturating_mul(10).saturating_add(digit);
+                }
+            }
+            (State::Csi, ';') => {
+                // An empty first parameter is still a parameter
+                self.param_count = self.param_count.max(1) + 1;
+            }
+            (State::Csi, '?') => self.private = true,
+            (State::Csi, '\u{40}'..='\u{7e}') => {
+                self.state = State::Text;
+                self.execute(c)?;
+            }
+            // Intermediate bytes are not supported, but must be skipped
+            (State::Csi, '\u{20}'..='\u{3f}') => {}
+            (State::Csi, _) => self.state = State::Text,
+            (State::Text, _) => unreachable!(),
+        }
+        Ok(())
+    }
+
+    /// Returns the i-th parameter of the sequence, or `default` if it is
+    /// missing or zero
+    fn param(&self, index: usize, default: usize) -> usize {
+        match self.params.get(index) {
+            Some(&value) if index < self.param_count && value != 0 => value,
+            _ => default,
+        }
+    }
+
+    /// Execute a complete control sequence
+    fn execute(&mut self, command: char) -> fmt::Result {
+        let (column, row) = self.output.cursor_position();
+        let (columns, rows) = self.dims();
+        let n = self.param(0, 1);
+        match (self.private, command) {
+            (true, 'h') | (true, 'l') => {
+                if self.param(0, 0) == 25 {
+                    // Not all devices can hide the cursor
+                    let _ = self.output.enable_cursor(command == 'h');
+                }
+                Ok(())
+            }
+            (true, _) => Ok(()),
+            (false, 'm') => self.select_graphic_rendition(),
+            (false, 'A') => self.move_cursor(column, row.saturating_sub(n)),
+            (false, 'B') => self.move_cursor(column, row.saturating_add(n)),
+            (false, 'C') => self.move_cursor(column.saturating_add(n), row),
+            (false, 'D') => self.move_cursor(column.saturating_sub(n), row),
+            (false, 'G') => self.move_cursor(n - 1, row),
+            (false, 'H') | (false, 'f') => self.move_cursor(self.param(1, 1) - 1, n - 1),
+            (false, 'J') => match self.param(0, 0) {
+                2 | 3 => {
+                    self.output
+                        .clear()
+                        .warning_as_error()
+                        .map_err(|_| fmt::Error)?;
+                    self.move_cursor(column, row)
+                }
+                _ => Ok(()),
+            },
+            (false, 'K') => {
+                let (start, end) = match self.param(0, 0) {
+                    0 => (column, columns),
+                    1 => (0, column + 1),
+                    2 => (0, columns),
+                    _ => return Ok(()),
+                };
+                // Writing to the bottom-right cell would scroll the screen
+                let end = if row + 1 == rows {
+                    end.min(columns.saturating_sub(1))
+                } else {
+                    end.min(columns)
+                };
+                self.move_cursor(start, row)?;
+                for _ in start..end {
+                    fmt::Write::write_str(self.output, " ")?;
+                }
+                self.move_cursor(column, row)
+            }
+            (false, 's') => {
+                self.saved_cursor = (column, row);
+                Ok(())
+            }
+            (false, 'u') => {
+                let (column, row) = self.saved_cursor;
+                self.move_cursor(column, row)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Handle an SGR (colors) control sequence
+    fn select_graphic_rendition(&mut self) -> fmt::Result {
+        for index in 0..self.param_count.clamp(1, MAX_PARAMS) {
+            match self.params[index] {
+                0 => {
+                    self.foreground = DEFAULT_FOREGROUND;
+                    self.background = DEFAULT_BACKGROUND;
+                    self.bold = false;
+                }
+                1 => self.bold = true,
+                22 => self.bold = false,
+                value @ 30..=37 => self.foreground = (value - 30) as u8,
+                39 => self.foreground = DEFAULT_FOREGROUND,
+                value @ 40..=47 => self.background = (value - 40) as u8,
+                49 => self.background = DEFAULT_BACKGROUND,
+                value @ 90..=97 => self.foreground = (value - 90 + 8) as u8,
+                // UEFI does not support bright background colors
+                value @ 100..=107 => self.background = (value - 100) as u8,
+                _ => {}
+            }
+        }
+
+        let foreground = if self.bold && self.foreground < 8 {
+            self.foreground + 8
+        } else {
+            self.foreground
+        };
+        self.output
+            .set_color(
+                ANSI_COLORS[usize::from(foreground)],
+                ANSI_COLORS[usize::from(self.background)],
+            )
+            .warning_as_error()
+            .map_err(|_| fmt::Error)
+    }
+
+    /// Move the cursor, clamping the position to the screen
+    fn move_cursor(&mut self, column: usize, row: usize) -> fmt::Result {
+        let (columns, rows) = self.dims();
+        self.output
+            .set_cursor_position(
+                column.min(columns.saturating_sub(1)),
+                row.min(rows.saturating_sub(1)),
+            )
+            .warning_as_error()
+            .map_err(|_| fmt::Error)
+    }
+
+    /// Returns the (columns, rows) of the current mode
+    fn dims(&self) -> (usize, usize) {
+        match self.output.current_mode() {
+            Ok(mode) => match mode.log() {
+                Some(mode) => (mode.columns(), mode.rows()),
+                None => (80, 25),
+            },
+            Err(_) => (80, 25),
+        }
+    }
+}
+
+impl fmt::Write for AnsiOutput<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut text_start = 0;
+        for (index, c) in s.char_indices() {
+            match self.state {
+                State::Text if c == '\u{1b}' => {
+                    if text_start != index {
+                        self.output.write_str(&s[text_start..index])?;
+                    }
+                    self.state = State::Escape;
+                }
+                State::Text => continue,
+                _ => self.escape_char(c)?,
+            }
+            text_start = index + c.len_utf8();
+        }
+        if self.state == State::Text && text_start != s.len() {
+            self.output.write_str(&s[text_start..])?;
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,8 @@
 use crate::proto::Protocol;
-use crate::{unsafe_guid, Char16, Event, Result, Status};
+use crate::table::boot::{BootServices, EventType, TimerTrigger, Tpl};
+use crate::{unsafe_guid, Char16, Event, Result, ResultExt, Status};
 use core::mem::MaybeUninit;
+use core::time::Duration;
 
 /// Interface for text-based input devices.
 #[repr(C)]
@@ -42,6 +44,59 @@ impl Input {
         }
     }
 
+    /// Waits for a key to be pressed for at most `timeout`, and returns it,
+    /// or `None` if no key was pressed in time.
+    ///
+    /// A key which is already available is returned immediately.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if there was an issue with the input device
+    /// - `OutOfResources` if the timer event could not be created
+    pub fn read_key_timeout(
+        &mut self,
+        bt: &BootServices,
+        timeout: Duration,
+    ) -> Result<Option<Key>> {
+        if let Some(key) = self.read_key()?.log() {
+            return Ok(Some(key).into());
+        }
+
+        let timer = unsafe { bt.create_event(EventType::TIMER, Tpl::APPLICATION, None) }?.log();
+        let result = self.wait_key_or_timer(bt, timer, timeout);
+        unsafe { bt.close_event(timer) }?.log();
+        result
+    }
+
+    /// Waits until a key is read or `timer` expires
+    fn wait_key_or_timer(
+        &mut self,
+        bt: &BootServices,
+        timer: Event,
+        timeout: Duration,
+    ) -> Result<Option<Key>> {
+        // The timer counts in units of 100ns
+        let ticks = timeout.as_nanos() / 100;
+        let ticks = if ticks > u128::from(u64::MAX) {
+            u64::MAX
+        } else {
+            ticks as u64
+        };
+        bt.set_timer(timer, TimerTrigger::Relative(ticks))?.log();
+
+        let mut events = [self.wait_for_key, timer];
+        loop {
+            let index = bt.wait_for_event(&mut events).discard_errdata()?.log();
+            if index != 0 {
+                return Ok(None.into());
+            }
+            // Another reader may have taken the key in the meantime
+            if let Some(key) = self.read_key()?.log() {
+                return Ok(Some(key).into());
+            }
+        }
+    }
+
     /// Event to be used with `BootServices::wait_for_event()` in order to wait
     /// for a key to be available
     pub fn wait_for_key_event(&self) -> Event {
@@ -70,6 +125,7 @@ impl From<RawKey> for Key {
 }
 
 /// A key read from the console (UEFI version)
+#[derive(Debug, Copy, Clone)]
 #[repr(C)]
 pub struct RawKey {
     /// The key's scan code.
@@ -0,0 +1,260 @@
+use super::input::{Key, RawKey, ScanCode};
+use crate::data_types::chars::NUL_16;
+use crate::proto::Protocol;
+use crate::{unsafe_guid, Event, Result, Status};
+use bitflags::bitflags;
+use core::ffi::c_void;
+use core::mem::MaybeUninit;
+use core::ptr;
+
+/// Extended interface for text-based input devices.
+///
+/// Unlike `Input`, this protocol reports the state of the modifier keys
+/// (Shift, Control, Alt...) and of the toggle keys (Caps Lock, Num Lock...),
+/// and allows registering functions which are called when a given key
+/// combination is pressed.
+#[repr(C)]
+#[unsafe_guid("dd9e7534-7762-4698-8c14-f58517a625aa")]
+#[derive(Protocol)]
+pub struct InputEx {
+    reset: extern "efiapi" fn(this: &mut InputEx, extended: bool) -> Status,
+    read_key_stroke_ex: extern "efiapi" fn(this: &mut InputEx, key_data: *mut KeyData) -> Status,
+    wait_for_key_ex: Event,
+    set_state: extern "efiapi" fn(this: &mut InputEx, key_toggle_state: &KeyToggleState) -> Status,
+    register_key_notify: extern "efiapi" fn(
+        this: &mut InputEx,
+        key_data: &KeyData,
+        key_notification_function: KeyNotifyFn,
+        notify_handle: &mut *mut c_void,
+    ) -> Status,
+    unregister_key_notify:
+        extern "efiapi" fn(this: &mut InputEx, notification_handle: *mut c_void) -> Status,
+}
+
+/// Function called when a registered key combination is pressed.
+///
+/// It receives the key that was pressed, along with the state of the
+/// modifier and toggle keys, and is called at `Tpl::NOTIFY` or below.
+pub type KeyNotifyFn = extern "efiapi" fn(key_data: &KeyData) -> Status;
+
+impl InputEx {
+    /// Resets the input device hardware.
+    ///
+    /// The `extended_verification` parameter is used to request that UEFI
+    /// performs an extended check and reset of the input device.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if the device is malfunctioning and cannot be reset.
+    pub fn reset(&mut self, extended_verification: bool) -> Result {
+        (self.reset)(self, extended_verification).into()
+    }
+
+    /// Reads the next keystroke from the input device, if any, along with the
+    /// state of the modifier and toggle keys.
+    ///
+    /// Use `wait_for_key_event()` with the `BootServices::wait_for_event()`
+    /// interface in order to wait for a key to be pressed.
+    ///
+    /// If the `KEY_STATE_EXPOSED` toggle state was enabled with `set_state()`,
+    /// pressing a modifier key on its own produces a keystroke with a null
+    /// scan code and character.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if there was an issue with the input device
+    /// - `Unsupported` if the device does not support this operation
+    pub fn read_key_stroke_ex(&mut self) -> Result<Option<KeyData>> {
+        let mut key_data = MaybeUninit::<KeyData>::uninit();
+
+        match (self.read_key_stroke_ex)(self, key_data.as_mut_ptr()) {
+            Status::NOT_READY => Ok(None.into()),
+            other => other.into_with_val(|| Some(unsafe { key_data.assume_init() })),
+        }
+    }
+
+    /// Event to be used with `BootServices::wait_for_event()` in order to wait
+    /// for a key to be available
+    pub fn wait_for_key_event(&self) -> Event {
+        self.wait_for_key_ex
+    }
+
+    /// Sets the state of the toggle keys, such as Caps Lock and Num Lock.
+    ///
+    /// `TOGGLE_STATE_VALID` is always set in the state passed to the device.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if there was an issue with the input device
+    /// - `Unsupported` if the device does not support setting this state
+    pub fn set_state(&mut self, toggle_state: KeyToggleState) -> Result {
+        let toggle_state = toggle_state | KeyToggleState::TOGGLE_STATE_VALID;
+        (self.set_state)(self, &toggle_state).into()
+    }
+
+    /// Registers a function to be called when the key combination described
+    /// by `key_data` is pressed.
+    ///
+    /// Bits of the modifier and toggle state of `key_data` which are not set
+    /// are ignored when matching keystrokes, as are the states themselves if
+    /// their `*_VALID` bit is clear.
+    ///
+    /// The returned handle must be passed to `unregister_key_notify()` before
+    /// the function can no longer be called, for example because the image
+    /// containing it is unloaded.
+    ///
+    /// # Errors
+    ///
+    /// - `OutOfResources` if the registration could not be allocated
+    pub fn register_key_notify(
+        &mut self,
+        key_data: &KeyData,
+        notify: KeyNotifyFn,
+    ) -> Result<KeyNotifyHandle> {
+        let mut handle = ptr::null_mut();
+        (self.register_key_notify)(self, key_data, notify, &mut handle)
+            .into_with_val(|| KeyNotifyHandle(handle))
+    }
+
+    /// Removes a key notification registered with `register_key_notify()`.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidParameter` if the handle is not a valid registration
+    pub fn unregister_key_notify(&mut self, handle: KeyNotifyHandle) -> Result {
+        (self.unregister_key_notify)(self, handle.0).into()
+    }
+}
+
+/// Handle identifying a key notification registered on an `InputEx` device.
+#[derive(Debug)]
+pub struct KeyNotifyHandle(*mut c_void);
+
+/// A keystroke along with the state of the modifier and toggle keys.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct KeyData {
+    key: RawKey,
+    key_state: KeyState,
+}
+
+impl KeyData {
+    /// Describe a key combination, to be passed to `register_key_notify()`.
+    pub fn new(key: Key, shift_state: KeyShiftState, toggle_state: KeyToggleState) -> Self {
+        let key = match key {
+            Key::Printable(unicode_char) => RawKey {
+                scan_code: ScanCode::NULL,
+                unicode_char,
+            },
+            Key::Special(scan_code) => RawKey {
+                scan_code,
+                unicode_char: NUL_16,
+            },
+        };
+        Self {
+            key,
+            key_state: KeyState {
+                key_shift_state: shift_state,
+                key_toggle_state: toggle_state,
+            },
+        }
+    }
+
+    /// The key that was pressed.
+    pub fn key(&self) -> Key {
+        self.key.into()
+    }
+
+    /// The state of the modifier keys, or an empty set if the device does not
+    /// report it.
+    pub fn shift_state(&self) -> KeyShiftState {
+        let state = self.key_state.key_shift_state;
+        if state.contains(KeyShiftState::SHIFT_STATE_VALID) {
+            state
+        } else {
+            KeyShiftState::empty()
+        }
+    }
+
+    /// The state of the toggle keys, or an empty set if the device does not
+    /// report it.
+    pub fn toggle_state(&self) -> KeyToggleState {
+        let state = self.key_state.key_toggle_state;
+        if state.contains(KeyToggleState::TOGGLE_STATE_VALID) {
+            state
+        } else {
+            KeyToggleState::empty()
+        }
+    }
+
+    /// True if either Control key was held when the key was pressed.
+    pub fn is_ctrl_pressed(&self) -> bool {
+        self.shift_state()
+            .intersects(KeyShiftState::LEFT_CONTROL | KeyShiftState::RIGHT_CONTROL)
+    }
+
+    /// True if either Alt key was held when the key was pressed.
+    pub fn is_alt_pressed(&self) -> bool {
+        self.shift_state()
+            .intersects(KeyShiftState::LEFT_ALT | KeyShiftState::RIGHT_ALT)
+    }
+
+    /// True if either Shift key was held when the key was pressed.
+    pub fn is_shift_pressed(&self) -> bool {
+        self.shift_state()
+            .intersects(KeyShiftState::LEFT_SHIFT | KeyShiftState::RIGHT_SHIFT)
+    }
+}
+
+/// State of the modifier and toggle keys.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+struct KeyState {
+    key_shift_state: KeyShiftState,
+    key_toggle_state: KeyToggleState,
+}
+
+bitflags! {
+    /// State of the modifier keys.
+    pub struct KeyShiftState: u32 {
+        /// The other bits of the state are valid.
+        const SHIFT_STATE_VALID = 0x8000_0000;
+        /// Right Shift key
+        const RIGHT_SHIFT = 0x0000_0001;
+        /// Left Shift key
+        const LEFT_SHIFT = 0x0000_0002;
+        /// Right Control key
+        const RIGHT_CONTROL = 0x0000_0004;
+        /// Left Control key
+        const LEFT_CONTROL = 0x0000_0008;
+        /// Right Alt key
+        const RIGHT_ALT = 0x0000_0010;
+        /// Left Alt key
+        const LEFT_ALT = 0x0000_0020;
+        /// Right Logo (Windows, Command...) key
+        const RIGHT_LOGO = 0x0000_0040;
+        /// Left Logo (Windows, Command...) key
+        const LEFT_LOGO = 0x0000_0080;
+        /// Menu key
+        const MENU_KEY = 0x0000_0100;
+        /// System Request key
+        const SYS_REQ = 0x0000_0200;
+    }
+}
+
+bitflags! {
+    /// State of the toggle keys.
+    pub struct KeyToggleState: u8 {
+        /// The other bits of the state are valid.
+        const TOGGLE_STATE_VALID = 0x80;
+        /// Report keystrokes of modifier keys pressed on their own, and
+        /// keystrokes which would otherwise be dropped.
+        const KEY_STATE_EXPOSED = 0x40;
+        /// Scroll Lock is active
+        const SCROLL_LOCK_ACTIVE = 0x01;
+        /// Num Lock is active
+        const NUM_LOCK_ACTIVE = 0x02;
+        /// Caps Lock is active
+        const CAPS_LOCK_ACTIVE = 0x04;
+    }
+}
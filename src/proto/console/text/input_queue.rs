@@ -0,0 +1,138 @@
+use super::{Input, Key};
+use crate::{Event, Result, ResultExt};
+
+/// Number of keys which can be buffered by an `InputQueue`.
+pub const INPUT_QUEUE_CAPACITY: usize = 32;
+
+/// Non-blocking buffered keyboard input.
+///
+/// The queue moves the keystrokes available on an `Input` device to its own
+/// buffer whenever it is accessed, so that render loops can poll for input
+/// without blocking, and process all the keys pressed since the last frame at
+/// once with `drain()`.
+///
+/// The event returned by `wait_for_key_event()` can be added to the events
+/// waited on by such a loop, to wake it up when a key is pressed.
+pub struct InputQueue<'a> {
+    input: &'a mut Input,
+    keys: [Key; INPUT_QUEUE_CAPACITY],
+    head: usize,
+    len: usize,
+}
+
+impl<'a> InputQueue<'a> {
+    /// Buffer the keystrokes of `input`.
+    pub fn new(input: &'a mut Input) -> Self {
+        Self {
+            input,
+            keys: [Key::Printable(Default::default()); INPUT_QUEUE_CAPACITY],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// Access the underlying input device.
+    pub fn input(&mut self) -> &mut Input {
+        self.input
+    }
+
+    /// Event to be used with `BootServices::wait_for_event()` in order to wait
+    /// for a key to be available on the device.
+    ///
+    /// Keys which are already buffered do not signal this event, so
+    /// `is_empty()` should be checked before waiting on it.
+    pub fn wait_for_key_event(&self) -> Event {
+        self.input.wait_for_key_event()
+    }
+
+    /// Returns the number of buffered keys.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// True if no key is buffered.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Moves the keys available on the device to the buffer, until it is
+    /// full.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if there was an issue with the input device
+    pub fn poll(&mut self) -> Result {
+        while self.len < INPUT_QUEUE_CAPACITY {
+            match self.input.read_key().warning_as_error()? {
+                Some(key) => {
+                    self.keys[(self.head + self.len) % INPUT_QUEUE_CAPACITY] = key;
+                    self.len += 1;
+                }
+                None => break,
+            }
+        }
+        Ok(().into())
+    }
+
+    /// Returns the oldest key pressed, if any.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if there was an issue with the input device
+    pub fn try_read_key(&mut self) -> Result<Option<Key>> {
+        self.poll()?.log();
+        Ok(self.pop().into())
+    }
+
+    /// Returns an iterator over all the keys pressed, oldest first, which
+    /// removes them from the buffer.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if there was an issue with the input device
+    pub fn drain(&mut self) -> Result<Drain<'_, 'a>> {
+        self.poll()?.log();
+        Ok(Drain { queue: self }.into())
+    }
+
+    /// Discards the buffered keys, and those available on the device.
+    pub fn clear(&mut self) -> Result {
+        loop {
+            self.head = 0;
+            self.len = 0;
+            self.poll()?.log();
+            if self.is_empty() {
+                return Ok(().into());
+            }
+        }
+    }
+
+    fn pop(&mut self) -> Option<Key> {
+        if self.len == 0 {
+            return None;
+        }
+        let key = self.keys[self.head];
+        self.head = (self.head + 1) % INPUT_QUEUE_CAPACITY;
+        self.len -= 1;
+        Some(key)
+    }
+}
+
+/// Iterator over the keys of an `InputQueue`, returned by `drain()`.
+pub struct Drain<'queue, 'input> {
+    queue: &'queue mut InputQueue<'input>,
+}
+
+impl Iterator for Drain<'_, '_> {
+    type Item = Key;
+
+    fn next(&mut self) -> Option<Key> {
+        self.queue.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.queue.len, Some(self.queue.len))
+    }
+}
+
+impl ExactSizeIterator for Drain<'_, '_> {}
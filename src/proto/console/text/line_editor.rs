@@ -0,0 +1,319 @@
+use super::{Input, Key, Output, ScanCode};
+use crate::table::boot::BootServices;
+use crate::{Result, ResultExt, Status};
+use alloc_api::boxed::Box;
+use alloc_api::string::String;
+use alloc_api::vec::Vec;
+use core::fmt::Write;
+
+/// Default number of lines kept in the history.
+const DEFAULT_HISTORY_SIZE: usize = 32;
+
+/// Function returning the possible completions of the text before the cursor.
+pub type Completer = Box<dyn FnMut(&str) -> Vec<String>>;
+
+/// Interactive line input, with editing and history.
+///
+/// `read_line()` echoes the keys typed on the `Input` device to the `Output`
+/// device, and supports the following keys:
+///
+/// - Left/Right, Home/End: move the cursor
+/// - Backspace/Delete: delete the character before/under the cursor
+/// - Up/Down: recall the previous/next line of the history
+/// - Tab: complete the text before the cursor, if a completer is set
+/// - Enter: accept the line
+/// - Escape: cancel the input
+pub struct LineEditor {
+    history: Vec<String>,
+    history_size: usize,
+    completer: Option<Completer>,
+}
+
+impl Default for LineEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LineEditor {
+    /// Create a line editor with an empty history.
+    pub fn new() -> Self {
+        Self {
+            history: Vec::new(),
+            history_size: DEFAULT_HISTORY_SIZE,
+            completer: None,
+        }
+    }
+
+    /// Sets the maximal number of lines kept in the history.
+    pub fn set_history_size(&mut self, size: usize) {
+        self.history_size = size;
+        self.truncate_history();
+    }
+
+    /// Lines previously read, oldest first.
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    /// Adds a line to the history, as if it had been read.
+    pub fn add_history(&mut self, line: &str) {
+        if line.is_empty() || self.history.last().map(String::as_str) == Some(line) {
+            return;
+        }
+        self.history.push(line.into());
+        self.truncate_history();
+    }
+
+    /// Sets the function used to complete the text before the cursor when
+    /// Tab is pressed.
+    ///
+    /// It returns the candidates replacing this text. A single candidate is
+    /// inserted directly. Otherwise, the longest prefix common to all
+    /// candidates is inserted, and the candidates are listed on the console.
+    pub fn set_completer(&mut self, completer: Completer) {
+        self.completer = Some(completer);
+    }
+
+    /// Print `prompt` and read a line, returning `None` if it was cancelled
+    /// by pressing Escape.
+    ///
+    /// Non-empty lines are added to the history.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if there was an issue with the input or output device
+    pub fn read_line(
+        &mut self,
+        bt: &BootServices,
+        input: &mut Input,
+        output: &mut Output,
+        prompt: &str,
+    ) -> Result<Option<String>> {
+        let mut state = EditState::new(output, prompt)?;
+        // Index of the recalled history line, equal to the history length
+        // while editing a new line
+        let mut history_index = self.history.len();
+        let mut new_line = Vec::new();
+
+        loop {
+            let key = read_key(bt, input)?;
+            match key {
+                Key::Printable(c) => match u16::from(c) {
+                    0x0d => break,
+                    0x08 => {
+                        if state.cursor > 0 {
+                            state.cursor -= 1;
+                            state.line.remove(state.cursor);
+                        }
+                    }
+                    0x09 => self.complete(&mut state, output, prompt)?,
+                    0x00 => {}
+                    _ => {
+                        state.line.insert(state.cursor, char::from(c));
+                        state.cursor += 1;
+                    }
+                },
+                Key::Special(ScanCode::ESCAPE) => {
+                    state.finish(output)?;
+                    return Ok(None.into());
+                }
+                Key::Special(ScanCode::LEFT) => state.cursor = state.cursor.saturating_sub(1),
+                Key::Special(ScanCode::RIGHT) => {
+                    state.cursor = (state.cursor + 1).min(state.line.len())
+                }
+                Key::Special(ScanCode::HOME) => state.cursor = 0,
+                Key::Special(ScanCode::END) => state.cursor = state.line.len(),
+                Key::Special(ScanCode::DELETE) => {
+                    if state.cursor < state.line.len() {
+                        state.line.remove(state.cursor);
+                    }
+                }
+                Key::Special(ScanCode::UP) if history_index > 0 => {
+                    if history_index == self.history.len() {
+                        new_line = state.line.clone();
+                    }
+                    history_index -= 1;
+                    state.set_line(self.history[history_index].chars().collect());
+                }
+                Key::Special(ScanCode::DOWN) if history_index < self.history.len() => {
+                    history_index += 1;
+                    let line = match self.history.get(history_index) {
+                        Some(line) => line.chars().collect(),
+                        None => new_line.clone(),
+                    };
+                    state.set_line(line);
+                }
+                Key::Special(_) => {}
+            }
+            state.redraw(output)?;
+        }
+
+        state.finish(output)?;
+        let line: String = state.line.into_iter().collect();
+        self.add_history(&line);
+        Ok(Some(line).into())
+    }
+
+    /// Complete the text before the cursor
+    fn complete(
+        &mut self,
+        state: &mut EditState,
+        output: &mut Output,
+        prompt: &str,
+    ) -> core::result::Result<(), Status> {
+        let completer = match &mut self.completer {
+            Some(completer) => completer,
+            None => return Ok(()),
+        };
+        let before: String = state.line[..state.cursor].iter().collect();
+        let candidates = completer(&before);
+        let replacement: Vec<char> = match candidates.as_slice() {
+            [] => return Ok(()),
+            [candidate] => candidate.chars().collect(),
+            [first, rest @ ..] => {
+                let mut prefix: Vec<char> = first.chars().collect();
+                for candidate in rest {
+                    let common = prefix
+                        .iter()
+                        .zip(candidate.chars())
+                        .take_while(|(a, b)| **a == *b)
+                        .count();
+                    prefix.truncate(common);
+                }
+
+                // List the candidates below the line, then start over
+                state.finish(output)?;
+                for candidate in &candidates {
+                    write_str(output, candidate)?;
+                    write_str(output, "  ")?;
+                }
+                write_str(output, "\n")?;
+                let line = core::mem::take(&mut state.line);
+                let cursor = state.cursor;
+                *state = EditState::new(output, prompt)?;
+                state.line = line;
+                state.cursor = cursor;
+
+                if prefix.len() < state.cursor {
+                    return Ok(());
+                }
+                prefix
+            }
+        };
+        let after = state.line.split_off(state.cursor);
+        state.line = replacement;
+        state.cursor = state.line.len();
+        state.line.extend(after);
+        Ok(())
+    }
+
+    fn truncate_history(&mut self) {
+        if self.history.len() > self.history_size {
+            let excess = self.history.len() - self.history_size;
+            self.history.drain(..excess);
+        }
+    }
+}
+
+/// Line being edited, and its position on the screen
+struct EditState {
+    line: Vec<char>,
+    cursor: usize,
+    /// Screen position of the first character of the line
+    start: (usize, usize),
+    /// Number of characters currently displayed
+    displayed: usize,
+    columns: usize,
+}
+
+impl EditState {
+    /// Print the prompt, and start editing an empty line after it
+    fn new(output: &mut Output, prompt: &str) -> core::result::Result<Self, Status> {
+        write_str(output, prompt)?;
+        let columns = match output
+            .current_mode()
+            .log_warning()
+            .map_err(|e| e.status())?
+        {
+            Some(mode) => mode.columns(),
+            None => 80,
+        };
+        Ok(Self {
+            line: Vec::new(),
+            cursor: 0,
+            start: output.cursor_position(),
+            displayed: 0,
+            columns,
+        })
+    }
+
+    fn set_line(&mut self, line: Vec<char>) {
+        self.cursor = line.len();
+        self.line = line;
+    }
+
+    /// Returns the screen position of the i-th character of the line
+    fn position(&self, index: usize) -> (usize, usize) {
+        let offset = self.start.0 + index;
+        (offset % self.columns, self.start.1 + offset / self.columns)
+    }
+
+    /// Print the line over the previously displayed one
+    fn redraw(&mut self, output: &mut Output) -> core::result::Result<(), Status> {
+        output
+            .set_cursor_position(self.start.0, self.start.1)
+            .warning_as_error()
+            .map_err(|e| e.status())?;
+        let mut text: String = self.line.iter().collect();
+        for _ in self.line.len()..self.displayed {
+            text.push(' ');
+        }
+        write_str(output, &text)?;
+        let written = self.line.len().max(self.displayed);
+        self.displayed = self.line.len();
+
+        // The screen scrolls if the text reaches its last line
+        let expected_row = self.position(written).1;
+        let row = output.cursor_position().1;
+        if expected_row > row {
+            self.start.1 = self.start.1.saturating_sub(expected_row - row);
+        }
+
+        let (column, row) = self.position(self.cursor);
+        output
+            .set_cursor_position(column, row)
+            .warning_as_error()
+            .map_err(|e| e.status())
+    }
+
+    /// Move the cursor to the line following the edited text
+    fn finish(&mut self, output: &mut Output) -> core::result::Result<(), Status> {
+        let (column, row) = self.position(self.line.len());
+        output
+            .set_cursor_position(column, row)
+            .warning_as_error()
+            .map_err(|e| e.status())?;
+        write_str(output, "\n")
+    }
+}
+
+/// Wait for a key to be pressed
+fn read_key(bt: &BootServices, input: &mut Input) -> core::result::Result<Key, Status> {
+    loop {
+        if let Some(key) = input
+            .read_key()
+            .warning_as_error()
+            .map_err(|e| e.status())?
+        {
+            return Ok(key);
+        }
+        bt.wait_for_event(&mut [input.wait_for_key_event()])
+            .warning_as_error()
+            .map_err(|e| e.status())?;
+    }
+}
+
+fn write_str(output: &mut Output, s: &str) -> core::result::Result<(), Status> {
+    output.write_str(s).map_err(|_| Status::DEVICE_ERROR)
+}
@@ -1,7 +1,23 @@
 //! Text I/O.
 
+mod ansi;
+pub use self::ansi::AnsiOutput;
+
 mod input;
 pub use self::input::{Input, Key, ScanCode};
 
+mod input_ex;
+pub use self::input_ex::{
+    InputEx, KeyData, KeyNotifyFn, KeyNotifyHandle, KeyShiftState, KeyToggleState,
+};
+
+mod input_queue;
+pub use self::input_queue::{Drain, InputQueue, INPUT_QUEUE_CAPACITY};
+
+#[cfg(feature = "exts")]
+mod line_editor;
+#[cfg(feature = "exts")]
+pub use self::line_editor::{Completer, LineEditor};
+
 mod output;
-pub use self::output::{Color, Output, OutputMode};
+pub use self::output::{Color, ColorGuard, ColorPair, Output, OutputMode};
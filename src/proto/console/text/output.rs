@@ -2,6 +2,7 @@ use crate::prelude::*;
 use crate::proto::Protocol;
 use crate::{unsafe_guid, CStr16, Char16, Completion, Result, Status};
 use core::fmt;
+use core::ops::{Deref, DerefMut};
 
 /// Interface for text-based output devices.
 ///
@@ -102,6 +103,30 @@ impl<'boot> Output<'boot> {
         (self.set_mode)(self, mode.index).into()
     }
 
+    /// Returns the text mode with the largest number of cells.
+    ///
+    /// If several modes have the same size, the one with the most columns is
+    /// preferred.
+    pub fn best_mode(&mut self) -> Option<OutputMode> {
+        self.modes()
+            .map(Completion::log)
+            .max_by_key(|mode| (mode.columns() * mode.rows(), mode.columns()))
+    }
+
+    /// Switches to the text mode with the largest number of cells, as
+    /// returned by `best_mode()`.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if the device had an error and could not set the mode
+    /// - `Unsupported` if no text mode is available
+    pub fn set_best_mode(&mut self) -> Result {
+        match self.best_mode() {
+            Some(mode) => self.set_mode(mode),
+            None => Err(Status::UNSUPPORTED.into()),
+        }
+    }
+
     /// Returns whether the cursor is currently shown or not.
     pub fn cursor_visible(&self) -> bool {
         self.data.cursor_visible
@@ -142,6 +167,59 @@ impl<'boot> Output<'boot> {
         let attr = ((bgc & 0x7) << 4) | (fgc & 0xF);
         (self.set_attribute)(self, attr).into()
     }
+
+    /// Returns the current text and background colors.
+    pub fn colors(&self) -> ColorPair {
+        let attr = self.data.attribute as usize;
+        ColorPair::new(COLORS[attr & 0xF], COLORS[(attr >> 4) & 0x7])
+    }
+
+    /// Sets the text and background colors, as with `set_color()`.
+    pub fn set_colors(&mut self, colors: ColorPair) -> Result {
+        self.set_color(colors.foreground, colors.background)
+    }
+
+    /// Runs `f` with the given colors, then restores the previous ones.
+    ///
+    /// The value returned by `f` is returned once the colors are restored.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if the colors could not be changed or restored
+    pub fn with_color<R>(
+        &mut self,
+        foreground: Color,
+        background: Color,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> Result<R> {
+        let saved = self.data.attribute as usize;
+        self.set_color(foreground, background)?.log();
+        let value = f(self);
+        (self.set_attribute)(self, saved).into_with_val(|| value)
+    }
+
+    /// Sets the given colors until the returned guard is dropped, which
+    /// restores the previous ones.
+    ///
+    /// The guard dereferences to this output device, so that text can be
+    /// printed through it.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if the colors could not be changed
+    pub fn color_guard<'out>(
+        &'out mut self,
+        foreground: Color,
+        background: Color,
+    ) -> Result<ColorGuard<'out, 'boot>> {
+        let saved = self.data.attribute as usize;
+        self.set_color(foreground, background)?.log();
+        Ok(ColorGuard {
+            output: self,
+            saved,
+        }
+        .into())
+    }
 }
 
 impl<'boot> fmt::Write for Output<'boot> {
@@ -197,6 +275,35 @@ impl<'boot> fmt::Write for Output<'boot> {
     }
 }
 
+/// Output device whose colors are restored when this guard is dropped,
+/// returned by `Output::color_guard()`.
+///
+/// Errors restoring the colors cannot be reported, and are ignored.
+pub struct ColorGuard<'out, 'boot> {
+    output: &'out mut Output<'boot>,
+    saved: usize,
+}
+
+impl<'boot> Deref for ColorGuard<'_, 'boot> {
+    type Target = Output<'boot>;
+
+    fn deref(&self) -> &Self::Target {
+        self.output
+    }
+}
+
+impl DerefMut for ColorGuard<'_, '_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.output
+    }
+}
+
+impl Drop for ColorGuard<'_, '_> {
+    fn drop(&mut self) {
+        let _ = (self.output.set_attribute)(self.output, self.saved);
+    }
+}
+
 /// The text mode (resolution) of the output device.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
 pub struct OutputMode {
@@ -269,12 +376,58 @@ struct OutputData {
     cursor_visible: bool,
 }
 
+/// Text and background colors of the UEFI console.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ColorPair {
+    /// Color of the text.
+    pub foreground: Color,
+    /// Color of the background, one of the first 8 colors.
+    pub background: Color,
+}
+
+impl ColorPair {
+    /// Create a pair of colors.
+    pub const fn new(foreground: Color, background: Color) -> Self {
+        Self {
+            foreground,
+            background,
+        }
+    }
+}
+
+impl Default for ColorPair {
+    /// Light gray on black, the colors of the console after a reset.
+    fn default() -> Self {
+        Self::new(Color::LightGray, Color::Black)
+    }
+}
+
+/// All the colors, indexed by their value.
+const COLORS: [Color; 16] = [
+    Color::Black,
+    Color::Blue,
+    Color::Green,
+    Color::Cyan,
+    Color::Red,
+    Color::Magenta,
+    Color::Brown,
+    Color::LightGray,
+    Color::DarkGray,
+    Color::LightBlue,
+    Color::LightGreen,
+    Color::LightCyan,
+    Color::LightRed,
+    Color::LightMagenta,
+    Color::Yellow,
+    Color::White,
+];
+
 /// Colors for the UEFI console.
 ///
 /// All colors can be used as foreground colors.
 /// The first 8 colors can also be used as background colors.
 #[allow(missing_docs)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum Color {
     Black = 0,
     Blue,
@@ -0,0 +1,245 @@
+//! Universal Graphics Adapter draw protocol.
+//!
+//! UGA is the predecessor of the graphics output protocol. It was deprecated
+//! by UEFI 2.0, but some older firmware, notably on Macs, only provides it.
+//! Unlike the GOP, it never exposes the frame buffer: all drawing is done with
+//! blit operations, which use the same pixel format as GOP blits.
+//!
+//! The `GraphicsDevice` type picks the GOP when it is available and falls
+//! back to UGA otherwise, for code which only needs to blit.
+
+use super::gop::{BltOp, BltPixel, BltRegion, GraphicsOutput};
+use crate::proto::Protocol;
+use crate::table::boot::BootServices;
+use crate::{unsafe_guid, Result, ResultExt, Status};
+use core::ptr;
+
+/// Provides basic drawing operations on an older video device.
+#[repr(C)]
+#[unsafe_guid("982c298b-f4fa-41cb-b838-77aa688fb839")]
+#[derive(Protocol)]
+pub struct UgaDraw {
+    get_mode: extern "efiapi" fn(
+        this: &UgaDraw,
+        horizontal_resolution: &mut u32,
+        vertical_resolution: &mut u32,
+        color_depth: &mut u32,
+        refresh_rate: &mut u32,
+    ) -> Status,
+    set_mode: extern "efiapi" fn(
+        this: &mut UgaDraw,
+        horizontal_resolution: u32,
+        vertical_resolution: u32,
+        color_depth: u32,
+        refresh_rate: u32,
+    ) -> Status,
+    // Same signature as the GOP blt, which was derived from this one.
+    #[allow(clippy::type_complexity)]
+    blt: unsafe extern "efiapi" fn(
+        this: &mut UgaDraw,
+        buffer: *mut BltPixel,
+        op: u32,
+        source_x: usize,
+        source_y: usize,
+        dest_x: usize,
+        dest_y: usize,
+        width: usize,
+        height: usize,
+        delta: usize,
+    ) -> Status,
+}
+
+/// Video mode of a UGA device.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct UgaMode {
+    /// Horizontal resolution, in pixels
+    pub horizontal_resolution: u32,
+    /// Vertical resolution, in pixels
+    pub vertical_resolution: u32,
+    /// Number of bits per pixel
+    pub color_depth: u32,
+    /// Refresh rate, in Hz
+    pub refresh_rate: u32,
+}
+
+impl UgaMode {
+    /// Returns the (horizontal, vertical) resolution.
+    pub fn resolution(&self) -> (usize, usize) {
+        (
+            self.horizontal_resolution as usize,
+            self.vertical_resolution as usize,
+        )
+    }
+}
+
+impl UgaDraw {
+    /// Returns the current video mode.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if the mode could not be retrieved
+    /// - `NotStarted` if the video mode has not been initialized
+    pub fn mode(&self) -> Result<UgaMode> {
+        let mut mode = UgaMode {
+            horizontal_resolution: 0,
+            vertical_resolution: 0,
+            color_depth: 0,
+            refresh_rate: 0,
+        };
+        (self.get_mode)(
+            self,
+            &mut mode.horizontal_resolution,
+            &mut mode.vertical_resolution,
+            &mut mode.color_depth,
+            &mut mode.refresh_rate,
+        )
+        .into_with_val(|| mode)
+    }
+
+    /// Sets the video mode, clearing the screen.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if the device had an error and could not set the mode
+    /// - `Unsupported` if the device does not support this mode
+    pub fn set_mode(&mut self, mode: &UgaMode) -> Result {
+        (self.set_mode)(
+            self,
+            mode.horizontal_resolution,
+            mode.vertical_resolution,
+            mode.color_depth,
+            mode.refresh_rate,
+        )
+        .into()
+    }
+
+    /// Performs a blt (block transfer) operation on the video display.
+    ///
+    /// The operations are the same as for `GraphicsOutput::blt()`.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if the device had an error and could not perform the
+    ///   operation
+    pub fn blt(&mut self, op: BltOp) -> Result {
+        let (width, height) = self.mode().warning_as_error()?.resolution();
+        let check_video = |(x, y): (usize, usize), (w, h): (usize, usize)| {
+            assert!(
+                x.saturating_add(w) <= width,
+                "Horizontal video coordinate out of bounds"
+            );
+            assert!(
+                y.saturating_add(h) <= height,
+                "Vertical video coordinate out of bounds"
+            );
+        };
+        // Returns the coordinates and delta to use for a region of a buffer
+        let check_buffer = |region: BltRegion, (w, h): (usize, usize), len: usize| match region {
+            BltRegion::Full => {
+                assert!(w.saturating_mul(h) <= len, "BltBuffer access out of bounds");
+                ((0, 0), 0)
+            }
+            BltRegion::SubRectangle {
+                coords: (x, y),
+                px_stride,
+            } => {
+                assert!(
+                    x.saturating_add(w) <= px_stride,
+                    "Horizontal BltBuffer coordinate out of bounds"
+                );
+                assert!(
+                    y.saturating_add(h).saturating_mul(px_stride) <= len,
+                    "Vertical BltBuffer coordinate out of bounds"
+                );
+                ((x, y), px_stride * core::mem::size_of::<BltPixel>())
+            }
+        };
+
+        let (buffer, op, src, dest, dims, delta) = match op {
+            BltOp::VideoFill { color, dest, dims } => {
+                check_video(dest, dims);
+                (&color as *const _ as *mut _, 0, (0, 0), dest, dims, 0)
+            }
+            BltOp::VideoToBltBuffer {
+                buffer,
+                src,
+                dest,
+                dims,
+            } => {
+                check_video(src, dims);
+                let (dest, delta) = check_buffer(dest, dims, buffer.len());
+                (buffer.as_mut_ptr(), 1, src, dest, dims, delta)
+            }
+            BltOp::BufferToVideo {
+                buffer,
+                src,
+                dest,
+                dims,
+            } => {
+                let (src, delta) = check_buffer(src, dims, buffer.len());
+                check_video(dest, dims);
+                (buffer.as_ptr() as *mut _, 2, src, dest, dims, delta)
+            }
+            BltOp::VideoToVideo { src, dest, dims } => {
+                check_video(src, dims);
+                check_video(dest, dims);
+                (ptr::null_mut(), 3, src, dest, dims, 0)
+            }
+        };
+
+        unsafe {
+            (self.blt)(
+                self, buffer, op, src.0, src.1, dest.0, dest.1, dims.0, dims.1, delta,
+            )
+        }
+        .into()
+    }
+}
+
+/// Video device which can be drawn on with blit operations.
+pub enum GraphicsDevice<'a> {
+    /// Device supporting the graphics output protocol
+    Gop(&'a mut GraphicsOutput<'a>),
+    /// Older device only supporting the UGA draw protocol
+    Uga(&'a mut UgaDraw),
+}
+
+impl<'a> GraphicsDevice<'a> {
+    /// Locate a video device, preferring the graphics output protocol over
+    /// the UGA draw protocol.
+    ///
+    /// # Errors
+    ///
+    /// - `NotFound` if neither protocol is available
+    pub fn locate(bt: &'a BootServices) -> Result<Self> {
+        if let Ok(gop) = bt.locate_protocol::<GraphicsOutput>() {
+            return Ok(gop.map(|gop| GraphicsDevice::Gop(unsafe { &mut *gop.get() })));
+        }
+        let uga = bt.locate_protocol::<UgaDraw>()?;
+        Ok(uga.map(|uga| GraphicsDevice::Uga(unsafe { &mut *uga.get() })))
+    }
+
+    /// Returns the (horizontal, vertical) resolution of the current mode.
+    pub fn resolution(&self) -> Result<(usize, usize)> {
+        match self {
+            GraphicsDevice::Gop(gop) => Ok(gop.current_mode_info().resolution().into()),
+            GraphicsDevice::Uga(uga) => uga.mode().map_inner(|mode| mode.resolution()),
+        }
+    }
+
+    /// Performs a blt (block transfer) operation on the video display.
+    pub fn blt(&mut self, op: BltOp) -> Result {
+        match self {
+            GraphicsDevice::Gop(gop) => gop.blt(op),
+            GraphicsDevice::Uga(uga) => uga.blt(op),
+        }
+    }
+
+    /// Returns the graphics output protocol, if it is the one being used.
+    pub fn gop(&mut self) -> Option<&mut GraphicsOutput<'a>> {
+        match self {
+            GraphicsDevice::Gop(gop) => Some(gop),
+            GraphicsDevice::Uga(_) => None,
+        }
+    }
+}
@@ -35,6 +35,51 @@ pub struct DevicePath {
     pub length: [u8; 2],
 }
 
+impl DevicePath {
+    /// Total size of this node in bytes, including its header
+    pub fn length(&self) -> u16 {
+        u16::from_le_bytes(self.length)
+    }
+
+    /// Iterate over the nodes of the device path instance starting with this
+    /// node, up to but excluding its end node
+    pub fn node_iter(&self) -> DevicePathNodes<'_> {
+        DevicePathNodes { node: Some(self) }
+    }
+
+    /// Interpret this node as a CD-ROM media node, if it is one
+    pub fn as_cdrom(&self) -> Option<&CdromDevicePath> {
+        if self.device_type == DeviceType::MEDIA
+            && self.sub_type == DeviceSubType::MEDIA_CD_ROM
+            && usize::from(self.length()) >= core::mem::size_of::<CdromDevicePath>()
+        {
+            Some(unsafe { &*(self as *const Self as *const CdromDevicePath) })
+        } else {
+            None
+        }
+    }
+}
+
+/// Iterator over the nodes of a device path instance
+pub struct DevicePathNodes<'a> {
+    node: Option<&'a DevicePath>,
+}
+
+impl<'a> Iterator for DevicePathNodes<'a> {
+    type Item = &'a DevicePath;
+
+    fn next(&mut self) -> Option<&'a DevicePath> {
+        let node = self.node.take()?;
+        // Also stop on malformed nodes, which would not make any progress
+        let length = usize::from(node.length());
+        if node.device_type == DeviceType::END || length < core::mem::size_of::<DevicePath>() {
+            return None;
+        }
+        self.node = Some(unsafe { &*(node as *const DevicePath as *const u8).add(length).cast() });
+        Some(node)
+    }
+}
+
 newtype_enum! {
 /// Type identifier for a DevicePath
 pub enum DeviceType: u8 => {
@@ -77,6 +122,10 @@ newtype_enum! {
 pub enum DeviceSubType: u8 => {
     /// End This Instance of a Device Path and start a new Device Path
     END_INSTANCE = 0x01,
+    /// CD-ROM Media Device Path
+    MEDIA_CD_ROM = 0x02,
+    /// Vendor-Defined Media Device Path
+    MEDIA_VENDOR = 0x03,
     /// End Entire Device Path
     END_ENTIRE = 0xFF,
 }}
@@ -96,3 +145,23 @@ pub struct AcpiDevicePath {
     /// thus strings must not be used for the _UID in the ACPI name space.
     pub uid: u32,
 }
+
+/// CD-ROM Media Device Path
+///
+/// Describes an El Torito boot entry of a CD-ROM, which the firmware exposes
+/// as a partition.
+#[repr(C, packed)]
+pub struct CdromDevicePath {
+    /// Type of device, which is Media Device Path
+    pub device_type: DeviceType,
+    /// Sub type of the device, which is CD-ROM Media Device Path
+    pub sub_type: DeviceSubType,
+    /// Size of this node in bytes
+    pub length: [u8; 2],
+    /// Index of the boot entry in the El Torito boot catalog. The initial/default entry is 0.
+    pub boot_entry: u32,
+    /// Starting RBA (2048-byte sector) of the partition on the medium
+    pub partition_start: u64,
+    /// Size of the partition in 2048-byte sectors
+    pub partition_size: u64,
+}
@@ -0,0 +1,112 @@
+//! HII database protocol.
+
+use super::keyboard::KeyboardLayout;
+use crate::proto::Protocol;
+use crate::result::Error;
+use crate::{unsafe_guid, Guid, Result, Status};
+use core::{mem, ptr};
+
+/// Registry of the HII package lists of the firmware.
+///
+/// Only the keyboard layout functions of this protocol are currently
+/// supported. They let applications find the keyboard layouts installed by
+/// the firmware and drivers, and select the one used to translate the keys
+/// pressed into characters.
+#[repr(C)]
+#[unsafe_guid("ef9fc172-a1b2-4693-b327-6d32fc416042")]
+#[derive(Protocol)]
+pub struct HiiDatabase {
+    new_package_list: usize,
+    remove_package_list: usize,
+    update_package_list: usize,
+    list_package_lists: usize,
+    export_package_lists: usize,
+    register_package_notify: usize,
+    unregister_package_notify: usize,
+    find_keyboard_layouts: unsafe extern "efiapi" fn(
+        this: &HiiDatabase,
+        key_guid_buffer_length: &mut u16,
+        key_guid_buffer: *mut Guid,
+    ) -> Status,
+    get_keyboard_layout: unsafe extern "efiapi" fn(
+        this: &HiiDatabase,
+        key_guid: *const Guid,
+        keyboard_layout_length: &mut u16,
+        keyboard_layout: *mut u8,
+    ) -> Status,
+    set_keyboard_layout: extern "efiapi" fn(this: &mut HiiDatabase, key_guid: &Guid) -> Status,
+    get_package_list_handle: usize,
+}
+
+impl HiiDatabase {
+    /// Writes the GUIDs of the installed keyboard layouts into `buffer`, and
+    /// returns how many there are.
+    ///
+    /// If the buffer is too small, the number of layouts is returned as part
+    /// of the error.
+    ///
+    /// # Errors
+    ///
+    /// - `BufferTooSmall` if the buffer cannot hold all the GUIDs
+    /// - `NotFound` if no keyboard layout is installed
+    pub fn find_keyboard_layouts(&self, buffer: &mut [Guid]) -> Result<usize, Option<usize>> {
+        let guid_size = mem::size_of::<Guid>();
+        let mut length = mem::size_of_val(buffer).min(usize::from(u16::MAX)) as u16;
+        unsafe { (self.find_keyboard_layouts)(self, &mut length, buffer.as_mut_ptr()) }.into_with(
+            || usize::from(length) / guid_size,
+            |s| {
+                if s == Status::BUFFER_TOO_SMALL {
+                    Some(usize::from(length) / guid_size)
+                } else {
+                    None
+                }
+            },
+        )
+    }
+
+    /// Reads the keyboard layout with the given GUID into `buffer`, or the
+    /// current layout if `guid` is `None`.
+    ///
+    /// If the buffer is too small, the required buffer size will be returned
+    /// as part of the error.
+    ///
+    /// # Errors
+    ///
+    /// - `BufferTooSmall` if the buffer cannot hold the layout
+    /// - `NotFound` if there is no such layout
+    /// - `VolumeCorrupted` if the firmware returned an invalid layout
+    pub fn keyboard_layout<'buf>(
+        &self,
+        guid: Option<&Guid>,
+        buffer: &'buf mut [u8],
+    ) -> Result<KeyboardLayout<'buf>, Option<usize>> {
+        let mut length = buffer.len().min(usize::from(u16::MAX)) as u16;
+        let guid = guid.map_or(ptr::null(), |guid| guid as *const Guid);
+        unsafe { (self.get_keyboard_layout)(self, guid, &mut length, buffer.as_mut_ptr()) }
+            .into_with_err(|s| {
+                if s == Status::BUFFER_TOO_SMALL {
+                    Some(usize::from(length))
+                } else {
+                    None
+                }
+            })?
+            .log();
+        let data = &buffer[..usize::from(length).min(buffer.len())];
+        match KeyboardLayout::parse(data) {
+            Some(layout) => Ok(layout.into()),
+            None => Err(Error::new(Status::VOLUME_CORRUPTED, None)),
+        }
+    }
+
+    /// Makes the keyboard layout with the given GUID the current one.
+    ///
+    /// The new layout is used by the console input drivers for the keys
+    /// pressed from now on.
+    ///
+    /// # Errors
+    ///
+    /// - `NotFound` if there is no such layout
+    pub fn set_keyboard_layout(&mut self, guid: &Guid) -> Result {
+        (self.set_keyboard_layout)(self, guid).into()
+    }
+}
@@ -0,0 +1,297 @@
+//! Keyboard layout packages.
+//!
+//! A keyboard layout maps the physical keys of a keyboard to the characters
+//! they produce. Layouts are registered with the HII database, and the one
+//! selected by `HiiDatabase::set_keyboard_layout()` is used by the console
+//! input drivers.
+
+use crate::Guid;
+use bitflags::bitflags;
+use core::convert::TryInto;
+use core::{fmt, ptr};
+
+/// Size of the header of a keyboard layout, before its key descriptors.
+const LAYOUT_HEADER_SIZE: usize = 23;
+
+/// Size of a key descriptor.
+const DESCRIPTOR_SIZE: usize = 16;
+
+/// A keyboard layout, read with `HiiDatabase::keyboard_layout()`.
+#[derive(Debug, Copy, Clone)]
+pub struct KeyboardLayout<'buf> {
+    data: &'buf [u8],
+    guid: Guid,
+    descriptor_count: usize,
+    descriptions_offset: usize,
+}
+
+impl<'buf> KeyboardLayout<'buf> {
+    /// Parses a keyboard layout, as stored in a keyboard layout package.
+    ///
+    /// Returns `None` if the layout is truncated or its header is invalid.
+    pub fn parse(data: &'buf [u8]) -> Option<Self> {
+        if data.len() < LAYOUT_HEADER_SIZE {
+            return None;
+        }
+        let length = usize::from(read_u16(data, 0)?);
+        if length < LAYOUT_HEADER_SIZE {
+            return None;
+        }
+        let data = data.get(..length)?;
+        let guid = data.get(2..18)?;
+        let guid = unsafe { ptr::read_unaligned(guid.as_ptr() as *const Guid) };
+        let descriptions_offset = u32::from_le_bytes(data[18..22].try_into().ok()?) as usize;
+        let descriptor_count = usize::from(data[22]);
+        if LAYOUT_HEADER_SIZE + descriptor_count * DESCRIPTOR_SIZE > length {
+            return None;
+        }
+        Some(Self {
+            data,
+            guid,
+            descriptor_count,
+            descriptions_offset,
+        })
+    }
+
+    /// Returns the GUID identifying this layout.
+    pub fn guid(&self) -> Guid {
+        self.guid
+    }
+
+    /// Returns the raw bytes of the layout.
+    pub fn as_bytes(&self) -> &'buf [u8] {
+        self.data
+    }
+
+    /// Returns the descriptors of the keys of this layout.
+    pub fn descriptors(&self) -> KeyDescriptors<'buf> {
+        KeyDescriptors {
+            data: &self.data
+                [LAYOUT_HEADER_SIZE..LAYOUT_HEADER_SIZE + self.descriptor_count * DESCRIPTOR_SIZE],
+        }
+    }
+
+    /// Returns the descriptor of a key, identified by its `EFI_KEY` position,
+    /// if this layout maps it.
+    pub fn descriptor(&self, key: u32) -> Option<KeyDescriptor> {
+        self.descriptors().find(|descriptor| descriptor.key == key)
+    }
+
+    /// Returns the names of this layout, in various languages.
+    ///
+    /// Descriptions which are truncated are ignored.
+    pub fn descriptions(&self) -> LayoutDescriptions<'buf> {
+        let count = read_u16(self.data, self.descriptions_offset).unwrap_or(0);
+        LayoutDescriptions {
+            data: self.data.get(self.descriptions_offset + 2..).unwrap_or(&[]),
+            remaining: usize::from(count),
+        }
+    }
+}
+
+/// Mapping of a key of a keyboard layout to the characters that it produces.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct KeyDescriptor {
+    /// Physical position of the key on the keyboard, as an `EFI_KEY` value.
+    pub key: u32,
+    /// UCS-2 character produced by the key alone, or 0.
+    pub unicode: u16,
+    /// UCS-2 character produced by the key with Shift, or 0.
+    pub shifted_unicode: u16,
+    /// UCS-2 character produced by the key with AltGr, or 0.
+    pub alt_gr_unicode: u16,
+    /// UCS-2 character produced by the key with Shift and AltGr, or 0.
+    pub shifted_alt_gr_unicode: u16,
+    /// Function of the key if it does not produce characters.
+    pub modifier: KeyModifier,
+    /// Modifiers which affect the character produced by the key.
+    pub affected_attribute: AffectedAttribute,
+}
+
+impl KeyDescriptor {
+    /// Returns the character produced by the key when the Shift and AltGr
+    /// modifiers are in the given states, if any.
+    pub fn character(&self, shift: bool, alt_gr: bool) -> Option<char> {
+        let code = match (shift, alt_gr) {
+            (false, false) => self.unicode,
+            (true, false) => self.shifted_unicode,
+            (false, true) => self.alt_gr_unicode,
+            (true, true) => self.shifted_alt_gr_unicode,
+        };
+        match code {
+            0 => None,
+            code => core::char::from_u32(u32::from(code)),
+        }
+    }
+
+    fn parse(data: &[u8]) -> Self {
+        let field = |index: usize| read_u16(data, 4 + index * 2).unwrap_or(0);
+        Self {
+            key: u32::from_le_bytes([data[0], data[1], data[2], data[3]]),
+            unicode: field(0),
+            shifted_unicode: field(1),
+            alt_gr_unicode: field(2),
+            shifted_alt_gr_unicode: field(3),
+            modifier: KeyModifier(field(4)),
+            affected_attribute: AffectedAttribute::from_bits_truncate(field(5)),
+        }
+    }
+}
+
+newtype_enum! {
+    /// Function of a key of a keyboard layout.
+    pub enum KeyModifier: u16 => #[allow(missing_docs)] {
+        /// The key produces characters.
+        NULL_MODIFIER       = 0x00,
+        LEFT_CONTROL        = 0x01,
+        RIGHT_CONTROL       = 0x02,
+        LEFT_ALT            = 0x03,
+        RIGHT_ALT           = 0x04,
+        ALT_GR              = 0x05,
+        INSERT              = 0x06,
+        DELETE              = 0x07,
+        PAGE_DOWN           = 0x08,
+        PAGE_UP             = 0x09,
+        HOME                = 0x0A,
+        END                 = 0x0B,
+        LEFT_SHIFT          = 0x0C,
+        RIGHT_SHIFT         = 0x0D,
+        CAPS_LOCK           = 0x0E,
+        NUM_LOCK            = 0x0F,
+        LEFT_ARROW          = 0x10,
+        RIGHT_ARROW         = 0x11,
+        DOWN_ARROW          = 0x12,
+        UP_ARROW            = 0x13,
+        /// Non-spacing key, such as a dead key for accents.
+        NS_KEY              = 0x14,
+        /// Key whose character depends on the previous non-spacing key.
+        NS_KEY_DEPENDENCY   = 0x15,
+    }
+}
+
+bitflags! {
+    /// Modifiers affecting the character produced by a key.
+    pub struct AffectedAttribute: u16 {
+        /// Shift selects the shifted character.
+        const STANDARD_SHIFT = 0x1;
+        /// Caps Lock inverts the effect of Shift.
+        const CAPS_LOCK = 0x2;
+        /// Num Lock affects the key.
+        const NUM_LOCK = 0x4;
+    }
+}
+
+/// Iterator over the key descriptors of a `KeyboardLayout`.
+#[derive(Debug, Clone)]
+pub struct KeyDescriptors<'buf> {
+    data: &'buf [u8],
+}
+
+impl Iterator for KeyDescriptors<'_> {
+    type Item = KeyDescriptor;
+
+    fn next(&mut self) -> Option<KeyDescriptor> {
+        if self.data.len() < DESCRIPTOR_SIZE {
+            return None;
+        }
+        let (descriptor, rest) = self.data.split_at(DESCRIPTOR_SIZE);
+        self.data = rest;
+        Some(KeyDescriptor::parse(descriptor))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.data.len() / DESCRIPTOR_SIZE;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for KeyDescriptors<'_> {}
+
+/// Name of a keyboard layout in a given language.
+#[derive(Debug, Copy, Clone)]
+pub struct LayoutDescription<'buf> {
+    language: &'buf [u8],
+    text: &'buf [u8],
+}
+
+impl<'buf> LayoutDescription<'buf> {
+    /// Returns the RFC 4646 identifier of the language, such as `en-US`.
+    pub fn language(&self) -> Ucs2Chars<'buf> {
+        Ucs2Chars {
+            data: self.language,
+        }
+    }
+
+    /// Returns the name of the layout.
+    pub fn text(&self) -> Ucs2Chars<'buf> {
+        Ucs2Chars { data: self.text }
+    }
+}
+
+/// Iterator over the descriptions of a `KeyboardLayout`.
+#[derive(Debug, Clone)]
+pub struct LayoutDescriptions<'buf> {
+    data: &'buf [u8],
+    remaining: usize,
+}
+
+impl<'buf> Iterator for LayoutDescriptions<'buf> {
+    type Item = LayoutDescription<'buf>;
+
+    fn next(&mut self) -> Option<LayoutDescription<'buf>> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        // The language is terminated by a space, and the text by a null
+        // character
+        let space = find_u16(self.data, u16::from(b' '))?;
+        let language = &self.data[..space];
+        let rest = &self.data[space + 2..];
+        let null = find_u16(rest, 0)?;
+        let text = &rest[..null];
+        self.data = &rest[null + 2..];
+        Some(LayoutDescription { language, text })
+    }
+}
+
+/// Characters of a UCS-2 string stored in a keyboard layout.
+///
+/// Code units which are not valid characters are replaced by U+FFFD.
+#[derive(Debug, Clone)]
+pub struct Ucs2Chars<'buf> {
+    data: &'buf [u8],
+}
+
+impl Iterator for Ucs2Chars<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let code = read_u16(self.data, 0)?;
+        self.data = &self.data[2..];
+        Some(core::char::from_u32(u32::from(code)).unwrap_or('\u{fffd}'))
+    }
+}
+
+impl fmt::Display for Ucs2Chars<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for c in self.clone() {
+            fmt::Write::write_char(f, c)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads a little-endian u16 at the given offset
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+/// Returns the byte offset of the first occurrence of a UCS-2 code unit
+fn find_u16(data: &[u8], code: u16) -> Option<usize> {
+    (0..data.len() / 2)
+        .map(|index| index * 2)
+        .find(|&offset| read_u16(data, offset) == Some(code))
+}
@@ -0,0 +1,8 @@
+//! Human Interface Infrastructure (HII) protocols.
+//!
+//! The HII stores the resources used by the firmware's user interface, such
+//! as strings, fonts, forms and keyboard layouts, in package lists
+//! registered with the HII database.
+
+pub mod database;
+pub mod keyboard;
@@ -2,11 +2,11 @@
 
 use crate::{
     data_types::{CStr16, Char16},
-    proto::Protocol,
+    proto::{device_path::DevicePath, Protocol},
     table::boot::MemoryType,
     unsafe_guid, Handle, Status,
 };
-use core::{ffi::c_void, str};
+use core::{ffi::c_void, slice, str};
 
 /// The LoadedImage protocol. This can be opened on any image handle using the `HandleProtocol` boot service.
 #[repr(C)]
@@ -19,7 +19,7 @@ pub struct LoadedImage {
 
     // Source location of the image
     device_handle: Handle,
-    _file_path: *const c_void, // TODO: not supported yet
+    file_path: *const DevicePath,
     _reserved: *const c_void,
 
     // Image load options
@@ -46,11 +46,41 @@ pub enum LoadOptionsError {
 }
 
 impl LoadedImage {
+    /// Returns a handle to the image which loaded this one, or `None` if it
+    /// was loaded by the firmware itself.
+    pub fn parent(&self) -> Option<Handle> {
+        if self.parent_handle.is_null() {
+            None
+        } else {
+            Some(self.parent_handle)
+        }
+    }
+
     /// Returns a handle to the storage device on which the image is located.
     pub fn device(&self) -> Handle {
         self.device_handle
     }
 
+    /// Returns the path of the image file on its device, or `None` if the
+    /// image was loaded from a memory buffer.
+    pub fn file_path(&self) -> Option<&DevicePath> {
+        unsafe { self.file_path.as_ref() }
+    }
+
+    /// Returns the raw load options of the image.
+    ///
+    /// These are the optional data of the boot option which started the
+    /// image, which are not necessarily a string, unlike those passed by the
+    /// shell.
+    pub fn load_options_data(&self) -> &[u8] {
+        if self.load_options.is_null() {
+            &[]
+        } else {
+            let len = self.load_options_size as usize;
+            unsafe { slice::from_raw_parts(self.load_options as *const u8, len) }
+        }
+    }
+
     /// Get the load options of the given image. If the image was executed from the EFI shell, or from a boot
     /// option, this is the command line that was used to execute it as a string. If no options were given, this
     /// returns `Ok("")`.
@@ -83,4 +113,24 @@ impl LoadedImage {
     pub fn info(&self) -> (usize, u64) {
         (self.image_base, self.image_size)
     }
+
+    /// Returns the address the image was loaded at.
+    pub fn image_base(&self) -> usize {
+        self.image_base
+    }
+
+    /// Returns the size of the loaded image in bytes.
+    pub fn image_size(&self) -> u64 {
+        self.image_size
+    }
+
+    /// Returns the type of the memory holding the code of the image.
+    pub fn image_code_type(&self) -> MemoryType {
+        self.image_code_type
+    }
+
+    /// Returns the type of the memory allocated for the data of the image.
+    pub fn image_data_type(&self) -> MemoryType {
+        self.image_data_type
+    }
 }
@@ -1,7 +1,12 @@
 //! Block I/O protocols.
 
 use crate::proto::Protocol;
-use crate::{unsafe_guid, Result, Status};
+use crate::table::boot::BootServices;
+use crate::{unsafe_guid, Event, Result, ResultExt, Status};
+#[cfg(feature = "exts")]
+use alloc_api::vec::Vec;
+use core::cell::UnsafeCell;
+use core::ptr;
 
 /// The Block I/O protocol.
 #[repr(C)]
@@ -30,6 +35,14 @@ pub struct BlockIO {
 }
 
 impl BlockIO {
+    /// Revision of the protocol implemented by the device.
+    ///
+    /// The fields of `BlockIOMedia` which are marked as belonging to a given
+    /// revision are only valid if the device reports at least that revision.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
     /// Pointer for block IO media.
     pub fn media(&self) -> &BlockIOMedia {
         unsafe { &*self.media }
@@ -100,6 +113,284 @@ impl BlockIO {
     }
 }
 
+/// The Block I/O 2 protocol.
+///
+/// This is an extension of the `BlockIO` protocol which can perform transfers
+/// asynchronously, signaling an event upon completion.
+///
+/// The `*_blocking` methods perform a synchronous transfer. The `*_async`
+/// methods start a transfer, run some caller-provided work while it is in
+/// progress, and wait for the transfer to complete before returning, which
+/// guarantees that the firmware is done with the buffer by the time it is
+/// released. The raw `*_ex` methods give full control over the transaction
+/// token, at the cost of being unsafe.
+#[repr(C)]
+#[unsafe_guid("a77b2472-e282-4e9f-a245-c2c0e27bbcc1")]
+#[derive(Protocol)]
+pub struct BlockIO2 {
+    media: *const BlockIOMedia,
+
+    reset: extern "efiapi" fn(this: &BlockIO2, extended_verification: bool) -> Status,
+    read_blocks_ex: unsafe extern "efiapi" fn(
+        this: &BlockIO2,
+        media_id: u32,
+        lba: Lba,
+        token: *mut BlockIO2Token,
+        buffer_size: usize,
+        buffer: *mut u8,
+    ) -> Status,
+    write_blocks_ex: unsafe extern "efiapi" fn(
+        this: &BlockIO2,
+        media_id: u32,
+        lba: Lba,
+        token: *mut BlockIO2Token,
+        buffer_size: usize,
+        buffer: *const u8,
+    ) -> Status,
+    flush_blocks_ex:
+        unsafe extern "efiapi" fn(this: &BlockIO2, token: *mut BlockIO2Token) -> Status,
+}
+
+impl BlockIO2 {
+    /// Pointer for block IO media.
+    pub fn media(&self) -> &BlockIOMedia {
+        unsafe { &*self.media }
+    }
+
+    /// Resets the block device hardware.
+    ///
+    /// Any pending asynchronous transfer is aborted, and its event is signaled
+    /// with a transaction status of `Status::ABORTED`.
+    ///
+    /// If `extended_verification` is true, the driver may perform a more
+    /// exhaustive verification operation of the device during reset.
+    ///
+    /// # Errors
+    /// * `uefi::Status::DEVICE_ERROR`  The block device is not functioning correctly and could not be reset.
+    pub fn reset(&mut self, extended_verification: bool) -> Result {
+        (self.reset)(self, extended_verification).into()
+    }
+
+    /// Read the requested number of blocks from the device, waiting for the
+    /// transfer to complete.
+    ///
+    /// See `BlockIO::read_blocks` for a description of the arguments and errors.
+    pub fn read_blocks_blocking(&self, media_id: u32, lba: Lba, buffer: &mut [u8]) -> Result {
+        unsafe {
+            (self.read_blocks_ex)(
+                self,
+                media_id,
+                lba,
+                ptr::null_mut(),
+                buffer.len(),
+                buffer.as_mut_ptr(),
+            )
+        }
+        .into()
+    }
+
+    /// Writes the requested number of blocks to the device, waiting for the
+    /// transfer to complete.
+    ///
+    /// See `BlockIO::write_blocks` for a description of the arguments and errors.
+    pub fn write_blocks_blocking(&mut self, media_id: u32, lba: Lba, buffer: &[u8]) -> Result {
+        unsafe {
+            (self.write_blocks_ex)(
+                self,
+                media_id,
+                lba,
+                ptr::null_mut(),
+                buffer.len(),
+                buffer.as_ptr(),
+            )
+        }
+        .into()
+    }
+
+    /// Flushes all modified data to a physical block device, waiting for the
+    /// flush to complete.
+    ///
+    /// See `BlockIO::flush_blocks` for a description of the errors.
+    pub fn flush_blocks_blocking(&mut self) -> Result {
+        unsafe { (self.flush_blocks_ex)(self, ptr::null_mut()) }.into()
+    }
+
+    /// Read the requested number of blocks from the device, running `work`
+    /// while the transfer is in progress.
+    ///
+    /// `event` is signaled by the firmware when the transfer completes, and
+    /// must therefore be usable with `BootServices::wait_for_event`, i.e. it
+    /// must not be of type `EventType::NOTIFY_SIGNAL`. Once `work` has
+    /// returned, this function waits for the transfer to complete.
+    ///
+    /// The errors are those of `BlockIO::read_blocks`, plus
+    /// `uefi::Status::OUT_OF_RESOURCES` if the request could not be queued.
+    ///
+    /// # Panics
+    /// Panics if waiting for `event` fails, since the firmware may still be
+    /// writing to `buffer` at that point.
+    pub fn read_blocks_async<R>(
+        &self,
+        bt: &BootServices,
+        event: Event,
+        media_id: u32,
+        lba: Lba,
+        buffer: &mut [u8],
+        work: impl FnOnce() -> R,
+    ) -> Result<R> {
+        let mut token = BlockIO2Token::new(event);
+        let issued = unsafe { self.read_blocks_ex(media_id, lba, &mut token, buffer) }?;
+        token.complete(bt, issued.status(), work)
+    }
+
+    /// Writes the requested number of blocks to the device, running `work`
+    /// while the transfer is in progress.
+    ///
+    /// See `read_blocks_async` for the requirements on `event`, and
+    /// `BlockIO::write_blocks` for a description of the errors.
+    ///
+    /// # Panics
+    /// Panics if waiting for `event` fails, since the firmware may still be
+    /// reading from `buffer` at that point.
+    pub fn write_blocks_async<R>(
+        &mut self,
+        bt: &BootServices,
+        event: Event,
+        media_id: u32,
+        lba: Lba,
+        buffer: &[u8],
+        work: impl FnOnce() -> R,
+    ) -> Result<R> {
+        let mut token = BlockIO2Token::new(event);
+        let issued = unsafe { self.write_blocks_ex(media_id, lba, &mut token, buffer) }?;
+        token.complete(bt, issued.status(), work)
+    }
+
+    /// Flushes all modified data to a physical block device, running `work`
+    /// while the flush is in progress.
+    ///
+    /// See `read_blocks_async` for the requirements on `event`, and
+    /// `BlockIO::flush_blocks` for a description of the errors.
+    ///
+    /// # Panics
+    /// Panics if waiting for `event` fails.
+    pub fn flush_blocks_async<R>(
+        &mut self,
+        bt: &BootServices,
+        event: Event,
+        work: impl FnOnce() -> R,
+    ) -> Result<R> {
+        let mut token = BlockIO2Token::new(event);
+        let issued = unsafe { self.flush_blocks_ex(&mut token) }?;
+        token.complete(bt, issued.status(), work)
+    }
+
+    /// Start reading the requested number of blocks from the device.
+    ///
+    /// The event of `token` is signaled once the transfer is complete, at which
+    /// point the outcome of the transfer can be queried using
+    /// `BlockIO2Token::transaction_status`.
+    ///
+    /// # Safety
+    /// Neither `token` nor `buffer` may be moved, freed or accessed until the
+    /// event of `token` has been signaled.
+    pub unsafe fn read_blocks_ex(
+        &self,
+        media_id: u32,
+        lba: Lba,
+        token: &mut BlockIO2Token,
+        buffer: &mut [u8],
+    ) -> Result {
+        (self.read_blocks_ex)(
+            self,
+            media_id,
+            lba,
+            token,
+            buffer.len(),
+            buffer.as_mut_ptr(),
+        )
+        .into()
+    }
+
+    /// Start writing the requested number of blocks to the device.
+    ///
+    /// # Safety
+    /// Neither `token` nor `buffer` may be moved, freed or accessed until the
+    /// event of `token` has been signaled.
+    pub unsafe fn write_blocks_ex(
+        &mut self,
+        media_id: u32,
+        lba: Lba,
+        token: &mut BlockIO2Token,
+        buffer: &[u8],
+    ) -> Result {
+        (self.write_blocks_ex)(self, media_id, lba, token, buffer.len(), buffer.as_ptr()).into()
+    }
+
+    /// Start flushing all modified data to a physical block device.
+    ///
+    /// # Safety
+    /// `token` may not be moved, freed or accessed until its event has been
+    /// signaled.
+    pub unsafe fn flush_blocks_ex(&mut self, token: &mut BlockIO2Token) -> Result {
+        (self.flush_blocks_ex)(self, token).into()
+    }
+}
+
+/// Transaction token of an asynchronous `BlockIO2` or `DiskIo2` operation
+#[repr(C)]
+pub struct BlockIO2Token {
+    event: Event,
+    // Written by the firmware behind the back of the compiler
+    transaction_status: UnsafeCell<Status>,
+}
+
+impl BlockIO2Token {
+    /// Create a token which signals `event` upon completion of a transaction
+    pub fn new(event: Event) -> Self {
+        Self {
+            event,
+            transaction_status: UnsafeCell::new(Status::SUCCESS),
+        }
+    }
+
+    /// Event signaled upon completion of the transaction
+    pub fn event(&self) -> Event {
+        self.event
+    }
+
+    /// Outcome of the transaction
+    ///
+    /// This is only meaningful once the event has been signaled.
+    pub fn transaction_status(&self) -> Status {
+        unsafe { ptr::read_volatile(self.transaction_status.get()) }
+    }
+
+    /// Run `work`, then wait for the pending transaction to complete
+    ///
+    /// `issue_status` is the (success or warning) status that was returned
+    /// when the transaction was started.
+    pub(crate) fn complete<R>(
+        &self,
+        bt: &BootServices,
+        issue_status: Status,
+        work: impl FnOnce() -> R,
+    ) -> Result<R> {
+        let output = work();
+        bt.wait_for_event(&mut [self.event])
+            .expect_success("Failed to wait for a pending I/O transaction");
+        self.transaction_status()
+            .into_with_val(|| output)
+            .map(|completion| completion.with_status(issue_status))
+    }
+}
+
+/// Revision 2 of the Block I/O protocol
+pub const BLOCK_IO_REVISION2: u64 = 0x0002_0001;
+
+/// Revision 3 of the Block I/O protocol
+pub const BLOCK_IO_REVISION3: u64 = (2 << 16) | 31;
+
 /// EFI LBA type
 pub type Lba = u64;
 
@@ -138,10 +429,16 @@ impl BlockIOMedia {
     }
 
     /// True if there is a media currently present in the device.
-    pub fn is_media_preset(&self) -> bool {
+    pub fn is_media_present(&self) -> bool {
         self.media_present
     }
 
+    /// True if there is a media currently present in the device.
+    #[deprecated(note = "misspelled, use `is_media_present` instead")]
+    pub fn is_media_preset(&self) -> bool {
+        self.is_media_present()
+    }
+
     /// True if block IO was produced to abstract partition structure.
     pub fn is_logical_partition(&self) -> bool {
         self.logical_partition
@@ -175,17 +472,62 @@ impl BlockIOMedia {
     }
 
     /// Returns the first LBA that is aligned to a physical block boundary.
+    ///
+    /// Only valid for devices implementing `BLOCK_IO_REVISION2` or later.
     pub fn lowest_aligned_lba(&self) -> Lba {
         self.lowest_aligned_lba
     }
 
     /// Returns the number of logical blocks per physical block.
+    ///
+    /// Only valid for devices implementing `BLOCK_IO_REVISION2` or later.
     pub fn logical_blocks_per_physical_block(&self) -> u32 {
         self.logical_blocks_per_physical_block
     }
 
     /// Returns the optimal transfer length granularity as a number of logical blocks.
+    ///
+    /// Only valid for devices implementing `BLOCK_IO_REVISION3` or later.
     pub fn optimal_transfer_length_granularity(&self) -> u32 {
         self.optimal_transfer_length_granularity
     }
 }
+
+/// Buffer satisfying the alignment requirements of a block device.
+#[cfg(feature = "exts")]
+pub(crate) struct BlockBuffer {
+    storage: Vec<u8>,
+    offset: usize,
+    len: usize,
+}
+
+#[cfg(feature = "exts")]
+impl BlockBuffer {
+    pub(crate) fn new(block_io: &BlockIO, len: usize) -> Self {
+        let align = (block_io.media().io_align() as usize).max(1);
+        let storage = alloc_api::vec![0; len + align - 1];
+        let offset = storage.as_ptr().align_offset(align);
+        Self {
+            storage,
+            offset,
+            len,
+        }
+    }
+
+    pub(crate) fn as_slice(&self) -> &[u8] {
+        &self.storage[self.offset..self.offset + self.len]
+    }
+
+    pub(crate) fn read(
+        &mut self,
+        block_io: &BlockIO,
+        lba: Lba,
+    ) -> core::result::Result<(), Status> {
+        let media_id = block_io.media().media_id();
+        let buffer = &mut self.storage[self.offset..self.offset + self.len];
+        block_io
+            .read_blocks(media_id, lba, buffer)
+            .log_warning()
+            .map_err(|error| error.status())
+    }
+}
@@ -0,0 +1,293 @@
+//! Disk I/O protocols.
+
+use super::block::BlockIO2Token;
+use crate::proto::Protocol;
+use crate::table::boot::BootServices;
+use crate::{unsafe_guid, Event, Result, Status};
+use core::ptr;
+
+/// The Disk I/O protocol.
+///
+/// This protocol is used to abstract the block accesses of the Block I/O
+/// protocol to a more general offset-length protocol. Firmware is responsible
+/// for adding this protocol to any Block I/O interface that appears in the
+/// system that does not already have a Disk I/O protocol.
+#[repr(C)]
+#[unsafe_guid("ce345171-ba0b-11d2-8e4f-00a0c969723b")]
+#[derive(Protocol)]
+pub struct DiskIo {
+    revision: u64,
+    read_disk: extern "efiapi" fn(
+        this: &DiskIo,
+        media_id: u32,
+        offset: u64,
+        len: usize,
+        buffer: *mut u8,
+    ) -> Status,
+    write_disk: extern "efiapi" fn(
+        this: &mut DiskIo,
+        media_id: u32,
+        offset: u64,
+        len: usize,
+        buffer: *const u8,
+    ) -> Status,
+}
+
+impl DiskIo {
+    /// Revision of the protocol implemented by the device.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Reads bytes from the disk device.
+    ///
+    /// # Arguments
+    /// * `media_id` - ID of the medium to be read.
+    /// * `offset` - Starting byte offset on the logical block I/O device to read from.
+    /// * `buffer` - Pointer to a buffer to read into.
+    ///
+    /// # Errors
+    /// * `uefi::Status::INVALID_PARAMETER` The read request contains device addresses that
+    ///   are not valid for the device.
+    /// * `uefi::Status::DEVICE_ERROR`      The device reported an error while performing
+    ///   the read operation.
+    /// * `uefi::Status::NO_MEDIA`          There is no medium in the device.
+    /// * `uefi::Status::MEDIA_CHANGED`     `media_id` is not for the current medium.
+    pub fn read_disk(&self, media_id: u32, offset: u64, buffer: &mut [u8]) -> Result {
+        (self.read_disk)(self, media_id, offset, buffer.len(), buffer.as_mut_ptr()).into()
+    }
+
+    /// Writes bytes to the disk device.
+    ///
+    /// # Arguments
+    /// * `media_id` - ID of the medium to be written.
+    /// * `offset` - Starting byte offset on the logical block I/O device to write to.
+    /// * `buffer` - Pointer to a buffer to write from.
+    ///
+    /// # Errors
+    /// * `uefi::Status::INVALID_PARAMETER` The write request contains device addresses that
+    ///   are not valid for the device.
+    /// * `uefi::Status::DEVICE_ERROR`      The device reported an error while performing
+    ///   the write operation.
+    /// * `uefi::Status::NO_MEDIA`          There is no medium in the device.
+    /// * `uefi::Status::MEDIA_CHANGED`     `media_id` is not for the current medium.
+    /// * `uefi::Status::WRITE_PROTECTED`   The device cannot be written to.
+    pub fn write_disk(&mut self, media_id: u32, offset: u64, buffer: &[u8]) -> Result {
+        (self.write_disk)(self, media_id, offset, buffer.len(), buffer.as_ptr()).into()
+    }
+}
+
+/// The Disk I/O 2 protocol.
+///
+/// This is an extension of the `DiskIo` protocol which can perform transfers
+/// asynchronously, signaling an event upon completion. Its methods follow the
+/// same conventions as those of `BlockIO2`.
+#[repr(C)]
+#[unsafe_guid("151c8eae-7f2c-472c-9e54-9828194f6a88")]
+#[derive(Protocol)]
+pub struct DiskIo2 {
+    revision: u64,
+    cancel: extern "efiapi" fn(this: &mut DiskIo2) -> Status,
+    read_disk_ex: unsafe extern "efiapi" fn(
+        this: &DiskIo2,
+        media_id: u32,
+        offset: u64,
+        token: *mut DiskIo2Token,
+        len: usize,
+        buffer: *mut u8,
+    ) -> Status,
+    write_disk_ex: unsafe extern "efiapi" fn(
+        this: &mut DiskIo2,
+        media_id: u32,
+        offset: u64,
+        token: *mut DiskIo2Token,
+        len: usize,
+        buffer: *const u8,
+    ) -> Status,
+    flush_disk_ex:
+        unsafe extern "efiapi" fn(this: &mut DiskIo2, token: *mut DiskIo2Token) -> Status,
+}
+
+impl DiskIo2 {
+    /// Revision of the protocol implemented by the device.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Terminates all outstanding asynchronous requests.
+    ///
+    /// The events of the aborted requests are signaled with a transaction
+    /// status of `Status::ABORTED`.
+    ///
+    /// # Errors
+    /// * `uefi::Status::DEVICE_ERROR`      The device reported an error while performing
+    ///   the cancel operation.
+    pub fn cancel(&mut self) -> Result {
+        (self.cancel)(self).into()
+    }
+
+    /// Reads bytes from the disk device, waiting for the transfer to complete.
+    ///
+    /// See `DiskIo::read_disk` for a description of the arguments and errors.
+    pub fn read_disk_blocking(&self, media_id: u32, offset: u64, buffer: &mut [u8]) -> Result {
+        unsafe {
+            (self.read_disk_ex)(
+                self,
+                media_id,
+                offset,
+                ptr::null_mut(),
+                buffer.len(),
+                buffer.as_mut_ptr(),
+            )
+        }
+        .into()
+    }
+
+    /// Writes bytes to the disk device, waiting for the transfer to complete.
+    ///
+    /// See `DiskIo::write_disk` for a description of the arguments and errors.
+    pub fn write_disk_blocking(&mut self, media_id: u32, offset: u64, buffer: &[u8]) -> Result {
+        unsafe {
+            (self.write_disk_ex)(
+                self,
+                media_id,
+                offset,
+                ptr::null_mut(),
+                buffer.len(),
+                buffer.as_ptr(),
+            )
+        }
+        .into()
+    }
+
+    /// Flushes all modified data to the physical device, waiting for the
+    /// flush to complete.
+    ///
+    /// # Errors
+    /// * `uefi::Status::DEVICE_ERROR`      The device reported an error while performing
+    ///   the flush operation.
+    /// * `uefi::Status::NO_MEDIA`          There is no medium in the device.
+    /// * `uefi::Status::MEDIA_CHANGED`     The medium in the device has changed since the
+    ///   last access.
+    /// * `uefi::Status::WRITE_PROTECTED`   The device cannot be written to.
+    pub fn flush_disk_blocking(&mut self) -> Result {
+        unsafe { (self.flush_disk_ex)(self, ptr::null_mut()) }.into()
+    }
+
+    /// Reads bytes from the disk device, running `work` while the transfer is
+    /// in progress.
+    ///
+    /// `event` must be usable with `BootServices::wait_for_event`, see
+    /// `BlockIO2::read_blocks_async` for details.
+    ///
+    /// # Panics
+    /// Panics if waiting for `event` fails, since the firmware may still be
+    /// writing to `buffer` at that point.
+    pub fn read_disk_async<R>(
+        &self,
+        bt: &BootServices,
+        event: Event,
+        media_id: u32,
+        offset: u64,
+        buffer: &mut [u8],
+        work: impl FnOnce() -> R,
+    ) -> Result<R> {
+        let mut token = DiskIo2Token::new(event);
+        let issued = unsafe { self.read_disk_ex(media_id, offset, &mut token, buffer) }?;
+        token.complete(bt, issued.status(), work)
+    }
+
+    /// Writes bytes to the disk device, running `work` while the transfer is
+    /// in progress.
+    ///
+    /// `event` must be usable with `BootServices::wait_for_event`, see
+    /// `BlockIO2::read_blocks_async` for details.
+    ///
+    /// # Panics
+    /// Panics if waiting for `event` fails, since the firmware may still be
+    /// reading from `buffer` at that point.
+    pub fn write_disk_async<R>(
+        &mut self,
+        bt: &BootServices,
+        event: Event,
+        media_id: u32,
+        offset: u64,
+        buffer: &[u8],
+        work: impl FnOnce() -> R,
+    ) -> Result<R> {
+        let mut token = DiskIo2Token::new(event);
+        let issued = unsafe { self.write_disk_ex(media_id, offset, &mut token, buffer) }?;
+        token.complete(bt, issued.status(), work)
+    }
+
+    /// Flushes all modified data to the physical device, running `work` while
+    /// the flush is in progress.
+    ///
+    /// # Panics
+    /// Panics if waiting for `event` fails.
+    pub fn flush_disk_async<R>(
+        &mut self,
+        bt: &BootServices,
+        event: Event,
+        work: impl FnOnce() -> R,
+    ) -> Result<R> {
+        let mut token = DiskIo2Token::new(event);
+        let issued = unsafe { self.flush_disk_ex(&mut token) }?;
+        token.complete(bt, issued.status(), work)
+    }
+
+    /// Start reading bytes from the disk device.
+    ///
+    /// The event of `token` is signaled once the transfer is complete, at which
+    /// point the outcome of the transfer can be queried using
+    /// `DiskIo2Token::transaction_status`.
+    ///
+    /// # Safety
+    /// Neither `token` nor `buffer` may be moved, freed or accessed until the
+    /// event of `token` has been signaled.
+    pub unsafe fn read_disk_ex(
+        &self,
+        media_id: u32,
+        offset: u64,
+        token: &mut DiskIo2Token,
+        buffer: &mut [u8],
+    ) -> Result {
+        (self.read_disk_ex)(
+            self,
+            media_id,
+            offset,
+            token,
+            buffer.len(),
+            buffer.as_mut_ptr(),
+        )
+        .into()
+    }
+
+    /// Start writing bytes to the disk device.
+    ///
+    /// # Safety
+    /// Neither `token` nor `buffer` may be moved, freed or accessed until the
+    /// event of `token` has been signaled.
+    pub unsafe fn write_disk_ex(
+        &mut self,
+        media_id: u32,
+        offset: u64,
+        token: &mut DiskIo2Token,
+        buffer: &[u8],
+    ) -> Result {
+        (self.write_disk_ex)(self, media_id, offset, token, buffer.len(), buffer.as_ptr()).into()
+    }
+
+    /// Start flushing all modified data to the physical device.
+    ///
+    /// # Safety
+    /// `token` may not be moved, freed or accessed until its event has been
+    /// signaled.
+    pub unsafe fn flush_disk_ex(&mut self, token: &mut DiskIo2Token) -> Result {
+        (self.flush_disk_ex)(self, token).into()
+    }
+}
+
+/// Transaction token of an asynchronous `DiskIo2` operation, which is the
+/// same as the token of `BlockIO2`
+pub type DiskIo2Token = BlockIO2Token;
@@ -0,0 +1,107 @@
+//! Erase Block protocol.
+
+use super::block::{BlockIO2Token, Lba};
+use crate::proto::Protocol;
+use crate::table::boot::BootServices;
+use crate::{unsafe_guid, Event, Result, Status};
+use core::ptr;
+
+/// The Erase Block protocol.
+///
+/// This protocol is produced by block devices which support erasing ranges of
+/// blocks, such as flash media supporting TRIM or UNMAP commands. Like the
+/// `BlockIO2` protocol, erase operations can complete asynchronously.
+#[repr(C)]
+#[unsafe_guid("95a9a93e-a86e-4926-aaef-9918e772d987")]
+#[derive(Protocol)]
+pub struct EraseBlock {
+    revision: u64,
+    erase_length_granularity: u32,
+    erase_blocks: unsafe extern "efiapi" fn(
+        this: &mut EraseBlock,
+        media_id: u32,
+        lba: Lba,
+        token: *mut EraseBlockToken,
+        size: usize,
+    ) -> Status,
+}
+
+impl EraseBlock {
+    /// Revision of the protocol implemented by the device.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Granularity of erase operations, as a number of logical blocks.
+    ///
+    /// Erasing ranges which are not aligned to this granularity may be
+    /// slower, or may leave parts of the range unerased.
+    pub fn erase_length_granularity(&self) -> u32 {
+        self.erase_length_granularity
+    }
+
+    /// Erase `size` bytes starting at the given logical block, waiting for
+    /// the operation to complete.
+    ///
+    /// # Arguments
+    /// * `media_id` - The media ID that the erase request is for.
+    /// * `lba` - The starting logical block address to be erased.
+    /// * `size` - The size in bytes to be erased, which must be a multiple of
+    ///   the block size of the device.
+    ///
+    /// # Errors
+    /// * `uefi::Status::WRITE_PROTECTED`    The device cannot be erased due to write protection.
+    /// * `uefi::Status::DEVICE_ERROR`       The device reported an error while attempting to
+    ///   perform the erase operation.
+    /// * `uefi::Status::INVALID_PARAMETER`  The erase request contains LBAs that are not valid.
+    /// * `uefi::Status::NO_MEDIA`           There is no media in the device.
+    /// * `uefi::Status::MEDIA_CHANGED`      The `media_id` is not for the current media.
+    pub fn erase_blocks_blocking(&mut self, media_id: u32, lba: Lba, size: usize) -> Result {
+        unsafe { (self.erase_blocks)(self, media_id, lba, ptr::null_mut(), size) }.into()
+    }
+
+    /// Erase `size` bytes starting at the given logical block, running `work`
+    /// while the operation is in progress.
+    ///
+    /// `event` must be usable with `BootServices::wait_for_event`, see
+    /// `BlockIO2::read_blocks_async` for details.
+    ///
+    /// # Panics
+    /// Panics if waiting for `event` fails.
+    pub fn erase_blocks_async<R>(
+        &mut self,
+        bt: &BootServices,
+        event: Event,
+        media_id: u32,
+        lba: Lba,
+        size: usize,
+        work: impl FnOnce() -> R,
+    ) -> Result<R> {
+        let mut token = EraseBlockToken::new(event);
+        let issued = unsafe { self.erase_blocks_ex(media_id, lba, &mut token, size) }?;
+        token.complete(bt, issued.status(), work)
+    }
+
+    /// Start erasing `size` bytes starting at the given logical block.
+    ///
+    /// The event of `token` is signaled once the operation is complete, at
+    /// which point its outcome can be queried using
+    /// `EraseBlockToken::transaction_status`.
+    ///
+    /// # Safety
+    /// `token` may not be moved, freed or accessed until its event has been
+    /// signaled.
+    pub unsafe fn erase_blocks_ex(
+        &mut self,
+        media_id: u32,
+        lba: Lba,
+        token: &mut EraseBlockToken,
+        size: usize,
+    ) -> Result {
+        (self.erase_blocks)(self, media_id, lba, token, size).into()
+    }
+}
+
+/// Transaction token of an asynchronous `EraseBlock` operation, which is the
+/// same as the token of `BlockIO2`
+pub type EraseBlockToken = BlockIO2Token;
@@ -1,8 +1,15 @@
+#[cfg(feature = "exts")]
+use super::FileSystemInfo;
 use super::{File, FileHandle, FileInfo, FromUefi, RegularFile};
 use crate::data_types::Align;
+use crate::io::{self, Seek, SeekFrom};
 use crate::prelude::*;
 use crate::Result;
+#[cfg(feature = "exts")]
+use alloc_api::{alloc::Layout, boxed::Box};
 use core::ffi::c_void;
+#[cfg(feature = "exts")]
+use core::mem;
 
 /// A `FileHandle` that is also a directory.
 ///
@@ -61,6 +68,76 @@ impl Directory {
     pub fn reset_entry_readout(&mut self) -> Result {
         self.0.set_position(0)
     }
+
+    /// Iterate over the remaining directory entries
+    ///
+    /// Each entry is read into a dynamically allocated `FileInfo`, so unlike
+    /// `read_entry()`, there is no need to provide and resize a buffer. Use
+    /// `reset_entry_readout()` beforehand to enumerate the directory from the
+    /// start.
+    #[cfg(feature = "exts")]
+    pub fn entries(&mut self) -> DirectoryEntries<'_> {
+        DirectoryEntries { dir: self }
+    }
+
+    /// Query information about the volume that this directory belongs to
+    ///
+    /// This takes care of sizing the info buffer. The information may only be
+    /// obtained on the root directory of a volume, as returned by
+    /// `SimpleFileSystem::open_volume()`.
+    #[cfg(feature = "exts")]
+    pub fn volume_info(&mut self) -> Result<Box<FileSystemInfo>> {
+        self.get_boxed_info::<FileSystemInfo>()
+    }
+}
+
+/// Iterator over the entries of a `Directory`, returned by `Directory::entries()`
+#[cfg(feature = "exts")]
+pub struct DirectoryEntries<'dir> {
+    dir: &'dir mut Directory,
+}
+
+#[cfg(feature = "exts")]
+impl<'dir> Iterator for DirectoryEntries<'dir> {
+    type Item = Result<Box<FileInfo>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Reading into an empty buffer either reports the end of the directory
+        // or the size which is needed to hold the next entry.
+        let size = match self.dir.read_entry(&mut []) {
+            Ok(_) => return None,
+            Err(err) => match err.split() {
+                (_, Some(size)) => size,
+                (status, None) => return Some(Err(status.into())),
+            },
+        };
+
+        // We add trailing padding because the size of a rust structure must
+        // always be a multiple of alignment.
+        let layout = Layout::from_size_align(size, FileInfo::alignment())
+            .unwrap()
+            .pad_to_align();
+        let mut buffer = crate::exts::allocate_buffer(layout);
+        let buffer_start = buffer.as_ptr();
+
+        let entry = self.dir.read_entry(&mut buffer).discard_errdata();
+        let entry = match entry {
+            Ok(completion) => completion.map(|info| {
+                // This operation is safe because info uses the exact memory
+                // of the provided buffer (so no memory is leaked), and the box
+                // is created if and only if buffer is leaked (so no memory can
+                // ever be freed twice).
+                let info = info.expect("Directory entry vanished between two reads");
+                assert_eq!(mem::size_of_val(info), layout.size());
+                assert_eq!(info as *const FileInfo as *const u8, buffer_start);
+                unsafe { Box::from_raw(info as *mut _) }
+            }),
+            Err(err) => return Some(Err(err)),
+        };
+        mem::forget(buffer);
+
+        Some(Ok(entry))
+    }
 }
 
 impl File for Directory {
@@ -69,3 +146,20 @@ impl File for Directory {
         self.0.handle()
     }
 }
+
+/// Directories only support rewinding, which restarts the enumeration of
+/// their entries. The firmware neither reports nor accepts any other position,
+/// so other seeks fail with `Status::UNSUPPORTED`. Reading and writing bytes
+/// does not apply to directories, which are read entry by entry.
+impl Seek for Directory {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        match position {
+            SeekFrom::Start(0) => self
+                .reset_entry_readout()
+                .log_warning()
+                .map(|_| 0)
+                .map_err(|err| err.status()),
+            _ => Err(Status::UNSUPPORTED),
+        }
+    }
+}
@@ -2,6 +2,8 @@ use super::FileAttribute;
 use crate::data_types::{chars::NUL_16, Align};
 use crate::table::runtime::Time;
 use crate::{unsafe_guid, CStr16, Char16, Identify};
+#[cfg(feature = "exts")]
+use alloc_api::{alloc::Layout, boxed::Box};
 use core::cmp;
 use core::convert::TryInto;
 use core::ffi::c_void;
@@ -68,8 +70,7 @@ impl<Header> NamedFileProtocolInfo<Header> {
 
         // Make sure that the storage is large enough for our needs
         let name_length_ucs2 = name.chars().count() + 1;
-        let name_size = name_length_ucs2 * mem::size_of::<Char16>();
-        let info_size = mem::size_of::<Header>() + name_size;
+        let info_size = Self::required_size(name);
         if storage.len() < info_size {
             return Err(FileInfoCreationError::InsufficientStorage(info_size));
         }
@@ -102,6 +103,56 @@ impl<Header> NamedFileProtocolInfo<Header> {
         info.name[name_length_ucs2 - 1] = NUL_16;
         Ok(info)
     }
+
+    /// Size in bytes of a `NamedFileProtocolInfo` holding the given name,
+    /// including its null terminator.
+    fn required_size(name: &str) -> usize {
+        let name_length_ucs2 = name.chars().count() + 1;
+        mem::size_of::<Header>() + name_length_ucs2 * mem::size_of::<Char16>()
+    }
+
+    /// Create a `NamedFileProtocolInfo` structure in a dynamically allocated,
+    /// correctly sized and aligned buffer
+    #[cfg(feature = "exts")]
+    fn new_boxed_impl(
+        header: Header,
+        name: &str,
+    ) -> core::result::Result<Box<Self>, FileInfoCreationError> {
+        // We add trailing padding because the size of a rust structure must
+        // always be a multiple of alignment.
+        let layout = Layout::from_size_align(Self::required_size(name), Self::alignment())
+            .unwrap()
+            .pad_to_align();
+        let mut buffer = crate::exts::allocate_buffer(layout);
+        let info_ptr = Self::new_impl(&mut buffer, header, name)? as *mut Self;
+
+        // This operation is safe because info uses the exact memory of the
+        // buffer (so no memory is leaked), and the box is created if and only
+        // if buffer is leaked (so no memory can ever be freed twice).
+        unsafe {
+            assert_eq!(mem::size_of_val(&*info_ptr), layout.size());
+            assert_eq!(info_ptr as *const u8, buffer.as_ptr());
+        }
+        mem::forget(buffer);
+        Ok(unsafe { Box::from_raw(info_ptr) })
+    }
+}
+
+#[cfg(feature = "exts")]
+impl<Header: Copy> Clone for Box<NamedFileProtocolInfo<Header>> {
+    fn clone(&self) -> Self {
+        let layout = Layout::for_value(&**self);
+        let buffer = crate::exts::allocate_buffer(layout);
+
+        // The header is `Copy` and the name is plain data, so a byte copy of
+        // the whole structure is a valid copy of it.
+        let info_ptr = Box::into_raw(buffer) as *mut u8 as *mut c_void;
+        unsafe {
+            let source = &**self as *const NamedFileProtocolInfo<Header> as *const u8;
+            core::ptr::copy_nonoverlapping(source, info_ptr as *mut u8, layout.size());
+            Box::from_raw(NamedFileProtocolInfo::from_uefi(info_ptr))
+        }
+    }
 }
 
 impl<Header> Align for NamedFileProtocolInfo<Header> {
@@ -155,7 +206,7 @@ pub enum FileInfoCreationError {
 pub type FileInfo = NamedFileProtocolInfo<FileInfoHeader>;
 
 /// Header for generic file information
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
 #[repr(C)]
 pub struct FileInfoHeader {
     size: u64,
@@ -198,7 +249,35 @@ impl FileInfo {
             attribute,
         };
         let info = Self::new_impl(storage, header, file_name)?;
-        info.header.size = mem::size_of_val(&info) as u64;
+        info.header.size = mem::size_of_val(info) as u64;
+        Ok(info)
+    }
+
+    /// Create a `FileInfo` structure in dynamically allocated storage
+    ///
+    /// This works like `FileInfo::new()`, but takes care of allocating a
+    /// correctly sized and aligned buffer for the data structure.
+    #[cfg(feature = "exts")]
+    pub fn new_boxed(
+        file_size: u64,
+        physical_size: u64,
+        create_time: Time,
+        last_access_time: Time,
+        modification_time: Time,
+        attribute: FileAttribute,
+        file_name: &str,
+    ) -> core::result::Result<Box<Self>, FileInfoCreationError> {
+        let header = FileInfoHeader {
+            size: 0,
+            file_size,
+            physical_size,
+            create_time,
+            last_access_time,
+            modification_time,
+            attribute,
+        };
+        let mut info = Self::new_boxed_impl(header, file_name)?;
+        info.header.size = mem::size_of_val(&*info) as u64;
         Ok(info)
     }
 
@@ -236,10 +315,137 @@ impl FileInfo {
     pub fn file_name(&self) -> &CStr16 {
         unsafe { CStr16::from_ptr(&self.name[0]) }
     }
+
+    /// Change the file size, which truncates or extends the file when this
+    /// information is set
+    pub fn set_file_size(&mut self, file_size: u64) {
+        self.header.file_size = file_size;
+    }
+
+    /// Change the time when the file was created
+    pub fn set_create_time(&mut self, create_time: Time) {
+        self.header.create_time = create_time;
+    }
+
+    /// Change the time when the file was last accessed
+    pub fn set_last_access_time(&mut self, last_access_time: Time) {
+        self.header.last_access_time = last_access_time;
+    }
+
+    /// Change the time when the file's contents were last modified
+    pub fn set_modification_time(&mut self, modification_time: Time) {
+        self.header.modification_time = modification_time;
+    }
+
+    /// Change the attribute bits of the file
+    ///
+    /// The `DIRECTORY` attribute can not be changed.
+    pub fn set_attribute(&mut self, attribute: FileAttribute) {
+        self.header.attribute = attribute;
+    }
 }
 
 impl FileProtocolInfo for FileInfo {}
 
+/// Builder for `FileInfo` structures
+///
+/// All fields except the file name are optional. Sizes default to zero, the
+/// times default to `Time::zero()` and the attributes default to empty.
+#[derive(Clone, Copy, Debug)]
+pub struct FileInfoBuilder<'name> {
+    file_size: u64,
+    physical_size: u64,
+    create_time: Time,
+    last_access_time: Time,
+    modification_time: Time,
+    attribute: FileAttribute,
+    file_name: &'name str,
+}
+
+impl<'name> FileInfoBuilder<'name> {
+    /// Start building a `FileInfo` for the given file name
+    pub fn new(file_name: &'name str) -> Self {
+        Self {
+            file_size: 0,
+            physical_size: 0,
+            create_time: Time::zero(),
+            last_access_time: Time::zero(),
+            modification_time: Time::zero(),
+            attribute: FileAttribute::empty(),
+            file_name,
+        }
+    }
+
+    /// Set the file size (number of bytes stored in the file)
+    pub fn file_size(mut self, file_size: u64) -> Self {
+        self.file_size = file_size;
+        self
+    }
+
+    /// Set the physical space consumed by the file on the volume
+    pub fn physical_size(mut self, physical_size: u64) -> Self {
+        self.physical_size = physical_size;
+        self
+    }
+
+    /// Set the time when the file was created
+    pub fn create_time(mut self, create_time: Time) -> Self {
+        self.create_time = create_time;
+        self
+    }
+
+    /// Set the time when the file was last accessed
+    pub fn last_access_time(mut self, last_access_time: Time) -> Self {
+        self.last_access_time = last_access_time;
+        self
+    }
+
+    /// Set the time when the file's contents were last modified
+    pub fn modification_time(mut self, modification_time: Time) -> Self {
+        self.modification_time = modification_time;
+        self
+    }
+
+    /// Set the attribute bits of the file
+    pub fn attribute(mut self, attribute: FileAttribute) -> Self {
+        self.attribute = attribute;
+        self
+    }
+
+    /// Create the `FileInfo` in user-provided storage
+    ///
+    /// See `FileInfo::new()` for the requirements on `storage`.
+    pub fn build<'buf>(
+        &self,
+        storage: &'buf mut [u8],
+    ) -> core::result::Result<&'buf mut FileInfo, FileInfoCreationError> {
+        FileInfo::new(
+            storage,
+            self.file_size,
+            self.physical_size,
+            self.create_time,
+            self.last_access_time,
+            self.modification_time,
+            self.attribute,
+            self.file_name,
+        )
+    }
+
+    /// Create the `FileInfo` in dynamically allocated storage
+    #[cfg(feature = "exts")]
+    pub fn build_boxed(&self) -> core::result::Result<Box<FileInfo>, FileInfoCreationError> {
+        FileInfo::new_boxed(
+            self.file_size,
+            self.physical_size,
+            self.create_time,
+            self.last_access_time,
+            self.modification_time,
+            self.attribute,
+            self.file_name,
+        )
+    }
+}
+
 /// System volume information
 ///
 /// May only be obtained on the root directory's file handle.
@@ -250,7 +456,7 @@ impl FileProtocolInfo for FileInfo {}
 pub type FileSystemInfo = NamedFileProtocolInfo<FileSystemInfoHeader>;
 
 /// Header for system volume information
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
 #[repr(C)]
 pub struct FileSystemInfoHeader {
     size: u64,
@@ -287,7 +493,31 @@ impl FileSystemInfo {
             block_size,
         };
         let info = Self::new_impl(storage, header, volume_label)?;
-        info.header.size = mem::size_of_val(&info) as u64;
+        info.header.size = mem::size_of_val(info) as u64;
+        Ok(info)
+    }
+
+    /// Create a `FileSystemInfo` structure in dynamically allocated storage
+    ///
+    /// This works like `FileSystemInfo::new()`, but takes care of allocating
+    /// a correctly sized and aligned buffer for the data structure.
+    #[cfg(feature = "exts")]
+    pub fn new_boxed(
+        read_only: bool,
+        volume_size: u64,
+        free_space: u64,
+        block_size: u32,
+        volume_label: &str,
+    ) -> core::result::Result<Box<Self>, FileInfoCreationError> {
+        let header = FileSystemInfoHeader {
+            size: 0,
+            read_only,
+            volume_size,
+            free_space,
+            block_size,
+        };
+        let mut info = Self::new_boxed_impl(header, volume_label)?;
+        info.header.size = mem::size_of_val(&*info) as u64;
         Ok(info)
     }
 
@@ -319,6 +549,85 @@ impl FileSystemInfo {
 
 impl FileProtocolInfo for FileSystemInfo {}
 
+/// Builder for `FileSystemInfo` structures
+///
+/// All fields except the volume label are optional. The volume is writable
+/// by default, and all sizes default to zero.
+#[derive(Clone, Copy, Debug)]
+pub struct FileSystemInfoBuilder<'label> {
+    read_only: bool,
+    volume_size: u64,
+    free_space: u64,
+    block_size: u32,
+    volume_label: &'label str,
+}
+
+impl<'label> FileSystemInfoBuilder<'label> {
+    /// Start building a `FileSystemInfo` for the given volume label
+    pub fn new(volume_label: &'label str) -> Self {
+        Self {
+            read_only: false,
+            volume_size: 0,
+            free_space: 0,
+            block_size: 0,
+            volume_label,
+        }
+    }
+
+    /// Set whether the volume only supports read access
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Set the number of bytes managed by the file system
+    pub fn volume_size(mut self, volume_size: u64) -> Self {
+        self.volume_size = volume_size;
+        self
+    }
+
+    /// Set the number of available bytes for use by the file system
+    pub fn free_space(mut self, free_space: u64) -> Self {
+        self.free_space = free_space;
+        self
+    }
+
+    /// Set the nominal block size by which files are typically grown
+    pub fn block_size(mut self, block_size: u32) -> Self {
+        self.block_size = block_size;
+        self
+    }
+
+    /// Create the `FileSystemInfo` in user-provided storage
+    ///
+    /// See `FileSystemInfo::new()` for the requirements on `storage`.
+    pub fn build<'buf>(
+        &self,
+        storage: &'buf mut [u8],
+    ) -> core::result::Result<&'buf mut FileSystemInfo, FileInfoCreationError> {
+        FileSystemInfo::new(
+            storage,
+            self.read_only,
+            self.volume_size,
+            self.free_space,
+            self.block_size,
+            self.volume_label,
+        )
+    }
+
+    /// Create the `FileSystemInfo` in dynamically allocated storage
+    #[cfg(feature = "exts")]
+    pub fn build_boxed(&self) -> core::result::Result<Box<FileSystemInfo>, FileInfoCreationError> {
+        FileSystemInfo::new_boxed(
+            self.read_only,
+            self.volume_size,
+            self.free_space,
+            self.block_size,
+            self.volume_label,
+        )
+    }
+}
+
 /// System volume label
 ///
 /// May only be obtained on the root directory's file handle.
@@ -326,7 +635,7 @@ impl FileProtocolInfo for FileSystemInfo {}
 pub type FileSystemVolumeLabel = NamedFileProtocolInfo<FileSystemVolumeLabelHeader>;
 
 /// Header for system volume label information
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
 #[repr(C)]
 pub struct FileSystemVolumeLabelHeader {}
 
@@ -348,6 +657,17 @@ impl FileSystemVolumeLabel {
         Self::new_impl(storage, header, volume_label)
     }
 
+    /// Create a `FileSystemVolumeLabel` structure in dynamically allocated
+    /// storage
+    ///
+    /// This works like `FileSystemVolumeLabel::new()`, but takes care of
+    /// allocating a correctly sized and aligned buffer for the data structure.
+    #[cfg(feature = "exts")]
+    pub fn new_boxed(volume_label: &str) -> core::result::Result<Box<Self>, FileInfoCreationError> {
+        let header = FileSystemVolumeLabelHeader {};
+        Self::new_boxed_impl(header, volume_label)
+    }
+
     /// Volume label
     pub fn volume_label(&self) -> &CStr16 {
         unsafe { CStr16::from_ptr(&self.name[0]) }
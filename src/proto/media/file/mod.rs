@@ -11,6 +11,7 @@ mod info;
 mod regular;
 
 use crate::prelude::*;
+use crate::result::Error;
 use crate::{CStr16, Char16, Guid, Result, Status};
 #[cfg(feature = "exts")]
 use alloc_api::{alloc::Layout, boxed::Box};
@@ -20,11 +21,15 @@ use core::mem;
 use core::ptr;
 
 pub use self::info::{
-    FileInfo, FileInfoHeader, FileProtocolInfo, FileSystemInfo, FileSystemInfoHeader,
-    FileSystemVolumeLabel, FileSystemVolumeLabelHeader, FromUefi, NamedFileProtocolInfo,
+    FileInfo, FileInfoBuilder, FileInfoHeader, FileProtocolInfo, FileSystemInfo,
+    FileSystemInfoBuilder, FileSystemInfoHeader, FileSystemVolumeLabel,
+    FileSystemVolumeLabelHeader, FromUefi, NamedFileProtocolInfo,
 };
 pub use self::{dir::Directory, regular::RegularFile};
 
+#[cfg(feature = "exts")]
+pub use self::dir::DirectoryEntries;
+
 /// Common interface to `FileHandle`, `RegularFile`, and `Directory`.
 ///
 /// `File` contains all functionality that is safe to perform on any type of
@@ -63,22 +68,34 @@ pub trait File: Sized {
             Err(Status::INVALID_PARAMETER.into())
         } else {
             let mut buf = [0u16; BUF_SIZE + 1];
-            let mut ptr = ptr::null_mut();
 
             let len = ucs2::encode(filename, &mut buf)?;
             let filename = unsafe { CStr16::from_u16_with_nul_unchecked(&buf[..=len]) };
+            self.open_cstr16(filename, open_mode, attributes)
+        }
+    }
 
-            unsafe {
-                (self.imp().open)(
-                    self.imp(),
-                    &mut ptr,
-                    filename.as_ptr(),
-                    open_mode,
-                    attributes,
-                )
-            }
-            .into_with_val(|| unsafe { FileHandle::new(ptr) })
+    /// Try to open a file relative to this file, given its UCS-2 path
+    ///
+    /// This works like `open()`, except that the path is passed to the
+    /// firmware as-is, without being encoded nor checked for length.
+    fn open_cstr16(
+        &mut self,
+        filename: &CStr16,
+        open_mode: FileMode,
+        attributes: FileAttribute,
+    ) -> Result<FileHandle> {
+        let mut ptr = ptr::null_mut();
+        unsafe {
+            (self.imp().open)(
+                self.imp(),
+                &mut ptr,
+                filename.as_ptr(),
+                open_mode,
+                attributes,
+            )
         }
+        .into_with_val(|| unsafe { FileHandle::new(ptr) })
     }
 
     /// Close this file handle. Same as dropping this structure.
@@ -156,7 +173,7 @@ pub trait File: Sized {
     /// * `uefi::Status::VOLUME_FULL`       Not enough space left on the volume to change the info
     fn set_info<Info: FileProtocolInfo + ?Sized>(&mut self, info: &Info) -> Result {
         let info_ptr = info as *const Info as *const c_void;
-        let info_size = mem::size_of_val(&info);
+        let info_size = mem::size_of_val(info);
         unsafe { (self.imp().set_info)(self.imp(), &Info::GUID, info_size, info_ptr).into() }
     }
 
@@ -173,12 +190,43 @@ pub trait File: Sized {
         (self.imp().flush)(self.imp()).into()
     }
 
+    /// Queries some information about a file into arbitrarily aligned storage
+    ///
+    /// This works like `get_info()`, except that `buffer` does not need to be
+    /// aligned: the information is written at the first suitably aligned
+    /// offset within it. This makes it easy to use a plain byte array on the
+    /// stack as storage.
+    ///
+    /// If the buffer is too small, the reported size includes enough slack to
+    /// account for the alignment padding of any buffer, so that the query is
+    /// guaranteed to succeed with a buffer of that size.
+    fn get_info_unaligned<'buf, Info: FileProtocolInfo + ?Sized>(
+        &mut self,
+        buffer: &'buf mut [u8],
+    ) -> Result<&'buf mut Info, Option<usize>> {
+        let slack = Info::alignment() - 1;
+        let offset = buffer.as_ptr().align_offset(Info::alignment());
+        let aligned = if offset <= buffer.len() {
+            &mut buffer[offset..]
+        } else {
+            &mut []
+        };
+        self.get_info::<Info>(aligned).map_err(|error| {
+            let (status, size) = error.split();
+            Error::new(status, size.map(|size| size + slack))
+        })
+    }
+
     #[cfg(feature = "exts")]
     /// Get the dynamically allocated info for a file
+    ///
+    /// This takes care of querying the size of the information, allocating a
+    /// suitably sized and aligned buffer, and retrying if the information
+    /// grew in the meantime.
     fn get_boxed_info<Info: FileProtocolInfo + ?Sized>(&mut self) -> Result<Box<Info>> {
         // Initially try get_info with an empty array, this should always fail
         // as all Info types at least need room for a null-terminator.
-        let size = match self
+        let mut size = match self
             .get_info::<Info>(&mut [])
             .expect_error("zero sized get_info unexpectedly succeeded")
             .split()
@@ -187,30 +235,77 @@ pub trait File: Sized {
             (_, Some(size)) => size,
         };
 
-        // We add trailing padding because the size of a rust structure must
-        // always be a multiple of alignment.
-        let layout = Layout::from_size_align(size, Info::alignment())
-            .unwrap()
-            .pad_to_align();
-        let mut buffer = crate::exts::allocate_buffer(layout);
-        let buffer_start = buffer.as_ptr();
-
-        let info = self
-            .get_info(&mut buffer)
-            .discard_errdata()?
-            .map(|info_ref| {
-                // This operation is safe because info uses the exact memory
-                // of the provied buffer (so no memory is leaked), and the box
-                // is created if and only if buffer is leaked (so no memory can
-                // ever be freed twice).
-
-                assert_eq!(mem::size_of_val(info_ref), layout.size());
-                assert_eq!(info_ref as *const Info as *const u8, buffer_start);
-                unsafe { Box::from_raw(info_ref as *mut _) }
-            });
-        mem::forget(buffer);
-
-        Ok(info)
+        loop {
+            // We add trailing padding because the size of a rust structure must
+            // always be a multiple of alignment.
+            let layout = Layout::from_size_align(size, Info::alignment())
+                .unwrap()
+                .pad_to_align();
+            let mut buffer = crate::exts::allocate_buffer(layout);
+            let buffer_start = buffer.as_ptr();
+
+            match self.get_info(&mut buffer) {
+                Ok(completion) => {
+                    let info = completion.map(|info_ref| {
+                        // This operation is safe because info uses the exact memory
+                        // of the provied buffer (so no memory is leaked), and the box
+                        // is created if and only if buffer is leaked (so no memory can
+                        // ever be freed twice).
+
+                        assert_eq!(mem::size_of_val(info_ref), layout.size());
+                        assert_eq!(info_ref as *const Info as *const u8, buffer_start);
+                        unsafe { Box::from_raw(info_ref as *mut _) }
+                    });
+                    mem::forget(buffer);
+                    return Ok(info);
+                }
+                Err(error) => match error.split() {
+                    // The information grew since the size was queried
+                    (Status::BUFFER_TOO_SMALL, Some(new_size)) => size = new_size,
+                    (s, _) => return Err(s.into()),
+                },
+            }
+        }
+    }
+    #[cfg(feature = "exts")]
+    /// Modify the `FileInfo` of a file
+    ///
+    /// The current information is queried and passed to `modify`, and the
+    /// result is then set on the file. The specification only allows removing
+    /// the `READ_ONLY` attribute of a read-only file on its own, so changes
+    /// which toggle this attribute are split into separate transactions: it is
+    /// cleared before, or set after, all other changes are applied.
+    ///
+    /// See `set_info()` for the possible errors.
+    fn modify_info(&mut self, modify: impl FnOnce(&mut FileInfo)) -> Result {
+        let (status, mut info) = self.get_boxed_info::<FileInfo>()?.split();
+        let was_read_only = info.attribute().contains(FileAttribute::READ_ONLY);
+        let mut modified = info.clone();
+        modify(&mut modified);
+        let read_only = modified.attribute().contains(FileAttribute::READ_ONLY);
+
+        let mut completion = crate::Completion::from(status);
+        if was_read_only && !read_only {
+            info.set_attribute(info.attribute() - FileAttribute::READ_ONLY);
+            completion = completion.with_status(self.set_info(&*info)?.status());
+        } else if !was_read_only && read_only {
+            modified.set_attribute(modified.attribute() - FileAttribute::READ_ONLY);
+            completion = completion.with_status(self.set_info(&*modified)?.status());
+            modified.set_attribute(modified.attribute() | FileAttribute::READ_ONLY);
+        }
+        Ok(completion.with_status(self.set_info(&*modified)?.status()))
+    }
+
+    #[cfg(feature = "exts")]
+    /// Set or clear the `READ_ONLY` attribute of a file
+    ///
+    /// See `modify_info()` for details.
+    fn set_read_only(&mut self, read_only: bool) -> Result {
+        self.modify_info(|info| {
+            let mut attribute = info.attribute();
+            attribute.set(FileAttribute::READ_ONLY, read_only);
+            info.set_attribute(attribute);
+        })
     }
 }
 
@@ -251,6 +346,24 @@ impl FileHandle {
             s => Err(s.into()),
         }
     }
+
+    /// Converts `File` into a `RegularFile`, or returns `None` if it is a
+    /// directory.
+    pub fn into_regular_file(self) -> Result<Option<RegularFile>> {
+        self.into_type().map_inner(|ty| match ty {
+            FileType::Regular(file) => Some(file),
+            FileType::Dir(_) => None,
+        })
+    }
+
+    /// Converts `File` into a `Directory`, or returns `None` if it is a
+    /// regular file.
+    pub fn into_directory(self) -> Result<Option<Directory>> {
+        self.into_type().map_inner(|ty| match ty {
+            FileType::Dir(dir) => Some(dir),
+            FileType::Regular(_) => None,
+        })
+    }
 }
 
 impl File for FileHandle {
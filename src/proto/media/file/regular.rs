@@ -1,5 +1,14 @@
+#[cfg(feature = "exts")]
+use super::FileInfo;
 use super::{File, FileHandle, FileInternal};
-use crate::{Result, Status};
+use crate::io::{self, Read, Seek, SeekFrom, Write};
+#[cfg(feature = "exts")]
+use crate::Completion;
+use crate::{Result, ResultExt as _, Status};
+#[cfg(feature = "exts")]
+use alloc_api::vec::Vec;
+#[cfg(feature = "exts")]
+use core::convert::TryFrom;
 
 /// A `FileHandle` that is also a regular (data) file.
 ///
@@ -97,6 +106,62 @@ impl RegularFile {
     pub fn set_position(&mut self, position: u64) -> Result {
         (self.imp().set_position)(self.imp(), position).into()
     }
+
+    /// Read all bytes from the current position until the end of the file
+    ///
+    /// The data is appended to `buffer`, which is grown as needed. The file
+    /// size is queried beforehand so that the whole file can usually be read
+    /// with a single allocation. Returns the number of bytes that were read.
+    ///
+    /// If an error occurs, the bytes which were read so far are kept in
+    /// `buffer`.
+    ///
+    /// # Errors
+    /// Any error returned by `get_info()`, `get_position()` or `read()`.
+    #[cfg(feature = "exts")]
+    pub fn read_to_end(&mut self, buffer: &mut Vec<u8>) -> Result<usize> {
+        const MIN_READ_SIZE: usize = 32;
+
+        let (status, info) = self.get_boxed_info::<FileInfo>()?.split();
+        let mut completion = Completion::new(status, ());
+        let (status, position) = self.get_position()?.split();
+        completion = completion.with_status(status);
+
+        // The file may still grow or shrink while we read it, so its size is
+        // only used as a hint and we keep going until a read returns nothing.
+        // If the reported size does not fit in memory, the buffer is grown
+        // incrementally instead.
+        let remaining = info.file_size().saturating_sub(position);
+        if let Ok(remaining) = usize::try_from(remaining) {
+            let _ = buffer.try_reserve(remaining);
+        }
+
+        let start_len = buffer.len();
+        loop {
+            if buffer.len() == buffer.capacity() {
+                buffer.reserve(MIN_READ_SIZE);
+            }
+
+            let len = buffer.len();
+            buffer.resize(buffer.capacity(), 0);
+            match self.read(&mut buffer[len..]).discard_errdata() {
+                Ok(read) => {
+                    let (status, read_size) = read.split();
+                    completion = completion.with_status(status);
+                    buffer.truncate(len + read_size);
+                    if read_size == 0 {
+                        break;
+                    }
+                }
+                Err(err) => {
+                    buffer.truncate(len);
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(completion.map(|_| buffer.len() - start_len))
+    }
 }
 
 impl File for RegularFile {
@@ -105,3 +170,65 @@ impl File for RegularFile {
         &mut self.0
     }
 }
+
+impl Read for RegularFile {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        RegularFile::read(self, buffer)
+            .discard_errdata()
+            .log_warning()
+            .map_err(|err| err.status())
+    }
+}
+
+impl Write for RegularFile {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        // Successful writes always consume the whole buffer
+        RegularFile::write(self, buffer)
+            .discard_errdata()
+            .log_warning()
+            .map(|_| buffer.len())
+            .map_err(|err| err.status())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        File::flush(self).log_warning().map_err(|err| err.status())
+    }
+}
+
+impl Seek for RegularFile {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match position {
+            SeekFrom::Start(position) => (position, 0),
+            SeekFrom::Current(offset) => (self.io_position()?, offset),
+            SeekFrom::End(offset) => {
+                self.io_set_position(Self::END_OF_FILE)?;
+                (self.io_position()?, offset)
+            }
+        };
+
+        let position = if offset >= 0 {
+            base.checked_add(offset as u64)
+        } else {
+            base.checked_sub(offset.unsigned_abs())
+        }
+        .ok_or(Status::INVALID_PARAMETER)?;
+
+        self.io_set_position(position)?;
+        Ok(position)
+    }
+}
+
+// Helpers for the `Seek` implementation
+impl RegularFile {
+    fn io_position(&mut self) -> io::Result<u64> {
+        self.get_position()
+            .log_warning()
+            .map_err(|err| err.status())
+    }
+
+    fn io_set_position(&mut self, position: u64) -> io::Result<()> {
+        self.set_position(position)
+            .log_warning()
+            .map_err(|err| err.status())
+    }
+}
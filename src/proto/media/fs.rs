@@ -1,8 +1,12 @@
 //! File system support protocols.
 
+#[cfg(feature = "exts")]
+use super::file::FileSystemInfo;
 use super::file::{Directory, FileHandle, FileImpl};
 use crate::proto::Protocol;
 use crate::{unsafe_guid, Result, Status};
+#[cfg(feature = "exts")]
+use alloc_api::boxed::Box;
 use core::ptr;
 
 /// Allows access to a FAT-12/16/32 file system.
@@ -34,4 +38,15 @@ impl SimpleFileSystem {
         (self.open_volume)(self, &mut ptr)
             .into_with_val(|| unsafe { Directory::new(FileHandle::new(ptr)) })
     }
+
+    /// Query information about the volume, such as its label and free space
+    ///
+    /// This opens the root directory of the volume and queries its
+    /// `FileSystemInfo`, see `Directory::volume_info()`.
+    #[cfg(feature = "exts")]
+    pub fn volume_info(&mut self) -> Result<Box<FileSystemInfo>> {
+        let (status, mut root) = self.open_volume()?.split();
+        root.volume_info()
+            .map(|completion| completion.with_status(status))
+    }
 }
@@ -0,0 +1,253 @@
+//! GUID Partition Table parsing.
+//!
+//! This module reads the GPT of a disk directly through its `BlockIO`
+//! protocol, which is useful when the firmware did not produce `PartitionInfo`
+//! protocols for the partitions of a disk, or when their header is needed.
+
+use super::block::{BlockBuffer, BlockIO, Lba};
+use super::partition::{GptPartitionEntry, GptPartitionType};
+use crate::{Guid, Result, Status};
+use alloc_api::vec::Vec;
+use core::{mem, ptr};
+
+/// Signature found at the start of every GPT header.
+pub const GPT_HEADER_SIGNATURE: [u8; 8] = *b"EFI PART";
+
+/// Size of the fields of a GPT header, which may be followed by padding.
+const GPT_HEADER_SIZE: usize = 92;
+
+/// Offset of the `header_crc32` field in the GPT header.
+const HEADER_CRC32_OFFSET: usize = 16;
+
+/// Largest partition entry array which is accepted, in bytes.
+///
+/// Partitioning tools normally create 128 entries of 128 bytes, this leaves
+/// plenty of room for larger tables while bounding the memory used to read a
+/// corrupted one.
+const MAX_PARTITION_ARRAY_SIZE: usize = 1024 * 1024;
+
+/// Header of a GUID Partition Table.
+///
+/// A disk contains two copies of this header: the primary one in LBA 1, and
+/// a backup one, normally in the last LBA of the disk.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct GptHeader {
+    /// Must be `GPT_HEADER_SIGNATURE`.
+    pub signature: [u8; 8],
+    /// Revision of the GPT format, 0x0001_0000 for UEFI 2.x.
+    pub revision: u32,
+    /// Size of the header in bytes.
+    pub header_size: u32,
+    /// CRC32 of the header, computed with this field set to zero.
+    pub header_crc32: u32,
+    /// Must be zero.
+    pub reserved: u32,
+    /// LBA containing this header.
+    pub my_lba: Lba,
+    /// LBA containing the other copy of the header.
+    pub alternate_lba: Lba,
+    /// First LBA that may be used by a partition.
+    pub first_usable_lba: Lba,
+    /// Last LBA that may be used by a partition.
+    pub last_usable_lba: Lba,
+    /// GUID identifying the disk.
+    pub disk_guid: Guid,
+    /// Starting LBA of the partition entry array.
+    pub partition_entry_lba: Lba,
+    /// Number of entries in the partition entry array.
+    pub number_of_partition_entries: u32,
+    /// Size in bytes of each partition entry, 128 times a power of two.
+    pub size_of_partition_entry: u32,
+    /// CRC32 of the partition entry array.
+    pub partition_entry_array_crc32: u32,
+}
+
+/// GUID Partition Table of a disk.
+#[derive(Debug)]
+pub struct GptDisk {
+    header: GptHeader,
+    entries: Vec<GptPartitionEntry>,
+    is_backup: bool,
+}
+
+impl GptDisk {
+    /// Read and validate the GUID Partition Table of a block device.
+    ///
+    /// The primary header and its partition entry array are read first. If
+    /// either has an invalid signature, size or CRC, the backup header found in
+    /// the last block of the device is used instead.
+    ///
+    /// # Errors
+    /// * `uefi::Status::NO_MEDIA`          There is no media in the device.
+    /// * `uefi::Status::NOT_FOUND`         The device does not contain a GPT.
+    /// * `uefi::Status::VOLUME_CORRUPTED`  Neither copy of the GPT is valid.
+    /// * `uefi::Status::DEVICE_ERROR`      The device reported an error while reading.
+    pub fn read(block_io: &BlockIO) -> Result<Self> {
+        let media = block_io.media();
+        if !media.is_media_present() {
+            return Err(Status::NO_MEDIA.into());
+        }
+
+        let primary = Self::read_copy(block_io, 1);
+        let error = match primary {
+            Ok(disk) => return Ok(disk.into()),
+            Err(error) => error,
+        };
+
+        match Self::read_copy(block_io, media.last_block()) {
+            Ok(mut disk) => {
+                disk.is_backup = true;
+                Ok(disk.into())
+            }
+            // Report the most informative of both errors
+            Err(Status::NOT_FOUND) => Err(error.into()),
+            Err(backup_error) => Err(backup_error.into()),
+        }
+    }
+
+    /// Header of the table.
+    pub fn header(&self) -> &GptHeader {
+        &self.header
+    }
+
+    /// True if the primary copy of the table was damaged and the backup copy
+    /// was used instead.
+    pub fn uses_backup_header(&self) -> bool {
+        self.is_backup
+    }
+
+    /// Iterate over the used entries of the partition entry array.
+    pub fn partitions(&self) -> impl Iterator<Item = &GptPartitionEntry> {
+        self.entries.iter().filter(|entry| {
+            let partition_type = entry.partition_type_guid;
+            partition_type != GptPartitionType::UNUSED_ENTRY
+        })
+    }
+
+    /// Find the partition with the given unique GUID.
+    pub fn find_partition(&self, unique_partition_guid: Guid) -> Option<&GptPartitionEntry> {
+        self.partitions().find(|entry| {
+            let guid = entry.unique_partition_guid;
+            guid == unique_partition_guid
+        })
+    }
+
+    /// Read one copy of the table, whose header is located at `lba`.
+    fn read_copy(block_io: &BlockIO, lba: Lba) -> core::result::Result<Self, Status> {
+        let media = block_io.media();
+        let block_size = media.block_size() as usize;
+        if block_size == 0 {
+            return Err(Status::NOT_FOUND);
+        }
+
+        let mut block = BlockBuffer::new(block_io, block_size);
+        block.read(block_io, lba)?;
+        let header = parse_header(block.as_slice(), lba, media.last_block())?;
+
+        // The size of the array was bounded by `parse_header()`
+        let entry_size = header.size_of_partition_entry as usize;
+        let array_size = header.number_of_partition_entries as usize * entry_size;
+        let array_blocks = array_size.div_ceil(block_size);
+        let last_array_lba = header
+            .partition_entry_lba
+            .checked_add(array_blocks as u64)
+            .ok_or(Status::VOLUME_CORRUPTED)?;
+        if header.partition_entry_lba < 2 || last_array_lba > media.last_block().saturating_add(1) {
+            return Err(Status::VOLUME_CORRUPTED);
+        }
+
+        let mut array = BlockBuffer::new(block_io, array_blocks * block_size);
+        if array_size != 0 {
+            array.read(block_io, header.partition_entry_lba)?;
+        }
+        let array = &array.as_slice()[..array_size];
+        if crc32(array) != header.partition_entry_array_crc32 {
+            return Err(Status::VOLUME_CORRUPTED);
+        }
+
+        // Entries may be larger than the structure defined by the current
+        // revision of the specification, ignore their trailing bytes.
+        let entries = array
+            .chunks_exact(entry_size)
+            .map(|entry| unsafe { ptr::read_unaligned(entry.as_ptr() as *const GptPartitionEntry) })
+            .collect();
+
+        Ok(Self {
+            header,
+            entries,
+            is_backup: false,
+        })
+    }
+}
+
+/// Parse and validate a GPT header read from `lba`.
+fn parse_header(
+    block: &[u8],
+    lba: Lba,
+    last_block: Lba,
+) -> core::result::Result<GptHeader, Status> {
+    if block.len() < mem::size_of::<GptHeader>() || block[..8] != GPT_HEADER_SIGNATURE {
+        return Err(Status::NOT_FOUND);
+    }
+    let header = unsafe { ptr::read_unaligned(block.as_ptr() as *const GptHeader) };
+
+    let header_size = header.header_size as usize;
+    if header_size < GPT_HEADER_SIZE || header_size > block.len() {
+        return Err(Status::VOLUME_CORRUPTED);
+    }
+
+    // The CRC is computed over the header with its own field zeroed
+    let mut crc = Crc32::new();
+    crc.update(&block[..HEADER_CRC32_OFFSET]);
+    crc.update(&[0; 4]);
+    crc.update(&block[HEADER_CRC32_OFFSET + 4..header_size]);
+    if crc.finish() != header.header_crc32 {
+        return Err(Status::VOLUME_CORRUPTED);
+    }
+
+    // Entries are 128 times a power of two bytes long
+    let entry_size = header.size_of_partition_entry as usize;
+    let array_size = (header.number_of_partition_entries as usize).checked_mul(entry_size);
+    if header.my_lba != lba
+        || entry_size < mem::size_of::<GptPartitionEntry>()
+        || !entry_size.is_power_of_two()
+        || !matches!(array_size, Some(size) if size <= MAX_PARTITION_ARRAY_SIZE)
+        || header.first_usable_lba > header.last_usable_lba
+        || header.last_usable_lba > last_block
+    {
+        return Err(Status::VOLUME_CORRUPTED);
+    }
+
+    Ok(header)
+}
+
+/// Compute the CRC32 of `data`, as used by GPT.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(data);
+    crc.finish()
+}
+
+/// Incremental CRC32 computation (IEEE 802.3 polynomial, reflected).
+struct Crc32(u32);
+
+impl Crc32 {
+    fn new() -> Self {
+        Crc32(!0)
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.0 ^= u32::from(byte);
+            for _ in 0..8 {
+                let mask = (self.0 & 1).wrapping_neg();
+                self.0 = (self.0 >> 1) ^ (0xedb8_8320 & mask);
+            }
+        }
+    }
+
+    fn finish(&self) -> u32 {
+        !self.0
+    }
+}
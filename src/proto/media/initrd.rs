@@ -0,0 +1,244 @@
+//! Linux initrd delivery.
+//!
+//! Since version 5.8, the EFI stub of the Linux kernel can load its initial
+//! ramdisk through a `LoadFile2` protocol installed on a vendor media device
+//! path with a well-known GUID, instead of having the bootloader place it in
+//! memory and patch the kernel command line.
+//!
+//! This module implements the bootloader side of that mechanism: an `Initrd`
+//! installs the device path and protocol, serving the ramdisk from an
+//! `InitrdSource`, and uninstalls them when it is dropped.
+
+use super::file::RegularFile;
+use super::load_file::LoadFile2;
+use crate::io::{Read, Seek, SeekFrom};
+use crate::proto::device_path::{DevicePath, DeviceSubType, DeviceType};
+use crate::table::boot::{BootServices, MemoryType};
+use crate::{Completion, Guid, Handle, Identify, Result, Status};
+use core::ffi::c_void;
+use core::{mem, ptr, slice};
+
+/// Vendor GUID of the device path on which the Linux kernel looks for its initrd
+pub const LINUX_EFI_INITRD_MEDIA_GUID: Guid = Guid::from_values(
+    0x5568e427,
+    0x68fc,
+    0x4f3d,
+    0xac74,
+    [0xca, 0x55, 0x52, 0x31, 0xcc, 0x68],
+);
+
+/// Vendor media device path node, followed by the end of the device path
+#[repr(C, packed)]
+struct InitrdDevicePath {
+    vendor: DevicePath,
+    vendor_guid: Guid,
+    end: DevicePath,
+}
+
+// The device path is plain immutable data, which is safe to share
+unsafe impl Sync for InitrdDevicePath {}
+
+/// Device path on which the `LoadFile2` protocol serving the initrd is installed
+static INITRD_DEVICE_PATH: InitrdDevicePath = InitrdDevicePath {
+    vendor: DevicePath {
+        device_type: DeviceType::MEDIA,
+        sub_type: DeviceSubType::MEDIA_VENDOR,
+        length: [20, 0],
+    },
+    vendor_guid: LINUX_EFI_INITRD_MEDIA_GUID,
+    end: DevicePath {
+        device_type: DeviceType::END,
+        sub_type: DeviceSubType::END_ENTIRE,
+        length: [4, 0],
+    },
+};
+
+/// Device path on which the Linux kernel looks for its initrd
+pub fn device_path() -> &'static DevicePath {
+    &INITRD_DEVICE_PATH.vendor
+}
+
+/// Source of the contents of an initrd
+pub trait InitrdSource {
+    /// Size of the initrd in bytes
+    fn size(&mut self) -> Result<usize>;
+
+    /// Fill `buffer`, which is exactly `size()` bytes long, with the initrd
+    fn read_into(&mut self, buffer: &mut [u8]) -> Result;
+}
+
+impl InitrdSource for &[u8] {
+    fn size(&mut self) -> Result<usize> {
+        Ok(self.len().into())
+    }
+
+    fn read_into(&mut self, buffer: &mut [u8]) -> Result {
+        buffer.copy_from_slice(self);
+        Ok(().into())
+    }
+}
+
+impl InitrdSource for RegularFile {
+    fn size(&mut self) -> Result<usize> {
+        let size = self.seek(SeekFrom::End(0))?;
+        Ok((size as usize).into())
+    }
+
+    fn read_into(&mut self, buffer: &mut [u8]) -> Result {
+        self.rewind()?;
+        self.read_exact(buffer)?;
+        Ok(().into())
+    }
+}
+
+/// `LoadFile2` instance along with the source that it serves
+#[repr(C)]
+struct InitrdLoader<S> {
+    proto: LoadFile2,
+    source: S,
+}
+
+/// Implementation of `LoadFile2::load_file()` for an `InitrdLoader<S>`
+unsafe extern "efiapi" fn load_initrd<S: InitrdSource>(
+    this: &mut LoadFile2,
+    file_path: *const DevicePath,
+    boot_policy: bool,
+    buffer_size: &mut usize,
+    buffer: *mut c_void,
+) -> Status {
+    if file_path.is_null() {
+        return Status::INVALID_PARAMETER;
+    }
+    if boot_policy {
+        return Status::UNSUPPORTED;
+    }
+
+    // The protocol is the first field of the loader
+    let loader = &mut *(this as *mut LoadFile2 as *mut InitrdLoader<S>);
+
+    let size = match loader.source.size() {
+        Ok(completion) => completion.log(),
+        Err(error) => return error.status(),
+    };
+    if buffer.is_null() || *buffer_size < size {
+        *buffer_size = size;
+        return Status::BUFFER_TOO_SMALL;
+    }
+
+    let buffer = slice::from_raw_parts_mut(buffer as *mut u8, size);
+    match loader.source.read_into(buffer) {
+        Ok(completion) => {
+            *buffer_size = size;
+            completion.status()
+        }
+        Err(error) => error.status(),
+    }
+}
+
+/// An initrd made available to the Linux kernel
+///
+/// The initrd is served for as long as this object is alive. Only one initrd
+/// should be installed at any given time.
+///
+/// The source must be `'static` because the firmware keeps a pointer to it,
+/// which would dangle if this object was leaked and the source freed.
+pub struct Initrd<'boot, S: InitrdSource + 'static> {
+    bt: &'boot BootServices,
+    handle: Handle,
+    loader: *mut InitrdLoader<S>,
+}
+
+impl<'boot, S: InitrdSource + 'static> Initrd<'boot, S> {
+    /// Install the initrd device path and a `LoadFile2` protocol serving the
+    /// contents of `source` on a new handle.
+    ///
+    /// # Errors
+    /// * `uefi::Status::UNSUPPORTED`  `source` needs more than 8-byte alignment.
+    pub fn install(bt: &'boot BootServices, source: S) -> Result<Self> {
+        // Pool allocations are 8-byte aligned
+        if mem::align_of::<InitrdLoader<S>>() > 8 {
+            return Err(Status::UNSUPPORTED.into());
+        }
+
+        let (alloc_status, loader) = bt
+            .allocate_pool(MemoryType::LOADER_DATA, mem::size_of::<InitrdLoader<S>>())?
+            .split();
+        let loader = loader as *mut InitrdLoader<S>;
+        unsafe {
+            loader.write(InitrdLoader {
+                proto: LoadFile2::new(load_initrd::<S>),
+                source,
+            });
+        }
+
+        let free_loader = || unsafe {
+            ptr::drop_in_place(loader);
+            // Nothing more can be done if this fails
+            let _ = bt.free_pool(loader as *mut u8);
+        };
+
+        let device_path = &INITRD_DEVICE_PATH as *const InitrdDevicePath as *mut c_void;
+        let (path_status, handle) =
+            match unsafe { bt.install_protocol_interface(None, &DevicePath::GUID, device_path) } {
+                Ok(completion) => completion.split(),
+                Err(error) => {
+                    free_loader();
+                    return Err(error);
+                }
+            };
+
+        let proto_status = match unsafe {
+            bt.install_protocol_interface(Some(handle), &LoadFile2::GUID, loader as *mut c_void)
+        } {
+            Ok(completion) => completion.status(),
+            Err(error) => {
+                unsafe {
+                    let _ = bt.uninstall_protocol_interface(handle, &DevicePath::GUID, device_path);
+                }
+                free_loader();
+                return Err(error);
+            }
+        };
+
+        let initrd = Self { bt, handle, loader };
+        Ok(Completion::new(alloc_status, initrd)
+            .with_status(path_status)
+            .with_status(proto_status))
+    }
+
+    /// Handle on which the initrd device path and `LoadFile2` protocol are
+    /// installed
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+}
+
+impl<S: InitrdSource + 'static> Drop for Initrd<'_, S> {
+    fn drop(&mut self) {
+        let device_path = &INITRD_DEVICE_PATH as *const InitrdDevicePath as *mut c_void;
+        unsafe {
+            if self
+                .bt
+                .uninstall_protocol_interface(
+                    self.handle,
+                    &LoadFile2::GUID,
+                    self.loader as *mut c_void,
+                )
+                .is_err()
+            {
+                // The firmware still references the loader, so it must be leaked
+                log::warn!("Failed to uninstall the initrd `LoadFile2` protocol");
+                return;
+            }
+            if self
+                .bt
+                .uninstall_protocol_interface(self.handle, &DevicePath::GUID, device_path)
+                .is_err()
+            {
+                log::warn!("Failed to uninstall the initrd device path");
+            }
+            ptr::drop_in_place(self.loader);
+            let _ = self.bt.free_pool(self.loader as *mut u8);
+        }
+    }
+}
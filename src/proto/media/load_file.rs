@@ -0,0 +1,184 @@
+//! Load File protocols.
+//!
+//! These protocols are produced by devices which can provide files in a way
+//! that is not covered by the file system protocols, such as network boot
+//! devices or firmware volumes.
+
+use crate::proto::device_path::DevicePath;
+use crate::proto::Protocol;
+use crate::{unsafe_guid, Result, Status};
+use core::ffi::c_void;
+#[cfg(feature = "exts")]
+use {crate::ResultExt, alloc_api::vec::Vec};
+
+/// Raw signature of the `LoadFile()` function of both load file protocols
+pub(crate) type LoadFileFn<P> = unsafe extern "efiapi" fn(
+    this: &mut P,
+    file_path: *const DevicePath,
+    boot_policy: bool,
+    buffer_size: &mut usize,
+    buffer: *mut c_void,
+) -> Status;
+
+/// The Load File protocol.
+///
+/// This protocol is used to load files from boot devices, typically network
+/// boot devices, which do not expose a file system.
+#[repr(C)]
+#[unsafe_guid("56ec3091-954c-11d2-8e3f-00a0c969723b")]
+#[derive(Protocol)]
+pub struct LoadFile {
+    load_file: LoadFileFn<LoadFile>,
+}
+
+impl LoadFile {
+    /// Load a file into `buffer`
+    ///
+    /// Returns the size of the file on success. If the buffer is too small,
+    /// the required buffer size is reported as part of the error, so a common
+    /// pattern is to call this function a first time with an empty buffer to
+    /// query the file size.
+    ///
+    /// # Arguments
+    /// * `file_path`    The device specific path of the file to load
+    /// * `boot_policy`  If true, the request originates from the boot manager
+    ///   and `file_path` may be a device path to the device itself rather than
+    ///   to a file on it. If false, `file_path` must match an exact file.
+    /// * `buffer`       The target buffer of the load operation
+    ///
+    /// # Errors
+    /// * `uefi::Status::UNSUPPORTED`        The device does not support the provided `boot_policy`
+    /// * `uefi::Status::INVALID_PARAMETER`  `file_path` is not a valid device path
+    /// * `uefi::Status::NO_MEDIA`           No medium was present to load the file
+    /// * `uefi::Status::DEVICE_ERROR`       The file was not loaded due to a device error
+    /// * `uefi::Status::NO_RESPONSE`        The remote system did not respond
+    /// * `uefi::Status::NOT_FOUND`          The file was not found
+    /// * `uefi::Status::ABORTED`            The file load process was manually cancelled
+    /// * `uefi::Status::BUFFER_TOO_SMALL`   The buffer is too small to hold the file,
+    ///   the required buffer size is provided into the error.
+    /// * `uefi::Status::WARN_FILE_SYSTEM`   The resulting buffer contains a UEFI-compliant
+    ///   file system.
+    pub fn load_file(
+        &mut self,
+        file_path: &DevicePath,
+        boot_policy: bool,
+        buffer: &mut [u8],
+    ) -> Result<usize, Option<usize>> {
+        let load_file = self.load_file;
+        load_file_impl(load_file, self, file_path, boot_policy, buffer)
+    }
+
+    /// Load a file into a newly allocated buffer
+    ///
+    /// This takes care of querying the file size before loading it. See
+    /// `load_file()` for a description of the arguments and errors.
+    #[cfg(feature = "exts")]
+    pub fn load_file_to_vec(
+        &mut self,
+        file_path: &DevicePath,
+        boot_policy: bool,
+    ) -> Result<Vec<u8>> {
+        let load_file = self.load_file;
+        load_file_to_vec_impl(load_file, self, file_path, boot_policy)
+    }
+}
+
+/// The Load File 2 protocol.
+///
+/// This protocol is used to obtain files from arbitrary devices which are not
+/// boot options, for example option ROMs or an initrd provided by the
+/// bootloader of an operating system.
+#[repr(C)]
+#[unsafe_guid("4006c0c1-fcb3-403e-996d-4a6c8724e06d")]
+#[derive(Protocol)]
+pub struct LoadFile2 {
+    load_file: LoadFileFn<LoadFile2>,
+}
+
+impl LoadFile2 {
+    /// Create an instance of the protocol backed by the given function
+    pub(crate) fn new(load_file: LoadFileFn<LoadFile2>) -> Self {
+        Self { load_file }
+    }
+
+    /// Load a file into `buffer`
+    ///
+    /// This function works like `LoadFile::load_file()`, except that there is
+    /// no boot policy: `file_path` must always match an exact file. The same
+    /// errors may be reported, except for `uefi::Status::WARN_FILE_SYSTEM`.
+    pub fn load_file(
+        &mut self,
+        file_path: &DevicePath,
+        buffer: &mut [u8],
+    ) -> Result<usize, Option<usize>> {
+        let load_file = self.load_file;
+        load_file_impl(load_file, self, file_path, false, buffer)
+    }
+
+    /// Load a file into a newly allocated buffer
+    ///
+    /// This takes care of querying the file size before loading it. See
+    /// `load_file()` for a description of the arguments and errors.
+    #[cfg(feature = "exts")]
+    pub fn load_file_to_vec(&mut self, file_path: &DevicePath) -> Result<Vec<u8>> {
+        let load_file = self.load_file;
+        load_file_to_vec_impl(load_file, self, file_path, false)
+    }
+}
+
+/// Shared implementation of the `load_file()` functions
+fn load_file_impl<P>(
+    load_file: LoadFileFn<P>,
+    this: &mut P,
+    file_path: &DevicePath,
+    boot_policy: bool,
+    buffer: &mut [u8],
+) -> Result<usize, Option<usize>> {
+    let mut buffer_size = buffer.len();
+    unsafe {
+        load_file(
+            this,
+            file_path,
+            boot_policy,
+            &mut buffer_size,
+            buffer.as_mut_ptr() as *mut c_void,
+        )
+    }
+    .into_with(
+        || buffer_size,
+        |s| {
+            if s == Status::BUFFER_TOO_SMALL {
+                Some(buffer_size)
+            } else {
+                None
+            }
+        },
+    )
+}
+
+/// Shared implementation of the `load_file_to_vec()` functions
+#[cfg(feature = "exts")]
+fn load_file_to_vec_impl<P>(
+    load_file: LoadFileFn<P>,
+    this: &mut P,
+    file_path: &DevicePath,
+    boot_policy: bool,
+) -> Result<Vec<u8>> {
+    // Query the size of the file
+    let size = match load_file_impl(load_file, this, file_path, boot_policy, &mut []) {
+        Ok(completion) => return Ok(completion.map(|_| Vec::new())),
+        Err(error) => match error.split() {
+            (Status::BUFFER_TOO_SMALL, Some(size)) => size,
+            (status, _) => return Err(status.into()),
+        },
+    };
+
+    // Load the file into a large enough buffer
+    let mut buffer = alloc_api::vec![0; size];
+    load_file_impl(load_file, this, file_path, boot_policy, &mut buffer)
+        .discard_errdata()
+        .map_inner(|size| {
+            buffer.truncate(size);
+            buffer
+        })
+}
@@ -0,0 +1,183 @@
+//! Legacy Master Boot Record parsing.
+//!
+//! This module reads the MBR of a disk directly through its `BlockIO`
+//! protocol, including the logical partitions described by the chain of
+//! Extended Boot Records of an extended partition. On GPT disks, the MBR is a
+//! protective MBR whose single partition covers the entire disk.
+
+use super::block::{BlockBuffer, BlockIO, Lba};
+use super::partition::{MbrOsType, MbrPartitionRecord};
+use crate::{Result, Status};
+use alloc_api::vec::Vec;
+use core::{fmt, mem, ptr};
+
+/// Signature found at the end of every MBR and EBR.
+pub const MBR_SIGNATURE: [u8; 2] = [0x55, 0xaa];
+
+/// Upper bound on the length of an EBR chain, to protect against loops.
+const MAX_LOGICAL_PARTITIONS: usize = 128;
+
+/// Legacy Master Boot Record, stored in LBA 0 of a disk.
+#[repr(C)]
+#[repr(packed)]
+#[derive(Clone, Copy)]
+pub struct MasterBootRecord {
+    /// Code executed by legacy BIOSes, unused by UEFI.
+    pub boot_code: [u8; 440],
+
+    /// Signature identifying the disk, unused by UEFI.
+    pub unique_mbr_signature: u32,
+
+    /// Unknown field, unused by UEFI.
+    pub unknown: u16,
+
+    /// The four primary partition records.
+    pub partition_records: [MbrPartitionRecord; 4],
+
+    /// Must be `MBR_SIGNATURE`.
+    pub signature: [u8; 2],
+}
+
+impl fmt::Debug for MasterBootRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let unique_mbr_signature = self.unique_mbr_signature;
+        let partition_records = self.partition_records;
+        f.debug_struct("MasterBootRecord")
+            .field("unique_mbr_signature", &unique_mbr_signature)
+            .field("partition_records", &partition_records)
+            .finish()
+    }
+}
+
+/// Legacy partition table of a disk.
+#[derive(Debug)]
+pub struct MbrDisk {
+    mbr: MasterBootRecord,
+    logical_partitions: Vec<MbrPartitionRecord>,
+}
+
+impl MbrDisk {
+    /// Read the MBR of a block device, and the chain of EBRs of its extended
+    /// partition if it has one.
+    ///
+    /// # Errors
+    /// * `uefi::Status::NO_MEDIA`          There is no media in the device.
+    /// * `uefi::Status::NOT_FOUND`         The device does not contain an MBR.
+    /// * `uefi::Status::VOLUME_CORRUPTED`  The chain of EBRs is invalid.
+    /// * `uefi::Status::DEVICE_ERROR`      The device reported an error while reading.
+    pub fn read(block_io: &BlockIO) -> Result<Self> {
+        let media = block_io.media();
+        if !media.is_media_present() {
+            return Err(Status::NO_MEDIA.into());
+        }
+        let block_size = media.block_size() as usize;
+        if block_size < mem::size_of::<MasterBootRecord>() {
+            return Err(Status::NOT_FOUND.into());
+        }
+
+        let mut block = BlockBuffer::new(block_io, block_size);
+        block.read(block_io, 0)?;
+        let mbr = parse_record(block.as_slice()).ok_or(Status::NOT_FOUND)?;
+
+        let mut disk = Self {
+            mbr,
+            logical_partitions: Vec::new(),
+        };
+        if !disk.is_protective() {
+            let extended = disk
+                .primary_partitions()
+                .find(|record| record.is_extended());
+            if let Some(&extended) = extended {
+                disk.logical_partitions = read_ebr_chain(block_io, &mut block, extended)?;
+            }
+        }
+        Ok(disk.into())
+    }
+
+    /// The Master Boot Record, as found in LBA 0.
+    pub fn master_boot_record(&self) -> &MasterBootRecord {
+        &self.mbr
+    }
+
+    /// True if this is the protective MBR of a GPT disk.
+    ///
+    /// The partitions of such a disk should be read from its GUID Partition
+    /// Table instead.
+    pub fn is_protective(&self) -> bool {
+        self.primary_partitions().any(|record| {
+            let os_type = record.os_type;
+            os_type == MbrOsType::GPT_PROTECTIVE
+        })
+    }
+
+    /// Iterate over the used primary partition records.
+    pub fn primary_partitions(&self) -> impl Iterator<Item = &MbrPartitionRecord> {
+        self.mbr
+            .partition_records
+            .iter()
+            .filter(|record| !record.is_unused())
+    }
+
+    /// Iterate over the logical partitions of the extended partition.
+    ///
+    /// Unlike in the EBRs they are read from, the `starting_lba` of these
+    /// records is relative to the start of the disk.
+    pub fn logical_partitions(&self) -> impl Iterator<Item = &MbrPartitionRecord> {
+        self.logical_partitions.iter()
+    }
+}
+
+/// Parse an MBR or EBR, returning `None` if its signature is invalid.
+fn parse_record(block: &[u8]) -> Option<MasterBootRecord> {
+    let mbr = unsafe { ptr::read_unaligned(block.as_ptr() as *const MasterBootRecord) };
+    if mbr.signature == MBR_SIGNATURE {
+        Some(mbr)
+    } else {
+        None
+    }
+}
+
+/// Follow the chain of EBRs of an extended partition.
+///
+/// In each EBR, the first record describes a logical partition relative to the
+/// EBR itself, and the second one points to the next EBR relative to the start
+/// of the extended partition.
+fn read_ebr_chain(
+    block_io: &BlockIO,
+    block: &mut BlockBuffer,
+    extended: MbrPartitionRecord,
+) -> core::result::Result<Vec<MbrPartitionRecord>, Status> {
+    let extended_start = extended.starting_lba;
+    let last_block = block_io.media().last_block();
+
+    let mut partitions = Vec::new();
+    let mut ebr_lba = extended_start;
+    loop {
+        if partitions.len() == MAX_LOGICAL_PARTITIONS || Lba::from(ebr_lba) > last_block {
+            return Err(Status::VOLUME_CORRUPTED);
+        }
+        block.read(block_io, ebr_lba.into())?;
+        let ebr = parse_record(block.as_slice()).ok_or(Status::VOLUME_CORRUPTED)?;
+
+        let mut logical = ebr.partition_records[0];
+        if !logical.is_unused() {
+            logical.starting_lba = ebr_lba
+                .checked_add(logical.starting_lba)
+                .ok_or(Status::VOLUME_CORRUPTED)?;
+            partitions.push(logical);
+        }
+
+        let next = ebr.partition_records[1];
+        if next.is_unused() {
+            return Ok(partitions);
+        }
+        let next_lba = extended_start
+            .checked_add(next.starting_lba)
+            .ok_or(Status::VOLUME_CORRUPTED)?;
+        // Each EBR must come after the previous one, which rules out loops
+        if next_lba <= ebr_lba {
+            return Err(Status::VOLUME_CORRUPTED);
+        }
+        ebr_lba = next_lba;
+    }
+}
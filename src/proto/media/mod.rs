@@ -7,5 +7,15 @@
 pub mod file;
 
 pub mod block;
+pub mod disk;
+pub mod erase_block;
 pub mod fs;
+#[cfg(feature = "exts")]
+pub mod gpt;
+pub mod initrd;
+pub mod load_file;
+#[cfg(feature = "exts")]
+pub mod mbr;
 pub mod partition;
+pub mod storage_security;
+pub mod tape;
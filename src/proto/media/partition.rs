@@ -1,7 +1,10 @@
 //! Partition information protocol.
 
+use crate::proto::device_path::{CdromDevicePath, DevicePath, DeviceSubType, DeviceType};
 use crate::proto::Protocol;
 use crate::{unsafe_guid, Char16, Guid};
+use bitflags::bitflags;
+use core::mem;
 
 newtype_enum! {
     /// MBR OS type.
@@ -14,6 +17,17 @@ newtype_enum! {
 
         /// UEFI system partition.
         UEFI_SYSTEM_PARTITION = 0xef,
+
+        /// Extended partition containing a chain of logical partitions,
+        /// addressed in CHS format.
+        EXTENDED = 0x05,
+
+        /// Extended partition containing a chain of logical partitions,
+        /// addressed in LBA format.
+        EXTENDED_LBA = 0x0f,
+
+        /// Extended partition used by Linux.
+        LINUX_EXTENDED = 0x85,
     }
 }
 
@@ -46,6 +60,22 @@ impl MbrPartitionRecord {
     pub fn is_bootable(&self) -> bool {
         self.boot_indicator == 0x80
     }
+
+    /// True if this record does not describe a partition.
+    pub fn is_unused(&self) -> bool {
+        let size_in_lba = self.size_in_lba;
+        self.os_type.0 == 0 || size_in_lba == 0
+    }
+
+    /// True if this record describes an extended partition, which contains
+    /// logical partitions.
+    pub fn is_extended(&self) -> bool {
+        let os_type = self.os_type;
+        matches!(
+            os_type,
+            MbrOsType::EXTENDED | MbrOsType::EXTENDED_LBA | MbrOsType::LINUX_EXTENDED
+        )
+    }
 }
 
 newtype_enum! {
@@ -82,6 +112,24 @@ newtype_enum! {
     }
 }
 
+bitflags! {
+    /// Attributes describing a GPT partition.
+    ///
+    /// Bits 3 through 47 are reserved by the UEFI specification, bits 48
+    /// through 63 are defined by the partition type.
+    #[repr(transparent)]
+    pub struct GptPartitionAttributes: u64 {
+        /// The partition is required for the platform to function.
+        const REQUIRED_PARTITION = 1;
+        /// Firmware must not produce an `BlockIO` protocol for this partition.
+        const NO_BLOCK_IO_PROTOCOL = 1 << 1;
+        /// The partition may be bootable by legacy BIOS firmware.
+        const LEGACY_BIOS_BOOTABLE = 1 << 2;
+        /// Mask of the bits whose meaning is specific to the partition type.
+        const TYPE_SPECIFIC_MASK = 0xffff_0000_0000_0000;
+    }
+}
+
 /// GPT/EFI Partition Entry.
 #[repr(C)]
 #[repr(packed)]
@@ -101,7 +149,7 @@ pub struct GptPartitionEntry {
     pub ending_lba: u64,
 
     /// All attribute bits of the partition.
-    pub attributes: u64,
+    pub attributes: GptPartitionAttributes,
 
     /// Null-terminated string containing a human-readable name of the
     /// partition.
@@ -117,8 +165,25 @@ impl GptPartitionEntry {
             .checked_sub(self.starting_lba)?
             .checked_add(1)
     }
+
+    /// True if this entry describes an EFI system partition.
+    pub fn is_efi_system_partition(&self) -> bool {
+        // Copy the field out of the packed struct before comparing it
+        let partition_type = self.partition_type_guid;
+        partition_type == GptPartitionType::EFI_SYSTEM_PARTITION
+    }
 }
 
+/// Vendor GUID of the media device path node that EDK II based firmware
+/// uses to expose UDF volumes as partitions.
+pub const UDF_DEVICE_PATH_GUID: Guid = Guid::from_values(
+    0x77ab535a,
+    0x45fc,
+    0x624b,
+    0x5560,
+    [0xf7, 0xb2, 0x81, 0xd1, 0xf9, 0x6e],
+);
+
 newtype_enum! {
     /// Partition type.
     pub enum PartitionType: u32 => {
@@ -196,4 +261,49 @@ impl PartitionInfo {
             None
         }
     }
+
+    /// Get the El Torito boot entry of a CD-ROM partition. Returns None if the
+    /// partition is not an El Torito partition.
+    ///
+    /// Such partitions have the `OTHER` partition type, and are identified by
+    /// the CD-ROM node ending `device_path`, which must be the device path of
+    /// the handle this protocol was opened on.
+    pub fn el_torito_entry<'a>(&self, device_path: &'a DevicePath) -> Option<&'a CdromDevicePath> {
+        if { self.partition_type } != PartitionType::OTHER {
+            return None;
+        }
+        device_path.node_iter().last()?.as_cdrom()
+    }
+
+    /// True if this is a UDF partition, such as the file system of a DVD.
+    ///
+    /// Such partitions have the `OTHER` partition type, and are identified by
+    /// the vendor node ending `device_path`, which must be the device path of
+    /// the handle this protocol was opened on. The UEFI specification does not
+    /// define how UDF volumes are exposed, so this only recognizes the scheme
+    /// used by EDK II.
+    pub fn is_udf(&self, device_path: &DevicePath) -> bool {
+        if { self.partition_type } != PartitionType::OTHER {
+            return false;
+        }
+        let node = match device_path.node_iter().last() {
+            Some(node) => node,
+            None => return false,
+        };
+        if node.device_type != DeviceType::MEDIA
+            || node.sub_type != DeviceSubType::MEDIA_VENDOR
+            || usize::from(node.length()) < mem::size_of::<DevicePath>() + mem::size_of::<Guid>()
+        {
+            return false;
+        }
+
+        // The vendor GUID immediately follows the node header
+        let guid = unsafe {
+            (node as *const DevicePath)
+                .add(1)
+                .cast::<Guid>()
+                .read_unaligned()
+        };
+        guid == UDF_DEVICE_PATH_GUID
+    }
 }
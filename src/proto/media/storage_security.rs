@@ -0,0 +1,121 @@
+//! Storage Security Command protocol.
+
+use crate::proto::Protocol;
+use crate::{unsafe_guid, Result, Status};
+
+/// The Storage Security Command protocol.
+///
+/// This protocol is used to send security protocol commands to a storage
+/// device, and to receive their responses. It is typically used to unlock
+/// self-encrypting drives through the TCG Opal or IEEE 1667 protocols.
+///
+/// Security protocols are identified by the SCSI or ATA security protocol ID
+/// that the device uses for them. The meaning of the protocol specific data
+/// depends on the protocol, see the SPC-4 and ATA8-ACS specifications.
+#[repr(C)]
+#[unsafe_guid("c88b0b6d-0dfc-49a7-9cb4-49074b4c3a78")]
+#[derive(Protocol)]
+pub struct StorageSecurityCommand {
+    receive_data: extern "efiapi" fn(
+        this: &mut StorageSecurityCommand,
+        media_id: u32,
+        timeout: u64,
+        security_protocol_id: u8,
+        security_protocol_specific_data: u16,
+        payload_buffer_size: usize,
+        payload_buffer: *mut u8,
+        payload_transfer_size: &mut usize,
+    ) -> Status,
+    send_data: extern "efiapi" fn(
+        this: &mut StorageSecurityCommand,
+        media_id: u32,
+        timeout: u64,
+        security_protocol_id: u8,
+        security_protocol_specific_data: u16,
+        payload_buffer_size: usize,
+        payload_buffer: *const u8,
+    ) -> Status,
+}
+
+impl StorageSecurityCommand {
+    /// Receive the response to a security protocol command from the device.
+    ///
+    /// Returns the number of bytes that were received. If `buffer` is too
+    /// small for the response, it is truncated and a
+    /// `uefi::Status::WARN_BUFFER_TOO_SMALL` warning is reported.
+    ///
+    /// # Arguments
+    /// * `media_id` - ID of the medium to receive data from.
+    /// * `timeout` - Timeout of the operation in units of 100ns, or 0 to wait
+    ///   indefinitely.
+    /// * `security_protocol_id` - Security protocol ID of the command.
+    /// * `security_protocol_specific_data` - Security protocol specific data
+    ///   of the command.
+    /// * `buffer` - Buffer receiving the response.
+    ///
+    /// # Errors
+    /// * `uefi::Status::UNSUPPORTED`        The device does not support security protocol
+    ///   commands, or the given security protocol.
+    /// * `uefi::Status::DEVICE_ERROR`       The device reported an error while attempting to
+    ///   perform the command.
+    /// * `uefi::Status::NO_MEDIA`           There is no media in the device.
+    /// * `uefi::Status::MEDIA_CHANGED`      The `media_id` is not for the current media.
+    /// * `uefi::Status::INVALID_PARAMETER`  The buffer is empty while the command requires
+    ///   a response.
+    /// * `uefi::Status::TIMEOUT`            The command did not complete in time.
+    pub fn receive_data(
+        &mut self,
+        media_id: u32,
+        timeout: u64,
+        security_protocol_id: u8,
+        security_protocol_specific_data: u16,
+        buffer: &mut [u8],
+    ) -> Result<usize> {
+        let mut transfer_size = 0;
+        (self.receive_data)(
+            self,
+            media_id,
+            timeout,
+            security_protocol_id,
+            security_protocol_specific_data,
+            buffer.len(),
+            buffer.as_mut_ptr(),
+            &mut transfer_size,
+        )
+        .into_with_val(|| transfer_size)
+    }
+
+    /// Send a security protocol command to the device.
+    ///
+    /// See `receive_data()` for a description of the arguments.
+    ///
+    /// # Errors
+    /// * `uefi::Status::UNSUPPORTED`        The device does not support security protocol
+    ///   commands, or the given security protocol.
+    /// * `uefi::Status::DEVICE_ERROR`       The device reported an error while attempting to
+    ///   perform the command.
+    /// * `uefi::Status::NO_MEDIA`           There is no media in the device.
+    /// * `uefi::Status::MEDIA_CHANGED`      The `media_id` is not for the current media.
+    /// * `uefi::Status::INVALID_PARAMETER`  The buffer is empty while the command requires
+    ///   a payload.
+    /// * `uefi::Status::TIMEOUT`            The command did not complete in time.
+    pub fn send_data(
+        &mut self,
+        media_id: u32,
+        timeout: u64,
+        security_protocol_id: u8,
+        security_protocol_specific_data: u16,
+        buffer: &[u8],
+    ) -> Result {
+        (self.send_data)(
+            self,
+            media_id,
+            timeout,
+            security_protocol_id,
+            security_protocol_specific_data,
+            buffer.len(),
+            buffer.as_ptr(),
+        )
+        .into()
+    }
+}
@@ -0,0 +1,136 @@
+//! Tape I/O protocol.
+
+use crate::proto::Protocol;
+use crate::{unsafe_guid, Result, Status};
+use core::ffi::c_void;
+
+/// The Tape I/O protocol.
+///
+/// This protocol provides services to control and access a tape device.
+#[repr(C)]
+#[unsafe_guid("1e93e633-d65a-459e-ab84-93d9ec266d18")]
+#[derive(Protocol)]
+pub struct TapeIo {
+    tape_read: extern "efiapi" fn(
+        this: &mut TapeIo,
+        buffer_size: &mut usize,
+        buffer: *mut c_void,
+    ) -> Status,
+    tape_write: extern "efiapi" fn(
+        this: &mut TapeIo,
+        buffer_size: &mut usize,
+        buffer: *const c_void,
+    ) -> Status,
+    tape_rewind: extern "efiapi" fn(this: &mut TapeIo) -> Status,
+    tape_space: extern "efiapi" fn(this: &mut TapeIo, direction: isize, ty: TapeMark) -> Status,
+    tape_write_fm: extern "efiapi" fn(this: &mut TapeIo, count: usize) -> Status,
+    tape_reset: extern "efiapi" fn(this: &mut TapeIo, extended_verification: bool) -> Status,
+}
+
+impl TapeIo {
+    /// Reads from the tape.
+    ///
+    /// Returns the number of bytes that were read. If an error occurs, the
+    /// number of bytes that were read before the error is reported as part of
+    /// the error.
+    ///
+    /// # Errors
+    /// * `uefi::Status::END_OF_MEDIA`       The end of the media was reached.
+    /// * `uefi::Status::END_OF_FILE`        A filemark was encountered.
+    /// * `uefi::Status::NO_MEDIA`           There is no media in the device.
+    /// * `uefi::Status::MEDIA_CHANGED`      The media in the device has changed.
+    /// * `uefi::Status::DEVICE_ERROR`       The device reported an error.
+    /// * `uefi::Status::NOT_READY`          The transfer failed because the device was not ready.
+    /// * `uefi::Status::TIMEOUT`            The transfer failed to complete in time.
+    pub fn read(&mut self, buffer: &mut [u8]) -> Result<usize, usize> {
+        let mut buffer_size = buffer.len();
+        (self.tape_read)(self, &mut buffer_size, buffer.as_mut_ptr() as *mut c_void)
+            .into_with(|| buffer_size, |_| buffer_size)
+    }
+
+    /// Writes to the tape.
+    ///
+    /// Returns the number of bytes that were written. If an error occurs, the
+    /// number of bytes that were written before the error is reported as part
+    /// of the error.
+    ///
+    /// # Errors
+    /// * `uefi::Status::END_OF_MEDIA`       The end of the media was reached.
+    /// * `uefi::Status::WRITE_PROTECTED`    The media in the device is write-protected.
+    /// * `uefi::Status::NO_MEDIA`           There is no media in the device.
+    /// * `uefi::Status::MEDIA_CHANGED`      The media in the device has changed.
+    /// * `uefi::Status::DEVICE_ERROR`       The device reported an error.
+    /// * `uefi::Status::NOT_READY`          The transfer failed because the device was not ready.
+    /// * `uefi::Status::TIMEOUT`            The transfer failed to complete in time.
+    pub fn write(&mut self, buffer: &[u8]) -> Result<usize, usize> {
+        let mut buffer_size = buffer.len();
+        (self.tape_write)(self, &mut buffer_size, buffer.as_ptr() as *const c_void)
+            .into_with(|| buffer_size, |_| buffer_size)
+    }
+
+    /// Rewinds the tape.
+    ///
+    /// # Errors
+    /// * `uefi::Status::NO_MEDIA`           There is no media in the device.
+    /// * `uefi::Status::MEDIA_CHANGED`      The media in the device has changed.
+    /// * `uefi::Status::DEVICE_ERROR`       The device reported an error.
+    /// * `uefi::Status::NOT_READY`          The device was not ready.
+    /// * `uefi::Status::TIMEOUT`            The rewind failed to complete in time.
+    pub fn rewind(&mut self) -> Result {
+        (self.tape_rewind)(self).into()
+    }
+
+    /// Positions the tape by spacing over data blocks or filemarks.
+    ///
+    /// # Arguments
+    /// * `count` - Number of marks to space over. Positive values move
+    ///   forward, negative values move backward.
+    /// * `mark` - Type of mark to space over.
+    ///
+    /// # Errors
+    /// * `uefi::Status::END_OF_MEDIA`       The end of the media was reached.
+    /// * `uefi::Status::NO_MEDIA`           There is no media in the device.
+    /// * `uefi::Status::MEDIA_CHANGED`      The media in the device has changed.
+    /// * `uefi::Status::DEVICE_ERROR`       The device reported an error.
+    /// * `uefi::Status::NOT_READY`          The device was not ready.
+    /// * `uefi::Status::TIMEOUT`            The operation failed to complete in time.
+    pub fn space(&mut self, count: isize, mark: TapeMark) -> Result {
+        (self.tape_space)(self, count, mark).into()
+    }
+
+    /// Writes `count` filemarks to the tape.
+    ///
+    /// # Errors
+    /// * `uefi::Status::END_OF_MEDIA`       The end of the media was reached.
+    /// * `uefi::Status::WRITE_PROTECTED`    The media in the device is write-protected.
+    /// * `uefi::Status::NO_MEDIA`           There is no media in the device.
+    /// * `uefi::Status::MEDIA_CHANGED`      The media in the device has changed.
+    /// * `uefi::Status::DEVICE_ERROR`       The device reported an error.
+    /// * `uefi::Status::NOT_READY`          The device was not ready.
+    /// * `uefi::Status::TIMEOUT`            The operation failed to complete in time.
+    pub fn write_filemarks(&mut self, count: usize) -> Result {
+        (self.tape_write_fm)(self, count).into()
+    }
+
+    /// Resets the tape device.
+    ///
+    /// If `extended_verification` is true, the driver may perform a more
+    /// exhaustive verification operation of the device during reset.
+    ///
+    /// # Errors
+    /// * `uefi::Status::DEVICE_ERROR`       The device is not functioning correctly and could
+    ///   not be reset.
+    pub fn reset(&mut self, extended_verification: bool) -> Result {
+        (self.tape_reset)(self, extended_verification).into()
+    }
+}
+
+newtype_enum! {
+    /// Type of mark to space over with `TapeIo::space()`
+    pub enum TapeMark: usize => {
+        /// Data block
+        BLOCK = 0,
+        /// Filemark
+        FILEMARK = 1,
+    }
+}
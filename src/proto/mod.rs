@@ -26,10 +26,13 @@ pub trait Protocol: Identify {}
 
 pub use uefi_macros::Protocol;
 
+pub mod bluetooth;
 pub mod console;
 pub mod debug;
 pub mod device_path;
+pub mod hii;
 pub mod loaded_image;
 pub mod media;
+pub mod network;
 pub mod pi;
 pub mod shim;
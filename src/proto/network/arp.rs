@@ -0,0 +1,411 @@
+//! Address resolution protocol.
+//!
+//! This protocol resolves the protocol (software) addresses of the hosts of a
+//! network, such as IPv4 addresses, to their hardware addresses, and manages
+//! the cache of the resolved addresses. It lets network stacks built on top of
+//! the simple or managed network protocols rely on the firmware for it.
+
+use super::{Ipv4Address, MacAddress, ServiceBinding};
+use crate::proto::Protocol;
+use crate::table::boot::BootServices;
+use crate::{unsafe_guid, Event, Result, ResultExt, Status};
+use core::ffi::c_void;
+use core::marker::PhantomData;
+use core::ops::Deref;
+use core::{ptr, slice};
+
+/// Protocol (EtherType) of IPv4, as used for the software addresses of ARP.
+pub const IPV4_ADDRESS_TYPE: u16 = 0x0800;
+
+/// Service binding creating `Arp` instances.
+///
+/// It is installed on the handles of the network interfaces.
+#[repr(transparent)]
+#[unsafe_guid("f44c00ee-1f2c-4a00-aa09-1c9f3e0800a3")]
+#[derive(Protocol)]
+pub struct ArpServiceBinding(ServiceBinding);
+
+impl Deref for ArpServiceBinding {
+    type Target = ServiceBinding;
+
+    fn deref(&self) -> &ServiceBinding {
+        &self.0
+    }
+}
+
+/// Address resolution for one protocol on a network interface.
+///
+/// An instance must be configured with `configure()` before it can be used.
+/// Software addresses are passed as raw bytes, whose size is the one of the
+/// station address of the configuration.
+#[repr(C)]
+#[unsafe_guid("f4b427bb-ba21-4f16-bc4e-43e416ab619c")]
+#[derive(Protocol)]
+pub struct Arp {
+    configure: extern "efiapi" fn(this: &Arp, config_data: *const ArpConfigData) -> Status,
+    add: extern "efiapi" fn(
+        this: &Arp,
+        deny_flag: bool,
+        target_sw_address: *const c_void,
+        target_hw_address: *const c_void,
+        timeout_value: u32,
+        overwrite: bool,
+    ) -> Status,
+    find: extern "efiapi" fn(
+        this: &Arp,
+        by_sw_address: bool,
+        address_buffer: *const c_void,
+        entry_length: *mut u32,
+        entry_count: *mut u32,
+        entries: *mut *mut u8,
+        refresh: bool,
+    ) -> Status,
+    delete: extern "efiapi" fn(
+        this: &Arp,
+        by_sw_address: bool,
+        address_buffer: *const c_void,
+    ) -> Status,
+    flush: extern "efiapi" fn(this: &Arp) -> Status,
+    request: unsafe extern "efiapi" fn(
+        this: &Arp,
+        target_sw_address: *const c_void,
+        resolved_event: Event,
+        target_hw_address: *mut c_void,
+    ) -> Status,
+    cancel: extern "efiapi" fn(
+        this: &Arp,
+        target_sw_address: *const c_void,
+        resolved_event: Event,
+    ) -> Status,
+}
+
+impl Arp {
+    /// Configures this instance, or resets it if `config_data` is `None`.
+    ///
+    /// Resetting cancels the pending requests of the instance, and removes
+    /// the cache entries it added.
+    ///
+    /// # Errors
+    ///
+    /// - `AccessDenied` if the instance is already configured for another
+    ///   station address
+    /// - `OutOfResources` if there are not enough resources for the instance
+    pub fn configure(&self, config_data: Option<&ArpConfigData>) -> Result {
+        let config_data = config_data.map_or(ptr::null(), |config| config as *const _);
+        (self.configure)(self, config_data).into()
+    }
+
+    /// Adds an entry to the cache, mapping `sw_address` to `hw_address`.
+    ///
+    /// If `hw_address` is `None`, the software address is denied instead,
+    /// and requests for it fail. The entry is removed after `timeout`, in
+    /// units of 100ns, or is static if it is 0.
+    ///
+    /// # Errors
+    ///
+    /// - `AccessDenied` if the entry exists, and `overwrite` is false
+    /// - `NotStarted` if this instance is not configured
+    pub fn add(
+        &self,
+        sw_address: &[u8],
+        hw_address: Option<&MacAddress>,
+        timeout: u32,
+        overwrite: bool,
+    ) -> Result {
+        (self.add)(
+            self,
+            hw_address.is_none(),
+            sw_address.as_ptr() as *const c_void,
+            hw_address.map_or(ptr::null(), |addr| addr.0.as_ptr() as *const c_void),
+            timeout,
+            overwrite,
+        )
+        .into()
+    }
+
+    /// Looks up the cache entries of this instance with the given software or
+    /// hardware address, or all of them if `address` is `None`.
+    ///
+    /// If `refresh` is true, the timeout of the entries found is restarted.
+    ///
+    /// # Errors
+    ///
+    /// - `NotFound` if no entry matches the address
+    /// - `NotStarted` if this instance is not configured
+    pub fn find<'boot>(
+        &self,
+        bt: &'boot BootServices,
+        by_sw_address: bool,
+        address: Option<&[u8]>,
+        refresh: bool,
+    ) -> Result<ArpEntries<'boot>> {
+        let mut entry_length = 0;
+        let mut entry_count = 0;
+        let mut entries = ptr::null_mut();
+        (self.find)(
+            self,
+            by_sw_address,
+            address.map_or(ptr::null(), |addr| addr.as_ptr() as *const c_void),
+            &mut entry_length,
+            &mut entry_count,
+            &mut entries,
+            refresh,
+        )
+        .into_with_val(|| ArpEntries {
+            bt,
+            buffer: entries,
+            entry_length: entry_length as usize,
+            entry_count: entry_count as usize,
+        })
+    }
+
+    /// Removes the cache entries of this instance with the given software or
+    /// hardware address, or all of them if `address` is `None`.
+    ///
+    /// # Errors
+    ///
+    /// - `NotFound` if no entry matches the address
+    /// - `NotStarted` if this instance is not configured
+    pub fn delete(&self, by_sw_address: bool, address: Option<&[u8]>) -> Result {
+        let address = address.map_or(ptr::null(), |addr| addr.as_ptr() as *const c_void);
+        (self.delete)(self, by_sw_address, address).into()
+    }
+
+    /// Removes all the dynamic entries of the cache, including those of the
+    /// other instances.
+    ///
+    /// # Errors
+    ///
+    /// - `NotFound` if the cache has no dynamic entry
+    /// - `NotStarted` if this instance is not configured
+    pub fn flush(&self) -> Result {
+        (self.flush)(self).into()
+    }
+
+    /// Starts resolving `sw_address`, writing its hardware address into
+    /// `hw_address` once done.
+    ///
+    /// If the address is in the cache, it is resolved immediately. Otherwise,
+    /// `resolved_event` is signaled once the resolution completes or times
+    /// out, and `NotReady` is returned.
+    ///
+    /// # Safety
+    ///
+    /// `hw_address` may not be moved, freed or accessed until the resolution
+    /// completed or was canceled.
+    ///
+    /// # Errors
+    ///
+    /// - `NotReady` if the resolution is pending
+    /// - `AccessDenied` if the address is denied by the cache
+    /// - `NotStarted` if this instance is not configured
+    pub unsafe fn request(
+        &self,
+        sw_address: &[u8],
+        resolved_event: Option<Event>,
+        hw_address: &mut MacAddress,
+    ) -> Result {
+        (self.request)(
+            self,
+            sw_address.as_ptr() as *const c_void,
+            resolved_event.unwrap_or_else(Event::null),
+            hw_address.0.as_mut_ptr() as *mut c_void,
+        )
+        .into()
+    }
+
+    /// Cancels the pending resolutions of `sw_address` signaling
+    /// `resolved_event`, or all of them for each `None`.
+    ///
+    /// # Errors
+    ///
+    /// - `NotFound` if no resolution is pending
+    /// - `NotStarted` if this instance is not configured
+    pub fn cancel(&self, sw_address: Option<&[u8]>, resolved_event: Option<Event>) -> Result {
+        let sw_address = sw_address.map_or(ptr::null(), |addr| addr.as_ptr() as *const c_void);
+        let resolved_event = resolved_event.unwrap_or_else(Event::null);
+        (self.cancel)(self, sw_address, resolved_event).into()
+    }
+
+    /// Resolves `sw_address` to a hardware address, waiting for the answer
+    /// of the network if it is not in the cache.
+    ///
+    /// `event` is signaled by the firmware when the resolution completes, and
+    /// must therefore be usable with `BootServices::wait_for_event`, i.e. it
+    /// must not be of type `EventType::NOTIFY_SIGNAL`.
+    ///
+    /// The errors are those of `request()`, plus `Timeout` if no host
+    /// answered.
+    ///
+    /// # Panics
+    ///
+    /// Panics if waiting for `event` fails, since the firmware may still
+    /// write the hardware address at that point.
+    pub fn resolve(
+        &self,
+        bt: &BootServices,
+        event: Event,
+        sw_address: &[u8],
+    ) -> Result<MacAddress> {
+        let mut hw_address = MacAddress::default();
+        match unsafe { self.request(sw_address, Some(event), &mut hw_address) } {
+            Ok(completion) => return Ok(completion.map(|()| hw_address)),
+            Err(err) if err.status() == Status::NOT_READY => {}
+            Err(err) => return Err(err),
+        }
+        bt.wait_for_event(&mut [event])
+            .expect_success("Failed to wait for a pending address resolution");
+        // The hardware address is left untouched if the resolution failed
+        if hw_address == MacAddress::default() {
+            Err(Status::TIMEOUT.into())
+        } else {
+            Ok(hw_address.into())
+        }
+    }
+}
+
+/// Configuration of an `Arp` instance.
+#[derive(Debug)]
+#[repr(C)]
+pub struct ArpConfigData<'addr> {
+    sw_address_type: u16,
+    sw_address_length: u8,
+    station_address: *const u8,
+    /// Lifetime of the dynamic cache entries, in units of 100ns, or 0 for
+    /// the default.
+    pub entry_time_out: u32,
+    /// Number of retries of the requests before giving up, or 0 for the
+    /// default.
+    pub retry_count: u32,
+    /// Time between the retries of the requests, in units of 100ns, or 0 for
+    /// the default.
+    pub retry_time_out: u32,
+    _address: PhantomData<&'addr [u8]>,
+}
+
+impl<'addr> ArpConfigData<'addr> {
+    /// Creates a configuration for resolving addresses of the protocol
+    /// (EtherType) `sw_address_type`, on behalf of `station_address`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the station address is longer than 255 bytes.
+    pub fn new(sw_address_type: u16, station_address: &'addr [u8]) -> Self {
+        assert!(
+            station_address.len() <= usize::from(u8::MAX),
+            "Station address is too long"
+        );
+        Self {
+            sw_address_type,
+            sw_address_length: station_address.len() as u8,
+            station_address: station_address.as_ptr(),
+            entry_time_out: 0,
+            retry_count: 0,
+            retry_time_out: 0,
+            _address: PhantomData,
+        }
+    }
+
+    /// Creates a configuration for resolving IPv4 addresses, on behalf of
+    /// `station_address`.
+    pub fn ipv4(station_address: &'addr Ipv4Address) -> Self {
+        Self::new(IPV4_ADDRESS_TYPE, &station_address.0)
+    }
+}
+
+/// Cache entries returned by `Arp::find()`.
+///
+/// The entries are freed when this object is dropped.
+pub struct ArpEntries<'boot> {
+    bt: &'boot BootServices,
+    buffer: *mut u8,
+    entry_length: usize,
+    entry_count: usize,
+}
+
+impl ArpEntries<'_> {
+    /// Number of entries.
+    pub fn len(&self) -> usize {
+        self.entry_count
+    }
+
+    /// True if there is no entry.
+    pub fn is_empty(&self) -> bool {
+        self.entry_count == 0
+    }
+
+    /// Returns an entry, or `None` if the index is out of bounds.
+    pub fn get(&self, index: usize) -> Option<ArpEntry<'_>> {
+        if index >= self.entry_count || self.entry_length < ENTRY_HEADER_SIZE {
+            return None;
+        }
+        let data = unsafe {
+            slice::from_raw_parts(
+                self.buffer.add(index * self.entry_length),
+                self.entry_length,
+            )
+        };
+        Some(ArpEntry { data })
+    }
+
+    /// Returns an iterator over the entries.
+    pub fn iter(&self) -> impl Iterator<Item = ArpEntry<'_>> {
+        (0..self.entry_count).filter_map(move |index| self.get(index))
+    }
+}
+
+impl Drop for ArpEntries<'_> {
+    fn drop(&mut self) {
+        if !self.buffer.is_null() {
+            // Nothing more can be done if this fails
+            let _ = self.bt.free_pool(self.buffer);
+        }
+    }
+}
+
+/// Size of the header of a cache entry, before its addresses.
+const ENTRY_HEADER_SIZE: usize = 12;
+
+/// Entry of the ARP cache.
+#[derive(Debug, Copy, Clone)]
+pub struct ArpEntry<'buf> {
+    data: &'buf [u8],
+}
+
+impl<'buf> ArpEntry<'buf> {
+    /// True if the software address is denied.
+    pub fn is_denied(&self) -> bool {
+        self.data[4] != 0
+    }
+
+    /// True if the entry does not expire.
+    pub fn is_static(&self) -> bool {
+        self.data[5] != 0
+    }
+
+    /// Type of the hardware address, as an ARP hardware type.
+    pub fn hw_address_type(&self) -> u16 {
+        u16::from_ne_bytes([self.data[6], self.data[7]])
+    }
+
+    /// Protocol (EtherType) of the software address.
+    pub fn sw_address_type(&self) -> u16 {
+        u16::from_ne_bytes([self.data[8], self.data[9]])
+    }
+
+    /// Software address of the entry.
+    pub fn sw_address(&self) -> &'buf [u8] {
+        let start = ENTRY_HEADER_SIZE;
+        self.data
+            .get(start..start + usize::from(self.data[11]))
+            .unwrap_or(&[])
+    }
+
+    /// Hardware address of the entry, or an empty slice if it is denied.
+    pub fn hw_address(&self) -> &'buf [u8] {
+        let start = ENTRY_HEADER_SIZE + usize::from(self.data[11]);
+        self.data
+            .get(start..start + usize::from(self.data[10]))
+            .unwrap_or(&[])
+    }
+}
@@ -0,0 +1,99 @@
+//! Packets processed by the crypto protocols of the network stack.
+//!
+//! Both the TLS and the supplicant protocols encrypt and decrypt packets in
+//! place of their caller, and hand back the processed packet as a new table
+//! of fragments, allocated by the firmware.
+
+use super::FragmentData;
+use crate::table::boot::BootServices;
+use crate::{Result, Status};
+use core::ffi::c_void;
+use core::{ptr, slice};
+
+/// Packet encrypted or decrypted by the firmware.
+///
+/// The packet is freed when this is dropped.
+pub struct CryptPacket<'boot> {
+    bt: &'boot BootServices,
+    table: *mut FragmentData,
+    count: u32,
+}
+
+impl<'boot> CryptPacket<'boot> {
+    /// Passes `data` as a table of one fragment to `process`, which may
+    /// replace it with a table of processed fragments
+    pub(super) fn process(
+        bt: &'boot BootServices,
+        data: &[u8],
+        process: impl FnOnce(&mut *mut FragmentData, &mut u32) -> Status,
+    ) -> Result<Self> {
+        let mut input = FragmentData {
+            fragment_length: data.len() as u32,
+            fragment_buffer: data.as_ptr() as *mut c_void,
+        };
+        let input_table: *mut FragmentData = &mut input;
+        let mut table = input_table;
+        let mut count = 1;
+        let status = process(&mut table, &mut count);
+        // The firmware replaces the table when it outputs a new packet
+        let table = if table == input_table {
+            ptr::null_mut()
+        } else {
+            table
+        };
+        let packet = Self { bt, table, count };
+        status.into_with_val(|| packet)
+    }
+}
+
+impl CryptPacket<'_> {
+    /// Iterates over the fragments of the packet.
+    pub fn fragments(&self) -> impl Iterator<Item = &[u8]> + '_ {
+        let table = if self.table.is_null() {
+            &[][..]
+        } else {
+            unsafe { slice::from_raw_parts(self.table, self.count as usize) }
+        };
+        table.iter().map(|fragment| unsafe {
+            slice::from_raw_parts(
+                fragment.fragment_buffer as *const u8,
+                fragment.fragment_length as usize,
+            )
+        })
+    }
+
+    /// Total size of the packet.
+    pub fn len(&self) -> usize {
+        self.fragments().map(|fragment| fragment.len()).sum()
+    }
+
+    /// True if the packet is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Copies as much of the packet as fits into `buffer`, and returns the
+    /// number of bytes copied.
+    pub fn copy_to(&self, buffer: &mut [u8]) -> usize {
+        let mut copied = 0;
+        for fragment in self.fragments() {
+            let len = fragment.len().min(buffer.len() - copied);
+            buffer[copied..copied + len].copy_from_slice(&fragment[..len]);
+            copied += len;
+        }
+        copied
+    }
+}
+
+impl Drop for CryptPacket<'_> {
+    fn drop(&mut self) {
+        if self.table.is_null() {
+            return;
+        }
+        // Nothing more can be done if freeing fails
+        for fragment in unsafe { slice::from_raw_parts(self.table, self.count as usize) } {
+            let _ = self.bt.free_pool(fragment.fragment_buffer as *mut u8);
+        }
+        let _ = self.bt.free_pool(self.table as *mut u8);
+    }
+}
@@ -0,0 +1,523 @@
+//! DHCPv4 protocol.
+//!
+//! This protocol obtains an IPv4 address lease for a network interface from a
+//! DHCP server, and gives access to the options sent by the server, such as
+//! the next-server address and the boot file name used for network boot.
+
+use super::{Ipv4Address, MacAddress, ServiceBinding};
+use crate::proto::Protocol;
+use crate::{unsafe_guid, Event, Result, Status};
+use core::ffi::c_void;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ops::Deref;
+use core::{ptr, slice};
+
+/// Service binding creating `Dhcp4` instances.
+///
+/// It is installed on the handles of the network interfaces.
+#[repr(transparent)]
+#[unsafe_guid("9d9a39d8-bd42-4a73-a4d5-8ee94be11380")]
+#[derive(Protocol)]
+pub struct Dhcp4ServiceBinding(ServiceBinding);
+
+impl Deref for Dhcp4ServiceBinding {
+    type Target = ServiceBinding;
+
+    fn deref(&self) -> &ServiceBinding {
+        &self.0
+    }
+}
+
+/// DHCPv4 client of a network interface.
+///
+/// An instance must be configured with `configure()`, then started with
+/// `start()` to obtain a lease.
+#[repr(C)]
+#[unsafe_guid("8a219718-4ef5-4761-91c8-c0f04bda9e56")]
+#[derive(Protocol)]
+pub struct Dhcp4 {
+    get_mode_data: extern "efiapi" fn(this: &Dhcp4, mode_data: *mut Dhcp4ModeData) -> Status,
+    configure: extern "efiapi" fn(this: &Dhcp4, config_data: *const Dhcp4ConfigData) -> Status,
+    start: extern "efiapi" fn(this: &Dhcp4, completion_event: Event) -> Status,
+    renew_rebind:
+        extern "efiapi" fn(this: &Dhcp4, rebind_request: bool, completion_event: Event) -> Status,
+    release: extern "efiapi" fn(this: &Dhcp4) -> Status,
+    stop: extern "efiapi" fn(this: &Dhcp4) -> Status,
+    build: usize,
+    transmit_receive: usize,
+    parse: usize,
+}
+
+impl Dhcp4 {
+    /// Returns the current state of this instance, and the lease it holds.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if the network interface could not be queried
+    pub fn mode_data(&self) -> Result<Dhcp4ModeData> {
+        let mut mode_data = MaybeUninit::<Dhcp4ModeData>::uninit();
+        (self.get_mode_data)(self, mode_data.as_mut_ptr())
+            .into_with_val(|| unsafe { mode_data.assume_init() })
+    }
+
+    /// Configures this instance, or resets it if `config_data` is `None`.
+    ///
+    /// An instance can only be configured in the `STOPPED` or `INIT` states,
+    /// and resetting it releases its lease.
+    ///
+    /// # Errors
+    ///
+    /// - `AccessDenied` if the instance is not in a state where it can be
+    ///   configured, or another instance is configured on the interface
+    /// - `InvalidParameter` if the configuration is not valid
+    pub fn configure(&self, config_data: Option<&Dhcp4ConfigData>) -> Result {
+        let config_data = config_data.map_or(ptr::null(), |config| config as *const _);
+        (self.configure)(self, config_data).into()
+    }
+
+    /// Starts the DHCP process, to obtain a lease.
+    ///
+    /// If `completion_event` is `None`, this function waits for the process to
+    /// complete. Otherwise, it returns immediately, and the event is signaled
+    /// once the process completes.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if the instance is not configured
+    /// - `AlreadyStarted` if the process was already started
+    /// - `Timeout` if no lease could be obtained
+    /// - `Aborted` if the callback aborted the process
+    pub fn start(&self, completion_event: Option<Event>) -> Result {
+        (self.start)(self, completion_event.unwrap_or_else(Event::null)).into()
+    }
+
+    /// Extends the current lease, from the server it was obtained from, or
+    /// from any server if `rebind_request` is true.
+    ///
+    /// See `start()` for the meaning of `completion_event`.
+    ///
+    /// # Errors
+    ///
+    /// - `AccessDenied` if the instance does not hold a lease
+    /// - `Timeout` if the lease could not be extended
+    pub fn renew_rebind(&self, rebind_request: bool, completion_event: Option<Event>) -> Result {
+        let completion_event = completion_event.unwrap_or_else(Event::null);
+        (self.renew_rebind)(self, rebind_request, completion_event).into()
+    }
+
+    /// Releases the current lease, and goes back to the `INIT` state.
+    ///
+    /// # Errors
+    ///
+    /// - `AccessDenied` if the instance does not hold a lease
+    pub fn release(&self) -> Result {
+        (self.release)(self).into()
+    }
+
+    /// Stops the DHCP process, and goes to the `STOPPED` state.
+    ///
+    /// The current lease is dropped without being released.
+    pub fn stop(&self) -> Result {
+        (self.stop)(self).into()
+    }
+}
+
+newtype_enum! {
+    /// State of the DHCP process of a `Dhcp4` instance.
+    pub enum Dhcp4State: u32 => {
+        /// The instance is not configured, or was stopped.
+        STOPPED     = 0,
+        /// The instance is configured, and the process can be started.
+        INIT        = 1,
+        /// Offers of the servers are being collected.
+        SELECTING   = 2,
+        /// An offer was selected, and is being requested.
+        REQUESTING  = 3,
+        /// A lease was obtained.
+        BOUND       = 4,
+        /// The lease is being extended from the server it was obtained from.
+        RENEWING    = 5,
+        /// The lease is being extended from any server.
+        REBINDING   = 6,
+        /// A previously allocated address is about to be requested again.
+        INIT_REBOOT = 7,
+        /// A previously allocated address is being requested again.
+        REBOOTING   = 8,
+    }
+}
+
+newtype_enum! {
+    /// Events of the DHCP process, reported to `Dhcp4ConfigData` callbacks.
+    pub enum Dhcp4Event: u32 => {
+        /// A DHCPDISCOVER packet is about to be sent.
+        SEND_DISCOVER   = 0x01,
+        /// A DHCPOFFER packet was received.
+        RCVD_OFFER      = 0x02,
+        /// All the offers were collected, and one is about to be selected.
+        SELECT_OFFER    = 0x03,
+        /// A DHCPREQUEST packet is about to be sent.
+        SEND_REQUEST    = 0x04,
+        /// A DHCPACK packet was received.
+        RCVD_ACK        = 0x05,
+        /// A DHCPNAK packet was received.
+        RCVD_NAK        = 0x06,
+        /// A DHCPDECLINE packet is about to be sent.
+        SEND_DECLINE    = 0x07,
+        /// A lease was obtained.
+        BOUND_COMPLETED = 0x08,
+        /// The lease is about to be renewed.
+        ENTER_RENEWING  = 0x09,
+        /// The lease is about to be rebound.
+        ENTER_REBINDING = 0x0a,
+        /// The lease expired, and the address was lost.
+        ADDRESS_LOST    = 0x0b,
+        /// The DHCP process failed.
+        FAIL            = 0x0c,
+    }
+}
+
+/// Type of the raw callbacks of the DHCP process.
+type Dhcp4Callback = extern "efiapi" fn(
+    this: &Dhcp4,
+    context: *mut c_void,
+    current_state: Dhcp4State,
+    dhcp4_event: Dhcp4Event,
+    packet: *const Dhcp4Packet,
+    new_packet: *mut *mut Dhcp4Packet,
+) -> Status;
+
+/// Configuration of a `Dhcp4` instance.
+#[repr(C)]
+pub struct Dhcp4ConfigData<'a> {
+    /// Number of DHCPDISCOVER packets sent before giving up, or 0 for the
+    /// default.
+    pub discover_try_count: u32,
+    discover_timeout: *const u32,
+    /// Number of DHCPREQUEST packets sent before giving up, or 0 for the
+    /// default.
+    pub request_try_count: u32,
+    request_timeout: *const u32,
+    /// Address previously allocated to the client, to be requested again, or
+    /// zero.
+    pub client_address: Ipv4Address,
+    callback: Option<Dhcp4Callback>,
+    callback_context: *mut c_void,
+    option_count: u32,
+    option_list: *const Dhcp4OptionRef<'a>,
+    _data: PhantomData<&'a mut ()>,
+}
+
+impl Default for Dhcp4ConfigData<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Dhcp4ConfigData<'a> {
+    /// Creates a configuration using the default timeouts, and without
+    /// callback or additional option.
+    pub fn new() -> Self {
+        Self {
+            discover_try_count: 0,
+            discover_timeout: ptr::null(),
+            request_try_count: 0,
+            request_timeout: ptr::null(),
+            client_address: Ipv4Address::UNSPECIFIED,
+            callback: None,
+            callback_context: ptr::null_mut(),
+            option_count: 0,
+            option_list: ptr::null(),
+            _data: PhantomData,
+        }
+    }
+
+    /// Sets the timeouts between the retransmissions of DHCPDISCOVER packets,
+    /// in seconds, which also sets `discover_try_count`.
+    pub fn set_discover_timeouts(&mut self, timeouts: &'a [u32]) {
+        self.discover_try_count = timeouts.len() as u32;
+        self.discover_timeout = timeouts.as_ptr();
+    }
+
+    /// Sets the timeouts between the retransmissions of DHCPREQUEST packets,
+    /// in seconds, which also sets `request_try_count`.
+    pub fn set_request_timeouts(&mut self, timeouts: &'a [u32]) {
+        self.request_try_count = timeouts.len() as u32;
+        self.request_timeout = timeouts.as_ptr();
+    }
+
+    /// Sets the options added to the packets sent by the client, such as a
+    /// parameter request list.
+    pub fn set_options(&mut self, options: &'a [Dhcp4OptionRef<'a>]) {
+        self.option_count = options.len() as u32;
+        self.option_list = options.as_ptr();
+    }
+
+    /// Sets a callback, called at each event of the DHCP process with the
+    /// current state and the packet sent or received, if any.
+    ///
+    /// The callback returns `Status::SUCCESS` to continue the process, or
+    /// `Status::ABORTED` to abort it. On `Dhcp4Event::RCVD_OFFER`, it can also
+    /// return `Status::NOT_READY` to keep collecting offers.
+    ///
+    /// # Safety
+    ///
+    /// The firmware keeps a reference to the callback, which must therefore
+    /// remain valid until the instance is reset or reconfigured.
+    pub unsafe fn set_callback<F>(&mut self, callback: &'a mut F)
+    where
+        F: FnMut(Dhcp4State, Dhcp4Event, Option<&Dhcp4Packet>) -> Status,
+    {
+        extern "efiapi" fn trampoline<F>(
+            _this: &Dhcp4,
+            context: *mut c_void,
+            current_state: Dhcp4State,
+            dhcp4_event: Dhcp4Event,
+            packet: *const Dhcp4Packet,
+            _new_packet: *mut *mut Dhcp4Packet,
+        ) -> Status
+        where
+            F: FnMut(Dhcp4State, Dhcp4Event, Option<&Dhcp4Packet>) -> Status,
+        {
+            let callback = unsafe { &mut *(context as *mut F) };
+            callback(current_state, dhcp4_event, unsafe { packet.as_ref() })
+        }
+        self.callback = Some(trampoline::<F>);
+        self.callback_context = callback as *mut F as *mut c_void;
+    }
+}
+
+/// Current state of a `Dhcp4` instance, and the lease it holds.
+#[repr(C)]
+pub struct Dhcp4ModeData {
+    /// State of the DHCP process.
+    pub state: Dhcp4State,
+    config_data: Dhcp4ConfigData<'static>,
+    /// Address allocated to the client.
+    pub client_address: Ipv4Address,
+    /// Hardware address of the client.
+    pub client_mac_address: MacAddress,
+    /// Address of the server which allocated the address.
+    pub server_address: Ipv4Address,
+    /// Address of the default router.
+    pub router_address: Ipv4Address,
+    /// Subnet mask of the allocated address.
+    pub subnet_mask: Ipv4Address,
+    /// Duration of the lease, in seconds, or `u32::MAX` if it is infinite.
+    pub lease_time: u32,
+    reply_packet: *const Dhcp4Packet,
+}
+
+impl Dhcp4ModeData {
+    /// Returns the DHCPACK packet of the server, which contains the options
+    /// of the lease.
+    ///
+    /// # Safety
+    ///
+    /// The packet belongs to the instance, and must not be accessed after the
+    /// state of the instance changed.
+    pub unsafe fn reply_packet(&self) -> Option<&Dhcp4Packet> {
+        self.reply_packet.as_ref()
+    }
+}
+
+/// Offsets of the fields of the fixed part of a DHCP packet.
+const YOUR_ADDRESS_OFFSET: usize = 16;
+const SERVER_ADDRESS_OFFSET: usize = 20;
+const GATEWAY_ADDRESS_OFFSET: usize = 24;
+const SERVER_NAME_OFFSET: usize = 44;
+const SERVER_NAME_SIZE: usize = 64;
+const BOOT_FILE_NAME_OFFSET: usize = 108;
+const BOOT_FILE_NAME_SIZE: usize = 128;
+const OPTIONS_OFFSET: usize = 240;
+
+/// DHCP packet, as sent or received by a `Dhcp4` instance.
+#[repr(C)]
+pub struct Dhcp4Packet {
+    size: u32,
+    length: u32,
+    data: [u8; 0],
+}
+
+impl Dhcp4Packet {
+    /// Returns the bytes of the packet, starting with its BOOTP header.
+    pub fn as_bytes(&self) -> &[u8] {
+        let length = self.length.min(self.size) as usize;
+        unsafe { slice::from_raw_parts(self.data.as_ptr(), length) }
+    }
+
+    /// Message operation code: 1 for a request, 2 for a reply.
+    pub fn op_code(&self) -> u8 {
+        self.as_bytes().first().copied().unwrap_or(0)
+    }
+
+    /// Transaction ID of the packet.
+    pub fn transaction_id(&self) -> u32 {
+        let bytes = self.field(4, 4);
+        u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+
+    /// Address allocated to the client by the server (`yiaddr`).
+    pub fn your_address(&self) -> Ipv4Address {
+        self.address(YOUR_ADDRESS_OFFSET)
+    }
+
+    /// Address of the next server to be used for network boot (`siaddr`).
+    pub fn server_address(&self) -> Ipv4Address {
+        self.address(SERVER_ADDRESS_OFFSET)
+    }
+
+    /// Address of the relay agent of the packet (`giaddr`).
+    pub fn gateway_address(&self) -> Ipv4Address {
+        self.address(GATEWAY_ADDRESS_OFFSET)
+    }
+
+    /// Host name of the server (`sname`), without its null terminator.
+    pub fn server_name(&self) -> &[u8] {
+        trim_nul(self.field(SERVER_NAME_OFFSET, SERVER_NAME_SIZE))
+    }
+
+    /// Name of the boot file (`file`), without its null terminator.
+    ///
+    /// Servers may instead send the name in the `BOOTFILE_NAME` option.
+    pub fn boot_file_name(&self) -> &[u8] {
+        trim_nul(self.field(BOOT_FILE_NAME_OFFSET, BOOT_FILE_NAME_SIZE))
+    }
+
+    /// Returns the options of the packet.
+    pub fn options(&self) -> Dhcp4Options<'_> {
+        Dhcp4Options::new(self.as_bytes().get(OPTIONS_OFFSET..).unwrap_or(&[]))
+    }
+
+    /// Returns the data of the first option with the given code, if any.
+    pub fn option(&self, code: Dhcp4OptionCode) -> Option<&[u8]> {
+        self.options()
+            .find(|option| option.code == code)
+            .map(|option| option.data)
+    }
+
+    fn field(&self, offset: usize, size: usize) -> &[u8] {
+        self.as_bytes()
+            .get(offset..offset + size)
+            .unwrap_or(&[0; 128][..size])
+    }
+
+    fn address(&self, offset: usize) -> Ipv4Address {
+        let bytes = self.field(offset, 4);
+        Ipv4Address::new(bytes[0], bytes[1], bytes[2], bytes[3])
+    }
+}
+
+/// Returns the bytes of a string before its first null character
+fn trim_nul(bytes: &[u8]) -> &[u8] {
+    let len = bytes
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(bytes.len());
+    &bytes[..len]
+}
+
+newtype_enum! {
+    /// Code of a DHCP option, as defined by RFC 2132.
+    pub enum Dhcp4OptionCode: u8 => #[allow(missing_docs)] {
+        PAD                    = 0,
+        SUBNET_MASK            = 1,
+        ROUTER                 = 3,
+        DNS_SERVERS            = 6,
+        HOST_NAME              = 12,
+        DOMAIN_NAME            = 15,
+        REQUESTED_ADDRESS      = 50,
+        LEASE_TIME             = 51,
+        OVERLOAD               = 52,
+        MESSAGE_TYPE           = 53,
+        SERVER_ID              = 54,
+        PARAMETER_REQUEST_LIST = 55,
+        VENDOR_CLASS_ID        = 60,
+        CLIENT_ID              = 61,
+        TFTP_SERVER_NAME       = 66,
+        BOOTFILE_NAME          = 67,
+        END                    = 255,
+    }
+}
+
+/// Option of a DHCP packet.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Dhcp4Option<'buf> {
+    /// Code of the option.
+    pub code: Dhcp4OptionCode,
+    /// Data of the option.
+    pub data: &'buf [u8],
+}
+
+/// Iterator over the options of a DHCP packet.
+///
+/// The iteration stops at the `END` option, or at the first truncated option.
+#[derive(Debug, Clone)]
+pub struct Dhcp4Options<'buf> {
+    data: &'buf [u8],
+}
+
+impl<'buf> Dhcp4Options<'buf> {
+    /// Parses the options stored in `data`, as encoded in DHCP packets after
+    /// the magic cookie.
+    pub fn new(data: &'buf [u8]) -> Self {
+        Self { data }
+    }
+}
+
+impl<'buf> Iterator for Dhcp4Options<'buf> {
+    type Item = Dhcp4Option<'buf>;
+
+    fn next(&mut self) -> Option<Dhcp4Option<'buf>> {
+        loop {
+            let code = Dhcp4OptionCode(*self.data.first()?);
+            match code {
+                Dhcp4OptionCode::PAD => self.data = &self.data[1..],
+                Dhcp4OptionCode::END => {
+                    self.data = &[];
+                    return None;
+                }
+                _ => {
+                    let length = usize::from(*self.data.get(1)?);
+                    let data = match self.data.get(2..2 + length) {
+                        Some(data) => data,
+                        None => {
+                            self.data = &[];
+                            return None;
+                        }
+                    };
+                    self.data = &self.data[2 + length..];
+                    return Some(Dhcp4Option { code, data });
+                }
+            }
+        }
+    }
+}
+
+/// Reference to an encoded option, to be added to the packets sent by a
+/// `Dhcp4` instance.
+#[repr(transparent)]
+#[derive(Debug, Copy, Clone)]
+pub struct Dhcp4OptionRef<'a> {
+    option: *const u8,
+    _data: PhantomData<&'a [u8]>,
+}
+
+impl<'a> Dhcp4OptionRef<'a> {
+    /// Refers to an option encoded as its code, the length of its data, and
+    /// its data.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size of `encoded` does not match the encoded length.
+    pub fn new(encoded: &'a [u8]) -> Self {
+        assert!(
+            encoded.len() >= 2 && usize::from(encoded[1]) + 2 == encoded.len(),
+            "Invalid encoded DHCP option"
+        );
+        Self {
+            option: encoded.as_ptr(),
+            _data: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,583 @@
+//! DHCPv6 protocol.
+//!
+//! This protocol obtains IPv6 addresses for a network interface from a DHCPv6
+//! server, as an identity association (IA), and gives access to the options
+//! sent by the server, such as the boot file URL used for network boot.
+
+use super::{Ipv6Address, ServiceBinding};
+use crate::proto::Protocol;
+use crate::table::boot::BootServices;
+use crate::{unsafe_guid, Event, Result, Status};
+use core::ffi::c_void;
+use core::marker::PhantomData;
+use core::ops::Deref;
+use core::{ptr, slice};
+
+/// Service binding creating `Dhcp6` instances.
+///
+/// It is installed on the handles of the network interfaces.
+#[repr(transparent)]
+#[unsafe_guid("9fb9a8a1-2f4a-43a6-889c-d0f7b6c47ad5")]
+#[derive(Protocol)]
+pub struct Dhcp6ServiceBinding(ServiceBinding);
+
+impl Deref for Dhcp6ServiceBinding {
+    type Target = ServiceBinding;
+
+    fn deref(&self) -> &ServiceBinding {
+        &self.0
+    }
+}
+
+/// DHCPv6 client of a network interface.
+///
+/// An instance must be configured with `configure()`, then started with
+/// `start()` to obtain the addresses of its identity association.
+#[repr(C)]
+#[unsafe_guid("87c8bad7-0595-4053-8297-dede395f5d5b")]
+#[derive(Protocol)]
+pub struct Dhcp6 {
+    get_mode_data: extern "efiapi" fn(
+        this: &Dhcp6,
+        mode_data: *mut RawModeData,
+        config_data: *mut Dhcp6ConfigData,
+    ) -> Status,
+    configure: extern "efiapi" fn(this: &Dhcp6, config_data: *const Dhcp6ConfigData) -> Status,
+    start: extern "efiapi" fn(this: &Dhcp6) -> Status,
+    info_request: usize,
+    renew_rebind: extern "efiapi" fn(this: &Dhcp6, rebind_request: bool) -> Status,
+    decline: extern "efiapi" fn(
+        this: &Dhcp6,
+        address_count: u32,
+        addresses: *const Ipv6Address,
+    ) -> Status,
+    release: extern "efiapi" fn(
+        this: &Dhcp6,
+        address_count: u32,
+        addresses: *const Ipv6Address,
+    ) -> Status,
+    stop: extern "efiapi" fn(this: &Dhcp6) -> Status,
+    parse: usize,
+}
+
+impl Dhcp6 {
+    /// Returns the client identifier of this instance, and its identity
+    /// association if it is configured.
+    ///
+    /// # Errors
+    ///
+    /// - `AccessDenied` if the instance is not configured
+    /// - `OutOfResources` if there are not enough resources to copy the data
+    pub fn mode_data<'boot>(&self, bt: &'boot BootServices) -> Result<Dhcp6ModeData<'boot>> {
+        let mut raw = RawModeData {
+            client_id: ptr::null_mut(),
+            ia: ptr::null_mut(),
+        };
+        (self.get_mode_data)(self, &mut raw, ptr::null_mut()).into_with_val(|| Dhcp6ModeData {
+            bt,
+            client_id: raw.client_id,
+            ia: raw.ia,
+        })
+    }
+
+    /// Configures this instance, or resets it if `config_data` is `None`.
+    ///
+    /// Resetting releases the addresses of the identity association.
+    ///
+    /// # Errors
+    ///
+    /// - `AccessDenied` if the instance is already configured, or another
+    ///   instance uses the same identity association
+    /// - `InvalidParameter` if the configuration is not valid
+    pub fn configure(&self, config_data: Option<&Dhcp6ConfigData>) -> Result {
+        let config_data = config_data.map_or(ptr::null(), |config| config as *const _);
+        (self.configure)(self, config_data).into()
+    }
+
+    /// Starts the DHCPv6 process, to obtain the addresses of the identity
+    /// association.
+    ///
+    /// If no `Dhcp6ConfigData::set_ia_info_event()` event was configured,
+    /// this function waits for the process to complete.
+    ///
+    /// # Errors
+    ///
+    /// - `AccessDenied` if the instance is not configured
+    /// - `AlreadyStarted` if the process was already started
+    /// - `Timeout` if no address could be obtained
+    /// - `Aborted` if the callback aborted the process
+    pub fn start(&self) -> Result {
+        (self.start)(self).into()
+    }
+
+    /// Extends the lifetime of the addresses, from the server they were
+    /// obtained from, or from any server if `rebind_request` is true.
+    ///
+    /// # Errors
+    ///
+    /// - `AccessDenied` if the identity association is not bound
+    /// - `AlreadyStarted` if the addresses are already being extended
+    pub fn renew_rebind(&self, rebind_request: bool) -> Result {
+        (self.renew_rebind)(self, rebind_request).into()
+    }
+
+    /// Informs the server that some addresses of the identity association are
+    /// already used on the network.
+    ///
+    /// # Errors
+    ///
+    /// - `AccessDenied` if the identity association is not bound
+    /// - `NotFound` if an address is not part of the identity association
+    pub fn decline(&self, addresses: &[Ipv6Address]) -> Result {
+        (self.decline)(self, addresses.len() as u32, addresses.as_ptr()).into()
+    }
+
+    /// Releases some addresses of the identity association, or all of them if
+    /// `addresses` is empty.
+    ///
+    /// # Errors
+    ///
+    /// - `AccessDenied` if the identity association is not bound
+    /// - `NotFound` if an address is not part of the identity association
+    pub fn release(&self, addresses: &[Ipv6Address]) -> Result {
+        let addresses_ptr = if addresses.is_empty() {
+            ptr::null()
+        } else {
+            addresses.as_ptr()
+        };
+        (self.release)(self, addresses.len() as u32, addresses_ptr).into()
+    }
+
+    /// Stops the DHCPv6 process, and releases the addresses of the identity
+    /// association.
+    pub fn stop(&self) -> Result {
+        (self.stop)(self).into()
+    }
+}
+
+newtype_enum! {
+    /// State of the identity association of a `Dhcp6` instance.
+    pub enum Dhcp6State: u32 => {
+        /// The process was not started, or failed.
+        INIT       = 0,
+        /// Advertisements of the servers are being collected.
+        SELECTING  = 1,
+        /// An advertisement was selected, and its addresses are being
+        /// requested.
+        REQUESTING = 2,
+        /// Addresses are being declined.
+        DECLINING  = 3,
+        /// Addresses are being confirmed after a link change.
+        CONFIRMING = 4,
+        /// Addresses are being released.
+        RELEASING  = 5,
+        /// The addresses were obtained.
+        BOUND      = 6,
+        /// The addresses are being extended from the server they were
+        /// obtained from.
+        RENEWING   = 7,
+        /// The addresses are being extended from any server.
+        REBINDING  = 8,
+    }
+}
+
+newtype_enum! {
+    /// Events of the DHCPv6 process, reported to `Dhcp6ConfigData` callbacks.
+    pub enum Dhcp6Event: u32 => {
+        /// A Solicit packet is about to be sent.
+        SEND_SOLICIT      = 0,
+        /// An Advertise packet was received.
+        RCVD_ADVERTISE    = 1,
+        /// All the advertisements were collected, and one is about to be
+        /// selected.
+        SELECT_ADVERTISE  = 2,
+        /// A Request packet is about to be sent.
+        SEND_REQUEST      = 3,
+        /// A Reply packet was received.
+        RCVD_REPLY        = 4,
+        /// A Reconfigure packet was received.
+        RCVD_RECONFIGURE  = 5,
+        /// A Decline packet is about to be sent.
+        SEND_DECLINE      = 6,
+        /// A Confirm packet is about to be sent.
+        SEND_CONFIRM      = 7,
+        /// A Release packet is about to be sent.
+        SEND_RELEASE      = 8,
+        /// A Renew packet is about to be sent.
+        SEND_RENEW        = 9,
+        /// A Rebind packet is about to be sent.
+        SEND_REBIND       = 10,
+    }
+}
+
+newtype_enum! {
+    /// Type of an identity association.
+    pub enum IaType: u16 => {
+        /// Non-temporary addresses.
+        NA = 3,
+        /// Temporary addresses.
+        TA = 4,
+    }
+}
+
+/// Identifier of an identity association.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(C)]
+pub struct IaDescriptor {
+    /// Type of the identity association.
+    pub ia_type: IaType,
+    /// Identifier of the identity association, unique for each type on a
+    /// network interface.
+    pub ia_id: u32,
+}
+
+/// Retransmission parameters of a DHCPv6 message, as defined by RFC 8415.
+#[derive(Debug, Default, Copy, Clone)]
+#[repr(C)]
+pub struct Dhcp6Retransmission {
+    /// Initial retransmission time, in seconds.
+    pub irt: u32,
+    /// Maximal number of retransmissions, or 0 for no limit.
+    pub mrc: u32,
+    /// Maximal retransmission time, in seconds, or 0 for no limit.
+    pub mrt: u32,
+    /// Maximal retransmission duration, in seconds, or 0 for no limit.
+    pub mrd: u32,
+}
+
+/// Type of the raw callbacks of the DHCPv6 process.
+type Dhcp6Callback = extern "efiapi" fn(
+    this: &Dhcp6,
+    context: *mut c_void,
+    current_state: Dhcp6State,
+    dhcp6_event: Dhcp6Event,
+    packet: *const Dhcp6Packet,
+    new_packet: *mut *mut Dhcp6Packet,
+) -> Status;
+
+/// Configuration of a `Dhcp6` instance.
+#[repr(C)]
+pub struct Dhcp6ConfigData<'a> {
+    callback: Option<Dhcp6Callback>,
+    callback_context: *mut c_void,
+    option_count: u32,
+    option_list: *const Dhcp6OptionRef<'a>,
+    /// Identity association whose addresses are obtained.
+    pub ia_descriptor: IaDescriptor,
+    ia_info_event: Event,
+    /// Accept the Reconfigure packets of the server.
+    pub reconfigure_accept: bool,
+    /// Obtain the addresses in two messages instead of four, if the server
+    /// supports it.
+    pub rapid_commit: bool,
+    solicit_retransmission: *const Dhcp6Retransmission,
+    _data: PhantomData<&'a mut ()>,
+}
+
+impl<'a> Dhcp6ConfigData<'a> {
+    /// Creates a configuration obtaining the addresses of the identity
+    /// association `ia_descriptor`, without callback or additional option.
+    pub fn new(ia_descriptor: IaDescriptor) -> Self {
+        Self {
+            callback: None,
+            callback_context: ptr::null_mut(),
+            option_count: 0,
+            option_list: ptr::null(),
+            ia_descriptor,
+            ia_info_event: Event::null(),
+            reconfigure_accept: false,
+            rapid_commit: false,
+            solicit_retransmission: ptr::null(),
+            _data: PhantomData,
+        }
+    }
+
+    /// Sets the options added to the packets sent by the client, such as an
+    /// option request option.
+    pub fn set_options(&mut self, options: &'a [Dhcp6OptionRef<'a>]) {
+        self.option_count = options.len() as u32;
+        self.option_list = options.as_ptr();
+    }
+
+    /// Sets the retransmission parameters of the Solicit packets.
+    pub fn set_solicit_retransmission(&mut self, retransmission: &'a Dhcp6Retransmission) {
+        self.solicit_retransmission = retransmission;
+    }
+
+    /// Sets an event signaled when the state of the identity association
+    /// changes, which makes `Dhcp6::start()` asynchronous.
+    pub fn set_ia_info_event(&mut self, event: Event) {
+        self.ia_info_event = event;
+    }
+
+    /// Sets a callback, called at each event of the DHCPv6 process with the
+    /// current state and the packet sent or received.
+    ///
+    /// The callback returns `Status::SUCCESS` to continue the process, or
+    /// `Status::ABORTED` to abort it.
+    ///
+    /// # Safety
+    ///
+    /// The firmware keeps a reference to the callback, which must therefore
+    /// remain valid until the instance is reset.
+    pub unsafe fn set_callback<F>(&mut self, callback: &'a mut F)
+    where
+        F: FnMut(Dhcp6State, Dhcp6Event, &Dhcp6Packet) -> Status,
+    {
+        extern "efiapi" fn trampoline<F>(
+            _this: &Dhcp6,
+            context: *mut c_void,
+            current_state: Dhcp6State,
+            dhcp6_event: Dhcp6Event,
+            packet: *const Dhcp6Packet,
+            _new_packet: *mut *mut Dhcp6Packet,
+        ) -> Status
+        where
+            F: FnMut(Dhcp6State, Dhcp6Event, &Dhcp6Packet) -> Status,
+        {
+            let callback = unsafe { &mut *(context as *mut F) };
+            match unsafe { packet.as_ref() } {
+                Some(packet) => callback(current_state, dhcp6_event, packet),
+                None => Status::SUCCESS,
+            }
+        }
+        self.callback = Some(trampoline::<F>);
+        self.callback_context = callback as *mut F as *mut c_void;
+    }
+}
+
+/// Mode data, as returned by the firmware.
+#[repr(C)]
+struct RawModeData {
+    client_id: *mut u8,
+    ia: *mut RawIa,
+}
+
+/// Identity association, as returned by the firmware.
+#[repr(C)]
+struct RawIa {
+    descriptor: IaDescriptor,
+    state: Dhcp6State,
+    reply_packet: *const Dhcp6Packet,
+    address_count: u32,
+    addresses: [Dhcp6IaAddress; 0],
+}
+
+/// Client identifier and identity association of a `Dhcp6` instance.
+///
+/// The data is freed when this object is dropped.
+pub struct Dhcp6ModeData<'boot> {
+    bt: &'boot BootServices,
+    client_id: *mut u8,
+    ia: *mut RawIa,
+}
+
+impl Dhcp6ModeData<'_> {
+    /// DHCP unique identifier (DUID) of the client.
+    pub fn client_id(&self) -> &[u8] {
+        if self.client_id.is_null() {
+            return &[];
+        }
+        unsafe {
+            let length = u16::from_ne_bytes([*self.client_id, *self.client_id.add(1)]);
+            slice::from_raw_parts(self.client_id.add(2), usize::from(length))
+        }
+    }
+
+    /// Identifier of the identity association, if the instance is configured.
+    pub fn ia_descriptor(&self) -> Option<IaDescriptor> {
+        self.ia().map(|ia| ia.descriptor)
+    }
+
+    /// State of the identity association, if the instance is configured.
+    pub fn state(&self) -> Option<Dhcp6State> {
+        self.ia().map(|ia| ia.state)
+    }
+
+    /// Addresses obtained for the identity association.
+    pub fn addresses(&self) -> &[Dhcp6IaAddress] {
+        match self.ia() {
+            Some(ia) => unsafe {
+                slice::from_raw_parts(ia.addresses.as_ptr(), ia.address_count as usize)
+            },
+            None => &[],
+        }
+    }
+
+    /// Returns the last Reply packet of the server, which contains the
+    /// options of the identity association.
+    ///
+    /// # Safety
+    ///
+    /// The packet belongs to the instance, and must not be accessed after the
+    /// state of the identity association changed.
+    pub unsafe fn reply_packet(&self) -> Option<&Dhcp6Packet> {
+        self.ia().and_then(|ia| ia.reply_packet.as_ref())
+    }
+
+    fn ia(&self) -> Option<&RawIa> {
+        unsafe { self.ia.as_ref() }
+    }
+}
+
+impl Drop for Dhcp6ModeData<'_> {
+    fn drop(&mut self) {
+        // Nothing more can be done if this fails
+        if !self.client_id.is_null() {
+            let _ = self.bt.free_pool(self.client_id);
+        }
+        if !self.ia.is_null() {
+            let _ = self.bt.free_pool(self.ia as *mut u8);
+        }
+    }
+}
+
+/// Address of an identity association.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct Dhcp6IaAddress {
+    /// IPv6 address.
+    pub address: Ipv6Address,
+    /// Time during which the address is preferred, in seconds.
+    pub preferred_lifetime: u32,
+    /// Time during which the address is valid, in seconds.
+    pub valid_lifetime: u32,
+}
+
+/// DHCPv6 packet, as sent or received by a `Dhcp6` instance.
+#[repr(C)]
+pub struct Dhcp6Packet {
+    size: u32,
+    length: u32,
+    data: [u8; 0],
+}
+
+impl Dhcp6Packet {
+    /// Returns the bytes of the packet, starting with its message type.
+    pub fn as_bytes(&self) -> &[u8] {
+        let length = self.length.min(self.size) as usize;
+        unsafe { slice::from_raw_parts(self.data.as_ptr(), length) }
+    }
+
+    /// Message type of the packet, such as 7 for a Reply.
+    pub fn message_type(&self) -> u8 {
+        self.as_bytes().first().copied().unwrap_or(0)
+    }
+
+    /// Transaction ID of the packet.
+    pub fn transaction_id(&self) -> u32 {
+        match self.as_bytes().get(1..4) {
+            Some(id) => u32::from_be_bytes([0, id[0], id[1], id[2]]),
+            None => 0,
+        }
+    }
+
+    /// Returns the options of the packet.
+    pub fn options(&self) -> Dhcp6Options<'_> {
+        Dhcp6Options::new(self.as_bytes().get(4..).unwrap_or(&[]))
+    }
+
+    /// Returns the data of the first option with the given code, if any.
+    pub fn option(&self, code: Dhcp6OptionCode) -> Option<&[u8]> {
+        self.options()
+            .find(|option| option.code == code)
+            .map(|option| option.data)
+    }
+}
+
+newtype_enum! {
+    /// Code of a DHCPv6 option, as defined by RFC 8415 and its extensions.
+    pub enum Dhcp6OptionCode: u16 => #[allow(missing_docs)] {
+        CLIENT_ID        = 1,
+        SERVER_ID        = 2,
+        IA_NA            = 3,
+        IA_TA            = 4,
+        IA_ADDR          = 5,
+        ORO              = 6,
+        PREFERENCE       = 7,
+        ELAPSED_TIME     = 8,
+        STATUS_CODE      = 13,
+        RAPID_COMMIT     = 14,
+        DNS_SERVERS      = 23,
+        DOMAIN_LIST      = 24,
+        BOOTFILE_URL     = 59,
+        BOOTFILE_PARAM   = 60,
+        CLIENT_ARCH_TYPE = 61,
+    }
+}
+
+/// Option of a DHCPv6 packet.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Dhcp6Option<'buf> {
+    /// Code of the option.
+    pub code: Dhcp6OptionCode,
+    /// Data of the option.
+    pub data: &'buf [u8],
+}
+
+/// Iterator over the options of a DHCPv6 packet.
+///
+/// Options such as `IA_NA` contain other options, after some fixed fields,
+/// which can be parsed with another `Dhcp6Options`. The iteration stops at
+/// the first truncated option.
+#[derive(Debug, Clone)]
+pub struct Dhcp6Options<'buf> {
+    data: &'buf [u8],
+}
+
+impl<'buf> Dhcp6Options<'buf> {
+    /// Parses the options stored in `data`, as encoded in DHCPv6 packets
+    /// after the transaction ID.
+    pub fn new(data: &'buf [u8]) -> Self {
+        Self { data }
+    }
+}
+
+impl<'buf> Iterator for Dhcp6Options<'buf> {
+    type Item = Dhcp6Option<'buf>;
+
+    fn next(&mut self) -> Option<Dhcp6Option<'buf>> {
+        let header = self.data.get(..4)?;
+        let code = Dhcp6OptionCode(u16::from_be_bytes([header[0], header[1]]));
+        let length = usize::from(u16::from_be_bytes([header[2], header[3]]));
+        match self.data.get(4..4 + length) {
+            Some(data) => {
+                self.data = &self.data[4 + length..];
+                Some(Dhcp6Option { code, data })
+            }
+            None => {
+                self.data = &[];
+                None
+            }
+        }
+    }
+}
+
+/// Reference to an encoded option, to be added to the packets sent by a
+/// `Dhcp6` instance.
+#[repr(transparent)]
+#[derive(Debug, Copy, Clone)]
+pub struct Dhcp6OptionRef<'a> {
+    option: *const u8,
+    _data: PhantomData<&'a [u8]>,
+}
+
+impl<'a> Dhcp6OptionRef<'a> {
+    /// Refers to an option encoded as its code and the length of its data, in
+    /// network byte order, followed by its data.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size of `encoded` does not match the encoded length.
+    pub fn new(encoded: &'a [u8]) -> Self {
+        assert!(
+            encoded.len() >= 4
+                && usize::from(u16::from_be_bytes([encoded[2], encoded[3]])) + 4 == encoded.len(),
+            "Invalid encoded DHCPv6 option"
+        );
+        Self {
+            option: encoded.as_ptr(),
+            _data: PhantomData,
+        }
+    }
+}
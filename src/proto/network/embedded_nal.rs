@@ -0,0 +1,357 @@
+//! TCP and UDP client stacks for [embedded-nal].
+//!
+//! `FirmwareStack` implements `TcpClientStack` and `UdpClientStack` on top of
+//! the TCP4 and UDP4 protocols of a network interface, so that the network
+//! clients written against these traits can run over the firmware's TCP/IP
+//! stack. Only IPv4 remote addresses are supported.
+//!
+//! Connecting and sending wait for completion, while receiving does not: the
+//! reception is queued to the firmware, and `nb::Error::WouldBlock` returned
+//! until it completes. Since the firmware writes to it in the meantime, the
+//! state of each reception is allocated on the heap.
+//!
+//! [embedded-nal]: https://docs.rs/embedded-nal
+
+use super::tcp4::{TcpConnection, TcpIoToken, TcpReceiveData};
+use super::udp4::{
+    Udp4, Udp4CompletionToken, Udp4ConfigData, Udp4ServiceBinding, Udp4TransmitData,
+};
+use crate::result::Error;
+use crate::table::boot::{BootServices, EventType, Tpl};
+use crate::{Event, Handle, Result, Status};
+use ::embedded_nal::{nb, TcpClientStack, TcpError, TcpErrorKind, UdpClientStack};
+use alloc_api::boxed::Box;
+use core::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use core::ptr;
+
+/// Size of the buffer receiving the data of a TCP socket.
+pub const TCP_RECEIVE_BUFFER_SIZE: usize = 4096;
+
+/// Network stack of the firmware, as seen through one network interface.
+pub struct FirmwareStack<'boot> {
+    bt: &'boot BootServices,
+    device: Handle,
+}
+
+impl<'boot> FirmwareStack<'boot> {
+    /// Creates a stack whose sockets are created on the network interface
+    /// `device`.
+    ///
+    /// The handle must support the TCP4 and UDP4 service bindings for the
+    /// sockets of the matching type to be created.
+    pub fn new(bt: &'boot BootServices, device: Handle) -> Self {
+        Self { bt, device }
+    }
+
+    /// Creates an event for the pending operations of a socket
+    fn create_event(&self) -> Result<Event> {
+        unsafe {
+            self.bt
+                .create_event(EventType::empty(), Tpl::APPLICATION, None)
+        }
+    }
+}
+
+impl TcpError for Error {
+    fn kind(&self) -> TcpErrorKind {
+        match self.status() {
+            Status::CONNECTION_FIN | Status::CONNECTION_RESET => TcpErrorKind::PipeClosed,
+            _ => TcpErrorKind::Other,
+        }
+    }
+}
+
+/// TCP socket of a `FirmwareStack`.
+///
+/// The connection is reset if it was not closed when this is dropped.
+pub struct TcpSocket<'boot> {
+    bt: &'boot BootServices,
+    connection: Option<TcpConnection<'boot>>,
+    rx: Box<TcpReception>,
+}
+
+/// State of the reception of a TCP socket
+struct TcpReception {
+    event: Event,
+    buffer: [u8; TCP_RECEIVE_BUFFER_SIZE],
+    data: Option<TcpReceiveData<'static>>,
+    token: Option<TcpIoToken<'static>>,
+    /// Range of the buffer holding data not yet returned
+    offset: usize,
+    end: usize,
+}
+
+impl TcpReception {
+    /// Queues a reception of data into the buffer
+    fn queue(&mut self, connection: &TcpConnection) -> Result {
+        // The buffer and data are referenced by raw pointers, and only
+        // accessed again once the firmware is done with them
+        let buffer = unsafe { &mut *ptr::addr_of_mut!(self.buffer) };
+        let data: *mut TcpReceiveData = self.data.insert(TcpReceiveData::new(buffer));
+        let token = self
+            .token
+            .insert(TcpIoToken::receive(self.event, unsafe { &mut *data }));
+        let outcome = unsafe { connection.tcp().receive(token) };
+        if outcome.is_err() {
+            self.token = None;
+        }
+        outcome
+    }
+
+    /// Checks whether the pending reception completed, and makes its data
+    /// available if so
+    fn complete(&mut self, bt: &BootServices) -> Result<bool> {
+        if !bt.check_event(self.event)?.log() {
+            return Ok(false.into());
+        }
+        let token = self.token.take().expect("No pending TCP reception");
+        let data = self.data.take().expect("No pending TCP reception");
+        token.completion_token.status().into_with_val(|| {
+            self.offset = 0;
+            self.end = data.data_length();
+            true
+        })
+    }
+}
+
+impl Drop for TcpSocket<'_> {
+    fn drop(&mut self) {
+        // The connection is destroyed first, so that the firmware no longer
+        // writes to the reception state
+        self.connection = None;
+        let _ = unsafe { self.bt.close_event(self.rx.event) };
+    }
+}
+
+impl<'boot> TcpClientStack for FirmwareStack<'boot> {
+    type TcpSocket = TcpSocket<'boot>;
+    type Error = Error;
+
+    fn socket(&mut self) -> core::result::Result<TcpSocket<'boot>, Error> {
+        let event = self.create_event()?.log();
+        let rx = Box::new(TcpReception {
+            event,
+            buffer: [0; TCP_RECEIVE_BUFFER_SIZE],
+            data: None,
+            token: None,
+            offset: 0,
+            end: 0,
+        });
+        Ok(TcpSocket {
+            bt: self.bt,
+            connection: None,
+            rx,
+        })
+    }
+
+    fn connect(
+        &mut self,
+        socket: &mut TcpSocket<'boot>,
+        remote: SocketAddr,
+    ) -> nb::Result<(), Error> {
+        if socket.connection.is_some() {
+            return Err(Error::from(Status::ALREADY_STARTED).into());
+        }
+        let remote = ipv4_remote(remote)?;
+        let address = (*remote.ip()).into();
+        let connection = TcpConnection::connect_to(self.bt, self.device, address, remote.port())?;
+        socket.connection = Some(connection.log());
+        Ok(())
+    }
+
+    fn send(&mut self, socket: &mut TcpSocket<'boot>, buffer: &[u8]) -> nb::Result<usize, Error> {
+        let connection = socket
+            .connection
+            .as_mut()
+            .ok_or_else(|| Error::from(Status::NOT_STARTED))?;
+        connection.send(buffer)?.log();
+        Ok(buffer.len())
+    }
+
+    fn receive(
+        &mut self,
+        socket: &mut TcpSocket<'boot>,
+        buffer: &mut [u8],
+    ) -> nb::Result<usize, Error> {
+        let connection = socket
+            .connection
+            .as_ref()
+            .ok_or_else(|| Error::from(Status::NOT_STARTED))?;
+        let rx = &mut *socket.rx;
+        if rx.offset == rx.end {
+            if rx.token.is_none() {
+                rx.queue(connection)?.log();
+            }
+            // Move the data from the interface, rather than waiting for the
+            // firmware to do it periodically
+            let _ = connection.tcp().poll();
+            if !rx.complete(self.bt)?.log() {
+                return Err(nb::Error::WouldBlock);
+            }
+        }
+        let len = buffer.len().min(rx.end - rx.offset);
+        buffer[..len].copy_from_slice(&rx.buffer[rx.offset..rx.offset + len]);
+        rx.offset += len;
+        Ok(len)
+    }
+
+    fn close(&mut self, mut socket: TcpSocket<'boot>) -> core::result::Result<(), Error> {
+        match socket.connection.take() {
+            Some(connection) => connection.close(false).map(|completion| completion.log()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// UDP socket of a `FirmwareStack`.
+pub struct UdpSocket<'boot> {
+    bt: &'boot BootServices,
+    binding: &'boot Udp4ServiceBinding,
+    handle: Handle,
+    udp: &'boot Udp4,
+    tx_event: Event,
+    rx_event: Event,
+    rx_token: Box<Udp4CompletionToken<'static>>,
+    receiving: bool,
+    connected: bool,
+}
+
+impl Drop for UdpSocket<'_> {
+    fn drop(&mut self) {
+        // Resetting the instance aborts the pending reception, so that the
+        // firmware no longer writes to its token. Nothing more can be done if
+        // any of this fails.
+        let _ = self.udp.configure(None);
+        let _ = self.binding.destroy_child(self.handle);
+        let _ = unsafe { self.bt.close_event(self.tx_event) };
+        let _ = unsafe { self.bt.close_event(self.rx_event) };
+    }
+}
+
+impl<'boot> UdpClientStack for FirmwareStack<'boot> {
+    type UdpSocket = UdpSocket<'boot>;
+    type Error = Error;
+
+    fn socket(&mut self) -> core::result::Result<UdpSocket<'boot>, Error> {
+        let binding = self
+            .bt
+            .handle_protocol::<Udp4ServiceBinding>(self.device)?
+            .log();
+        let binding = unsafe { &*binding.get() };
+        let handle = binding.create_child()?.log();
+        let udp = match self.bt.handle_protocol::<Udp4>(handle) {
+            Ok(udp) => unsafe { &*udp.log().get() },
+            Err(err) => {
+                let _ = binding.destroy_child(handle);
+                return Err(err);
+            }
+        };
+        let tx_event = match self.create_event() {
+            Ok(event) => event.log(),
+            Err(err) => {
+                let _ = binding.destroy_child(handle);
+                return Err(err);
+            }
+        };
+        let rx_event = match self.create_event() {
+            Ok(event) => event.log(),
+            Err(err) => {
+                let _ = unsafe { self.bt.close_event(tx_event) };
+                let _ = binding.destroy_child(handle);
+                return Err(err);
+            }
+        };
+        Ok(UdpSocket {
+            bt: self.bt,
+            binding,
+            handle,
+            udp,
+            tx_event,
+            rx_event,
+            rx_token: Box::new(Udp4CompletionToken::new(rx_event)),
+            receiving: false,
+            connected: false,
+        })
+    }
+
+    fn connect(
+        &mut self,
+        socket: &mut UdpSocket<'boot>,
+        remote: SocketAddr,
+    ) -> core::result::Result<(), Error> {
+        let remote = ipv4_remote(remote)?;
+        if socket.connected {
+            socket.udp.configure(None)?.log();
+            socket.connected = false;
+            // Resetting the instance aborts the pending reception, whose
+            // event must not be mistaken for the next one
+            if socket.receiving {
+                let _ = self.bt.check_event(socket.rx_event);
+                socket.receiving = false;
+            }
+        }
+        let config = Udp4ConfigData {
+            remote_address: (*remote.ip()).into(),
+            remote_port: remote.port(),
+            ..Udp4ConfigData::default()
+        };
+        socket.udp.configure(Some(&config))?.log();
+        socket.connected = true;
+        Ok(())
+    }
+
+    fn send(&mut self, socket: &mut UdpSocket<'boot>, buffer: &[u8]) -> nb::Result<(), Error> {
+        if !socket.connected {
+            return Err(Error::from(Status::NOT_STARTED).into());
+        }
+        let data = Udp4TransmitData::new(buffer);
+        socket
+            .udp
+            .transmit_blocking(self.bt, socket.tx_event, &data)?
+            .log();
+        Ok(())
+    }
+
+    fn receive(
+        &mut self,
+        socket: &mut UdpSocket<'boot>,
+        buffer: &mut [u8],
+    ) -> nb::Result<(usize, SocketAddr), Error> {
+        if !socket.connected {
+            return Err(Error::from(Status::NOT_STARTED).into());
+        }
+        if !socket.receiving {
+            *socket.rx_token = Udp4CompletionToken::new(socket.rx_event);
+            unsafe { socket.udp.receive(&mut socket.rx_token) }?.log();
+            socket.receiving = true;
+        }
+        let _ = socket.udp.poll();
+        if !self.bt.check_event(socket.rx_event)?.log() {
+            return Err(nb::Error::WouldBlock);
+        }
+        socket.receiving = false;
+        let outcome: Result = socket.rx_token.status().into();
+        outcome?.log();
+        let data = unsafe { socket.rx_token.receive_data() }
+            .ok_or_else(|| Error::from(Status::ABORTED))?;
+        // The datagram is truncated if it does not fit in the buffer
+        let len = data.copy_to(buffer);
+        let session = *data.session();
+        self.bt.signal_event(data.recycle_event())?.log();
+        let source = SocketAddrV4::new(Ipv4Addr::from(session.source_address), session.source_port);
+        Ok((len, source.into()))
+    }
+
+    fn close(&mut self, socket: UdpSocket<'boot>) -> core::result::Result<(), Error> {
+        drop(socket);
+        Ok(())
+    }
+}
+
+/// Checks that `remote` is an IPv4 address
+fn ipv4_remote(remote: SocketAddr) -> core::result::Result<SocketAddrV4, Error> {
+    match remote {
+        SocketAddr::V4(remote) => Ok(remote),
+        SocketAddr::V6(_) => Err(Status::UNSUPPORTED.into()),
+    }
+}
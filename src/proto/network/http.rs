@@ -0,0 +1,1004 @@
+//! HTTP protocol.
+//!
+//! Each `Http` instance is an HTTP client, created with `HttpServiceBinding`,
+//! which sends requests to and receives responses from one server at a time.
+//! Like the other protocols of the network stack, its operations are
+//! asynchronous, and are tracked with tokens whose event is signaled upon
+//! completion.
+
+use super::{Ipv4Address, Ipv6Address, ServiceBinding};
+use crate::proto::Protocol;
+#[cfg(feature = "exts")]
+use crate::result::Error;
+use crate::table::boot::BootServices;
+#[cfg(feature = "exts")]
+use crate::table::boot::{EventType, Tpl};
+#[cfg(feature = "exts")]
+use crate::Handle;
+use crate::{
+    unsafe_guid, CStr16, CStr8, Char16, Char8, Completion, Event, Result, ResultExt, Status,
+};
+#[cfg(feature = "exts")]
+use alloc_api::{string::String, vec, vec::Vec};
+use core::cell::UnsafeCell;
+use core::ffi::c_void;
+use core::marker::PhantomData;
+use core::ops::Deref;
+use core::{ptr, slice};
+
+/// Service binding creating `Http` instances.
+///
+/// It is installed on the handles of the network interfaces.
+#[repr(transparent)]
+#[unsafe_guid("bdc8e6af-d9bc-4379-a72a-e0c4e75dae1c")]
+#[derive(Protocol)]
+pub struct HttpServiceBinding(ServiceBinding);
+
+impl Deref for HttpServiceBinding {
+    type Target = ServiceBinding;
+
+    fn deref(&self) -> &ServiceBinding {
+        &self.0
+    }
+}
+
+/// HTTP client.
+///
+/// An instance must be configured with `configure()` before it can send
+/// requests. The server is picked from the URL of the first request, and
+/// the instance must be reset to talk to another server.
+#[repr(C)]
+#[unsafe_guid("7a59b29b-910b-4171-8242-a85a0df25b5b")]
+#[derive(Protocol)]
+pub struct Http {
+    get_mode_data: extern "efiapi" fn(this: &Http, http_config_data: *mut RawConfigData) -> Status,
+    configure: extern "efiapi" fn(this: &Http, http_config_data: *const RawConfigData) -> Status,
+    request: unsafe extern "efiapi" fn(this: &Http, token: *mut HttpToken) -> Status,
+    cancel: unsafe extern "efiapi" fn(this: &Http, token: *mut HttpToken) -> Status,
+    response: unsafe extern "efiapi" fn(this: &Http, token: *mut HttpToken) -> Status,
+    poll: extern "efiapi" fn(this: &Http) -> Status,
+}
+
+impl Http {
+    /// Returns the current configuration of this instance, or `None` if it
+    /// is not configured.
+    pub fn config_data(&self) -> Result<Option<HttpConfig>> {
+        let mut access_point = RawAccessPoint {
+            ipv6: Httpv6AccessPoint::default(),
+        };
+        let mut raw = RawConfigData {
+            http_version: HttpVersion::UNSUPPORTED,
+            time_out_millisec: 0,
+            local_address_is_ipv6: false,
+            access_point: &mut access_point as *mut RawAccessPoint as *mut c_void,
+        };
+        match (self.get_mode_data)(self, &mut raw) {
+            Status::NOT_STARTED => Ok(None.into()),
+            status => status.into_with_val(|| {
+                let access_point = if raw.local_address_is_ipv6 {
+                    HttpAccessPoint::Ipv6(unsafe { access_point.ipv6 })
+                } else {
+                    HttpAccessPoint::Ipv4(unsafe { access_point.ipv4 })
+                };
+                Some(HttpConfig {
+                    version: raw.http_version,
+                    timeout_ms: raw.time_out_millisec,
+                    access_point,
+                })
+            }),
+        }
+    }
+
+    /// Configures this instance, or resets it if `config` is `None`.
+    ///
+    /// Resetting closes the connection to the server, and cancels all the
+    /// pending operations.
+    ///
+    /// # Errors
+    ///
+    /// - `AlreadyStarted` if the instance is already configured
+    /// - `InvalidParameter` if the configuration is not valid
+    /// - `Unsupported` if the HTTP version is not supported
+    pub fn configure(&self, config: Option<&HttpConfig>) -> Result {
+        let config = match config {
+            Some(config) => config,
+            None => return (self.configure)(self, ptr::null()).into(),
+        };
+        let (local_address_is_ipv6, access_point) = match &config.access_point {
+            HttpAccessPoint::Ipv4(access_point) => (false, access_point as *const _ as *mut c_void),
+            HttpAccessPoint::Ipv6(access_point) => (true, access_point as *const _ as *mut c_void),
+        };
+        let raw = RawConfigData {
+            http_version: config.version,
+            time_out_millisec: config.timeout_ms,
+            local_address_is_ipv6,
+            access_point,
+        };
+        (self.configure)(self, &raw).into()
+    }
+
+    /// Queues the request described by the message of `token`, created with
+    /// `HttpMessage::request()`, for transmission.
+    ///
+    /// # Safety
+    ///
+    /// Neither `token` nor the message it refers to may be moved, freed or
+    /// accessed until the event of `token` has been signaled.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `AccessDenied` if the URL is for another server than the previous
+    ///   requests
+    /// - `Unsupported` if the method or the URL scheme is not supported
+    /// - `InvalidParameter` if the message is not valid
+    pub unsafe fn request(&self, token: &mut HttpToken) -> Result {
+        (self.request)(self, token).into()
+    }
+
+    /// Queues `token` to be completed with the next response of the server.
+    ///
+    /// The message of `token`, created with `HttpMessage::response()`,
+    /// receives the status code and headers of the response, and its body
+    /// buffer receives the start of the body. The rest of the body is then
+    /// received with messages created with `HttpMessage::body()`.
+    ///
+    /// The headers of the response are allocated by the firmware, and must be
+    /// freed with `HttpMessage::free_headers()`.
+    ///
+    /// # Safety
+    ///
+    /// Neither `token` nor the message it refers to may be moved, freed or
+    /// accessed until the event of `token` has been signaled.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `AccessDenied` if no request was sent
+    pub unsafe fn response(&self, token: &mut HttpToken) -> Result {
+        (self.response)(self, token).into()
+    }
+
+    /// Aborts a pending operation, or all of them if `token` is `None`.
+    ///
+    /// The event of the aborted tokens is signaled, and their status is set
+    /// to `Aborted`.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `NotFound` if the token is not in a queue
+    pub fn cancel(&self, token: Option<&mut HttpToken>) -> Result {
+        let token = token.map_or(ptr::null_mut(), |token| token as *mut HttpToken);
+        unsafe { (self.cancel)(self, token) }.into()
+    }
+
+    /// Moves data between the network interface and the queues of this
+    /// instance.
+    ///
+    /// This is normally done periodically by the firmware, but calling it
+    /// manually may increase the throughput.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `NotReady` if no data was moved
+    pub fn poll(&self) -> Result {
+        (self.poll)(self).into()
+    }
+
+    /// Sends a request, and waits for its transmission to complete.
+    ///
+    /// `event` is signaled by the firmware when the transmission completes,
+    /// and must therefore be usable with `BootServices::wait_for_event`, i.e.
+    /// it must not be of type `EventType::NOTIFY_SIGNAL`.
+    ///
+    /// The errors are those of `request()`, plus the outcome of the
+    /// transmission.
+    ///
+    /// # Panics
+    ///
+    /// Panics if waiting for `event` fails, since the firmware may still be
+    /// reading from the request at that point.
+    pub fn send_request(
+        &self,
+        bt: &BootServices,
+        event: Event,
+        method: HttpMethod,
+        url: &CStr16,
+        headers: &[HttpHeader],
+        body: &[u8],
+    ) -> Result {
+        let request = HttpRequestData::new(method, url);
+        let mut message = HttpMessage::request(&request, headers, body);
+        let mut token = HttpToken::new(event, &mut message);
+        let issued = unsafe { self.request(&mut token) }?;
+        token
+            .wait(bt)
+            .map(|completion| completion.with_status(issued.status()))
+    }
+
+    /// Waits for the response of the server, and receives the start of its
+    /// body into `body`.
+    ///
+    /// The rest of the body is then received with `receive_body()`. See
+    /// `send_request()` for the requirements on `event`. The errors are
+    /// those of `response()`, plus the outcome of the reception.
+    ///
+    /// # Panics
+    ///
+    /// Panics if waiting for `event` fails, since the firmware may still be
+    /// writing to the response at that point.
+    pub fn receive_response<'boot>(
+        &self,
+        bt: &'boot BootServices,
+        event: Event,
+        body: &mut [u8],
+    ) -> Result<HttpResponse<'boot>> {
+        let mut response_data = HttpResponseData::default();
+        let mut message = HttpMessage::response(&mut response_data, body);
+        let (issued, outcome) = {
+            let mut token = HttpToken::new(event, &mut message);
+            let issued = unsafe { self.response(&mut token) }?;
+            (issued, token.wait(bt))
+        };
+        let (headers, header_count, body_length) =
+            (message.headers, message.header_count, message.body_length);
+        // Build the response first, to free the headers if the reception failed
+        let response = HttpResponse {
+            bt,
+            status_code: response_data.status_code(),
+            headers,
+            header_count,
+            body_length,
+        };
+        outcome?.log();
+        Ok(Completion::new(issued.status(), response))
+    }
+
+    /// Waits for more of the body of the response, and receives it into
+    /// `body`.
+    ///
+    /// Returns the number of bytes received. See `send_request()` for the
+    /// requirements on `event`. The errors are those of `response()`, plus
+    /// the outcome of the reception.
+    ///
+    /// # Panics
+    ///
+    /// Panics if waiting for `event` fails, since the firmware may still be
+    /// writing to the body at that point.
+    pub fn receive_body(&self, bt: &BootServices, event: Event, body: &mut [u8]) -> Result<usize> {
+        let mut message = HttpMessage::body(body);
+        let issued = {
+            let mut token = HttpToken::new(event, &mut message);
+            let issued = unsafe { self.response(&mut token) }?;
+            token.wait(bt)?.log();
+            issued
+        };
+        Ok(Completion::new(issued.status(), message.body_length))
+    }
+}
+
+newtype_enum! {
+    /// Version of the HTTP protocol.
+    pub enum HttpVersion: u32 => {
+        /// HTTP/1.0.
+        HTTP_1_0    = 0,
+        /// HTTP/1.1.
+        HTTP_1_1    = 1,
+        /// Unsupported version.
+        UNSUPPORTED = 2,
+    }
+}
+
+newtype_enum! {
+    /// Method of an HTTP request.
+    pub enum HttpMethod: u32 => #[allow(missing_docs)] {
+        GET     = 0,
+        POST    = 1,
+        PATCH   = 2,
+        OPTIONS = 3,
+        CONNECT = 4,
+        HEAD    = 5,
+        PUT     = 6,
+        DELETE  = 7,
+        TRACE   = 8,
+    }
+}
+
+newtype_enum! {
+    /// Status code of an HTTP response, as encoded by UEFI.
+    ///
+    /// Use `code()` to get the numeric code.
+    pub enum HttpStatusCode: u32 => #[allow(missing_docs)] {
+        UNSUPPORTED                             = 0,
+        STATUS_100_CONTINUE                     = 1,
+        STATUS_101_SWITCHING_PROTOCOLS          = 2,
+        STATUS_200_OK                           = 3,
+        STATUS_201_CREATED                      = 4,
+        STATUS_202_ACCEPTED                     = 5,
+        STATUS_203_NON_AUTHORITATIVE_INFORMATION = 6,
+        STATUS_204_NO_CONTENT                   = 7,
+        STATUS_205_RESET_CONTENT                = 8,
+        STATUS_206_PARTIAL_CONTENT              = 9,
+        STATUS_300_MULTIPLE_CHOICES             = 10,
+        STATUS_301_MOVED_PERMANENTLY            = 11,
+        STATUS_302_FOUND                        = 12,
+        STATUS_303_SEE_OTHER                    = 13,
+        STATUS_304_NOT_MODIFIED                 = 14,
+        STATUS_305_USE_PROXY                    = 15,
+        STATUS_307_TEMPORARY_REDIRECT           = 16,
+        STATUS_400_BAD_REQUEST                  = 17,
+        STATUS_401_UNAUTHORIZED                 = 18,
+        STATUS_402_PAYMENT_REQUIRED             = 19,
+        STATUS_403_FORBIDDEN                    = 20,
+        STATUS_404_NOT_FOUND                    = 21,
+        STATUS_405_METHOD_NOT_ALLOWED           = 22,
+        STATUS_406_NOT_ACCEPTABLE               = 23,
+        STATUS_407_PROXY_AUTHENTICATION_REQUIRED = 24,
+        STATUS_408_REQUEST_TIME_OUT             = 25,
+        STATUS_409_CONFLICT                     = 26,
+        STATUS_410_GONE                         = 27,
+        STATUS_411_LENGTH_REQUIRED              = 28,
+        STATUS_412_PRECONDITION_FAILED          = 29,
+        STATUS_413_REQUEST_ENTITY_TOO_LARGE     = 30,
+        STATUS_414_REQUEST_URI_TOO_LARGE        = 31,
+        STATUS_415_UNSUPPORTED_MEDIA_TYPE       = 32,
+        STATUS_416_REQUESTED_RANGE_NOT_SATISFIED = 33,
+        STATUS_417_EXPECTATION_FAILED           = 34,
+        STATUS_500_INTERNAL_SERVER_ERROR        = 35,
+        STATUS_501_NOT_IMPLEMENTED              = 36,
+        STATUS_502_BAD_GATEWAY                  = 37,
+        STATUS_503_SERVICE_UNAVAILABLE          = 38,
+        STATUS_504_GATEWAY_TIME_OUT             = 39,
+        STATUS_505_HTTP_VERSION_NOT_SUPPORTED   = 40,
+        STATUS_308_PERMANENT_REDIRECT           = 41,
+        STATUS_429_TOO_MANY_REQUESTS            = 42,
+    }
+}
+
+impl HttpStatusCode {
+    /// Numeric codes of the statuses, in the order of their UEFI encoding
+    const CODES: [u16; 42] = [
+        100, 101, 200, 201, 202, 203, 204, 205, 206, 300, 301, 302, 303, 304, 305, 307, 400, 401,
+        402, 403, 404, 405, 406, 407, 408, 409, 410, 411, 412, 413, 414, 415, 416, 417, 500, 501,
+        502, 503, 504, 505, 308, 429,
+    ];
+
+    /// Numeric code of the status, such as 404, or `None` if it is not
+    /// supported.
+    pub fn code(self) -> Option<u16> {
+        let index = (self.0 as usize).checked_sub(1)?;
+        Self::CODES.get(index).copied()
+    }
+
+    /// True for the 2xx codes, which report a success.
+    pub fn is_success(self) -> bool {
+        matches!(self.code(), Some(200..=299))
+    }
+
+    /// True for the 3xx codes, which report a redirection.
+    pub fn is_redirection(self) -> bool {
+        matches!(self.code(), Some(300..=399))
+    }
+}
+
+/// Local end of an HTTP connection over IPv4.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct Httpv4AccessPoint {
+    /// Use the default address of the interface, rather than `local_address`
+    /// and `local_subnet`.
+    pub use_default_address: bool,
+    /// Local address.
+    pub local_address: Ipv4Address,
+    /// Subnet mask of the local address.
+    pub local_subnet: Ipv4Address,
+    /// Local port, or 0 to pick one.
+    pub local_port: u16,
+}
+
+impl Default for Httpv4AccessPoint {
+    fn default() -> Self {
+        Self {
+            use_default_address: true,
+            local_address: Ipv4Address::UNSPECIFIED,
+            local_subnet: Ipv4Address::UNSPECIFIED,
+            local_port: 0,
+        }
+    }
+}
+
+/// Local end of an HTTP connection over IPv6.
+#[derive(Debug, Default, Copy, Clone)]
+#[repr(C)]
+pub struct Httpv6AccessPoint {
+    /// Local address, or zero to let the IPv6 layer pick one.
+    pub local_address: Ipv6Address,
+    /// Local port, or 0 to pick one.
+    pub local_port: u16,
+}
+
+/// Local end of an HTTP connection.
+#[derive(Debug, Copy, Clone)]
+pub enum HttpAccessPoint {
+    /// Connection over IPv4.
+    Ipv4(Httpv4AccessPoint),
+    /// Connection over IPv6.
+    Ipv6(Httpv6AccessPoint),
+}
+
+/// Configuration of an `Http` instance.
+#[derive(Debug, Copy, Clone)]
+pub struct HttpConfig {
+    /// Version of the requests.
+    pub version: HttpVersion,
+    /// Time after which requests fail, in milliseconds, or 0 to use the
+    /// default of the firmware.
+    pub timeout_ms: u32,
+    /// Local end of the connections.
+    pub access_point: HttpAccessPoint,
+}
+
+impl Default for HttpConfig {
+    /// HTTP/1.1 over IPv4, from the default address of the interface.
+    fn default() -> Self {
+        Self {
+            version: HttpVersion::HTTP_1_1,
+            timeout_ms: 0,
+            access_point: HttpAccessPoint::Ipv4(Httpv4AccessPoint::default()),
+        }
+    }
+}
+
+/// Configuration of an `Http` instance, as stored by the firmware.
+#[repr(C)]
+struct RawConfigData {
+    http_version: HttpVersion,
+    time_out_millisec: u32,
+    local_address_is_ipv6: bool,
+    access_point: *mut c_void,
+}
+
+/// Storage for either access point, to read the configuration.
+#[repr(C)]
+union RawAccessPoint {
+    ipv4: Httpv4AccessPoint,
+    ipv6: Httpv6AccessPoint,
+}
+
+/// Header of an HTTP message, made of a field name and value.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct HttpHeader<'a> {
+    field_name: *const Char8,
+    field_value: *const Char8,
+    _strings: PhantomData<&'a CStr8>,
+}
+
+impl<'a> HttpHeader<'a> {
+    /// Creates a header, such as `Accept: */*`.
+    pub fn new(name: &'a CStr8, value: &'a CStr8) -> Self {
+        Self {
+            field_name: name.as_ptr(),
+            field_value: value.as_ptr(),
+            _strings: PhantomData,
+        }
+    }
+
+    /// Field name of the header.
+    pub fn name(&self) -> &CStr8 {
+        unsafe { CStr8::from_ptr(self.field_name) }
+    }
+
+    /// Field value of the header.
+    pub fn value(&self) -> &CStr8 {
+        unsafe { CStr8::from_ptr(self.field_value) }
+    }
+}
+
+/// Method and URL of an HTTP request.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct HttpRequestData<'a> {
+    method: HttpMethod,
+    url: *const Char16,
+    _url: PhantomData<&'a CStr16>,
+}
+
+impl<'a> HttpRequestData<'a> {
+    /// Creates a request of `url`, such as `http://example.com/index.html`.
+    pub fn new(method: HttpMethod, url: &'a CStr16) -> Self {
+        Self {
+            method,
+            url: url.as_ptr(),
+            _url: PhantomData,
+        }
+    }
+
+    /// Method of the request.
+    pub fn method(&self) -> HttpMethod {
+        self.method
+    }
+
+    /// URL of the request.
+    pub fn url(&self) -> &CStr16 {
+        unsafe { CStr16::from_ptr(self.url) }
+    }
+}
+
+/// Status of an HTTP response.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct HttpResponseData {
+    status_code: HttpStatusCode,
+}
+
+impl Default for HttpResponseData {
+    fn default() -> Self {
+        Self {
+            status_code: HttpStatusCode::UNSUPPORTED,
+        }
+    }
+}
+
+impl HttpResponseData {
+    /// Status code of the response.
+    pub fn status_code(&self) -> HttpStatusCode {
+        self.status_code
+    }
+}
+
+/// HTTP message, sent or received by an `Http` instance.
+#[repr(C)]
+pub struct HttpMessage<'a> {
+    data: *mut c_void,
+    header_count: usize,
+    headers: *mut HttpHeader<'static>,
+    body_length: usize,
+    body: *mut c_void,
+    _data: PhantomData<&'a mut [u8]>,
+}
+
+impl<'a> HttpMessage<'a> {
+    /// Describes a request, with its headers and body.
+    pub fn request(
+        request: &'a HttpRequestData<'a>,
+        headers: &'a [HttpHeader<'a>],
+        body: &'a [u8],
+    ) -> Self {
+        Self {
+            data: request as *const HttpRequestData as *mut c_void,
+            header_count: headers.len(),
+            headers: headers.as_ptr() as *mut HttpHeader<'static>,
+            body_length: body.len(),
+            body: body.as_ptr() as *mut c_void,
+            _data: PhantomData,
+        }
+    }
+
+    /// Creates a message receiving the status and headers of a response into
+    /// `response`, and the start of its body into `body`.
+    pub fn response(response: &'a mut HttpResponseData, body: &'a mut [u8]) -> Self {
+        Self {
+            data: response as *mut HttpResponseData as *mut c_void,
+            header_count: 0,
+            headers: ptr::null_mut(),
+            body_length: body.len(),
+            body: body.as_mut_ptr() as *mut c_void,
+            _data: PhantomData,
+        }
+    }
+
+    /// Creates a message receiving more of the body of a response into
+    /// `body`.
+    pub fn body(body: &'a mut [u8]) -> Self {
+        Self {
+            data: ptr::null_mut(),
+            header_count: 0,
+            headers: ptr::null_mut(),
+            body_length: body.len(),
+            body: body.as_mut_ptr() as *mut c_void,
+            _data: PhantomData,
+        }
+    }
+
+    /// Headers of the message.
+    ///
+    /// # Safety
+    ///
+    /// For a received response, the token of this message must have been
+    /// completed.
+    pub unsafe fn headers(&self) -> &[HttpHeader<'_>] {
+        if self.headers.is_null() {
+            &[]
+        } else {
+            slice::from_raw_parts(self.headers, self.header_count)
+        }
+    }
+
+    /// Size of the body of the message, i.e., for a received response, how
+    /// many bytes of the body were received.
+    pub fn body_length(&self) -> usize {
+        self.body_length
+    }
+
+    /// Frees the headers of a received response.
+    ///
+    /// # Safety
+    ///
+    /// The headers must have been received by `Http::response()`, and may not
+    /// be accessed afterwards.
+    pub unsafe fn free_headers(&mut self, bt: &BootServices) {
+        free_headers(bt, self.headers, self.header_count);
+        self.headers = ptr::null_mut();
+        self.header_count = 0;
+    }
+}
+
+/// Token tracking an asynchronous operation of an `Http` instance.
+#[repr(C)]
+pub struct HttpToken<'msg> {
+    event: Event,
+    // Written by the firmware behind the back of the compiler
+    status: UnsafeCell<Status>,
+    message: *mut c_void,
+    _message: PhantomData<&'msg mut ()>,
+}
+
+impl<'msg> HttpToken<'msg> {
+    /// Creates a token sending or receiving `message`, which signals `event`
+    /// upon completion.
+    pub fn new(event: Event, message: &'msg mut HttpMessage<'_>) -> Self {
+        Self {
+            event,
+            status: UnsafeCell::new(Status::SUCCESS),
+            message: message as *mut HttpMessage as *mut c_void,
+            _message: PhantomData,
+        }
+    }
+
+    /// Event signaled upon completion of the operation.
+    pub fn event(&self) -> Event {
+        self.event
+    }
+
+    /// Outcome of the operation.
+    ///
+    /// This is only meaningful once the event has been signaled.
+    pub fn status(&self) -> Status {
+        unsafe { ptr::read_volatile(self.status.get()) }
+    }
+
+    /// Waits for the pending operation to complete, and returns its outcome.
+    fn wait(&self, bt: &BootServices) -> Result {
+        bt.wait_for_event(&mut [self.event])
+            .expect_success("Failed to wait for a pending HTTP operation");
+        self.status().into()
+    }
+}
+
+/// Status and headers of an HTTP response, received with
+/// `Http::receive_response()`.
+///
+/// The headers are freed when this is dropped.
+pub struct HttpResponse<'boot> {
+    bt: &'boot BootServices,
+    status_code: HttpStatusCode,
+    headers: *mut HttpHeader<'static>,
+    header_count: usize,
+    body_length: usize,
+}
+
+impl HttpResponse<'_> {
+    /// Status code of the response.
+    pub fn status_code(&self) -> HttpStatusCode {
+        self.status_code
+    }
+
+    /// Headers of the response.
+    pub fn headers(&self) -> &[HttpHeader<'_>] {
+        if self.headers.is_null() {
+            &[]
+        } else {
+            unsafe { slice::from_raw_parts(self.headers, self.header_count) }
+        }
+    }
+
+    /// Value of the first header whose field name is `name`, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&CStr8> {
+        self.headers()
+            .iter()
+            .find(|header| {
+                header
+                    .name()
+                    .to_bytes()
+                    .eq_ignore_ascii_case(name.as_bytes())
+            })
+            .map(|header| header.value())
+    }
+
+    /// Number of bytes of the body received along with the headers.
+    pub fn body_length(&self) -> usize {
+        self.body_length
+    }
+}
+
+impl Drop for HttpResponse<'_> {
+    fn drop(&mut self) {
+        unsafe { free_headers(self.bt, self.headers, self.header_count) };
+    }
+}
+
+/// Frees headers allocated by the firmware, along with their strings
+unsafe fn free_headers(bt: &BootServices, headers: *mut HttpHeader, count: usize) {
+    if headers.is_null() {
+        return;
+    }
+    // Nothing more can be done if freeing fails
+    for header in slice::from_raw_parts(headers, count) {
+        let _ = bt.free_pool(header.field_name as *mut u8);
+        let _ = bt.free_pool(header.field_value as *mut u8);
+    }
+    let _ = bt.free_pool(headers as *mut u8);
+}
+
+/// Most redirections followed by `get()`
+#[cfg(feature = "exts")]
+const MAX_REDIRECTS: usize = 8;
+
+/// Size of the pieces in which `get()` receives bodies
+#[cfg(feature = "exts")]
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Downloads the resource at `url`, such as `http://10.0.2.2/vmlinuz`.
+///
+/// See `get_with_progress()`.
+#[cfg(feature = "exts")]
+pub fn get(bt: &BootServices, url: &str) -> Result<Vec<u8>, Option<HttpStatusCode>> {
+    get_with_progress(bt, url, |_, _| {})
+}
+
+/// Downloads the resource at `url`, such as `http://10.0.2.2/vmlinuz`,
+/// through the first network interface supporting HTTP.
+///
+/// Redirections are followed, and `progress` is called with the number of
+/// bytes received so far and, if the server announced it, the size of the
+/// resource, every time a piece of it is received.
+///
+/// # Errors
+///
+/// - `InvalidParameter` if the URL is not an absolute HTTP(S) URL
+/// - `Unsupported` if no network interface supports HTTP
+/// - `HttpError` if the server answered with an error, whose status code is
+///   returned as part of the error, or redirected too many times
+/// - `OutOfResources` if the announced size of the resource can not be
+///   allocated
+/// - The errors of `Http::configure()`, `Http::send_request()` and
+///   `Http::receive_response()`
+#[cfg(feature = "exts")]
+pub fn get_with_progress(
+    bt: &BootServices,
+    url: &str,
+    mut progress: impl FnMut(usize, Option<usize>),
+) -> Result<Vec<u8>, Option<HttpStatusCode>> {
+    let no_data = |err: Error| Error::new(err.status(), None);
+    let invalid_url = || Error::new(Status::INVALID_PARAMETER, None);
+
+    split_url(url).ok_or_else(invalid_url)?;
+    let device = *bt
+        .find_handles::<HttpServiceBinding>()
+        .map_err(no_data)?
+        .log()
+        .first()
+        .ok_or_else(|| Error::new(Status::UNSUPPORTED, None))?;
+
+    let mut url = String::from(url);
+    let mut chunk = vec![0; CHUNK_SIZE];
+    for _ in 0..=MAX_REDIRECTS {
+        // An instance only talks to one server, so each request needs its own
+        let session = Session::open(bt, device, &url).map_err(no_data)?.log();
+        let response = session.get(&url, &mut chunk).map_err(no_data)?.log();
+        let status_code = response.status_code();
+
+        if status_code.is_redirection() {
+            let location = response
+                .header("Location")
+                .and_then(|location| core::str::from_utf8(location.to_bytes()).ok())
+                .ok_or_else(|| Error::new(Status::HTTP_ERROR, Some(status_code)))?;
+            url = resolve_url(&url, location).ok_or_else(invalid_url)?;
+            continue;
+        }
+        if !status_code.is_success() {
+            return Err(Error::new(Status::HTTP_ERROR, Some(status_code)));
+        }
+
+        let total = response
+            .header("Content-Length")
+            .and_then(|length| core::str::from_utf8(length.to_bytes()).ok())
+            .and_then(|length| length.trim().parse::<usize>().ok());
+        // The length comes from the server, so it may not fit in memory
+        let mut data = Vec::new();
+        data.try_reserve(total.unwrap_or(0))
+            .map_err(|_| Error::new(Status::OUT_OF_RESOURCES, None))?;
+        data.extend_from_slice(&chunk[..response.body_length().min(chunk.len())]);
+        progress(data.len(), total);
+        if status_code == HttpStatusCode::STATUS_204_NO_CONTENT {
+            return Ok(data.into());
+        }
+
+        // Without a length, the end of the body is an empty piece, or the
+        // server closing the connection
+        while total.is_none_or(|total| data.len() < total) {
+            match session.http.receive_body(bt, session.event, &mut chunk) {
+                Ok(received) => {
+                    let received = received.log();
+                    if received == 0 {
+                        break;
+                    }
+                    data.extend_from_slice(&chunk[..received]);
+                    progress(data.len(), total);
+                }
+                Err(err) if total.is_none() && err.status() == Status::CONNECTION_FIN => break,
+                Err(err) => return Err(no_data(err)),
+            }
+        }
+        return Ok(data.into());
+    }
+    Err(Error::new(Status::HTTP_ERROR, None))
+}
+
+/// Instance of `Http` used by `get()`, destroyed when dropped
+#[cfg(feature = "exts")]
+struct Session<'boot> {
+    bt: &'boot BootServices,
+    binding: &'boot HttpServiceBinding,
+    child: Handle,
+    http: &'boot Http,
+    event: Event,
+}
+
+#[cfg(feature = "exts")]
+impl<'boot> Session<'boot> {
+    /// Creates and configures an instance on `device`, able to reach the
+    /// server of `url`
+    fn open(bt: &'boot BootServices, device: Handle, url: &str) -> Result<Self> {
+        let binding = bt.handle_protocol::<HttpServiceBinding>(device)?.log();
+        let binding = unsafe { &*binding.get() };
+        let child = binding.create_child()?.log();
+        let http = match bt.handle_protocol::<Http>(child) {
+            Ok(http) => unsafe { &*http.log().get() },
+            Err(err) => {
+                let _ = binding.destroy_child(child);
+                return Err(err);
+            }
+        };
+        let event = match unsafe { bt.create_event(EventType::empty(), Tpl::APPLICATION, None) } {
+            Ok(event) => event.log(),
+            Err(err) => {
+                let _ = binding.destroy_child(child);
+                return Err(err);
+            }
+        };
+        let session = Self {
+            bt,
+            binding,
+            child,
+            http,
+            event,
+        };
+
+        // IPv6 hosts are written between brackets
+        let is_ipv6 = split_url(url).is_some_and(|(_, authority, _)| authority.starts_with('['));
+        let config = HttpConfig {
+            access_point: if is_ipv6 {
+                HttpAccessPoint::Ipv6(Httpv6AccessPoint::default())
+            } else {
+                HttpAccessPoint::Ipv4(Httpv4AccessPoint::default())
+            },
+            ..HttpConfig::default()
+        };
+        session.http.configure(Some(&config))?.log();
+        Ok(session.into())
+    }
+
+    /// Sends a GET request of `url`, and receives the response and the start
+    /// of its body into `body`
+    fn get(&self, url: &str, body: &mut [u8]) -> Result<HttpResponse<'boot>> {
+        let (_, authority, _) = split_url(url).ok_or(Status::INVALID_PARAMETER)?;
+        if !url.is_ascii() || url.contains('\0') {
+            return Err(Status::INVALID_PARAMETER.into());
+        }
+        let url_ucs2: Vec<u16> = url.bytes().map(u16::from).chain(Some(0)).collect();
+        let url_ucs2 =
+            CStr16::from_u16_with_nul(&url_ucs2).map_err(|_| Status::INVALID_PARAMETER)?;
+        let host: Vec<u8> = authority.bytes().chain(Some(0)).collect();
+
+        let headers = [
+            HttpHeader::new(cstr8(b"Host\0"), cstr8(&host)),
+            HttpHeader::new(cstr8(b"Accept\0"), cstr8(b"*/*\0")),
+            HttpHeader::new(cstr8(b"User-Agent\0"), cstr8(b"uefi-rs\0")),
+        ];
+        self.http
+            .send_request(
+                self.bt,
+                self.event,
+                HttpMethod::GET,
+                url_ucs2,
+                &headers,
+                &[],
+            )?
+            .log();
+        self.http.receive_response(self.bt, self.event, body)
+    }
+}
+
+#[cfg(feature = "exts")]
+impl Drop for Session<'_> {
+    fn drop(&mut self) {
+        // Nothing more can be done if any of this fails
+        let _ = self.http.configure(None);
+        let _ = self.binding.destroy_child(self.child);
+        let _ = unsafe { self.bt.close_event(self.event) };
+    }
+}
+
+/// Converts a nul-terminated ASCII string without inner nul
+#[cfg(feature = "exts")]
+fn cstr8(bytes: &[u8]) -> &CStr8 {
+    match CStr8::from_bytes_with_nul(bytes) {
+        Ok(string) => string,
+        Err(_) => panic!("Header is not a nul-terminated string"),
+    }
+}
+
+/// Splits an absolute HTTP(S) URL into its scheme, authority and path
+#[cfg(feature = "exts")]
+fn split_url(url: &str) -> Option<(&str, &str, &str)> {
+    let (scheme, rest) = url.split_once("://")?;
+    if !scheme.eq_ignore_ascii_case("http") && !scheme.eq_ignore_ascii_case("https") {
+        return None;
+    }
+    let (authority, path) = match rest.find(['/', '?']) {
+        Some(index) => rest.split_at(index),
+        None => (rest, ""),
+    };
+    if authority.is_empty() {
+        return None;
+    }
+    Some((scheme, authority, path))
+}
+
+/// Resolves the target of a redirection, relative to the URL which was
+/// redirected
+#[cfg(feature = "exts")]
+fn resolve_url(base: &str, location: &str) -> Option<String> {
+    let (scheme, authority, path) = split_url(base)?;
+    let mut url = String::new();
+    if location.contains("://") {
+        url.push_str(location);
+    } else if location.starts_with("//") {
+        url.push_str(scheme);
+        url.push(':');
+        url.push_str(location);
+    } else if location.starts_with('/') {
+        url.push_str(scheme);
+        url.push_str("://");
+        url.push_str(authority);
+        url.push_str(location);
+    } else {
+        // Relative to the directory of the path, ignoring its query
+        let path = path.split('?').next().unwrap_or("");
+        let directory = &path[..path.rfind('/').map_or(0, |index| index + 1)];
+        url.push_str(scheme);
+        url.push_str("://");
+        url.push_str(authority);
+        if directory.is_empty() {
+            url.push('/');
+        }
+        url.push_str(directory);
+        url.push_str(location);
+    }
+    split_url(&url)?;
+    Some(url)
+}
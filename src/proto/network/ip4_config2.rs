@@ -0,0 +1,340 @@
+//! IPv4 configuration protocol.
+//!
+//! This protocol reads and changes the IPv4 configuration of a network
+//! interface, as used by the firmware's network stack: whether it is obtained
+//! by DHCP or set manually, and the addresses of the interface, gateways and
+//! DNS servers.
+
+use super::{Ipv4Address, MacAddress};
+use crate::proto::Protocol;
+use crate::result::Error;
+use crate::{unsafe_guid, CStr16, Event, Result, Status};
+use core::ffi::c_void;
+use core::{mem, ptr, slice};
+
+/// IPv4 configuration of a network interface.
+///
+/// It is installed on the handles of the network interfaces.
+#[repr(C)]
+#[unsafe_guid("5b446ed1-e30b-4faa-871a-3654eca36080")]
+#[derive(Protocol)]
+pub struct Ip4Config2 {
+    set_data: extern "efiapi" fn(
+        this: &Ip4Config2,
+        data_type: Ip4Config2DataType,
+        data_size: usize,
+        data: *const c_void,
+    ) -> Status,
+    get_data: extern "efiapi" fn(
+        this: &Ip4Config2,
+        data_type: Ip4Config2DataType,
+        data_size: &mut usize,
+        data: *mut c_void,
+    ) -> Status,
+    register_data_notify: extern "efiapi" fn(
+        this: &Ip4Config2,
+        data_type: Ip4Config2DataType,
+        event: Event,
+    ) -> Status,
+    unregister_data_notify: extern "efiapi" fn(
+        this: &Ip4Config2,
+        data_type: Ip4Config2DataType,
+        event: Event,
+    ) -> Status,
+}
+
+impl Ip4Config2 {
+    /// Reads the description of the interface into `buffer`.
+    ///
+    /// If the buffer is too small, the required buffer size will be returned
+    /// as part of the error.
+    ///
+    /// # Errors
+    ///
+    /// - `BufferTooSmall` if the buffer cannot hold the description
+    pub fn interface_info<'buf>(
+        &self,
+        buffer: &'buf mut [u8],
+    ) -> Result<Ip4InterfaceInfo<'buf>, Option<usize>> {
+        let mut size = buffer.len();
+        let status = (self.get_data)(
+            self,
+            Ip4Config2DataType::INTERFACE_INFO,
+            &mut size,
+            buffer.as_mut_ptr() as *mut c_void,
+        );
+        status
+            .into_with_err(|s| {
+                if s == Status::BUFFER_TOO_SMALL {
+                    Some(size)
+                } else {
+                    None
+                }
+            })?
+            .log();
+        if size < mem::size_of::<RawInterfaceInfo>() {
+            return Err(Error::new(Status::VOLUME_CORRUPTED, None));
+        }
+        let raw = unsafe { ptr::read_unaligned(buffer.as_ptr() as *const RawInterfaceInfo) };
+        let route_table = if raw.route_table.is_null() {
+            &[][..]
+        } else {
+            unsafe { slice::from_raw_parts(raw.route_table, raw.route_table_size as usize) }
+        };
+        Ok(Ip4InterfaceInfo { raw, route_table }.into())
+    }
+
+    /// Returns the policy of the interface.
+    pub fn policy(&self) -> Result<Ip4Config2Policy> {
+        let mut policy = Ip4Config2Policy::STATIC;
+        self.get_array(Ip4Config2DataType::POLICY, slice::from_mut(&mut policy))
+            .map_err(|err| err.status().into())
+            .map(|completion| completion.map(|_| policy))
+    }
+
+    /// Changes the policy of the interface.
+    ///
+    /// Changing the policy clears the manual addresses, gateways and DNS
+    /// servers of the interface.
+    pub fn set_policy(&self, policy: Ip4Config2Policy) -> Result {
+        self.set_array(Ip4Config2DataType::POLICY, &[policy])
+    }
+
+    /// Writes the manual addresses of the interface into `buffer`, and
+    /// returns how many there are.
+    ///
+    /// If the buffer is too small, the number of addresses is returned as
+    /// part of the error.
+    ///
+    /// # Errors
+    ///
+    /// - `BufferTooSmall` if the buffer cannot hold all the addresses
+    /// - `NotFound` if the interface has no manual address
+    pub fn manual_addresses(
+        &self,
+        buffer: &mut [Ip4ManualAddress],
+    ) -> Result<usize, Option<usize>> {
+        self.get_array(Ip4Config2DataType::MANUAL_ADDRESS, buffer)
+    }
+
+    /// Sets the manual addresses of the interface, which requires the static
+    /// policy.
+    ///
+    /// The addresses are checked on the network first, in which case
+    /// `NotReady` is returned and the outcome is reported through the events
+    /// registered with `register_data_notify()`.
+    ///
+    /// # Errors
+    ///
+    /// - `NotReady` if the addresses are being checked
+    /// - `WriteProtected` if the policy is not static
+    /// - `InvalidParameter` if an address is not valid
+    pub fn set_manual_addresses(&self, addresses: &[Ip4ManualAddress]) -> Result {
+        self.set_array(Ip4Config2DataType::MANUAL_ADDRESS, addresses)
+    }
+
+    /// Writes the gateways of the interface into `buffer`, and returns how
+    /// many there are.
+    ///
+    /// If the buffer is too small, the number of gateways is returned as part
+    /// of the error.
+    ///
+    /// # Errors
+    ///
+    /// - `BufferTooSmall` if the buffer cannot hold all the gateways
+    /// - `NotFound` if the interface has no gateway
+    pub fn gateways(&self, buffer: &mut [Ipv4Address]) -> Result<usize, Option<usize>> {
+        self.get_array(Ip4Config2DataType::GATEWAY, buffer)
+    }
+
+    /// Sets the gateways of the interface, which requires the static policy.
+    ///
+    /// # Errors
+    ///
+    /// - `WriteProtected` if the policy is not static
+    /// - `InvalidParameter` if an address is not valid
+    pub fn set_gateways(&self, gateways: &[Ipv4Address]) -> Result {
+        self.set_array(Ip4Config2DataType::GATEWAY, gateways)
+    }
+
+    /// Writes the DNS servers of the interface into `buffer`, and returns how
+    /// many there are.
+    ///
+    /// If the buffer is too small, the number of servers is returned as part
+    /// of the error.
+    ///
+    /// # Errors
+    ///
+    /// - `BufferTooSmall` if the buffer cannot hold all the servers
+    /// - `NotFound` if the interface has no DNS server
+    pub fn dns_servers(&self, buffer: &mut [Ipv4Address]) -> Result<usize, Option<usize>> {
+        self.get_array(Ip4Config2DataType::DNS_SERVER, buffer)
+    }
+
+    /// Sets the DNS servers of the interface, which requires the static
+    /// policy.
+    ///
+    /// # Errors
+    ///
+    /// - `WriteProtected` if the policy is not static
+    /// - `InvalidParameter` if an address is not valid
+    pub fn set_dns_servers(&self, servers: &[Ipv4Address]) -> Result {
+        self.set_array(Ip4Config2DataType::DNS_SERVER, servers)
+    }
+
+    /// Registers an event, which is signaled when the configuration data of
+    /// the given type changes.
+    ///
+    /// # Errors
+    ///
+    /// - `AccessDenied` if the event is already registered for this type
+    /// - `Unsupported` if the data type is not supported
+    pub fn register_data_notify(&self, data_type: Ip4Config2DataType, event: Event) -> Result {
+        (self.register_data_notify)(self, data_type, event).into()
+    }
+
+    /// Unregisters an event registered with `register_data_notify()`.
+    ///
+    /// # Errors
+    ///
+    /// - `NotFound` if the event is not registered for this type
+    pub fn unregister_data_notify(&self, data_type: Ip4Config2DataType, event: Event) -> Result {
+        (self.unregister_data_notify)(self, data_type, event).into()
+    }
+
+    /// Reads configuration data made of an array of `T`
+    fn get_array<T>(
+        &self,
+        data_type: Ip4Config2DataType,
+        buffer: &mut [T],
+    ) -> Result<usize, Option<usize>> {
+        let item_size = mem::size_of::<T>();
+        let mut size = mem::size_of_val(buffer);
+        (self.get_data)(
+            self,
+            data_type,
+            &mut size,
+            buffer.as_mut_ptr() as *mut c_void,
+        )
+        .into_with(
+            || size / item_size,
+            |s| {
+                if s == Status::BUFFER_TOO_SMALL {
+                    Some(size / item_size)
+                } else {
+                    None
+                }
+            },
+        )
+    }
+
+    /// Changes configuration data made of an array of `T`
+    fn set_array<T>(&self, data_type: Ip4Config2DataType, data: &[T]) -> Result {
+        let size = mem::size_of_val(data);
+        (self.set_data)(self, data_type, size, data.as_ptr() as *const c_void).into()
+    }
+}
+
+newtype_enum! {
+    /// Type of the configuration data of an `Ip4Config2` instance.
+    pub enum Ip4Config2DataType: u32 => {
+        /// Description of the interface, read-only.
+        INTERFACE_INFO = 0,
+        /// Policy of the interface.
+        POLICY         = 1,
+        /// Addresses set manually.
+        MANUAL_ADDRESS = 2,
+        /// Gateways.
+        GATEWAY        = 3,
+        /// DNS servers.
+        DNS_SERVER     = 4,
+    }
+}
+
+newtype_enum! {
+    /// Source of the IPv4 configuration of a network interface.
+    pub enum Ip4Config2Policy: u32 => {
+        /// The configuration is set manually.
+        STATIC = 0,
+        /// The configuration is obtained by DHCP.
+        DHCP   = 1,
+    }
+}
+
+/// IPv4 address set manually on a network interface.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+#[repr(C)]
+pub struct Ip4ManualAddress {
+    /// Address of the interface.
+    pub address: Ipv4Address,
+    /// Subnet mask of the address.
+    pub subnet_mask: Ipv4Address,
+}
+
+/// Entry of the routing table of a network interface.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(C)]
+pub struct Ip4RouteEntry {
+    /// Address of the destination subnet.
+    pub subnet_address: Ipv4Address,
+    /// Mask of the destination subnet.
+    pub subnet_mask: Ipv4Address,
+    /// Gateway to the subnet, or zero if it is directly reachable.
+    pub gateway_address: Ipv4Address,
+}
+
+/// Description of a network interface, as stored by the firmware.
+#[derive(Copy, Clone)]
+#[repr(C)]
+struct RawInterfaceInfo {
+    name: [u16; 32],
+    if_type: u8,
+    hw_address_size: u32,
+    hw_address: MacAddress,
+    station_address: Ipv4Address,
+    subnet_mask: Ipv4Address,
+    route_table_size: u32,
+    route_table: *const Ip4RouteEntry,
+}
+
+/// Description of a network interface, read with
+/// `Ip4Config2::interface_info()`.
+#[derive(Copy, Clone)]
+pub struct Ip4InterfaceInfo<'buf> {
+    raw: RawInterfaceInfo,
+    route_table: &'buf [Ip4RouteEntry],
+}
+
+impl Ip4InterfaceInfo<'_> {
+    /// Name of the interface, such as `eth0`.
+    pub fn name(&self) -> Option<&CStr16> {
+        let len = self.raw.name.iter().position(|&c| c == 0)?;
+        CStr16::from_u16_with_nul(&self.raw.name[..=len]).ok()
+    }
+
+    /// Type of the interface, as defined by the IANA `ifType` numbers.
+    pub fn if_type(&self) -> u8 {
+        self.raw.if_type
+    }
+
+    /// Hardware address of the interface.
+    pub fn hw_address(&self) -> &[u8] {
+        let size = (self.raw.hw_address_size as usize).min(self.raw.hw_address.0.len());
+        &self.raw.hw_address.0[..size]
+    }
+
+    /// Current address of the interface.
+    pub fn station_address(&self) -> Ipv4Address {
+        self.raw.station_address
+    }
+
+    /// Subnet mask of the current address.
+    pub fn subnet_mask(&self) -> Ipv4Address {
+        self.raw.subnet_mask
+    }
+
+    /// Routing table of the interface.
+    pub fn route_table(&self) -> &[Ip4RouteEntry] {
+        self.route_table
+    }
+}
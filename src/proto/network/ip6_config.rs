@@ -0,0 +1,374 @@
+//! IPv6 configuration protocol.
+//!
+//! This protocol reads and changes the IPv6 configuration of a network
+//! interface, as used by the firmware's network stack: whether it is obtained
+//! automatically or set manually, and the addresses of the interface,
+//! gateways and DNS servers.
+
+use super::{Ipv6Address, MacAddress};
+use crate::proto::Protocol;
+use crate::result::Error;
+use crate::{unsafe_guid, CStr16, Event, Result, Status};
+use core::ffi::c_void;
+use core::{mem, ptr, slice};
+
+/// IPv6 configuration of a network interface.
+///
+/// It is installed on the handles of the network interfaces.
+#[repr(C)]
+#[unsafe_guid("937fe521-95ae-4d1a-8929-48bcd90ad31a")]
+#[derive(Protocol)]
+pub struct Ip6Config {
+    set_data: extern "efiapi" fn(
+        this: &Ip6Config,
+        data_type: Ip6ConfigDataType,
+        data_size: usize,
+        data: *const c_void,
+    ) -> Status,
+    get_data: extern "efiapi" fn(
+        this: &Ip6Config,
+        data_type: Ip6ConfigDataType,
+        data_size: &mut usize,
+        data: *mut c_void,
+    ) -> Status,
+    register_data_notify:
+        extern "efiapi" fn(this: &Ip6Config, data_type: Ip6ConfigDataType, event: Event) -> Status,
+    unregister_data_notify:
+        extern "efiapi" fn(this: &Ip6Config, data_type: Ip6ConfigDataType, event: Event) -> Status,
+}
+
+impl Ip6Config {
+    /// Reads the description of the interface into `buffer`.
+    ///
+    /// If the buffer is too small, the required buffer size will be returned
+    /// as part of the error.
+    ///
+    /// # Errors
+    ///
+    /// - `BufferTooSmall` if the buffer cannot hold the description
+    pub fn interface_info<'buf>(
+        &self,
+        buffer: &'buf mut [u8],
+    ) -> Result<Ip6InterfaceInfo<'buf>, Option<usize>> {
+        let mut size = buffer.len();
+        let status = (self.get_data)(
+            self,
+            Ip6ConfigDataType::INTERFACE_INFO,
+            &mut size,
+            buffer.as_mut_ptr() as *mut c_void,
+        );
+        status
+            .into_with_err(|s| {
+                if s == Status::BUFFER_TOO_SMALL {
+                    Some(size)
+                } else {
+                    None
+                }
+            })?
+            .log();
+        if size < mem::size_of::<RawInterfaceInfo>() {
+            return Err(Error::new(Status::VOLUME_CORRUPTED, None));
+        }
+        let raw = unsafe { ptr::read_unaligned(buffer.as_ptr() as *const RawInterfaceInfo) };
+        let addresses = if raw.address_info.is_null() {
+            &[][..]
+        } else {
+            unsafe { slice::from_raw_parts(raw.address_info, raw.address_info_count as usize) }
+        };
+        let route_table = if raw.route_table.is_null() {
+            &[][..]
+        } else {
+            unsafe { slice::from_raw_parts(raw.route_table, raw.route_count as usize) }
+        };
+        Ok(Ip6InterfaceInfo {
+            raw,
+            addresses,
+            route_table,
+        }
+        .into())
+    }
+
+    /// Returns the policy of the interface.
+    pub fn policy(&self) -> Result<Ip6ConfigPolicy> {
+        let mut policy = Ip6ConfigPolicy::MANUAL;
+        self.get_array(Ip6ConfigDataType::POLICY, slice::from_mut(&mut policy))
+            .map_err(|err| err.status().into())
+            .map(|completion| completion.map(|_| policy))
+    }
+
+    /// Changes the policy of the interface.
+    ///
+    /// Changing the policy clears the manual addresses, gateways and DNS
+    /// servers of the interface.
+    pub fn set_policy(&self, policy: Ip6ConfigPolicy) -> Result {
+        self.set_array(Ip6ConfigDataType::POLICY, &[policy])
+    }
+
+    /// Returns the number of Neighbor Solicitation messages sent to detect a
+    /// duplicate address, before an address is used.
+    pub fn dup_addr_detect_transmits(&self) -> Result<u32> {
+        let mut transmits = 0;
+        self.get_array(
+            Ip6ConfigDataType::DUP_ADDR_DETECT_TRANSMITS,
+            slice::from_mut(&mut transmits),
+        )
+        .map_err(|err| err.status().into())
+        .map(|completion| completion.map(|_| transmits))
+    }
+
+    /// Changes the number of Neighbor Solicitation messages sent to detect a
+    /// duplicate address, 0 disabling the detection.
+    pub fn set_dup_addr_detect_transmits(&self, transmits: u32) -> Result {
+        self.set_array(Ip6ConfigDataType::DUP_ADDR_DETECT_TRANSMITS, &[transmits])
+    }
+
+    /// Writes the manual addresses of the interface into `buffer`, and
+    /// returns how many there are.
+    ///
+    /// If the buffer is too small, the number of addresses is returned as
+    /// part of the error.
+    ///
+    /// # Errors
+    ///
+    /// - `BufferTooSmall` if the buffer cannot hold all the addresses
+    /// - `NotFound` if the interface has no manual address
+    pub fn manual_addresses(
+        &self,
+        buffer: &mut [Ip6ManualAddress],
+    ) -> Result<usize, Option<usize>> {
+        self.get_array(Ip6ConfigDataType::MANUAL_ADDRESS, buffer)
+    }
+
+    /// Sets the manual addresses of the interface, which requires the manual
+    /// policy.
+    ///
+    /// The addresses are checked for duplicates on the network first, in
+    /// which case `NotReady` is returned and the outcome is reported through
+    /// the events registered with `register_data_notify()`.
+    ///
+    /// # Errors
+    ///
+    /// - `NotReady` if the addresses are being checked
+    /// - `WriteProtected` if the policy is not manual
+    /// - `InvalidParameter` if an address is not valid
+    pub fn set_manual_addresses(&self, addresses: &[Ip6ManualAddress]) -> Result {
+        self.set_array(Ip6ConfigDataType::MANUAL_ADDRESS, addresses)
+    }
+
+    /// Writes the gateways of the interface into `buffer`, and returns how
+    /// many there are.
+    ///
+    /// If the buffer is too small, the number of gateways is returned as part
+    /// of the error.
+    ///
+    /// # Errors
+    ///
+    /// - `BufferTooSmall` if the buffer cannot hold all the gateways
+    /// - `NotFound` if the interface has no gateway
+    pub fn gateways(&self, buffer: &mut [Ipv6Address]) -> Result<usize, Option<usize>> {
+        self.get_array(Ip6ConfigDataType::GATEWAY, buffer)
+    }
+
+    /// Sets the gateways of the interface, which requires the manual policy.
+    ///
+    /// # Errors
+    ///
+    /// - `WriteProtected` if the policy is not manual
+    /// - `InvalidParameter` if an address is not valid
+    pub fn set_gateways(&self, gateways: &[Ipv6Address]) -> Result {
+        self.set_array(Ip6ConfigDataType::GATEWAY, gateways)
+    }
+
+    /// Writes the DNS servers of the interface into `buffer`, and returns how
+    /// many there are.
+    ///
+    /// If the buffer is too small, the number of servers is returned as part
+    /// of the error.
+    ///
+    /// # Errors
+    ///
+    /// - `BufferTooSmall` if the buffer cannot hold all the servers
+    /// - `NotFound` if the interface has no DNS server
+    pub fn dns_servers(&self, buffer: &mut [Ipv6Address]) -> Result<usize, Option<usize>> {
+        self.get_array(Ip6ConfigDataType::DNS_SERVER, buffer)
+    }
+
+    /// Sets the DNS servers of the interface, which requires the manual
+    /// policy.
+    ///
+    /// # Errors
+    ///
+    /// - `WriteProtected` if the policy is not manual
+    /// - `InvalidParameter` if an address is not valid
+    pub fn set_dns_servers(&self, servers: &[Ipv6Address]) -> Result {
+        self.set_array(Ip6ConfigDataType::DNS_SERVER, servers)
+    }
+
+    /// Registers an event, which is signaled when the configuration data of
+    /// the given type changes.
+    ///
+    /// # Errors
+    ///
+    /// - `AccessDenied` if the event is already registered for this type
+    /// - `Unsupported` if the data type is not supported
+    pub fn register_data_notify(&self, data_type: Ip6ConfigDataType, event: Event) -> Result {
+        (self.register_data_notify)(self, data_type, event).into()
+    }
+
+    /// Unregisters an event registered with `register_data_notify()`.
+    ///
+    /// # Errors
+    ///
+    /// - `NotFound` if the event is not registered for this type
+    pub fn unregister_data_notify(&self, data_type: Ip6ConfigDataType, event: Event) -> Result {
+        (self.unregister_data_notify)(self, data_type, event).into()
+    }
+
+    /// Reads configuration data made of an array of `T`
+    fn get_array<T>(
+        &self,
+        data_type: Ip6ConfigDataType,
+        buffer: &mut [T],
+    ) -> Result<usize, Option<usize>> {
+        let item_size = mem::size_of::<T>();
+        let mut size = mem::size_of_val(buffer);
+        (self.get_data)(
+            self,
+            data_type,
+            &mut size,
+            buffer.as_mut_ptr() as *mut c_void,
+        )
+        .into_with(
+            || size / item_size,
+            |s| {
+                if s == Status::BUFFER_TOO_SMALL {
+                    Some(size / item_size)
+                } else {
+                    None
+                }
+            },
+        )
+    }
+
+    /// Changes configuration data made of an array of `T`
+    fn set_array<T>(&self, data_type: Ip6ConfigDataType, data: &[T]) -> Result {
+        let size = mem::size_of_val(data);
+        (self.set_data)(self, data_type, size, data.as_ptr() as *const c_void).into()
+    }
+}
+
+newtype_enum! {
+    /// Type of the configuration data of an `Ip6Config` instance.
+    pub enum Ip6ConfigDataType: u32 => {
+        /// Description of the interface, read-only.
+        INTERFACE_INFO            = 0,
+        /// Alternative interface identifier, used to build addresses.
+        ALT_INTERFACE_ID          = 1,
+        /// Policy of the interface.
+        POLICY                    = 2,
+        /// Number of messages sent to detect duplicate addresses.
+        DUP_ADDR_DETECT_TRANSMITS = 3,
+        /// Addresses set manually.
+        MANUAL_ADDRESS            = 4,
+        /// Gateways.
+        GATEWAY                   = 5,
+        /// DNS servers.
+        DNS_SERVER                = 6,
+    }
+}
+
+newtype_enum! {
+    /// Source of the IPv6 configuration of a network interface.
+    pub enum Ip6ConfigPolicy: u32 => {
+        /// The configuration is set manually.
+        MANUAL    = 0,
+        /// The configuration is obtained by stateless autoconfiguration or
+        /// DHCPv6.
+        AUTOMATIC = 1,
+    }
+}
+
+/// IPv6 address set manually on a network interface.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+#[repr(C)]
+pub struct Ip6ManualAddress {
+    /// Address of the interface.
+    pub address: Ipv6Address,
+    /// True if the address is an anycast address.
+    pub is_anycast: bool,
+    /// Length of the prefix of the address, in bits.
+    pub prefix_length: u8,
+}
+
+/// Address of a network interface.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(C)]
+pub struct Ip6AddressInfo {
+    /// Address of the interface.
+    pub address: Ipv6Address,
+    /// Length of the prefix of the address, in bits.
+    pub prefix_length: u8,
+}
+
+/// Entry of the routing table of a network interface.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(C)]
+pub struct Ip6RouteEntry {
+    /// Gateway to the destination, or zero if it is directly reachable.
+    pub gateway: Ipv6Address,
+    /// Destination prefix.
+    pub destination: Ipv6Address,
+    /// Length of the destination prefix, in bits.
+    pub prefix_length: u8,
+}
+
+/// Description of a network interface, as stored by the firmware.
+#[derive(Copy, Clone)]
+#[repr(C)]
+struct RawInterfaceInfo {
+    name: [u16; 32],
+    if_type: u8,
+    hw_address_size: u32,
+    hw_address: MacAddress,
+    address_info_count: u32,
+    address_info: *const Ip6AddressInfo,
+    route_count: u32,
+    route_table: *const Ip6RouteEntry,
+}
+
+/// Description of a network interface, read with `Ip6Config::interface_info()`.
+#[derive(Copy, Clone)]
+pub struct Ip6InterfaceInfo<'buf> {
+    raw: RawInterfaceInfo,
+    addresses: &'buf [Ip6AddressInfo],
+    route_table: &'buf [Ip6RouteEntry],
+}
+
+impl Ip6InterfaceInfo<'_> {
+    /// Name of the interface, such as `eth0`.
+    pub fn name(&self) -> Option<&CStr16> {
+        let len = self.raw.name.iter().position(|&c| c == 0)?;
+        CStr16::from_u16_with_nul(&self.raw.name[..=len]).ok()
+    }
+
+    /// Type of the interface, as defined by the IANA `ifType` numbers.
+    pub fn if_type(&self) -> u8 {
+        self.raw.if_type
+    }
+
+    /// Hardware address of the interface.
+    pub fn hw_address(&self) -> &[u8] {
+        let size = (self.raw.hw_address_size as usize).min(self.raw.hw_address.0.len());
+        &self.raw.hw_address.0[..size]
+    }
+
+    /// Current addresses of the interface.
+    pub fn addresses(&self) -> &[Ip6AddressInfo] {
+        self.addresses
+    }
+
+    /// Routing table of the interface.
+    pub fn route_table(&self) -> &[Ip6RouteEntry] {
+        self.route_table
+    }
+}
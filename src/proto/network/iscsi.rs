@@ -0,0 +1,73 @@
+//! iSCSI initiator name protocol.
+
+use crate::proto::Protocol;
+use crate::result::Error;
+use crate::{unsafe_guid, CStr8, Completion, Result, Status};
+use core::ffi::c_void;
+
+/// iSCSI initiator name.
+///
+/// This is the worldwide name of the host, used by the iSCSI driver to log
+/// into targets, such as `iqn.2022-01.com.example:host`. It is stored in a
+/// non-volatile variable by the firmware.
+#[repr(C)]
+#[unsafe_guid("59324945-ec44-4c0d-b1cd-9db139df070c")]
+#[derive(Protocol)]
+pub struct IscsiInitiatorName {
+    get: extern "efiapi" fn(
+        this: &IscsiInitiatorName,
+        buffer_size: &mut usize,
+        buffer: *mut c_void,
+    ) -> Status,
+    set: extern "efiapi" fn(
+        this: &IscsiInitiatorName,
+        buffer_size: &mut usize,
+        buffer: *const c_void,
+    ) -> Status,
+}
+
+impl IscsiInitiatorName {
+    /// Reads the initiator name into `buffer`.
+    ///
+    /// If the buffer is too small, the required buffer size will be returned
+    /// as part of the error.
+    ///
+    /// # Errors
+    ///
+    /// - `BufferTooSmall` if the buffer cannot hold the name
+    /// - `NotFound` if no name is set
+    /// - `DeviceError` if the name could not be read
+    pub fn get<'buf>(&self, buffer: &'buf mut [u8]) -> Result<&'buf CStr8, Option<usize>> {
+        let mut size = buffer.len();
+        let status = (self.get)(self, &mut size, buffer.as_mut_ptr() as *mut c_void);
+        match status {
+            Status::BUFFER_TOO_SMALL => Err(Error::new(status, Some(size))),
+            status if status.is_error() => Err(Error::new(status, None)),
+            status => {
+                // The name may be followed by padding up to the returned size
+                let name = &buffer[..size.min(buffer.len())];
+                let len = name
+                    .iter()
+                    .position(|&c| c == 0)
+                    .ok_or_else(|| Error::new(Status::VOLUME_CORRUPTED, None))?;
+                let name = unsafe { CStr8::from_bytes_with_nul_unchecked(&name[..=len]) };
+                Ok(Completion::new(status, name))
+            }
+        }
+    }
+
+    /// Sets the initiator name, which must be in the iSCSI Qualified Name
+    /// (IQN) or IEEE EUI-64 format.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidParameter` if the name is not in a valid format
+    /// - `BufferTooSmall` if the name is too long
+    /// - `WriteProtected` if the name cannot be changed
+    /// - `DeviceError` if the name could not be stored
+    pub fn set(&self, name: &CStr8) -> Result {
+        let name = name.to_bytes_with_nul();
+        let mut size = name.len();
+        (self.set)(self, &mut size, name.as_ptr() as *const c_void).into()
+    }
+}
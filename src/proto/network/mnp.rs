@@ -0,0 +1,508 @@
+//! Managed network protocol.
+//!
+//! This protocol sends and receives raw frames on a network interface, like
+//! the simple network protocol, but lets several users share the interface
+//! with the firmware's network stack. Each user creates its own instance with
+//! `ManagedNetworkServiceBinding`, and configures it to receive the frames of
+//! the protocols it handles.
+
+use super::snp::NetworkMode;
+use super::{IpAddress, MacAddress, ServiceBinding};
+use crate::proto::Protocol;
+use crate::table::boot::BootServices;
+use crate::table::runtime::Time;
+use crate::{unsafe_guid, Completion, Event, Result, ResultExt, Status};
+use core::cell::UnsafeCell;
+use core::ffi::c_void;
+use core::marker::PhantomData;
+use core::ops::Deref;
+use core::{ptr, slice};
+
+/// Service binding creating `ManagedNetwork` instances.
+///
+/// It is installed on the handles of the network interfaces.
+#[repr(transparent)]
+#[unsafe_guid("f36ff770-a7e1-42cf-9ed2-56f0f271f44c")]
+#[derive(Protocol)]
+pub struct ManagedNetworkServiceBinding(ServiceBinding);
+
+impl Deref for ManagedNetworkServiceBinding {
+    type Target = ServiceBinding;
+
+    fn deref(&self) -> &ServiceBinding {
+        &self.0
+    }
+}
+
+/// Shared access to a network interface.
+///
+/// An instance must be configured with `configure()` before it can send and
+/// receive frames. Sending and receiving are asynchronous: the operation is
+/// queued with a `CompletionToken`, whose event is signaled once it completes.
+#[repr(C)]
+#[unsafe_guid("7ab33a91-ace5-4326-b572-e7ee33d39f16")]
+#[derive(Protocol)]
+pub struct ManagedNetwork {
+    get_mode_data: extern "efiapi" fn(
+        this: &ManagedNetwork,
+        config_data: *mut ManagedNetworkConfigData,
+        snp_mode_data: *mut NetworkMode,
+    ) -> Status,
+    configure: extern "efiapi" fn(
+        this: &ManagedNetwork,
+        config_data: *const ManagedNetworkConfigData,
+    ) -> Status,
+    mcast_ip_to_mac: extern "efiapi" fn(
+        this: &ManagedNetwork,
+        ipv6: bool,
+        ip: &IpAddress,
+        mac: &mut MacAddress,
+    ) -> Status,
+    groups: extern "efiapi" fn(this: &ManagedNetwork, join: bool, mac: *const MacAddress) -> Status,
+    transmit:
+        unsafe extern "efiapi" fn(this: &ManagedNetwork, token: *mut CompletionToken) -> Status,
+    receive:
+        unsafe extern "efiapi" fn(this: &ManagedNetwork, token: *mut CompletionToken) -> Status,
+    cancel: unsafe extern "efiapi" fn(this: &ManagedNetwork, token: *mut CompletionToken) -> Status,
+    poll: extern "efiapi" fn(this: &ManagedNetwork) -> Status,
+}
+
+impl ManagedNetwork {
+    /// Returns the current configuration of this instance, or `None` if it
+    /// is not configured.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if the network interface could not be queried
+    pub fn config_data(&self) -> Result<Option<ManagedNetworkConfigData>> {
+        let mut config_data = ManagedNetworkConfigData::default();
+        let status = (self.get_mode_data)(self, &mut config_data, ptr::null_mut());
+        match status {
+            Status::NOT_STARTED => Ok(None.into()),
+            status => status.into_with_val(|| Some(config_data)),
+        }
+    }
+
+    /// Writes the state and capabilities of the underlying network interface
+    /// into `mode`.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if the network interface could not be queried
+    pub fn network_mode(&self, mode: &mut NetworkMode) -> Result {
+        match (self.get_mode_data)(self, ptr::null_mut(), mode) {
+            // The mode of the interface is returned even if this instance is
+            // not configured.
+            Status::NOT_STARTED => Ok(().into()),
+            status => status.into(),
+        }
+    }
+
+    /// Configures this instance, or resets it if `config_data` is `None`.
+    ///
+    /// Resetting cancels all the pending operations of the instance, and
+    /// stops it from receiving frames.
+    ///
+    /// # Errors
+    ///
+    /// - `Unsupported` if a receive filter is not supported by the interface
+    /// - `DeviceError` if the network interface could not be configured
+    pub fn configure(&self, config_data: Option<&ManagedNetworkConfigData>) -> Result {
+        let config_data = config_data.map_or(ptr::null(), |config| config as *const _);
+        (self.configure)(self, config_data).into()
+    }
+
+    /// Converts a multicast IPv4 or IPv6 address to the matching multicast
+    /// MAC address.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidParameter` if the IP address is not a multicast address
+    /// - `NotStarted` if this instance is not configured
+    pub fn mcast_ip_to_mac(&self, ipv6: bool, ip: &IpAddress) -> Result<MacAddress> {
+        let mut mac = MacAddress::default();
+        (self.mcast_ip_to_mac)(self, ipv6, ip, &mut mac).into_with_val(|| mac)
+    }
+
+    /// Starts receiving the frames sent to a multicast MAC address.
+    ///
+    /// # Errors
+    ///
+    /// - `AlreadyStarted` if the address was already joined
+    /// - `NotStarted` if this instance is not configured
+    pub fn join_group(&self, mac: &MacAddress) -> Result {
+        (self.groups)(self, true, mac).into()
+    }
+
+    /// Stops receiving the frames sent to a multicast MAC address, or to any
+    /// of them if `mac` is `None`.
+    ///
+    /// # Errors
+    ///
+    /// - `NotFound` if the address was not joined
+    /// - `NotStarted` if this instance is not configured
+    pub fn leave_group(&self, mac: Option<&MacAddress>) -> Result {
+        let mac = mac.map_or(ptr::null(), |mac| mac as *const MacAddress);
+        (self.groups)(self, false, mac).into()
+    }
+
+    /// Queues the frame described by `token`, created with
+    /// `CompletionToken::transmit()`, for transmission.
+    ///
+    /// # Safety
+    ///
+    /// Neither `token` nor the data it refers to may be moved, freed or
+    /// accessed until the event of `token` has been signaled.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `AccessDenied` if the token is already in a queue
+    /// - `InvalidParameter` if the frame is not valid
+    pub unsafe fn transmit(&self, token: &mut CompletionToken) -> Result {
+        (self.transmit)(self, token).into()
+    }
+
+    /// Queues `token`, created with `CompletionToken::new()`, to be completed
+    /// with the next frame received by this instance.
+    ///
+    /// Once the event of `token` has been signaled, the frame can be read
+    /// with `CompletionToken::receive_data()`, and must be returned to the
+    /// interface by signaling its `ReceiveData::recycle_event()`.
+    ///
+    /// # Safety
+    ///
+    /// `token` may not be moved, freed or accessed until its event has been
+    /// signaled.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `AccessDenied` if the token is already in a queue
+    pub unsafe fn receive(&self, token: &mut CompletionToken) -> Result {
+        (self.receive)(self, token).into()
+    }
+
+    /// Aborts a pending operation, or all of them if `token` is `None`.
+    ///
+    /// The event of the aborted tokens is signaled, and their status is set
+    /// to `Aborted`.
+    ///
+    /// # Errors
+    ///
+    /// - `NotFound` if the token is not in a queue
+    /// - `NotStarted` if this instance is not configured
+    pub fn cancel(&self, token: Option<&mut CompletionToken>) -> Result {
+        let token = token.map_or(ptr::null_mut(), |token| token as *mut CompletionToken);
+        unsafe { (self.cancel)(self, token) }.into()
+    }
+
+    /// Moves frames between the network interface and the queues of this
+    /// instance.
+    ///
+    /// This is normally done periodically by the firmware, but calling it
+    /// manually may increase the throughput.
+    ///
+    /// # Errors
+    ///
+    /// - `NotReady` if no frame was moved
+    /// - `Timeout` if the interface took too long to answer
+    /// - `NotStarted` if this instance is not configured
+    pub fn poll(&self) -> Result {
+        (self.poll)(self).into()
+    }
+
+    /// Sends a frame, and waits for its transmission to complete.
+    ///
+    /// `event` is signaled by the firmware when the transmission completes,
+    /// and must therefore be usable with `BootServices::wait_for_event`, i.e.
+    /// it must not be of type `EventType::NOTIFY_SIGNAL`.
+    ///
+    /// The errors are those of `transmit()`, plus the outcome of the
+    /// transmission.
+    ///
+    /// # Panics
+    ///
+    /// Panics if waiting for `event` fails, since the firmware may still be
+    /// reading from the data at that point.
+    pub fn transmit_blocking(
+        &self,
+        bt: &BootServices,
+        event: Event,
+        data: &TransmitData,
+    ) -> Result {
+        let mut token = CompletionToken::transmit(event, data);
+        let issued = unsafe { self.transmit(&mut token) }?;
+        token
+            .wait(bt)
+            .map(|completion| completion.with_status(issued.status()))
+    }
+
+    /// Waits for a frame to be received, and copies it into `buffer`,
+    /// including its media header.
+    ///
+    /// Returns the size of the frame, which is larger than the buffer if the
+    /// frame was truncated.
+    ///
+    /// See `transmit_blocking()` for the requirements on `event`. The errors
+    /// are those of `receive()`, plus the outcome of the reception.
+    ///
+    /// # Panics
+    ///
+    /// Panics if waiting for `event` fails, since the firmware may still be
+    /// writing to the token at that point.
+    pub fn receive_blocking(
+        &self,
+        bt: &BootServices,
+        event: Event,
+        buffer: &mut [u8],
+    ) -> Result<usize> {
+        let mut token = CompletionToken::new(event);
+        let issued = unsafe { self.receive(&mut token) }?;
+        token.wait(bt)?.log();
+        let data = unsafe { token.receive_data() }.ok_or(Status::ABORTED)?;
+        let frame = data.frame();
+        let len = frame.len().min(buffer.len());
+        buffer[..len].copy_from_slice(&frame[..len]);
+        bt.signal_event(data.recycle_event())?.log();
+        Ok(Completion::new(issued.status(), frame.len()))
+    }
+}
+
+/// Configuration of a `ManagedNetwork` instance.
+#[derive(Debug, Default, Copy, Clone)]
+#[repr(C)]
+pub struct ManagedNetworkConfigData {
+    /// Time after which received frames which were not read are dropped, in
+    /// microseconds, or 0 to keep them.
+    pub received_queue_timeout_value: u32,
+    /// Time after which frames which could not be sent are dropped, in
+    /// microseconds, or 0 to keep them.
+    pub transmit_queue_timeout_value: u32,
+    /// Protocol (EtherType) of the frames received, or 0 for all of them.
+    pub protocol_type_filter: u16,
+    /// Receive the frames sent to the address of the interface.
+    pub enable_unicast_receive: bool,
+    /// Receive the frames sent to the multicast groups joined.
+    pub enable_multicast_receive: bool,
+    /// Receive the frames sent to the broadcast address.
+    pub enable_broadcast_receive: bool,
+    /// Receive all the frames.
+    pub enable_promiscuous_receive: bool,
+    /// Discard the queued frames when the instance is reset.
+    pub flush_queues_on_reset: bool,
+    /// Record the time at which frames are received.
+    pub enable_receive_timestamps: bool,
+    /// Do not let the firmware poll the interface periodically, `poll()` must
+    /// then be called to send and receive frames.
+    pub disable_background_polling: bool,
+}
+
+/// Token tracking an asynchronous operation of a `ManagedNetwork`.
+#[repr(C)]
+pub struct CompletionToken<'data> {
+    event: Event,
+    // Both written by the firmware behind the back of the compiler
+    status: UnsafeCell<Status>,
+    packet: UnsafeCell<*mut c_void>,
+    _data: PhantomData<&'data TransmitData<'data>>,
+}
+
+impl CompletionToken<'static> {
+    /// Creates a token for receiving a frame, which signals `event` upon
+    /// completion.
+    pub fn new(event: Event) -> Self {
+        Self {
+            event,
+            status: UnsafeCell::new(Status::SUCCESS),
+            packet: UnsafeCell::new(ptr::null_mut()),
+            _data: PhantomData,
+        }
+    }
+}
+
+impl<'data> CompletionToken<'data> {
+    /// Creates a token for sending the frame described by `data`, which
+    /// signals `event` upon completion.
+    pub fn transmit(event: Event, data: &'data TransmitData<'data>) -> Self {
+        Self {
+            event,
+            status: UnsafeCell::new(Status::SUCCESS),
+            packet: UnsafeCell::new(data as *const TransmitData as *mut c_void),
+            _data: PhantomData,
+        }
+    }
+
+    /// Event signaled upon completion of the operation.
+    pub fn event(&self) -> Event {
+        self.event
+    }
+
+    /// Outcome of the operation.
+    ///
+    /// This is only meaningful once the event has been signaled.
+    pub fn status(&self) -> Status {
+        unsafe { ptr::read_volatile(self.status.get()) }
+    }
+
+    /// Returns the frame received by a completed receive operation.
+    ///
+    /// # Safety
+    ///
+    /// This token must have been passed to `ManagedNetwork::receive()`, and
+    /// its event signaled. The frame may not be accessed after its recycle
+    /// event has been signaled.
+    pub unsafe fn receive_data(&self) -> Option<&ReceiveData> {
+        let packet = ptr::read_volatile(self.packet.get());
+        (packet as *const ReceiveData).as_ref()
+    }
+
+    /// Waits for the pending operation to complete, and returns its outcome.
+    fn wait(&self, bt: &BootServices) -> Result {
+        bt.wait_for_event(&mut [self.event])
+            .expect_success("Failed to wait for a pending network operation");
+        self.status().into()
+    }
+}
+
+/// Frame received by a `ManagedNetwork` instance.
+///
+/// The frame belongs to the firmware, and must be returned to it by signaling
+/// `recycle_event()` once it has been processed.
+#[repr(C)]
+pub struct ReceiveData {
+    timestamp: Time,
+    recycle_event: Event,
+    packet_length: u32,
+    header_length: u32,
+    address_length: u32,
+    data_length: u32,
+    broadcast_flag: bool,
+    multicast_flag: bool,
+    promiscuous_flag: bool,
+    protocol_type: u16,
+    destination_address: *const u8,
+    source_address: *const u8,
+    media_header: *const u8,
+    packet_data: *const u8,
+}
+
+impl ReceiveData {
+    /// Time at which the frame was received, if the instance records it.
+    pub fn timestamp(&self) -> &Time {
+        &self.timestamp
+    }
+
+    /// Event to signal to return the frame to the firmware.
+    pub fn recycle_event(&self) -> Event {
+        self.recycle_event
+    }
+
+    /// True if the frame was sent to the broadcast address.
+    pub fn is_broadcast(&self) -> bool {
+        self.broadcast_flag
+    }
+
+    /// True if the frame was sent to a multicast address.
+    pub fn is_multicast(&self) -> bool {
+        self.multicast_flag
+    }
+
+    /// True if the frame was only received because of the promiscuous mode.
+    pub fn is_promiscuous(&self) -> bool {
+        self.promiscuous_flag
+    }
+
+    /// Protocol (EtherType) of the frame.
+    pub fn protocol_type(&self) -> u16 {
+        self.protocol_type
+    }
+
+    /// Hardware address the frame was sent to.
+    pub fn destination_address(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.destination_address, self.address_length as usize) }
+    }
+
+    /// Hardware address the frame was sent from.
+    pub fn source_address(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.source_address, self.address_length as usize) }
+    }
+
+    /// Whole frame, including its media header.
+    pub fn frame(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.media_header, self.packet_length as usize) }
+    }
+
+    /// Media header of the frame.
+    pub fn header(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.media_header, self.header_length as usize) }
+    }
+
+    /// Data of the frame, after its media header.
+    pub fn data(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.packet_data, self.data_length as usize) }
+    }
+}
+
+/// Frame to be sent by a `ManagedNetwork` instance.
+#[repr(C)]
+pub struct TransmitData<'data> {
+    destination_address: *const MacAddress,
+    source_address: *const MacAddress,
+    protocol_type: u16,
+    data_length: u32,
+    header_length: u16,
+    fragment_count: u16,
+    fragment_length: u32,
+    fragment_buffer: *const u8,
+    _data: PhantomData<&'data [u8]>,
+}
+
+impl<'data> TransmitData<'data> {
+    /// Describes a frame of `data`, whose media header is built by the
+    /// firmware from the destination address, the protocol and the address
+    /// of the interface.
+    pub fn new(destination: &'data MacAddress, protocol_type: u16, data: &'data [u8]) -> Self {
+        Self {
+            destination_address: destination,
+            source_address: ptr::null(),
+            protocol_type,
+            data_length: data.len() as u32,
+            header_length: 0,
+            fragment_count: 1,
+            fragment_length: data.len() as u32,
+            fragment_buffer: data.as_ptr(),
+            _data: PhantomData,
+        }
+    }
+
+    /// Describes a whole frame, which starts with its own media header of
+    /// `header_length` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the frame is shorter than its header.
+    pub fn with_header(header_length: u16, frame: &'data [u8]) -> Self {
+        let data_length = frame
+            .len()
+            .checked_sub(usize::from(header_length))
+            .expect("Frame is shorter than its media header");
+        Self {
+            destination_address: ptr::null(),
+            source_address: ptr::null(),
+            protocol_type: 0,
+            data_length: data_length as u32,
+            header_length,
+            fragment_count: 1,
+            fragment_length: frame.len() as u32,
+            fragment_buffer: frame.as_ptr(),
+            _data: PhantomData,
+        }
+    }
+
+    /// Sends the frame from `source` rather than the address of the
+    /// interface.
+    pub fn set_source(&mut self, source: &'data MacAddress) {
+        self.source_address = source;
+    }
+}
@@ -0,0 +1,81 @@
+//! Network access protocols.
+//!
+//! These protocols give access to the network interfaces of the machine, from
+//! raw Ethernet frames up to the firmware's own TCP/IP stack.
+
+use crate::{Handle, Result, Status};
+
+pub use crate::data_types::{IpAddress, Ipv4Address, Ipv6Address, MacAddress};
+
+pub mod arp;
+mod crypt;
+pub mod dhcp4;
+pub mod dhcp6;
+#[cfg(feature = "embedded-nal")]
+pub mod embedded_nal;
+pub mod http;
+pub mod ip4_config2;
+pub mod ip6_config;
+pub mod iscsi;
+pub mod mnp;
+pub mod mtftp4;
+#[cfg(feature = "smoltcp")]
+pub mod smoltcp;
+pub mod snp;
+pub mod supplicant;
+mod tcp;
+pub mod tcp4;
+pub mod tcp6;
+pub mod tls;
+mod udp;
+pub mod udp4;
+pub mod udp6;
+pub mod wifi;
+
+/// Functions shared by the service binding protocols of the network stack.
+///
+/// The protocols of the network stack, such as the managed network protocol,
+/// are not installed directly on the handle of a network interface. Instead,
+/// each user creates a child handle through the matching service binding
+/// protocol, and opens the protocol on it. The child is then independent of
+/// the other users of the interface, and should be destroyed once done.
+#[repr(C)]
+pub struct ServiceBinding {
+    create_child: extern "efiapi" fn(this: &ServiceBinding, child: &mut Handle) -> Status,
+    destroy_child: extern "efiapi" fn(this: &ServiceBinding, child: Handle) -> Status,
+}
+
+impl ServiceBinding {
+    /// Creates a child handle, on which the protocol of the service is
+    /// installed.
+    ///
+    /// # Errors
+    ///
+    /// - `OutOfResources` if there are not enough resources to create the child
+    pub fn create_child(&self) -> Result<Handle> {
+        let mut child = unsafe { Handle::uninitialized() };
+        (self.create_child)(self, &mut child).into_with_val(|| child)
+    }
+
+    /// Destroys a child handle created by `create_child()`, and uninstalls the
+    /// protocol of the service from it.
+    ///
+    /// The protocol must not be used after its child was destroyed.
+    ///
+    /// # Errors
+    ///
+    /// - `Unsupported` if the handle was not created by this service
+    /// - `AccessDenied` if the protocol is still in use
+    pub fn destroy_child(&self, child: Handle) -> Result {
+        (self.destroy_child)(self, child).into()
+    }
+}
+
+/// Buffer of a packet split in several fragments, as used by the transport
+/// protocols.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub(crate) struct FragmentData {
+    pub(crate) fragment_length: u32,
+    pub(crate) fragment_buffer: *mut core::ffi::c_void,
+}
@@ -0,0 +1,592 @@
+//! Multicast TFTP over IPv4 protocol.
+//!
+//! Each `Mtftp4` instance downloads or uploads files from one TFTP server,
+//! created with `Mtftp4ServiceBinding`. On top of plain TFTP, it supports
+//! option negotiation (RFC 2347), such as the block size and transfer size
+//! options, and multicast downloads (RFC 2090).
+
+use super::{Ipv4Address, ServiceBinding};
+use crate::proto::Protocol;
+use crate::result::Error;
+use crate::table::boot::BootServices;
+use crate::{unsafe_guid, CStr8, Char8, Event, Result, Status};
+use core::cell::UnsafeCell;
+use core::ffi::c_void;
+use core::fmt::{self, Debug, Formatter};
+use core::marker::PhantomData;
+use core::ops::Deref;
+use core::{ptr, slice};
+
+/// Service binding creating `Mtftp4` instances.
+///
+/// It is installed on the handles of the network interfaces.
+#[repr(transparent)]
+#[unsafe_guid("2fe800be-8f01-4aa6-946b-d71388e1833f")]
+#[derive(Protocol)]
+pub struct Mtftp4ServiceBinding(ServiceBinding);
+
+impl Deref for Mtftp4ServiceBinding {
+    type Target = ServiceBinding;
+
+    fn deref(&self) -> &ServiceBinding {
+        &self.0
+    }
+}
+
+/// TFTP client over IPv4.
+///
+/// An instance must be configured with `configure()` before it can transfer
+/// files.
+#[repr(C)]
+#[unsafe_guid("78247c57-63db-4708-99c2-a8b4a9a61f6b")]
+#[derive(Protocol)]
+pub struct Mtftp4 {
+    get_mode_data: extern "efiapi" fn(this: &Mtftp4, mode_data: &mut Mtftp4ModeData) -> Status,
+    configure: extern "efiapi" fn(this: &Mtftp4, config_data: *const Mtftp4ConfigData) -> Status,
+    get_info: extern "efiapi" fn(
+        this: &Mtftp4,
+        override_data: *const Mtftp4OverrideData,
+        filename: *const Char8,
+        mode_str: *const Char8,
+        option_count: u8,
+        option_list: *const Mtftp4Option,
+        packet_length: &mut u32,
+        packet: &mut *mut u8,
+    ) -> Status,
+    parse_options: extern "efiapi" fn(
+        this: &Mtftp4,
+        packet_len: u32,
+        packet: *const u8,
+        option_count: &mut u32,
+        option_list: &mut *mut Mtftp4Option<'static>,
+    ) -> Status,
+    read_file: unsafe extern "efiapi" fn(this: &Mtftp4, token: *mut Mtftp4Token) -> Status,
+    write_file: unsafe extern "efiapi" fn(this: &Mtftp4, token: *mut Mtftp4Token) -> Status,
+    read_directory: unsafe extern "efiapi" fn(this: &Mtftp4, token: *mut Mtftp4Token) -> Status,
+    poll: extern "efiapi" fn(this: &Mtftp4) -> Status,
+}
+
+impl Mtftp4 {
+    /// Returns the current configuration of this instance, or `None` if it
+    /// is not configured.
+    pub fn config_data(&self) -> Result<Option<Mtftp4ConfigData>> {
+        let mut mode_data = Mtftp4ModeData {
+            config_data: Mtftp4ConfigData::default(),
+            supported_option_count: 0,
+            supported_options: ptr::null_mut(),
+            unsupported_option_count: 0,
+            unsupported_options: ptr::null_mut(),
+        };
+        match (self.get_mode_data)(self, &mut mode_data) {
+            Status::NOT_STARTED => Ok(None.into()),
+            status => status.into_with_val(|| Some(mode_data.config_data)),
+        }
+    }
+
+    /// Configures this instance, or resets it if `config_data` is `None`.
+    ///
+    /// Resetting aborts the transfer in progress.
+    ///
+    /// # Errors
+    ///
+    /// - `NoMapping` if the default address is not yet available
+    /// - `AccessDenied` if a transfer is in progress
+    /// - `InvalidParameter` if the configuration is not valid
+    pub fn configure(&self, config_data: Option<&Mtftp4ConfigData>) -> Result {
+        let config_data = config_data.map_or(ptr::null(), |config| config as *const _);
+        (self.configure)(self, config_data).into()
+    }
+
+    /// Requests `filename` from the server, and returns its first answer,
+    /// without transferring the file.
+    ///
+    /// This is mostly useful to negotiate `options`, such as the `tsize`
+    /// option to learn the size of the file, whose values can then be read
+    /// with `parse_options()`. The transfer uses the `octet` mode, unless
+    /// another one is given.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `TftpError` if the server answered with an error packet, which is
+    ///   then the returned packet
+    /// - `Timeout` if the server did not answer
+    /// - `Unsupported` if an option is not supported by the instance
+    pub fn get_info<'boot>(
+        &self,
+        bt: &'boot BootServices,
+        override_data: Option<&Mtftp4OverrideData>,
+        filename: &CStr8,
+        mode: Option<&CStr8>,
+        options: &[Mtftp4Option<'_>],
+    ) -> Result<Mtftp4Packet<'boot>, Option<Mtftp4Packet<'boot>>> {
+        let override_data = override_data.map_or(ptr::null(), |data| data as *const _);
+        let mode = mode.map_or(ptr::null(), |mode| mode.as_ptr());
+        let mut length = 0;
+        let mut data = ptr::null_mut();
+        let status = (self.get_info)(
+            self,
+            override_data,
+            filename.as_ptr(),
+            mode,
+            options.len() as u8,
+            options.as_ptr(),
+            &mut length,
+            &mut data,
+        );
+        let packet = || {
+            (!data.is_null()).then(|| Mtftp4Packet {
+                bt,
+                data,
+                length: length as usize,
+            })
+        };
+        if status.is_success() {
+            match packet() {
+                Some(packet) => Ok(packet.into()),
+                None => Err(Error::new(Status::PROTOCOL_ERROR, None)),
+            }
+        } else {
+            Err(Error::new(status, packet()))
+        }
+    }
+
+    /// Parses the options of an option acknowledgement packet, such as one
+    /// returned by `get_info()`.
+    ///
+    /// # Errors
+    ///
+    /// - `NotFound` if the packet carries no option
+    /// - `InvalidParameter` if the packet is not an option acknowledgement
+    /// - `VolumeCorrupted` if the options are malformed
+    pub fn parse_options<'packet>(
+        &self,
+        bt: &'packet BootServices,
+        packet: &'packet [u8],
+    ) -> Result<Mtftp4Options<'packet>> {
+        let mut count = 0;
+        let mut options: *mut Mtftp4Option<'static> = ptr::null_mut();
+        (self.parse_options)(
+            self,
+            packet.len() as u32,
+            packet.as_ptr(),
+            &mut count,
+            &mut options,
+        )
+        .into_with_val(|| Mtftp4Options {
+            bt,
+            options: options.cast(),
+            count: count as usize,
+        })
+    }
+
+    /// Starts the download described by `token`, created with
+    /// `Mtftp4Token::read()`.
+    ///
+    /// If the token has no event, the download is complete when this
+    /// returns. Otherwise, its outcome is in the status of the token once its
+    /// event has been signaled.
+    ///
+    /// # Safety
+    ///
+    /// If the token has an event, neither `token` nor the data it refers to
+    /// may be moved, freed or accessed until the event has been signaled.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `AlreadyStarted` if a transfer is in progress
+    /// - `NoMapping` if the default address is not yet available
+    /// - `Unsupported` if an option is not supported by the instance
+    /// - `BufferTooSmall` if the file does not fit in the buffer, in which
+    ///   case the buffer size of the token may hold the size of the file
+    /// - `TftpError` if the server answered with an error packet
+    pub unsafe fn read_file(&self, token: &mut Mtftp4Token) -> Result {
+        (self.read_file)(self, token).into()
+    }
+
+    /// Starts the upload described by `token`, created with
+    /// `Mtftp4Token::write()`.
+    ///
+    /// See `read_file()` for the meaning of the event of the token.
+    ///
+    /// # Safety
+    ///
+    /// See `read_file()`.
+    ///
+    /// # Errors
+    ///
+    /// The errors are those of `read_file()`, except `BufferTooSmall`.
+    pub unsafe fn write_file(&self, token: &mut Mtftp4Token) -> Result {
+        (self.write_file)(self, token).into()
+    }
+
+    /// Starts the download of the listing of the directory described by
+    /// `token`, created with `Mtftp4Token::read()`.
+    ///
+    /// This is an extension of TFTP, which most servers do not support. See
+    /// `read_file()` for the meaning of the event of the token.
+    ///
+    /// # Safety
+    ///
+    /// See `read_file()`.
+    ///
+    /// # Errors
+    ///
+    /// The errors are those of `read_file()`.
+    pub unsafe fn read_directory(&self, token: &mut Mtftp4Token) -> Result {
+        (self.read_directory)(self, token).into()
+    }
+
+    /// Moves packets between the network interface and this instance.
+    ///
+    /// This is normally done periodically by the firmware, but calling it
+    /// manually may increase the throughput.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `NotReady` if no packet was moved
+    /// - `Timeout` if the interface took too long to answer
+    pub fn poll(&self) -> Result {
+        (self.poll)(self).into()
+    }
+
+    /// Downloads `filename` into `buffer`, negotiating `options` with the
+    /// server, and returns the size of the file.
+    ///
+    /// If the buffer is too small, and the size of the file is known, for
+    /// instance because the `tsize` option was negotiated, this size will be
+    /// returned as part of the error. The errors are those of `read_file()`.
+    pub fn download(
+        &self,
+        filename: &CStr8,
+        options: &[Mtftp4Option<'_>],
+        buffer: &mut [u8],
+    ) -> Result<usize, Option<usize>> {
+        let buffer_len = buffer.len();
+        let mut token = Mtftp4Token::read(Event::null(), filename, options, buffer);
+        let status = unsafe { (self.read_file)(self, &mut token) };
+        let size = token.buffer_size() as usize;
+        status.into_with(
+            || size,
+            |s| {
+                if s == Status::BUFFER_TOO_SMALL && size > buffer_len {
+                    Some(size)
+                } else {
+                    None
+                }
+            },
+        )
+    }
+
+    /// Uploads `data` as `filename`, negotiating `options` with the server.
+    ///
+    /// The errors are those of `write_file()`.
+    pub fn upload(&self, filename: &CStr8, options: &[Mtftp4Option<'_>], data: &[u8]) -> Result {
+        let mut token = Mtftp4Token::write(Event::null(), filename, options, data);
+        unsafe { self.write_file(&mut token) }
+    }
+}
+
+/// State of an `Mtftp4` instance, as returned by the firmware
+#[repr(C)]
+struct Mtftp4ModeData {
+    config_data: Mtftp4ConfigData,
+    supported_option_count: u8,
+    supported_options: *mut *mut Char8,
+    unsupported_option_count: u8,
+    unsupported_options: *mut *mut Char8,
+}
+
+/// Configuration of an `Mtftp4` instance.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct Mtftp4ConfigData {
+    /// Use the default address of the interface, rather than `station_ip`,
+    /// `subnet_mask` and `gateway_ip`.
+    pub use_default_setting: bool,
+    /// Local address.
+    pub station_ip: Ipv4Address,
+    /// Subnet mask of the local address.
+    pub subnet_mask: Ipv4Address,
+    /// Local port, or 0 to pick one.
+    pub local_port: u16,
+    /// Gateway to the server, or zero if it is on the local subnet.
+    pub gateway_ip: Ipv4Address,
+    /// Address of the server.
+    pub server_ip: Ipv4Address,
+    /// Port of the server the requests are sent to.
+    pub initial_server_port: u16,
+    /// Number of times a request is sent before giving up.
+    pub try_count: u16,
+    /// Time to wait for an answer to a request, in seconds.
+    pub timeout_value: u16,
+}
+
+impl Mtftp4ConfigData {
+    /// Configuration for the TFTP server at `server_ip`, on the standard TFTP
+    /// port, using the default address of the interface.
+    pub fn new(server_ip: Ipv4Address) -> Self {
+        Self {
+            server_ip,
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for Mtftp4ConfigData {
+    fn default() -> Self {
+        Self {
+            use_default_setting: true,
+            station_ip: Ipv4Address::UNSPECIFIED,
+            subnet_mask: Ipv4Address::UNSPECIFIED,
+            local_port: 0,
+            gateway_ip: Ipv4Address::UNSPECIFIED,
+            server_ip: Ipv4Address::UNSPECIFIED,
+            initial_server_port: 69,
+            try_count: 4,
+            timeout_value: 3,
+        }
+    }
+}
+
+/// Settings of a single transfer, overriding those of the instance.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct Mtftp4OverrideData {
+    /// Gateway to the server, or zero if it is on the local subnet.
+    pub gateway_ip: Ipv4Address,
+    /// Address of the server.
+    pub server_ip: Ipv4Address,
+    /// Port of the server the request is sent to.
+    pub server_port: u16,
+    /// Number of times the request is sent before giving up.
+    pub try_count: u16,
+    /// Time to wait for an answer to the request, in seconds.
+    pub timeout_value: u16,
+}
+
+/// Option negotiated with a TFTP server, such as `blksize` or `tsize`.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct Mtftp4Option<'a> {
+    option_str: *const Char8,
+    value_str: *const Char8,
+    _strings: PhantomData<&'a CStr8>,
+}
+
+impl<'a> Mtftp4Option<'a> {
+    /// Creates an option, such as `blksize` with the value `1468`.
+    pub fn new(option: &'a CStr8, value: &'a CStr8) -> Self {
+        Self {
+            option_str: option.as_ptr(),
+            value_str: value.as_ptr(),
+            _strings: PhantomData,
+        }
+    }
+
+    /// Name of the option.
+    pub fn option(&self) -> &CStr8 {
+        unsafe { CStr8::from_ptr(self.option_str) }
+    }
+
+    /// Value of the option.
+    pub fn value(&self) -> &CStr8 {
+        unsafe { CStr8::from_ptr(self.value_str) }
+    }
+}
+
+/// Token describing a transfer of an `Mtftp4` instance.
+#[repr(C)]
+pub struct Mtftp4Token<'data> {
+    // Written by the firmware behind the back of the compiler, like the
+    // buffer size
+    status: UnsafeCell<Status>,
+    event: Event,
+    override_data: *const Mtftp4OverrideData,
+    filename: *const Char8,
+    mode_str: *const Char8,
+    option_count: u32,
+    option_list: *const Mtftp4Option<'data>,
+    buffer_size: UnsafeCell<u64>,
+    buffer: *mut c_void,
+    context: *mut c_void,
+    check_packet: *const c_void,
+    timeout_callback: *const c_void,
+    packet_needed: *const c_void,
+    _data: PhantomData<&'data mut [u8]>,
+}
+
+impl<'data> Mtftp4Token<'data> {
+    /// Creates a token for downloading `filename` into `buffer`, which
+    /// signals `event` upon completion.
+    ///
+    /// `event` may be `Event::null()`, to make the transfer blocking.
+    pub fn read(
+        event: Event,
+        filename: &'data CStr8,
+        options: &'data [Mtftp4Option<'data>],
+        buffer: &'data mut [u8],
+    ) -> Self {
+        Self::new(event, filename, options, buffer.as_mut_ptr(), buffer.len())
+    }
+
+    /// Creates a token for uploading `data` as `filename`, which signals
+    /// `event` upon completion.
+    ///
+    /// `event` may be `Event::null()`, to make the transfer blocking.
+    pub fn write(
+        event: Event,
+        filename: &'data CStr8,
+        options: &'data [Mtftp4Option<'data>],
+        data: &'data [u8],
+    ) -> Self {
+        Self::new(
+            event,
+            filename,
+            options,
+            data.as_ptr() as *mut u8,
+            data.len(),
+        )
+    }
+
+    /// Transfers the file in the given mode, such as `netascii`, rather than
+    /// `octet`.
+    pub fn set_mode(&mut self, mode: &'data CStr8) {
+        self.mode_str = mode.as_ptr();
+    }
+
+    /// Uses `override_data` for this transfer, rather than the configuration
+    /// of the instance.
+    pub fn set_override(&mut self, override_data: &'data Mtftp4OverrideData) {
+        self.override_data = override_data;
+    }
+
+    /// Event signaled upon completion of the transfer.
+    pub fn event(&self) -> Event {
+        self.event
+    }
+
+    /// Outcome of the transfer.
+    ///
+    /// This is only meaningful once the transfer has completed.
+    pub fn status(&self) -> Status {
+        unsafe { ptr::read_volatile(self.status.get()) }
+    }
+
+    /// Size of the file once the transfer has completed, or if the buffer of
+    /// a download was too small, the size of the file if it is known.
+    pub fn buffer_size(&self) -> u64 {
+        unsafe { ptr::read_volatile(self.buffer_size.get()) }
+    }
+
+    fn new(
+        event: Event,
+        filename: &'data CStr8,
+        options: &'data [Mtftp4Option<'data>],
+        buffer: *mut u8,
+        buffer_size: usize,
+    ) -> Self {
+        Self {
+            status: UnsafeCell::new(Status::SUCCESS),
+            event,
+            override_data: ptr::null(),
+            filename: filename.as_ptr(),
+            mode_str: ptr::null(),
+            option_count: options.len() as u32,
+            option_list: options.as_ptr(),
+            buffer_size: UnsafeCell::new(buffer_size as u64),
+            buffer: buffer as *mut c_void,
+            context: ptr::null_mut(),
+            check_packet: ptr::null(),
+            timeout_callback: ptr::null(),
+            packet_needed: ptr::null(),
+            _data: PhantomData,
+        }
+    }
+}
+
+/// TFTP packet received by `Mtftp4::get_info()`.
+///
+/// The packet is freed when this is dropped.
+pub struct Mtftp4Packet<'boot> {
+    bt: &'boot BootServices,
+    data: *mut u8,
+    length: usize,
+}
+
+impl Mtftp4Packet<'_> {
+    /// Raw bytes of the packet, starting with its opcode.
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.data, self.length) }
+    }
+
+    /// Opcode of the packet, such as 6 for an option acknowledgement or 5 for
+    /// an error.
+    pub fn opcode(&self) -> u16 {
+        match self.as_bytes() {
+            [high, low, ..] => u16::from_be_bytes([*high, *low]),
+            _ => 0,
+        }
+    }
+}
+
+impl Debug for Mtftp4Packet<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Mtftp4Packet")
+            .field("opcode", &self.opcode())
+            .field("length", &self.length)
+            .finish()
+    }
+}
+
+impl Drop for Mtftp4Packet<'_> {
+    fn drop(&mut self) {
+        // Nothing more can be done if freeing fails
+        let _ = self.bt.free_pool(self.data);
+    }
+}
+
+/// Options parsed by `Mtftp4::parse_options()`.
+///
+/// The options point into the parsed packet, and their list is freed when
+/// this is dropped.
+pub struct Mtftp4Options<'packet> {
+    bt: &'packet BootServices,
+    options: *mut Mtftp4Option<'packet>,
+    count: usize,
+}
+
+impl<'packet> Mtftp4Options<'packet> {
+    /// The parsed options.
+    pub fn options(&self) -> &[Mtftp4Option<'packet>] {
+        if self.options.is_null() {
+            &[]
+        } else {
+            unsafe { slice::from_raw_parts(self.options, self.count) }
+        }
+    }
+
+    /// Value of the option named `name`, ignoring case.
+    pub fn get(&self, name: &str) -> Option<&CStr8> {
+        self.options()
+            .iter()
+            .find(|option| {
+                option
+                    .option()
+                    .to_bytes()
+                    .eq_ignore_ascii_case(name.as_bytes())
+            })
+            .map(|option| option.value())
+    }
+}
+
+impl Drop for Mtftp4Options<'_> {
+    fn drop(&mut self) {
+        if !self.options.is_null() {
+            // Nothing more can be done if freeing fails
+            let _ = self.bt.free_pool(self.options as *mut u8);
+        }
+    }
+}
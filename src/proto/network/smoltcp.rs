@@ -0,0 +1,234 @@
+//! Ethernet device for the [smoltcp] network stack.
+//!
+//! `SnpDevice` implements `smoltcp::phy::Device` on top of the simple network
+//! protocol, so that a whole TCP/IP stack can be run independently of the
+//! firmware's, e.g. when it is missing or broken. Like any user of the simple
+//! network protocol, it takes the interface away from the firmware's stack.
+//!
+//! The frames are stored in a caller-provided `SnpBuffers`, since those which
+//! are queued for transmission are still read by the firmware after the
+//! transmit token has been consumed, and must therefore not move.
+//!
+//! [smoltcp]: https://docs.rs/smoltcp
+
+use super::snp::{NetworkState, ReceiveFlags, SimpleNetwork};
+use crate::table::boot::BootServices;
+use crate::{Result, Status};
+use ::smoltcp::phy::{self, DeviceCapabilities, Medium};
+use ::smoltcp::time::Instant;
+
+/// Size of an Ethernet frame, including its header but not its checksum.
+pub const FRAME_SIZE: usize = 1514;
+
+/// Number of frames which can be queued for transmission at once.
+pub const TX_QUEUE_SIZE: usize = 8;
+
+/// Frame buffers of an `SnpDevice`.
+pub struct SnpBuffers {
+    rx: [u8; FRAME_SIZE],
+    tx: [[u8; FRAME_SIZE]; TX_QUEUE_SIZE],
+    tx_pending: [bool; TX_QUEUE_SIZE],
+}
+
+impl SnpBuffers {
+    /// Creates empty buffers.
+    pub const fn new() -> Self {
+        Self {
+            rx: [0; FRAME_SIZE],
+            tx: [[0; FRAME_SIZE]; TX_QUEUE_SIZE],
+            tx_pending: [false; TX_QUEUE_SIZE],
+        }
+    }
+}
+
+impl Default for SnpBuffers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Ethernet device sending and receiving frames through a `SimpleNetwork`
+/// interface.
+pub struct SnpDevice<'a> {
+    bt: &'a BootServices,
+    snp: &'a SimpleNetwork,
+    buffers: &'a mut SnpBuffers,
+}
+
+impl<'a> SnpDevice<'a> {
+    /// Creates a device for the interface `snp`, starting and initializing it
+    /// if needed, and enabling the reception of the frames sent to its
+    /// address and to the broadcast address.
+    ///
+    /// # Errors
+    ///
+    /// - `Unsupported` if the interface is not an Ethernet interface
+    /// - the errors of `SimpleNetwork::start()`, `initialize()` and
+    ///   `receive_filters()`
+    pub fn new(
+        bt: &'a BootServices,
+        snp: &'a SimpleNetwork,
+        buffers: &'a mut SnpBuffers,
+    ) -> Result<Self> {
+        if snp.mode().state == NetworkState::STOPPED {
+            snp.start()?.log();
+        }
+        if snp.mode().state == NetworkState::STARTED {
+            snp.initialize(0, 0)?.log();
+        }
+        let mode = snp.mode();
+        if mode.hw_address_size != 6 || mode.media_header_size != 14 {
+            return Err(Status::UNSUPPORTED.into());
+        }
+        let filters = ReceiveFlags::UNICAST | ReceiveFlags::BROADCAST;
+        snp.receive_filters(filters, ReceiveFlags::empty(), false, None)?
+            .log();
+        buffers.tx_pending = [false; TX_QUEUE_SIZE];
+        Ok(Self { bt, snp, buffers }.into())
+    }
+
+    /// MAC address of the interface, for the configuration of the stack.
+    pub fn mac_address(&self) -> [u8; 6] {
+        self.snp.mode().current_address.ethernet()
+    }
+
+    /// Marks the transmit buffers returned by the firmware as free again
+    fn recycle_tx_buffers(&mut self) {
+        while let Ok(completion) = self.snp.get_recycled_transmit_buffer() {
+            let recycled = match completion.log() {
+                Some(recycled) => recycled,
+                None => break,
+            };
+            let buffers = &mut *self.buffers;
+            for (buffer, pending) in buffers.tx.iter().zip(buffers.tx_pending.iter_mut()) {
+                if core::ptr::eq(buffer.as_ptr(), recycled) {
+                    *pending = false;
+                }
+            }
+        }
+    }
+
+    /// Token for the next free transmit buffer, if any
+    fn tx_token(&mut self) -> Option<SnpTxToken<'_>> {
+        self.recycle_tx_buffers();
+        let index = self
+            .buffers
+            .tx_pending
+            .iter()
+            .position(|pending| !pending)?;
+        Some(SnpTxToken {
+            snp: self.snp,
+            buffer: &mut self.buffers.tx[index],
+            pending: &mut self.buffers.tx_pending[index],
+        })
+    }
+}
+
+impl phy::Device for SnpDevice<'_> {
+    type RxToken<'b>
+        = SnpRxToken<'b>
+    where
+        Self: 'b;
+    type TxToken<'b>
+        = SnpTxToken<'b>
+    where
+        Self: 'b;
+
+    fn receive(&mut self, _: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        // The event is signaled by the firmware once a frame is available
+        let event = self.snp.wait_for_packet_event();
+        if !self.bt.check_event(event).ok()?.log() {
+            return None;
+        }
+        self.recycle_tx_buffers();
+        let index = self
+            .buffers
+            .tx_pending
+            .iter()
+            .position(|pending| !pending)?;
+
+        let SnpBuffers {
+            rx, tx, tx_pending, ..
+        } = &mut *self.buffers;
+        let len = self.snp.receive(rx, None, None, None, None).ok()?.log();
+        let rx_token = SnpRxToken { frame: &rx[..len] };
+        let tx_token = SnpTxToken {
+            snp: self.snp,
+            buffer: &mut tx[index],
+            pending: &mut tx_pending[index],
+        };
+        Some((rx_token, tx_token))
+    }
+
+    fn transmit(&mut self, _: Instant) -> Option<Self::TxToken<'_>> {
+        self.tx_token()
+    }
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        let mode = self.snp.mode();
+        let mut capabilities = DeviceCapabilities::default();
+        capabilities.medium = Medium::Ethernet;
+        capabilities.max_transmission_unit =
+            (mode.media_header_size + mode.max_packet_size).min(FRAME_SIZE as u32) as usize;
+        capabilities.max_burst_size = Some(TX_QUEUE_SIZE);
+        capabilities
+    }
+}
+
+impl Drop for SnpDevice<'_> {
+    fn drop(&mut self) {
+        // The firmware may still be reading from the transmit buffers, which
+        // must stay in place until they are all returned. Give up after a
+        // second, in case the interface is stuck.
+        for _ in 0..1000 {
+            self.recycle_tx_buffers();
+            if !self.buffers.tx_pending.contains(&true) {
+                return;
+            }
+            self.bt.stall(1000);
+        }
+    }
+}
+
+/// Frame received by an `SnpDevice`.
+pub struct SnpRxToken<'a> {
+    frame: &'a [u8],
+}
+
+impl phy::RxToken for SnpRxToken<'_> {
+    fn consume<R, F>(self, f: F) -> R
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        f(self.frame)
+    }
+}
+
+/// Free transmit buffer of an `SnpDevice`.
+pub struct SnpTxToken<'a> {
+    snp: &'a SimpleNetwork,
+    buffer: &'a mut [u8; FRAME_SIZE],
+    pending: &'a mut bool,
+}
+
+impl phy::TxToken for SnpTxToken<'_> {
+    /// Builds a frame of `len` bytes with `f`, and queues it for transmission.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the frame is larger than `FRAME_SIZE`, which is larger than
+    /// the maximum transmission unit of the device.
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let frame = &mut self.buffer[..len];
+        let result = f(frame);
+        // The frame is dropped if it cannot be queued, like it would be by a
+        // saturated link, and the upper layers will retransmit it
+        if self.snp.transmit(0, frame, None, None, None).is_ok() {
+            *self.pending = true;
+        }
+        result
+    }
+}
@@ -0,0 +1,484 @@
+//! Simple network protocol.
+//!
+//! This protocol gives raw access to a network interface, which sends and
+//! receives whole frames, including their media (Ethernet) header. Using it
+//! directly takes the interface away from the firmware's network stack, the
+//! managed network protocol should be preferred to share it.
+
+use super::{IpAddress, MacAddress};
+use crate::proto::Protocol;
+use crate::{unsafe_guid, Event, Result, Status};
+use bitflags::bitflags;
+use core::ffi::c_void;
+use core::{mem, ptr};
+
+/// Raw access to a network interface.
+#[repr(C)]
+#[unsafe_guid("a19832b9-ac25-11d3-9a2d-0090273fc14d")]
+#[derive(Protocol)]
+pub struct SimpleNetwork {
+    revision: u64,
+    start: extern "efiapi" fn(this: &SimpleNetwork) -> Status,
+    stop: extern "efiapi" fn(this: &SimpleNetwork) -> Status,
+    initialize: extern "efiapi" fn(
+        this: &SimpleNetwork,
+        extra_rx_buffer_size: usize,
+        extra_tx_buffer_size: usize,
+    ) -> Status,
+    reset: extern "efiapi" fn(this: &SimpleNetwork, extended_verification: bool) -> Status,
+    shutdown: extern "efiapi" fn(this: &SimpleNetwork) -> Status,
+    receive_filters: extern "efiapi" fn(
+        this: &SimpleNetwork,
+        enable: u32,
+        disable: u32,
+        reset_mcast_filter: bool,
+        mcast_filter_count: usize,
+        mcast_filter: *const MacAddress,
+    ) -> Status,
+    station_address:
+        extern "efiapi" fn(this: &SimpleNetwork, reset: bool, new: *const MacAddress) -> Status,
+    statistics: extern "efiapi" fn(
+        this: &SimpleNetwork,
+        reset: bool,
+        statistics_size: *mut usize,
+        statistics_table: *mut NetworkStatistics,
+    ) -> Status,
+    mcast_ip_to_mac: extern "efiapi" fn(
+        this: &SimpleNetwork,
+        ipv6: bool,
+        ip: &IpAddress,
+        mac: &mut MacAddress,
+    ) -> Status,
+    nv_data: extern "efiapi" fn(
+        this: &SimpleNetwork,
+        read_write: bool,
+        offset: usize,
+        buffer_size: usize,
+        buffer: *mut c_void,
+    ) -> Status,
+    get_status: extern "efiapi" fn(
+        this: &SimpleNetwork,
+        interrupt_status: *mut InterruptStatus,
+        tx_buf: *mut *mut c_void,
+    ) -> Status,
+    transmit: extern "efiapi" fn(
+        this: &SimpleNetwork,
+        header_size: usize,
+        buffer_size: usize,
+        buffer: *const c_void,
+        src_addr: *const MacAddress,
+        dest_addr: *const MacAddress,
+        protocol: *const u16,
+    ) -> Status,
+    receive: extern "efiapi" fn(
+        this: &SimpleNetwork,
+        header_size: *mut usize,
+        buffer_size: &mut usize,
+        buffer: *mut c_void,
+        src_addr: *mut MacAddress,
+        dest_addr: *mut MacAddress,
+        protocol: *mut u16,
+    ) -> Status,
+    wait_for_packet: Event,
+    mode: *const NetworkMode,
+}
+
+impl SimpleNetwork {
+    /// Revision of the protocol implemented by the interface.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Returns the current state and capabilities of the interface.
+    pub fn mode(&self) -> &NetworkMode {
+        unsafe { &*self.mode }
+    }
+
+    /// Changes the state of the interface from stopped to started.
+    ///
+    /// # Errors
+    ///
+    /// - `AlreadyStarted` if the interface was already started
+    /// - `DeviceError` if the command could not be sent to the interface
+    pub fn start(&self) -> Result {
+        (self.start)(self).into()
+    }
+
+    /// Changes the state of the interface from started to stopped.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if the interface was not started
+    /// - `DeviceError` if the command could not be sent to the interface
+    pub fn stop(&self) -> Result {
+        (self.stop)(self).into()
+    }
+
+    /// Resets the interface and allocates its transmit and receive buffers,
+    /// with the requested extra space, before it can send and receive frames.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if the interface was not started
+    /// - `DeviceError` if the command could not be sent to the interface
+    pub fn initialize(&self, extra_rx_buffer_size: usize, extra_tx_buffer_size: usize) -> Result {
+        (self.initialize)(self, extra_rx_buffer_size, extra_tx_buffer_size).into()
+    }
+
+    /// Resets the interface, and reinitializes it with the parameters of the
+    /// previous call to `initialize()`.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if the interface was not started
+    /// - `DeviceError` if the command could not be sent to the interface
+    pub fn reset(&self, extended_verification: bool) -> Result {
+        (self.reset)(self, extended_verification).into()
+    }
+
+    /// Resets the interface, and frees its transmit and receive buffers.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if the interface was not started
+    /// - `DeviceError` if the command could not be sent to the interface
+    pub fn shutdown(&self) -> Result {
+        (self.shutdown)(self).into()
+    }
+
+    /// Enables and disables receive filters, and replaces the multicast
+    /// addresses received if `mcast_filter` is specified.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if the interface was not started
+    /// - `InvalidParameter` if a filter or too many multicast addresses are
+    ///   not supported
+    /// - `DeviceError` if the command could not be sent to the interface
+    pub fn receive_filters(
+        &self,
+        enable: ReceiveFlags,
+        disable: ReceiveFlags,
+        reset_mcast_filter: bool,
+        mcast_filter: Option<&[MacAddress]>,
+    ) -> Result {
+        let (count, filter) =
+            mcast_filter.map_or((0, ptr::null()), |filter| (filter.len(), filter.as_ptr()));
+        (self.receive_filters)(
+            self,
+            enable.bits(),
+            disable.bits(),
+            reset_mcast_filter,
+            count,
+            filter,
+        )
+        .into()
+    }
+
+    /// Changes the MAC address of the interface, or resets it to the
+    /// permanent address if `new` is `None`.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if the interface was not started
+    /// - `Unsupported` if the address cannot be changed
+    pub fn station_address(&self, new: Option<&MacAddress>) -> Result {
+        let new_ptr = new.map_or(ptr::null(), |new| new as *const MacAddress);
+        (self.station_address)(self, new.is_none(), new_ptr).into()
+    }
+
+    /// Returns the traffic statistics of the interface.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if the interface was not started
+    /// - `Unsupported` if the interface does not collect statistics
+    pub fn statistics(&self) -> Result<NetworkStatistics> {
+        let mut statistics = NetworkStatistics::default();
+        let mut size = mem::size_of::<NetworkStatistics>();
+        (self.statistics)(self, false, &mut size, &mut statistics).into_with_val(|| statistics)
+    }
+
+    /// Resets the traffic statistics of the interface.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if the interface was not started
+    /// - `Unsupported` if the interface does not collect statistics
+    pub fn reset_statistics(&self) -> Result {
+        (self.statistics)(self, true, ptr::null_mut(), ptr::null_mut()).into()
+    }
+
+    /// Converts a multicast IPv4 or IPv6 address to the matching multicast
+    /// MAC address.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidParameter` if the IP address is not a multicast address
+    /// - `Unsupported` if the interface does not support the conversion
+    pub fn mcast_ip_to_mac(&self, ipv6: bool, ip: &IpAddress) -> Result<MacAddress> {
+        let mut mac = MacAddress::default();
+        (self.mcast_ip_to_mac)(self, ipv6, ip, &mut mac).into_with_val(|| mac)
+    }
+
+    /// Reads the non-volatile storage of the interface, at an offset which
+    /// must be a multiple of `NetworkMode::nv_ram_access_size`.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidParameter` if the offset or size are not properly aligned
+    /// - `Unsupported` if the interface has no non-volatile storage
+    pub fn read_nv_data(&self, offset: usize, buffer: &mut [u8]) -> Result {
+        (self.nv_data)(
+            self,
+            true,
+            offset,
+            buffer.len(),
+            buffer.as_mut_ptr() as *mut c_void,
+        )
+        .into()
+    }
+
+    /// Writes the non-volatile storage of the interface, at an offset which
+    /// must be a multiple of `NetworkMode::nv_ram_access_size`.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidParameter` if the offset or size are not properly aligned
+    /// - `Unsupported` if the interface has no non-volatile storage
+    pub fn write_nv_data(&self, offset: usize, buffer: &[u8]) -> Result {
+        (self.nv_data)(
+            self,
+            false,
+            offset,
+            buffer.len(),
+            buffer.as_ptr() as *mut c_void,
+        )
+        .into()
+    }
+
+    /// Reads and clears the interrupt status of the interface.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if the interface was not started
+    /// - `DeviceError` if the command could not be sent to the interface
+    pub fn get_interrupt_status(&self) -> Result<InterruptStatus> {
+        let mut interrupt_status = InterruptStatus::empty();
+        (self.get_status)(self, &mut interrupt_status, ptr::null_mut())
+            .into_with_val(|| interrupt_status)
+    }
+
+    /// Returns a buffer passed to `transmit()` whose frame was sent, and which
+    /// can be reused, if any.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if the interface was not started
+    /// - `DeviceError` if the command could not be sent to the interface
+    pub fn get_recycled_transmit_buffer(&self) -> Result<Option<*mut u8>> {
+        let mut tx_buf = ptr::null_mut();
+        (self.get_status)(self, ptr::null_mut(), &mut tx_buf).into_with_val(|| {
+            if tx_buf.is_null() {
+                None
+            } else {
+                Some(tx_buf as *mut u8)
+            }
+        })
+    }
+
+    /// Queues a frame for transmission.
+    ///
+    /// If `header_size` is 0, `buffer` must contain the whole frame, including
+    /// its media header. Otherwise, the interface fills in the header of this
+    /// size at the start of the buffer, using `dest_addr`, `protocol` and
+    /// `src_addr` or the current address of the interface.
+    ///
+    /// The buffer must not be modified until it is returned by
+    /// `get_recycled_transmit_buffer()`.
+    ///
+    /// # Errors
+    ///
+    /// - `NotReady` if the transmit queue is full
+    /// - `BufferTooSmall` if the buffer is too small for the header
+    /// - `InvalidParameter` if the header size does not match the media
+    pub fn transmit(
+        &self,
+        header_size: usize,
+        buffer: &[u8],
+        src_addr: Option<&MacAddress>,
+        dest_addr: Option<&MacAddress>,
+        protocol: Option<u16>,
+    ) -> Result {
+        let protocol_ptr = protocol.as_ref().map_or(ptr::null(), |p| p as *const u16);
+        (self.transmit)(
+            self,
+            header_size,
+            buffer.len(),
+            buffer.as_ptr() as *const c_void,
+            src_addr.map_or(ptr::null(), |addr| addr as *const MacAddress),
+            dest_addr.map_or(ptr::null(), |addr| addr as *const MacAddress),
+            protocol_ptr,
+        )
+        .into()
+    }
+
+    /// Receives a frame into `buffer`, returning its size.
+    ///
+    /// The frame includes its media header. If they are specified, the size
+    /// of the header, the source and destination addresses and the protocol
+    /// of the frame are also written.
+    ///
+    /// # Errors
+    ///
+    /// - `NotReady` if no frame was received
+    /// - `BufferTooSmall` if the buffer is too small for the frame
+    /// - `NotStarted` if the interface was not started
+    pub fn receive(
+        &self,
+        buffer: &mut [u8],
+        header_size: Option<&mut usize>,
+        src_addr: Option<&mut MacAddress>,
+        dest_addr: Option<&mut MacAddress>,
+        protocol: Option<&mut u16>,
+    ) -> Result<usize> {
+        let mut buffer_size = buffer.len();
+        (self.receive)(
+            self,
+            header_size.map_or(ptr::null_mut(), |size| size as *mut usize),
+            &mut buffer_size,
+            buffer.as_mut_ptr() as *mut c_void,
+            src_addr.map_or(ptr::null_mut(), |addr| addr as *mut MacAddress),
+            dest_addr.map_or(ptr::null_mut(), |addr| addr as *mut MacAddress),
+            protocol.map_or(ptr::null_mut(), |p| p as *mut u16),
+        )
+        .into_with_val(|| buffer_size)
+    }
+
+    /// Event to be used with `BootServices::wait_for_event()` in order to wait
+    /// for a frame to be received.
+    pub fn wait_for_packet_event(&self) -> Event {
+        self.wait_for_packet
+    }
+}
+
+bitflags! {
+    /// Kinds of frames received by an interface.
+    pub struct ReceiveFlags: u32 {
+        /// Frames sent to the address of the interface.
+        const UNICAST = 0x01;
+        /// Frames sent to one of the multicast addresses of the filter.
+        const MULTICAST = 0x02;
+        /// Frames sent to the broadcast address.
+        const BROADCAST = 0x04;
+        /// All frames.
+        const PROMISCUOUS = 0x08;
+        /// All multicast frames.
+        const PROMISCUOUS_MULTICAST = 0x10;
+    }
+}
+
+bitflags! {
+    /// Interrupts reported by `SimpleNetwork::get_interrupt_status()`.
+    pub struct InterruptStatus: u32 {
+        /// A frame was received.
+        const RECEIVE = 0x01;
+        /// A frame was sent.
+        const TRANSMIT = 0x02;
+        /// A command was completed.
+        const COMMAND = 0x04;
+        /// A software interrupt was raised.
+        const SOFTWARE = 0x08;
+    }
+}
+
+newtype_enum! {
+    /// State of a network interface.
+    pub enum NetworkState: u32 => {
+        /// The interface is stopped.
+        STOPPED     = 0,
+        /// The interface is started, but cannot send or receive frames.
+        STARTED     = 1,
+        /// The interface can send and receive frames.
+        INITIALIZED = 2,
+    }
+}
+
+/// Current state and capabilities of a network interface.
+#[derive(Debug)]
+#[repr(C)]
+pub struct NetworkMode {
+    /// State of the interface.
+    pub state: NetworkState,
+    /// Size of the hardware addresses, in bytes.
+    pub hw_address_size: u32,
+    /// Size of the media header of frames, in bytes.
+    pub media_header_size: u32,
+    /// Maximal size of the data of a frame, in bytes.
+    pub max_packet_size: u32,
+    /// Size of the non-volatile storage, in bytes.
+    pub nv_ram_size: u32,
+    /// Granularity of the accesses to the non-volatile storage, in bytes.
+    pub nv_ram_access_size: u32,
+    /// Receive filters supported by the interface.
+    pub receive_filter_mask: ReceiveFlags,
+    /// Receive filters currently enabled.
+    pub receive_filter_setting: ReceiveFlags,
+    /// Maximal number of multicast addresses in the filter.
+    pub max_mcast_filter_count: u32,
+    /// Number of multicast addresses in the filter.
+    pub mcast_filter_count: u32,
+    /// Multicast addresses in the filter.
+    pub mcast_filter: [MacAddress; 16],
+    /// Address currently used by the interface.
+    pub current_address: MacAddress,
+    /// Broadcast address of the media.
+    pub broadcast_address: MacAddress,
+    /// Permanent address of the interface.
+    pub permanent_address: MacAddress,
+    /// Type of the interface, as defined by the IANA `ifType` numbers.
+    pub if_type: u8,
+    /// True if the address of the interface can be changed.
+    pub mac_address_changeable: bool,
+    /// True if several frames can be queued for transmission.
+    pub multiple_tx_supported: bool,
+    /// True if the interface can report whether a medium is present.
+    pub media_present_supported: bool,
+    /// True if a medium, such as a cable, is present.
+    pub media_present: bool,
+}
+
+/// Traffic statistics of a network interface.
+///
+/// Counters which are not supported by the interface are left to zero.
+#[allow(missing_docs)]
+#[derive(Debug, Default, Copy, Clone)]
+#[repr(C)]
+pub struct NetworkStatistics {
+    pub rx_total_frames: u64,
+    pub rx_good_frames: u64,
+    pub rx_undersize_frames: u64,
+    pub rx_oversize_frames: u64,
+    pub rx_dropped_frames: u64,
+    pub rx_unicast_frames: u64,
+    pub rx_broadcast_frames: u64,
+    pub rx_multicast_frames: u64,
+    pub rx_crc_error_frames: u64,
+    pub rx_total_bytes: u64,
+    pub tx_total_frames: u64,
+    pub tx_good_frames: u64,
+    pub tx_undersize_frames: u64,
+    pub tx_oversize_frames: u64,
+    pub tx_dropped_frames: u64,
+    pub tx_unicast_frames: u64,
+    pub tx_broadcast_frames: u64,
+    pub tx_multicast_frames: u64,
+    pub tx_crc_error_frames: u64,
+    pub tx_total_bytes: u64,
+    pub collisions: u64,
+    pub unsupported_protocol: u64,
+    pub rx_duplicated_frames: u64,
+    pub rx_decrypt_error_frames: u64,
+    pub tx_error_frames: u64,
+    pub tx_retry_frames: u64,
+}
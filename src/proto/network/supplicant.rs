@@ -0,0 +1,283 @@
+//! Supplicant protocol.
+//!
+//! `Supplicant` authenticates a wireless interface to the network it joins,
+//! by running the 802.11i handshake with the access point. It is used by the
+//! wireless MAC connection protocol, which only needs the credentials of the
+//! network to be set beforehand.
+
+use super::crypt::CryptPacket;
+use super::wifi::{Ieee80211Ssid, Ieee80211SuiteSelector};
+use super::{FragmentData, ServiceBinding};
+use crate::proto::Protocol;
+use crate::table::boot::BootServices;
+use crate::{unsafe_guid, CStr8, Result, Status};
+use core::ffi::c_void;
+use core::ops::Deref;
+use core::{mem, ptr};
+
+/// Service binding creating `Supplicant` instances.
+///
+/// It is installed on the handles of the wireless network interfaces.
+#[repr(transparent)]
+#[unsafe_guid("45bcd98e-59ad-4174-9546-344a07485898")]
+#[derive(Protocol)]
+pub struct SupplicantServiceBinding(ServiceBinding);
+
+impl Deref for SupplicantServiceBinding {
+    type Target = ServiceBinding;
+
+    fn deref(&self) -> &ServiceBinding {
+        &self.0
+    }
+}
+
+/// 802.11i supplicant.
+///
+/// The credentials and ciphers of the network are set with the `set_*()`
+/// methods, before connecting to it with the wireless MAC connection
+/// protocol.
+#[repr(C)]
+#[unsafe_guid("54fcc43e-aa89-4333-9a85-cdea24051e9e")]
+#[derive(Protocol)]
+pub struct Supplicant {
+    build_response_packet: extern "efiapi" fn(
+        this: &Supplicant,
+        request_buffer: *const u8,
+        request_buffer_size: usize,
+        buffer: *mut u8,
+        buffer_size: &mut usize,
+    ) -> Status,
+    process_packet: extern "efiapi" fn(
+        this: &Supplicant,
+        fragment_table: &mut *mut FragmentData,
+        fragment_count: &mut u32,
+        crypt_mode: SupplicantCryptMode,
+    ) -> Status,
+    set_data: extern "efiapi" fn(
+        this: &Supplicant,
+        data_type: SupplicantDataType,
+        data: *const c_void,
+        data_size: usize,
+    ) -> Status,
+    get_data: extern "efiapi" fn(
+        this: &Supplicant,
+        data_type: SupplicantDataType,
+        data: *mut u8,
+        data_size: &mut usize,
+    ) -> Status,
+}
+
+impl Supplicant {
+    /// Sets data of the given type.
+    ///
+    /// The typed methods below should be preferred, this is for the data
+    /// types which have none.
+    ///
+    /// # Errors
+    ///
+    /// - `Unsupported` if the data type cannot be set
+    /// - `InvalidParameter` if the data is not valid
+    /// - `OutOfResources` if the data could not be stored
+    pub fn set_data(&self, data_type: SupplicantDataType, data: &[u8]) -> Result {
+        (self.set_data)(self, data_type, data.as_ptr() as *const c_void, data.len()).into()
+    }
+
+    /// Reads data of the given type into `buffer`, and returns its size.
+    ///
+    /// If the buffer is too small, the required buffer size will be returned
+    /// as part of the error.
+    ///
+    /// # Errors
+    ///
+    /// - `BufferTooSmall` if the buffer cannot hold the data
+    /// - `NotFound` if the data is not available
+    /// - `Unsupported` if the data type cannot be read
+    pub fn get_data(
+        &self,
+        data_type: SupplicantDataType,
+        buffer: &mut [u8],
+    ) -> Result<usize, Option<usize>> {
+        let mut size = buffer.len();
+        (self.get_data)(self, data_type, buffer.as_mut_ptr(), &mut size).into_with(
+            || size,
+            |s| {
+                if s == Status::BUFFER_TOO_SMALL {
+                    Some(size)
+                } else {
+                    None
+                }
+            },
+        )
+    }
+
+    /// Sets the authentication and key management suite of the network.
+    pub fn set_akm_suite(&self, suite: Ieee80211SuiteSelector) -> Result {
+        self.set_value(SupplicantDataType::AKM_SUITE, &suite)
+    }
+
+    /// Sets the cipher suite protecting the unicast traffic of the network.
+    pub fn set_pairwise_cipher_suite(&self, suite: Ieee80211SuiteSelector) -> Result {
+        self.set_value(SupplicantDataType::PAIRWISE_CIPHER_SUITE, &suite)
+    }
+
+    /// Sets the cipher suite protecting the broadcast and multicast traffic
+    /// of the network.
+    pub fn set_group_data_cipher_suite(&self, suite: Ieee80211SuiteSelector) -> Result {
+        self.set_value(SupplicantDataType::GROUP_DATA_CIPHER_SUITE, &suite)
+    }
+
+    /// Sets the pre-shared key of the network, as an ASCII passphrase of 8 to
+    /// 63 characters.
+    pub fn set_psk_password(&self, password: &CStr8) -> Result {
+        self.set_data(
+            SupplicantDataType::PSK_PASSWORD,
+            password.to_bytes_with_nul(),
+        )
+    }
+
+    /// Sets the SSID of the network.
+    pub fn set_target_ssid(&self, ssid: &Ieee80211Ssid) -> Result {
+        self.set_value(SupplicantDataType::TARGET_SSID_NAME, ssid)
+    }
+
+    /// Sets the MAC address of the access point of the network.
+    pub fn set_target_mac(&self, mac: &[u8; 6]) -> Result {
+        self.set_value(SupplicantDataType::TARGET_SSID_MAC, mac)
+    }
+
+    /// Returns the MAC address of the wireless interface.
+    pub fn station_mac(&self) -> Result<[u8; 6]> {
+        let mut mac = [0; 6];
+        let mut size = mem::size_of_val(&mac);
+        let status = (self.get_data)(
+            self,
+            SupplicantDataType::STATION_MAC,
+            mac.as_mut_ptr(),
+            &mut size,
+        );
+        status.into_with_val(|| mac)
+    }
+
+    /// Processes `request`, the EAPOL packet received from the access point,
+    /// and writes the EAPOL packet to send back into `buffer`.
+    ///
+    /// Returns the size of the packet to send, which may be zero. If the
+    /// buffer is too small, the required buffer size will be returned as
+    /// part of the error.
+    ///
+    /// # Errors
+    ///
+    /// - `BufferTooSmall` if the buffer cannot hold the packet
+    /// - `NotReady` if the credentials of the network are not set
+    /// - `Unsupported` if the request type is not supported
+    pub fn build_response_packet(
+        &self,
+        request: Option<&[u8]>,
+        buffer: &mut [u8],
+    ) -> Result<usize, Option<usize>> {
+        let (request_buffer, request_size) = request.map_or((ptr::null(), 0), |request| {
+            (request.as_ptr(), request.len())
+        });
+        let mut size = buffer.len();
+        (self.build_response_packet)(
+            self,
+            request_buffer,
+            request_size,
+            buffer.as_mut_ptr(),
+            &mut size,
+        )
+        .into_with(
+            || size,
+            |s| {
+                if s == Status::BUFFER_TOO_SMALL {
+                    Some(size)
+                } else {
+                    None
+                }
+            },
+        )
+    }
+
+    /// Encrypts or decrypts an 802.11 frame with the keys negotiated by the
+    /// handshake, for interfaces without hardware ciphers.
+    ///
+    /// The processed frame is allocated by the firmware, and freed when the
+    /// returned `SupplicantPacket` is dropped.
+    ///
+    /// # Errors
+    ///
+    /// - `NotReady` if no keys were negotiated
+    /// - `Aborted` if the frame could not be processed
+    pub fn process_packet<'boot>(
+        &self,
+        bt: &'boot BootServices,
+        frame: &[u8],
+        mode: SupplicantCryptMode,
+    ) -> Result<SupplicantPacket<'boot>> {
+        CryptPacket::process(bt, frame, |table, count| {
+            (self.process_packet)(self, table, count, mode)
+        })
+    }
+
+    /// Sets data made of a single value
+    fn set_value<T>(&self, data_type: SupplicantDataType, value: &T) -> Result {
+        let data = value as *const T as *const c_void;
+        (self.set_data)(self, data_type, data, mem::size_of::<T>()).into()
+    }
+}
+
+newtype_enum! {
+    /// Type of the data of a `Supplicant` instance.
+    pub enum SupplicantDataType: u32 => {
+        /// Authentication and key management suite, as an
+        /// `Ieee80211SuiteSelector`.
+        AKM_SUITE                         = 0,
+        /// Group data cipher suite, as an `Ieee80211SuiteSelector`.
+        GROUP_DATA_CIPHER_SUITE           = 1,
+        /// Pairwise cipher suite, as an `Ieee80211SuiteSelector`.
+        PAIRWISE_CIPHER_SUITE             = 2,
+        /// Pre-shared key passphrase, as a nul-terminated ASCII string.
+        PSK_PASSWORD                      = 3,
+        /// SSID of the network, as an `Ieee80211Ssid`.
+        TARGET_SSID_NAME                  = 4,
+        /// MAC address of the interface.
+        STATION_MAC                       = 5,
+        /// MAC address of the access point.
+        TARGET_SSID_MAC                   = 6,
+        /// Pairwise transient key.
+        PTK                               = 7,
+        /// Group temporal key.
+        GTK                               = 8,
+        /// State of the supplicant.
+        STATE                             = 9,
+        /// State of the link.
+        LINK_STATE                        = 10,
+        /// Whether the keys must be refreshed.
+        KEY_REFRESH                       = 11,
+        /// Supported authentication and key management suites.
+        SUPPORTED_AKM_SUITES              = 12,
+        /// Cipher suites supported in software.
+        SUPPORTED_SOFTWARE_CIPHER_SUITES  = 13,
+        /// Cipher suites supported in hardware.
+        SUPPORTED_HARDWARE_CIPHER_SUITES  = 14,
+        /// Integrity group temporal key.
+        IGTK                              = 15,
+        /// Pairwise master key.
+        PMK                               = 16,
+    }
+}
+
+newtype_enum! {
+    /// Direction in which `Supplicant::process_packet()` processes frames.
+    pub enum SupplicantCryptMode: u32 => {
+        /// Encrypt frames, to send them.
+        ENCRYPT = 1,
+        /// Decrypt frames received from the access point.
+        DECRYPT = 2,
+    }
+}
+
+/// Frame output by `Supplicant::process_packet()`.
+///
+/// The frame is freed when this is dropped.
+pub type SupplicantPacket<'boot> = CryptPacket<'boot>;
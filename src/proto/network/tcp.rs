@@ -0,0 +1,428 @@
+//! Types shared by the TCP4 and TCP6 protocols.
+//!
+//! Both protocols use the same tokens, buffers and tuning options, and only
+//! differ by their addresses and configuration. `Connection` builds blocking
+//! sockets on top of either of them, through the `TcpProtocol` trait.
+
+use super::{FragmentData, ServiceBinding};
+use crate::proto::Protocol;
+use crate::table::boot::{BootServices, EventType, Tpl};
+use crate::{Completion, Event, Handle, Result, ResultExt, Status};
+use core::cell::UnsafeCell;
+use core::ffi::c_void;
+use core::marker::PhantomData;
+use core::ops::Deref;
+use core::ptr;
+
+/// Tuning options of a TCP connection.
+///
+/// Zero values select the defaults of the firmware.
+#[allow(missing_docs)]
+#[derive(Debug, Default, Copy, Clone)]
+#[repr(C)]
+pub struct TcpOption {
+    pub receive_buffer_size: u32,
+    pub send_buffer_size: u32,
+    pub max_syn_back_log: u32,
+    pub connection_timeout: u32,
+    pub data_retries: u32,
+    pub fin_timeout: u32,
+    pub time_wait_timeout: u32,
+    pub keep_alive_probes: u32,
+    pub keep_alive_time: u32,
+    pub keep_alive_interval: u32,
+    pub enable_nagle: bool,
+    pub enable_time_stamp: bool,
+    pub enable_window_scaling: bool,
+    pub enable_selective_ack: bool,
+    pub enable_path_mtu_discovery: bool,
+}
+
+/// Token tracking an asynchronous operation of a TCP instance.
+#[repr(C)]
+pub struct TcpCompletionToken {
+    event: Event,
+    // Written by the firmware behind the back of the compiler
+    status: UnsafeCell<Status>,
+}
+
+impl TcpCompletionToken {
+    /// Creates a token which signals `event` upon completion.
+    pub fn new(event: Event) -> Self {
+        Self {
+            event,
+            status: UnsafeCell::new(Status::SUCCESS),
+        }
+    }
+
+    /// Event signaled upon completion of the operation.
+    pub fn event(&self) -> Event {
+        self.event
+    }
+
+    /// Outcome of the operation.
+    ///
+    /// This is only meaningful once the event has been signaled.
+    pub fn status(&self) -> Status {
+        unsafe { ptr::read_volatile(self.status.get()) }
+    }
+}
+
+/// Token tracking the acceptance of a connection.
+#[repr(C)]
+pub struct TcpListenToken {
+    /// Completion of the operation.
+    pub completion_token: TcpCompletionToken,
+    new_child_handle: Handle,
+}
+
+impl TcpListenToken {
+    /// Creates a token which signals `event` upon completion.
+    pub fn new(event: Event) -> Self {
+        Self {
+            completion_token: TcpCompletionToken::new(event),
+            new_child_handle: unsafe { Handle::uninitialized() },
+        }
+    }
+
+    /// Handle of the instance of the accepted connection.
+    ///
+    /// This is only meaningful once the operation succeeded.
+    pub fn new_child_handle(&self) -> Handle {
+        self.new_child_handle
+    }
+}
+
+/// Token tracking the transmission or reception of data.
+#[repr(C)]
+pub struct TcpIoToken<'data> {
+    /// Completion of the operation.
+    pub completion_token: TcpCompletionToken,
+    packet: *mut c_void,
+    _data: PhantomData<&'data mut ()>,
+}
+
+impl<'data> TcpIoToken<'data> {
+    /// Creates a token receiving data into `data`, which signals `event` upon
+    /// completion.
+    pub fn receive(event: Event, data: &'data mut TcpReceiveData<'_>) -> Self {
+        Self {
+            completion_token: TcpCompletionToken::new(event),
+            packet: data as *mut TcpReceiveData as *mut c_void,
+            _data: PhantomData,
+        }
+    }
+
+    /// Creates a token sending `data`, which signals `event` upon completion.
+    pub fn transmit(event: Event, data: &'data TcpTransmitData<'_>) -> Self {
+        Self {
+            completion_token: TcpCompletionToken::new(event),
+            packet: data as *const TcpTransmitData as *mut c_void,
+            _data: PhantomData,
+        }
+    }
+}
+
+/// Token tracking the closing of a connection.
+#[repr(C)]
+pub struct TcpCloseToken {
+    /// Completion of the operation.
+    pub completion_token: TcpCompletionToken,
+    /// Reset the connection instead of closing it gracefully.
+    pub abort_on_close: bool,
+}
+
+impl TcpCloseToken {
+    /// Creates a token which signals `event` upon completion.
+    pub fn new(event: Event, abort_on_close: bool) -> Self {
+        Self {
+            completion_token: TcpCompletionToken::new(event),
+            abort_on_close,
+        }
+    }
+}
+
+/// Buffer receiving data from a TCP instance.
+#[repr(C)]
+pub struct TcpReceiveData<'buf> {
+    urgent_flag: bool,
+    data_length: u32,
+    fragment_count: u32,
+    fragment: FragmentData,
+    _buffer: PhantomData<&'buf mut [u8]>,
+}
+
+impl<'buf> TcpReceiveData<'buf> {
+    /// Receives data into `buffer`.
+    pub fn new(buffer: &'buf mut [u8]) -> Self {
+        let length = buffer.len().min(u32::MAX as usize) as u32;
+        Self {
+            urgent_flag: false,
+            data_length: length,
+            fragment_count: 1,
+            fragment: FragmentData {
+                fragment_length: length,
+                fragment_buffer: buffer.as_mut_ptr() as *mut c_void,
+            },
+            _buffer: PhantomData,
+        }
+    }
+
+    /// Number of bytes received.
+    pub fn data_length(&self) -> usize {
+        self.data_length as usize
+    }
+
+    /// True if the data is urgent.
+    pub fn is_urgent(&self) -> bool {
+        self.urgent_flag
+    }
+}
+
+/// Data to be sent by a TCP instance.
+#[repr(C)]
+pub struct TcpTransmitData<'buf> {
+    /// Send the data immediately.
+    pub push: bool,
+    /// Mark the data as urgent.
+    pub urgent: bool,
+    data_length: u32,
+    fragment_count: u32,
+    fragment: FragmentData,
+    _buffer: PhantomData<&'buf [u8]>,
+}
+
+impl<'buf> TcpTransmitData<'buf> {
+    /// Sends `data`, or its first 4GiB if it is larger.
+    pub fn new(data: &'buf [u8]) -> Self {
+        let length = data.len().min(u32::MAX as usize) as u32;
+        Self {
+            push: true,
+            urgent: false,
+            data_length: length,
+            fragment_count: 1,
+            fragment: FragmentData {
+                fragment_length: length,
+                fragment_buffer: data.as_ptr() as *mut c_void,
+            },
+            _buffer: PhantomData,
+        }
+    }
+
+    /// Number of bytes sent.
+    pub fn data_length(&self) -> usize {
+        self.data_length as usize
+    }
+}
+
+/// Operations of the TCP protocols used by `Connection`.
+///
+/// This trait lives in a private module, so it cannot be implemented outside
+/// of this crate. The `start_*` methods have the same safety requirements as
+/// the matching methods of the protocols.
+pub trait TcpProtocol: Protocol {
+    /// Service binding creating instances of the protocol.
+    type ServiceBinding: Protocol + Deref<Target = ServiceBinding>;
+
+    /// Returns true if the connection is closed, or if the instance is not
+    /// configured.
+    fn is_closed(&self) -> bool;
+
+    /// Resets the instance.
+    fn reset(&self) -> Result;
+
+    /// Starts connecting to the remote end.
+    unsafe fn start_connect(&self, token: &mut TcpCompletionToken) -> Result;
+
+    /// Starts accepting a connection.
+    unsafe fn start_accept(&self, token: &mut TcpListenToken) -> Result;
+
+    /// Queues data for transmission.
+    unsafe fn start_transmit(&self, token: &mut TcpIoToken) -> Result;
+
+    /// Queues a buffer to receive data.
+    unsafe fn start_receive(&self, token: &mut TcpIoToken) -> Result;
+
+    /// Starts closing the connection.
+    unsafe fn start_close(&self, token: &mut TcpCloseToken) -> Result;
+}
+
+/// TCP connection, used like a blocking socket.
+///
+/// The operations wait for their completion, and the instance is reset and
+/// destroyed when the connection is dropped, resetting the connection if it
+/// was not closed.
+pub struct Connection<'boot, P: TcpProtocol> {
+    bt: &'boot BootServices,
+    binding: &'boot P::ServiceBinding,
+    handle: Handle,
+    tcp: &'boot P,
+    event: Event,
+    closed: bool,
+}
+
+impl<'boot, P: TcpProtocol> Connection<'boot, P> {
+    /// Returns the underlying instance.
+    pub fn tcp(&self) -> &P {
+        self.tcp
+    }
+
+    /// Connects an active instance to its remote end.
+    ///
+    /// # Errors
+    ///
+    /// - `ConnectionRefused` if the remote end refused the connection
+    /// - `Timeout` if the remote end did not answer
+    /// - The errors of the `connect()` method of the protocol
+    pub fn connect(&mut self) -> Result {
+        let mut token = TcpCompletionToken::new(self.event);
+        let issued = unsafe { self.tcp.start_connect(&mut token) }?;
+        self.wait(&token)
+            .map(|completion| completion.with_status(issued.status()))
+    }
+
+    /// Waits for a connection on a passive instance, and returns it.
+    ///
+    /// # Errors
+    ///
+    /// - The errors of the `accept()` method of the protocol
+    pub fn accept(&mut self) -> Result<Self> {
+        let mut token = TcpListenToken::new(self.event);
+        let issued = unsafe { self.tcp.start_accept(&mut token) }?;
+        self.wait(&token.completion_token)?.log();
+        let connection = Self::from_child(self.bt, self.binding, token.new_child_handle())?;
+        Ok(connection.with_status(issued.status()))
+    }
+
+    /// Sends all of `data`.
+    ///
+    /// # Errors
+    ///
+    /// - `ConnectionReset` if the remote end reset the connection
+    /// - The errors of the `transmit()` method of the protocol
+    pub fn send(&mut self, mut data: &[u8]) -> Result {
+        // Each transmission sends at most 4GiB
+        let mut status = Status::SUCCESS;
+        loop {
+            let tx_data = TcpTransmitData::new(data);
+            let mut token = TcpIoToken::transmit(self.event, &tx_data);
+            let issued = unsafe { self.tcp.start_transmit(&mut token) }?;
+            self.wait(&token.completion_token)?.log();
+            if issued.status() != Status::SUCCESS {
+                status = issued.status();
+            }
+            data = &data[tx_data.data_length()..];
+            if data.is_empty() {
+                return Ok(Completion::new(status, ()));
+            }
+        }
+    }
+
+    /// Waits for data, and receives it into `buffer`.
+    ///
+    /// Returns the number of bytes received, or 0 if the remote end closed
+    /// the connection.
+    ///
+    /// # Errors
+    ///
+    /// - `ConnectionReset` if the remote end reset the connection
+    /// - The errors of the `receive()` method of the protocol
+    pub fn receive(&mut self, buffer: &mut [u8]) -> Result<usize> {
+        let mut rx_data = TcpReceiveData::new(buffer);
+        let mut token = TcpIoToken::receive(self.event, &mut rx_data);
+        let issued = match unsafe { self.tcp.start_receive(&mut token) } {
+            Ok(issued) => issued,
+            Err(err) if err.status() == Status::CONNECTION_FIN => return Ok(0.into()),
+            Err(err) => return Err(err),
+        };
+        match self.wait(&token.completion_token) {
+            Ok(_) => {}
+            Err(err) if err.status() == Status::CONNECTION_FIN => return Ok(0.into()),
+            Err(err) => return Err(err),
+        }
+        Ok(Completion::new(issued.status(), rx_data.data_length()))
+    }
+
+    /// Closes the connection, gracefully or by resetting it.
+    ///
+    /// # Errors
+    ///
+    /// - The errors of the `close()` method of the protocol
+    pub fn close(mut self, abort: bool) -> Result {
+        self.closed = true;
+        self.close_inner(abort)
+    }
+
+    /// Opens the instance on `handle`, a child of `binding`
+    ///
+    /// The child is destroyed if this fails.
+    pub(super) fn from_child(
+        bt: &'boot BootServices,
+        binding: &'boot P::ServiceBinding,
+        handle: Handle,
+    ) -> Result<Self> {
+        let tcp = match bt.handle_protocol::<P>(handle) {
+            Ok(tcp) => unsafe { &*tcp.log().get() },
+            Err(err) => {
+                let _ = binding.destroy_child(handle);
+                return Err(err);
+            }
+        };
+        let event = match unsafe { bt.create_event(EventType::empty(), Tpl::APPLICATION, None) } {
+            Ok(event) => event.log(),
+            Err(err) => {
+                let _ = binding.destroy_child(handle);
+                return Err(err);
+            }
+        };
+        Ok(Self {
+            bt,
+            binding,
+            handle,
+            tcp,
+            event,
+            closed: false,
+        }
+        .into())
+    }
+
+    /// Creates an instance on the network interface `device`
+    pub(super) fn create(bt: &'boot BootServices, device: Handle) -> Result<Self> {
+        let binding = bt.handle_protocol::<P::ServiceBinding>(device)?.log();
+        let binding = unsafe { &*binding.get() };
+        let handle = binding.create_child()?.log();
+        Self::from_child(bt, binding, handle)
+    }
+
+    fn close_inner(&mut self, abort: bool) -> Result {
+        let mut token = TcpCloseToken::new(self.event, abort);
+        let issued = unsafe { self.tcp.start_close(&mut token) }?;
+        self.wait(&token.completion_token)
+            .map(|completion| completion.with_status(issued.status()))
+    }
+
+    /// Waits for the operation of `token` to complete, and returns its outcome
+    ///
+    /// # Panics
+    ///
+    /// Panics if waiting fails, since the firmware may still be using the
+    /// token at that point.
+    fn wait(&self, token: &TcpCompletionToken) -> Result {
+        self.bt
+            .wait_for_event(&mut [self.event])
+            .expect_success("Failed to wait for a pending TCP operation");
+        token.status().into()
+    }
+}
+
+impl<P: TcpProtocol> Drop for Connection<'_, P> {
+    fn drop(&mut self) {
+        // Nothing more can be done if any of this fails
+        if !self.closed && !self.tcp.is_closed() {
+            let _ = self.close_inner(true);
+        }
+        let _ = self.tcp.reset();
+        let _ = self.binding.destroy_child(self.handle);
+        let _ = unsafe { self.bt.close_event(self.event) };
+    }
+}
@@ -0,0 +1,432 @@
+//! TCP over IPv4 protocol.
+//!
+//! Each `Tcp4` instance is one endpoint of a TCP connection, created with
+//! `Tcp4ServiceBinding`. All its operations are asynchronous, and complete by
+//! signaling the event of a token. `TcpConnection` wraps an instance and
+//! manages its tokens and events, to use it like a blocking socket.
+
+use super::tcp::{Connection, TcpProtocol};
+use super::{Ipv4Address, ServiceBinding};
+use crate::proto::Protocol;
+use crate::table::boot::BootServices;
+use crate::{unsafe_guid, Handle, Result, Status};
+use core::ffi::c_void;
+use core::marker::PhantomData;
+use core::ops::Deref;
+use core::ptr;
+
+pub use super::tcp::{
+    TcpCloseToken, TcpCompletionToken, TcpIoToken, TcpListenToken, TcpOption, TcpReceiveData,
+    TcpTransmitData,
+};
+
+/// Service binding creating `Tcp4` instances.
+///
+/// It is installed on the handles of the network interfaces.
+#[repr(transparent)]
+#[unsafe_guid("00720665-67eb-4a99-baf7-d3c33a1c7cc9")]
+#[derive(Protocol)]
+pub struct Tcp4ServiceBinding(ServiceBinding);
+
+impl Deref for Tcp4ServiceBinding {
+    type Target = ServiceBinding;
+
+    fn deref(&self) -> &ServiceBinding {
+        &self.0
+    }
+}
+
+/// Endpoint of a TCP connection over IPv4.
+#[repr(C)]
+#[unsafe_guid("65530bc7-a359-410f-b010-5aadc7ec2b62")]
+#[derive(Protocol)]
+pub struct Tcp4 {
+    get_mode_data: extern "efiapi" fn(
+        this: &Tcp4,
+        tcp4_state: *mut Tcp4State,
+        tcp4_config_data: *mut Tcp4ConfigData,
+        ip4_mode_data: *mut c_void,
+        mnp_config_data: *mut c_void,
+        snp_mode_data: *mut c_void,
+    ) -> Status,
+    configure: extern "efiapi" fn(this: &Tcp4, tcp_config_data: *const Tcp4ConfigData) -> Status,
+    routes: extern "efiapi" fn(
+        this: &Tcp4,
+        delete_route: bool,
+        subnet_address: &Ipv4Address,
+        subnet_mask: &Ipv4Address,
+        gateway_address: &Ipv4Address,
+    ) -> Status,
+    connect: unsafe extern "efiapi" fn(this: &Tcp4, token: *mut TcpCompletionToken) -> Status,
+    accept: unsafe extern "efiapi" fn(this: &Tcp4, token: *mut TcpListenToken) -> Status,
+    transmit: unsafe extern "efiapi" fn(this: &Tcp4, token: *mut TcpIoToken) -> Status,
+    receive: unsafe extern "efiapi" fn(this: &Tcp4, token: *mut TcpIoToken) -> Status,
+    close: unsafe extern "efiapi" fn(this: &Tcp4, token: *mut TcpCloseToken) -> Status,
+    cancel: unsafe extern "efiapi" fn(this: &Tcp4, token: *mut TcpCompletionToken) -> Status,
+    poll: extern "efiapi" fn(this: &Tcp4) -> Status,
+}
+
+impl Tcp4 {
+    /// Returns the state of the connection.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    pub fn state(&self) -> Result<Tcp4State> {
+        let mut state = Tcp4State::CLOSED;
+        (self.get_mode_data)(
+            self,
+            &mut state,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+        )
+        .into_with_val(|| state)
+    }
+
+    /// Returns the current configuration of this instance.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    pub fn config_data(&self) -> Result<Tcp4ConfigData<'static>> {
+        let mut config_data = Tcp4ConfigData::default();
+        (self.get_mode_data)(
+            self,
+            ptr::null_mut(),
+            &mut config_data,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+        )
+        .into_with_val(|| config_data)
+    }
+
+    /// Configures this instance, or resets it if `config_data` is `None`.
+    ///
+    /// Resetting aborts the connection, and cancels all the pending
+    /// operations.
+    ///
+    /// # Errors
+    ///
+    /// - `NoMapping` if the default address is not yet available
+    /// - `AccessDenied` if the instance is already configured
+    /// - `InvalidParameter` if the configuration is not valid
+    pub fn configure(&self, config_data: Option<&Tcp4ConfigData>) -> Result {
+        let config_data = config_data.map_or(ptr::null(), |config| config as *const _);
+        (self.configure)(self, config_data).into()
+    }
+
+    /// Adds a route to a subnet, through a gateway or directly if it is zero.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `AccessDenied` if the route already exists
+    pub fn add_route(
+        &self,
+        subnet_address: &Ipv4Address,
+        subnet_mask: &Ipv4Address,
+        gateway_address: &Ipv4Address,
+    ) -> Result {
+        (self.routes)(self, false, subnet_address, subnet_mask, gateway_address).into()
+    }
+
+    /// Deletes a route added with `add_route()`.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `NotFound` if the route does not exist
+    pub fn delete_route(
+        &self,
+        subnet_address: &Ipv4Address,
+        subnet_mask: &Ipv4Address,
+        gateway_address: &Ipv4Address,
+    ) -> Result {
+        (self.routes)(self, true, subnet_address, subnet_mask, gateway_address).into()
+    }
+
+    /// Starts connecting to the remote end of an active instance.
+    ///
+    /// # Safety
+    ///
+    /// `token` may not be moved, freed or accessed until its event has been
+    /// signaled.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `AccessDenied` if the instance is passive or not closed
+    pub unsafe fn connect(&self, token: &mut TcpCompletionToken) -> Result {
+        (self.connect)(self, token).into()
+    }
+
+    /// Starts accepting a connection on a passive instance.
+    ///
+    /// Once the event of `token` is signaled, the accepted connection is an
+    /// instance on a new child handle, to be destroyed with the service
+    /// binding of this instance.
+    ///
+    /// # Safety
+    ///
+    /// `token` may not be moved, freed or accessed until its event has been
+    /// signaled.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `AccessDenied` if the instance is active or not listening
+    pub unsafe fn accept(&self, token: &mut TcpListenToken) -> Result {
+        (self.accept)(self, token).into()
+    }
+
+    /// Queues data for transmission.
+    ///
+    /// # Safety
+    ///
+    /// Neither `token` nor the data it refers to may be moved, freed or
+    /// accessed until the event of `token` has been signaled.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `AccessDenied` if the connection is not established
+    /// - `OutOfResources` if the data could not be queued
+    pub unsafe fn transmit(&self, token: &mut TcpIoToken) -> Result {
+        (self.transmit)(self, token).into()
+    }
+
+    /// Queues a buffer to receive data.
+    ///
+    /// # Safety
+    ///
+    /// Neither `token` nor the buffer it refers to may be moved, freed or
+    /// accessed until the event of `token` has been signaled.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `ConnectionFin` if the remote end closed the connection
+    pub unsafe fn receive(&self, token: &mut TcpIoToken) -> Result {
+        (self.receive)(self, token).into()
+    }
+
+    /// Starts closing the connection, gracefully or by resetting it.
+    ///
+    /// # Safety
+    ///
+    /// `token` may not be moved, freed or accessed until its event has been
+    /// signaled.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `AccessDenied` if the connection is already being closed
+    pub unsafe fn close(&self, token: &mut TcpCloseToken) -> Result {
+        (self.close)(self, token).into()
+    }
+
+    /// Aborts the pending operations of this instance.
+    ///
+    /// The event of the aborted tokens is signaled, and their status is set to
+    /// `Aborted`.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `NotFound` if no operation is pending
+    pub fn cancel_all(&self) -> Result {
+        unsafe { (self.cancel)(self, ptr::null_mut()) }.into()
+    }
+
+    /// Moves packets between the network interface and the queues of this
+    /// instance.
+    ///
+    /// This is normally done periodically by the firmware, but calling it
+    /// manually may increase the throughput.
+    ///
+    /// # Errors
+    ///
+    /// - `NotReady` if no packet was moved
+    pub fn poll(&self) -> Result {
+        (self.poll)(self).into()
+    }
+}
+
+newtype_enum! {
+    /// State of a TCP connection, as defined by RFC 793.
+    pub enum Tcp4State: u32 => #[allow(missing_docs)] {
+        CLOSED       = 0,
+        LISTEN       = 1,
+        SYN_SENT     = 2,
+        SYN_RECEIVED = 3,
+        ESTABLISHED  = 4,
+        FIN_WAIT1    = 5,
+        FIN_WAIT2    = 6,
+        CLOSING      = 7,
+        TIME_WAIT    = 8,
+        CLOSE_WAIT   = 9,
+        LAST_ACK     = 10,
+    }
+}
+
+/// Local and remote ends of a TCP connection over IPv4.
+#[derive(Debug, Default, Copy, Clone)]
+#[repr(C)]
+pub struct Tcp4AccessPoint {
+    /// Use the default address of the interface, rather than
+    /// `station_address` and `subnet_mask`.
+    pub use_default_address: bool,
+    /// Local address.
+    pub station_address: Ipv4Address,
+    /// Subnet mask of the local address.
+    pub subnet_mask: Ipv4Address,
+    /// Local port, or 0 to pick one.
+    pub station_port: u16,
+    /// Remote address, or zero to accept any host on a passive instance.
+    pub remote_address: Ipv4Address,
+    /// Remote port, or 0 to accept any port on a passive instance.
+    pub remote_port: u16,
+    /// True to connect to the remote end, false to wait for connections.
+    pub active_flag: bool,
+}
+
+/// Configuration of a `Tcp4` instance.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct Tcp4ConfigData<'a> {
+    /// IPv4 type of service of the packets.
+    pub type_of_service: u8,
+    /// IPv4 time to live of the packets.
+    pub time_to_live: u8,
+    /// Ends of the connection.
+    pub access_point: Tcp4AccessPoint,
+    control_option: *const TcpOption,
+    _option: PhantomData<&'a TcpOption>,
+}
+
+impl Default for Tcp4ConfigData<'_> {
+    fn default() -> Self {
+        Self {
+            type_of_service: 0,
+            time_to_live: 64,
+            access_point: Tcp4AccessPoint::default(),
+            control_option: ptr::null(),
+            _option: PhantomData,
+        }
+    }
+}
+
+impl<'a> Tcp4ConfigData<'a> {
+    /// Creates a configuration connecting from the default address of the
+    /// interface to `remote_address:remote_port`.
+    pub fn active(remote_address: Ipv4Address, remote_port: u16) -> Self {
+        Self {
+            access_point: Tcp4AccessPoint {
+                use_default_address: true,
+                remote_address,
+                remote_port,
+                active_flag: true,
+                ..Tcp4AccessPoint::default()
+            },
+            ..Self::default()
+        }
+    }
+
+    /// Creates a configuration accepting connections from any host on
+    /// `station_port`, at the default address of the interface.
+    pub fn passive(station_port: u16) -> Self {
+        Self {
+            access_point: Tcp4AccessPoint {
+                use_default_address: true,
+                station_port,
+                ..Tcp4AccessPoint::default()
+            },
+            ..Self::default()
+        }
+    }
+
+    /// Sets the tuning options of the connection.
+    pub fn set_control_option(&mut self, option: &'a TcpOption) {
+        self.control_option = option;
+    }
+}
+
+impl TcpProtocol for Tcp4 {
+    type ServiceBinding = Tcp4ServiceBinding;
+
+    fn is_closed(&self) -> bool {
+        !matches!(self.state(), Ok(state) if state.unwrap() != Tcp4State::CLOSED)
+    }
+
+    fn reset(&self) -> Result {
+        self.configure(None)
+    }
+
+    unsafe fn start_connect(&self, token: &mut TcpCompletionToken) -> Result {
+        self.connect(token)
+    }
+
+    unsafe fn start_accept(&self, token: &mut TcpListenToken) -> Result {
+        self.accept(token)
+    }
+
+    unsafe fn start_transmit(&self, token: &mut TcpIoToken) -> Result {
+        self.transmit(token)
+    }
+
+    unsafe fn start_receive(&self, token: &mut TcpIoToken) -> Result {
+        self.receive(token)
+    }
+
+    unsafe fn start_close(&self, token: &mut TcpCloseToken) -> Result {
+        self.close(token)
+    }
+}
+
+/// TCP connection over IPv4, used like a blocking socket.
+///
+/// The operations wait for their completion, and the instance is reset and
+/// destroyed when the connection is dropped, resetting the connection if it
+/// was not closed.
+pub type TcpConnection<'boot> = Connection<'boot, Tcp4>;
+
+impl<'boot> Connection<'boot, Tcp4> {
+    /// Creates an instance on the network interface `device`, and configures
+    /// it.
+    ///
+    /// An active configuration must then be connected with `connect()`, and
+    /// a passive one can accept connections with `accept()`.
+    ///
+    /// # Errors
+    ///
+    /// - `Unsupported` if the interface does not support TCP over IPv4
+    /// - The errors of `Tcp4::configure()`
+    pub fn open(bt: &'boot BootServices, device: Handle, config: &Tcp4ConfigData) -> Result<Self> {
+        let connection = Self::create(bt, device)?.log();
+        connection.tcp().configure(Some(config))?.log();
+        Ok(connection.into())
+    }
+
+    /// Connects to `remote_address:remote_port` through the network interface
+    /// `device`.
+    ///
+    /// # Errors
+    ///
+    /// - `ConnectionRefused` if the remote end refused the connection
+    /// - `Timeout` if the remote end did not answer
+    /// - The errors of `open()`
+    pub fn connect_to(
+        bt: &'boot BootServices,
+        device: Handle,
+        remote_address: Ipv4Address,
+        remote_port: u16,
+    ) -> Result<Self> {
+        let config = Tcp4ConfigData::active(remote_address, remote_port);
+        let mut connection = Self::open(bt, device, &config)?.log();
+        connection.connect()?.log();
+        Ok(connection.into())
+    }
+}
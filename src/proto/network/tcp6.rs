@@ -0,0 +1,388 @@
+//! TCP over IPv6 protocol.
+//!
+//! Each `Tcp6` instance is one endpoint of a TCP connection, created with
+//! `Tcp6ServiceBinding`. All its operations are asynchronous, and complete by
+//! signaling the event of a token. `Tcp6Connection` wraps an instance and
+//! manages its tokens and events, to use it like a blocking socket.
+
+use super::tcp::{Connection, TcpProtocol};
+use super::{Ipv6Address, ServiceBinding};
+use crate::proto::Protocol;
+use crate::table::boot::BootServices;
+use crate::{unsafe_guid, Handle, Result, Status};
+use core::ffi::c_void;
+use core::marker::PhantomData;
+use core::ops::Deref;
+use core::ptr;
+
+pub use super::tcp::{
+    TcpCloseToken, TcpCompletionToken, TcpIoToken, TcpListenToken, TcpOption, TcpReceiveData,
+    TcpTransmitData,
+};
+
+/// Service binding creating `Tcp6` instances.
+///
+/// It is installed on the handles of the network interfaces.
+#[repr(transparent)]
+#[unsafe_guid("ec20eb79-6c1a-4664-9a0d-d2e4cc16d664")]
+#[derive(Protocol)]
+pub struct Tcp6ServiceBinding(ServiceBinding);
+
+impl Deref for Tcp6ServiceBinding {
+    type Target = ServiceBinding;
+
+    fn deref(&self) -> &ServiceBinding {
+        &self.0
+    }
+}
+
+/// Endpoint of a TCP connection over IPv6.
+#[repr(C)]
+#[unsafe_guid("46e44855-bd60-4ab7-ab0d-a679b9447d77")]
+#[derive(Protocol)]
+pub struct Tcp6 {
+    get_mode_data: extern "efiapi" fn(
+        this: &Tcp6,
+        tcp6_state: *mut Tcp6State,
+        tcp6_config_data: *mut Tcp6ConfigData,
+        ip6_mode_data: *mut c_void,
+        mnp_config_data: *mut c_void,
+        snp_mode_data: *mut c_void,
+    ) -> Status,
+    configure: extern "efiapi" fn(this: &Tcp6, tcp_config_data: *const Tcp6ConfigData) -> Status,
+    connect: unsafe extern "efiapi" fn(this: &Tcp6, token: *mut TcpCompletionToken) -> Status,
+    accept: unsafe extern "efiapi" fn(this: &Tcp6, token: *mut TcpListenToken) -> Status,
+    transmit: unsafe extern "efiapi" fn(this: &Tcp6, token: *mut TcpIoToken) -> Status,
+    receive: unsafe extern "efiapi" fn(this: &Tcp6, token: *mut TcpIoToken) -> Status,
+    close: unsafe extern "efiapi" fn(this: &Tcp6, token: *mut TcpCloseToken) -> Status,
+    cancel: unsafe extern "efiapi" fn(this: &Tcp6, token: *mut TcpCompletionToken) -> Status,
+    poll: extern "efiapi" fn(this: &Tcp6) -> Status,
+}
+
+impl Tcp6 {
+    /// Returns the state of the connection.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    pub fn state(&self) -> Result<Tcp6State> {
+        let mut state = Tcp6State::CLOSED;
+        (self.get_mode_data)(
+            self,
+            &mut state,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+        )
+        .into_with_val(|| state)
+    }
+
+    /// Returns the current configuration of this instance.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    pub fn config_data(&self) -> Result<Tcp6ConfigData<'static>> {
+        let mut config_data = Tcp6ConfigData::default();
+        (self.get_mode_data)(
+            self,
+            ptr::null_mut(),
+            &mut config_data,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+        )
+        .into_with_val(|| config_data)
+    }
+
+    /// Configures this instance, or resets it if `config_data` is `None`.
+    ///
+    /// Resetting aborts the connection, and cancels all the pending
+    /// operations.
+    ///
+    /// # Errors
+    ///
+    /// - `NoMapping` if the interface has no usable address yet
+    /// - `AccessDenied` if the instance is already configured
+    /// - `InvalidParameter` if the configuration is not valid
+    pub fn configure(&self, config_data: Option<&Tcp6ConfigData>) -> Result {
+        let config_data = config_data.map_or(ptr::null(), |config| config as *const _);
+        (self.configure)(self, config_data).into()
+    }
+
+    /// Starts connecting to the remote end of an active instance.
+    ///
+    /// # Safety
+    ///
+    /// `token` may not be moved, freed or accessed until its event has been
+    /// signaled.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `AccessDenied` if the instance is passive or not closed
+    pub unsafe fn connect(&self, token: &mut TcpCompletionToken) -> Result {
+        (self.connect)(self, token).into()
+    }
+
+    /// Starts accepting a connection on a passive instance.
+    ///
+    /// Once the event of `token` is signaled, the accepted connection is an
+    /// instance on a new child handle, to be destroyed with the service
+    /// binding of this instance.
+    ///
+    /// # Safety
+    ///
+    /// `token` may not be moved, freed or accessed until its event has been
+    /// signaled.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `AccessDenied` if the instance is active or not listening
+    pub unsafe fn accept(&self, token: &mut TcpListenToken) -> Result {
+        (self.accept)(self, token).into()
+    }
+
+    /// Queues data for transmission.
+    ///
+    /// # Safety
+    ///
+    /// Neither `token` nor the data it refers to may be moved, freed or
+    /// accessed until the event of `token` has been signaled.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `AccessDenied` if the connection is not established
+    /// - `OutOfResources` if the data could not be queued
+    pub unsafe fn transmit(&self, token: &mut TcpIoToken) -> Result {
+        (self.transmit)(self, token).into()
+    }
+
+    /// Queues a buffer to receive data.
+    ///
+    /// # Safety
+    ///
+    /// Neither `token` nor the buffer it refers to may be moved, freed or
+    /// accessed until the event of `token` has been signaled.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `ConnectionFin` if the remote end closed the connection
+    pub unsafe fn receive(&self, token: &mut TcpIoToken) -> Result {
+        (self.receive)(self, token).into()
+    }
+
+    /// Starts closing the connection, gracefully or by resetting it.
+    ///
+    /// # Safety
+    ///
+    /// `token` may not be moved, freed or accessed until its event has been
+    /// signaled.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `AccessDenied` if the connection is already being closed
+    pub unsafe fn close(&self, token: &mut TcpCloseToken) -> Result {
+        (self.close)(self, token).into()
+    }
+
+    /// Aborts the pending operations of this instance.
+    ///
+    /// The event of the aborted tokens is signaled, and their status is set to
+    /// `Aborted`.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `NotFound` if no operation is pending
+    pub fn cancel_all(&self) -> Result {
+        unsafe { (self.cancel)(self, ptr::null_mut()) }.into()
+    }
+
+    /// Moves packets between the network interface and the queues of this
+    /// instance.
+    ///
+    /// This is normally done periodically by the firmware, but calling it
+    /// manually may increase the throughput.
+    ///
+    /// # Errors
+    ///
+    /// - `NotReady` if no packet was moved
+    pub fn poll(&self) -> Result {
+        (self.poll)(self).into()
+    }
+}
+
+newtype_enum! {
+    /// State of a TCP connection, as defined by RFC 793.
+    pub enum Tcp6State: u32 => #[allow(missing_docs)] {
+        CLOSED       = 0,
+        LISTEN       = 1,
+        SYN_SENT     = 2,
+        SYN_RECEIVED = 3,
+        ESTABLISHED  = 4,
+        FIN_WAIT1    = 5,
+        FIN_WAIT2    = 6,
+        CLOSING      = 7,
+        TIME_WAIT    = 8,
+        CLOSE_WAIT   = 9,
+        LAST_ACK     = 10,
+    }
+}
+
+/// Local and remote ends of a TCP connection over IPv6.
+#[derive(Debug, Default, Copy, Clone)]
+#[repr(C)]
+pub struct Tcp6AccessPoint {
+    /// Local address, or zero to let the IPv6 layer pick one.
+    pub station_address: Ipv6Address,
+    /// Local port, or 0 to pick one.
+    pub station_port: u16,
+    /// Remote address, or zero to accept any host on a passive instance.
+    pub remote_address: Ipv6Address,
+    /// Remote port, or 0 to accept any port on a passive instance.
+    pub remote_port: u16,
+    /// True to connect to the remote end, false to wait for connections.
+    pub active_flag: bool,
+}
+
+/// Configuration of a `Tcp6` instance.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct Tcp6ConfigData<'a> {
+    /// IPv6 traffic class of the packets.
+    pub traffic_class: u8,
+    /// IPv6 hop limit of the packets.
+    pub hop_limit: u8,
+    /// Ends of the connection.
+    pub access_point: Tcp6AccessPoint,
+    control_option: *const TcpOption,
+    _option: PhantomData<&'a TcpOption>,
+}
+
+impl Default for Tcp6ConfigData<'_> {
+    fn default() -> Self {
+        Self {
+            traffic_class: 0,
+            hop_limit: 64,
+            access_point: Tcp6AccessPoint::default(),
+            control_option: ptr::null(),
+            _option: PhantomData,
+        }
+    }
+}
+
+impl<'a> Tcp6ConfigData<'a> {
+    /// Creates a configuration connecting to `remote_address:remote_port`,
+    /// from an address picked by the IPv6 layer.
+    pub fn active(remote_address: Ipv6Address, remote_port: u16) -> Self {
+        Self {
+            access_point: Tcp6AccessPoint {
+                remote_address,
+                remote_port,
+                active_flag: true,
+                ..Tcp6AccessPoint::default()
+            },
+            ..Self::default()
+        }
+    }
+
+    /// Creates a configuration accepting connections from any host on
+    /// `station_port`, at any address of the interface.
+    pub fn passive(station_port: u16) -> Self {
+        Self {
+            access_point: Tcp6AccessPoint {
+                station_port,
+                ..Tcp6AccessPoint::default()
+            },
+            ..Self::default()
+        }
+    }
+
+    /// Sets the tuning options of the connection.
+    pub fn set_control_option(&mut self, option: &'a TcpOption) {
+        self.control_option = option;
+    }
+}
+
+impl TcpProtocol for Tcp6 {
+    type ServiceBinding = Tcp6ServiceBinding;
+
+    fn is_closed(&self) -> bool {
+        !matches!(self.state(), Ok(state) if state.unwrap() != Tcp6State::CLOSED)
+    }
+
+    fn reset(&self) -> Result {
+        self.configure(None)
+    }
+
+    unsafe fn start_connect(&self, token: &mut TcpCompletionToken) -> Result {
+        self.connect(token)
+    }
+
+    unsafe fn start_accept(&self, token: &mut TcpListenToken) -> Result {
+        self.accept(token)
+    }
+
+    unsafe fn start_transmit(&self, token: &mut TcpIoToken) -> Result {
+        self.transmit(token)
+    }
+
+    unsafe fn start_receive(&self, token: &mut TcpIoToken) -> Result {
+        self.receive(token)
+    }
+
+    unsafe fn start_close(&self, token: &mut TcpCloseToken) -> Result {
+        self.close(token)
+    }
+}
+
+/// TCP connection over IPv6, used like a blocking socket.
+///
+/// The operations wait for their completion, and the instance is reset and
+/// destroyed when the connection is dropped, resetting the connection if it
+/// was not closed.
+pub type Tcp6Connection<'boot> = Connection<'boot, Tcp6>;
+
+impl<'boot> Connection<'boot, Tcp6> {
+    /// Creates an instance on the network interface `device`, and configures
+    /// it.
+    ///
+    /// An active configuration must then be connected with `connect()`, and
+    /// a passive one can accept connections with `accept()`.
+    ///
+    /// # Errors
+    ///
+    /// - `Unsupported` if the interface does not support TCP over IPv6
+    /// - The errors of `Tcp6::configure()`
+    pub fn open(bt: &'boot BootServices, device: Handle, config: &Tcp6ConfigData) -> Result<Self> {
+        let connection = Self::create(bt, device)?.log();
+        connection.tcp().configure(Some(config))?.log();
+        Ok(connection.into())
+    }
+
+    /// Connects to `remote_address:remote_port` through the network interface
+    /// `device`.
+    ///
+    /// # Errors
+    ///
+    /// - `ConnectionRefused` if the remote end refused the connection
+    /// - `Timeout` if the remote end did not answer
+    /// - The errors of `open()`
+    pub fn connect_to(
+        bt: &'boot BootServices,
+        device: Handle,
+        remote_address: Ipv6Address,
+        remote_port: u16,
+    ) -> Result<Self> {
+        let config = Tcp6ConfigData::active(remote_address, remote_port);
+        let mut connection = Self::open(bt, device, &config)?.log();
+        connection.connect()?.log();
+        Ok(connection.into())
+    }
+}
@@ -0,0 +1,519 @@
+//! TLS protocols.
+//!
+//! `Tls` runs the TLS state machine of the firmware's crypto library on the
+//! records given to it: it does not send or receive anything itself, so the
+//! caller moves the records over a transport such as TCP. Its instances are
+//! created with `TlsServiceBinding`, and their certificates and keys are set
+//! with the `TlsConfiguration` protocol of the same handle.
+
+use super::crypt::CryptPacket;
+use super::{FragmentData, ServiceBinding};
+use crate::proto::Protocol;
+use crate::table::boot::BootServices;
+use crate::{unsafe_guid, CStr8, Char8, Result, Status};
+use bitflags::bitflags;
+use core::ffi::c_void;
+use core::ops::Deref;
+use core::{mem, ptr};
+
+/// Service binding creating `Tls` instances.
+///
+/// It is installed on the handle of the TLS driver, rather than on those of
+/// the network interfaces.
+#[repr(transparent)]
+#[unsafe_guid("952cb795-ff36-48cf-a249-4df486d6ab8d")]
+#[derive(Protocol)]
+pub struct TlsServiceBinding(ServiceBinding);
+
+impl Deref for TlsServiceBinding {
+    type Target = ServiceBinding;
+
+    fn deref(&self) -> &ServiceBinding {
+        &self.0
+    }
+}
+
+/// TLS session.
+///
+/// The session is configured with the `set_*()` methods before the
+/// handshake, which is then driven with `build_response_packet()`. Once
+/// connected, application data is encrypted and decrypted with
+/// `process_packet()`.
+#[repr(C)]
+#[unsafe_guid("00ca959f-6cfa-4db1-95bc-e46c47514390")]
+#[derive(Protocol)]
+pub struct Tls {
+    set_session_data: extern "efiapi" fn(
+        this: &Tls,
+        data_type: TlsSessionDataType,
+        data: *const c_void,
+        data_size: usize,
+    ) -> Status,
+    get_session_data: extern "efiapi" fn(
+        this: &Tls,
+        data_type: TlsSessionDataType,
+        data: *mut c_void,
+        data_size: &mut usize,
+    ) -> Status,
+    build_response_packet: extern "efiapi" fn(
+        this: &Tls,
+        request_buffer: *const u8,
+        request_size: usize,
+        buffer: *mut u8,
+        buffer_size: &mut usize,
+    ) -> Status,
+    process_packet: extern "efiapi" fn(
+        this: &Tls,
+        fragment_table: &mut *mut FragmentData,
+        fragment_count: &mut u32,
+        crypt_mode: TlsCryptMode,
+    ) -> Status,
+}
+
+impl Tls {
+    /// Sets session data of the given type.
+    ///
+    /// The typed methods below should be preferred, this is for the data
+    /// types which have none.
+    ///
+    /// # Errors
+    ///
+    /// - `Unsupported` if the data type cannot be set
+    /// - `AccessDenied` if the data cannot be set in the current state
+    /// - `InvalidParameter` if the data is not valid
+    pub fn set_session_data(&self, data_type: TlsSessionDataType, data: &[u8]) -> Result {
+        (self.set_session_data)(self, data_type, data.as_ptr() as *const c_void, data.len()).into()
+    }
+
+    /// Reads session data of the given type into `buffer`, and returns its
+    /// size.
+    ///
+    /// If the buffer is too small, the required buffer size will be returned
+    /// as part of the error.
+    ///
+    /// # Errors
+    ///
+    /// - `BufferTooSmall` if the buffer cannot hold the data
+    /// - `Unsupported` if the data type cannot be read
+    /// - `NotReady` if the data is not available in the current state
+    pub fn get_session_data(
+        &self,
+        data_type: TlsSessionDataType,
+        buffer: &mut [u8],
+    ) -> Result<usize, Option<usize>> {
+        let mut size = buffer.len();
+        (self.get_session_data)(
+            self,
+            data_type,
+            buffer.as_mut_ptr() as *mut c_void,
+            &mut size,
+        )
+        .into_with(
+            || size,
+            |s| {
+                if s == Status::BUFFER_TOO_SMALL {
+                    Some(size)
+                } else {
+                    None
+                }
+            },
+        )
+    }
+
+    /// Returns the TLS version of the session.
+    pub fn version(&self) -> Result<TlsVersion> {
+        self.get_value(TlsSessionDataType::VERSION)
+    }
+
+    /// Sets the TLS version of the session.
+    pub fn set_version(&self, version: TlsVersion) -> Result {
+        self.set_value(TlsSessionDataType::VERSION, &version)
+    }
+
+    /// Sets whether the session is the client or the server end.
+    pub fn set_connection_end(&self, connection_end: TlsConnectionEnd) -> Result {
+        self.set_value(TlsSessionDataType::CONNECTION_END, &connection_end)
+    }
+
+    /// Sets the cipher suites offered, by order of preference.
+    pub fn set_cipher_list(&self, ciphers: &[TlsCipher]) -> Result {
+        let size = mem::size_of_val(ciphers);
+        let data = ciphers.as_ptr() as *const c_void;
+        (self.set_session_data)(self, TlsSessionDataType::CIPHER_LIST, data, size).into()
+    }
+
+    /// Sets how the certificate of the peer is verified.
+    pub fn set_verify(&self, verify: TlsVerify) -> Result {
+        self.set_value(TlsSessionDataType::VERIFY_METHOD, &verify.bits())
+    }
+
+    /// Sets the name of the host the certificate of the peer must match.
+    pub fn set_verify_host(&self, flags: TlsVerifyHostFlags, host_name: &CStr8) -> Result {
+        let verify_host = RawVerifyHost {
+            flags: flags.bits(),
+            host_name: host_name.as_ptr(),
+        };
+        self.set_value(TlsSessionDataType::VERIFY_HOST, &verify_host)
+    }
+
+    /// Returns the state of the session.
+    pub fn session_state(&self) -> Result<TlsSessionState> {
+        self.get_value(TlsSessionDataType::SESSION_STATE)
+    }
+
+    /// Sets the state of the session, e.g. to `TlsSessionState::NOT_STARTED`
+    /// to start a new handshake.
+    pub fn set_session_state(&self, state: TlsSessionState) -> Result {
+        self.set_value(TlsSessionDataType::SESSION_STATE, &state)
+    }
+
+    /// Returns the random value sent by the client in the handshake.
+    pub fn client_random(&self) -> Result<TlsRandom> {
+        self.get_value(TlsSessionDataType::CLIENT_RANDOM)
+    }
+
+    /// Returns the random value sent by the server in the handshake.
+    pub fn server_random(&self) -> Result<TlsRandom> {
+        self.get_value(TlsSessionDataType::SERVER_RANDOM)
+    }
+
+    /// Processes `request`, the handshake or alert records received from the
+    /// peer, and writes the records to send back into `buffer`.
+    ///
+    /// With no request, this starts the handshake of a client, or builds a
+    /// close notification for a connected session. Returns the size of the
+    /// records to send, which may be zero. If the buffer is too small, the
+    /// required buffer size will be returned as part of the error.
+    ///
+    /// # Errors
+    ///
+    /// - `BufferTooSmall` if the buffer cannot hold the records
+    /// - `NotReady` if no response can be built in the current state
+    /// - `Aborted` if the handshake failed, in which case the records in
+    ///   the buffer carry the alert to send
+    pub fn build_response_packet(
+        &self,
+        request: Option<&[u8]>,
+        buffer: &mut [u8],
+    ) -> Result<usize, Option<usize>> {
+        let (request_buffer, request_size) = request.map_or((ptr::null(), 0), |request| {
+            (request.as_ptr(), request.len())
+        });
+        let mut size = buffer.len();
+        (self.build_response_packet)(
+            self,
+            request_buffer,
+            request_size,
+            buffer.as_mut_ptr(),
+            &mut size,
+        )
+        .into_with(
+            || size,
+            |s| {
+                if s == Status::BUFFER_TOO_SMALL {
+                    Some(size)
+                } else {
+                    None
+                }
+            },
+        )
+    }
+
+    /// Encrypts or decrypts the application data records in `records`.
+    ///
+    /// To encrypt, each record holds a TLS record header followed by the
+    /// plain text. To decrypt, the records are those received from the peer.
+    /// The output records are allocated by the firmware, and freed when the
+    /// returned `TlsPacket` is dropped.
+    ///
+    /// # Errors
+    ///
+    /// - `NotReady` if the session is not connected
+    /// - `Aborted` if the records could not be processed
+    pub fn process_packet<'boot>(
+        &self,
+        bt: &'boot BootServices,
+        records: &[u8],
+        mode: TlsCryptMode,
+    ) -> Result<TlsPacket<'boot>> {
+        CryptPacket::process(bt, records, |table, count| {
+            (self.process_packet)(self, table, count, mode)
+        })
+    }
+
+    /// Reads session data made of a single value
+    fn get_value<T>(&self, data_type: TlsSessionDataType) -> Result<T> {
+        let mut value = mem::MaybeUninit::<T>::uninit();
+        let mut size = mem::size_of::<T>();
+        let status = (self.get_session_data)(
+            self,
+            data_type,
+            value.as_mut_ptr() as *mut c_void,
+            &mut size,
+        );
+        if status.is_success() && size != mem::size_of::<T>() {
+            return Err(Status::VOLUME_CORRUPTED.into());
+        }
+        status.into_with_val(|| unsafe { value.assume_init() })
+    }
+
+    /// Sets session data made of a single value
+    fn set_value<T>(&self, data_type: TlsSessionDataType, value: &T) -> Result {
+        let data = value as *const T as *const c_void;
+        (self.set_session_data)(self, data_type, data, mem::size_of::<T>()).into()
+    }
+}
+
+/// Certificates and keys of a `Tls` instance.
+///
+/// It is installed on the child handles created with `TlsServiceBinding`,
+/// next to their `Tls` protocol.
+#[repr(C)]
+#[unsafe_guid("1682fe44-bd7a-4407-b7c7-dca37ca3922d")]
+#[derive(Protocol)]
+pub struct TlsConfiguration {
+    set_data: extern "efiapi" fn(
+        this: &TlsConfiguration,
+        data_type: TlsConfigDataType,
+        data: *const c_void,
+        data_size: usize,
+    ) -> Status,
+    get_data: extern "efiapi" fn(
+        this: &TlsConfiguration,
+        data_type: TlsConfigDataType,
+        data: *mut c_void,
+        data_size: &mut usize,
+    ) -> Status,
+}
+
+impl TlsConfiguration {
+    /// Sets configuration data of the given type.
+    ///
+    /// # Errors
+    ///
+    /// - `Unsupported` if the data type cannot be set
+    /// - `InvalidParameter` if the data is not valid
+    pub fn set_data(&self, data_type: TlsConfigDataType, data: &[u8]) -> Result {
+        (self.set_data)(self, data_type, data.as_ptr() as *const c_void, data.len()).into()
+    }
+
+    /// Reads configuration data of the given type into `buffer`, and returns
+    /// its size.
+    ///
+    /// If the buffer is too small, the required buffer size will be returned
+    /// as part of the error.
+    ///
+    /// # Errors
+    ///
+    /// - `BufferTooSmall` if the buffer cannot hold the data
+    /// - `NotFound` if the data was not set
+    /// - `Unsupported` if the data type cannot be read
+    pub fn get_data(
+        &self,
+        data_type: TlsConfigDataType,
+        buffer: &mut [u8],
+    ) -> Result<usize, Option<usize>> {
+        let mut size = buffer.len();
+        (self.get_data)(
+            self,
+            data_type,
+            buffer.as_mut_ptr() as *mut c_void,
+            &mut size,
+        )
+        .into_with(
+            || size,
+            |s| {
+                if s == Status::BUFFER_TOO_SMALL {
+                    Some(size)
+                } else {
+                    None
+                }
+            },
+        )
+    }
+
+    /// Adds a trusted CA certificate, in DER or PEM format.
+    pub fn add_ca_certificate(&self, certificate: &[u8]) -> Result {
+        self.set_data(TlsConfigDataType::CA_CERTIFICATE, certificate)
+    }
+
+    /// Sets the certificate of this end, in DER or PEM format.
+    pub fn set_host_public_cert(&self, certificate: &[u8]) -> Result {
+        self.set_data(TlsConfigDataType::HOST_PUBLIC_CERT, certificate)
+    }
+
+    /// Sets the private key matching the certificate of this end.
+    pub fn set_host_private_key(&self, key: &[u8]) -> Result {
+        self.set_data(TlsConfigDataType::HOST_PRIVATE_KEY, key)
+    }
+
+    /// Sets the list of revoked certificates.
+    pub fn set_cert_revocation_list(&self, list: &[u8]) -> Result {
+        self.set_data(TlsConfigDataType::CERT_REVOCATION_LIST, list)
+    }
+}
+
+newtype_enum! {
+    /// Type of the session data of a `Tls` instance.
+    pub enum TlsSessionDataType: u32 => {
+        /// TLS version, as a `TlsVersion`.
+        VERSION            = 0,
+        /// End of the connection, as a `TlsConnectionEnd`.
+        CONNECTION_END     = 1,
+        /// Offered cipher suites, as an array of `TlsCipher`.
+        CIPHER_LIST        = 2,
+        /// Offered compression methods, as an array of bytes.
+        COMPRESSION_METHOD = 3,
+        /// Extensions of the handshake, as raw extensions.
+        EXTENSION_DATA     = 4,
+        /// Verification of the peer, as a `u32` made of `TlsVerify` flags.
+        VERIFY_METHOD      = 5,
+        /// Identifier of the session.
+        SESSION_ID         = 6,
+        /// State of the session, as a `TlsSessionState`.
+        SESSION_STATE      = 7,
+        /// Random value of the client, as a `TlsRandom`.
+        CLIENT_RANDOM      = 8,
+        /// Random value of the server, as a `TlsRandom`.
+        SERVER_RANDOM      = 9,
+        /// Master secret of the session.
+        KEY_MATERIAL       = 10,
+        /// Host name which the certificate of the peer must match.
+        VERIFY_HOST        = 11,
+    }
+}
+
+newtype_enum! {
+    /// Type of the configuration data of a `TlsConfiguration` instance.
+    pub enum TlsConfigDataType: u32 => {
+        /// Certificate of this end.
+        HOST_PUBLIC_CERT     = 0,
+        /// Private key of this end.
+        HOST_PRIVATE_KEY     = 1,
+        /// Trusted CA certificates.
+        CA_CERTIFICATE       = 2,
+        /// Revoked certificates.
+        CERT_REVOCATION_LIST = 3,
+    }
+}
+
+newtype_enum! {
+    /// End of a TLS connection.
+    pub enum TlsConnectionEnd: u32 => {
+        /// The end which starts the handshake.
+        CLIENT = 0,
+        /// The end which answers the handshake.
+        SERVER = 1,
+    }
+}
+
+newtype_enum! {
+    /// State of a TLS session.
+    pub enum TlsSessionState: u32 => {
+        /// The handshake has not started yet.
+        NOT_STARTED = 0,
+        /// The handshake is in progress.
+        HANDSHAKING = 1,
+        /// The handshake succeeded, and application data can be exchanged.
+        CONNECTED   = 2,
+        /// The session is being closed.
+        CLOSING     = 3,
+        /// The session failed.
+        ERROR       = 4,
+    }
+}
+
+newtype_enum! {
+    /// Direction in which `Tls::process_packet()` processes records.
+    pub enum TlsCryptMode: u32 => {
+        /// Encrypt plain text records, to send them.
+        ENCRYPT = 1,
+        /// Decrypt records received from the peer.
+        DECRYPT = 2,
+    }
+}
+
+/// Version of the TLS protocol, such as 1.2 for TLS 1.2.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(C)]
+pub struct TlsVersion {
+    /// Major version, 3 for all TLS versions.
+    pub major: u8,
+    /// Minor version, 1 for TLS 1.0 up to 4 for TLS 1.3.
+    pub minor: u8,
+}
+
+impl TlsVersion {
+    /// TLS 1.0.
+    pub const TLS_1_0: TlsVersion = TlsVersion { major: 3, minor: 1 };
+    /// TLS 1.1.
+    pub const TLS_1_1: TlsVersion = TlsVersion { major: 3, minor: 2 };
+    /// TLS 1.2.
+    pub const TLS_1_2: TlsVersion = TlsVersion { major: 3, minor: 3 };
+    /// TLS 1.3.
+    pub const TLS_1_3: TlsVersion = TlsVersion { major: 3, minor: 4 };
+}
+
+/// Cipher suite, as registered with the IANA, such as `[0x00, 0x9c]` for
+/// `TLS_RSA_WITH_AES_128_GCM_SHA256`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(C)]
+pub struct TlsCipher {
+    /// First byte of the identifier.
+    pub data1: u8,
+    /// Second byte of the identifier.
+    pub data2: u8,
+}
+
+/// Random value exchanged in a TLS handshake.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(C)]
+pub struct TlsRandom {
+    /// Time at which the value was generated, in seconds since 1970.
+    pub gmt_unix_time: u32,
+    /// Random bytes.
+    pub random_bytes: [u8; 28],
+}
+
+bitflags! {
+    /// Verification of the certificate of the peer.
+    pub struct TlsVerify: u32 {
+        /// Verify the certificate of the peer, if it sends one.
+        const PEER = 0x1;
+        /// Fail if a client does not send a certificate, for servers.
+        const FAIL_IF_NO_PEER_CERT = 0x2;
+        /// Only request the certificate of a client once, for servers.
+        const CLIENT_ONCE = 0x4;
+    }
+}
+
+bitflags! {
+    /// Matching of the host name against the certificate of the peer.
+    pub struct TlsVerifyHostFlags: u32 {
+        /// Always check the subject name, even with alternative names.
+        const ALWAYS_CHECK_SUBJECT = 0x01;
+        /// Do not match wildcards.
+        const NO_WILDCARDS = 0x02;
+        /// Do not match wildcards in part of a label.
+        const NO_PARTIAL_WILDCARDS = 0x04;
+        /// Let wildcards match several labels.
+        const MULTI_LABEL_WILDCARDS = 0x08;
+        /// Let the host name match any subdomain of a name starting with a
+        /// dot.
+        const SINGLE_LABEL_SUBDOMAINS = 0x10;
+        /// Never check the subject name.
+        const NEVER_CHECK_SUBJECT = 0x20;
+    }
+}
+
+/// Host name verification data, as expected by the firmware
+#[repr(C)]
+struct RawVerifyHost {
+    flags: u32,
+    host_name: *const Char8,
+}
+
+/// Records output by `Tls::process_packet()`.
+///
+/// The records are freed when this is dropped.
+pub type TlsPacket<'boot> = CryptPacket<'boot>;
@@ -0,0 +1,184 @@
+//! Types shared by the UDP4 and UDP6 protocols.
+//!
+//! Both protocols use the same tokens and datagram layouts, and only differ by
+//! their session data, which holds the addresses and ports of a datagram.
+
+use super::FragmentData;
+use crate::table::boot::BootServices;
+use crate::table::runtime::Time;
+use crate::{Event, Result, ResultExt, Status};
+use core::cell::UnsafeCell;
+use core::ffi::c_void;
+use core::marker::PhantomData;
+use core::{ptr, slice};
+
+/// Token tracking an asynchronous operation of a UDP instance, whose
+/// datagrams are addressed by session data of type `S`.
+#[repr(C)]
+pub struct UdpCompletionToken<'data, S> {
+    event: Event,
+    // Both written by the firmware behind the back of the compiler
+    status: UnsafeCell<Status>,
+    packet: UnsafeCell<*mut c_void>,
+    _data: PhantomData<(&'data (), S)>,
+}
+
+impl<S: Copy> UdpCompletionToken<'static, S> {
+    /// Creates a token for receiving a datagram, which signals `event` upon
+    /// completion.
+    pub fn new(event: Event) -> Self {
+        Self::with_packet(event, ptr::null())
+    }
+}
+
+impl<'data, S: Copy> UdpCompletionToken<'data, S> {
+    /// Event signaled upon completion of the operation.
+    pub fn event(&self) -> Event {
+        self.event
+    }
+
+    /// Outcome of the operation.
+    ///
+    /// This is only meaningful once the event has been signaled.
+    pub fn status(&self) -> Status {
+        unsafe { ptr::read_volatile(self.status.get()) }
+    }
+
+    /// Returns the datagram received by a completed receive operation.
+    ///
+    /// # Safety
+    ///
+    /// This token must have been passed to the `receive()` method of the
+    /// protocol, and its event signaled. The datagram may not be accessed
+    /// after its recycle event has been signaled.
+    pub unsafe fn receive_data(&self) -> Option<&UdpReceiveData<S>> {
+        let packet = ptr::read_volatile(self.packet.get());
+        (packet as *const UdpReceiveData<S>).as_ref()
+    }
+
+    /// Creates a token for sending the datagram at `packet`
+    pub(super) fn with_packet(event: Event, packet: *const c_void) -> Self {
+        Self {
+            event,
+            status: UnsafeCell::new(Status::SUCCESS),
+            packet: UnsafeCell::new(packet as *mut c_void),
+            _data: PhantomData,
+        }
+    }
+
+    /// Waits for the pending operation to complete, and returns its outcome.
+    pub(super) fn wait(&self, bt: &BootServices) -> Result {
+        bt.wait_for_event(&mut [self.event])
+            .expect_success("Failed to wait for a pending UDP operation");
+        self.status().into()
+    }
+
+    /// Waits for a pending receive operation, copies the datagram into
+    /// `buffer`, and returns it to the firmware.
+    pub(super) fn wait_for_datagram(
+        &self,
+        bt: &BootServices,
+        buffer: &mut [u8],
+    ) -> Result<(usize, S)> {
+        self.wait(bt)?.log();
+        let data = unsafe { self.receive_data() }.ok_or(Status::ABORTED)?;
+        data.copy_to(buffer);
+        let received = (data.data_length(), *data.session());
+        bt.signal_event(data.recycle_event())?.log();
+        Ok(received.into())
+    }
+}
+
+/// Datagram received by a UDP instance.
+///
+/// The datagram belongs to the firmware, and must be returned to it by
+/// signaling `recycle_event()` once it has been processed. Its data may be
+/// split across several fragments.
+#[repr(C)]
+pub struct UdpReceiveData<S> {
+    timestamp: Time,
+    recycle_signal: Event,
+    session: S,
+    data_length: u32,
+    fragment_count: u32,
+    fragment_table: [FragmentData; 0],
+}
+
+impl<S> UdpReceiveData<S> {
+    /// Time at which the datagram was received.
+    pub fn timestamp(&self) -> &Time {
+        &self.timestamp
+    }
+
+    /// Event to signal to return the datagram to the firmware.
+    pub fn recycle_event(&self) -> Event {
+        self.recycle_signal
+    }
+
+    /// Addresses and ports the datagram was sent from and to.
+    pub fn session(&self) -> &S {
+        &self.session
+    }
+
+    /// Size of the data of the datagram.
+    pub fn data_length(&self) -> usize {
+        self.data_length as usize
+    }
+
+    /// Iterates over the fragments of the data of the datagram.
+    pub fn fragments(&self) -> impl Iterator<Item = &[u8]> + '_ {
+        let table = unsafe {
+            slice::from_raw_parts(self.fragment_table.as_ptr(), self.fragment_count as usize)
+        };
+        table.iter().map(|fragment| unsafe {
+            slice::from_raw_parts(
+                fragment.fragment_buffer as *const u8,
+                fragment.fragment_length as usize,
+            )
+        })
+    }
+
+    /// Copies as much of the data of the datagram as fits into `buffer`, and
+    /// returns the number of bytes copied.
+    pub fn copy_to(&self, buffer: &mut [u8]) -> usize {
+        let mut copied = 0;
+        for fragment in self.fragments() {
+            let len = fragment.len().min(buffer.len() - copied);
+            buffer[copied..copied + len].copy_from_slice(&fragment[..len]);
+            copied += len;
+        }
+        copied
+    }
+}
+
+/// Data of a datagram to be sent, made of `N` fragments.
+///
+/// This is the tail shared by the transmit data of both protocols.
+#[repr(C)]
+pub(super) struct FragmentTable<'data, const N: usize> {
+    data_length: u32,
+    fragment_count: u32,
+    fragments: [FragmentData; N],
+    _data: PhantomData<&'data [u8]>,
+}
+
+impl<'data, const N: usize> FragmentTable<'data, N> {
+    /// Describes the concatenation of `fragments`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the datagram is larger than 4GiB.
+    pub(super) fn new(fragments: [&'data [u8]; N]) -> Self {
+        let data_length: usize = fragments.iter().map(|fragment| fragment.len()).sum();
+        assert!(data_length <= u32::MAX as usize, "Datagram is too large");
+        Self {
+            data_length: data_length as u32,
+            fragment_count: N as u32,
+            fragments: fragments.map(|fragment| FragmentData {
+                fragment_length: fragment.len() as u32,
+                fragment_buffer: fragment.as_ptr() as *mut c_void,
+            }),
+            _data: PhantomData,
+        }
+    }
+}
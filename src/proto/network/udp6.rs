@@ -0,0 +1,335 @@
+//! UDP over IPv6 protocol.
+//!
+//! Each `Udp6` instance sends and receives datagrams on one local port,
+//! created with `Udp6ServiceBinding`. Like the managed network protocol,
+//! sending and receiving are asynchronous, and are tracked with tokens whose
+//! event is signaled upon completion.
+
+use super::udp::FragmentTable;
+use super::{Ipv6Address, ServiceBinding};
+use crate::proto::Protocol;
+use crate::table::boot::BootServices;
+use crate::{unsafe_guid, Event, Result, Status};
+use core::ffi::c_void;
+use core::ops::Deref;
+use core::ptr;
+
+pub use super::udp::{UdpCompletionToken, UdpReceiveData};
+
+/// Service binding creating `Udp6` instances.
+///
+/// It is installed on the handles of the network interfaces.
+#[repr(transparent)]
+#[unsafe_guid("66ed4721-3c98-4d3e-81e3-d03dd39a7254")]
+#[derive(Protocol)]
+pub struct Udp6ServiceBinding(ServiceBinding);
+
+impl Deref for Udp6ServiceBinding {
+    type Target = ServiceBinding;
+
+    fn deref(&self) -> &ServiceBinding {
+        &self.0
+    }
+}
+
+/// Endpoint sending and receiving UDP datagrams over IPv6.
+///
+/// An instance must be configured with `configure()` before it can send and
+/// receive datagrams.
+#[repr(C)]
+#[unsafe_guid("4f948815-b4b9-43cb-8a33-90e060b34955")]
+#[derive(Protocol)]
+pub struct Udp6 {
+    get_mode_data: extern "efiapi" fn(
+        this: &Udp6,
+        udp6_config_data: *mut Udp6ConfigData,
+        ip6_mode_data: *mut c_void,
+        mnp_config_data: *mut c_void,
+        snp_mode_data: *mut c_void,
+    ) -> Status,
+    configure: extern "efiapi" fn(this: &Udp6, udp_config_data: *const Udp6ConfigData) -> Status,
+    groups: extern "efiapi" fn(
+        this: &Udp6,
+        join_flag: bool,
+        multicast_address: *const Ipv6Address,
+    ) -> Status,
+    transmit: unsafe extern "efiapi" fn(this: &Udp6, token: *mut Udp6CompletionToken) -> Status,
+    receive: unsafe extern "efiapi" fn(this: &Udp6, token: *mut Udp6CompletionToken) -> Status,
+    cancel: unsafe extern "efiapi" fn(this: &Udp6, token: *mut Udp6CompletionToken) -> Status,
+    poll: extern "efiapi" fn(this: &Udp6) -> Status,
+}
+
+impl Udp6 {
+    /// Returns the current configuration of this instance, or `None` if it
+    /// is not configured.
+    pub fn config_data(&self) -> Result<Option<Udp6ConfigData>> {
+        let mut config_data = Udp6ConfigData::default();
+        let status = (self.get_mode_data)(
+            self,
+            &mut config_data,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+        );
+        match status {
+            Status::NOT_STARTED => Ok(None.into()),
+            status => status.into_with_val(|| Some(config_data)),
+        }
+    }
+
+    /// Configures this instance, or resets it if `config_data` is `None`.
+    ///
+    /// Resetting cancels all the pending operations, and leaves all the
+    /// multicast groups.
+    ///
+    /// # Errors
+    ///
+    /// - `NoMapping` if the interface has no usable address yet
+    /// - `AlreadyStarted` if the instance is already configured
+    /// - `AccessDenied` if the port is already used by another instance
+    /// - `InvalidParameter` if the configuration is not valid
+    pub fn configure(&self, config_data: Option<&Udp6ConfigData>) -> Result {
+        let config_data = config_data.map_or(ptr::null(), |config| config as *const _);
+        (self.configure)(self, config_data).into()
+    }
+
+    /// Receives the datagrams sent to a multicast group.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `AlreadyStarted` if the group was already joined
+    /// - `InvalidParameter` if the address is not a multicast address
+    pub fn join_group(&self, address: &Ipv6Address) -> Result {
+        (self.groups)(self, true, address).into()
+    }
+
+    /// Stops receiving the datagrams sent to a multicast group, or to all of
+    /// them if `address` is `None`.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `NotFound` if the group was not joined
+    pub fn leave_group(&self, address: Option<&Ipv6Address>) -> Result {
+        let address = address.map_or(ptr::null(), |address| address as *const _);
+        (self.groups)(self, false, address).into()
+    }
+
+    /// Queues the datagram described by `token`, created with
+    /// `Udp6CompletionToken::transmit()`, for transmission.
+    ///
+    /// # Safety
+    ///
+    /// Neither `token` nor the data it refers to may be moved, freed or
+    /// accessed until the event of `token` has been signaled.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `NoMapping` if the interface has no usable address yet
+    /// - `AccessDenied` if the token is already in a queue
+    /// - `NotFound` if there is no route to the destination
+    /// - `BadBufferSize` if the datagram is too large
+    pub unsafe fn transmit(&self, token: &mut Udp6CompletionToken) -> Result {
+        (self.transmit)(self, token).into()
+    }
+
+    /// Queues `token`, created with `Udp6CompletionToken::new()`, to be
+    /// completed with the next datagram received by this instance.
+    ///
+    /// Once the event of `token` has been signaled, the datagram can be read
+    /// with `Udp6CompletionToken::receive_data()`, and must be returned to the
+    /// firmware by signaling its `Udp6ReceiveData::recycle_event()`.
+    ///
+    /// # Safety
+    ///
+    /// `token` may not be moved, freed or accessed until its event has been
+    /// signaled.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `NoMapping` if the interface has no usable address yet
+    /// - `AccessDenied` if the token is already in a queue
+    pub unsafe fn receive(&self, token: &mut Udp6CompletionToken) -> Result {
+        (self.receive)(self, token).into()
+    }
+
+    /// Aborts a pending operation, or all of them if `token` is `None`.
+    ///
+    /// The event of the aborted tokens is signaled, and their status is set
+    /// to `Aborted`.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if this instance is not configured
+    /// - `NotFound` if the token is not in a queue
+    pub fn cancel(&self, token: Option<&mut Udp6CompletionToken>) -> Result {
+        let token = token.map_or(ptr::null_mut(), |token| token as *mut _);
+        unsafe { (self.cancel)(self, token) }.into()
+    }
+
+    /// Moves datagrams between the network interface and the queues of this
+    /// instance.
+    ///
+    /// This is normally done periodically by the firmware, but calling it
+    /// manually may increase the throughput.
+    ///
+    /// # Errors
+    ///
+    /// - `NotReady` if no datagram was moved
+    /// - `Timeout` if the interface took too long to answer
+    pub fn poll(&self) -> Result {
+        (self.poll)(self).into()
+    }
+
+    /// Sends a datagram, and waits for its transmission to complete.
+    ///
+    /// `event` is signaled by the firmware when the transmission completes,
+    /// and must therefore be usable with `BootServices::wait_for_event`, i.e.
+    /// it must not be of type `EventType::NOTIFY_SIGNAL`.
+    ///
+    /// The errors are those of `transmit()`, plus the outcome of the
+    /// transmission.
+    ///
+    /// # Panics
+    ///
+    /// Panics if waiting for `event` fails, since the firmware may still be
+    /// reading from the data at that point.
+    pub fn transmit_blocking<const N: usize>(
+        &self,
+        bt: &BootServices,
+        event: Event,
+        data: &Udp6TransmitData<N>,
+    ) -> Result {
+        let mut token = Udp6CompletionToken::transmit(event, data);
+        let issued = unsafe { self.transmit(&mut token) }?;
+        token
+            .wait(bt)
+            .map(|completion| completion.with_status(issued.status()))
+    }
+
+    /// Waits for a datagram to be received, and copies its data into
+    /// `buffer`.
+    ///
+    /// Returns the size of the datagram, which is larger than the buffer if
+    /// the datagram was truncated, and the addresses it was sent from and to.
+    ///
+    /// See `transmit_blocking()` for the requirements on `event`. The errors
+    /// are those of `receive()`, plus the outcome of the reception.
+    ///
+    /// # Panics
+    ///
+    /// Panics if waiting for `event` fails, since the firmware may still be
+    /// writing to the token at that point.
+    pub fn receive_blocking(
+        &self,
+        bt: &BootServices,
+        event: Event,
+        buffer: &mut [u8],
+    ) -> Result<(usize, Udp6SessionData)> {
+        let mut token = Udp6CompletionToken::new(event);
+        let issued = unsafe { self.receive(&mut token) }?;
+        token
+            .wait_for_datagram(bt, buffer)
+            .map(|completion| completion.with_status(issued.status()))
+    }
+}
+
+/// Configuration of a `Udp6` instance.
+#[derive(Debug, Default, Copy, Clone)]
+#[repr(C)]
+pub struct Udp6ConfigData {
+    /// Receive all the datagrams, whatever their destination.
+    pub accept_promiscuous: bool,
+    /// Receive the datagrams sent to any port.
+    pub accept_any_port: bool,
+    /// Share the port with other instances.
+    pub allow_duplicate_port: bool,
+    /// IPv6 traffic class of the datagrams sent.
+    pub traffic_class: u8,
+    /// IPv6 hop limit of the datagrams sent, or 0 to use the default.
+    pub hop_limit: u8,
+    /// Time after which receptions fail, in microseconds, or 0 to wait
+    /// forever.
+    pub receive_timeout: u32,
+    /// Time after which transmissions fail, in microseconds, or 0 to wait
+    /// forever.
+    pub transmit_timeout: u32,
+    /// Local address, or zero to let the IPv6 layer pick one.
+    pub station_address: Ipv6Address,
+    /// Local port, or 0 to pick one.
+    pub station_port: u16,
+    /// Remote address, or zero to send to and receive from any host.
+    pub remote_address: Ipv6Address,
+    /// Remote port, or 0 to send to and receive from any port.
+    pub remote_port: u16,
+}
+
+/// Addresses and ports of a datagram.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+#[repr(C)]
+pub struct Udp6SessionData {
+    /// Address the datagram is sent from.
+    pub source_address: Ipv6Address,
+    /// Port the datagram is sent from.
+    pub source_port: u16,
+    /// Address the datagram is sent to.
+    pub destination_address: Ipv6Address,
+    /// Port the datagram is sent to.
+    pub destination_port: u16,
+}
+
+/// Token tracking an asynchronous operation of a `Udp6` instance.
+pub type Udp6CompletionToken<'data> = UdpCompletionToken<'data, Udp6SessionData>;
+
+impl<'data> UdpCompletionToken<'data, Udp6SessionData> {
+    /// Creates a token for sending the datagram described by `data`, which
+    /// signals `event` upon completion.
+    pub fn transmit<const N: usize>(event: Event, data: &'data Udp6TransmitData<'_, N>) -> Self {
+        Self::with_packet(event, data as *const Udp6TransmitData<N> as *const c_void)
+    }
+}
+
+/// Datagram received by a `Udp6` instance.
+pub type Udp6ReceiveData = UdpReceiveData<Udp6SessionData>;
+
+/// Datagram to be sent by a `Udp6` instance, made of `N` fragments.
+#[repr(C)]
+pub struct Udp6TransmitData<'data, const N: usize = 1> {
+    session_data: *const Udp6SessionData,
+    fragment_table: FragmentTable<'data, N>,
+}
+
+impl<'data> Udp6TransmitData<'data> {
+    /// Describes a datagram of `data`, sent to the remote end of the
+    /// instance.
+    pub fn new(data: &'data [u8]) -> Self {
+        Self::from_fragments([data])
+    }
+}
+
+impl<'data, const N: usize> Udp6TransmitData<'data, N> {
+    /// Describes a datagram made of the concatenation of `fragments`, sent to
+    /// the remote end of the instance.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the datagram is larger than 4GiB.
+    pub fn from_fragments(fragments: [&'data [u8]; N]) -> Self {
+        Self {
+            session_data: ptr::null(),
+            fragment_table: FragmentTable::new(fragments),
+        }
+    }
+
+    /// Sends the datagram to the destination of `session`, rather than the
+    /// remote end of the instance.
+    ///
+    /// The source address and port of `session` may be zero, to use those of
+    /// the instance.
+    pub fn set_session(&mut self, session: &'data Udp6SessionData) {
+        self.session_data = session;
+    }
+}
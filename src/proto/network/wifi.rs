@@ -0,0 +1,494 @@
+//! Wireless MAC connection protocol.
+//!
+//! `WirelessMacConnectionII` scans for wireless networks, and connects the
+//! interface to one of them. The credentials of protected networks are set
+//! beforehand with the `Supplicant` protocol of the interface. Once
+//! connected, the interface is used through the rest of the network stack
+//! like a wired one.
+
+use crate::proto::Protocol;
+use crate::result::Error;
+use crate::table::boot::{BootServices, EventType, Tpl};
+use crate::{unsafe_guid, Event, Result, ResultExt, Status};
+use core::{ptr, slice};
+
+#[cfg(feature = "exts")]
+use super::supplicant::Supplicant;
+#[cfg(feature = "exts")]
+use crate::{CStr8, Handle};
+#[cfg(feature = "exts")]
+use alloc_api::vec::Vec;
+
+/// Wireless network connection manager.
+///
+/// It is installed on the handles of the wireless network interfaces.
+#[repr(C)]
+#[unsafe_guid("1b0fb9bf-699d-4fdd-a7c3-2546681bf63b")]
+#[derive(Protocol)]
+pub struct WirelessMacConnectionII {
+    get_networks: unsafe extern "efiapi" fn(
+        this: &WirelessMacConnectionII,
+        token: *mut GetNetworksToken,
+    ) -> Status,
+    connect_network: unsafe extern "efiapi" fn(
+        this: &WirelessMacConnectionII,
+        token: *mut ConnectNetworkToken,
+    ) -> Status,
+    disconnect_network: unsafe extern "efiapi" fn(
+        this: &WirelessMacConnectionII,
+        token: *mut DisconnectNetworkToken,
+    ) -> Status,
+}
+
+impl WirelessMacConnectionII {
+    /// Scans for the wireless networks in range, and waits for the scan to
+    /// complete.
+    ///
+    /// If `ssid` is given, the scan also probes for this network, which is
+    /// needed to find hidden networks.
+    ///
+    /// # Errors
+    ///
+    /// - `NotReady` if the interface is busy
+    /// - `NotFound` if no network was found
+    /// - `DeviceError` if the scan failed
+    pub fn get_networks<'boot>(
+        &self,
+        bt: &'boot BootServices,
+        ssid: Option<&Ieee80211Ssid>,
+    ) -> Result<Ieee80211Networks<'boot>> {
+        let data = GetNetworksData {
+            num_of_ssid: ssid.is_some() as u32,
+            ssid_list: [ssid.copied().unwrap_or_default()],
+        };
+        let mut token = GetNetworksToken {
+            event: Event::null(),
+            status: PENDING,
+            data: &data,
+            result: ptr::null_mut(),
+        };
+        complete(bt, ptr::addr_of!(token.status), |event| {
+            token.event = event;
+            unsafe { (self.get_networks)(self, &mut token) }
+        })?
+        .log();
+        if token.result.is_null() {
+            return Err(Status::NOT_FOUND.into());
+        }
+        Ok(Ieee80211Networks {
+            bt,
+            result: token.result,
+        }
+        .into())
+    }
+
+    /// Connects the interface to `network`, as returned by `get_networks()`,
+    /// and waits for the connection to complete, or to fail after `timeout`
+    /// seconds.
+    ///
+    /// The credentials of a protected network must be set beforehand with
+    /// the `Supplicant` protocol of the interface. If the connection is
+    /// refused, the reason will be returned as part of the error.
+    ///
+    /// # Errors
+    ///
+    /// - `NotReady` if the interface is busy
+    /// - `Unsupported` if the network uses a security suite which is not
+    ///   supported
+    /// - `ProtocolError` if the connection was refused
+    /// - `Timeout` if the connection did not complete in time
+    pub fn connect_network(
+        &self,
+        bt: &BootServices,
+        network: &Ieee80211Network,
+        timeout: u32,
+    ) -> Result<(), Option<Ieee80211ConnectResultCode>> {
+        let data = ConnectNetworkData {
+            network,
+            failure_timeout: timeout,
+        };
+        let mut token = ConnectNetworkToken {
+            event: Event::null(),
+            status: PENDING,
+            data: &data,
+            result_code: Ieee80211ConnectResultCode::SUCCESS,
+        };
+        let status = complete(bt, ptr::addr_of!(token.status), |event| {
+            token.event = event;
+            unsafe { (self.connect_network)(self, &mut token) }
+        });
+        match status {
+            Ok(completion) if token.result_code == Ieee80211ConnectResultCode::SUCCESS => {
+                Ok(completion)
+            }
+            Ok(_) => Err(Error::new(Status::PROTOCOL_ERROR, Some(token.result_code))),
+            Err(err) => Err(Error::new(err.status(), None)),
+        }
+    }
+
+    /// Disconnects the interface from its network, and waits for the
+    /// disconnection to complete.
+    ///
+    /// # Errors
+    ///
+    /// - `NotReady` if the interface is busy
+    /// - `NotFound` if the interface is not connected
+    pub fn disconnect_network(&self, bt: &BootServices) -> Result {
+        let mut token = DisconnectNetworkToken {
+            event: Event::null(),
+            status: PENDING,
+        };
+        complete(bt, ptr::addr_of!(token.status), |event| {
+            token.event = event;
+            unsafe { (self.disconnect_network)(self, &mut token) }
+        })
+    }
+}
+
+/// Status of the tokens whose operation is still in progress
+///
+/// The tokens must be signaled with a notification event, which cannot be
+/// waited for, so their status is polled instead.
+const PENDING: Status = Status::NOT_READY;
+
+/// Issues an operation with a new notification event, and waits for the
+/// firmware to update the status of its token
+fn complete(
+    bt: &BootServices,
+    status: *const Status,
+    issue: impl FnOnce(Event) -> Status,
+) -> Result {
+    fn ignore(_: Event) {}
+
+    let new_event =
+        unsafe { bt.create_event(EventType::NOTIFY_SIGNAL, Tpl::CALLBACK, Some(ignore)) }?.log();
+    let issued = issue(new_event);
+    if issued.is_success() {
+        while unsafe { ptr::read_volatile(status) } == PENDING {
+            bt.stall(10_000);
+        }
+    }
+    unsafe { bt.close_event(new_event) }
+        .expect_success("Failed to close a wireless connection event");
+    if issued.is_success() {
+        unsafe { ptr::read_volatile(status) }.into()
+    } else {
+        issued.into()
+    }
+}
+
+/// SSID of a wireless network, of at most 32 bytes.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+#[repr(C)]
+pub struct Ieee80211Ssid {
+    ssid_len: u8,
+    ssid: [u8; 32],
+}
+
+impl Ieee80211Ssid {
+    /// Creates an SSID from its bytes, or returns `None` if it is longer
+    /// than 32 bytes.
+    pub fn new(ssid: &[u8]) -> Option<Self> {
+        let mut this = Self::default();
+        this.ssid.get_mut(..ssid.len())?.copy_from_slice(ssid);
+        this.ssid_len = ssid.len() as u8;
+        Some(this)
+    }
+
+    /// Bytes of the SSID, usually an UTF-8 string.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.ssid[..(self.ssid_len as usize).min(self.ssid.len())]
+    }
+}
+
+/// Security suite of a wireless network, identified by its organization and
+/// its type within the organization.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(C)]
+pub struct Ieee80211SuiteSelector {
+    /// Identifier of the organization defining the suite.
+    pub oui: [u8; 3],
+    /// Type of the suite.
+    pub suite_type: u8,
+}
+
+impl Ieee80211SuiteSelector {
+    /// Key management with 802.1X authentication.
+    pub const AKM_8021X: Self = Self::ieee(1);
+    /// Key management with a pre-shared key, as used by WPA2-Personal.
+    pub const AKM_PSK: Self = Self::ieee(2);
+    /// Key management with simultaneous authentication of equals, as used by
+    /// WPA3-Personal.
+    pub const AKM_SAE: Self = Self::ieee(8);
+    /// TKIP cipher.
+    pub const CIPHER_TKIP: Self = Self::ieee(2);
+    /// CCMP cipher, with 128-bit AES.
+    pub const CIPHER_CCMP: Self = Self::ieee(4);
+
+    /// Suite defined by the IEEE 802.11 standard
+    const fn ieee(suite_type: u8) -> Self {
+        Self {
+            oui: [0x00, 0x0f, 0xac],
+            suite_type,
+        }
+    }
+}
+
+/// List of security suites, as returned by the firmware
+#[repr(C)]
+struct SuiteList {
+    count: u16,
+    list: [Ieee80211SuiteSelector; 0],
+}
+
+impl SuiteList {
+    /// Suites of the list at `list`, which may be null
+    unsafe fn suites<'a>(list: *const SuiteList) -> &'a [Ieee80211SuiteSelector] {
+        match list.as_ref() {
+            Some(list) => slice::from_raw_parts(list.list.as_ptr(), list.count as usize),
+            None => &[],
+        }
+    }
+}
+
+newtype_enum! {
+    /// Type of a wireless network.
+    pub enum Ieee80211BssType: u32 => {
+        /// Network of stations connected through an access point.
+        INFRASTRUCTURE = 0,
+        /// Network of stations connected directly to each other.
+        INDEPENDENT    = 1,
+        /// Mesh network.
+        MESH           = 2,
+        /// Any type of network.
+        ANY            = 3,
+    }
+}
+
+newtype_enum! {
+    /// Outcome of a connection to a wireless network.
+    pub enum Ieee80211ConnectResultCode: u32 => {
+        /// The connection succeeded.
+        SUCCESS                   = 0,
+        /// The access point refused the connection.
+        REFUSED                   = 1,
+        /// The connection failed.
+        FAILED                    = 2,
+        /// The connection did not complete in time.
+        FAILURE_TIMEOUT           = 3,
+        /// The connection failed for another reason.
+        FAILED_REASON_UNSPECIFIED = 4,
+    }
+}
+
+/// Wireless network found by `WirelessMacConnectionII::get_networks()`.
+#[repr(C)]
+pub struct Ieee80211Network {
+    bss_type: Ieee80211BssType,
+    ssid: Ieee80211Ssid,
+    akm_suite: *const SuiteList,
+    cipher_suite: *const SuiteList,
+}
+
+impl Ieee80211Network {
+    /// Type of the network.
+    pub fn bss_type(&self) -> Ieee80211BssType {
+        self.bss_type
+    }
+
+    /// SSID of the network.
+    pub fn ssid(&self) -> &Ieee80211Ssid {
+        &self.ssid
+    }
+
+    /// Key management suites supported by the network, which are empty for
+    /// open networks.
+    pub fn akm_suites(&self) -> &[Ieee80211SuiteSelector] {
+        unsafe { SuiteList::suites(self.akm_suite) }
+    }
+
+    /// Cipher suites supported by the network.
+    pub fn cipher_suites(&self) -> &[Ieee80211SuiteSelector] {
+        unsafe { SuiteList::suites(self.cipher_suite) }
+    }
+}
+
+/// Wireless network found by a scan, along with the quality of its signal.
+#[repr(C)]
+pub struct Ieee80211NetworkDescription {
+    network: Ieee80211Network,
+    network_quality: u8,
+}
+
+impl Ieee80211NetworkDescription {
+    /// The network.
+    pub fn network(&self) -> &Ieee80211Network {
+        &self.network
+    }
+
+    /// Quality of the signal of the network, from 0 to 100.
+    pub fn quality(&self) -> u8 {
+        self.network_quality
+    }
+}
+
+/// Wireless networks found by `WirelessMacConnectionII::get_networks()`.
+///
+/// The results are freed when this is dropped.
+pub struct Ieee80211Networks<'boot> {
+    bt: &'boot BootServices,
+    result: *mut GetNetworksResult,
+}
+
+impl Ieee80211Networks<'_> {
+    /// The networks found, with their signal quality.
+    pub fn networks(&self) -> &[Ieee80211NetworkDescription] {
+        let result = unsafe { &*self.result };
+        unsafe {
+            slice::from_raw_parts(
+                result.network_desc.as_ptr(),
+                result.num_of_network_desc as usize,
+            )
+        }
+    }
+
+    /// The network named `ssid` with the best signal, if any.
+    pub fn find(&self, ssid: &[u8]) -> Option<&Ieee80211Network> {
+        self.networks()
+            .iter()
+            .filter(|desc| desc.network.ssid.as_bytes() == ssid)
+            .max_by_key(|desc| desc.network_quality)
+            .map(|desc| &desc.network)
+    }
+}
+
+impl Drop for Ieee80211Networks<'_> {
+    fn drop(&mut self) {
+        // The suite lists of the networks may point into the results, so
+        // only the results are freed. Nothing more can be done if this fails.
+        let _ = self.bt.free_pool(self.result as *mut u8);
+    }
+}
+
+/// Networks to probe for, as expected by the firmware
+#[repr(C)]
+struct GetNetworksData {
+    num_of_ssid: u32,
+    ssid_list: [Ieee80211Ssid; 1],
+}
+
+/// Networks found by a scan, as returned by the firmware
+#[repr(C)]
+struct GetNetworksResult {
+    num_of_network_desc: u8,
+    network_desc: [Ieee80211NetworkDescription; 0],
+}
+
+#[repr(C)]
+struct GetNetworksToken {
+    event: Event,
+    status: Status,
+    data: *const GetNetworksData,
+    result: *mut GetNetworksResult,
+}
+
+#[repr(C)]
+struct ConnectNetworkData {
+    network: *const Ieee80211Network,
+    failure_timeout: u32,
+}
+
+#[repr(C)]
+struct ConnectNetworkToken {
+    event: Event,
+    status: Status,
+    data: *const ConnectNetworkData,
+    result_code: Ieee80211ConnectResultCode,
+}
+
+#[repr(C)]
+struct DisconnectNetworkToken {
+    event: Event,
+    status: Status,
+}
+
+/// Time after which `connect()` gives up, in seconds
+#[cfg(feature = "exts")]
+const CONNECT_TIMEOUT: u32 = 20;
+
+/// Connects the first wireless interface in range of the network named
+/// `ssid`, and returns the handle of this interface.
+///
+/// The network is protected with the pre-shared key passphrase `psk`, or
+/// open if it is `None`. If the network refuses the connection, the reason
+/// will be returned as part of the error.
+///
+/// # Errors
+///
+/// - `Unsupported` if there is no wireless interface
+/// - `NotFound` if the network was not found
+/// - `InvalidParameter` if the SSID or the passphrase is not valid
+/// - the errors of `WirelessMacConnectionII::connect_network()`
+#[cfg(feature = "exts")]
+pub fn connect(
+    bt: &BootServices,
+    ssid: &str,
+    psk: Option<&str>,
+) -> Result<Handle, Option<Ieee80211ConnectResultCode>> {
+    let no_data = |err: Error| Error::new(err.status(), None);
+    let invalid_parameter = || Error::new(Status::INVALID_PARAMETER, None);
+
+    let target = Ieee80211Ssid::new(ssid.as_bytes()).ok_or_else(invalid_parameter)?;
+    let psk = match psk {
+        Some(psk) if !(8..=63).contains(&psk.len()) || !psk.is_ascii() => {
+            return Err(invalid_parameter())
+        }
+        Some(psk) => Some(psk.bytes().chain([0]).collect::<Vec<u8>>()),
+        None => None,
+    };
+
+    let handles = bt
+        .find_handles::<WirelessMacConnectionII>()
+        .map_err(|_| Error::new(Status::UNSUPPORTED, None))?
+        .log();
+    for handle in handles {
+        let wmc = bt.handle_protocol::<WirelessMacConnectionII>(handle);
+        let wmc = unsafe { &*wmc.map_err(no_data)?.log().get() };
+
+        let networks = match wmc.get_networks(bt, Some(&target)) {
+            Ok(networks) => networks.log(),
+            Err(_) => continue,
+        };
+        let network = match networks.find(target.as_bytes()) {
+            Some(network) => network,
+            None => continue,
+        };
+
+        if let Some(psk) = &psk {
+            let supplicant = bt.handle_protocol::<Supplicant>(handle);
+            let supplicant = unsafe { &*supplicant.map_err(no_data)?.log().get() };
+            let akm = network
+                .akm_suites()
+                .first()
+                .copied()
+                .unwrap_or(Ieee80211SuiteSelector::AKM_PSK);
+            let cipher = network
+                .cipher_suites()
+                .first()
+                .copied()
+                .unwrap_or(Ieee80211SuiteSelector::CIPHER_CCMP);
+            let psk = unsafe { CStr8::from_bytes_with_nul_unchecked(psk) };
+            supplicant.set_akm_suite(akm).map_err(no_data)?.log();
+            supplicant
+                .set_pairwise_cipher_suite(cipher)
+                .map_err(no_data)?
+                .log();
+            supplicant.set_psk_password(psk).map_err(no_data)?.log();
+            supplicant.set_target_ssid(&target).map_err(no_data)?.log();
+        }
+
+        wmc.connect_network(bt, network, CONNECT_TIMEOUT)?.log();
+        return Ok(handle.into());
+    }
+    Err(Error::new(Status::NOT_FOUND, None))
+}
@@ -201,6 +201,79 @@ impl MpServices {
         .into()
     }
 
+    /// Executes a closure on all APs in blocking mode.
+    ///
+    /// The closure is called with the number of the processor running it.
+    /// Unless `single_thread` is set, the APs run it concurrently, which is
+    /// why it must be `Sync`. Like the procedures of `startup_all_aps()`, it
+    /// must not use the UEFI services, which may not be called from an AP.
+    ///
+    /// The APs are stopped if the closure does not return before `timeout`
+    /// on all of them, in which case `Timeout` is returned.
+    ///
+    /// # Errors
+    ///
+    /// - `NotStarted` if there is no enabled AP
+    /// - `Timeout` if the closure did not return in time on all APs
+    pub fn startup_all_aps_with<F>(
+        &self,
+        single_thread: bool,
+        procedure: &F,
+        timeout: Option<Duration>,
+    ) -> Result
+    where
+        F: Fn(usize) + Sync,
+    {
+        extern "efiapi" fn trampoline<F: Fn(usize) + Sync>(arg: *mut c_void) {
+            let context = unsafe { &*(arg as *const ClosureContext<F>) };
+            let processor_number = context.processor_number();
+            let procedure = unsafe { &*context.procedure };
+            procedure(processor_number); // SAFETY: Aborting panics are assumed here
+        }
+
+        let mut context = ClosureContext {
+            mp_services: self,
+            procedure: procedure as *const F as *mut F,
+        };
+        let argument = &mut context as *mut ClosureContext<F> as *mut c_void;
+        self.startup_all_aps(single_thread, trampoline::<F>, argument, timeout)
+    }
+
+    /// Executes a closure on a specific AP in blocking mode.
+    ///
+    /// The caveats of `startup_all_aps_with()` also apply here. Since a single
+    /// AP runs the closure while the BSP waits, the closure does not need to
+    /// be `Sync`.
+    ///
+    /// # Errors
+    ///
+    /// - `NotFound` if there is no such AP
+    /// - `InvalidParameter` if the AP is disabled
+    /// - `Timeout` if the closure did not return in time
+    pub fn startup_this_ap_with<F>(
+        &self,
+        processor_number: usize,
+        procedure: &mut F,
+        timeout: Option<Duration>,
+    ) -> Result
+    where
+        F: FnMut(usize) + Send,
+    {
+        extern "efiapi" fn trampoline<F: FnMut(usize) + Send>(arg: *mut c_void) {
+            let context = unsafe { &*(arg as *const ClosureContext<F>) };
+            let processor_number = context.processor_number();
+            let procedure = unsafe { &mut *context.procedure };
+            procedure(processor_number); // SAFETY: Aborting panics are assumed here
+        }
+
+        let mut context = ClosureContext {
+            mp_services: self,
+            procedure,
+        };
+        let argument = &mut context as *mut ClosureContext<F> as *mut c_void;
+        self.startup_this_ap(processor_number, trampoline::<F>, argument, timeout)
+    }
+
     /// Switches the requested AP to be the BSP from that point onward.
     pub fn switch_bsp(&self, processor_number: usize, enable_old_bsp: bool) -> Result {
         (self.switch_bsp)(self, processor_number, enable_old_bsp).into()
@@ -234,3 +307,19 @@ impl MpServices {
         (self.who_am_i)(self, &mut processor_number).into_with_val(|| processor_number)
     }
 }
+
+/// Argument of the procedures running the closures on the APs
+struct ClosureContext<'a, F> {
+    mp_services: &'a MpServices,
+    procedure: *mut F,
+}
+
+impl<F> ClosureContext<'_, F> {
+    /// Number of the processor running the procedure
+    fn processor_number(&self) -> usize {
+        // This cannot fail when called from an AP
+        let mut processor_number = 0;
+        let _ = (self.mp_services.who_am_i)(self.mp_services, &mut processor_number);
+        processor_number
+    }
+}
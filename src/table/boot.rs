@@ -2,17 +2,25 @@
 
 use super::Header;
 use crate::data_types::Align;
-use crate::proto::{device_path::DevicePath, Protocol};
 #[cfg(feature = "exts")]
-use crate::proto::{loaded_image::LoadedImage, media::fs::SimpleFileSystem};
-use crate::{Char16, Event, Guid, Handle, Result, Status};
+use crate::proto::media::fs::SimpleFileSystem;
+use crate::proto::{device_path::DevicePath, loaded_image::LoadedImage, Protocol};
 #[cfg(feature = "exts")]
-use alloc_api::vec::Vec;
+use crate::result::Error;
+use crate::{CStr16, Char16, Event, Guid, Handle, Result, Status};
+#[cfg(feature = "exts")]
+use alloc_api::{boxed::Box, sync::Arc, vec::Vec};
 use bitflags::bitflags;
 use core::cell::UnsafeCell;
 use core::ffi::c_void;
+#[cfg(feature = "exts")]
+use core::fmt;
 use core::mem::{self, MaybeUninit};
-use core::ptr;
+use core::ops::{Deref, DerefMut};
+#[cfg(feature = "exts")]
+use core::sync::atomic::{AtomicPtr, Ordering};
+use core::time::Duration;
+use core::{ptr, slice};
 
 /// Contains pointers to all of the boot services.
 #[repr(C)]
@@ -56,18 +64,30 @@ pub struct BootServices {
         events: *mut Event,
         out_index: *mut usize,
     ) -> Status,
-    signal_event: usize,
-    close_event: usize,
-    check_event: usize,
+    signal_event: unsafe extern "efiapi" fn(event: Event) -> Status,
+    close_event: unsafe extern "efiapi" fn(event: Event) -> Status,
+    check_event: unsafe extern "efiapi" fn(event: Event) -> Status,
 
     // Protocol handlers
-    install_protocol_interface: usize,
-    reinstall_protocol_interface: usize,
-    uninstall_protocol_interface: usize,
+    install_protocol_interface: unsafe extern "efiapi" fn(
+        handle: &mut Handle,
+        guid: &Guid,
+        interface_type: InterfaceType,
+        interface: *mut c_void,
+    ) -> Status,
+    reinstall_protocol_interface: unsafe extern "efiapi" fn(
+        handle: Handle,
+        protocol: &Guid,
+        old_interface: *mut c_void,
+        new_interface: *mut c_void,
+    ) -> Status,
+    uninstall_protocol_interface:
+        unsafe extern "efiapi" fn(handle: Handle, guid: &Guid, interface: *mut c_void) -> Status,
     handle_protocol:
         extern "efiapi" fn(handle: Handle, proto: &Guid, out_proto: &mut *mut c_void) -> Status,
     _reserved: usize,
-    register_protocol_notify: usize,
+    register_protocol_notify:
+        extern "efiapi" fn(protocol: &Guid, event: Event, registration: &mut *mut c_void) -> Status,
     locate_handle: unsafe extern "efiapi" fn(
         search_ty: i32,
         proto: *const Guid,
@@ -80,7 +100,8 @@ pub struct BootServices {
         device_path: &mut *mut DevicePath,
         out_handle: *mut Handle,
     ) -> Status,
-    install_configuration_table: usize,
+    install_configuration_table:
+        extern "efiapi" fn(guid_entry: &Guid, table_ptr: *const c_void) -> Status,
 
     // Image services
     load_image: unsafe extern "efiapi" fn(
@@ -96,13 +117,18 @@ pub struct BootServices {
         exit_data_size: *mut usize,
         exit_data: &mut *mut Char16,
     ) -> Status,
-    exit: usize,
+    exit: unsafe extern "efiapi" fn(
+        image_handle: Handle,
+        exit_status: Status,
+        exit_data_size: usize,
+        exit_data: *mut Char16,
+    ) -> Status,
     unload_image: extern "efiapi" fn(image_handle: Handle) -> Status,
     exit_boot_services:
         unsafe extern "efiapi" fn(image_handle: Handle, map_key: MemoryMapKey) -> Status,
 
     // Misc services
-    get_next_monotonic_count: usize,
+    get_next_monotonic_count: extern "efiapi" fn(count: &mut u64) -> Status,
     stall: extern "efiapi" fn(microseconds: usize) -> Status,
     set_watchdog_timer: unsafe extern "efiapi" fn(
         timeout: usize,
@@ -112,17 +138,50 @@ pub struct BootServices {
     ) -> Status,
 
     // Driver support services
-    connect_controller: usize,
-    disconnect_controller: usize,
+    connect_controller: unsafe extern "efiapi" fn(
+        controller: Handle,
+        driver_image: *const Handle,
+        remaining_device_path: *const DevicePath,
+        recursive: bool,
+    ) -> Status,
+    disconnect_controller:
+        extern "efiapi" fn(controller: Handle, driver_image: Handle, child: Handle) -> Status,
 
     // Protocol open / close services
-    open_protocol: usize,
-    close_protocol: usize,
-    open_protocol_information: usize,
+    open_protocol: extern "efiapi" fn(
+        handle: Handle,
+        protocol: &Guid,
+        interface: *mut *mut c_void,
+        agent_handle: Handle,
+        controller_handle: Handle,
+        attributes: u32,
+    ) -> Status,
+    close_protocol: extern "efiapi" fn(
+        handle: Handle,
+        protocol: &Guid,
+        agent_handle: Handle,
+        controller_handle: Handle,
+    ) -> Status,
+    open_protocol_information: unsafe extern "efiapi" fn(
+        handle: Handle,
+        protocol: &Guid,
+        entry_buffer: &mut *mut OpenProtocolInformationEntry,
+        entry_count: &mut usize,
+    ) -> Status,
 
     // Library services
-    protocols_per_handle: usize,
-    locate_handle_buffer: usize,
+    protocols_per_handle: unsafe extern "efiapi" fn(
+        handle: Handle,
+        protocol_buffer: &mut *mut *const Guid,
+        protocol_buffer_count: &mut usize,
+    ) -> Status,
+    locate_handle_buffer: unsafe extern "efiapi" fn(
+        search_ty: i32,
+        proto: *const Guid,
+        key: *mut c_void,
+        no_handles: &mut usize,
+        buf: &mut *mut Handle,
+    ) -> Status,
     locate_protocol: extern "efiapi" fn(
         proto: &Guid,
         registration: *mut c_void,
@@ -132,14 +191,22 @@ pub struct BootServices {
     uninstall_multiple_protocol_interfaces: usize,
 
     // CRC services
-    calculate_crc32: usize,
+    calculate_crc32:
+        unsafe extern "efiapi" fn(data: *const c_void, data_size: usize, crc32: &mut u32) -> Status,
 
     // Misc services
     copy_mem: unsafe extern "efiapi" fn(dest: *mut u8, src: *const u8, len: usize),
     set_mem: unsafe extern "efiapi" fn(buffer: *mut u8, len: usize, value: u8),
 
     // New event functions (UEFI 2.0 or newer)
-    create_event_ex: usize,
+    create_event_ex: unsafe extern "efiapi" fn(
+        ty: u32,
+        notify_tpl: Tpl,
+        notify_func: Option<EventNotifyFn>,
+        notify_ctx: *mut c_void,
+        event_group: *const Guid,
+        event: *mut Event,
+    ) -> Status,
 }
 
 impl BootServices {
@@ -188,11 +255,51 @@ impl BootServices {
         (self.free_pages)(addr, count).into()
     }
 
-    /// Retrieves the size, in bytes, of the current memory map.
+    /// Allocates zeroed memory pages like `allocate_pages()`, and frees them
+    /// when the returned `PagesBox` is dropped.
+    ///
+    /// # Errors
+    ///
+    /// - `OutOfResources` if the pages cannot be allocated
+    /// - `NotFound` if the requested pages do not exist
+    pub fn allocate_pages_scoped(
+        &self,
+        ty: AllocateType,
+        mem_ty: MemoryType,
+        count: usize,
+    ) -> Result<PagesBox<'_>> {
+        let address = self.allocate_pages(ty, mem_ty, count)?.log();
+        let pages = PagesBox {
+            boot_services: self,
+            address,
+            count,
+        };
+        unsafe { ptr::write_bytes(address as *mut u8, 0, pages.len()) };
+        Ok(pages.into())
+    }
+
+    /// Allocates zeroed memory pages at `address`, and frees them when the
+    /// returned `PagesBox` is dropped.
+    ///
+    /// # Errors
+    ///
+    /// - the errors of `allocate_pages_scoped()`
+    pub fn allocate_pages_scoped_at(
+        &self,
+        address: usize,
+        mem_ty: MemoryType,
+        count: usize,
+    ) -> Result<PagesBox<'_>> {
+        self.allocate_pages_scoped(AllocateType::Address(address), mem_ty, count)
+    }
+
+    /// Retrieves the recommended size, in bytes, of a buffer for the current
+    /// memory map.
     ///
     /// A buffer of this size will be capable of holding the whole current memory map,
-    /// including padding. Note, however, that allocations will increase the size of the
-    /// memory map, therefore it is better to allocate some extra space.
+    /// including padding. Since allocations increase the size of the memory map, it
+    /// includes room for a few more descriptors, e.g. those added by allocating the
+    /// buffer itself.
     pub fn memory_map_size(&self) -> usize {
         let mut map_size = 0;
         let mut map_key = MemoryMapKey(0);
@@ -210,7 +317,39 @@ impl BootServices {
         };
         assert_eq!(status, Status::BUFFER_TOO_SMALL);
 
-        map_size
+        map_size + MEMORY_MAP_EXTRA_ENTRIES * entry_size
+    }
+
+    /// Retrieves the current memory map into a buffer allocated from a pool,
+    /// which is freed when the returned map is dropped.
+    ///
+    /// The buffer is allocated again if the memory map grew too much in the
+    /// meantime.
+    ///
+    /// # Errors
+    ///
+    /// - `OutOfResources` if the buffer cannot be allocated
+    pub fn memory_map_alloc(&self, mem_ty: MemoryType) -> Result<MemoryMap<'_>> {
+        loop {
+            let size = self.memory_map_size();
+            let pool = self.allocate_pool(mem_ty, size)?.log();
+            // Pool allocations are aligned to 8 bytes, like the descriptors
+            let buffer = unsafe { slice::from_raw_parts_mut(pool, size) };
+            match self.memory_map(buffer) {
+                Ok(completion) => {
+                    return Ok(completion.map(|mut map| {
+                        map.pool = Some(self);
+                        map
+                    }))
+                }
+                Err(err) => {
+                    let _ = self.free_pool(pool);
+                    if err.status() != Status::BUFFER_TOO_SMALL {
+                        return Err(err);
+                    }
+                }
+            }
+        }
     }
 
     /// Retrieves the current memory map.
@@ -220,18 +359,13 @@ impl BootServices {
     ///
     /// The buffer must be aligned like a `MemoryDescriptor`.
     ///
-    /// The returned key is a unique identifier of the current configuration of memory.
-    /// Any allocations or such will change the memory map's key.
+    /// The key of the returned map is a unique identifier of the current
+    /// configuration of memory. Any allocations or such will change the memory
+    /// map's key.
     ///
     /// If you want to store the resulting memory map without having to keep
-    /// the buffer around, you can use `.copied().collect()` on the iterator.
-    pub fn memory_map<'buf>(
-        &self,
-        buffer: &'buf mut [u8],
-    ) -> Result<(
-        MemoryMapKey,
-        impl ExactSizeIterator<Item = &'buf MemoryDescriptor> + Clone,
-    )> {
+    /// the buffer around, you can use `.entries().copied().collect()` on it.
+    pub fn memory_map<'buf>(&self, buffer: &'buf mut [u8]) -> Result<MemoryMap<'buf>> {
         let mut map_size = buffer.len();
         MemoryDescriptor::assert_aligned(buffer);
         #[allow(clippy::cast_ptr_alignment)]
@@ -255,15 +389,13 @@ impl BootServices {
                 &mut entry_version,
             )
         }
-        .into_with_val(move || {
-            let len = map_size / entry_size;
-            let iter = MemoryMapIter {
-                buffer,
-                entry_size,
-                index: 0,
-                len,
-            };
-            (map_key, iter)
+        .into_with_val(move || MemoryMap {
+            buffer,
+            pool: None,
+            key: map_key,
+            desc_size: entry_size,
+            desc_version: entry_version,
+            len: map_size / entry_size,
         })
     }
 
@@ -278,6 +410,44 @@ impl BootServices {
         (self.free_pool)(addr).into()
     }
 
+    /// Moves `value` to memory allocated from a pool, which is freed when the
+    /// returned `PoolBox` is dropped.
+    ///
+    /// This works without the global allocator of the `alloc` feature.
+    ///
+    /// # Errors
+    ///
+    /// - `OutOfResources` if the memory cannot be allocated
+    /// - `Unsupported` if `T` is aligned to more than the 8 bytes which pool
+    ///   allocations are aligned to
+    pub fn allocate_pool_box<T>(&self, mem_ty: MemoryType, value: T) -> Result<PoolBox<'_, T>> {
+        if mem::align_of::<T>() > 8 {
+            return Err(Status::UNSUPPORTED.into());
+        }
+        let ptr = self
+            .allocate_pool(mem_ty, mem::size_of::<T>().max(1))?
+            .log() as *mut T;
+        unsafe {
+            ptr.write(value);
+            Ok(PoolBox::from_raw(self, ptr).into())
+        }
+    }
+
+    /// Allocates a zeroed buffer of `len` bytes from a pool, which is freed
+    /// when the returned `PoolBox` is dropped.
+    ///
+    /// # Errors
+    ///
+    /// - `OutOfResources` if the memory cannot be allocated
+    pub fn allocate_pool_slice(&self, mem_ty: MemoryType, len: usize) -> Result<PoolBox<'_, [u8]>> {
+        let ptr = self.allocate_pool(mem_ty, len.max(1))?.log();
+        unsafe {
+            ptr::write_bytes(ptr, 0, len);
+            let ptr = ptr::slice_from_raw_parts_mut(ptr, len);
+            Ok(PoolBox::from_raw(self, ptr).into())
+        }
+    }
+
     /// Creates an event
     ///
     /// This function creates a new event of the specified type and returns it.
@@ -298,6 +468,31 @@ impl BootServices {
         event_ty: EventType,
         notify_tpl: Tpl,
         notify_fn: Option<fn(Event)>,
+    ) -> Result<Event> {
+        self.create_event_ex(event_ty, notify_tpl, notify_fn, None)
+    }
+
+    /// Creates an event, which may belong to an event group.
+    ///
+    /// This works like `create_event()`, but when an event of the group
+    /// `event_group` is signaled, all the events of the group are. The
+    /// standard groups, such as `EVENT_GROUP_READY_TO_BOOT`, are signaled by
+    /// the firmware when the platform reaches the matching stage.
+    ///
+    /// The event types `SIGNAL_EXIT_BOOT_SERVICES` and
+    /// `SIGNAL_VIRTUAL_ADDRESS_CHANGE` cannot be used with a group: the groups
+    /// `EVENT_GROUP_EXIT_BOOT_SERVICES` and `EVENT_GROUP_VIRTUAL_ADDRESS_CHANGE`
+    /// replace them, with `EventType::NOTIFY_SIGNAL`.
+    ///
+    /// # Safety
+    ///
+    /// See `create_event()`.
+    pub unsafe fn create_event_ex(
+        &self,
+        event_ty: EventType,
+        notify_tpl: Tpl,
+        notify_fn: Option<fn(Event)>,
+        event_group: Option<&Guid>,
     ) -> Result<Event> {
         // Prepare storage for the output Event
         let mut event = MaybeUninit::<Event>::uninit();
@@ -317,16 +512,41 @@ impl BootServices {
             .unwrap_or((None, ptr::null_mut()));
 
         // Now we're ready to call UEFI
-        (self.create_event)(
+        self.create_event_raw(
             event_ty,
             notify_tpl,
             notify_func,
             notify_ctx,
+            event_group,
             event.as_mut_ptr(),
         )
         .into_with_val(|| event.assume_init())
     }
 
+    /// Creates an event with `CreateEventEx()` if it belongs to a group, and
+    /// `CreateEvent()` otherwise, which is available on older firmware
+    unsafe fn create_event_raw(
+        &self,
+        event_ty: EventType,
+        notify_tpl: Tpl,
+        notify_func: Option<EventNotifyFn>,
+        notify_ctx: *mut c_void,
+        event_group: Option<&Guid>,
+        event: *mut Event,
+    ) -> Status {
+        match event_group {
+            Some(event_group) => (self.create_event_ex)(
+                event_ty.bits(),
+                notify_tpl,
+                notify_func,
+                notify_ctx,
+                event_group,
+                event,
+            ),
+            None => (self.create_event)(event_ty, notify_tpl, notify_func, notify_ctx, event),
+        }
+    }
+
     /// Stops execution until an event is signaled
     ///
     /// This function must be called at priority level `Tpl::APPLICATION`. If an
@@ -379,6 +599,225 @@ impl BootServices {
         unsafe { (self.set_timer)(event, ty, time) }.into()
     }
 
+    /// Sets a `EventType::TIMER` event to be signaled every `period`.
+    ///
+    /// The period is rounded up to the 100ns units of the firmware, and a zero
+    /// period signals the event on every timer tick.
+    pub fn set_timer_periodic(&self, event: Event, period: Duration) -> Result {
+        self.set_timer(event, TimerTrigger::Periodic(hundreds_ns(period)))
+    }
+
+    /// Sets a `EventType::TIMER` event to be signaled once, after `delay`.
+    ///
+    /// The delay is rounded up to the 100ns units of the firmware, and a zero
+    /// delay signals the event on the next timer tick.
+    pub fn set_timer_relative(&self, event: Event, delay: Duration) -> Result {
+        self.set_timer(event, TimerTrigger::Relative(hundreds_ns(delay)))
+    }
+
+    /// Creates a `Timer`, whose event is closed when it is dropped.
+    ///
+    /// The timer is not set: it must be started with `Timer::set_relative()`
+    /// or `Timer::set_periodic()`.
+    pub fn create_timer(&self) -> Result<Timer<'_>> {
+        let event = unsafe { self.create_event(EventType::TIMER, Tpl::APPLICATION, None) }?;
+        Ok(event.map(|event| Timer {
+            boot_services: self,
+            event,
+        }))
+    }
+
+    /// Places an event in the signaled state.
+    ///
+    /// If the event has a notification function, it is queued to run.
+    pub fn signal_event(&self, event: Event) -> Result {
+        unsafe { (self.signal_event)(event) }.into()
+    }
+
+    /// Closes an event, removing its timer and pending notification.
+    ///
+    /// # Safety
+    ///
+    /// The event must not be used after it was closed, which includes closing
+    /// it again. In particular, the events owned by a `Timer`, a
+    /// `ClosureEvent` or a `ProtocolRegistration` must not be closed, since
+    /// they are closed when these are dropped.
+    pub unsafe fn close_event(&self, event: Event) -> Result {
+        (self.close_event)(event).into()
+    }
+
+    /// Checks whether an event is in the signaled state, without waiting.
+    ///
+    /// If the event is signaled, its signaled state is cleared. Otherwise, its
+    /// notification function, if any, is queued like in `wait_for_event()`.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidParameter` if the event is of type `EventType::NOTIFY_SIGNAL`
+    pub fn check_event(&self, event: Event) -> Result<bool> {
+        match unsafe { (self.check_event)(event) } {
+            Status::NOT_READY => Ok(false.into()),
+            status => status.into_with_val(|| true),
+        }
+    }
+
+    /// Installs a protocol interface on a device handle.
+    ///
+    /// If `handle` is `None`, a new handle is created and returned. Otherwise
+    /// the interface is added to the existing handle, which is returned.
+    ///
+    /// # Safety
+    ///
+    /// `interface` must point to a valid implementation of the protocol
+    /// identified by `protocol`, which must remain valid until the interface
+    /// is uninstalled.
+    ///
+    /// # Errors
+    /// * `uefi::Status::OUT_OF_RESOURCES`   Space for a new handle could not be allocated
+    /// * `uefi::Status::INVALID_PARAMETER`  The protocol is already installed on the handle
+    pub unsafe fn install_protocol_interface(
+        &self,
+        handle: Option<Handle>,
+        protocol: &Guid,
+        interface: *mut c_void,
+    ) -> Result<Handle> {
+        let mut handle = handle.unwrap_or_else(|| Handle::uninitialized());
+        (self.install_protocol_interface)(
+            &mut handle,
+            protocol,
+            InterfaceType::NATIVE_INTERFACE,
+            interface,
+        )
+        .into_with_val(|| handle)
+    }
+
+    /// Replaces a protocol interface of a device handle by another one.
+    ///
+    /// The drivers using the old interface are disconnected from the handle,
+    /// and reconnected so that they use the new one, and the notifications
+    /// registered for the protocol are signaled. `new_interface` may also be
+    /// equal to `old_interface`, in order to signal that its implementation
+    /// changed.
+    ///
+    /// # Safety
+    ///
+    /// Like for `uninstall_protocol_interface()`, no one may be using the old
+    /// interface anymore, unless it is the same as the new one. The new one
+    /// must remain valid until it is uninstalled, like for
+    /// `install_protocol_interface()`.
+    ///
+    /// # Errors
+    /// * `uefi::Status::NOT_FOUND`          The old interface was not found on the handle
+    /// * `uefi::Status::ACCESS_DENIED`      The old interface is still used by a driver
+    pub unsafe fn reinstall_protocol_interface(
+        &self,
+        handle: Handle,
+        protocol: &Guid,
+        old_interface: *mut c_void,
+        new_interface: *mut c_void,
+    ) -> Result {
+        (self.reinstall_protocol_interface)(handle, protocol, old_interface, new_interface).into()
+    }
+
+    /// Removes a protocol interface from a device handle.
+    ///
+    /// Once the last interface of a handle is removed, the handle is freed.
+    ///
+    /// # Safety
+    ///
+    /// The caller is responsible for making sure that no one is using the
+    /// interface anymore. The firmware will refuse to uninstall it if it has
+    /// been opened by a driver, but not if e.g. a pointer to it was obtained
+    /// through `handle_protocol()`.
+    ///
+    /// # Errors
+    /// * `uefi::Status::NOT_FOUND`          The interface was not found on the handle
+    /// * `uefi::Status::ACCESS_DENIED`      The interface is still used by a driver
+    pub unsafe fn uninstall_protocol_interface(
+        &self,
+        handle: Handle,
+        protocol: &Guid,
+        interface: *mut c_void,
+    ) -> Result {
+        (self.uninstall_protocol_interface)(handle, protocol, interface).into()
+    }
+
+    /// Installs several protocol interfaces on a device handle, all at once.
+    ///
+    /// Like `install_protocol_interface()`, a new handle is created if
+    /// `handle` is `None`. If any interface cannot be installed, those which
+    /// were are uninstalled again, so that the handle is left unchanged.
+    ///
+    /// The firmware's `InstallMultipleProtocolInterfaces()` is variadic, and
+    /// therefore cannot be called with a slice. This installs the interfaces
+    /// one by one instead, at `Tpl::NOTIFY`, so that no notification sees a
+    /// partial installation, but without checking for duplicate device paths.
+    ///
+    /// # Safety
+    ///
+    /// Each interface must point to a valid implementation of the protocol
+    /// paired with it, which must remain valid until it is uninstalled.
+    ///
+    /// # Errors
+    ///
+    /// - the errors of `install_protocol_interface()`
+    pub unsafe fn install_multiple_protocol_interfaces(
+        &self,
+        handle: Option<Handle>,
+        interfaces: &[(&Guid, *mut c_void)],
+    ) -> Result<Handle> {
+        let _guard = self.raise_tpl(Tpl::NOTIFY);
+        let mut handle = handle;
+        for (installed, &(protocol, interface)) in interfaces.iter().enumerate() {
+            match self.install_protocol_interface(handle, protocol, interface) {
+                Ok(new_handle) => handle = Some(new_handle.log()),
+                Err(err) => {
+                    if let Some(handle) = handle {
+                        for &(protocol, interface) in interfaces[..installed].iter().rev() {
+                            let _ = self.uninstall_protocol_interface(handle, protocol, interface);
+                        }
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        handle
+            .ok_or_else(|| Status::INVALID_PARAMETER.into())
+            .map(Into::into)
+    }
+
+    /// Removes several protocol interfaces from a device handle, all at once.
+    ///
+    /// If any interface cannot be uninstalled, those which were are installed
+    /// again, so that the handle is left unchanged. Like for
+    /// `install_multiple_protocol_interfaces()`, this is done one interface
+    /// at a time, at `Tpl::NOTIFY`.
+    ///
+    /// # Safety
+    ///
+    /// See `uninstall_protocol_interface()`.
+    ///
+    /// # Errors
+    ///
+    /// - the errors of `uninstall_protocol_interface()`
+    pub unsafe fn uninstall_multiple_protocol_interfaces(
+        &self,
+        handle: Handle,
+        interfaces: &[(&Guid, *mut c_void)],
+    ) -> Result {
+        let _guard = self.raise_tpl(Tpl::NOTIFY);
+        for (uninstalled, &(protocol, interface)) in interfaces.iter().enumerate() {
+            if let Err(err) = self.uninstall_protocol_interface(handle, protocol, interface) {
+                // The handle still exists, since this interface is installed
+                for &(protocol, interface) in interfaces[..uninstalled].iter().rev() {
+                    let _ = self.install_protocol_interface(Some(handle), protocol, interface);
+                }
+                return Err(err);
+            }
+        }
+        Ok(().into())
+    }
+
     /// Query a handle for a certain protocol.
     ///
     /// This function attempts to get the protocol implementation of a handle,
@@ -388,6 +827,9 @@ impl BootServices {
     /// provides no mechanism to protect against concurrent usage. Such
     /// protections must be implemented by user-level code, for example via a
     /// global `HashSet`.
+    ///
+    /// The UEFI specification deprecates this function in favor of
+    /// `open_protocol()`, which should be used by drivers in particular.
     pub fn handle_protocol<P: Protocol>(&self, handle: Handle) -> Result<&UnsafeCell<P>> {
         let mut ptr = ptr::null_mut();
         (self.handle_protocol)(handle, &P::GUID, &mut ptr).into_with_val(|| {
@@ -396,6 +838,159 @@ impl BootServices {
         })
     }
 
+    /// Returns the `LoadedImage` interface of the running image, given the
+    /// handle passed to its entry point.
+    ///
+    /// The interface is valid for as long as the image runs, and is not
+    /// modified by the firmware.
+    ///
+    /// # Errors
+    ///
+    /// - `Unsupported` if the handle is not an image handle
+    pub fn current_loaded_image(&self, image_handle: Handle) -> Result<&LoadedImage> {
+        self.handle_protocol::<LoadedImage>(image_handle)
+            .map(|completion| completion.map(|loaded_image| unsafe { &*loaded_image.get() }))
+    }
+
+    /// Opens a protocol interface of a handle, on behalf of the `agent` image
+    /// or driver, and controller if the agent is a driver.
+    ///
+    /// Unlike `handle_protocol()`, this records who is using the interface,
+    /// which is closed when the returned `ScopedProtocol` is dropped. With
+    /// `OpenProtocolAttributes::BY_DRIVER` or `EXCLUSIVE`, the firmware also
+    /// prevents concurrent users from opening it the same way, and asks the
+    /// drivers using it to stop doing so, respectively.
+    ///
+    /// Like for `handle_protocol()`, the interface is not protected against
+    /// concurrent usages which bypass this function.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidParameter` if `attributes` include `TEST_PROTOCOL`, which is
+    ///   what `test_protocol()` is for, or name no agent or controller they
+    ///   require
+    /// - `Unsupported` if the handle does not support the protocol
+    /// - `AccessDenied` or `AlreadyStarted` if the interface was already
+    ///   opened in a conflicting way
+    pub fn open_protocol<P: Protocol>(
+        &self,
+        handle: Handle,
+        agent: Handle,
+        controller: Option<Handle>,
+        attributes: OpenProtocolAttributes,
+    ) -> Result<ScopedProtocol<'_, P>> {
+        if attributes.contains(OpenProtocolAttributes::TEST_PROTOCOL) {
+            return Err(Status::INVALID_PARAMETER.into());
+        }
+        let controller = controller.unwrap_or_else(Handle::null);
+        let mut interface = ptr::null_mut();
+        (self.open_protocol)(
+            handle,
+            &P::GUID,
+            &mut interface,
+            agent,
+            controller,
+            attributes.bits(),
+        )
+        .into_with_val(|| ScopedProtocol {
+            boot_services: self,
+            interface: unsafe { &*(interface as *mut P as *mut UnsafeCell<P>) },
+            handle,
+            agent,
+            controller,
+        })
+    }
+
+    /// Tests whether a handle supports a protocol, without opening it.
+    ///
+    /// # Errors
+    ///
+    /// - `Unsupported` if the handle does not support the protocol
+    pub fn test_protocol<P: Protocol>(
+        &self,
+        handle: Handle,
+        agent: Handle,
+        controller: Option<Handle>,
+    ) -> Result {
+        (self.open_protocol)(
+            handle,
+            &P::GUID,
+            ptr::null_mut(),
+            agent,
+            controller.unwrap_or_else(Handle::null),
+            OpenProtocolAttributes::TEST_PROTOCOL.bits(),
+        )
+        .into()
+    }
+
+    /// Closes a protocol interface of a handle opened by `agent`, and
+    /// `controller` if any, with `open_protocol()`.
+    ///
+    /// # Safety
+    ///
+    /// The interface must no longer be used through the `ScopedProtocol` which
+    /// opened it, which closes it again when dropped.
+    ///
+    /// # Errors
+    ///
+    /// - `NotFound` if the interface is not opened by the agent and controller
+    pub unsafe fn close_protocol(
+        &self,
+        handle: Handle,
+        protocol: &Guid,
+        agent: Handle,
+        controller: Option<Handle>,
+    ) -> Result {
+        let controller = controller.unwrap_or_else(Handle::null);
+        (self.close_protocol)(handle, protocol, agent, controller).into()
+    }
+
+    /// Registers `event` to be signaled whenever an interface of `protocol`
+    /// is installed or reinstalled.
+    ///
+    /// The returned key can be used with `SearchType::ByRegisterNotify` to
+    /// list the handles on which it happened, one at a time. The registration
+    /// ends when the event is closed.
+    ///
+    /// # Errors
+    ///
+    /// - `OutOfResources` if the registration cannot be allocated
+    pub fn register_protocol_notify(
+        &self,
+        protocol: &Guid,
+        event: Event,
+    ) -> Result<ProtocolSearchKey> {
+        let mut key = ptr::null_mut();
+        (self.register_protocol_notify)(protocol, event, &mut key)
+            .into_with_val(|| ProtocolSearchKey(key))
+    }
+
+    /// Watches for the installation of the interfaces of a protocol, e.g. on
+    /// the devices which are plugged in.
+    ///
+    /// The handles on which an interface was installed since the last call
+    /// are returned by `ProtocolRegistration::next_handle()`, and the event of
+    /// the registration is signaled when there are some. Those on which it
+    /// was already installed before are not.
+    ///
+    /// # Errors
+    ///
+    /// - the errors of `create_event()` and `register_protocol_notify()`
+    pub fn watch_protocol<P: Protocol>(&self) -> Result<ProtocolRegistration<'_>> {
+        let event = unsafe { self.create_event(EventType::empty(), Tpl::APPLICATION, None) }?.log();
+        match self.register_protocol_notify(&P::GUID, event) {
+            Ok(key) => Ok(key.map(|key| ProtocolRegistration {
+                boot_services: self,
+                event,
+                key,
+            })),
+            Err(err) => {
+                let _ = unsafe { self.close_event(event) };
+                Err(err)
+            }
+        }
+    }
+
     /// Enumerates all handles installed on the system which match a certain query.
     ///
     /// You should first call this function with `None` for the output buffer,
@@ -417,10 +1012,7 @@ impl BootServices {
         };
 
         // Obtain the needed data from the parameters.
-        let (ty, guid, key) = match search_ty {
-            SearchType::AllHandles => (0, ptr::null(), ptr::null_mut()),
-            SearchType::ByProtocol(guid) => (2, guid as *const _, ptr::null_mut()),
-        };
+        let (ty, guid, key) = search_ty.to_raw();
 
         let status = unsafe { (self.locate_handle)(ty, guid, key, &mut buffer_size, buffer) };
 
@@ -433,13 +1025,162 @@ impl BootServices {
         }
     }
 
+    /// Enumerates all handles installed on the system which match a certain
+    /// query, in a buffer allocated by the firmware.
+    ///
+    /// Unlike `locate_handle()`, this needs a single call, and works without
+    /// an allocator. The buffer is freed when the returned `HandleBuffer` is
+    /// dropped.
+    ///
+    /// # Errors
+    ///
+    /// - `NotFound` if no handle matches the query
+    /// - `OutOfResources` if the buffer cannot be allocated
+    pub fn locate_handle_buffer(&self, search_ty: SearchType) -> Result<HandleBuffer<'_>> {
+        let (ty, guid, key) = search_ty.to_raw();
+        let mut count = 0;
+        let mut buffer = ptr::null_mut();
+        unsafe { (self.locate_handle_buffer)(ty, guid, key, &mut count, &mut buffer) }
+            .into_with_val(|| HandleBuffer {
+                handles: unsafe {
+                    PoolBox::from_raw(self, ptr::slice_from_raw_parts_mut(buffer, count))
+                },
+            })
+    }
+
     /// Locates the handle to a device on the device path that supports the specified protocol.
     pub fn locate_device_path<P: Protocol>(&self, device_path: &mut DevicePath) -> Result<Handle> {
+        self.locate_device_path_remaining::<P>(device_path)
+            .map(|completion| completion.map(|(handle, _)| handle))
+    }
+
+    /// Locates the handle whose device path is the longest prefix of
+    /// `device_path` among the handles supporting a protocol, and returns it
+    /// with the rest of the path, e.g. the file path of a boot option.
+    ///
+    /// The remaining path starts with an end node if the whole path matched.
+    ///
+    /// # Errors
+    ///
+    /// - `NotFound` if no handle on the path supports the protocol
+    pub fn locate_device_path_remaining<'a, P: Protocol>(
+        &self,
+        device_path: &'a DevicePath,
+    ) -> Result<(Handle, &'a DevicePath)> {
+        let mut handle = Handle::null();
+        // The firmware only moves the pointer forward, within the path
+        let mut remaining = device_path as *const DevicePath as *mut DevicePath;
+        unsafe { (self.locate_device_path)(&P::GUID, &mut remaining, &mut handle) }
+            .into_with_val(|| (handle, unsafe { &*remaining }))
+    }
+
+    /// Connects drivers to a controller, so that they produce the protocols
+    /// of the device, e.g. once a partition or RAM disk handle was installed.
+    ///
+    /// All the drivers which support the controller are connected, unless a
+    /// `driver_image` is given, which is then tried first.
+    /// `remaining_device_path` selects the child controllers to create, if
+    /// the drivers are bus drivers, and they are all created if it is `None`.
+    /// If `recursive` is set, the child controllers are connected as well,
+    /// and so on.
+    ///
+    /// # Errors
+    ///
+    /// - `NotFound` if no driver was connected
+    /// - `SecurityViolation` if the user is not allowed to use the device
+    pub fn connect_controller(
+        &self,
+        controller: Handle,
+        driver_image: Option<Handle>,
+        remaining_device_path: Option<&DevicePath>,
+        recursive: bool,
+    ) -> Result {
+        // The driver images are given as a null-terminated list
+        let driver_images = [driver_image.unwrap_or_else(Handle::null), Handle::null()];
+        let driver_images = if driver_image.is_some() {
+            driver_images.as_ptr()
+        } else {
+            ptr::null()
+        };
+        let remaining_device_path =
+            remaining_device_path.map_or(ptr::null(), |path| path as *const _);
+        unsafe {
+            (self.connect_controller)(controller, driver_images, remaining_device_path, recursive)
+        }
+        .into()
+    }
+
+    /// Disconnects drivers from a controller.
+    ///
+    /// All the drivers managing the controller are disconnected, unless a
+    /// `driver_image` is given. If a `child` controller is also given, only
+    /// that one is destroyed by the driver, which otherwise destroys them all
+    /// and stops managing the controller.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidParameter` if the driver does not manage the controller
+    /// - `OutOfResources` or `DeviceError` if a driver could not be stopped
+    pub fn disconnect_controller(
+        &self,
+        controller: Handle,
+        driver_image: Option<Handle>,
+        child: Option<Handle>,
+    ) -> Result {
+        (self.disconnect_controller)(
+            controller,
+            driver_image.unwrap_or_else(Handle::null),
+            child.unwrap_or_else(Handle::null),
+        )
+        .into()
+    }
+
+    /// Adds, replaces or removes an entry of the configuration table of the
+    /// system table, as listed by `SystemTable::config_table()`.
+    ///
+    /// The entry identified by `guid` points to `table`, and is removed if
+    /// `table` is null. The memory of a replaced or removed table is not
+    /// freed.
+    ///
+    /// # Safety
+    ///
+    /// `table` must point to a table of the format identified by `guid`, and
+    /// remain valid for as long as it is installed. Tables which are passed to
+    /// an operating system must be in memory which it preserves, such as
+    /// `MemoryType::RUNTIME_SERVICES_DATA` or `ACPI_RECLAIM` memory.
+    ///
+    /// # Errors
+    ///
+    /// - `NotFound` if a table to remove is not installed
+    /// - `OutOfResources` if the configuration table cannot be grown
+    pub unsafe fn install_configuration_table(&self, guid: &Guid, table: *const c_void) -> Result {
+        (self.install_configuration_table)(guid, table).into()
+    }
+
+    /// Copies `data` to `MemoryType::RUNTIME_SERVICES_DATA` memory, and
+    /// installs it as the configuration table identified by `guid`, e.g. to
+    /// pass it to the operating system which is booted next.
+    ///
+    /// The copy is returned, and stays allocated even after the table is
+    /// replaced or removed.
+    ///
+    /// # Errors
+    ///
+    /// - `OutOfResources` if the copy cannot be allocated, or the
+    ///   configuration table cannot be grown
+    pub fn install_configuration_table_data(&self, guid: &Guid, data: &[u8]) -> Result<*const u8> {
+        let table = self
+            .allocate_pool(MemoryType::RUNTIME_SERVICES_DATA, data.len())?
+            .log();
         unsafe {
-            let mut handle = Handle::uninitialized();
-            let mut device_path_ptr = device_path as *mut DevicePath;
-            (self.locate_device_path)(&P::GUID, &mut device_path_ptr, &mut handle)
-                .into_with_val(|| handle)
+            ptr::copy_nonoverlapping(data.as_ptr(), table, data.len());
+            match self.install_configuration_table(guid, table as *const c_void) {
+                Ok(completion) => Ok(completion.map(|_| table as *const u8)),
+                Err(err) => {
+                    let _ = self.free_pool(table);
+                    Err(err)
+                }
+            }
         }
     }
 
@@ -467,6 +1208,14 @@ impl BootServices {
     }
 
     /// Unload an EFI image.
+    ///
+    /// This frees the images which were loaded but not started, e.g. after a
+    /// failed chainload. Started images are only unloaded if they support it.
+    ///
+    /// # Errors
+    ///
+    /// - `Unsupported` if the image was started and cannot be unloaded
+    /// - the error returned by the image's unload function
     pub fn unload_image(&self, image_handle: Handle) -> Result {
         (self.unload_image)(image_handle).into()
     }
@@ -481,6 +1230,53 @@ impl BootServices {
         }
     }
 
+    /// Exits an image, returning `exit_status` and `exit_data` to the image
+    /// which started it.
+    ///
+    /// When called on the running image, this does not return: control goes
+    /// back to `start_image()`, and the image is unloaded unless it is a
+    /// driver which returned a success status. When called on an image which
+    /// was loaded but not started, it is unloaded like by `unload_image()`.
+    ///
+    /// The exit data is copied to the pool, as required by the specification,
+    /// and is usually a message describing the error.
+    ///
+    /// # Safety
+    ///
+    /// Nothing is dropped when exiting the running image, and its memory may
+    /// be freed. Everything which the firmware could still call, such as the
+    /// notification functions of events and the installed protocols, must be
+    /// released beforehand.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidParameter` if the image was started, but is not running
+    /// - `OutOfResources` if the exit data cannot be copied
+    pub unsafe fn exit(
+        &self,
+        image_handle: Handle,
+        exit_status: Status,
+        exit_data: Option<&CStr16>,
+    ) -> Result {
+        let (size, data) = match exit_data {
+            Some(exit_data) => {
+                let exit_data = exit_data.to_u16_slice_with_nul();
+                let size = mem::size_of_val(exit_data);
+                let data = self.allocate_pool(MemoryType::LOADER_DATA, size)?.log();
+                ptr::copy_nonoverlapping(exit_data.as_ptr() as *const u8, data, size);
+                (size, data as *mut Char16)
+            }
+            None => (0, ptr::null_mut()),
+        };
+        let status = (self.exit)(image_handle, exit_status, size, data);
+        // The exit data is only taken over by the firmware when exiting the
+        // running image
+        if !data.is_null() {
+            let _ = self.free_pool(data as *mut u8);
+        }
+        status.into()
+    }
+
     /// Exits the UEFI boot services
     ///
     /// This unsafe method is meant to be an implementation detail of the safe
@@ -499,13 +1295,67 @@ impl BootServices {
         (self.exit_boot_services)(image, mmap_key).into()
     }
 
+    /// Returns the next value of the platform's monotonic counter.
+    ///
+    /// Each call returns a larger value than the previous one, even across
+    /// boots, since the high half is incremented on each boot.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if the counter overflowed
+    pub fn get_next_monotonic_count(&self) -> Result<MonotonicCount> {
+        let mut count = 0;
+        (self.get_next_monotonic_count)(&mut count).into_with_val(|| MonotonicCount(count))
+    }
+
     /// Stalls the processor for an amount of time.
     ///
-    /// The time is in microseconds.
+    /// The time is in microseconds. See `stall_for()` for longer durations.
     pub fn stall(&self, time: usize) {
         assert_eq!((self.stall)(time), Status::SUCCESS);
     }
 
+    /// Stalls the processor for a duration, rounded up to the microsecond.
+    ///
+    /// Durations whose microseconds do not fit in a `usize`, i.e. of more
+    /// than 71 minutes on 32-bit platforms, are stalled for in several times.
+    pub fn stall_for(&self, duration: Duration) {
+        let mut remaining = micros(duration);
+        while remaining != 0 {
+            let time = remaining.min(usize::MAX as u128) as usize;
+            self.stall(time);
+            remaining -= time as u128;
+        }
+    }
+
+    /// Stalls the processor until an event is signaled, for at most
+    /// `timeout`, and returns whether the event was signaled.
+    ///
+    /// The event is checked every `STALL_POLL_INTERVAL`, by busy-waiting
+    /// rather than sleeping, so that this works at a raised `Tpl`, unlike
+    /// `wait_for_event()`. The events which are signaled by the firmware at
+    /// a lower `Tpl`, such as timers, are not signaled in the meantime
+    /// though.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidParameter` if the event is of type `EventType::NOTIFY_SIGNAL`
+    pub fn stall_until(&self, event: Event, timeout: Duration) -> Result<bool> {
+        let interval = micros(STALL_POLL_INTERVAL);
+        let mut remaining = micros(timeout);
+        loop {
+            if self.check_event(event)?.log() {
+                return Ok(true.into());
+            }
+            if remaining == 0 {
+                return Ok(false.into());
+            }
+            let time = remaining.min(interval);
+            self.stall(time as usize);
+            remaining -= time;
+        }
+    }
+
     /// Set the watchdog timer.
     ///
     /// UEFI will start a 5-minute countdown after an UEFI image is loaded.
@@ -541,13 +1391,95 @@ impl BootServices {
                     d.contains(&0),
                     "Watchdog data must start with a null-terminated string"
                 );
-                (d.len(), d.as_mut_ptr())
+                (mem::size_of_val(d), d.as_mut_ptr())
             })
             .unwrap_or((0, ptr::null_mut()));
 
         unsafe { (self.set_watchdog_timer)(timeout, watchdog_code, data_len, data) }.into()
     }
 
+    /// Restarts the watchdog timer with a `timeout`, rounded up to seconds.
+    ///
+    /// Like with `set_watchdog_timer()`, the `watchdog_code` must be above
+    /// 0xffff, and is logged along with the `message`, if any, when the timer
+    /// expires. A zero timeout disables the watchdog.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `watchdog_code` is reserved for firmware use.
+    pub fn arm_watchdog(
+        &self,
+        timeout: Duration,
+        watchdog_code: u64,
+        message: Option<&CStr16>,
+    ) -> Result {
+        assert!(
+            watchdog_code > 0xffff,
+            "Invalid use of a reserved firmware watchdog code"
+        );
+
+        // The firmware takes a number of seconds as a `usize`
+        let secs = timeout
+            .as_secs()
+            .saturating_add(u64::from(timeout.subsec_nanos() != 0));
+        let timeout = secs.min(usize::MAX as u64) as usize;
+        let (data_len, data) = message.map_or((0, ptr::null()), |message| {
+            let message = message.to_u16_slice_with_nul();
+            (mem::size_of_val(message), message.as_ptr())
+        });
+        unsafe { (self.set_watchdog_timer)(timeout, watchdog_code, data_len, data) }.into()
+    }
+
+    /// Disables the watchdog timer, e.g. before running for a long time, like
+    /// an installer or a memory test would.
+    pub fn disable_watchdog(&self) -> Result {
+        unsafe { (self.set_watchdog_timer)(0, 0, 0, ptr::null()) }.into()
+    }
+
+    /// Computes the 32-bit CRC of `data`, as used by the headers of the UEFI
+    /// tables and by GPT partition tables.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidParameter` if `data` is empty
+    pub fn calculate_crc32(&self, data: &[u8]) -> Result<u32> {
+        let mut crc32 = 0;
+        unsafe { (self.calculate_crc32)(data.as_ptr() as *const c_void, data.len(), &mut crc32) }
+            .into_with_val(|| crc32)
+    }
+
+    /// Computes the CRC of the table starting with `header`, and stores it in
+    /// the header, e.g. after the table was patched.
+    ///
+    /// # Safety
+    ///
+    /// `header` must point to a table which spans the `size` bytes given by
+    /// its header, and is valid for reads and writes.
+    pub unsafe fn update_table_crc32(&self, header: *mut Header) -> Result {
+        (*header).crc = 0;
+        // The whole table is read through the pointer, which is why this
+        // cannot take a reference to the header alone
+        let table = slice::from_raw_parts(header as *const u8, (*header).size as usize);
+        let crc32 = self.calculate_crc32(table)?;
+        Ok(crc32.map(|crc32| (*header).crc = crc32))
+    }
+
+    /// Checks whether the CRC stored in the header of a table matches its
+    /// contents.
+    ///
+    /// The CRC is computed with the CRC field of the header set to zero, which
+    /// is why this needs to modify it while computing it.
+    ///
+    /// # Safety
+    ///
+    /// The requirements of `update_table_crc32()` apply.
+    pub unsafe fn verify_table_crc32(&self, header: *mut Header) -> Result<bool> {
+        let expected = (*header).crc;
+        let outcome = self.update_table_crc32(header);
+        let actual = mem::replace(&mut (*header).crc, expected);
+        outcome.map(|completion| completion.map(|_| actual == expected))
+    }
+
     /// Returns a protocol implementation, if present on the system.
     ///
     /// The caveats of `BootServices::handle_protocol()` also apply here.
@@ -561,23 +1493,69 @@ impl BootServices {
 
     /// Copies memory from source to destination. The buffers can overlap.
     ///
+    /// The copy is done by the firmware, with the `CopyMem` boot service,
+    /// out of sight of the compiler: unlike `ptr::copy()`, it is never elided
+    /// or merged with other accesses, even if the memory is not otherwise
+    /// read or written by Rust code, e.g. for MMIO or NVDIMM regions. The
+    /// width and order of the accesses is up to the firmware though, so
+    /// registers which must be accessed in a certain way still need
+    /// `ptr::write_volatile()`.
+    ///
     /// # Safety
     ///
     /// This function is unsafe as it can be used to violate most safety
-    /// invariants of the Rust type system.
+    /// invariants of the Rust type system. Both buffers must be valid for
+    /// `size` bytes, and the destination must not be referenced while it is
+    /// written to.
     pub unsafe fn memmove(&self, dest: *mut u8, src: *const u8, size: usize) {
         (self.copy_mem)(dest, src, size);
     }
 
     /// Sets a buffer to a certain value.
     ///
+    /// Like `memmove()`, this is done by the firmware, with the `SetMem` boot
+    /// service.
+    ///
     /// # Safety
     ///
     /// This function is unsafe as it can be used to violate most safety
-    /// invariants of the Rust type system.
+    /// invariants of the Rust type system. The buffer must be valid for
+    /// `size` bytes, and not be referenced while it is written to.
     pub unsafe fn set_mem(&self, buffer: *mut u8, size: usize, value: u8) {
         (self.set_mem)(buffer, size, value);
     }
+
+    /// Fills a buffer with `count` copies of a 64-bit value, such as a
+    /// pattern of page table entries.
+    ///
+    /// Like `memmove()`, the buffer is written by the firmware, with the
+    /// `SetMem` boot service if all the bytes of the value are the same, and
+    /// by copying the first value with `CopyMem` otherwise. The buffer does
+    /// not need to be aligned.
+    ///
+    /// # Safety
+    ///
+    /// The buffer must be valid for `count` values, and not be referenced
+    /// while it is written to.
+    pub unsafe fn set_mem64(&self, buffer: *mut u64, count: usize, value: u64) {
+        let size = count * mem::size_of::<u64>();
+        let bytes = value.to_ne_bytes();
+        if bytes.iter().all(|&byte| byte == bytes[0]) {
+            return self.set_mem(buffer as *mut u8, size, bytes[0]);
+        }
+        if count == 0 {
+            return;
+        }
+        // Double the initialized part of the buffer with each copy
+        let buffer = buffer as *mut u8;
+        ptr::write_unaligned(buffer as *mut u64, value);
+        let mut filled = mem::size_of::<u64>();
+        while filled < size {
+            let len = filled.min(size - filled);
+            self.memmove(buffer.add(filled), buffer, len);
+            filled += len;
+        }
+    }
 }
 
 #[cfg(feature = "exts")]
@@ -611,6 +1589,334 @@ impl BootServices {
             .map(|completion| completion.with_status(status2))
     }
 
+    /// Lists the agents which opened a protocol interface of a handle with
+    /// `open_protocol()`, or `handle_protocol()`.
+    ///
+    /// # Errors
+    ///
+    /// - `NotFound` if the handle does not support the protocol
+    pub fn open_protocol_information<P: Protocol>(
+        &self,
+        handle: Handle,
+    ) -> Result<Vec<OpenProtocolInformationEntry>> {
+        self.open_protocol_information_by_guid(handle, &P::GUID)
+    }
+
+    /// Lists the agents using the interface of a protocol given by GUID
+    fn open_protocol_information_by_guid(
+        &self,
+        handle: Handle,
+        protocol: &Guid,
+    ) -> Result<Vec<OpenProtocolInformationEntry>> {
+        let mut buffer = ptr::null_mut();
+        let mut count = 0;
+        unsafe { (self.open_protocol_information)(handle, protocol, &mut buffer, &mut count) }
+            .into_with_val(|| {
+                // The entries are allocated from pool by the firmware, and
+                // must be freed once copied
+                let entries = unsafe { slice::from_raw_parts(buffer, count) }.to_vec();
+                let _ = self.free_pool(buffer as *mut u8);
+                entries
+            })
+    }
+
+    /// Lists the GUIDs of the protocols whose interfaces are installed on a
+    /// handle.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidParameter` if the handle is not valid
+    /// - `OutOfResources` if the list cannot be allocated
+    pub fn protocols_per_handle(&self, handle: Handle) -> Result<Vec<Guid>> {
+        let mut buffer = ptr::null_mut();
+        let mut count = 0;
+        unsafe { (self.protocols_per_handle)(handle, &mut buffer, &mut count) }.into_with_val(
+            || {
+                // The array of pointers is allocated from pool, but the GUIDs
+                // themselves belong to the firmware
+                let guids = unsafe { slice::from_raw_parts(buffer, count) }
+                    .iter()
+                    .map(|&guid| unsafe { *guid })
+                    .collect();
+                let _ = self.free_pool(buffer as *mut u8);
+                guids
+            },
+        )
+    }
+
+    /// Takes a snapshot of the handle database: all the handles, with the
+    /// protocols installed on each of them, and the agents using each
+    /// interface, like the `dh` command of the UEFI shell prints them.
+    ///
+    /// The database is read at `Tpl::NOTIFY`, so that no handle or interface
+    /// is added or removed in the meantime.
+    ///
+    /// # Errors
+    ///
+    /// - `OutOfResources` if the snapshot cannot be allocated
+    pub fn all_handles(&self) -> Result<Vec<HandleInfo>> {
+        let _guard = unsafe { self.raise_tpl(Tpl::NOTIFY) };
+        let handles = self.locate_handle_buffer(SearchType::AllHandles)?.log();
+        let mut infos = Vec::with_capacity(handles.len());
+        for &handle in handles.iter() {
+            let guids = self.protocols_per_handle(handle)?.log();
+            let mut protocols = Vec::with_capacity(guids.len());
+            for guid in guids {
+                let open_information = self.open_protocol_information_by_guid(handle, &guid)?.log();
+                protocols.push(ProtocolInfo {
+                    guid,
+                    open_information,
+                });
+            }
+            infos.push(HandleInfo { handle, protocols });
+        }
+        Ok(infos.into())
+    }
+
+    /// Calls `notify` with each handle on which an interface of a protocol is
+    /// installed or reinstalled, from then on and as long as the returned
+    /// event is not dropped.
+    ///
+    /// Like the notification function of `create_event_with_closure()`, the
+    /// closure runs at `Tpl::CALLBACK`, interrupting the code running at a
+    /// lower priority level, and must be `'static`.
+    ///
+    /// # Errors
+    ///
+    /// - the errors of `create_event_with_closure()` and
+    ///   `register_protocol_notify()`
+    pub fn watch_protocol_with_closure<'a, P, F>(
+        &'a self,
+        mut notify: F,
+    ) -> Result<ClosureEvent<'a>>
+    where
+        P: Protocol,
+        F: FnMut(Handle) + Send + 'static,
+    {
+        // The closure outlives the returned event if it is leaked, but not
+        // the boot services, since the protocols are only installed while
+        // they run
+        let boot_services = self as *const BootServices as usize;
+        // The key is only known once the event exists, and is null until then
+        let key = Arc::new(AtomicPtr::new(ptr::null_mut()));
+        let notify_key = key.clone();
+        let event = self
+            .create_event_with_closure(EventType::NOTIFY_SIGNAL, Tpl::CALLBACK, move |_| {
+                let key = ProtocolSearchKey(notify_key.load(Ordering::Acquire));
+                if key.0.is_null() {
+                    return;
+                }
+                let boot_services = unsafe { &*(boot_services as *const BootServices) };
+                let mut handle = [Handle::null()];
+                let search_type = SearchType::ByRegisterNotify(key);
+                while let Ok(completion) =
+                    boot_services.locate_handle(search_type, Some(&mut handle))
+                {
+                    if completion.log() == 0 {
+                        break;
+                    }
+                    notify(handle[0]);
+                }
+            })?
+            .log();
+        let registration = self.register_protocol_notify(&P::GUID, event.event())?;
+        key.store(registration.log().0, Ordering::Release);
+        // Deliver the installations which happened while the key was null
+        self.signal_event(event.event())?.log();
+        Ok(event.into())
+    }
+
+    /// Installs a protocol implemented in Rust on a device handle, or on a new
+    /// handle if `handle` is `None`.
+    ///
+    /// The interface is moved to the heap, and stays installed as long as it
+    /// is not uninstalled with `InstalledProtocol::uninstall()`, even if the
+    /// returned `InstalledProtocol` is dropped.
+    ///
+    /// The functions of the interface may be called by other images, and are
+    /// expected to follow the `efiapi` calling convention.
+    ///
+    /// # Errors
+    ///
+    /// - the errors of `install_protocol_interface()`
+    pub fn install_protocol<P: Protocol>(
+        &self,
+        handle: Option<Handle>,
+        interface: Box<P>,
+    ) -> Result<InstalledProtocol<'_, P>> {
+        let interface = Box::into_raw(interface);
+        match unsafe { self.install_protocol_interface(handle, &P::GUID, interface as *mut c_void) }
+        {
+            Ok(handle) => Ok(handle.map(|handle| InstalledProtocol {
+                boot_services: self,
+                handle,
+                interface,
+            })),
+            Err(err) => {
+                drop(unsafe { Box::from_raw(interface) });
+                Err(err)
+            }
+        }
+    }
+
+    /// Waits for any of `events` to be signaled, or for `timeout` to expire,
+    /// and returns the index of the event which was signaled, or `None` on
+    /// timeout.
+    ///
+    /// This works like `wait_for_event()`, to which a timer event is added if
+    /// there is a `timeout`.
+    ///
+    /// # Errors
+    ///
+    /// - the errors of `wait_for_event()`, and those of `create_timer()`
+    pub fn wait_for_any(
+        &self,
+        events: &[Event],
+        timeout: Option<Duration>,
+    ) -> Result<Option<usize>, Option<usize>> {
+        let mut wait_events = Vec::with_capacity(events.len() + 1);
+        wait_events.extend_from_slice(events);
+        let timer = match timeout {
+            Some(timeout) => {
+                let timer = self
+                    .create_timer()
+                    .map_err(|err| Error::new(err.status(), None))?
+                    .log();
+                timer
+                    .set_relative(timeout)
+                    .map_err(|err| Error::new(err.status(), None))?
+                    .log();
+                wait_events.push(timer.event());
+                Some(timer)
+            }
+            None => None,
+        };
+        let outcome = self.wait_for_event(&mut wait_events);
+        // The timer is closed once the wait ended
+        drop(timer);
+        // The timer is not one of the caller's events
+        let caller_index = |index: usize| Some(index).filter(|&index| index < events.len());
+        match outcome {
+            Ok(index) => Ok(index.map(caller_index)),
+            Err(err) => {
+                let index = (*err.data()).and_then(caller_index);
+                Err(Error::new(err.status(), index))
+            }
+        }
+    }
+
+    /// Waits for an event to be signaled, or for `timeout` to expire, and
+    /// returns whether the event was signaled.
+    ///
+    /// # Errors
+    ///
+    /// - the errors of `wait_for_any()`
+    pub fn wait_for_event_timeout(&self, event: Event, timeout: Duration) -> Result<bool> {
+        self.wait_for_any(&[event], Some(timeout))
+            .map(|completion| completion.map(|index| index.is_some()))
+            .map_err(|err| err.status().into())
+    }
+
+    /// Creates an event whose notification function is a Rust closure.
+    ///
+    /// This works like `create_event()`, with `EventType::NOTIFY_SIGNAL` or
+    /// `EventType::NOTIFY_WAIT` in `event_ty`, but the closure can capture
+    /// state. It is allocated on the heap, and freed after the event is
+    /// closed, when the returned `ClosureEvent` is dropped.
+    ///
+    /// The closure runs at `notify_tpl`, interrupting the code running at a
+    /// lower priority level, which is why it must be `Send`. Its priority
+    /// level must therefore be `Tpl::CALLBACK` or `Tpl::NOTIFY`. It must also
+    /// be `'static`, since the event keeps calling it if the `ClosureEvent`
+    /// is leaked.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidParameter` if `event_ty` has no notification type, or if
+    ///   `notify_tpl` is not one of the above
+    pub fn create_event_with_closure<'a, F>(
+        &'a self,
+        event_ty: EventType,
+        notify_tpl: Tpl,
+        notify: F,
+    ) -> Result<ClosureEvent<'a>>
+    where
+        F: FnMut(Event) + Send + 'static,
+    {
+        self.create_closure_event(event_ty, notify_tpl, None, notify)
+    }
+
+    /// Creates an event of the group `event_group` whose notification
+    /// function is a Rust closure.
+    ///
+    /// This combines `create_event_ex()` and `create_event_with_closure()`,
+    /// e.g. to run a closure once the platform is `EVENT_GROUP_READY_TO_BOOT`.
+    ///
+    /// # Errors
+    ///
+    /// - the errors of `create_event_with_closure()`
+    pub fn create_event_ex_with_closure<'a, F>(
+        &'a self,
+        event_ty: EventType,
+        notify_tpl: Tpl,
+        event_group: &Guid,
+        notify: F,
+    ) -> Result<ClosureEvent<'a>>
+    where
+        F: FnMut(Event) + Send + 'static,
+    {
+        self.create_closure_event(event_ty, notify_tpl, Some(event_group), notify)
+    }
+
+    fn create_closure_event<'a, F>(
+        &'a self,
+        event_ty: EventType,
+        notify_tpl: Tpl,
+        event_group: Option<&Guid>,
+        notify: F,
+    ) -> Result<ClosureEvent<'a>>
+    where
+        F: FnMut(Event) + Send + 'static,
+    {
+        unsafe extern "efiapi" fn notify_trampoline<F: FnMut(Event)>(e: Event, ctx: *mut c_void) {
+            // The notification functions of an event never run concurrently
+            let notify = &mut *(ctx as *mut F);
+            notify(e); // SAFETY: Aborting panics are assumed here
+        }
+        unsafe fn drop_closure<F>(ctx: *mut c_void) {
+            drop(Box::from_raw(ctx as *mut F));
+        }
+
+        let notify_types = EventType::NOTIFY_SIGNAL | EventType::NOTIFY_WAIT;
+        if !event_ty.intersects(notify_types)
+            || !(notify_tpl == Tpl::CALLBACK || notify_tpl == Tpl::NOTIFY)
+        {
+            return Err(Status::INVALID_PARAMETER.into());
+        }
+
+        let ctx = Box::into_raw(Box::new(notify)) as *mut c_void;
+        let mut event = MaybeUninit::<Event>::uninit();
+        let status = unsafe {
+            self.create_event_raw(
+                event_ty,
+                notify_tpl,
+                Some(notify_trampoline::<F>),
+                ctx,
+                event_group,
+                event.as_mut_ptr(),
+            )
+        };
+        if status.is_error() {
+            unsafe { drop_closure::<F>(ctx) };
+        }
+        status.into_with_val(|| ClosureEvent {
+            boot_services: self,
+            event: unsafe { event.assume_init() },
+            ctx,
+            drop_closure: drop_closure::<F>,
+        })
+    }
+
     /// Retrieves the `SimpleFileSystem` protocol associated with
     /// the device the given image was loaded from.
     ///
@@ -621,9 +1927,8 @@ impl BootServices {
         image_handle: Handle,
     ) -> Result<&UnsafeCell<SimpleFileSystem>> {
         let loaded_image = self
-            .handle_protocol::<LoadedImage>(image_handle)?
+            .current_loaded_image(image_handle)?
             .expect("Failed to retrieve `LoadedImage` protocol from handle");
-        let loaded_image = unsafe { &*loaded_image.get() };
 
         let device_handle = loaded_image.device();
 
@@ -669,14 +1974,264 @@ pub enum Tpl: usize => {
     HIGH_LEVEL  = 31,
 }}
 
+/// Memory pages allocated by `BootServices::allocate_pages_scoped()`.
+///
+/// The pages are freed when this is dropped, unless they are released with
+/// `into_raw()`.
+pub struct PagesBox<'boot> {
+    boot_services: &'boot BootServices,
+    address: u64,
+    count: usize,
+}
+
+impl PagesBox<'_> {
+    /// Physical address of the first page.
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    /// Number of pages.
+    pub fn page_count(&self) -> usize {
+        self.count
+    }
+
+    /// Releases the pages without freeing them, e.g. to hand them over to the
+    /// operating system, and returns their physical address.
+    pub fn into_raw(self) -> u64 {
+        let address = self.address;
+        mem::forget(self);
+        address
+    }
+}
+
+impl Deref for PagesBox<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.address as *const u8, self.count * PAGE_SIZE) }
+    }
+}
+
+impl DerefMut for PagesBox<'_> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.address as *mut u8, self.count * PAGE_SIZE) }
+    }
+}
+
+impl Drop for PagesBox<'_> {
+    fn drop(&mut self) {
+        let _ = self.boot_services.free_pages(self.address, self.count);
+    }
+}
+
+/// Handles returned by `BootServices::locate_handle_buffer()`.
+///
+/// The buffer is freed when this is dropped.
+pub struct HandleBuffer<'boot> {
+    handles: PoolBox<'boot, [Handle]>,
+}
+
+impl Deref for HandleBuffer<'_> {
+    type Target = [Handle];
+
+    fn deref(&self) -> &[Handle] {
+        &self.handles
+    }
+}
+
+/// Value stored in memory allocated from a pool, like a `Box`.
+///
+/// The value is dropped and its memory freed when this is dropped.
+pub struct PoolBox<'boot, T: ?Sized> {
+    boot_services: &'boot BootServices,
+    ptr: *mut T,
+}
+
+impl<'boot, T: ?Sized> PoolBox<'boot, T> {
+    /// Takes ownership of a value allocated from a pool, such as a buffer
+    /// returned by the firmware.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to a valid value, allocated with
+    /// `BootServices::allocate_pool()`, and not be owned by anything else.
+    pub unsafe fn from_raw(boot_services: &'boot BootServices, ptr: *mut T) -> Self {
+        Self { boot_services, ptr }
+    }
+
+    /// Releases the value without freeing it, and returns a pointer to it.
+    pub fn into_raw(self) -> *mut T {
+        let ptr = self.ptr;
+        mem::forget(self);
+        ptr
+    }
+}
+
+impl<T: ?Sized> Deref for PoolBox<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.ptr }
+    }
+}
+
+impl<T: ?Sized> DerefMut for PoolBox<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.ptr }
+    }
+}
+
+impl<T: ?Sized> Drop for PoolBox<'_, T> {
+    fn drop(&mut self) {
+        unsafe { ptr::drop_in_place(self.ptr) };
+        let _ = self.boot_services.free_pool(self.ptr as *mut u8);
+    }
+}
+
+/// Protocol implemented in Rust, installed by
+/// `BootServices::install_protocol()`.
+///
+/// Dropping this leaves the interface installed, and leaks it.
+#[cfg(feature = "exts")]
+pub struct InstalledProtocol<'boot, P: Protocol> {
+    boot_services: &'boot BootServices,
+    handle: Handle,
+    interface: *mut P,
+}
+
+#[cfg(feature = "exts")]
+impl<'boot, P: Protocol> InstalledProtocol<'boot, P> {
+    /// Handle the interface is installed on.
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+
+    /// Returns the installed interface, which may be used by other images.
+    pub fn interface(&self) -> *mut P {
+        self.interface
+    }
+
+    /// Replaces the installed interface by `interface`, and returns the
+    /// previous one.
+    ///
+    /// # Errors
+    ///
+    /// - the errors of `BootServices::reinstall_protocol_interface()`, in
+    ///   which case the previous interface remains installed
+    pub fn reinstall(&mut self, interface: Box<P>) -> Result<Box<P>> {
+        let interface = Box::into_raw(interface);
+        let outcome = unsafe {
+            self.boot_services.reinstall_protocol_interface(
+                self.handle,
+                &P::GUID,
+                self.interface as *mut c_void,
+                interface as *mut c_void,
+            )
+        };
+        match outcome {
+            Ok(completion) => {
+                let old_interface = mem::replace(&mut self.interface, interface);
+                Ok(completion.map(|_| unsafe { Box::from_raw(old_interface) }))
+            }
+            Err(err) => {
+                drop(unsafe { Box::from_raw(interface) });
+                Err(err)
+            }
+        }
+    }
+
+    /// Uninstalls the interface, and returns it.
+    ///
+    /// # Errors
+    ///
+    /// - the errors of `BootServices::uninstall_protocol_interface()`, along
+    ///   with this installation, as the interface remains installed
+    pub fn uninstall(self) -> Result<Box<P>, Self> {
+        let protocol = self.interface as *mut c_void;
+        match unsafe {
+            self.boot_services
+                .uninstall_protocol_interface(self.handle, &P::GUID, protocol)
+        } {
+            Ok(completion) => Ok(completion.map(|_| unsafe { Box::from_raw(self.interface) })),
+            Err(err) => Err(Error::new(err.status(), self)),
+        }
+    }
+}
+
+#[cfg(feature = "exts")]
+impl<P: Protocol> fmt::Debug for InstalledProtocol<'_, P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("InstalledProtocol")
+            .field("handle", &self.handle)
+            .field("interface", &self.interface)
+            .finish()
+    }
+}
+
+/// Protocol notification registration, created by
+/// `BootServices::watch_protocol()`.
+///
+/// The registration ends, and its event is closed, when this is dropped.
+pub struct ProtocolRegistration<'boot> {
+    boot_services: &'boot BootServices,
+    event: Event,
+    key: ProtocolSearchKey,
+}
+
+impl ProtocolRegistration<'_> {
+    /// Returns the event which is signaled when an interface is installed,
+    /// e.g. to wait for it along with other events.
+    pub fn event(&self) -> Event {
+        self.event
+    }
+
+    /// Returns the search key of the registration.
+    pub fn key(&self) -> ProtocolSearchKey {
+        self.key
+    }
+
+    /// Returns the next handle on which an interface was installed, or
+    /// `None` if there is none left.
+    pub fn next_handle(&self) -> Result<Option<Handle>> {
+        let mut handle = [Handle::null()];
+        let search_type = SearchType::ByRegisterNotify(self.key);
+        match self
+            .boot_services
+            .locate_handle(search_type, Some(&mut handle))
+        {
+            Ok(completion) => {
+                Ok(completion.map(|found| if found == 0 { None } else { Some(handle[0]) }))
+            }
+            Err(err) if err.status() == Status::NOT_FOUND => Ok(None.into()),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+impl Drop for ProtocolRegistration<'_> {
+    fn drop(&mut self) {
+        let _ = unsafe { self.boot_services.close_event(self.event) };
+    }
+}
+
 /// RAII guard for task priority level changes
 ///
-/// Will automatically restore the former task priority level when dropped.
+/// Will automatically restore the former task priority level when dropped,
+/// including while unwinding, so it must be kept alive for the duration of
+/// the critical section.
+#[must_use = "the former task priority level is restored when the guard is dropped"]
 pub struct TplGuard<'boot> {
     boot_services: &'boot BootServices,
     old_tpl: Tpl,
 }
 
+impl TplGuard<'_> {
+    /// Returns the task priority level which is restored when this is dropped.
+    pub fn old_tpl(&self) -> Tpl {
+        self.old_tpl
+    }
+}
+
 impl Drop for TplGuard<'_> {
     fn drop(&mut self) {
         unsafe {
@@ -685,6 +2240,233 @@ impl Drop for TplGuard<'_> {
     }
 }
 
+/// Timer event, created by `BootServices::create_timer()`.
+///
+/// The event is closed when this is dropped.
+pub struct Timer<'boot> {
+    boot_services: &'boot BootServices,
+    event: Event,
+}
+
+impl Timer<'_> {
+    /// Returns the event of the timer, e.g. to wait for it along with other
+    /// events.
+    pub fn event(&self) -> Event {
+        self.event
+    }
+
+    /// Signals the timer once, after `delay`, replacing its previous trigger.
+    pub fn set_relative(&self, delay: Duration) -> Result {
+        self.boot_services.set_timer_relative(self.event, delay)
+    }
+
+    /// Signals the timer every `period`, replacing its previous trigger.
+    pub fn set_periodic(&self, period: Duration) -> Result {
+        self.boot_services.set_timer_periodic(self.event, period)
+    }
+
+    /// Stops the timer.
+    pub fn cancel(&self) -> Result {
+        self.boot_services
+            .set_timer(self.event, TimerTrigger::Cancel)
+    }
+
+    /// Waits for the timer to be signaled, and clears its signaled state.
+    ///
+    /// This must be called at `Tpl::APPLICATION`, and never returns if the
+    /// timer is not set.
+    pub fn wait(&self) -> Result {
+        self.boot_services
+            .wait_for_event(&mut [self.event])
+            .map_err(|err| err.status().into())
+            .map(|completion| completion.map(|_| ()))
+    }
+
+    /// Checks whether the timer was signaled, without waiting, and clears its
+    /// signaled state if so.
+    pub fn is_expired(&self) -> Result<bool> {
+        self.boot_services.check_event(self.event)
+    }
+}
+
+impl Drop for Timer<'_> {
+    fn drop(&mut self) {
+        let _ = unsafe { self.boot_services.close_event(self.event) };
+    }
+}
+
+bitflags! {
+    /// Attributes of `BootServices::open_protocol()`, describing how the
+    /// interface is used.
+    pub struct OpenProtocolAttributes: u32 {
+        /// Used like with `BootServices::handle_protocol()`.
+        const BY_HANDLE_PROTOCOL = 0x01;
+        /// Used by an application or driver, without the knowledge of the
+        /// drivers managing the handle.
+        const GET_PROTOCOL = 0x02;
+        /// Only tested for, with `BootServices::test_protocol()`.
+        const TEST_PROTOCOL = 0x04;
+        /// Used by a bus driver for one of its child controllers, so that
+        /// they are stopped along with it.
+        const BY_CHILD_CONTROLLER = 0x08;
+        /// Used by a driver managing the handle as a controller, which
+        /// prevents other drivers from doing the same.
+        const BY_DRIVER = 0x10;
+        /// Used by a single agent, which asks the drivers using the
+        /// interface to stop doing so. Can be combined with `BY_DRIVER`.
+        const EXCLUSIVE = 0x20;
+    }
+}
+
+/// Protocol interface opened by `BootServices::open_protocol()`.
+///
+/// The interface is closed when this is dropped.
+pub struct ScopedProtocol<'boot, P: Protocol> {
+    boot_services: &'boot BootServices,
+    interface: &'boot UnsafeCell<P>,
+    handle: Handle,
+    agent: Handle,
+    controller: Handle,
+}
+
+impl<'boot, P: Protocol> ScopedProtocol<'boot, P> {
+    /// Returns the interface, like it would be by
+    /// `BootServices::handle_protocol()`, for as long as it stays open.
+    pub fn interface(&self) -> &UnsafeCell<P> {
+        self.interface
+    }
+
+    /// Handle whose interface was opened.
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+}
+
+impl<P: Protocol> Drop for ScopedProtocol<'_, P> {
+    fn drop(&mut self) {
+        let status =
+            (self.boot_services.close_protocol)(self.handle, &P::GUID, self.agent, self.controller);
+        // Closing only fails if the interface was closed behind our back
+        debug_assert!(!status.is_error(), "Failed to close protocol: {:?}", status);
+    }
+}
+
+/// Agent which opened a protocol interface, as listed by
+/// `BootServices::open_protocol_information()`.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct OpenProtocolInformationEntry {
+    agent_handle: Handle,
+    controller_handle: Handle,
+    attributes: u32,
+    open_count: u32,
+}
+
+impl OpenProtocolInformationEntry {
+    /// Image or driver which opened the interface.
+    pub fn agent(&self) -> Handle {
+        self.agent_handle
+    }
+
+    /// Controller which the interface was opened for, if any.
+    pub fn controller(&self) -> Option<Handle> {
+        if self.controller_handle.is_null() {
+            None
+        } else {
+            Some(self.controller_handle)
+        }
+    }
+
+    /// How the interface is used.
+    pub fn attributes(&self) -> OpenProtocolAttributes {
+        OpenProtocolAttributes::from_bits_truncate(self.attributes)
+    }
+
+    /// Number of times the agent opened the interface this way.
+    pub fn open_count(&self) -> u32 {
+        self.open_count
+    }
+}
+
+/// Handle of the handle database, as listed by `BootServices::all_handles()`.
+#[cfg(feature = "exts")]
+#[derive(Debug, Clone)]
+pub struct HandleInfo {
+    handle: Handle,
+    protocols: Vec<ProtocolInfo>,
+}
+
+#[cfg(feature = "exts")]
+impl HandleInfo {
+    /// The handle itself.
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+
+    /// Protocols whose interfaces are installed on the handle.
+    pub fn protocols(&self) -> &[ProtocolInfo] {
+        &self.protocols
+    }
+
+    /// Returns whether an interface of a protocol is installed on the handle.
+    pub fn supports(&self, protocol: &Guid) -> bool {
+        self.protocols.iter().any(|info| info.guid == *protocol)
+    }
+}
+
+/// Protocol installed on a handle, as listed by `BootServices::all_handles()`.
+#[cfg(feature = "exts")]
+#[derive(Debug, Clone)]
+pub struct ProtocolInfo {
+    guid: Guid,
+    open_information: Vec<OpenProtocolInformationEntry>,
+}
+
+#[cfg(feature = "exts")]
+impl ProtocolInfo {
+    /// GUID of the protocol.
+    pub fn guid(&self) -> &Guid {
+        &self.guid
+    }
+
+    /// Agents using the interface, like `BootServices::open_protocol_information()`
+    /// lists them.
+    pub fn open_information(&self) -> &[OpenProtocolInformationEntry] {
+        &self.open_information
+    }
+}
+
+/// Event whose notification function is a closure, created by
+/// `BootServices::create_event_with_closure()`.
+///
+/// The event is closed and the closure freed when this is dropped, so the
+/// event must not be closed with `BootServices::close_event()`.
+#[cfg(feature = "exts")]
+pub struct ClosureEvent<'a> {
+    boot_services: &'a BootServices,
+    event: Event,
+    ctx: *mut c_void,
+    drop_closure: unsafe fn(*mut c_void),
+}
+
+#[cfg(feature = "exts")]
+impl ClosureEvent<'_> {
+    /// Returns the event, to signal it or set its timer.
+    pub fn event(&self) -> Event {
+        self.event
+    }
+}
+
+#[cfg(feature = "exts")]
+impl Drop for ClosureEvent<'_> {
+    fn drop(&mut self) {
+        // No notification is pending nor running once the event is closed,
+        // since this is not `Send` and thus not dropped by one
+        let _ = unsafe { self.boot_services.close_event(self.event) };
+        unsafe { (self.drop_closure)(self.ctx) };
+    }
+}
+
 /// Type of allocation to perform.
 #[derive(Debug, Copy, Clone)]
 pub enum AllocateType {
@@ -754,6 +2536,9 @@ impl MemoryType {
 /// Memory descriptor version number
 pub const MEMORY_DESCRIPTOR_VERSION: u32 = 1;
 
+/// Size of the pages counted by memory descriptors, in bytes.
+pub const PAGE_SIZE: usize = 4096;
+
 /// A structure describing a region of memory.
 #[derive(Debug, Copy, Clone)]
 #[repr(C)]
@@ -785,6 +2570,15 @@ impl Default for MemoryDescriptor {
     }
 }
 
+impl MemoryDescriptor {
+    /// End of the physical range, or `None` if it overflows
+    fn phys_end(&self) -> Option<u64> {
+        self.page_count
+            .checked_mul(PAGE_SIZE as u64)
+            .and_then(|size| self.phys_start.checked_add(size))
+    }
+}
+
 impl Align for MemoryDescriptor {
     fn alignment() -> usize {
         mem::align_of::<Self>()
@@ -829,9 +2623,173 @@ bitflags! {
 #[repr(C)]
 pub struct MemoryMapKey(usize);
 
+/// Memory map returned by `BootServices::memory_map()`.
+///
+/// The firmware may space the descriptors by more than the size of a
+/// `MemoryDescriptor`, to leave room for future extensions of the structure.
+/// This type takes care of this stride, and keeps the extra bytes of each
+/// descriptor when reordering them.
+///
+/// The map only borrows its buffer, unless it was retrieved by
+/// `BootServices::memory_map_alloc()`. To keep the map after exiting the boot
+/// services, give it a buffer which lives forever, e.g. one allocated from a
+/// pool.
+pub struct MemoryMap<'buf> {
+    buffer: &'buf mut [u8],
+    /// Boot services which allocated the buffer from a pool, if any
+    pool: Option<&'buf BootServices>,
+    key: MemoryMapKey,
+    desc_size: usize,
+    desc_version: u32,
+    len: usize,
+}
+
+impl<'buf> MemoryMap<'buf> {
+    /// Key identifying the memory map, as needed to exit the boot services.
+    ///
+    /// The key only stays valid until the memory map changes.
+    pub fn key(&self) -> MemoryMapKey {
+        self.key
+    }
+
+    /// Distance between two descriptors in the buffer, in bytes.
+    pub fn desc_size(&self) -> usize {
+        self.desc_size
+    }
+
+    /// Version of the descriptors, as in `MEMORY_DESCRIPTOR_VERSION`.
+    pub fn desc_version(&self) -> u32 {
+        self.desc_version
+    }
+
+    /// Number of descriptors in the map.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the map has no descriptor.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the descriptor at `index`, if any.
+    pub fn get(&self, index: usize) -> Option<&MemoryDescriptor> {
+        if index < self.len {
+            let ptr = self.buffer[index * self.desc_size..].as_ptr();
+            Some(unsafe { &*(ptr as *const MemoryDescriptor) })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the descriptor at `index` for modification, if any.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut MemoryDescriptor> {
+        if index < self.len {
+            let ptr = self.buffer[index * self.desc_size..].as_mut_ptr();
+            Some(unsafe { &mut *(ptr as *mut MemoryDescriptor) })
+        } else {
+            None
+        }
+    }
+
+    /// Iterates over the descriptors, in the order of the map.
+    pub fn entries(&self) -> MemoryMapIter<'_> {
+        MemoryMapIter {
+            buffer: self.buffer,
+            entry_size: self.desc_size,
+            index: 0,
+            len: self.len,
+        }
+    }
+
+    /// Sorts the descriptors by physical address.
+    pub fn sort(&mut self) {
+        // The map is usually small and almost sorted, and cannot be sorted
+        // as a slice due to its stride
+        for i in 1..self.len {
+            let mut j = i;
+            while j > 0 && self.descriptor(j - 1).phys_start > self.descriptor(j).phys_start {
+                self.swap(j - 1, j);
+                j -= 1;
+            }
+        }
+    }
+
+    /// Sorts the descriptors by physical address, and merges the adjacent
+    /// ones which have the same type and attributes.
+    ///
+    /// The virtual start of a merged descriptor is that of its first part,
+    /// so this should be done before assigning virtual addresses.
+    pub fn merge(&mut self) {
+        self.sort();
+        if self.len == 0 {
+            return;
+        }
+        let mut last = 0;
+        for i in 1..self.len {
+            let (previous, next) = (self.descriptor(last), self.descriptor(i));
+            let mergeable = previous.ty == next.ty
+                && previous.att == next.att
+                && previous.phys_end() == Some(next.phys_start);
+            if mergeable {
+                let page_count = next.page_count;
+                self.get_mut(last).unwrap().page_count += page_count;
+            } else {
+                last += 1;
+                if last != i {
+                    let size = self.desc_size;
+                    self.buffer
+                        .copy_within(i * size..(i + 1) * size, last * size);
+                }
+            }
+        }
+        self.len = last + 1;
+    }
+
+    /// Returns the descriptor of the range containing the physical address
+    /// `address`, if any.
+    pub fn find(&self, address: u64) -> Option<&MemoryDescriptor> {
+        self.entries().find(|desc| {
+            address >= desc.phys_start
+                && (address - desc.phys_start) / (PAGE_SIZE as u64) < desc.page_count
+        })
+    }
+
+    /// Descriptor at `index`, which must be in the map
+    fn descriptor(&self, index: usize) -> &MemoryDescriptor {
+        self.get(index)
+            .expect("Memory descriptor index out of bounds")
+    }
+
+    /// Swaps the descriptors at `i` and `j`, with `i < j`, including their
+    /// extra bytes
+    fn swap(&mut self, i: usize, j: usize) {
+        let size = self.desc_size;
+        let (low, high) = self.buffer.split_at_mut(j * size);
+        low[i * size..(i + 1) * size].swap_with_slice(&mut high[..size]);
+    }
+}
+
+impl Drop for MemoryMap<'_> {
+    fn drop(&mut self) {
+        if let Some(boot_services) = self.pool {
+            let _ = boot_services.free_pool(self.buffer.as_mut_ptr());
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a MemoryMap<'_> {
+    type Item = &'a MemoryDescriptor;
+    type IntoIter = MemoryMapIter<'a>;
+
+    fn into_iter(self) -> MemoryMapIter<'a> {
+        self.entries()
+    }
+}
+
 /// An iterator of memory descriptors
 #[derive(Debug, Clone)]
-struct MemoryMapIter<'buf> {
+pub struct MemoryMapIter<'buf> {
     buffer: &'buf [u8],
     entry_size: usize,
     index: usize,
@@ -864,6 +2822,23 @@ impl<'buf> Iterator for MemoryMapIter<'buf> {
 
 impl ExactSizeIterator for MemoryMapIter<'_> {}
 
+newtype_enum! {
+    /// Interface type of a protocol interface
+    ///
+    /// Only one interface type is defined by the UEFI specification.
+    pub enum InterfaceType: u32 => {
+        /// Native interface
+        NATIVE_INTERFACE = 0,
+    }
+}
+
+/// Number of memory descriptors by which the memory map may grow between
+/// its size estimate and its retrieval, included in the estimate
+const MEMORY_MAP_EXTRA_ENTRIES: usize = 8;
+
+/// Interval at which `BootServices::stall_until()` checks its event.
+pub const STALL_POLL_INTERVAL: Duration = Duration::from_micros(100);
+
 /// The type of handle search to perform.
 #[derive(Debug, Copy, Clone)]
 pub enum SearchType<'guid> {
@@ -874,14 +2849,35 @@ pub enum SearchType<'guid> {
     /// If the protocol implements the `Protocol` interface,
     /// you can use the `from_proto` function to construct a new `SearchType`.
     ByProtocol(&'guid Guid),
-    // TODO: add ByRegisterNotify once the corresponding function is implemented.
+    /// Returns the next handle on which an interface was installed since the
+    /// last search, for a `BootServices::register_protocol_notify()`
+    /// registration.
+    ///
+    /// A single handle is returned per search, and `NotFound` once there are
+    /// none left.
+    ByRegisterNotify(ProtocolSearchKey),
 }
 
+/// Key of a protocol notification registration, returned by
+/// `BootServices::register_protocol_notify()`.
+#[derive(Debug, Copy, Clone)]
+#[repr(transparent)]
+pub struct ProtocolSearchKey(*mut c_void);
+
 impl<'guid> SearchType<'guid> {
     /// Constructs a new search type for a specified protocol.
     pub fn from_proto<P: Protocol>() -> Self {
         SearchType::ByProtocol(&P::GUID)
     }
+
+    /// Search type, protocol and search key of the raw interfaces
+    fn to_raw(self) -> (i32, *const Guid, *mut c_void) {
+        match self {
+            SearchType::AllHandles => (0, ptr::null(), ptr::null_mut()),
+            SearchType::ByRegisterNotify(key) => (1, ptr::null(), key.0),
+            SearchType::ByProtocol(guid) => (2, guid as *const _, ptr::null_mut()),
+        }
+    }
 }
 
 bitflags! {
@@ -917,9 +2913,93 @@ bitflags! {
     }
 }
 
+/// Event group signaled when `ExitBootServices()` is called, right after
+/// `EVENT_GROUP_BEFORE_EXIT_BOOT_SERVICES`.
+pub const EVENT_GROUP_EXIT_BOOT_SERVICES: Guid = Guid::from_values(
+    0x27abf055,
+    0xb1b8,
+    0x4c26,
+    0x8048,
+    [0x74, 0x8f, 0x37, 0xba, 0xa2, 0xdf],
+);
+
+/// Event group signaled when `ExitBootServices()` is called, before the
+/// memory map is checked. Since UEFI 2.8.
+pub const EVENT_GROUP_BEFORE_EXIT_BOOT_SERVICES: Guid = Guid::from_values(
+    0x8be0e274,
+    0x3970,
+    0x4b44,
+    0x80c5,
+    [0x1a, 0xb9, 0x50, 0x2f, 0x3b, 0xfc],
+);
+
+/// Event group signaled when `SetVirtualAddressMap()` is called.
+pub const EVENT_GROUP_VIRTUAL_ADDRESS_CHANGE: Guid = Guid::from_values(
+    0x13fa7698,
+    0xc831,
+    0x49c7,
+    0x87ea,
+    [0x8f, 0x43, 0xfc, 0xc2, 0x51, 0x96],
+);
+
+/// Event group signaled when the memory map changes.
+pub const EVENT_GROUP_MEMORY_MAP_CHANGE: Guid = Guid::from_values(
+    0x78bee926,
+    0x692f,
+    0x48fd,
+    0x9edb,
+    [0x01, 0x42, 0x2e, 0xf0, 0xd7, 0xab],
+);
+
+/// Event group signaled by the boot manager right before it boots an option.
+pub const EVENT_GROUP_READY_TO_BOOT: Guid = Guid::from_values(
+    0x7ce88fb3,
+    0x4bd7,
+    0x4679,
+    0x87a8,
+    [0xa8, 0xd8, 0xde, 0xe5, 0x0d, 0x2b],
+);
+
+/// Event group signaled when `ResetSystem()` is called. Since UEFI 2.7.
+pub const EVENT_GROUP_RESET_SYSTEM: Guid = Guid::from_values(
+    0x62da6a56,
+    0x13fb,
+    0x485a,
+    0xa8da,
+    [0xa3, 0xdd, 0x79, 0x12, 0xcb, 0x6b],
+);
+
+/// Value of the monotonic counter, returned by
+/// `BootServices::get_next_monotonic_count()`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct MonotonicCount(pub u64);
+
+impl MonotonicCount {
+    /// Upper 32 bits, which are incremented on each boot.
+    pub fn high(&self) -> u32 {
+        (self.0 >> 32) as u32
+    }
+
+    /// Lower 32 bits, which are incremented on each call during a boot.
+    pub fn low(&self) -> u32 {
+        self.0 as u32
+    }
+}
+
 /// Raw event notification function
 type EventNotifyFn = unsafe extern "efiapi" fn(event: Event, context: *mut c_void);
 
+/// Converts a duration to the microseconds of `stall()`, rounding it up
+fn micros(duration: Duration) -> u128 {
+    duration.as_nanos().saturating_add(999) / 1000
+}
+
+/// Converts a duration to the 100ns units of the timers, rounding it up
+fn hundreds_ns(duration: Duration) -> u64 {
+    let hundreds_ns = duration.as_nanos().saturating_add(99) / 100;
+    hundreds_ns.min(u128::from(u64::MAX)) as u64
+}
+
 /// Timer events manipulation
 pub enum TimerTrigger {
     /// Cancel event's timer
@@ -167,6 +167,27 @@ impl Time {
         }
     }
 
+    /// Build an all-zero UEFI time struct
+    ///
+    /// This is not a valid date, but some interfaces interpret it specially.
+    /// For example, zero times in a `FileInfo` passed to `File::set_info()`
+    /// are left unchanged.
+    pub const fn zero() -> Self {
+        Self {
+            year: 0,
+            month: 0,
+            day: 0,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            _pad1: 0,
+            nanosecond: 0,
+            time_zone: 0,
+            daylight: Daylight::empty(),
+            _pad2: 0,
+        }
+    }
+
     /// Query the year
     pub fn year(&self) -> u16 {
         self.year
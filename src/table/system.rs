@@ -4,10 +4,14 @@ use core::slice;
 use crate::proto::console::text;
 use crate::{CStr16, Char16, Handle, Result, ResultExt, Status};
 
-use super::boot::{BootServices, MemoryDescriptor};
+use super::boot::{BootServices, MemoryMap, MemoryType};
 use super::runtime::RuntimeServices;
 use super::{cfg, Header, Revision};
 
+/// Number of times `SystemTable::exit_boot_services` tries to exit the boot
+/// services, if the memory map keeps changing under its feet.
+const EXIT_BOOT_SERVICES_RETRIES: usize = 8;
+
 /// Marker trait used to provide different views of the UEFI System Table
 pub trait SystemTableView {}
 
@@ -126,34 +130,64 @@ impl SystemTable<Boot> {
     /// moment where boot services are exited, and calling the UEFI memory
     /// allocator will not be possible after the first attempt to exit the boot
     /// services. Therefore, UEFI applications are advised to allocate storage
-    /// for the memory map right before exiting boot services, and to allocate a
-    /// bit more storage than requested by memory_map_size.
+    /// for the memory map right before exiting boot services. The size estimate
+    /// leaves room for a few more descriptors.
+    ///
+    /// The firmware refuses to exit with `InvalidParameter` if the memory map
+    /// changed since it was fetched, e.g. because an event handler allocated
+    /// memory. The memory map is then fetched again, and exiting retried a few
+    /// times before giving up with that error.
     ///
     /// If `exit_boot_services` succeeds, it will return a runtime view of the
     /// system table which more accurately reflects the state of the UEFI
-    /// firmware following exit from boot services, along with a high-level
-    /// iterator to the UEFI memory map.
+    /// firmware following exit from boot services, along with the UEFI memory
+    /// map.
+    ///
+    /// The wrappers returned by the boot services borrow this table, by way
+    /// of the `&BootServices` returned by `boot_services()`: protocol
+    /// interfaces, scoped protocols, pool and page allocations, timers...
+    /// Consuming the table makes the compiler reject any later use of them,
+    /// such as a pool freed after the boot services are exited:
+    ///
+    /// ```compile_fail
+    /// # use uefi::prelude::*;
+    /// # use uefi::table::boot::MemoryType;
+    /// fn exit(image: Handle, st: SystemTable<Boot>) {
+    ///     let bt = st.boot_services();
+    ///     let pool = bt.allocate_pool_slice(MemoryType::LOADER_DATA, 16).unwrap_success();
+    ///     let _ = st.exit_boot_services_safe(image, MemoryType::LOADER_DATA);
+    ///     drop(pool);
+    /// }
+    /// ```
+    ///
+    /// This is not checked for everything though:
+    ///
+    /// - `Handle` and `Event` are plain `Copy` identifiers, which are not tied
+    ///   to this table. An event created before exiting the boot services
+    ///   stays alive afterwards, and its notification function may still run,
+    ///   e.g. for the events of `EVENT_GROUP_EXIT_BOOT_SERVICES`.
+    /// - A `ClosureEvent` or `Timer` which is leaked, e.g. with
+    ///   `mem::forget()`, is never closed.
+    /// - The copies of the table made by `unsafe_clone()`, such as the global
+    ///   table of `uefi-services`, escape the borrow.
     pub fn exit_boot_services(
         self,
         image: Handle,
         mmap_buf: &mut [u8],
-    ) -> Result<(
-        SystemTable<Runtime>,
-        impl ExactSizeIterator<Item = &MemoryDescriptor> + Clone,
-    )> {
+    ) -> Result<(SystemTable<Runtime>, MemoryMap<'_>)> {
         unsafe {
             let boot_services = self.boot_services();
 
-            loop {
+            for _ in 0..EXIT_BOOT_SERVICES_RETRIES {
                 // Fetch a memory map, propagate errors and split the completion
                 // FIXME: This sad pointer hack works around a current
                 //        limitation of the NLL analysis (see Rust bug 51526).
                 let mmap_buf = &mut *(mmap_buf as *mut [u8]);
                 let mmap_comp = boot_services.memory_map(mmap_buf)?;
-                let (mmap_status, (mmap_key, mmap_iter)) = mmap_comp.split();
+                let (mmap_status, mmap) = mmap_comp.split();
 
                 // Try to exit boot services using this memory map key
-                let result = boot_services.exit_boot_services(image, mmap_key);
+                let result = boot_services.exit_boot_services(image, mmap.key());
 
                 // Did we fail because the memory map was updated concurrently?
                 if result.status() == Status::INVALID_PARAMETER {
@@ -166,13 +200,53 @@ impl SystemTable<Boot> {
                             table: self.table,
                             _marker: PhantomData,
                         };
-                        comp.map(|_| (st, mmap_iter)).with_status(mmap_status)
+                        comp.map(|_| (st, mmap)).with_status(mmap_status)
                     });
                 }
             }
+            Err(Status::INVALID_PARAMETER.into())
         }
     }
 
+    /// Exit the UEFI boot services, storing the memory map in a pool of type
+    /// `memory_type`
+    ///
+    /// This works like `exit_boot_services`, but allocates the storage of the
+    /// memory map itself, with some room for the map to grow. Since no memory
+    /// can be freed once the boot services are exited, the pool is never
+    /// freed, which lets the memory map live forever. Loaders usually store
+    /// it in `MemoryType::LOADER_DATA`, which the OS can reclaim once it is
+    /// done with the map.
+    ///
+    /// The pool is allocated again if the memory map grew too much before
+    /// the boot services could be exited.
+    pub fn exit_boot_services_safe(
+        self,
+        image: Handle,
+        memory_type: MemoryType,
+    ) -> Result<(SystemTable<Runtime>, MemoryMap<'static>)> {
+        let boot_services = self.boot_services();
+        let mmap_buf = loop {
+            let size = boot_services.memory_map_size();
+            let pool = boot_services.allocate_pool(memory_type, size)?.log();
+            let mmap_buf = unsafe { slice::from_raw_parts_mut(pool, size) };
+
+            // Check that the memory map fits while memory can still be
+            // allocated, exiting the boot services fetches it again
+            let fits = boot_services.memory_map(&mut *mmap_buf).map(|_| ());
+            match fits {
+                Ok(_) => break mmap_buf,
+                Err(err) => {
+                    let _ = boot_services.free_pool(pool);
+                    if err.status() != Status::BUFFER_TOO_SMALL {
+                        return Err(err);
+                    }
+                }
+            }
+        };
+        self.exit_boot_services(image, mmap_buf)
+    }
+
     /// Clone this boot-time UEFI system table interface
     ///
     /// # Safety
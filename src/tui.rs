@@ -0,0 +1,590 @@
+//! Minimal text user interface widgets.
+//!
+//! The widgets of this module are drawn with the `SimpleTextOutput` protocol
+//! and driven by the `SimpleTextInput` protocol, which makes them usable in
+//! boot menus and installers before any graphics mode has been set. They only
+//! use the box drawing and block element characters which the UEFI
+//! specification requires every console to support.
+//!
+//! Interactive widgets can either be driven by the caller, which forwards the
+//! keys it reads to `handle_key()` and redraws them, or run until the user
+//! makes a choice with `run()` or `show()`.
+
+use crate::proto::console::text::{Color, ColorPair, Input, Key, Output, ScanCode};
+use crate::table::boot::BootServices;
+use crate::{Result, ResultExt, Status};
+use core::fmt::Write;
+
+/// Colors used to draw the widgets.
+#[derive(Debug, Copy, Clone)]
+pub struct Theme {
+    /// Colors of regular text.
+    pub text: ColorPair,
+    /// Colors of the selected item of a list.
+    pub highlight: ColorPair,
+    /// Colors of the frames and their titles.
+    pub border: ColorPair,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            text: ColorPair::new(Color::LightGray, Color::Black),
+            highlight: ColorPair::new(Color::Black, Color::LightGray),
+            border: ColorPair::new(Color::White, Color::Black),
+        }
+    }
+}
+
+/// Rectangular area of the screen, in characters.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Rect {
+    /// Column of the left edge.
+    pub column: usize,
+    /// Row of the top edge.
+    pub row: usize,
+    /// Number of columns.
+    pub width: usize,
+    /// Number of rows.
+    pub height: usize,
+}
+
+impl Rect {
+    /// Create an area from its top-left corner and size.
+    pub fn new(column: usize, row: usize, width: usize, height: usize) -> Self {
+        Self {
+            column,
+            row,
+            width,
+            height,
+        }
+    }
+
+    /// Create an area of the given size centered on a screen of
+    /// (columns, rows), and clipped to it.
+    pub fn centered(screen: (usize, usize), width: usize, height: usize) -> Self {
+        let width = width.min(screen.0);
+        let height = height.min(screen.1);
+        Self::new(
+            (screen.0 - width) / 2,
+            (screen.1 - height) / 2,
+            width,
+            height,
+        )
+    }
+
+    /// Returns the area inside of a one character wide frame.
+    pub fn inner(&self) -> Self {
+        Self::new(
+            self.column + 1,
+            self.row + 1,
+            self.width.saturating_sub(2),
+            self.height.saturating_sub(2),
+        )
+    }
+}
+
+/// Returns the (columns, rows) of the current mode of `output`.
+pub fn screen_size(output: &Output) -> (usize, usize) {
+    match output.current_mode() {
+        Ok(mode) => match mode.log() {
+            Some(mode) => (mode.columns(), mode.rows()),
+            None => (80, 25),
+        },
+        Err(_) => (80, 25),
+    }
+}
+
+/// Draws a frame around `area` with an optional title on its top edge, and
+/// clears its inside.
+pub fn draw_frame(output: &mut Output, area: Rect, title: Option<&str>, theme: &Theme) -> Result {
+    if area.width < 2 || area.height < 2 {
+        return Ok(().into());
+    }
+    let mut painter = Painter::new(output);
+    let inner = area.width - 2;
+    let bottom = area.row + area.height - 1;
+
+    painter.set_color(theme.border)?;
+    painter.move_to(area.column, area.row)?;
+    painter.put('\u{250c}', 1)?;
+    painter.put('\u{2500}', inner)?;
+    painter.put('\u{2510}', 1)?;
+    for row in area.row + 1..bottom {
+        painter.move_to(area.column, row)?;
+        painter.put('\u{2502}', 1)?;
+        painter.set_color(theme.text)?;
+        painter.put(' ', inner)?;
+        painter.set_color(theme.border)?;
+        painter.put('\u{2502}', 1)?;
+    }
+    painter.move_to(area.column, bottom)?;
+    painter.put('\u{2514}', 1)?;
+    painter.put('\u{2500}', inner)?;
+    painter.put('\u{2518}', 1)?;
+
+    if let Some(title) = title {
+        if inner > 2 {
+            painter.move_to(area.column + 1, area.row)?;
+            painter.put(' ', 1)?;
+            painter.text(title, inner - 2)?;
+            painter.put(' ', 1)?;
+        }
+    }
+    Ok(().into())
+}
+
+/// Outcome of a key press on an interactive widget.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Selection {
+    /// No choice has been made yet.
+    Pending,
+    /// The item with the given index was chosen by pressing Enter.
+    Selected(usize),
+    /// The choice was cancelled by pressing Escape.
+    Cancelled,
+}
+
+/// Scrollable list of items of which one is selected.
+///
+/// The selection is moved with the Up/Down, Page Up/Page Down and Home/End
+/// keys, and confirmed with Enter.
+pub struct SelectList<'a> {
+    items: &'a [&'a str],
+    area: Rect,
+    theme: Theme,
+    selected: usize,
+    top: usize,
+}
+
+impl<'a> SelectList<'a> {
+    /// Create a list showing `items` in `area`, with the first one selected.
+    pub fn new(items: &'a [&'a str], area: Rect) -> Self {
+        Self {
+            items,
+            area,
+            theme: Theme::default(),
+            selected: 0,
+            top: 0,
+        }
+    }
+
+    /// Sets the colors of the list.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    /// Returns the index of the selected item.
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Selects an item, scrolling the list to show it if needed.
+    ///
+    /// Indices past the end of the list select the last item.
+    pub fn set_selected(&mut self, index: usize) {
+        self.selected = index.min(self.items.len().saturating_sub(1));
+        let height = self.area.height.max(1);
+        if self.selected < self.top {
+            self.top = self.selected;
+        } else if self.selected >= self.top + height {
+            self.top = self.selected + 1 - height;
+        }
+    }
+
+    /// Draws the visible items, highlighting the selected one.
+    pub fn draw(&self, output: &mut Output) -> Result {
+        let mut painter = Painter::new(output);
+        for line in 0..self.area.height {
+            let index = self.top + line;
+            let colors = if index == self.selected {
+                self.theme.highlight
+            } else {
+                self.theme.text
+            };
+            painter.set_color(colors)?;
+            painter.move_to(self.area.column, self.area.row + line)?;
+            let item = self.items.get(index).copied().unwrap_or("");
+            painter.put(' ', 1)?;
+            let written = painter.text(item, self.area.width.saturating_sub(1))?;
+            painter.put(' ', self.area.width.saturating_sub(1 + written))?;
+        }
+        Ok(().into())
+    }
+
+    /// Updates the selection according to a key press.
+    pub fn handle_key(&mut self, key: Key) -> Selection {
+        let page = self.area.height.max(1);
+        match key {
+            Key::Printable(c) if u16::from(c) == 0x0d => {
+                if self.items.is_empty() {
+                    Selection::Pending
+                } else {
+                    Selection::Selected(self.selected)
+                }
+            }
+            Key::Special(ScanCode::ESCAPE) => Selection::Cancelled,
+            Key::Special(ScanCode::UP) => {
+                self.set_selected(self.selected.saturating_sub(1));
+                Selection::Pending
+            }
+            Key::Special(ScanCode::DOWN) => {
+                self.set_selected(self.selected + 1);
+                Selection::Pending
+            }
+            Key::Special(ScanCode::PAGE_UP) => {
+                self.set_selected(self.selected.saturating_sub(page));
+                Selection::Pending
+            }
+            Key::Special(ScanCode::PAGE_DOWN) => {
+                self.set_selected(self.selected + page);
+                Selection::Pending
+            }
+            Key::Special(ScanCode::HOME) => {
+                self.set_selected(0);
+                Selection::Pending
+            }
+            Key::Special(ScanCode::END) => {
+                self.set_selected(self.items.len());
+                Selection::Pending
+            }
+            _ => Selection::Pending,
+        }
+    }
+
+    /// Lets the user choose an item, returning its index, or `None` if the
+    /// choice was cancelled.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if there was an issue with the input or output device
+    pub fn run(
+        &mut self,
+        bt: &BootServices,
+        input: &mut Input,
+        output: &mut Output,
+    ) -> Result<Option<usize>> {
+        loop {
+            self.draw(output)?.log();
+            match self.handle_key(read_key(bt, input)?) {
+                Selection::Pending => {}
+                Selection::Selected(index) => return Ok(Some(index).into()),
+                Selection::Cancelled => return Ok(None.into()),
+            }
+        }
+    }
+}
+
+/// List of choices shown in a titled frame at the center of the screen.
+pub struct Menu<'a> {
+    title: &'a str,
+    items: &'a [&'a str],
+    theme: Theme,
+    selected: usize,
+}
+
+impl<'a> Menu<'a> {
+    /// Create a menu offering the given choices.
+    pub fn new(title: &'a str, items: &'a [&'a str]) -> Self {
+        Self {
+            title,
+            items,
+            theme: Theme::default(),
+            selected: 0,
+        }
+    }
+
+    /// Sets the colors of the menu.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    /// Sets the item selected when the menu is shown.
+    pub fn set_selected(&mut self, index: usize) {
+        self.selected = index;
+    }
+
+    /// Returns the area covered by the menu on a screen of (columns, rows).
+    pub fn area(&self, screen: (usize, usize)) -> Rect {
+        let widest = self
+            .items
+            .iter()
+            .map(|item| item.chars().count())
+            .chain(Some(self.title.chars().count()))
+            .max()
+            .unwrap_or(0);
+        // Frame, and a space on each side of the items
+        Rect::centered(screen, widest + 4, self.items.len() + 2)
+    }
+
+    /// Shows the menu until the user chooses an item, returning its index,
+    /// or `None` if the choice was cancelled.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if there was an issue with the input or output device
+    pub fn run(
+        &mut self,
+        bt: &BootServices,
+        input: &mut Input,
+        output: &mut Output,
+    ) -> Result<Option<usize>> {
+        let area = self.area(screen_size(output));
+        draw_frame(output, area, Some(self.title), &self.theme)?.log();
+        let mut list = SelectList::new(self.items, area.inner());
+        list.set_theme(self.theme);
+        list.set_selected(self.selected);
+        let choice = list.run(bt, input, output)?.log();
+        self.selected = list.selected();
+        Ok(choice.into())
+    }
+}
+
+/// Horizontal bar showing the progress of an operation.
+pub struct ProgressBar {
+    column: usize,
+    row: usize,
+    width: usize,
+    theme: Theme,
+}
+
+impl ProgressBar {
+    /// Create a bar of `width` characters, including the percentage, starting
+    /// at the given (column, row).
+    pub fn new(column: usize, row: usize, width: usize) -> Self {
+        Self {
+            column,
+            row,
+            width,
+            theme: Theme::default(),
+        }
+    }
+
+    /// Sets the colors of the bar.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    /// Draws the bar filled to the fraction `done / total`, followed by the
+    /// matching percentage.
+    ///
+    /// `done` values larger than `total` are shown as complete.
+    pub fn draw(&self, output: &mut Output, done: usize, total: usize) -> Result {
+        let done = done.min(total);
+        let percent = (done * 100).checked_div(total).unwrap_or(100);
+        // Room for " 100%"
+        let bar = self.width.saturating_sub(5);
+        let filled = (done * bar).checked_div(total).unwrap_or(bar);
+
+        let mut painter = Painter::new(output);
+        painter.set_color(self.theme.text)?;
+        painter.move_to(self.column, self.row)?;
+        painter.put('\u{2588}', filled)?;
+        painter.put('\u{2591}', bar - filled)?;
+        let mut label = Label::default();
+        let _ = write!(label, "{:>4}%", percent);
+        painter.text(label.as_str(), self.width - bar)?;
+        Ok(().into())
+    }
+}
+
+/// Text shown in a titled frame at the center of the screen, until a key is
+/// pressed.
+pub struct MessageBox<'a> {
+    title: &'a str,
+    text: &'a str,
+    theme: Theme,
+}
+
+impl<'a> MessageBox<'a> {
+    /// Create a message box. Lines of the text are separated by `\n`.
+    pub fn new(title: &'a str, text: &'a str) -> Self {
+        Self {
+            title,
+            text,
+            theme: Theme::default(),
+        }
+    }
+
+    /// Sets the colors of the message box.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    /// Returns the area covered by the message box on a screen of
+    /// (columns, rows).
+    pub fn area(&self, screen: (usize, usize)) -> Rect {
+        let widest = self
+            .text
+            .lines()
+            .map(|line| line.chars().count())
+            .chain(Some(self.title.chars().count()))
+            .max()
+            .unwrap_or(0);
+        Rect::centered(screen, widest + 4, self.text.lines().count() + 2)
+    }
+
+    /// Draws the message box.
+    pub fn draw(&self, output: &mut Output) -> Result {
+        let area = self.area(screen_size(output));
+        draw_frame(output, area, Some(self.title), &self.theme)?.log();
+        let inner = area.inner();
+        let mut painter = Painter::new(output);
+        painter.set_color(self.theme.text)?;
+        for (row, line) in self.text.lines().take(inner.height).enumerate() {
+            painter.move_to(inner.column + 1, inner.row + row)?;
+            painter.text(line, inner.width.saturating_sub(2))?;
+        }
+        Ok(().into())
+    }
+
+    /// Draws the message box and waits for a key to be pressed, which is
+    /// returned.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if there was an issue with the input or output device
+    pub fn show(&self, bt: &BootServices, input: &mut Input, output: &mut Output) -> Result<Key> {
+        self.draw(output)?.log();
+        Ok(read_key(bt, input)?.into())
+    }
+}
+
+/// Writes to an output device, clipping the text to the screen, and
+/// restores its colors once dropped
+struct Painter<'out, 'boot> {
+    output: &'out mut Output<'boot>,
+    saved_colors: ColorPair,
+    columns: usize,
+    rows: usize,
+    /// Position of the next character
+    column: usize,
+    row: usize,
+}
+
+impl<'out, 'boot> Painter<'out, 'boot> {
+    fn new(output: &'out mut Output<'boot>) -> Self {
+        let (columns, rows) = screen_size(output);
+        let saved_colors = output.colors();
+        Self {
+            output,
+            saved_colors,
+            columns,
+            rows,
+            column: columns,
+            row: rows,
+        }
+    }
+
+    fn set_color(&mut self, colors: ColorPair) -> StatusResult {
+        self.output
+            .set_colors(colors)
+            .warning_as_error()
+            .map_err(|e| e.status())
+    }
+
+    fn move_to(&mut self, column: usize, row: usize) -> StatusResult {
+        self.column = column;
+        self.row = row;
+        if column >= self.columns || row >= self.rows {
+            return Ok(());
+        }
+        self.output
+            .set_cursor_position(column, row)
+            .warning_as_error()
+            .map_err(|e| e.status())
+    }
+
+    /// Returns how many characters can be written on the current line.
+    ///
+    /// Writing to the bottom-right cell would scroll the screen.
+    fn room(&self) -> usize {
+        if self.row >= self.rows {
+            0
+        } else if self.row + 1 == self.rows {
+            self.columns.saturating_sub(self.column + 1)
+        } else {
+            self.columns.saturating_sub(self.column)
+        }
+    }
+
+    /// Repeats a character
+    fn put(&mut self, c: char, count: usize) -> StatusResult {
+        let count = count.min(self.room());
+        let mut buf = [0; 128];
+        let width = c.len_utf8();
+        let mut remaining = count;
+        while remaining > 0 {
+            let chunk = remaining.min(buf.len() / width);
+            for i in 0..chunk {
+                c.encode_utf8(&mut buf[i * width..]);
+            }
+            let s = core::str::from_utf8(&buf[..chunk * width]).unwrap();
+            self.output.write_str(s).map_err(|_| Status::DEVICE_ERROR)?;
+            remaining -= chunk;
+        }
+        self.column += count;
+        Ok(())
+    }
+
+    /// Writes at most `max` characters of `s`, returning how many were
+    /// written
+    fn text(&mut self, s: &str, max: usize) -> core::result::Result<usize, Status> {
+        let count = max.min(self.room());
+        let end = s.char_indices().nth(count).map_or(s.len(), |(i, _)| i);
+        let s = &s[..end];
+        let written = s.chars().count();
+        self.output.write_str(s).map_err(|_| Status::DEVICE_ERROR)?;
+        self.column += written;
+        Ok(written)
+    }
+}
+
+impl Drop for Painter<'_, '_> {
+    fn drop(&mut self) {
+        let _ = self.output.set_colors(self.saved_colors);
+    }
+}
+
+type StatusResult = core::result::Result<(), Status>;
+
+/// Short text formatted on the stack
+#[derive(Default)]
+struct Label {
+    buf: [u8; 8],
+    len: usize,
+}
+
+impl Label {
+    fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.buf[..self.len]).unwrap_or("")
+    }
+}
+
+impl Write for Label {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let end = self.len + s.len();
+        if end > self.buf.len() {
+            return Err(core::fmt::Error);
+        }
+        self.buf[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// Wait for a key to be pressed
+fn read_key(bt: &BootServices, input: &mut Input) -> core::result::Result<Key, Status> {
+    loop {
+        if let Some(key) = input
+            .read_key()
+            .warning_as_error()
+            .map_err(|e| e.status())?
+        {
+            return Ok(key);
+        }
+        bt.wait_for_event(&mut [input.wait_for_key_event()])
+            .warning_as_error()
+            .map_err(|e| e.status())?;
+    }
+}
@@ -1,7 +1,8 @@
 //! This crate simplifies the writing of higher-level code for UEFI.
 //!
-//! It initializes the memory allocation and logging crates,
-//! allowing code to use Rust's data structures and to log errors.
+//! It initializes the memory allocation and logging crates, and the
+//! printing macros, allowing code to use Rust's data structures, to log
+//! errors and to print text with `uefi::println!`.
 //!
 //! It also stores a global reference to the UEFI system table,
 //! in order to reduce the redundant passing of references to it.
@@ -73,6 +74,7 @@ pub fn init(st: &SystemTable<Boot>) -> Result {
         // Setup logging and memory allocation
         let boot_services = st.boot_services();
         init_logger(st);
+        uefi::print::init(st);
         uefi::alloc::init(boot_services);
 
         // Schedule these tools to be disabled on exit from UEFI boot services
@@ -120,6 +122,7 @@ fn exit_boot_services(_e: Event) {
             logger.disable();
         }
     }
+    uefi::print::exit_boot_services();
     uefi::alloc::exit_boot_services();
 }
 
@@ -8,11 +8,15 @@ pub fn test(bt: &BootServices) {
     info!("Testing memory functions");
 
     allocate_pages(bt);
+    allocate_pages_scoped(bt);
+    allocate_pool_box(bt);
     vec_alloc();
     alloc_alignment();
     memmove(bt);
+    set_mem64(bt);
 
     memory_map(bt);
+    memory_map_alloc(bt);
 }
 
 fn allocate_pages(bt: &BootServices) {
@@ -37,6 +41,56 @@ fn allocate_pages(bt: &BootServices) {
     bt.free_pages(pgs, 1).unwrap_success();
 }
 
+fn allocate_pages_scoped(bt: &BootServices) {
+    info!("Allocating some scoped pages of memory");
+
+    let mut pages = bt
+        .allocate_pages_scoped(AllocateType::AnyPages, MemoryType::LOADER_DATA, 2)
+        .expect_success("Failed to allocate pages of memory");
+    assert_eq!(
+        pages.address() % 4096,
+        0,
+        "Page pointer is not page-aligned"
+    );
+    assert_eq!(pages.len(), 2 * 4096);
+    assert!(pages.iter().all(|&byte| byte == 0), "Pages are not zeroed");
+    pages[0] = 0xF0;
+    pages[8191] = 0x23;
+
+    // The same pages can be allocated again once they were freed.
+    let address = pages.address();
+    drop(pages);
+    let pages = bt
+        .allocate_pages_scoped_at(address as usize, MemoryType::LOADER_DATA, 2)
+        .expect_success("Failed to allocate the freed pages");
+    assert_eq!(pages.address(), address);
+}
+
+fn allocate_pool_box(bt: &BootServices) {
+    info!("Allocating values from a pool");
+
+    let mut value = bt
+        .allocate_pool_box(MemoryType::LOADER_DATA, [1u64, 2, 3])
+        .expect_success("Failed to allocate a value from a pool");
+    value[2] = 4;
+    assert_eq!(*value, [1, 2, 4]);
+
+    let mut buffer = bt
+        .allocate_pool_slice(MemoryType::LOADER_DATA, 100)
+        .expect_success("Failed to allocate a buffer from a pool");
+    assert_eq!(buffer.len(), 100);
+    assert!(buffer.iter().all(|&byte| byte == 0), "Buffer is not zeroed");
+    buffer[99] = 0x23;
+
+    // Values which are not aligned enough cannot be stored in a pool.
+    #[repr(align(0x100))]
+    struct Block([u8; 0x100]);
+
+    assert!(bt
+        .allocate_pool_box(MemoryType::LOADER_DATA, Block([0; 0x100]))
+        .is_err());
+}
+
 // Simple test to ensure our custom allocator works with the `alloc` crate.
 fn vec_alloc() {
     info!("Allocating a vector through the `alloc` crate");
@@ -81,14 +135,33 @@ fn memmove(bt: &BootServices) {
     assert_eq!(dest, src, "Failed to copy memory");
 }
 
+// Test that the `set_mem64` function works, whatever the alignment.
+fn set_mem64(bt: &BootServices) {
+    info!("Testing the `set_mem64` function");
+
+    let mut buffer = [0u8; 8 * 5 + 1];
+    let value = 0x0123_4567_89ab_cdef_u64;
+    unsafe {
+        bt.set_mem64(buffer[1..].as_mut_ptr() as *mut u64, 5, value);
+    }
+    assert_eq!(buffer[0], 0, "Wrote before the buffer");
+    for chunk in buffer[1..].chunks(8) {
+        assert_eq!(chunk, value.to_ne_bytes(), "Failed to set memory");
+    }
+
+    let mut buffer = [0u64; 3];
+    unsafe {
+        bt.set_mem64(buffer.as_mut_ptr(), buffer.len(), u64::MAX);
+    }
+    assert_eq!(buffer, [u64::MAX; 3], "Failed to set memory");
+}
+
 fn memory_map(bt: &BootServices) {
     info!("Testing memory map functions");
 
-    // Get an estimate of the memory map size.
-    let map_sz = bt.memory_map_size();
-
-    // 8 extra descriptors should be enough.
-    let buf_sz = map_sz + 8 * mem::size_of::<MemoryDescriptor>();
+    // Get an estimate of the memory map size, which leaves room for the
+    // descriptors added by allocating the buffer.
+    let buf_sz = bt.memory_map_size();
 
     // We will use vectors for convencience.
     let mut buffer = Vec::with_capacity(buf_sz);
@@ -97,12 +170,16 @@ fn memory_map(bt: &BootServices) {
         buffer.set_len(buf_sz);
     }
 
-    let (_key, desc_iter) = bt
+    let mut map = bt
         .memory_map(&mut buffer)
         .expect_success("Failed to retrieve UEFI memory map");
+    assert!(
+        map.desc_size() >= mem::size_of::<MemoryDescriptor>(),
+        "Memory descriptors are too small"
+    );
 
     // Collect the descriptors into a vector
-    let descriptors = desc_iter.copied().collect::<Vec<_>>();
+    let descriptors = map.entries().copied().collect::<Vec<_>>();
 
     // Ensured we have at least one entry.
     // Real memory maps usually have dozens of entries.
@@ -118,4 +195,43 @@ fn memory_map(bt: &BootServices) {
     }
     let page_count = first_desc.page_count;
     assert!(page_count != 0, "Memory map entry has zero size");
+
+    // Merging must keep the map sorted, and cover the same addresses
+    map.merge();
+    assert!(map.len() <= descriptors.len(), "Merging added descriptors");
+    let mut previous_start = None;
+    for desc in &map {
+        assert!(
+            previous_start.map_or(true, |start| start < desc.phys_start),
+            "Memory map is not sorted"
+        );
+        previous_start = Some(desc.phys_start);
+    }
+    let pages = |descs: &mut dyn Iterator<Item = &MemoryDescriptor>| {
+        descs.map(|desc| desc.page_count).sum::<u64>()
+    };
+    assert_eq!(
+        pages(&mut map.entries()),
+        pages(&mut descriptors.iter()),
+        "Merging changed the size of the memory map"
+    );
+
+    let last_desc = descriptors[descriptors.len() - 1];
+    let found = map
+        .find(last_desc.phys_start)
+        .expect("Failed to find a memory range by address");
+    assert_eq!(found.ty, last_desc.ty);
+}
+
+fn memory_map_alloc(bt: &BootServices) {
+    info!("Retrieving the memory map into a pool");
+
+    let map = bt
+        .memory_map_alloc(MemoryType::LOADER_DATA)
+        .expect_success("Failed to retrieve UEFI memory map");
+    assert!(!map.is_empty(), "Memory map is empty");
+    let code = map
+        .find(memory_map_alloc as fn(&BootServices) as usize as u64)
+        .expect("The code of the image is not in the memory map");
+    assert_eq!(code.ty, MemoryType::LOADER_CODE);
 }
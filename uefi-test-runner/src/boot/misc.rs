@@ -1,14 +1,127 @@
+use alloc::sync::Arc;
+use core::mem;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::time::Duration;
 use uefi::prelude::*;
 use uefi::table::boot::{BootServices, EventType, TimerTrigger, Tpl};
+use uefi::table::Header;
+use uefi::{CStr16, Guid};
 
 pub fn test(bt: &BootServices) {
     info!("Testing timer...");
     test_timer(bt);
+    test_timer_duration(bt);
+    info!("Testing stalls...");
+    test_stall(bt);
+    info!("Testing waits with a timeout...");
+    test_wait_for_any(bt);
+    info!("Testing closure events...");
+    test_closure_event(bt);
+    info!("Testing event groups...");
+    test_event_group(bt);
+    info!("Testing task priority levels...");
+    test_tpl(bt);
+    info!("Testing configuration tables...");
+    test_configuration_table(bt);
+    info!("Testing CRC computations...");
+    test_crc32(bt);
+    info!("Testing monotonic counter...");
+    test_monotonic_count(bt);
     info!("Testing watchdog...");
     test_watchdog(bt);
 }
 
+fn test_tpl(bt: &BootServices) {
+    let count = Arc::new(AtomicUsize::new(0));
+    let notified = count.clone();
+    let event = bt
+        .create_event_with_closure(EventType::NOTIFY_SIGNAL, Tpl::CALLBACK, move |_| {
+            notified.fetch_add(1, Ordering::SeqCst);
+        })
+        .expect_success("Failed to create closure event");
+    {
+        let guard = unsafe { bt.raise_tpl(Tpl::NOTIFY) };
+        assert_eq!(guard.old_tpl(), Tpl::APPLICATION);
+        bt.signal_event(event.event())
+            .expect_success("Failed to signal closure event");
+        // The notification is held back until the priority level is restored
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+    }
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+}
+
+fn test_configuration_table(bt: &BootServices) {
+    const TEST_TABLE_GUID: Guid = Guid::from_values(
+        0x9b4e_27d1,
+        0x53a0,
+        0x4c8e,
+        0x8f12,
+        [0x0a, 0x6c, 0x3b, 0xe4, 0x71, 0x5d],
+    );
+
+    let data = *b"uefi-rs test table";
+    let table = bt
+        .install_configuration_table_data(&TEST_TABLE_GUID, &data)
+        .expect_success("Failed to install configuration table");
+    assert_eq!(
+        unsafe { core::slice::from_raw_parts(table, data.len()) },
+        &data
+    );
+    unsafe { bt.install_configuration_table(&TEST_TABLE_GUID, core::ptr::null()) }
+        .expect_success("Failed to remove configuration table");
+    bt.free_pool(table as *mut u8)
+        .expect_success("Failed to free configuration table");
+}
+
+fn test_crc32(bt: &BootServices) {
+    let crc32 = bt
+        .calculate_crc32(b"123456789")
+        .expect_success("Failed to compute CRC");
+    assert_eq!(crc32, 0xcbf4_3926);
+
+    // A table made of a header followed by some data
+    let mut table = [0u64; 8];
+    let header = table.as_mut_ptr() as *mut Header;
+    unsafe {
+        (*header).size = mem::size_of_val(&table) as u32;
+        bt.update_table_crc32(header)
+            .expect_success("Failed to update table CRC");
+        assert!(bt
+            .verify_table_crc32(header)
+            .expect_success("Failed to verify table CRC"));
+    }
+    table[7] = 1;
+    let header = table.as_mut_ptr() as *mut Header;
+    assert!(!unsafe { bt.verify_table_crc32(header) }.expect_success("Failed to verify table CRC"));
+}
+
+fn test_monotonic_count(bt: &BootServices) {
+    let first = bt
+        .get_next_monotonic_count()
+        .expect_success("Failed to read monotonic counter");
+    let second = bt
+        .get_next_monotonic_count()
+        .expect_success("Failed to read monotonic counter");
+    assert!(second > first);
+    assert_eq!(second.high(), first.high());
+    assert_eq!(second.low(), first.low() + 1);
+}
+
 fn test_watchdog(bt: &BootServices) {
+    let message = [
+        u16::from(b'T'),
+        u16::from(b'e'),
+        u16::from(b's'),
+        u16::from(b't'),
+        0,
+    ];
+    let message =
+        CStr16::from_u16_with_nul(&message).unwrap_or_else(|_| panic!("Invalid watchdog message"));
+    bt.arm_watchdog(Duration::from_secs(300), 0x10000, Some(message))
+        .expect_success("Could not arm watchdog timer");
+    bt.disable_watchdog()
+        .expect_success("Could not disable watchdog timer");
+
     // Disable the UEFI watchdog timer
     bt.set_watchdog_timer(0, 0x10000, None)
         .expect_success("Could not set watchdog timer");
@@ -23,3 +136,114 @@ fn test_timer(bt: &BootServices) {
     bt.wait_for_event(&mut events)
         .expect_success("Wait for event failed");
 }
+
+fn test_timer_duration(bt: &BootServices) {
+    let timer = bt.create_timer().expect_success("Failed to create timer");
+    assert!(!timer.is_expired().expect_success("Failed to check timer"));
+    timer
+        .set_relative(Duration::from_millis(1))
+        .expect_success("Failed to set timer");
+    timer.wait().expect_success("Failed to wait for timer");
+
+    timer
+        .set_periodic(Duration::from_micros(100))
+        .expect_success("Failed to set periodic timer");
+    bt.stall(10_000);
+    assert!(timer.is_expired().expect_success("Failed to check timer"));
+    timer.cancel().expect_success("Failed to cancel timer");
+}
+
+fn test_stall(bt: &BootServices) {
+    bt.stall_for(Duration::from_millis(1));
+    // Sub-microsecond durations are rounded up rather than ignored
+    bt.stall_for(Duration::from_nanos(1));
+
+    let timer = bt.create_timer().expect_success("Failed to create timer");
+    let signaled = bt
+        .stall_until(timer.event(), Duration::from_millis(1))
+        .expect_success("Failed to poll timer");
+    assert!(!signaled);
+    timer
+        .set_relative(Duration::from_millis(1))
+        .expect_success("Failed to set timer");
+    let signaled = bt
+        .stall_until(timer.event(), Duration::from_secs(5))
+        .expect_success("Failed to poll timer");
+    assert!(signaled);
+}
+
+fn test_wait_for_any(bt: &BootServices) {
+    let never = bt.create_timer().expect_success("Failed to create timer");
+    let soon = bt.create_timer().expect_success("Failed to create timer");
+    soon.set_relative(Duration::from_millis(1))
+        .expect_success("Failed to set timer");
+
+    let events = [never.event(), soon.event()];
+    let fired = bt
+        .wait_for_any(&events, Some(Duration::from_secs(5)))
+        .expect_success("Failed to wait for events");
+    assert_eq!(fired, Some(1));
+
+    let fired = bt
+        .wait_for_any(&events, Some(Duration::from_millis(1)))
+        .expect_success("Failed to wait for events");
+    assert_eq!(fired, None);
+    let signaled = bt
+        .wait_for_event_timeout(never.event(), Duration::from_millis(1))
+        .expect_success("Failed to wait for event");
+    assert!(!signaled);
+}
+
+fn test_closure_event(bt: &BootServices) {
+    let count = Arc::new(AtomicUsize::new(0));
+    let notified = count.clone();
+    let event = bt
+        .create_event_with_closure(EventType::NOTIFY_SIGNAL, Tpl::CALLBACK, move |_| {
+            notified.fetch_add(1, Ordering::SeqCst);
+        })
+        .expect_success("Failed to create closure event");
+    bt.signal_event(event.event())
+        .expect_success("Failed to signal closure event");
+    // The notification runs as soon as the priority level drops back
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+    drop(event);
+}
+
+fn test_event_group(bt: &BootServices) {
+    const TEST_EVENT_GROUP: Guid = Guid::from_values(
+        0x3c1f_5a27,
+        0x8e0b,
+        0x4d6f,
+        0xb2a4,
+        [0x6d, 0x19, 0xe0, 0x73, 0xc5, 0x8a],
+    );
+
+    let count = Arc::new(AtomicUsize::new(0));
+    let notify = |count: Arc<AtomicUsize>| {
+        move |_| {
+            count.fetch_add(1, Ordering::SeqCst);
+        }
+    };
+    let first = bt
+        .create_event_ex_with_closure(
+            EventType::NOTIFY_SIGNAL,
+            Tpl::CALLBACK,
+            &TEST_EVENT_GROUP,
+            notify(count.clone()),
+        )
+        .expect_success("Failed to create event in group");
+    let second = bt
+        .create_event_ex_with_closure(
+            EventType::NOTIFY_SIGNAL,
+            Tpl::CALLBACK,
+            &TEST_EVENT_GROUP,
+            notify(count.clone()),
+        )
+        .expect_success("Failed to create event in group");
+
+    // Signaling an event of the group signals all of them
+    bt.signal_event(first.event())
+        .expect_success("Failed to signal event group");
+    assert_eq!(count.load(Ordering::SeqCst), 2);
+    drop(second);
+}
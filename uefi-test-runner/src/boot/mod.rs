@@ -1,10 +1,13 @@
 use uefi::table::boot::BootServices;
+use uefi::Handle;
 
-pub fn test(bt: &BootServices) {
+pub fn test(image: Handle, bt: &BootServices) {
     info!("Testing boot services");
     memory::test(bt);
     misc::test(bt);
+    protocols::test(image, bt);
 }
 
 mod memory;
 mod misc;
+mod protocols;
@@ -0,0 +1,280 @@
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::ffi::c_void;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use uefi::prelude::*;
+use uefi::proto::device_path::{DevicePath, DeviceType};
+use uefi::proto::loaded_image::LoadedImage;
+use uefi::proto::Protocol;
+use uefi::table::boot::{BootServices, MemoryType, OpenProtocolAttributes, SearchType};
+use uefi::{Guid, Identify};
+
+pub fn test(image: Handle, bt: &BootServices) {
+    info!("Testing protocol opening...");
+    test_open_protocol(image, bt);
+    test_current_loaded_image(image, bt);
+    info!("Testing handle buffers...");
+    test_locate_handle_buffer(bt);
+    test_protocols_per_handle(image, bt);
+    test_locate_device_path(image, bt);
+    test_all_handles(image, bt);
+    info!("Testing protocol installation...");
+    test_install_protocol(bt);
+    test_install_multiple_protocol_interfaces(bt);
+    info!("Testing protocol notifications...");
+    test_watch_protocol(bt);
+    info!("Testing controller connection...");
+    test_connect_controller(image, bt);
+}
+
+/// Protocol installed by the tests
+#[repr(C)]
+struct TestProtocol {
+    value: u32,
+}
+
+unsafe impl Identify for TestProtocol {
+    const GUID: Guid = Guid::from_values(
+        0x6a4d_93b0,
+        0x2c85,
+        0x4a2b,
+        0x9c1e,
+        [0x3e, 0x51, 0x7a, 0x0f, 0x44, 0xd2],
+    );
+}
+
+impl Protocol for TestProtocol {}
+
+fn test_open_protocol(image: Handle, bt: &BootServices) {
+    bt.test_protocol::<LoadedImage>(image, image, None)
+        .expect_success("Image does not support `LoadedImage`");
+
+    let loaded_image = bt
+        .open_protocol::<LoadedImage>(image, image, None, OpenProtocolAttributes::GET_PROTOCOL)
+        .expect_success("Failed to open `LoadedImage`");
+    let loaded_image = unsafe { &*loaded_image.interface().get() };
+    info!("Image loaded from device {:?}", loaded_image.device());
+
+    let driver = bt
+        .open_protocol::<LoadedImage>(image, image, Some(image), OpenProtocolAttributes::BY_DRIVER)
+        .expect_success("Failed to open `LoadedImage` by driver");
+    let entries = bt
+        .open_protocol_information::<LoadedImage>(image)
+        .expect_success("Failed to list the agents using `LoadedImage`");
+    assert!(entries.iter().any(
+        |entry| entry.attributes() == OpenProtocolAttributes::BY_DRIVER
+            && entry.controller().is_some()
+    ));
+
+    // The same driver cannot manage the same controller twice
+    assert!(bt
+        .open_protocol::<LoadedImage>(image, image, Some(image), OpenProtocolAttributes::BY_DRIVER)
+        .is_err());
+    drop(driver);
+    let entries = bt
+        .open_protocol_information::<LoadedImage>(image)
+        .expect_success("Failed to list the agents using `LoadedImage`");
+    assert!(!entries
+        .iter()
+        .any(|entry| entry.attributes() == OpenProtocolAttributes::BY_DRIVER));
+}
+
+fn test_current_loaded_image(image: Handle, bt: &BootServices) {
+    let loaded_image = bt
+        .current_loaded_image(image)
+        .expect_success("Failed to get the `LoadedImage` of the running image");
+    // This function is part of the image
+    let address = test_current_loaded_image as fn(Handle, &BootServices) as usize;
+    let base = loaded_image.image_base();
+    assert!(address >= base && ((address - base) as u64) < loaded_image.image_size());
+    assert_eq!(loaded_image.image_code_type(), MemoryType::LOADER_CODE);
+    assert!(loaded_image.file_path().is_some());
+    info!(
+        "Image loaded with {} bytes of load options",
+        loaded_image.load_options_data().len()
+    );
+}
+
+fn test_protocols_per_handle(image: Handle, bt: &BootServices) {
+    let guids = bt
+        .protocols_per_handle(image)
+        .expect_success("Failed to list the protocols of the image");
+    assert!(guids.contains(&LoadedImage::GUID));
+}
+
+fn test_locate_device_path(image: Handle, bt: &BootServices) {
+    let loaded_image = bt
+        .handle_protocol::<LoadedImage>(image)
+        .expect_success("Failed to open `LoadedImage`");
+    let device = unsafe { &*loaded_image.get() }.device();
+    let device_path = bt
+        .handle_protocol::<DevicePath>(device)
+        .expect_success("Failed to open the device path of the image's device");
+    let device_path = unsafe { &*device_path.get() };
+
+    // The path of the device matches itself entirely
+    let (handle, remaining) = bt
+        .locate_device_path_remaining::<DevicePath>(device_path)
+        .expect_success("Failed to locate the image's device");
+    assert_eq!(handle, device);
+    assert_eq!(remaining.device_type, DeviceType::END);
+}
+
+fn test_all_handles(image: Handle, bt: &BootServices) {
+    let handles = bt
+        .all_handles()
+        .expect_success("Failed to take a snapshot of the handle database");
+    let count = bt
+        .locate_handle(SearchType::AllHandles, None)
+        .expect_success("Failed to count the handles");
+    assert_eq!(handles.len(), count);
+
+    let info = handles
+        .iter()
+        .find(|info| info.handle() == image)
+        .expect("The image is missing from the handle database");
+    assert!(info.supports(&LoadedImage::GUID));
+    // `handle_protocol()` opened the interface for the tests above, and
+    // interfaces opened this way are never closed
+    let loaded_image = info
+        .protocols()
+        .iter()
+        .find(|protocol| *protocol.guid() == LoadedImage::GUID)
+        .unwrap();
+    assert!(!loaded_image.open_information().is_empty());
+}
+
+fn test_locate_handle_buffer(bt: &BootServices) {
+    let search_type = SearchType::from_proto::<LoadedImage>();
+    let images = bt
+        .locate_handle_buffer(search_type)
+        .expect_success("Failed to locate the images");
+    let count = bt
+        .locate_handle(search_type, None)
+        .expect_success("Failed to count the images");
+    assert_eq!(images.len(), count);
+
+    let handles = bt
+        .locate_handle_buffer(SearchType::AllHandles)
+        .expect_success("Failed to locate all handles");
+    assert!(handles.len() >= images.len());
+}
+
+fn test_install_protocol(bt: &BootServices) {
+    let mut installed = bt
+        .install_protocol(None, Box::new(TestProtocol { value: 42 }))
+        .expect_success("Failed to install protocol");
+    let protocol = bt
+        .handle_protocol::<TestProtocol>(installed.handle())
+        .expect_success("Failed to find the installed protocol");
+    assert_eq!(unsafe { (*protocol.get()).value }, 42);
+    let handles = bt
+        .locate_handle_buffer(SearchType::from_proto::<TestProtocol>())
+        .expect_success("Failed to locate the installed protocol");
+    assert_eq!(handles.len(), 1);
+    drop(handles);
+
+    let old_protocol = installed
+        .reinstall(Box::new(TestProtocol { value: 43 }))
+        .expect_success("Failed to reinstall protocol");
+    assert_eq!(old_protocol.value, 42);
+    let protocol = bt
+        .handle_protocol::<TestProtocol>(installed.handle())
+        .expect_success("Failed to find the reinstalled protocol");
+    assert_eq!(unsafe { (*protocol.get()).value }, 43);
+
+    let protocol = installed
+        .uninstall()
+        .expect_success("Failed to uninstall protocol");
+    assert_eq!(protocol.value, 43);
+    assert!(bt
+        .locate_handle_buffer(SearchType::from_proto::<TestProtocol>())
+        .is_err());
+}
+
+fn test_install_multiple_protocol_interfaces(bt: &BootServices) {
+    let mut first = TestProtocol { value: 1 };
+    let mut second = TestProtocol { value: 2 };
+    let first = &mut first as *mut TestProtocol as *mut c_void;
+    let second = &mut second as *mut TestProtocol as *mut c_void;
+
+    // The same protocol cannot be installed twice on a handle, so the first
+    // interface must be uninstalled again
+    let interfaces = [(&TestProtocol::GUID, first), (&TestProtocol::GUID, second)];
+    assert!(unsafe { bt.install_multiple_protocol_interfaces(None, &interfaces) }.is_err());
+    assert!(bt
+        .locate_handle_buffer(SearchType::from_proto::<TestProtocol>())
+        .is_err());
+
+    let handle = unsafe { bt.install_multiple_protocol_interfaces(None, &interfaces[..1]) }
+        .expect_success("Failed to install protocols");
+    unsafe { bt.uninstall_multiple_protocol_interfaces(handle, &interfaces[..1]) }
+        .expect_success("Failed to uninstall protocols");
+}
+
+fn test_watch_protocol(bt: &BootServices) {
+    let registration = bt
+        .watch_protocol::<TestProtocol>()
+        .expect_success("Failed to watch protocol");
+    let installations = Arc::new(AtomicUsize::new(0));
+    let notified = installations.clone();
+    let notification = bt
+        .watch_protocol_with_closure::<TestProtocol, _>(move |_| {
+            notified.fetch_add(1, Ordering::SeqCst);
+        })
+        .expect_success("Failed to watch protocol with a closure");
+    assert!(registration
+        .next_handle()
+        .expect_success("Failed to get the next handle")
+        .is_none());
+
+    let installed = bt
+        .install_protocol(None, Box::new(TestProtocol { value: 0 }))
+        .expect_success("Failed to install protocol");
+    assert!(bt
+        .check_event(registration.event())
+        .expect_success("Failed to check the registration event"));
+    let handle = registration
+        .next_handle()
+        .expect_success("Failed to get the next handle")
+        .expect("The installation was not notified");
+    assert_eq!(handle, installed.handle());
+    assert!(registration
+        .next_handle()
+        .expect_success("Failed to get the next handle")
+        .is_none());
+    assert_eq!(installations.load(Ordering::SeqCst), 1);
+
+    drop(notification);
+    installed
+        .uninstall()
+        .expect_success("Failed to uninstall protocol");
+}
+
+fn test_connect_controller(image: Handle, bt: &BootServices) {
+    let loaded_image = bt
+        .handle_protocol::<LoadedImage>(image)
+        .expect_success("Failed to open `LoadedImage`");
+    let device = unsafe { &*loaded_image.get() }.device();
+    // The drivers of the boot device are already connected, so connecting
+    // them again either does nothing or finds no other driver
+    match bt.connect_controller(device, None, None, false) {
+        Ok(completion) => completion.unwrap(),
+        Err(err) => assert_eq!(err.status(), Status::NOT_FOUND),
+    }
+
+    // The test protocol is not supported by any driver
+    let installed = bt
+        .install_protocol(None, Box::new(TestProtocol { value: 0 }))
+        .expect_success("Failed to install protocol");
+    let handle = installed.handle();
+    let err = bt
+        .connect_controller(handle, None, None, true)
+        .expect_err("A driver supports the test protocol");
+    assert_eq!(err.status(), Status::NOT_FOUND);
+    bt.disconnect_controller(handle, None, None)
+        .expect_success("Failed to disconnect controller");
+    installed
+        .uninstall()
+        .expect_success("Failed to uninstall protocol");
+}
@@ -11,10 +11,9 @@ extern crate alloc;
 // Keep this line to ensure the `mem*` functions are linked in.
 extern crate rlibc;
 
-use core::mem;
 use uefi::prelude::*;
 use uefi::proto::console::serial::Serial;
-use uefi::table::boot::MemoryDescriptor;
+use uefi::table::boot::MemoryType;
 
 mod boot;
 mod proto;
@@ -40,7 +39,7 @@ fn efi_main(image: Handle, st: SystemTable<Boot>) -> Status {
         .expect("Failed to retrieve boot file system")
         .unwrap();
 
-    boot::test(bt);
+    boot::test(image, bt);
 
     // Test all the supported protocols.
     proto::test(&st);
@@ -122,11 +121,8 @@ fn shutdown(image: uefi::Handle, st: SystemTable<Boot>) -> ! {
     }
 
     // Exit boot services as a proof that it works :)
-    let max_mmap_size =
-        st.boot_services().memory_map_size() + 8 * mem::size_of::<MemoryDescriptor>();
-    let mut mmap_storage = vec![0; max_mmap_size].into_boxed_slice();
-    let (st, _iter) = st
-        .exit_boot_services(image, &mut mmap_storage[..])
+    let (st, _mmap) = st
+        .exit_boot_services_safe(image, MemoryType::LOADER_DATA)
         .expect_success("Failed to exit boot services");
 
     #[cfg(target_arch = "x86_64")]
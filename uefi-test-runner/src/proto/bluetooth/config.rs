@@ -0,0 +1,35 @@
+use uefi::prelude::*;
+use uefi::proto::bluetooth::config::{BluetoothClassOfDevice, BluetoothConfig};
+
+pub fn test(bt: &BootServices) {
+    info!("Running Bluetooth configuration protocol test");
+    test_class_of_device();
+
+    if let Ok(config) = bt.locate_protocol::<BluetoothConfig>() {
+        let config =
+            config.expect("Warnings encountered while opening Bluetooth configuration protocol");
+        let config = unsafe { &*config.get() };
+
+        config
+            .init()
+            .expect_success("Failed to initialize Bluetooth controller");
+        let address = config
+            .address()
+            .expect_success("Failed to get Bluetooth controller address");
+        info!("Bluetooth controller address: {:02x?}", address.0);
+
+        if let Err(err) = config.scan(true, 0) {
+            warn!("Failed to scan for Bluetooth devices: {:?}", err.status());
+        }
+    } else {
+        warn!("Bluetooth configuration protocol is not supported");
+    }
+}
+
+fn test_class_of_device() {
+    // Keyboard in limited discoverable mode
+    let class = BluetoothClassOfDevice([0x40, 0x25, 0x00]);
+    assert_eq!(class.major_device_class(), 5);
+    assert_eq!(class.minor_device_class(), 0x10);
+    assert_eq!(class.major_service_class(), 1);
+}
@@ -0,0 +1,9 @@
+use uefi::prelude::*;
+
+pub fn test(bt: &BootServices) {
+    info!("Testing Bluetooth protocols");
+
+    config::test(bt);
+}
+
+mod config;
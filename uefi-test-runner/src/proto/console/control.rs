@@ -0,0 +1,31 @@
+use uefi::prelude::*;
+use uefi::proto::console::control::{ConsoleControl, ScreenMode};
+use uefi::table::boot::BootServices;
+
+pub fn test(bt: &BootServices) {
+    info!("Running console control protocol test");
+    if let Ok(control) = bt.locate_protocol::<ConsoleControl>() {
+        let control = control.expect("Warnings encountered while opening console control protocol");
+        let control = unsafe { &mut *control.get() };
+
+        let state = control
+            .state()
+            .expect_success("Failed to query console state");
+        info!("Console state: {:?}", state);
+
+        // Switch back and forth, ending on the mode the tests started with.
+        let other = if state.mode == ScreenMode::TEXT {
+            ScreenMode::GRAPHICS
+        } else {
+            ScreenMode::TEXT
+        };
+        if control.set_mode(other).is_ok() {
+            assert_eq!(control.mode().unwrap_success(), other);
+        }
+        control
+            .set_mode(state.mode)
+            .expect_success("Failed to restore console mode");
+    } else {
+        warn!("Console control protocol is not supported");
+    }
+}
@@ -0,0 +1,65 @@
+use uefi::proto::console::edid::{Edid, EdidActive, EdidDiscovered, EDID_HEADER};
+use uefi::table::boot::BootServices;
+
+pub fn test(bt: &BootServices) {
+    info!("Running EDID protocol test");
+    test_parser();
+
+    if let Ok(edid) = bt.locate_protocol::<EdidActive>() {
+        let edid = edid.expect("Warnings encountered while opening EDID active protocol");
+        let edid = unsafe { &*edid.get() };
+        match edid.parse() {
+            Some(edid) => info!("Active EDID: {:?}", edid),
+            None => info!("No valid active EDID"),
+        }
+    } else {
+        warn!("EDID active protocol is not supported");
+    }
+
+    if let Ok(edid) = bt.locate_protocol::<EdidDiscovered>() {
+        let edid = edid.expect("Warnings encountered while opening EDID discovered protocol");
+        let edid = unsafe { &*edid.get() };
+        info!(
+            "Discovered EDID size: {}",
+            edid.edid().map_or(0, |edid| edid.len())
+        );
+    } else {
+        warn!("EDID discovered protocol is not supported");
+    }
+}
+
+// Parse a hand-built base block.
+fn test_parser() {
+    let mut block = [0u8; 128];
+    block[..8].copy_from_slice(&EDID_HEADER);
+    // "ABC", product 0x1234, EDID 1.4
+    block[8..12].copy_from_slice(&[0x04, 0x43, 0x34, 0x12]);
+    block[18..20].copy_from_slice(&[1, 4]);
+    // 1920x1080 at 148.5 MHz, 527x296 mm
+    block[54..72].copy_from_slice(&[
+        0x02, 0x3a, 0x80, 0x18, 0x71, 0x38, 0x2d, 0x40, 0x58, 0x2c, 0x45, 0x00, 0x0f, 0x28, 0x21,
+        0x00, 0x00, 0x1e,
+    ]);
+    block[72..77].copy_from_slice(&[0, 0, 0, 0xfc, 0]);
+    block[77..90].copy_from_slice(b"Test panel\n  ");
+    let sum = block.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte));
+    block[127] = 0u8.wrapping_sub(sum);
+
+    let edid = Edid::parse(&block).expect("Failed to parse EDID");
+    assert_eq!(&edid.manufacturer_id(), b"ABC");
+    assert_eq!(edid.product_code(), 0x1234);
+    assert_eq!(edid.version(), (1, 4));
+    assert_eq!(edid.display_name(), Some("Test panel"));
+
+    let timing = edid.preferred_timing().expect("No preferred timing");
+    assert_eq!(timing.resolution(), (1920, 1080));
+    assert_eq!(timing.pixel_clock_khz, 148_500);
+    assert_eq!(
+        (timing.horizontal_size_mm, timing.vertical_size_mm),
+        (527, 296)
+    );
+    assert_eq!(edid.detailed_timings().count(), 1);
+
+    block[127] ^= 1;
+    assert!(Edid::parse(&block).is_none());
+}
@@ -1,5 +1,19 @@
+use core::fmt::Write;
+use embedded_graphics_core::draw_target::DrawTarget;
+use embedded_graphics_core::geometry::{OriginDimensions, Point, Size};
+use embedded_graphics_core::pixelcolor::{Rgb888, RgbColor};
+use embedded_graphics_core::primitives::Rectangle;
+use embedded_graphics_core::Pixel;
+use uefi::bmp::{BmpImage, Position, Scaling};
 use uefi::prelude::*;
-use uefi::proto::console::gop::{BltOp, BltPixel, FrameBuffer, GraphicsOutput, PixelFormat};
+use uefi::proto::console::blt_batch::BltBatch;
+use uefi::proto::console::font::Font;
+use uefi::proto::console::framebuffer::Framebuffer;
+use uefi::proto::console::gop::{
+    BltOp, BltPixel, FrameBuffer, GopDisplay, GraphicsOutput, PixelFormat,
+};
+use uefi::proto::console::graphics_console::GraphicsConsole;
+use uefi::proto::console::metrics::{ConsoleMetrics, STANDARD_GLYPH_SIZE};
 use uefi::table::boot::BootServices;
 
 pub fn test(bt: &BootServices) {
@@ -13,6 +27,14 @@ pub fn test(bt: &BootServices) {
         draw_fb(gop);
 
         crate::check_screenshot(bt, "gop_test");
+
+        draw_display(gop);
+        draw_back_buffer(gop);
+        draw_bmp(gop);
+        capture_screen(gop);
+        draw_text(gop);
+        console_metrics(gop);
+        batch_blt(gop);
     } else {
         // No tests can be run.
         warn!("UEFI Graphics Output Protocol is not supported");
@@ -21,18 +43,28 @@ pub fn test(bt: &BootServices) {
 
 // Set a larger graphics mode.
 fn set_graphics_mode(gop: &mut GraphicsOutput) {
+    let best = gop.best_mode().expect("No graphics mode available");
+    let best_res = best.info().resolution();
+    assert!(gop.modes().all(|mode| {
+        let (width, height) = mode.unwrap().info().resolution();
+        width * height <= best_res.0 * best_res.1
+    }));
+    if let Some(mode) = gop.best_mode_with_format(PixelFormat::Bgr) {
+        info!("Highest BGR resolution: {:?}", mode.info().resolution());
+    }
+
     // We know for sure QEMU has a 1024x768 mode.
     let mode = gop
-        .modes()
-        .map(|mode| mode.expect("Warnings encountered while querying mode"))
-        .find(|mode| {
-            let info = mode.info();
-            info.resolution() == (1024, 768)
-        })
-        .unwrap();
+        .mode_matching(1024, 768)
+        .expect("No 1024x768 graphics mode");
 
     gop.set_mode(&mode)
         .expect_success("Failed to set graphics mode");
+
+    let current = gop
+        .current_mode()
+        .expect_success("Failed to query current mode");
+    assert_eq!(current.info().resolution(), (1024, 768));
 }
 
 // Fill the screen with color.
@@ -89,3 +121,223 @@ fn draw_fb(gop: &mut GraphicsOutput) {
     fill_rectangle((50, 30), (150, 600), [250, 128, 64]);
     fill_rectangle((400, 120), (750, 450), [16, 128, 255]);
 }
+
+// Draw through the clipping display wrapper.
+fn draw_display(gop: &mut GraphicsOutput) {
+    let info = gop.current_mode_info();
+    if info.pixel_format() != PixelFormat::BltOnly {
+        let pixel = BltPixel::new(0x12, 0x34, 0x56);
+        let encoded = info.encode_pixel(pixel).unwrap();
+        assert_eq!(info.bytes_per_pixel(), Some(4));
+        match info.pixel_format() {
+            PixelFormat::Rgb => assert_eq!(encoded, 0x56_34_12),
+            PixelFormat::Bgr => assert_eq!(encoded, 0x12_34_56),
+            _ => {}
+        }
+    }
+
+    let mut display = GopDisplay::new(gop);
+    assert_eq!(display.size(), Size::new(1024, 768));
+
+    display
+        .clear(Rgb888::BLACK)
+        .expect("Failed to clear the display");
+
+    // Partially off-screen rectangles are clipped
+    display
+        .fill_solid(
+            &Rectangle::new(Point::new(-50, -50), Size::new(100, 100)),
+            Rgb888::RED,
+        )
+        .expect("Failed to fill a clipped rectangle");
+    display
+        .fill_solid(
+            &Rectangle::new(Point::new(1000, 700), Size::new(100, 100)),
+            Rgb888::GREEN,
+        )
+        .expect("Failed to fill a clipped rectangle");
+
+    let diagonal = (-10..800).map(|i| Pixel(Point::new(i, i), Rgb888::WHITE));
+    display.draw_iter(diagonal).expect("Failed to draw pixels");
+}
+
+// Draw through a back buffer, then flush it to the screen.
+fn draw_back_buffer(gop: &mut GraphicsOutput) {
+    let mut fb = Framebuffer::new(gop).expect_success("Failed to create back buffer");
+    assert_eq!(fb.size(), (1024, 768));
+    assert_eq!(fb.pixels().len(), 1024 * 768);
+
+    fb.clear(BltPixel::new(32, 32, 32));
+    fb.fill_rect((1000, 740), (100, 100), BltPixel::new(0, 0, 255));
+    fb.set_pixel(2000, 2000, BltPixel::new(255, 0, 0));
+    let image = [BltPixel::new(255, 255, 0); 16 * 16];
+    fb.draw_image((1020, 10), (16, 16), &image);
+
+    let corner = fb.pixel(1023, 767).unwrap();
+    assert_eq!((corner.red, corner.green, corner.blue), (0, 0, 255));
+    let clipped = fb.pixel(1023, 10).unwrap();
+    assert_eq!((clipped.red, clipped.green, clipped.blue), (255, 255, 0));
+    assert!(fb.pixel(1024, 0).is_none());
+
+    fb.flush().expect_success("Failed to flush back buffer");
+    fb.flush_all()
+        .expect_success("Failed to flush entire back buffer");
+}
+
+// Decode a small bitmap and draw it in various ways.
+fn draw_bmp(gop: &mut GraphicsOutput) {
+    // 2x2 24-bit image stored bottom-up, with rows padded to 8 bytes
+    let mut bmp = vec![0u8; 54];
+    bmp[..2].copy_from_slice(b"BM");
+    bmp[10] = 54;
+    bmp[14] = 40;
+    bmp[18] = 2;
+    bmp[22] = 2;
+    bmp[26] = 1;
+    bmp[28] = 24;
+    // Bottom row: blue, white. Top row: red, green.
+    bmp.extend_from_slice(&[255, 0, 0, 255, 255, 255, 0, 0]);
+    bmp.extend_from_slice(&[0, 0, 255, 0, 255, 0, 0, 0]);
+
+    let image = BmpImage::decode(&bmp).expect_success("Failed to decode BMP image");
+    assert_eq!(image.size(), (2, 2));
+    let rgb = |x, y| {
+        let pixel = image.pixel(x, y).unwrap();
+        (pixel.red, pixel.green, pixel.blue)
+    };
+    assert_eq!(rgb(0, 0), (255, 0, 0));
+    assert_eq!(rgb(1, 0), (0, 255, 0));
+    assert_eq!(rgb(0, 1), (0, 0, 255));
+    assert_eq!(rgb(1, 1), (255, 255, 255));
+
+    let resized = image.resize(4, 4);
+    let pixel = resized.pixel(3, 0).unwrap();
+    assert_eq!((pixel.red, pixel.green, pixel.blue), (0, 255, 0));
+
+    assert!(BmpImage::decode(&bmp[..60]).is_err());
+
+    image
+        .draw(gop, Position::Center, Scaling::Fit)
+        .expect_success("Failed to draw fitted BMP image");
+    image
+        .draw(gop, Position::At(1020, 0), Scaling::Factor(8))
+        .expect_success("Failed to draw scaled BMP image");
+    image
+        .draw(gop, Position::At(0, 0), Scaling::None)
+        .expect_success("Failed to draw BMP image");
+}
+
+// Capture the screen and round-trip it through the BMP encoder.
+fn capture_screen(gop: &mut GraphicsOutput) {
+    let screenshot = BmpImage::capture(gop).expect_success("Failed to capture screen");
+    assert_eq!(screenshot.size(), (1024, 768));
+    // Drawn by draw_bmp()
+    let pixel = screenshot.pixel(0, 0).unwrap();
+    assert_eq!((pixel.red, pixel.green, pixel.blue), (255, 0, 0));
+
+    let bmp = screenshot.encode();
+    assert_eq!(bmp.len(), 54 + 1024 * 3 * 768);
+    let decoded = BmpImage::decode(&bmp).expect_success("Failed to decode screenshot");
+    assert_eq!(decoded.size(), screenshot.size());
+    let same = |a: &BltPixel, b: &BltPixel| (a.red, a.green, a.blue) == (b.red, b.green, b.blue);
+    assert!(decoded
+        .pixels()
+        .iter()
+        .zip(screenshot.pixels())
+        .all(|(a, b)| same(a, b)));
+}
+
+// Print text with a bitmap font.
+fn draw_text(gop: &mut GraphicsOutput) {
+    let font = Font::builtin();
+    assert_eq!(font.glyph_size(), (8, 8));
+    let glyph = font.glyph('T').unwrap();
+    assert!(glyph.is_set(1, 0) && !glyph.is_set(0, 0) && glyph.is_set(3, 6));
+    assert!(font.glyph('\u{e9}').is_none());
+
+    // PSF1 font with 256 glyphs of 8x2 pixels
+    let mut psf = vec![0x36, 0x04, 0x00, 0x02];
+    psf.resize(4 + 256 * 2, 0);
+    psf[4 + usize::from(b'A') * 2] = 0x81;
+    let psf_font = Font::from_psf(&psf).expect("Failed to load PSF font");
+    assert_eq!(psf_font.glyph_size(), (8, 2));
+    let glyph = psf_font.glyph('A').unwrap();
+    assert!(glyph.is_set(0, 0) && glyph.is_set(7, 0) && !glyph.is_set(0, 1));
+    assert!(Font::from_psf(&psf[..100]).is_none());
+
+    let mut console = GraphicsConsole::new(gop);
+    assert_eq!(console.size(), (128, 96));
+    console
+        .clear()
+        .expect_success("Failed to clear graphics console");
+    console.set_colors(BltPixel::new(255, 255, 255), BltPixel::new(0, 0, 128));
+    console
+        .enable_cursor(true)
+        .expect_success("Failed to enable cursor");
+
+    writeln!(console, "Hello from the graphics console!").unwrap();
+    write!(console, "\tTabbed\r").unwrap();
+    assert_eq!(console.cursor_position(), (0, 1));
+
+    console
+        .set_cursor_position(0, 95)
+        .expect_success("Failed to move cursor");
+    // Scrolls the screen
+    writeln!(console, "Last line").unwrap();
+    assert_eq!(console.cursor_position(), (0, 95));
+    assert!(console.set_cursor_position(128, 0).is_err());
+}
+
+// Correlate graphics modes with text modes.
+fn console_metrics(gop: &mut GraphicsOutput) {
+    // 100x31 standard glyphs are centered vertically on a 800x600 screen
+    let metrics = ConsoleMetrics::from_modes((800, 600), (100, 31)).unwrap();
+    assert_eq!(metrics.cell_size, STANDARD_GLYPH_SIZE);
+    assert_eq!(metrics.origin, (0, 5));
+    assert_eq!(metrics.cell_position(2, 1), (16, 24));
+    assert_eq!(metrics.cell_at(17, 25), Some((2, 1)));
+    assert_eq!(metrics.cell_at(0, 0), None);
+
+    // Text which does not fit with standard glyphs covers the screen
+    let metrics = ConsoleMetrics::from_modes((640, 480), (80, 30)).unwrap();
+    assert_eq!(metrics.cell_size, (8, 16));
+    assert_eq!(metrics.origin, (0, 0));
+
+    let resolution = gop.current_mode_info().resolution();
+    let metrics = ConsoleMetrics::from_modes(resolution, (80, 25)).unwrap();
+    info!("80x25 text console on {:?}: {:?}", resolution, metrics);
+}
+
+// Record blit operations, and perform them at once.
+fn batch_blt(gop: &mut GraphicsOutput) {
+    let red = BltPixel::new(255, 0, 0);
+    let blue = BltPixel::new(0, 0, 255);
+    let mut batch = BltBatch::new();
+
+    // A row of adjacent tiles becomes a single fill
+    for x in 0..8 {
+        batch.fill(red, (x * 10, 0), (10, 10));
+    }
+    assert_eq!(batch.len(), 1);
+    batch.fill(red, (0, 10), (80, 10));
+    assert_eq!(batch.len(), 1);
+    batch.fill(blue, (80, 0), (10, 20));
+    batch.fill(red, (90, 0), (10, 20));
+    batch.fill(red, (0, 0), (0, 10));
+    assert_eq!(batch.len(), 3);
+    batch.copy((0, 0), (0, 20), (100, 20));
+    assert_eq!(batch.len(), 4);
+
+    batch
+        .submit(gop)
+        .expect_success("Failed to submit blit batch");
+    assert!(batch.is_empty());
+
+    batch.set_coalescing(false);
+    batch.fill(blue, (0, 0), (10, 10));
+    batch.fill(blue, (10, 0), (10, 10));
+    assert_eq!(batch.len(), 2);
+    batch
+        .submit(gop)
+        .expect_success("Failed to submit blit batch");
+}
@@ -0,0 +1,13 @@
+use core::time::Duration;
+use uefi::prelude::*;
+use uefi::proto::console::text::Input;
+
+pub fn test(stdin: &mut Input, bt: &BootServices) {
+    info!("Running text input timeout test");
+
+    // No keys are pressed during the tests, so the wait must time out
+    let key = stdin
+        .read_key_timeout(bt, Duration::from_millis(10))
+        .expect_success("Failed to wait for a key");
+    assert_eq!(key, None);
+}
@@ -0,0 +1,44 @@
+use core::convert::TryFrom;
+use uefi::prelude::*;
+use uefi::proto::console::text::{InputEx, Key, KeyData, KeyShiftState, KeyToggleState};
+use uefi::table::boot::BootServices;
+use uefi::Char16;
+
+extern "efiapi" fn on_ctrl_c(_key_data: &KeyData) -> Status {
+    Status::SUCCESS
+}
+
+pub fn test(bt: &BootServices) {
+    info!("Running extended text input protocol test");
+    if let Ok(input) = bt.locate_protocol::<InputEx>() {
+        let input = input.expect("Warnings encountered while opening extended input protocol");
+        let input = unsafe { &mut *input.get() };
+
+        match input
+            .read_key_stroke_ex()
+            .expect_success("Failed to read keystroke")
+        {
+            Some(key_data) => info!(
+                "Pending keystroke: {:?}, shift state: {:?}, toggle state: {:?}",
+                key_data.key(),
+                key_data.shift_state(),
+                key_data.toggle_state()
+            ),
+            None => info!("No keystroke pending"),
+        }
+
+        let ctrl_c = KeyData::new(
+            Key::Printable(Char16::try_from('c').unwrap()),
+            KeyShiftState::SHIFT_STATE_VALID | KeyShiftState::LEFT_CONTROL,
+            KeyToggleState::empty(),
+        );
+        let handle = input
+            .register_key_notify(&ctrl_c, on_ctrl_c)
+            .expect_success("Failed to register key notification");
+        input
+            .unregister_key_notify(handle)
+            .expect_success("Failed to unregister key notification");
+    } else {
+        warn!("No extended text input device found");
+    }
+}
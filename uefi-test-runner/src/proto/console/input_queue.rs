@@ -0,0 +1,20 @@
+use uefi::prelude::*;
+use uefi::proto::console::text::{Input, InputQueue};
+
+pub fn test(stdin: &mut Input) {
+    info!("Running buffered keyboard input test");
+    let mut queue = InputQueue::new(stdin);
+
+    queue
+        .clear()
+        .expect_success("Failed to discard pending keys");
+    assert!(queue.is_empty());
+
+    // No keys are pressed during the tests
+    let key = queue
+        .try_read_key()
+        .expect_success("Failed to poll for keys");
+    assert_eq!(key, None);
+    let keys = queue.drain().expect_success("Failed to drain keys");
+    assert_eq!(keys.count(), 0);
+}
@@ -4,14 +4,26 @@ pub fn test(st: &SystemTable<Boot>) {
     info!("Testing console protocols");
 
     stdout::test(st.stdout());
+    input_queue::test(st.stdin());
 
     let bt = st.boot_services();
+    input::test(st.stdin(), bt);
+    control::test(bt);
     serial::test(bt);
+    input_ex::test(bt);
     gop::test(bt);
+    edid::test(bt);
+    uga::test(bt);
     pointer::test(bt);
 }
 
+mod control;
+mod edid;
 mod gop;
+mod input;
+mod input_ex;
+mod input_queue;
 mod pointer;
 mod serial;
 mod stdout;
+mod uga;
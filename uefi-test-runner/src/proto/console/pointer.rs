@@ -1,5 +1,8 @@
 use uefi::prelude::*;
-use uefi::proto::console::pointer::Pointer;
+use uefi::proto::console::pointer::{
+    AbsolutePointer, AbsolutePointerAttributes, AbsolutePointerButtons, AbsolutePointerMode,
+    AbsolutePointerState, Pointer, PointerButtons, PointerMode, PointerState, PointerTracker,
+};
 use uefi::table::boot::BootServices;
 
 pub fn test(bt: &BootServices) {
@@ -24,4 +27,82 @@ pub fn test(bt: &BootServices) {
     } else {
         warn!("No pointer device found");
     }
+
+    info!("Running absolute pointer protocol test");
+    if let Ok(pointer) = bt.locate_protocol::<AbsolutePointer>() {
+        let pointer =
+            pointer.expect("Warnings encountered while opening absolute pointer protocol");
+        let pointer = unsafe { &mut *pointer.get() };
+        info!("Absolute pointer mode: {:#?}", pointer.mode());
+
+        pointer
+            .reset(false)
+            .expect_success("Failed to reset absolute pointer device");
+
+        let state = pointer
+            .read_state()
+            .expect_success("Failed to retrieve absolute pointer state");
+        if let Some(state) = state {
+            info!(
+                "New absolute pointer state: {:#?}, on a 640x480 screen: {:?}",
+                state,
+                pointer.mode().scale_position(&state, 640, 480)
+            );
+        } else {
+            info!("Absolute pointer state has not changed since the last query");
+        }
+    } else {
+        warn!("No absolute pointer device found");
+    }
+
+    tracker();
+}
+
+// Feed synthetic device states to a pointer tracker.
+fn tracker() {
+    let mut tracker = PointerTracker::new(640, 480);
+    assert_eq!(tracker.position(), (320, 240));
+
+    // 2 counts/mm at the default speed of 4 pixels/mm
+    let mode = PointerMode {
+        resolution: (2, 2, 0),
+        has_button: (true, false),
+    };
+    let change = tracker.update(
+        &mode,
+        &PointerState {
+            relative_movement: (10, -3, 0),
+            button: (true, true),
+        },
+    );
+    assert_eq!(tracker.position(), (340, 234));
+    assert!(change.moved);
+    assert_eq!(change.pressed, PointerButtons::PRIMARY);
+
+    // Movements are clamped to the screen
+    let change = tracker.update(
+        &mode,
+        &PointerState {
+            relative_movement: (-1000, 1000, 0),
+            button: (false, false),
+        },
+    );
+    assert_eq!(tracker.position(), (0, 479));
+    assert_eq!(change.released, PointerButtons::PRIMARY);
+
+    let mode = AbsolutePointerMode {
+        absolute_min: (0, 0, 0),
+        absolute_max: (1000, 1000, 0),
+        attributes: AbsolutePointerAttributes::empty(),
+    };
+    let change = tracker.update_absolute(
+        &mode,
+        &AbsolutePointerState {
+            current: (500, 0, 0),
+            active_buttons: AbsolutePointerButtons::TOUCH_ACTIVE,
+        },
+    );
+    assert_eq!(tracker.position(), (319, 0));
+    assert_eq!(change.pressed, PointerButtons::PRIMARY);
+    assert!(change.released.is_empty());
 }
@@ -1,3 +1,4 @@
+use uefi::io::Write;
 use uefi::prelude::*;
 use uefi::proto::console::serial::{ControlBits, Serial};
 use uefi::table::boot::BootServices;
@@ -44,6 +45,16 @@ pub fn test(bt: &BootServices) {
 
         assert_eq!(OUTPUT, &input[..]);
 
+        info!("Serial device revision: {:#x}", serial.revision());
+        serial
+            .write_all(OUTPUT)
+            .expect("Failed to write to serial port through the I/O traits");
+        let mut input = [0u8; MSG_LEN];
+        serial
+            .read_with_timeout(&mut input, 100_000)
+            .expect_success("Failed to read from serial port with a timeout");
+        assert_eq!(OUTPUT, &input[..]);
+
         // Clean up after ourselves
         serial
             .reset()
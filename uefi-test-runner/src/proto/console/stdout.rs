@@ -1,5 +1,9 @@
+use core::convert::TryFrom;
+use core::fmt::Write;
 use uefi::prelude::*;
-use uefi::proto::console::text::{Color, Output};
+use uefi::proto::console::text::{AnsiOutput, Color, ColorPair, Key, LineEditor, Output, ScanCode};
+use uefi::tui::{self, MessageBox, ProgressBar, Rect, SelectList, Selection, Theme};
+use uefi::Char16;
 
 pub fn test(stdout: &mut Output) {
     info!("Running text output protocol test");
@@ -7,6 +11,7 @@ pub fn test(stdout: &mut Output) {
     get_current_mode(stdout);
     change_text_mode(stdout);
     change_color(stdout);
+    scoped_colors(stdout);
     center_text(stdout);
 
     // Print all modes.
@@ -20,6 +25,11 @@ pub fn test(stdout: &mut Output) {
         );
     }
 
+    ansi_escapes(stdout);
+    print_macros();
+    line_editor_history();
+    tui_widgets(stdout);
+
     // Should clean up after us.
     stdout.reset(false).unwrap_success();
 }
@@ -32,14 +42,16 @@ fn get_current_mode(stdout: &mut Output) {
 
 // Switch to the maximum supported text mode.
 fn change_text_mode(stdout: &mut Output) {
-    let best_mode = stdout
-        .modes()
-        .last()
-        .unwrap()
-        .expect("Warnings encountered while querying text mode");
+    let best_mode = stdout.best_mode().expect("No text mode available");
+    assert!(stdout.modes().all(|mode| {
+        let mode = mode.expect("Warnings encountered while querying text mode");
+        mode.columns() * mode.rows() <= best_mode.columns() * best_mode.rows()
+    }));
     stdout
-        .set_mode(best_mode)
+        .set_best_mode()
         .expect_success("Failed to change text mode");
+    let current_mode = stdout.current_mode().unwrap_success();
+    assert_eq!(current_mode, Some(best_mode));
 }
 
 // Set a new color, and paint the background with it.
@@ -73,3 +85,127 @@ fn center_text(stdout: &mut Output) {
             _ => panic!("Failed to hide cursor"),
         });
 }
+
+// Print with temporary colors, which must not leak into later output.
+fn scoped_colors(stdout: &mut Output) {
+    let colors = stdout.colors();
+    assert_eq!(colors, ColorPair::new(Color::White, Color::Blue));
+
+    let value = stdout
+        .with_color(Color::Yellow, Color::Red, |stdout| {
+            assert_eq!(stdout.colors(), ColorPair::new(Color::Yellow, Color::Red));
+            writeln!(stdout, "Temporary colors").unwrap();
+            42
+        })
+        .expect_success("Failed to print with temporary colors");
+    assert_eq!(value, 42);
+    assert_eq!(stdout.colors(), colors);
+
+    {
+        let mut guard = stdout
+            .color_guard(Color::LightGreen, Color::Black)
+            .expect_success("Failed to set temporary colors");
+        writeln!(guard, "Guarded colors").unwrap();
+    }
+    assert_eq!(stdout.colors(), colors);
+}
+
+// Print text containing ANSI escape sequences.
+fn ansi_escapes(stdout: &mut Output) {
+    let mut ansi = AnsiOutput::new(stdout);
+    writeln!(
+        ansi,
+        "\x1b[1;32mGreen\x1b[0m and \x1b[41mred background\x1b[m"
+    )
+    .unwrap();
+
+    write!(ansi, "\x1b[3;11H").unwrap();
+    assert_eq!(ansi.output().cursor_position(), (10, 2));
+    write!(ansi, "\x1b[2A\x1b[4D").unwrap();
+    assert_eq!(ansi.output().cursor_position(), (6, 0));
+
+    // Sequences may be split across writes
+    write!(ansi, "\x1b[").unwrap();
+    write!(ansi, "5G\x1b[s\x1b[K\x1b[1;1H\x1b[u").unwrap();
+    assert_eq!(ansi.output().cursor_position(), (4, 0));
+
+    // Unknown sequences are ignored
+    write!(ansi, "\x1b[?1049h\x1b[5n\x1b[0J\r\n").unwrap();
+}
+
+// Print to the standard output and error without a reference to them.
+fn print_macros() {
+    uefi::print!("Printed with the print! macro, ");
+    uefi::println!("and the println! macro: {}", 42);
+    uefi::println!();
+    uefi::eprint!("Printed to the standard error, ");
+    uefi::eprintln!("with a {}", "newline");
+}
+
+// Check the history of the line editor, which needs no key presses.
+fn line_editor_history() {
+    let mut editor = LineEditor::new();
+    editor.add_history("first");
+    editor.add_history("first");
+    editor.add_history("");
+    editor.add_history("second");
+    editor.add_history("third");
+    assert_eq!(editor.history(), ["first", "second", "third"]);
+
+    editor.set_history_size(2);
+    assert_eq!(editor.history(), ["second", "third"]);
+
+    editor.set_completer(alloc::boxed::Box::new(|text: &str| {
+        ["help", "halt"]
+            .iter()
+            .filter(|command| command.starts_with(text))
+            .map(|command| (*command).into())
+            .collect()
+    }));
+}
+
+// Draw the text user interface widgets, and drive a list without key presses.
+fn tui_widgets(stdout: &mut Output) {
+    stdout.clear().expect_success("Failed to clear screen");
+
+    let area = Rect::new(2, 1, 20, 5);
+    tui::draw_frame(stdout, area, Some("Boot"), &Theme::default())
+        .expect_success("Failed to draw frame");
+
+    let items = ["Linux", "Windows", "Shell", "Reboot"];
+    let mut list = SelectList::new(&items, Rect::new(3, 2, 18, 3));
+    list.draw(stdout).expect_success("Failed to draw list");
+    assert_eq!(
+        list.handle_key(Key::Special(ScanCode::UP)),
+        Selection::Pending
+    );
+    assert_eq!(list.selected(), 0);
+    list.handle_key(Key::Special(ScanCode::END));
+    assert_eq!(list.selected(), 3);
+    list.handle_key(Key::Special(ScanCode::PAGE_UP));
+    assert_eq!(list.selected(), 0);
+    list.handle_key(Key::Special(ScanCode::DOWN));
+    list.draw(stdout).expect_success("Failed to draw list");
+    let enter = Key::Printable(Char16::try_from('\r').unwrap());
+    assert_eq!(list.handle_key(enter), Selection::Selected(1));
+    assert_eq!(
+        list.handle_key(Key::Special(ScanCode::ESCAPE)),
+        Selection::Cancelled
+    );
+
+    let bar = ProgressBar::new(2, 7, 30);
+    for done in 0..=4 {
+        bar.draw(stdout, done, 4)
+            .expect_success("Failed to draw progress bar");
+    }
+
+    let message = MessageBox::new("Notice", "Widgets drawn\nsuccessfully");
+    let screen = tui::screen_size(stdout);
+    let message_area = message.area(screen);
+    assert_eq!((message_area.width, message_area.height), (17, 4));
+    message
+        .draw(stdout)
+        .expect_success("Failed to draw message box");
+
+    stdout.clear().expect_success("Failed to clear screen");
+}
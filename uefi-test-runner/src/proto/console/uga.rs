@@ -0,0 +1,35 @@
+use uefi::prelude::*;
+use uefi::proto::console::gop::{BltOp, BltPixel};
+use uefi::proto::console::uga::{GraphicsDevice, UgaDraw};
+use uefi::table::boot::BootServices;
+
+pub fn test(bt: &BootServices) {
+    info!("Running UGA draw protocol test");
+    if let Ok(uga) = bt.locate_protocol::<UgaDraw>() {
+        let uga = uga.expect("Warnings encountered while opening UGA draw protocol");
+        let uga = unsafe { &mut *uga.get() };
+        let mode = uga.mode().expect_success("Failed to query UGA mode");
+        info!("UGA mode: {:?}", mode);
+    } else {
+        warn!("UGA draw protocol is not supported");
+    }
+
+    info!("Running graphics device test");
+    if let Ok(device) = GraphicsDevice::locate(bt) {
+        let mut device = device.expect("Warnings encountered while locating graphics device");
+        let (width, height) = device
+            .resolution()
+            .expect_success("Failed to query graphics device resolution");
+        info!("Graphics device resolution: {}x{}", width, height);
+
+        device
+            .blt(BltOp::VideoFill {
+                color: BltPixel::new(0, 128, 0),
+                dest: (0, 0),
+                dims: (width.min(16), height.min(16)),
+            })
+            .expect_success("Failed to fill rectangle on graphics device");
+    } else {
+        warn!("No graphics device found");
+    }
+}
@@ -0,0 +1,90 @@
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use uefi::prelude::*;
+use uefi::proto::hii::database::HiiDatabase;
+use uefi::proto::hii::keyboard::{AffectedAttribute, KeyModifier, KeyboardLayout};
+use uefi::table::boot::BootServices;
+use uefi::Guid;
+
+pub fn test(bt: &BootServices) {
+    info!("Running HII database protocol test");
+    if let Ok(database) = bt.locate_protocol::<HiiDatabase>() {
+        let database = database.expect("Warnings encountered while opening HII database");
+        let database = unsafe { &mut *database.get() };
+
+        let mut guids = [Guid::from_values(0, 0, 0, 0, [0; 6]); 16];
+        match database.find_keyboard_layouts(&mut guids) {
+            Ok(count) => {
+                let count = count.unwrap();
+                for guid in &guids[..count] {
+                    info!("Keyboard layout: {}", guid);
+                }
+            }
+            Err(err) => warn!("No keyboard layout found: {:?}", err.status()),
+        }
+
+        let mut buffer = [0; 4096];
+        if let Ok(layout) = database.keyboard_layout(None, &mut buffer) {
+            let layout = layout.unwrap();
+            let guid = layout.guid();
+            info!(
+                "Current keyboard layout {} maps {} keys",
+                guid,
+                layout.descriptors().len()
+            );
+            for description in layout.descriptions() {
+                info!("- {}: {}", description.language(), description.text());
+            }
+            database
+                .set_keyboard_layout(&guid)
+                .expect_success("Failed to set the keyboard layout");
+        } else {
+            warn!("No current keyboard layout");
+        }
+    } else {
+        warn!("HII database protocol is not supported");
+    }
+
+    parse_layout();
+}
+
+// Parse a layout mapping a single key.
+fn parse_layout() {
+    let mut data = Vec::new();
+    let push_u16 = |data: &mut Vec<u8>, value: u16| data.extend_from_slice(&value.to_le_bytes());
+    let descriptions = "en-US US\0";
+    let length = 23 + 16 + 2 + descriptions.len() * 2;
+
+    push_u16(&mut data, length as u16);
+    data.extend_from_slice(&[0x11; 16]);
+    data.extend_from_slice(&(23u32 + 16).to_le_bytes());
+    data.push(1);
+    // EfiKeyC1, the A key
+    data.extend_from_slice(&25u32.to_le_bytes());
+    for value in [u16::from(b'a'), u16::from(b'A'), 0, 0, 0, 0x3].iter() {
+        push_u16(&mut data, *value);
+    }
+    push_u16(&mut data, 1);
+    for c in descriptions.encode_utf16() {
+        push_u16(&mut data, c);
+    }
+
+    let layout = KeyboardLayout::parse(&data).expect("Failed to parse keyboard layout");
+    let key = layout.descriptor(25).expect("Missing key descriptor");
+    assert_eq!(key.character(false, false), Some('a'));
+    assert_eq!(key.character(true, false), Some('A'));
+    assert_eq!(key.character(false, true), None);
+    assert_eq!(key.modifier, KeyModifier::NULL_MODIFIER);
+    assert_eq!(
+        key.affected_attribute,
+        AffectedAttribute::STANDARD_SHIFT | AffectedAttribute::CAPS_LOCK
+    );
+
+    let mut descriptions = layout.descriptions();
+    let description = descriptions.next().expect("Missing layout description");
+    assert_eq!(description.language().to_string(), "en-US");
+    assert_eq!(description.text().to_string(), "US");
+    assert!(descriptions.next().is_none());
+
+    assert!(KeyboardLayout::parse(&data[..30]).is_none());
+}
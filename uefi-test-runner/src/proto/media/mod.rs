@@ -1,6 +1,26 @@
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use uefi::data_types::PathBuf;
+use uefi::fat::{DiskVolume, FatFileSystem};
+use uefi::fs::FileSystem;
+use uefi::io::{Read, Seek, SeekFrom};
 use uefi::prelude::*;
+use uefi::proto::device_path::DevicePath;
+use uefi::proto::media::block::{BlockIO, BlockIO2};
+use uefi::proto::media::disk::{DiskIo, DiskIo2};
+use uefi::proto::media::erase_block::EraseBlock;
+use uefi::proto::media::file::{
+    Directory, File, FileAttribute, FileHandle, FileInfo, FileInfoBuilder, FileMode,
+    FileSystemInfoBuilder,
+};
 use uefi::proto::media::fs::SimpleFileSystem;
+use uefi::proto::media::gpt::GptDisk;
+use uefi::proto::media::initrd::{self, Initrd};
+use uefi::proto::media::load_file::LoadFile2;
+use uefi::proto::media::mbr::MbrDisk;
 use uefi::proto::media::partition::PartitionInfo;
+use uefi::proto::Protocol;
+use uefi::table::boot::{EventType, Tpl};
 
 pub fn test(bt: &BootServices) {
     info!("Testing Media Access protocols");
@@ -30,6 +50,42 @@ pub fn test(bt: &BootServices) {
             info!("Root directory entry: {:?}", file_info);
         }
         directory.reset_entry_readout().unwrap().unwrap();
+
+        // Enumerate the directory again, letting the iterator manage buffers.
+        for entry in directory.entries() {
+            let entry = entry.expect_success("Failed to read directory entry");
+            info!("Root directory entry (boxed): {:?}", entry);
+        }
+        directory.rewind().expect("Failed to rewind directory");
+        assert_eq!(
+            directory.seek(SeekFrom::Current(0)),
+            Err(Status::UNSUPPORTED)
+        );
+
+        test_read_to_end(&mut directory);
+        test_read_only(&mut directory);
+        // Deliberately misalign the storage of the query
+        let mut storage = [0u8; 257];
+        let root_info = directory
+            .get_info_unaligned::<FileInfo>(&mut storage[1..])
+            .expect_success("Failed to query root directory info");
+        assert!(root_info.attribute().contains(FileAttribute::DIRECTORY));
+
+        let volume_info = sfs
+            .volume_info()
+            .expect_success("Failed to query volume info");
+        info!("Volume info: {:?}", volume_info);
+        assert_eq!(
+            directory
+                .volume_info()
+                .expect_success("Failed to query volume info from root")
+                .volume_size(),
+            volume_info.volume_size()
+        );
+
+        test_paths();
+        test_info_builders();
+        test_file_system(sfs);
     } else {
         warn!("`SimpleFileSystem` protocol is not available");
     }
@@ -48,8 +104,483 @@ pub fn test(bt: &BootServices) {
             info!("MBR partition: {:?}", mbr);
         } else if let Some(gpt) = pi.gpt_partition_entry() {
             info!("GPT partition: {:?}", gpt);
+            if gpt.is_efi_system_partition() {
+                info!("Found the EFI system partition: {}", {
+                    gpt.unique_partition_guid
+                });
+            }
+        } else if let Ok(device_path) = bt.handle_protocol::<DevicePath>(handle) {
+            let device_path = unsafe { &*device_path.expect("Warning opening device path").get() };
+            if let Some(cdrom) = pi.el_torito_entry(device_path) {
+                let (entry, start) = (cdrom.boot_entry, cdrom.partition_start);
+                info!("El Torito partition: boot entry {}, start {}", entry, start);
+            } else if pi.is_udf(device_path) {
+                info!("UDF partition");
+            } else {
+                info!("Unknown partition");
+            }
         } else {
             info!("Unknown partition");
         }
     }
+
+    test_block_io(bt);
+    test_block_io2(bt);
+    test_gpt(bt);
+    test_mbr(bt);
+    test_disk_io(bt);
+    test_disk_io2(bt);
+    test_fat(bt);
+    test_erase_block(bt);
+    test_initrd(bt);
+}
+
+// Set and clear the read-only attribute of a temporary file, including
+// together with another change to its info.
+fn test_read_only(root: &mut Directory) {
+    info!("Toggling the read-only attribute of a file");
+
+    let mut file = root
+        .open(
+            "test_read_only.txt",
+            FileMode::CreateReadWrite,
+            FileAttribute::empty(),
+        )
+        .expect_success("Failed to create file");
+    let is_read_only = |file: &mut FileHandle| {
+        file.get_boxed_info::<FileInfo>()
+            .expect_success("Failed to query file info")
+            .attribute()
+            .contains(FileAttribute::READ_ONLY)
+    };
+
+    file.set_read_only(true)
+        .expect_success("Failed to make file read-only");
+    assert!(is_read_only(&mut file));
+
+    // Clearing the attribute together with another change must be split
+    file.modify_info(|info| {
+        info.set_attribute(info.attribute() - FileAttribute::READ_ONLY);
+        info.set_file_size(4);
+    })
+    .expect_success("Failed to modify read-only file info");
+    assert!(!is_read_only(&mut file));
+
+    file.delete().expect_success("Failed to delete file");
+}
+
+// Check path manipulation on a few simple paths.
+fn test_paths() {
+    let path: PathBuf = "\\EFI\\\\Boot\\".parse().unwrap();
+    let components: Vec<_> = path.components().map(|c| c.to_string()).collect();
+    assert_eq!(components, ["EFI", "Boot"]);
+    assert_eq!(path.file_name().unwrap().to_string(), "Boot");
+    assert_eq!(path.parent().unwrap().to_string(), "\\EFI");
+
+    let file: PathBuf = "BootX64.efi".parse().unwrap();
+    let joined = path.parent().unwrap().join(&file);
+    assert_eq!(joined.to_string(), "\\EFI\\BootX64.efi");
+    assert_eq!(joined.as_cstr16().to_string(), joined.to_string());
+
+    let mut popped = joined.clone();
+    assert!(popped.pop());
+    assert!(popped.pop());
+    assert_eq!(popped.to_string(), "\\");
+    assert!(!popped.pop());
+}
+
+// Build info structures with default and explicit fields.
+fn test_info_builders() {
+    let info = FileInfoBuilder::new("test.txt")
+        .file_size(42)
+        .attribute(FileAttribute::READ_ONLY)
+        .build_boxed()
+        .unwrap_or_else(|_| panic!("Failed to build `FileInfo`"));
+    assert_eq!(info.file_size(), 42);
+    assert_eq!(info.physical_size(), 0);
+    assert_eq!(info.attribute(), FileAttribute::READ_ONLY);
+    assert_eq!(info.file_name().to_string(), "test.txt");
+
+    // Clones are independent copies
+    let mut copy = info.clone();
+    copy.set_file_size(7);
+    assert_eq!((info.file_size(), copy.file_size()), (42, 7));
+    assert_eq!(copy.file_name().to_string(), "test.txt");
+
+    let mut storage = [0u64; 16];
+    let storage =
+        unsafe { core::slice::from_raw_parts_mut(storage.as_mut_ptr() as *mut u8, 16 * 8) };
+    let info = FileSystemInfoBuilder::new("VOLUME")
+        .read_only(true)
+        .block_size(512)
+        .build(storage)
+        .unwrap_or_else(|_| panic!("Failed to build `FileSystemInfo`"));
+    assert!(info.read_only());
+    assert_eq!(info.block_size(), 512);
+    assert_eq!(info.volume_label().to_string(), "VOLUME");
+}
+
+// Exercise the high-level file system API in a scratch directory.
+fn test_file_system(sfs: &mut SimpleFileSystem) {
+    info!("Testing the high-level file system API");
+
+    let path = |path: &str| path.parse::<PathBuf>().unwrap();
+    let mut fs = FileSystem::new(sfs);
+    fs.create_dir_all(&path("\\test_fs\\nested\\dir"))
+        .expect_success("Failed to create directories");
+
+    // The second write is shorter, so the file must be truncated
+    let file = path("\\test_fs\\nested\\file.txt");
+    fs.write(&file, b"first write")
+        .expect_success("Failed to write file");
+    fs.write(&file, b"second")
+        .expect_success("Failed to overwrite file");
+    let content = fs.read(&file).expect_success("Failed to read file");
+    assert_eq!(&content[..], b"second");
+
+    let copy = path("\\test_fs\\nested\\dir\\copy.txt");
+    fs.copy(&file, &copy).expect_success("Failed to copy file");
+    let metadata = fs
+        .metadata(&copy)
+        .expect_success("Failed to query metadata");
+    assert_eq!(metadata.file_size(), content.len() as u64);
+
+    fs.remove_file(&copy)
+        .expect_success("Failed to remove file");
+    fs.remove_dir(&path("\\test_fs\\nested\\dir"))
+        .expect_success("Failed to remove directory");
+    fs.remove_dir_all(&path("\\test_fs"))
+        .expect_success("Failed to remove directory tree");
+    assert_eq!(
+        fs.metadata(&path("\\test_fs"))
+            .expect_error("Directory tree was not removed")
+            .status(),
+        Status::NOT_FOUND
+    );
+}
+
+// Open protocol `P` on every handle which supports it.
+fn protocol_instances<'boot, P: Protocol + 'boot>(
+    bt: &'boot BootServices,
+) -> impl Iterator<Item = (Handle, &'boot P)> {
+    let handles = bt
+        .find_handles::<P>()
+        .expect_success("Failed to get protocol handles");
+
+    handles.into_iter().map(move |handle| {
+        let protocol = bt
+            .handle_protocol::<P>(handle)
+            .expect_success("Failed to open protocol");
+        (handle, unsafe { &*protocol.get() })
+    })
+}
+
+// Read the first block of every block device that has media inserted.
+fn test_block_io(bt: &BootServices) {
+    for (_, block_io) in protocol_instances::<BlockIO>(bt) {
+        let media = block_io.media();
+
+        info!(
+            "Block device: revision={:#x}, block size={}, last block={}",
+            block_io.revision(),
+            media.block_size(),
+            media.last_block()
+        );
+
+        // Pool allocations are only guaranteed to be 8-byte aligned
+        if !media.is_media_present() || media.io_align() > 8 {
+            continue;
+        }
+
+        let mut block = vec![0; media.block_size() as usize];
+        block_io
+            .read_blocks(media.media_id(), 0, &mut block)
+            .expect_success("Failed to read first block");
+    }
+}
+
+fn test_gpt(bt: &BootServices) {
+    for (_, block_io) in protocol_instances::<BlockIO>(bt) {
+        let media = block_io.media();
+        if media.is_logical_partition() {
+            continue;
+        }
+
+        match GptDisk::read(block_io) {
+            Ok(gpt) => {
+                let gpt = gpt.expect("Warning encountered while reading the GPT");
+                let header = gpt.header();
+                info!(
+                    "GPT disk {}: {} entries, backup header used: {}",
+                    header.disk_guid,
+                    header.number_of_partition_entries,
+                    gpt.uses_backup_header()
+                );
+                for entry in gpt.partitions() {
+                    let (start, end) = (entry.starting_lba, entry.ending_lba);
+                    info!("GPT partition: LBA {}..={}", start, end);
+                }
+            }
+            Err(err) => info!("No valid GPT on block device: {:?}", err.status()),
+        }
+    }
+}
+
+fn test_mbr(bt: &BootServices) {
+    for (_, block_io) in protocol_instances::<BlockIO>(bt) {
+        if block_io.media().is_logical_partition() {
+            continue;
+        }
+
+        match MbrDisk::read(block_io) {
+            Ok(mbr) => {
+                let mbr = mbr.expect("Warning encountered while reading the MBR");
+                info!("MBR disk: protective: {}", mbr.is_protective());
+                for record in mbr.primary_partitions().chain(mbr.logical_partitions()) {
+                    let (os_type, start, size) =
+                        (record.os_type, record.starting_lba, record.size_in_lba);
+                    info!(
+                        "MBR partition: type {:?}, start {}, size {}",
+                        os_type, start, size
+                    );
+                }
+            }
+            Err(err) => info!("No valid MBR on block device: {:?}", err.status()),
+        }
+    }
+}
+
+/// Path of the test runner's own image on the emulated system partition
+const IMAGE_PATH: &str = if cfg!(target_arch = "x86_64") {
+    "EFI\\Boot\\BootX64.efi"
+} else {
+    "EFI\\Boot\\BootAA64.efi"
+};
+
+// Read our own executable back from the file system.
+fn test_read_to_end(root: &mut Directory) {
+    info!("Reading the test runner image from disk");
+
+    let handle = root
+        .open(IMAGE_PATH, FileMode::Read, FileAttribute::empty())
+        .expect_success("Failed to open test runner image");
+    let mut file = handle
+        .into_regular_file()
+        .unwrap_success()
+        .expect("Test runner image is a directory");
+
+    let mut data = vec![];
+    let size = file
+        .read_to_end(&mut data)
+        .expect_success("Failed to read test runner image");
+
+    assert_eq!(size, data.len());
+    assert_eq!(
+        &data[..2],
+        b"MZ",
+        "Test runner image is not a PE executable"
+    );
+
+    info!("Reading the test runner image through the I/O traits");
+
+    let end = file.seek(SeekFrom::End(0)).expect("Failed to seek to end");
+    assert_eq!(end, size as u64);
+    file.rewind().expect("Failed to rewind");
+    let mut magic = [0; 2];
+    file.read_exact(&mut magic).expect("Failed to read magic");
+    assert_eq!(&magic, b"MZ");
+    assert_eq!(file.stream_position(), Ok(2));
+}
+
+// Check that byte-granularity disk reads match the underlying block device.
+fn test_disk_io(bt: &BootServices) {
+    for (handle, disk_io) in protocol_instances::<DiskIo>(bt) {
+        // Disk I/O is layered on top of Block I/O, which provides the media ID
+        let block_io = bt
+            .handle_protocol::<BlockIO>(handle)
+            .expect_success("Disk I/O handle has no block I/O protocol");
+        let block_io = unsafe { &*block_io.get() };
+        let media = block_io.media();
+
+        if !media.is_media_present() || media.io_align() > 8 {
+            continue;
+        }
+
+        let mut block = vec![0; media.block_size() as usize];
+        block_io
+            .read_blocks(media.media_id(), 0, &mut block)
+            .expect_success("Failed to read first block");
+
+        // Deliberately read an unaligned range
+        let mut bytes = [0; 16];
+        disk_io
+            .read_disk(media.media_id(), 3, &mut bytes)
+            .expect_success("Failed to read from disk");
+        assert_eq!(&bytes[..], &block[3..19], "Disk I/O and block I/O disagree");
+    }
+}
+
+// Mount every FAT partition, list its root directory and look for our image.
+fn test_fat(bt: &BootServices) {
+    for (handle, disk_io) in protocol_instances::<DiskIo>(bt) {
+        let block_io = bt
+            .handle_protocol::<BlockIO>(handle)
+            .expect_success("Disk I/O handle has no block I/O protocol");
+        let media = unsafe { &*block_io.get() }.media();
+        if !media.is_media_present() || !media.is_logical_partition() {
+            continue;
+        }
+
+        let volume = DiskVolume::new(disk_io, media.media_id(), 0);
+        let mut fat = match FatFileSystem::mount(volume) {
+            Ok(fat) => fat.expect("Warning encountered while mounting FAT volume"),
+            Err(_) => continue,
+        };
+        info!("Found {:?} volume", fat.fat_type());
+
+        let entries = fat
+            .read_dir("\\")
+            .expect_success("Failed to list FAT root directory");
+        for entry in &entries {
+            info!(
+                "FAT root entry: {} ({} bytes)",
+                entry.name(),
+                entry.file_size()
+            );
+        }
+
+        if let Ok(image) = fat.read(IMAGE_PATH) {
+            let image = image.expect("Warning encountered while reading from FAT volume");
+            assert_eq!(
+                &image[..2],
+                b"MZ",
+                "Test runner image read from FAT volume is not a PE executable"
+            );
+        }
+    }
+}
+
+// Read the first block of every asynchronous block device, both synchronously
+// and asynchronously, and check that both reads agree.
+fn test_block_io2(bt: &BootServices) {
+    let handles = match bt.find_handles::<BlockIO2>() {
+        Ok(handles) => handles.unwrap(),
+        Err(_) => {
+            warn!("`BlockIO2` protocol is not available");
+            return;
+        }
+    };
+
+    for handle in handles {
+        let block_io = bt
+            .handle_protocol::<BlockIO2>(handle)
+            .expect_success("Failed to get block I/O 2 protocol");
+        let block_io = unsafe { &*block_io.get() };
+        let media = block_io.media();
+
+        if !media.is_media_present() || media.io_align() > 8 {
+            continue;
+        }
+
+        let block_size = media.block_size() as usize;
+        let mut sync_block = vec![0; block_size];
+        block_io
+            .read_blocks_blocking(media.media_id(), 0, &mut sync_block)
+            .expect_success("Failed to read first block synchronously");
+
+        let event = unsafe { bt.create_event(EventType::empty(), Tpl::APPLICATION, None) }
+            .expect_success("Failed to create completion event");
+        let mut async_block = vec![0; block_size];
+        block_io
+            .read_blocks_async(bt, event, media.media_id(), 0, &mut async_block, || {
+                info!("Reading first block asynchronously")
+            })
+            .expect_success("Failed to read first block asynchronously");
+
+        assert_eq!(sync_block, async_block, "Block I/O 2 reads disagree");
+    }
+}
+
+// Check that asynchronous disk reads match synchronous ones.
+fn test_disk_io2(bt: &BootServices) {
+    let handles = match bt.find_handles::<DiskIo2>() {
+        Ok(handles) => handles.unwrap(),
+        Err(_) => {
+            warn!("`DiskIo2` protocol is not available");
+            return;
+        }
+    };
+
+    for handle in handles {
+        let disk_io = bt
+            .handle_protocol::<DiskIo2>(handle)
+            .expect_success("Failed to get disk I/O 2 protocol");
+        let disk_io = unsafe { &*disk_io.get() };
+
+        let block_io = bt
+            .handle_protocol::<BlockIO>(handle)
+            .expect_success("Disk I/O 2 handle has no block I/O protocol");
+        let media = unsafe { &*block_io.get() }.media();
+
+        if !media.is_media_present() {
+            continue;
+        }
+
+        let mut sync_bytes = [0; 16];
+        disk_io
+            .read_disk_blocking(media.media_id(), 3, &mut sync_bytes)
+            .expect_success("Failed to read from disk synchronously");
+
+        let event = unsafe { bt.create_event(EventType::empty(), Tpl::APPLICATION, None) }
+            .expect_success("Failed to create completion event");
+        let mut async_bytes = [0; 16];
+        disk_io
+            .read_disk_async(bt, event, media.media_id(), 3, &mut async_bytes, || {
+                info!("Reading from disk asynchronously")
+            })
+            .expect_success("Failed to read from disk asynchronously");
+
+        assert_eq!(sync_bytes, async_bytes, "Disk I/O 2 reads disagree");
+    }
+}
+
+// Erasing would destroy the test disk, so only query the erase parameters.
+fn test_erase_block(bt: &BootServices) {
+    let handles = match bt.find_handles::<EraseBlock>() {
+        Ok(handles) => handles.unwrap(),
+        Err(_) => {
+            warn!("`EraseBlock` protocol is not available");
+            return;
+        }
+    };
+
+    for handle in handles {
+        let erase_block = bt
+            .handle_protocol::<EraseBlock>(handle)
+            .expect_success("Failed to get erase block protocol");
+        let erase_block = unsafe { &*erase_block.get() };
+        info!(
+            "Erasable device: revision={:#x}, granularity={} blocks",
+            erase_block.revision(),
+            erase_block.erase_length_granularity()
+        );
+    }
+}
+
+// Serve an initrd from memory and load it back like the Linux kernel would.
+fn test_initrd(bt: &BootServices) {
+    info!("Testing Linux initrd delivery");
+
+    let contents: &[u8] = b"initrd contents";
+    let initrd = Initrd::install(bt, contents).expect_success("Failed to install initrd");
+
+    let load_file = bt
+        .handle_protocol::<LoadFile2>(initrd.handle())
+        .expect_success("Failed to get initrd `LoadFile2` protocol");
+    let load_file = unsafe { &mut *load_file.get() };
+
+    let loaded = load_file
+        .load_file_to_vec(initrd::device_path())
+        .expect_success("Failed to load initrd");
+    assert_eq!(&loaded[..], contents, "Loaded initrd does not match");
 }
@@ -9,9 +9,12 @@ pub fn test(st: &SystemTable<Boot>) {
 
     find_protocol(bt);
 
+    bluetooth::test(bt);
     console::test(st);
     debug::test(bt);
+    hii::test(bt);
     media::test(bt);
+    network::test(bt);
     pi::test(bt);
     shim::test(bt);
 }
@@ -29,8 +32,11 @@ fn find_protocol(bt: &BootServices) {
     );
 }
 
+mod bluetooth;
 mod console;
 mod debug;
+mod hii;
 mod media;
+mod network;
 mod pi;
 mod shim;
@@ -0,0 +1,59 @@
+use uefi::prelude::*;
+use uefi::proto::network::arp::{Arp, ArpConfigData, ArpServiceBinding, IPV4_ADDRESS_TYPE};
+use uefi::proto::network::{Ipv4Address, MacAddress};
+
+pub fn test(bt: &BootServices) {
+    info!("Running ARP protocol test");
+    let handles = match bt.find_handles::<ArpServiceBinding>() {
+        Ok(handles) => handles.unwrap(),
+        Err(_) => {
+            warn!("ARP protocol is not supported");
+            return;
+        }
+    };
+
+    for handle in handles {
+        let binding = bt
+            .handle_protocol::<ArpServiceBinding>(handle)
+            .expect_success("Failed to open ARP service binding");
+        let binding = unsafe { &*binding.get() };
+
+        let child = binding
+            .create_child()
+            .expect_success("Failed to create ARP instance");
+        let arp = bt
+            .handle_protocol::<Arp>(child)
+            .expect_success("Failed to open ARP protocol");
+        let arp = unsafe { &*arp.get() };
+
+        let station = Ipv4Address::new(10, 0, 2, 15);
+        arp.configure(Some(&ArpConfigData::ipv4(&station)))
+            .expect_success("Failed to configure ARP instance");
+
+        // Add a static entry, and look it up without touching the network
+        let target = [10, 0, 2, 200];
+        let hw_address: MacAddress = "02:00:00:00:00:42".parse().unwrap();
+        assert_eq!(hw_address, MacAddress::from([0x02, 0, 0, 0, 0, 0x42]));
+        arp.add(&target, Some(&hw_address), 0, true)
+            .expect_success("Failed to add ARP cache entry");
+        {
+            let entries = arp
+                .find(bt, true, Some(&target), false)
+                .expect_success("Failed to find ARP cache entry");
+            assert_eq!(entries.len(), 1);
+            let entry = entries.get(0).unwrap();
+            assert_eq!(entry.sw_address_type(), IPV4_ADDRESS_TYPE);
+            assert_eq!(entry.sw_address(), &target);
+            assert_eq!(entry.hw_address(), &hw_address.ethernet());
+            assert!(entry.is_static() && !entry.is_denied());
+        }
+        arp.delete(true, Some(&target))
+            .expect_success("Failed to delete ARP cache entry");
+
+        arp.configure(None)
+            .expect_success("Failed to reset ARP instance");
+        binding
+            .destroy_child(child)
+            .expect_success("Failed to destroy ARP instance");
+    }
+}
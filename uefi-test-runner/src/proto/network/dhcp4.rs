@@ -0,0 +1,113 @@
+use uefi::prelude::*;
+use uefi::proto::network::dhcp4::{
+    Dhcp4, Dhcp4ConfigData, Dhcp4Event, Dhcp4OptionCode, Dhcp4OptionRef, Dhcp4Options,
+    Dhcp4ServiceBinding, Dhcp4State,
+};
+
+pub fn test(bt: &BootServices) {
+    info!("Running DHCPv4 protocol test");
+    parse_options();
+
+    let handles = match bt.find_handles::<Dhcp4ServiceBinding>() {
+        Ok(handles) => handles.unwrap(),
+        Err(_) => {
+            warn!("DHCPv4 protocol is not supported");
+            return;
+        }
+    };
+
+    for handle in handles {
+        let binding = bt
+            .handle_protocol::<Dhcp4ServiceBinding>(handle)
+            .expect_success("Failed to open DHCPv4 service binding");
+        let binding = unsafe { &*binding.get() };
+
+        let child = binding
+            .create_child()
+            .expect_success("Failed to create DHCPv4 instance");
+        let dhcp = bt
+            .handle_protocol::<Dhcp4>(child)
+            .expect_success("Failed to open DHCPv4 protocol");
+        let dhcp = unsafe { &*dhcp.get() };
+
+        let mode = dhcp
+            .mode_data()
+            .expect_success("Failed to query DHCPv4 mode data");
+        assert_eq!(mode.state, Dhcp4State::STOPPED);
+
+        let mut events = 0;
+        let mut callback = |_state, event, _packet: Option<&_>| {
+            if event == Dhcp4Event::SEND_DISCOVER {
+                events += 1;
+            }
+            Status::SUCCESS
+        };
+        let parameters = [
+            Dhcp4OptionCode::PARAMETER_REQUEST_LIST.0,
+            2,
+            Dhcp4OptionCode::TFTP_SERVER_NAME.0,
+            Dhcp4OptionCode::BOOTFILE_NAME.0,
+        ];
+        let options = [Dhcp4OptionRef::new(&parameters)];
+        let timeouts = [4];
+        let mut config = Dhcp4ConfigData::new();
+        config.set_discover_timeouts(&timeouts);
+        config.set_options(&options);
+        unsafe { config.set_callback(&mut callback) };
+
+        dhcp.configure(Some(&config))
+            .expect_success("Failed to configure DHCPv4 instance");
+        let started = dhcp.start(None);
+        match &started {
+            Ok(_) => {
+                let mode = dhcp
+                    .mode_data()
+                    .expect_success("Failed to query DHCPv4 mode data");
+                assert_eq!(mode.state, Dhcp4State::BOUND);
+                info!(
+                    "DHCPv4 lease: {:?}, server {:?}, lease time {}s",
+                    mode.client_address, mode.server_address, mode.lease_time
+                );
+                if let Some(packet) = unsafe { mode.reply_packet() } {
+                    info!(
+                        "DHCPv4 next server {:?}, boot file {:?}",
+                        packet.server_address(),
+                        core::str::from_utf8(packet.boot_file_name())
+                    );
+                }
+                dhcp.release()
+                    .expect_success("Failed to release DHCPv4 lease");
+            }
+            Err(err) => warn!("Failed to obtain a DHCPv4 lease: {:?}", err.status()),
+        }
+        dhcp.stop().expect_success("Failed to stop DHCPv4 instance");
+        dhcp.configure(None)
+            .expect_success("Failed to reset DHCPv4 instance");
+        binding
+            .destroy_child(child)
+            .expect_success("Failed to destroy DHCPv4 instance");
+        if started.is_ok() {
+            assert!(events > 0, "DHCPv4 callback was not called");
+        }
+    }
+}
+
+fn parse_options() {
+    let data = [
+        0, // Padding
+        53, 1, 5, // DHCPACK
+        67, 4, b'b', b'o', b'o', b't', // Boot file name
+        255, 1, 2, 3, // End
+    ];
+    let mut options = Dhcp4Options::new(&data);
+    let message_type = options.next().unwrap();
+    assert_eq!(message_type.code, Dhcp4OptionCode::MESSAGE_TYPE);
+    assert_eq!(message_type.data, &[5]);
+    let boot_file = options.next().unwrap();
+    assert_eq!(boot_file.code, Dhcp4OptionCode::BOOTFILE_NAME);
+    assert_eq!(boot_file.data, b"boot");
+    assert!(options.next().is_none());
+
+    // Truncated options are ignored
+    assert!(Dhcp4Options::new(&[12, 4, b'a']).next().is_none());
+}
@@ -0,0 +1,71 @@
+use uefi::prelude::*;
+use uefi::proto::network::dhcp6::{
+    Dhcp6, Dhcp6ConfigData, Dhcp6OptionCode, Dhcp6Options, Dhcp6ServiceBinding, IaDescriptor,
+    IaType,
+};
+
+pub fn test(bt: &BootServices) {
+    info!("Running DHCPv6 protocol test");
+    parse_options();
+
+    let handles = match bt.find_handles::<Dhcp6ServiceBinding>() {
+        Ok(handles) => handles.unwrap(),
+        Err(_) => {
+            warn!("DHCPv6 protocol is not supported");
+            return;
+        }
+    };
+
+    for handle in handles {
+        let binding = bt
+            .handle_protocol::<Dhcp6ServiceBinding>(handle)
+            .expect_success("Failed to open DHCPv6 service binding");
+        let binding = unsafe { &*binding.get() };
+
+        let child = binding
+            .create_child()
+            .expect_success("Failed to create DHCPv6 instance");
+        let dhcp = bt
+            .handle_protocol::<Dhcp6>(child)
+            .expect_success("Failed to open DHCPv6 protocol");
+        let dhcp = unsafe { &*dhcp.get() };
+
+        let descriptor = IaDescriptor {
+            ia_type: IaType::NA,
+            ia_id: 0x7565_6669,
+        };
+        dhcp.configure(Some(&Dhcp6ConfigData::new(descriptor)))
+            .expect_success("Failed to configure DHCPv6 instance");
+        {
+            let mode = dhcp
+                .mode_data(bt)
+                .expect_success("Failed to query DHCPv6 mode data");
+            info!("DHCPv6 client ID: {:02x?}", mode.client_id());
+            assert_eq!(mode.ia_descriptor(), Some(descriptor));
+        }
+
+        // There is no DHCPv6 server on the test network, so the addresses
+        // are not requested.
+        dhcp.configure(None)
+            .expect_success("Failed to reset DHCPv6 instance");
+        binding
+            .destroy_child(child)
+            .expect_success("Failed to destroy DHCPv6 instance");
+    }
+}
+
+fn parse_options() {
+    let data = [
+        0, 59, 0, 4, b't', b'f', b't', b'p', // Boot file URL
+        0, 3, 0, 12, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, // Empty IA_NA
+        0, 1, 0, 8, 0, 3, // Truncated client ID
+    ];
+    let mut options = Dhcp6Options::new(&data);
+    let url = options.next().unwrap();
+    assert_eq!(url.code, Dhcp6OptionCode::BOOTFILE_URL);
+    assert_eq!(url.data, b"tftp");
+    let ia = options.next().unwrap();
+    assert_eq!(ia.code, Dhcp6OptionCode::IA_NA);
+    assert!(Dhcp6Options::new(&ia.data[12..]).next().is_none());
+    assert!(options.next().is_none());
+}
@@ -0,0 +1,98 @@
+use uefi::prelude::*;
+use uefi::proto::network::http::{
+    self, Http, HttpAccessPoint, HttpConfig, HttpServiceBinding, HttpStatusCode, HttpVersion,
+};
+
+pub fn test(bt: &BootServices) {
+    info!("Running HTTP protocol test");
+    test_status_codes();
+    test_invalid_urls(bt);
+
+    let handles = match bt.find_handles::<HttpServiceBinding>() {
+        Ok(handles) => handles.unwrap(),
+        Err(_) => {
+            warn!("HTTP protocol is not supported");
+            return;
+        }
+    };
+
+    for handle in handles {
+        let binding = bt
+            .handle_protocol::<HttpServiceBinding>(handle)
+            .expect_success("Failed to open HTTP service binding");
+        let binding = unsafe { &*binding.get() };
+
+        let child = binding
+            .create_child()
+            .expect_success("Failed to create HTTP instance");
+        let http = bt
+            .handle_protocol::<Http>(child)
+            .expect_success("Failed to open HTTP protocol");
+        let http = unsafe { &*http.get() };
+
+        assert!(http
+            .config_data()
+            .expect_success("Failed to get HTTP configuration")
+            .is_none());
+
+        http.configure(Some(&HttpConfig::default()))
+            .expect_success("Failed to configure HTTP instance");
+        let config = http
+            .config_data()
+            .expect_success("Failed to get HTTP configuration")
+            .expect("HTTP instance is not configured");
+        assert_eq!(config.version, HttpVersion::HTTP_1_1);
+        assert!(matches!(config.access_point, HttpAccessPoint::Ipv4(_)));
+
+        http.configure(None)
+            .expect_success("Failed to reset HTTP instance");
+        binding
+            .destroy_child(child)
+            .expect_success("Failed to destroy HTTP instance");
+    }
+}
+
+fn test_status_codes() {
+    assert_eq!(HttpStatusCode::UNSUPPORTED.code(), None);
+    assert_eq!(HttpStatusCode::STATUS_100_CONTINUE.code(), Some(100));
+    assert_eq!(HttpStatusCode::STATUS_200_OK.code(), Some(200));
+    assert_eq!(
+        HttpStatusCode::STATUS_307_TEMPORARY_REDIRECT.code(),
+        Some(307)
+    );
+    assert_eq!(HttpStatusCode::STATUS_404_NOT_FOUND.code(), Some(404));
+    assert_eq!(
+        HttpStatusCode::STATUS_417_EXPECTATION_FAILED.code(),
+        Some(417)
+    );
+    assert_eq!(
+        HttpStatusCode::STATUS_505_HTTP_VERSION_NOT_SUPPORTED.code(),
+        Some(505)
+    );
+    assert_eq!(
+        HttpStatusCode::STATUS_308_PERMANENT_REDIRECT.code(),
+        Some(308)
+    );
+    assert_eq!(
+        HttpStatusCode::STATUS_429_TOO_MANY_REQUESTS.code(),
+        Some(429)
+    );
+    assert_eq!(HttpStatusCode(43).code(), None);
+
+    assert!(HttpStatusCode::STATUS_204_NO_CONTENT.is_success());
+    assert!(HttpStatusCode::STATUS_302_FOUND.is_redirection());
+    assert!(!HttpStatusCode::STATUS_500_INTERNAL_SERVER_ERROR.is_success());
+}
+
+fn test_invalid_urls(bt: &BootServices) {
+    // These are rejected before touching the network
+    for url in &[
+        "",
+        "example.com/index.html",
+        "ftp://example.com/",
+        "http:///index.html",
+    ] {
+        let err = http::get(bt, url).expect_err("Invalid URL was accepted");
+        assert_eq!(err.status(), Status::INVALID_PARAMETER);
+    }
+}
@@ -0,0 +1,51 @@
+use alloc::string::ToString;
+use uefi::prelude::*;
+use uefi::proto::network::ip4_config2::Ip4Config2;
+use uefi::proto::network::Ipv4Address;
+
+pub fn test(bt: &BootServices) {
+    info!("Running IPv4 configuration protocol test");
+    let handles = match bt.find_handles::<Ip4Config2>() {
+        Ok(handles) => handles.unwrap(),
+        Err(_) => {
+            warn!("IPv4 configuration protocol is not supported");
+            return;
+        }
+    };
+
+    for handle in handles {
+        let config = bt
+            .handle_protocol::<Ip4Config2>(handle)
+            .expect_success("Failed to open IPv4 configuration protocol");
+        let config = unsafe { &*config.get() };
+
+        let mut buffer = [0; 1024];
+        let info = config
+            .interface_info(&mut buffer)
+            .expect_success("Failed to query interface info");
+        let name = info.name().map(|name| name.to_string());
+        info!(
+            "IPv4 interface {:?}: address {:?}, subnet mask {:?}, {} routes",
+            name,
+            info.station_address(),
+            info.subnet_mask(),
+            info.route_table().len()
+        );
+
+        let policy = config
+            .policy()
+            .expect_success("Failed to query IPv4 policy");
+        info!("IPv4 policy: {:?}", policy);
+
+        let mut servers = [Ipv4Address::UNSPECIFIED; 8];
+        match config.dns_servers(&mut servers) {
+            Ok(count) => info!("DNS servers: {:?}", &servers[..count.unwrap()]),
+            Err(err) => info!("No DNS server: {:?}", err.status()),
+        }
+        let mut gateways = [Ipv4Address::UNSPECIFIED; 8];
+        match config.gateways(&mut gateways) {
+            Ok(count) => info!("Gateways: {:?}", &gateways[..count.unwrap()]),
+            Err(err) => info!("No gateway: {:?}", err.status()),
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use alloc::string::ToString;
+use uefi::prelude::*;
+use uefi::proto::network::ip6_config::Ip6Config;
+use uefi::proto::network::Ipv6Address;
+
+pub fn test(bt: &BootServices) {
+    info!("Running IPv6 configuration protocol test");
+    let handles = match bt.find_handles::<Ip6Config>() {
+        Ok(handles) => handles.unwrap(),
+        Err(_) => {
+            warn!("IPv6 configuration protocol is not supported");
+            return;
+        }
+    };
+
+    for handle in handles {
+        let config = bt
+            .handle_protocol::<Ip6Config>(handle)
+            .expect_success("Failed to open IPv6 configuration protocol");
+        let config = unsafe { &*config.get() };
+
+        let mut buffer = [0; 1024];
+        let info = config
+            .interface_info(&mut buffer)
+            .expect_success("Failed to query interface info");
+        let name = info.name().map(|name| name.to_string());
+        info!(
+            "IPv6 interface {:?}: {} addresses, {} routes",
+            name,
+            info.addresses().len(),
+            info.route_table().len()
+        );
+        for address in info.addresses() {
+            info!(
+                "IPv6 address: {:02x?}/{}",
+                address.address, address.prefix_length
+            );
+        }
+
+        let policy = config
+            .policy()
+            .expect_success("Failed to query IPv6 policy");
+        let transmits = config
+            .dup_addr_detect_transmits()
+            .expect_success("Failed to query duplicate address detection");
+        info!(
+            "IPv6 policy: {:?}, {} duplicate address detection transmits",
+            policy, transmits
+        );
+
+        let mut servers = [Ipv6Address::UNSPECIFIED; 8];
+        match config.dns_servers(&mut servers) {
+            Ok(count) => info!("DNS servers: {:?}", &servers[..count.unwrap()]),
+            Err(err) => info!("No DNS server: {:?}", err.status()),
+        }
+    }
+}
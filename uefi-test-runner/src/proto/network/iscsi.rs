@@ -0,0 +1,30 @@
+use uefi::prelude::*;
+use uefi::proto::network::iscsi::IscsiInitiatorName;
+
+pub fn test(bt: &BootServices) {
+    info!("Running iSCSI initiator name protocol test");
+    if let Ok(initiator) = bt.locate_protocol::<IscsiInitiatorName>() {
+        let initiator =
+            initiator.expect("Warnings encountered while opening iSCSI initiator name protocol");
+        let initiator = unsafe { &*initiator.get() };
+
+        // The name is persistent, so it is only read
+        let mut buffer = [0; 224];
+        match initiator.get(&mut buffer) {
+            Ok(name) => {
+                let name = name.unwrap();
+                assert!(!name.to_bytes().is_empty());
+                info!(
+                    "iSCSI initiator name: {:?}",
+                    core::str::from_utf8(name.to_bytes())
+                );
+            }
+            Err(err) if err.status() == Status::NOT_FOUND => {
+                warn!("No iSCSI initiator name is set")
+            }
+            Err(err) => panic!("Failed to get iSCSI initiator name: {:?}", err.status()),
+        }
+    } else {
+        warn!("iSCSI initiator name protocol is not supported");
+    }
+}
@@ -0,0 +1,67 @@
+use core::mem::MaybeUninit;
+use uefi::prelude::*;
+use uefi::proto::network::mnp::{
+    ManagedNetwork, ManagedNetworkConfigData, ManagedNetworkServiceBinding,
+};
+use uefi::proto::network::snp::NetworkMode;
+
+pub fn test(bt: &BootServices) {
+    info!("Running managed network protocol test");
+    let handles = match bt.find_handles::<ManagedNetworkServiceBinding>() {
+        Ok(handles) => handles.unwrap(),
+        Err(_) => {
+            warn!("Managed network protocol is not supported");
+            return;
+        }
+    };
+
+    for handle in handles {
+        let binding = bt
+            .handle_protocol::<ManagedNetworkServiceBinding>(handle)
+            .expect_success("Failed to open managed network service binding");
+        let binding = unsafe { &*binding.get() };
+
+        let child = binding
+            .create_child()
+            .expect_success("Failed to create managed network instance");
+        let mnp = bt
+            .handle_protocol::<ManagedNetwork>(child)
+            .expect_success("Failed to open managed network protocol");
+        let mnp = unsafe { &*mnp.get() };
+
+        assert!(mnp
+            .config_data()
+            .expect_success("Failed to query managed network configuration")
+            .is_none());
+
+        let mut mode = MaybeUninit::<NetworkMode>::uninit();
+        mnp.network_mode(unsafe { &mut *mode.as_mut_ptr() })
+            .expect_success("Failed to query network interface mode");
+        let mode = unsafe { mode.assume_init() };
+        info!(
+            "Managed network interface: state {:?}, media header size {}",
+            mode.state, mode.media_header_size
+        );
+
+        // Only receive frames of an unassigned protocol, so as not to steal
+        // the traffic of the firmware.
+        let config = ManagedNetworkConfigData {
+            protocol_type_filter: 0x88b5,
+            enable_unicast_receive: true,
+            ..ManagedNetworkConfigData::default()
+        };
+        mnp.configure(Some(&config))
+            .expect_success("Failed to configure managed network instance");
+        let current = mnp
+            .config_data()
+            .expect_success("Failed to query managed network configuration")
+            .expect("Managed network instance is not configured");
+        assert_eq!(current.protocol_type_filter, 0x88b5);
+        mnp.configure(None)
+            .expect_success("Failed to reset managed network instance");
+
+        binding
+            .destroy_child(child)
+            .expect_success("Failed to destroy managed network instance");
+    }
+}
@@ -0,0 +1,39 @@
+use uefi::prelude::*;
+
+pub fn test(bt: &BootServices) {
+    info!("Testing Network protocols");
+
+    snp::test(bt);
+    mnp::test(bt);
+    arp::test(bt);
+    dhcp4::test(bt);
+    dhcp6::test(bt);
+    ip4_config2::test(bt);
+    ip6_config::test(bt);
+    iscsi::test(bt);
+    tcp4::test(bt);
+    tcp6::test(bt);
+    udp4::test(bt);
+    udp6::test(bt);
+    mtftp4::test(bt);
+    wifi::test(bt);
+    http::test(bt);
+    tls::test(bt);
+}
+
+mod arp;
+mod dhcp4;
+mod dhcp6;
+mod http;
+mod ip4_config2;
+mod ip6_config;
+mod iscsi;
+mod mnp;
+mod mtftp4;
+mod snp;
+mod tcp4;
+mod tcp6;
+mod tls;
+mod udp4;
+mod udp6;
+mod wifi;
@@ -0,0 +1,93 @@
+use alloc::string::ToString;
+use uefi::prelude::*;
+use uefi::proto::network::mtftp4::{Mtftp4, Mtftp4ConfigData, Mtftp4Option, Mtftp4ServiceBinding};
+use uefi::proto::network::Ipv4Address;
+use uefi::CStr8;
+
+pub fn test(bt: &BootServices) {
+    info!("Running MTFTP4 protocol test");
+    let handles = match bt.find_handles::<Mtftp4ServiceBinding>() {
+        Ok(handles) => handles.unwrap(),
+        Err(_) => {
+            warn!("MTFTP4 protocol is not supported");
+            return;
+        }
+    };
+
+    for handle in handles {
+        let binding = bt
+            .handle_protocol::<Mtftp4ServiceBinding>(handle)
+            .expect_success("Failed to open MTFTP4 service binding");
+        let binding = unsafe { &*binding.get() };
+
+        let child = binding
+            .create_child()
+            .expect_success("Failed to create MTFTP4 instance");
+        let mtftp = bt
+            .handle_protocol::<Mtftp4>(child)
+            .expect_success("Failed to open MTFTP4 protocol");
+        let mtftp = unsafe { &*mtftp.get() };
+
+        assert!(mtftp
+            .config_data()
+            .expect_success("Failed to get MTFTP4 configuration")
+            .is_none());
+
+        let config_data = Mtftp4ConfigData {
+            try_count: 1,
+            timeout_value: 1,
+            ..Mtftp4ConfigData::new(Ipv4Address::new(10, 0, 2, 2))
+        };
+        match mtftp.configure(Some(&config_data)) {
+            Ok(completion) => completion.log(),
+            Err(err) if err.status() == Status::NO_MAPPING => {
+                warn!("The interface has no IPv4 address yet");
+                binding
+                    .destroy_child(child)
+                    .expect_success("Failed to destroy MTFTP4 instance");
+                continue;
+            }
+            Err(err) => panic!("Failed to configure MTFTP4 instance: {:?}", err.status()),
+        }
+
+        let config = mtftp
+            .config_data()
+            .expect_success("Failed to get MTFTP4 configuration")
+            .expect("MTFTP4 instance is not configured");
+        assert_eq!(config.server_ip.to_string(), "10.0.2.2");
+        assert_eq!(config.initial_server_port, 69);
+
+        // Ask the host for the size of a file, which it may not serve
+        let filename = cstr8(b"uefi-test-runner\0");
+        let tsize = Mtftp4Option::new(cstr8(b"tsize\0"), cstr8(b"0\0"));
+        match mtftp.get_info(bt, None, filename, None, &[tsize]) {
+            Ok(packet) => {
+                let packet = packet.unwrap();
+                let options = mtftp.parse_options(bt, packet.as_bytes());
+                match options {
+                    Ok(options) => {
+                        let options = options.unwrap();
+                        let size = options.get("tsize").map(|size| size.to_bytes());
+                        info!("File size: {:?}", size.map(core::str::from_utf8));
+                    }
+                    Err(err) => warn!("Failed to parse MTFTP4 options: {:?}", err.status()),
+                }
+            }
+            Err(err) => warn!("Failed to get MTFTP4 file info: {:?}", err.status()),
+        }
+
+        mtftp
+            .configure(None)
+            .expect_success("Failed to reset MTFTP4 instance");
+        binding
+            .destroy_child(child)
+            .expect_success("Failed to destroy MTFTP4 instance");
+    }
+}
+
+fn cstr8(bytes: &[u8]) -> &CStr8 {
+    match CStr8::from_bytes_with_nul(bytes) {
+        Ok(s) => s,
+        Err(_) => panic!("Invalid string"),
+    }
+}
@@ -0,0 +1,39 @@
+use uefi::prelude::*;
+use uefi::proto::network::snp::{NetworkState, SimpleNetwork};
+
+pub fn test(bt: &BootServices) {
+    info!("Running simple network protocol test");
+    if let Ok(snp) = bt.locate_protocol::<SimpleNetwork>() {
+        let snp = snp.expect("Warnings encountered while opening simple network protocol");
+        let snp = unsafe { &*snp.get() };
+
+        let mode = snp.mode();
+        info!(
+            "Network interface: state {:?}, address {}, media present: {}",
+            mode.state, mode.current_address, mode.media_present
+        );
+
+        if mode.state == NetworkState::STOPPED {
+            snp.start()
+                .expect_success("Failed to start network interface");
+        }
+        if snp.mode().state == NetworkState::STARTED {
+            snp.initialize(0, 0)
+                .expect_success("Failed to initialize network interface");
+        }
+        assert_eq!(snp.mode().state, NetworkState::INITIALIZED);
+
+        match snp.statistics() {
+            Ok(statistics) => {
+                let statistics = statistics.unwrap();
+                info!(
+                    "Network statistics: {} frames received, {} frames sent",
+                    statistics.rx_total_frames, statistics.tx_total_frames
+                );
+            }
+            Err(err) => warn!("Network statistics are not available: {:?}", err.status()),
+        }
+    } else {
+        warn!("Simple network protocol is not supported");
+    }
+}
@@ -0,0 +1,41 @@
+use uefi::prelude::*;
+use uefi::proto::network::tcp4::{Tcp4ConfigData, Tcp4ServiceBinding, Tcp4State, TcpConnection};
+
+pub fn test(bt: &BootServices) {
+    info!("Running TCP4 protocol test");
+    let handles = match bt.find_handles::<Tcp4ServiceBinding>() {
+        Ok(handles) => handles.unwrap(),
+        Err(_) => {
+            warn!("TCP4 protocol is not supported");
+            return;
+        }
+    };
+
+    for handle in handles {
+        // A passive connection does not need a peer, only an address
+        let listener = match TcpConnection::open(bt, handle, &Tcp4ConfigData::passive(8080)) {
+            Ok(listener) => listener.unwrap(),
+            Err(err) if err.status() == Status::NO_MAPPING => {
+                warn!("The interface has no IPv4 address yet");
+                continue;
+            }
+            Err(err) => panic!("Failed to open TCP4 listener: {:?}", err.status()),
+        };
+
+        let state = listener
+            .tcp()
+            .state()
+            .expect_success("Failed to get TCP4 state");
+        assert_eq!(state, Tcp4State::LISTEN);
+
+        let config = listener
+            .tcp()
+            .config_data()
+            .expect_success("Failed to get TCP4 configuration");
+        assert_eq!(config.access_point.station_port, 8080);
+        assert!(!config.access_point.active_flag);
+
+        // Dropping the listener resets and destroys the instance
+        drop(listener);
+    }
+}
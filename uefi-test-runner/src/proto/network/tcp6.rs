@@ -0,0 +1,41 @@
+use uefi::prelude::*;
+use uefi::proto::network::tcp6::{Tcp6ConfigData, Tcp6Connection, Tcp6ServiceBinding, Tcp6State};
+
+pub fn test(bt: &BootServices) {
+    info!("Running TCP6 protocol test");
+    let handles = match bt.find_handles::<Tcp6ServiceBinding>() {
+        Ok(handles) => handles.unwrap(),
+        Err(_) => {
+            warn!("TCP6 protocol is not supported");
+            return;
+        }
+    };
+
+    for handle in handles {
+        // A passive connection does not need a peer, only an address
+        let listener = match Tcp6Connection::open(bt, handle, &Tcp6ConfigData::passive(8080)) {
+            Ok(listener) => listener.unwrap(),
+            Err(err) if err.status() == Status::NO_MAPPING => {
+                warn!("The interface has no IPv6 address yet");
+                continue;
+            }
+            Err(err) => panic!("Failed to open TCP6 listener: {:?}", err.status()),
+        };
+
+        let state = listener
+            .tcp()
+            .state()
+            .expect_success("Failed to get TCP6 state");
+        assert_eq!(state, Tcp6State::LISTEN);
+
+        let config = listener
+            .tcp()
+            .config_data()
+            .expect_success("Failed to get TCP6 configuration");
+        assert_eq!(config.access_point.station_port, 8080);
+        assert!(!config.access_point.active_flag);
+
+        // Dropping the listener resets and destroys the instance
+        drop(listener);
+    }
+}
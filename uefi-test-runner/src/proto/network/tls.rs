@@ -0,0 +1,67 @@
+use uefi::prelude::*;
+use uefi::proto::network::tls::{
+    Tls, TlsConfigDataType, TlsConfiguration, TlsConnectionEnd, TlsServiceBinding, TlsSessionState,
+    TlsVerify, TlsVersion,
+};
+
+pub fn test(bt: &BootServices) {
+    info!("Running TLS protocol test");
+
+    let handles = match bt.find_handles::<TlsServiceBinding>() {
+        Ok(handles) => handles.unwrap(),
+        Err(_) => {
+            warn!("TLS protocol is not supported");
+            return;
+        }
+    };
+
+    for handle in handles {
+        let binding = bt
+            .handle_protocol::<TlsServiceBinding>(handle)
+            .expect_success("Failed to open TLS service binding");
+        let binding = unsafe { &*binding.get() };
+
+        let child = binding
+            .create_child()
+            .expect_success("Failed to create TLS instance");
+        let tls = bt
+            .handle_protocol::<Tls>(child)
+            .expect_success("Failed to open TLS protocol");
+        let tls = unsafe { &*tls.get() };
+
+        tls.set_version(TlsVersion::TLS_1_2)
+            .expect_success("Failed to set TLS version");
+        assert_eq!(
+            tls.version().expect_success("Failed to get TLS version"),
+            TlsVersion::TLS_1_2
+        );
+        tls.set_connection_end(TlsConnectionEnd::CLIENT)
+            .expect_success("Failed to set TLS connection end");
+        tls.set_verify(TlsVerify::empty())
+            .expect_success("Failed to set TLS verification");
+        assert_eq!(
+            tls.session_state()
+                .expect_success("Failed to get TLS session state"),
+            TlsSessionState::NOT_STARTED
+        );
+
+        match bt.handle_protocol::<TlsConfiguration>(child) {
+            Ok(config) => {
+                let config = unsafe { &*config.unwrap().get() };
+                let mut buffer = [0; 16];
+                // CA certificates may be provisioned by the platform, or missing
+                if let Ok(size) = config.get_data(TlsConfigDataType::CA_CERTIFICATE, &mut buffer) {
+                    info!(
+                        "TLS instance has {} bytes of CA certificates",
+                        size.unwrap()
+                    );
+                }
+            }
+            Err(_) => warn!("TLS configuration protocol is not supported"),
+        }
+
+        binding
+            .destroy_child(child)
+            .expect_success("Failed to destroy TLS instance");
+    }
+}
@@ -0,0 +1,75 @@
+use uefi::prelude::*;
+use uefi::proto::network::udp4::{
+    Udp4, Udp4ConfigData, Udp4ServiceBinding, Udp4SessionData, Udp4TransmitData,
+};
+use uefi::table::boot::{EventType, Tpl};
+
+pub fn test(bt: &BootServices) {
+    info!("Running UDP4 protocol test");
+    let handles = match bt.find_handles::<Udp4ServiceBinding>() {
+        Ok(handles) => handles.unwrap(),
+        Err(_) => {
+            warn!("UDP4 protocol is not supported");
+            return;
+        }
+    };
+
+    for handle in handles {
+        let binding = bt
+            .handle_protocol::<Udp4ServiceBinding>(handle)
+            .expect_success("Failed to open UDP4 service binding");
+        let binding = unsafe { &*binding.get() };
+
+        let child = binding
+            .create_child()
+            .expect_success("Failed to create UDP4 instance");
+        let udp = bt
+            .handle_protocol::<Udp4>(child)
+            .expect_success("Failed to open UDP4 protocol");
+        let udp = unsafe { &*udp.get() };
+
+        assert!(udp
+            .config_data()
+            .expect_success("Failed to get UDP4 configuration")
+            .is_none());
+
+        match udp.configure(Some(&Udp4ConfigData::default())) {
+            Ok(completion) => completion.log(),
+            Err(err) if err.status() == Status::NO_MAPPING => {
+                warn!("The interface has no IPv4 address yet");
+                binding
+                    .destroy_child(child)
+                    .expect_success("Failed to destroy UDP4 instance");
+                continue;
+            }
+            Err(err) => panic!("Failed to configure UDP4 instance: {:?}", err.status()),
+        }
+
+        let config = udp
+            .config_data()
+            .expect_success("Failed to get UDP4 configuration")
+            .expect("UDP4 instance is not configured");
+        assert_ne!(config.station_port, 0, "No local port was picked");
+
+        // Send a datagram made of two fragments to the discard port of the host
+        let event = unsafe { bt.create_event(EventType::empty(), Tpl::APPLICATION, None) }
+            .expect_success("Failed to create event");
+        let session = Udp4SessionData {
+            destination_address: "10.0.2.2".parse().unwrap(),
+            destination_port: 9,
+            ..Udp4SessionData::default()
+        };
+        let mut data = Udp4TransmitData::from_fragments([b"Hello, ", b"UDP4"]);
+        data.set_session(&session);
+        if let Err(err) = udp.transmit_blocking(bt, event, &data) {
+            warn!("Failed to send UDP4 datagram: {:?}", err.status());
+        }
+        unsafe { bt.close_event(event) }.expect_success("Failed to close event");
+
+        udp.configure(None)
+            .expect_success("Failed to reset UDP4 instance");
+        binding
+            .destroy_child(child)
+            .expect_success("Failed to destroy UDP4 instance");
+    }
+}
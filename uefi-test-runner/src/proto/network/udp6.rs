@@ -0,0 +1,76 @@
+use uefi::prelude::*;
+use uefi::proto::network::udp6::{
+    Udp6, Udp6ConfigData, Udp6ServiceBinding, Udp6SessionData, Udp6TransmitData,
+};
+use uefi::table::boot::{EventType, Tpl};
+
+pub fn test(bt: &BootServices) {
+    info!("Running UDP6 protocol test");
+    let handles = match bt.find_handles::<Udp6ServiceBinding>() {
+        Ok(handles) => handles.unwrap(),
+        Err(_) => {
+            warn!("UDP6 protocol is not supported");
+            return;
+        }
+    };
+
+    for handle in handles {
+        let binding = bt
+            .handle_protocol::<Udp6ServiceBinding>(handle)
+            .expect_success("Failed to open UDP6 service binding");
+        let binding = unsafe { &*binding.get() };
+
+        let child = binding
+            .create_child()
+            .expect_success("Failed to create UDP6 instance");
+        let udp = bt
+            .handle_protocol::<Udp6>(child)
+            .expect_success("Failed to open UDP6 protocol");
+        let udp = unsafe { &*udp.get() };
+
+        assert!(udp
+            .config_data()
+            .expect_success("Failed to get UDP6 configuration")
+            .is_none());
+
+        match udp.configure(Some(&Udp6ConfigData::default())) {
+            Ok(completion) => completion.log(),
+            Err(err) if err.status() == Status::NO_MAPPING => {
+                warn!("The interface has no IPv6 address yet");
+                binding
+                    .destroy_child(child)
+                    .expect_success("Failed to destroy UDP6 instance");
+                continue;
+            }
+            Err(err) => panic!("Failed to configure UDP6 instance: {:?}", err.status()),
+        }
+
+        let config = udp
+            .config_data()
+            .expect_success("Failed to get UDP6 configuration")
+            .expect("UDP6 instance is not configured");
+        assert_ne!(config.station_port, 0, "No local port was picked");
+
+        // Send a datagram made of two fragments to the discard port of all the
+        // nodes of the link
+        let event = unsafe { bt.create_event(EventType::empty(), Tpl::APPLICATION, None) }
+            .expect_success("Failed to create event");
+        let session = Udp6SessionData {
+            destination_address: "ff02::1".parse().unwrap(),
+            destination_port: 9,
+            ..Udp6SessionData::default()
+        };
+        let mut data = Udp6TransmitData::from_fragments([b"Hello, ", b"UDP6"]);
+        data.set_session(&session);
+        if let Err(err) = udp.transmit_blocking(bt, event, &data) {
+            warn!("Failed to send UDP6 datagram: {:?}", err.status());
+        }
+        unsafe { bt.close_event(event) }.expect_success("Failed to close event");
+
+        udp.configure(None)
+            .expect_success("Failed to reset UDP6 instance");
+        binding
+            .destroy_child(child)
+            .expect_success("Failed to destroy UDP6 instance");
+    }
+}
@@ -0,0 +1,59 @@
+use uefi::prelude::*;
+use uefi::proto::network::wifi::{self, Ieee80211Ssid, WirelessMacConnectionII};
+
+pub fn test(bt: &BootServices) {
+    info!("Running wireless MAC connection protocol test");
+    test_ssid();
+    test_invalid_credentials(bt);
+
+    let handles = match bt.find_handles::<WirelessMacConnectionII>() {
+        Ok(handles) => handles.unwrap(),
+        Err(_) => {
+            warn!("Wireless MAC connection protocol is not supported");
+            return;
+        }
+    };
+
+    for handle in handles {
+        let wmc = bt
+            .handle_protocol::<WirelessMacConnectionII>(handle)
+            .expect_success("Failed to open wireless MAC connection protocol");
+        let wmc = unsafe { &*wmc.get() };
+
+        match wmc.get_networks(bt, None) {
+            Ok(networks) => {
+                for desc in networks.unwrap().networks() {
+                    let network = desc.network();
+                    info!(
+                        "Wireless network {:?}: quality {}, {} key management suites",
+                        core::str::from_utf8(network.ssid().as_bytes()),
+                        desc.quality(),
+                        network.akm_suites().len()
+                    );
+                }
+            }
+            Err(err) => warn!("Failed to scan for wireless networks: {:?}", err.status()),
+        }
+    }
+}
+
+fn test_ssid() {
+    let ssid = Ieee80211Ssid::new(b"uefi-rs").expect("Failed to create SSID");
+    assert_eq!(ssid.as_bytes(), b"uefi-rs");
+    assert!(Ieee80211Ssid::new(&[b'a'; 32]).is_some());
+    assert!(Ieee80211Ssid::new(&[b'a'; 33]).is_none());
+}
+
+fn test_invalid_credentials(bt: &BootServices) {
+    let ssid = "a-network-name-which-is-longer-than-32-bytes";
+    for (ssid, psk) in [
+        (ssid, None),
+        ("uefi-rs", Some("short")),
+        ("uefi-rs", Some("é")),
+    ] {
+        match wifi::connect(bt, ssid, psk) {
+            Ok(_) => panic!("Invalid credentials were accepted"),
+            Err(err) => assert_eq!(err.status(), Status::INVALID_PARAMETER),
+        }
+    }
+}
@@ -24,6 +24,7 @@ pub fn test(bt: &BootServices) {
         test_get_processor_info(mp_support);
         test_startup_all_aps(mp_support, bt);
         test_startup_this_ap(mp_support, bt);
+        test_startup_with_closures(mp_support);
         test_enable_disable_ap(mp_support);
         test_switch_bsp_and_who_am_i(mp_support);
     } else {
@@ -118,6 +119,30 @@ fn test_startup_this_ap(mps: &MpServices, bt: &BootServices) {
     }
 }
 
+fn test_startup_with_closures(mps: &MpServices) {
+    // Ensure that the closure runs once on each AP
+    let counter = AtomicUsize::new(0);
+    let increment = |processor_number| {
+        if processor_number != 0 {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+    };
+    mps.startup_all_aps_with(false, &increment, None)
+        .unwrap()
+        .unwrap();
+    assert_eq!(counter.load(Ordering::Relaxed), NUM_CPUS - 1);
+
+    // Ensure that each AP can modify the state of the closure
+    for i in 1..NUM_CPUS {
+        let mut processor_number = 0;
+        let mut find_processor_number = |number| processor_number = number;
+        mps.startup_this_ap_with(i, &mut find_processor_number, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(processor_number, i);
+    }
+}
+
 fn test_enable_disable_ap(mps: &MpServices) {
     // Disable second CPU
     mps.enable_disable_ap(1, false, None).unwrap().unwrap();